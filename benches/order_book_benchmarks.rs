@@ -1,5 +1,7 @@
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use order_book::{Decimal, MarketDepthCache, Order, OrderBook, Side};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use order_book::{MarketDepthCache, Order, OrderBook, OrderBookConfig, Side};
 use parking_lot::RwLock;
 use std::sync::Arc;
 
@@ -13,7 +15,7 @@ fn benchmark_single_order_insertion(criterion: &mut Criterion) {
 
         bencher.iter(|| {
             let order = Order::new(price_counter, 100, Side::Bid);
-            let event = order_book.insert_order(order);
+            let event = order_book.insert_order(order).event;
             black_box(event);
             price_counter += 0.01; // Ensure unique prices
         });
@@ -25,7 +27,7 @@ fn benchmark_single_order_insertion(criterion: &mut Criterion) {
 
         bencher.iter(|| {
             let order = Order::new(price_counter, 100, Side::Ask);
-            let event = order_book.insert_order(order);
+            let event = order_book.insert_order(order).event;
             black_box(event);
             price_counter += 0.01;
         });
@@ -45,7 +47,7 @@ fn benchmark_order_insertion_with_cache(criterion: &mut Criterion) {
 
         bencher.iter(|| {
             let order = Order::new(price_counter, 100, Side::Bid);
-            let event = order_book.insert_order(order);
+            let event = order_book.insert_order(order).event;
             market_depth_cache.process_order_event(event);
             price_counter += 0.01;
         });
@@ -100,8 +102,12 @@ fn benchmark_market_depth_retrieval(criterion: &mut Criterion) {
             let bid_price = 100.0 - (i as f64 * 0.01);
             let ask_price = 101.0 + (i as f64 * 0.01);
 
-            let bid_event = order_book.insert_order(Order::new(bid_price, 100, Side::Bid));
-            let ask_event = order_book.insert_order(Order::new(ask_price, 100, Side::Ask));
+            let bid_event = order_book
+                .insert_order(Order::new(bid_price, 100, Side::Bid))
+                .event;
+            let ask_event = order_book
+                .insert_order(Order::new(ask_price, 100, Side::Ask))
+                .event;
 
             market_depth_cache.process_order_event(bid_event);
             market_depth_cache.process_order_event(ask_event);
@@ -117,6 +123,18 @@ fn benchmark_market_depth_retrieval(criterion: &mut Criterion) {
                 });
             },
         );
+
+        benchmark_group.bench_with_input(
+            BenchmarkId::new("copy_top_10_levels_into", cache_size),
+            &market_depth_cache,
+            |bencher, cache| {
+                let (mut bids, mut asks) = (Vec::with_capacity(10), Vec::with_capacity(10));
+                bencher.iter(|| {
+                    cache.copy_top_levels_into(&mut bids, &mut asks, 10);
+                    black_box((&bids, &asks));
+                });
+            },
+        );
     }
 
     benchmark_group.finish();
@@ -178,8 +196,12 @@ fn benchmark_concurrent_depth_reads(criterion: &mut Criterion) {
         let bid_price = 100.0 - (i as f64 * 0.01);
         let ask_price = 101.0 + (i as f64 * 0.01);
 
-        let bid_event = order_book.insert_order(Order::new(bid_price, 100, Side::Bid));
-        let ask_event = order_book.insert_order(Order::new(ask_price, 100, Side::Ask));
+        let bid_event = order_book
+            .insert_order(Order::new(bid_price, 100, Side::Bid))
+            .event;
+        let ask_event = order_book
+            .insert_order(Order::new(ask_price, 100, Side::Ask))
+            .event;
 
         market_depth_cache.process_order_event(bid_event);
         market_depth_cache.process_order_event(ask_event);
@@ -238,7 +260,9 @@ fn benchmark_mixed_workload(criterion: &mut Criterion) {
                         let price = 100.0 + (i as f64 * 0.01);
                         let event = {
                             let mut book_lock = book.write();
-                            book_lock.insert_order(Order::new(price, 100, Side::Bid))
+                            book_lock
+                                .insert_order(Order::new(price, 100, Side::Bid))
+                                .event
                         };
                         cache.process_order_event(event);
                     }
@@ -274,7 +298,7 @@ fn benchmark_mixed_workload(criterion: &mut Criterion) {
             let mut book = order_book_arc.write();
             for i in 0..1000 {
                 let price = 100.0 + (i as f64 * 0.01);
-                let event = book.insert_order(Order::new(price, 100, Side::Bid));
+                let event = book.insert_order(Order::new(price, 100, Side::Bid)).event;
                 market_depth_cache_arc.process_order_event(event);
             }
         }
@@ -294,7 +318,9 @@ fn benchmark_mixed_workload(criterion: &mut Criterion) {
                         let price = 200.0 + (i as f64 * 0.01);
                         let event = {
                             let mut book_lock = book.write();
-                            book_lock.insert_order(Order::new(price, 100, Side::Bid))
+                            book_lock
+                                .insert_order(Order::new(price, 100, Side::Bid))
+                                .event
                         };
                         cache.process_order_event(event);
                     }
@@ -342,7 +368,7 @@ fn benchmark_cache_event_processing(criterion: &mut Criterion) {
                     for i in 0..event_count {
                         let price = 100.0 + (i as f64 * 0.01);
                         let side = if i % 2 == 0 { Side::Bid } else { Side::Ask };
-                        let event = order_book.insert_order(Order::new(price, 100, side));
+                        let event = order_book.insert_order(Order::new(price, 100, side)).event;
                         market_depth_cache.process_order_event(event);
                     }
 
@@ -355,6 +381,46 @@ fn benchmark_cache_event_processing(criterion: &mut Criterion) {
     benchmark_group.finish();
 }
 
+/// Benchmark the happy path of matching: a single aggressive order sweeping many levels.
+fn benchmark_matching_sweep(criterion: &mut Criterion) {
+    let mut benchmark_group = criterion.benchmark_group("matching_sweep");
+
+    // Iterate over a range of swept level counts, with a few orders per level
+    for level_count in [1u64, 10, 50] {
+        let orders_per_level = 4;
+        benchmark_group.throughput(Throughput::Elements(level_count * orders_per_level));
+
+        benchmark_group.bench_with_input(
+            BenchmarkId::new("sweep_levels", level_count),
+            &level_count,
+            |bencher, &level_count| {
+                let mut order_book = OrderBook::with_config(OrderBookConfig {
+                    order_capacity: 1_024,
+                    ..OrderBookConfig::default()
+                });
+                for level in 0..level_count {
+                    for _ in 0..orders_per_level {
+                        let price = 100.0 + (level as f64 * 0.01);
+                        order_book.insert_order(Order::new(price, 100, Side::Ask));
+                    }
+                }
+
+                bencher.iter_batched(
+                    || order_book.clone(),
+                    |mut order_book| {
+                        let sweep =
+                            Order::new(1_000.0, level_count * orders_per_level * 100, Side::Bid);
+                        black_box(order_book.match_order(sweep));
+                    },
+                    BatchSize::SmallInput,
+                );
+            },
+        );
+    }
+
+    benchmark_group.finish();
+}
+
 // Define the benchmarks group to generate the reports automatically
 criterion_group!(
     benches,
@@ -366,6 +432,7 @@ criterion_group!(
     benchmark_concurrent_depth_reads,
     benchmark_mixed_workload,
     benchmark_cache_event_processing,
+    benchmark_matching_sweep,
 );
 
 criterion_main!(benches);
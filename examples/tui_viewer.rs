@@ -0,0 +1,41 @@
+//! Feeds a synthetic order flow into the terminal viewer.
+//!
+//! Run with `cargo run --example tui_viewer --features tui` and press `q` to quit.
+
+use order_book::tui::BookViewer;
+use order_book::{Order, OrderBook, Side};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+fn main() -> std::io::Result<()> {
+    let (event_sender, event_receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut order_book = OrderBook::new();
+        // Small linear congruential generator, good enough for a demo order flow
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+
+        loop {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            let offset = ((state >> 33) % 500) as f64 * 0.01;
+            let quantity = (state >> 50) % 100 + 1;
+
+            let order = if state & 1 == 0 {
+                Order::new(99.99 - offset, quantity, Side::Bid)
+            } else {
+                Order::new(100.01 + offset, quantity, Side::Ask)
+            };
+
+            let event = order_book.insert_order(order).event;
+            if event_sender.send(event).is_err() {
+                break; // The viewer has been closed
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    });
+
+    BookViewer::new(15, 100).run(event_receiver)
+}
@@ -0,0 +1,140 @@
+//! Zero-copy archives of book and depth snapshots, built on `rkyv`.
+//!
+//! An archive is laid out so that it can be used where it lies: once a snapshot file
+//! is read or memory-mapped, `access_book` and `access_depth` validate the bytes and
+//! return a reference to the archived snapshot without deserializing it, so looking up
+//! a few levels of a multi-gigabyte history does not pay for decoding all of it.
+//! Depth levels are archived as arrays in ascending price order, which
+//! `ArchivedDepthSnapshot::quantity_at` binary searches in place, and the
+//! `deserialize_*` functions convert an archive back into an owned snapshot when one is
+//! needed.
+//!
+//! Archives must be accessed from memory aligned like the `AlignedVec` they were
+//! written into, which memory-mapped files are.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::archive::{access_depth, archive_depth, deserialize_depth};
+//! use order_book::{MarketDepthCache, Order, OrderBook, Side};
+//! use rust_decimal::Decimal;
+//!
+//! let mut order_book = OrderBook::new();
+//! let cache = MarketDepthCache::new();
+//! cache.process_order_event(order_book.insert_order(Order::new(100.50, 10, Side::Bid)).event);
+//!
+//! let bytes = archive_depth(&cache.snapshot());
+//! let archived = access_depth(&bytes).unwrap();
+//! assert_eq!(archived.sequence, 1);
+//! assert_eq!(archived.quantity_at(Decimal::new(100, 0), Side::Bid), Some(10));
+//! assert_eq!(deserialize_depth(archived), cache.snapshot());
+//! ```
+
+use crate::types::{
+    ArchivedBookSnapshot, ArchivedDepthSnapshot, BookSnapshot, DepthSnapshot, Side,
+};
+use rkyv::{Deserialize, Infallible};
+use rust_decimal::Decimal;
+use std::fmt;
+
+pub use rkyv::AlignedVec;
+
+/// The bytes given to `access_book` or `access_depth` are not a valid archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidArchive {
+    /// Why validation failed
+    reason: String,
+}
+
+impl fmt::Display for InvalidArchive {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "invalid snapshot archive: {}", self.reason)
+    }
+}
+
+impl std::error::Error for InvalidArchive {}
+
+/// Archives a book snapshot.
+pub fn archive_book(snapshot: &BookSnapshot) -> AlignedVec {
+    rkyv::to_bytes::<_, 4096>(snapshot).expect("snapshots archive infallibly")
+}
+
+/// Archives a depth snapshot.
+pub fn archive_depth(snapshot: &DepthSnapshot) -> AlignedVec {
+    rkyv::to_bytes::<_, 4096>(snapshot).expect("snapshots archive infallibly")
+}
+
+/// Validates an archived book snapshot and returns it in place.
+///
+/// ## Arguments
+///
+/// * `bytes`: The archive, as written by `archive_book`
+///
+/// ## Returns
+///
+/// The archived snapshot, borrowing `bytes`, or `InvalidArchive` if the bytes are not
+/// a valid archive
+pub fn access_book(bytes: &[u8]) -> Result<&ArchivedBookSnapshot, InvalidArchive> {
+    rkyv::check_archived_root::<BookSnapshot>(bytes).map_err(|error| InvalidArchive {
+        reason: error.to_string(),
+    })
+}
+
+/// Validates an archived depth snapshot and returns it in place.
+///
+/// ## Arguments
+///
+/// * `bytes`: The archive, as written by `archive_depth`
+///
+/// ## Returns
+///
+/// The archived snapshot, borrowing `bytes`, or `InvalidArchive` if the bytes are not
+/// a valid archive
+pub fn access_depth(bytes: &[u8]) -> Result<&ArchivedDepthSnapshot, InvalidArchive> {
+    rkyv::check_archived_root::<DepthSnapshot>(bytes).map_err(|error| InvalidArchive {
+        reason: error.to_string(),
+    })
+}
+
+/// Converts an archived book snapshot back into an owned one.
+pub fn deserialize_book(archived: &ArchivedBookSnapshot) -> BookSnapshot {
+    archived
+        .deserialize(&mut Infallible)
+        .expect("snapshots deserialize infallibly")
+}
+
+/// Converts an archived depth snapshot back into an owned one.
+pub fn deserialize_depth(archived: &ArchivedDepthSnapshot) -> DepthSnapshot {
+    archived
+        .deserialize(&mut Infallible)
+        .expect("snapshots deserialize infallibly")
+}
+
+impl ArchivedDepthSnapshot {
+    /// Looks up the quantity at an aggregated level without deserializing the snapshot.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price_level`: The aggregated price level
+    /// * `side`: The side to look the level up on
+    ///
+    /// ## Returns
+    ///
+    /// The quantity at the level, or `None` if the snapshot has no such level
+    pub fn quantity_at(&self, price_level: Decimal, side: Side) -> Option<u64> {
+        let levels = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        let position = levels
+            .binary_search_by(|level| {
+                let price: Decimal = level
+                    .key
+                    .deserialize(&mut Infallible)
+                    .expect("decimals deserialize infallibly");
+                price.cmp(&price_level)
+            })
+            .ok()?;
+        Some(levels[position].value)
+    }
+}
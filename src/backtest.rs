@@ -0,0 +1,556 @@
+//! A backtesting harness filling a strategy's orders against a book replayed from
+//! historical data.
+//!
+//! A `Backtest` applies historical `Command`s, e.g. those of a recorded session, to an
+//! `OrderBook`, and fills the orders a strategy submits meanwhile under a configurable
+//! `FillModel`. The strategy's orders are simulated next to the replayed book rather
+//! than inserted into it, so the history replays exactly as it happened and its
+//! commands keep addressing the same orders:
+//!
+//! - An order reaches the book `FillModel::latency` after its submission, by the
+//!   book's clock, and so do cancellations; an order can fill while its cancellation
+//!   is on its way
+//! - On arrival, the part of an order crossing the opposite side fills right away
+//!   against the depth resting there, level by level, as a taker
+//! - The rest waits at its price behind the queue the `QueueModel` assumes, and fills
+//!   as a maker from the historical trades: trades printed through its price fill it
+//!   directly, and trades at its price once they have consumed the queue ahead of it
+//!
+//! The replayed book never sees the strategy's orders, so liquidity they take stays in
+//! the book and their trades move no price: the model suits strategies whose orders
+//! are small next to the book. Every fill is appended to a fill log and to the
+//! strategy's `Position`, whose realized and unrealized P&L the backtest reports.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::backtest::{Backtest, FillModel, QueueModel};
+//! use order_book::{Command, Order, OrderBook, Side};
+//! use rust_decimal::Decimal;
+//!
+//! let fill_model = FillModel {
+//!     queue_model: QueueModel::BackOfQueue,
+//!     ..FillModel::default()
+//! };
+//! let mut backtest = Backtest::new(OrderBook::deterministic(7), fill_model);
+//! backtest.step(Command::Insert(Order::new(100.00, 10, Side::Bid))).unwrap();
+//! backtest.step(Command::Insert(Order::new(101.00, 10, Side::Ask))).unwrap();
+//!
+//! // The strategy joins the bid behind the 10 already resting there, ahead of a later 10
+//! backtest.submit(Order::new(100.00, 5, Side::Bid));
+//! backtest.step(Command::Insert(Order::new(100.00, 10, Side::Bid))).unwrap();
+//! backtest.step(Command::Match(Order::new(100.00, 12, Side::Ask))).unwrap();
+//! assert_eq!(backtest.fills()[0].quantity, 2);
+//! assert_eq!(backtest.position().quantity, 2);
+//!
+//! // Marked at the mid of 100.50
+//! assert_eq!(backtest.total_pnl(), Some(Decimal::ONE));
+//! ```
+
+use crate::book_view::BookView;
+use crate::error::Result;
+use crate::order_book::OrderBook;
+use crate::types::{Command, Order, OrderEvent, Side, Trade};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// Where a strategy's resting order is assumed to join the queue of its price level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum QueueModel {
+    /// Behind all the quantity resting at the price when the order arrives, which only
+    /// trades at the price consume
+    #[default]
+    BackOfQueue,
+    /// Behind all the quantity resting at the price when the order arrives, which trades
+    /// consume and cancellations at the price shrink in proportion to the part of the
+    /// level ahead of the order
+    Proportional,
+    /// Ahead of every order resting at the price, so that any trade at the price fills
+    /// it, an optimistic bound
+    FrontOfQueue,
+}
+
+/// The fill assumptions of a `Backtest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FillModel {
+    /// The time between a strategy's submission or cancellation and its arrival at the
+    /// book, by the book's clock
+    pub latency: Duration,
+    /// Where resting orders join the queue of their price level
+    pub queue_model: QueueModel,
+}
+
+/// The identifier a `Backtest` assigns to a strategy's order.
+///
+/// The strategy's orders never rest in the replayed book, so their identifiers are
+/// apart from the book's `OrderId`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StrategyOrderId(pub u64);
+
+/// Whether a fill took liquidity from the book or provided it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Liquidity {
+    /// The order rested and was executed against
+    Maker,
+    /// The order crossed the book on arrival
+    Taker,
+}
+
+/// An execution of a strategy's order, recorded in a `Backtest`'s fill log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    /// The order executed
+    pub order_id: StrategyOrderId,
+    /// The side of the order
+    pub side: Side,
+    /// The execution price
+    pub price: Decimal,
+    /// The quantity executed
+    pub quantity: u64,
+    /// When the execution happened, by the book's clock
+    pub timestamp_nanos: u64,
+    /// Whether the order took or provided liquidity
+    pub liquidity: Liquidity,
+}
+
+/// A strategy's position and its P&L, accounted at average cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// The quantity held, negative when short
+    pub quantity: i64,
+    /// The average price of the quantity held, 0 when flat
+    pub average_price: Decimal,
+    /// The P&L of the quantity closed so far
+    pub realized_pnl: Decimal,
+}
+
+impl Position {
+    /// Returns the P&L of the quantity held, marked at `mark_price`.
+    pub fn unrealized_pnl(&self, mark_price: Decimal) -> Decimal {
+        (mark_price - self.average_price) * Decimal::from(self.quantity)
+    }
+
+    /// Books a fill into the position.
+    fn apply(&mut self, fill: &Fill) {
+        let quantity = fill.quantity as i64;
+        let signed_quantity = match fill.side {
+            Side::Bid => quantity,
+            Side::Ask => -quantity,
+        };
+        if self.quantity == 0 || self.quantity.signum() == signed_quantity.signum() {
+            let held = Decimal::from(self.quantity.abs());
+            self.average_price = (self.average_price * held + fill.price * Decimal::from(quantity))
+                / (held + Decimal::from(quantity));
+        } else {
+            let closed = self.quantity.abs().min(quantity);
+            self.realized_pnl += (fill.price - self.average_price)
+                * Decimal::from(closed)
+                * Decimal::from(self.quantity.signum());
+            if quantity > self.quantity.abs() {
+                // The fill reversed the position, whose new part was opened at its price
+                self.average_price = fill.price;
+            }
+        }
+        self.quantity += signed_quantity;
+        if self.quantity == 0 {
+            self.average_price = Decimal::ZERO;
+        }
+    }
+}
+
+/// A strategy driven by `Backtest::run`.
+pub trait Strategy {
+    /// Called after every historical command is applied, with the events it produced.
+    ///
+    /// The strategy submits and cancels orders through `backtest`.
+    fn on_update(&mut self, backtest: &mut Backtest, events: &[OrderEvent]);
+
+    /// Called for every fill of the strategy's orders, before the next `on_update`.
+    fn on_fill(&mut self, _fill: &Fill) {}
+}
+
+/// A request of the strategy on its way to the book.
+#[derive(Debug, Clone)]
+enum Request {
+    /// An order to simulate
+    Submit(StrategyOrderId, Order),
+    /// The cancellation of an order
+    Cancel(StrategyOrderId),
+}
+
+/// A strategy's order resting next to the replayed book.
+#[derive(Debug, Clone)]
+struct SimulatedOrder {
+    /// The order, whose quantity is the quantity left to fill
+    order: Order,
+    /// The quantity assumed to rest ahead of the order at its price
+    queue_ahead: u64,
+}
+
+/// A replayed book with the strategy's simulated orders, their fills, and its position.
+///
+/// See the module documentation for the fill model.
+#[derive(Debug)]
+pub struct Backtest {
+    /// The book the history is replayed into
+    order_book: OrderBook,
+    /// The fill assumptions
+    fill_model: FillModel,
+    /// The identifier of the last order submitted
+    last_order_id: u64,
+    /// The requests on their way to the book, with their arrival times, in arrival order
+    requests: VecDeque<(u64, Request)>,
+    /// The strategy's orders resting next to the book
+    open_orders: BTreeMap<StrategyOrderId, SimulatedOrder>,
+    /// Every fill, oldest first
+    fills: Vec<Fill>,
+    /// The number of fills `run` reported to its strategy
+    reported_fills: usize,
+    /// The strategy's position
+    position: Position,
+    /// The number of historical commands `run` skipped for being rejected by the book
+    rejected_commands: usize,
+}
+
+impl Backtest {
+    /// Creates a backtest replaying history into a book.
+    ///
+    /// Latency is measured by the book's clock, so the history should set the book's
+    /// time (`Command::SetTime`), or the book be created with `OrderBook::deterministic`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book to replay into, usually empty
+    /// * `fill_model`: The fill assumptions
+    pub fn new(order_book: OrderBook, fill_model: FillModel) -> Self {
+        Backtest {
+            order_book,
+            fill_model,
+            last_order_id: 0,
+            requests: VecDeque::new(),
+            open_orders: BTreeMap::new(),
+            fills: Vec::new(),
+            reported_fills: 0,
+            position: Position::default(),
+            rejected_commands: 0,
+        }
+    }
+
+    /// Submits a strategy's order, which reaches the book after the model's latency.
+    ///
+    /// Without latency, the order arrives at once, and its crossing part is filled
+    /// before this returns.
+    pub fn submit(&mut self, order: Order) -> StrategyOrderId {
+        self.last_order_id += 1;
+        let order_id = StrategyOrderId(self.last_order_id);
+        self.send(Request::Submit(order_id, order));
+        order_id
+    }
+
+    /// Requests the cancellation of a strategy's order, which takes effect after the
+    /// model's latency, unless the order is filled in full meanwhile.
+    ///
+    /// ## Returns
+    ///
+    /// `false` if the order is neither resting nor on its way to the book
+    pub fn cancel(&mut self, order_id: StrategyOrderId) -> bool {
+        let pending = self
+            .requests
+            .iter()
+            .any(|(_, request)| matches!(request, Request::Submit(id, _) if *id == order_id));
+        if !pending && !self.open_orders.contains_key(&order_id) {
+            return false;
+        }
+        self.send(Request::Cancel(order_id));
+        true
+    }
+
+    /// Applies a historical command to the book and fills the strategy's resting orders
+    /// from the trades it printed.
+    ///
+    /// Requests due by the book's time after the command then arrive.
+    ///
+    /// ## Returns
+    ///
+    /// The command's events, or the book's error if it rejected the command, leaving
+    /// the book and the strategy's orders unchanged
+    pub fn step(&mut self, command: Command) -> Result<Vec<OrderEvent>> {
+        let levels_before: Vec<u64> = match self.fill_model.queue_model {
+            QueueModel::Proportional => self
+                .open_orders
+                .values()
+                .map(|open| {
+                    self.order_book
+                        .quantity_at(open.order.price, open.order.side)
+                })
+                .collect(),
+            QueueModel::BackOfQueue | QueueModel::FrontOfQueue => Vec::new(),
+        };
+        let trades_before = self.order_book.trades().len();
+        let events = self.order_book.apply(command)?;
+        // Ending a session hands its trades over, and prints none
+        let trades = self
+            .order_book
+            .trades()
+            .get(trades_before..)
+            .unwrap_or_default()
+            .to_vec();
+
+        if !levels_before.is_empty() {
+            self.advance_queues(&events, &trades, &levels_before);
+        }
+        self.fill_from_trades(&trades);
+        self.deliver_due_requests();
+        Ok(events)
+    }
+
+    /// Replays historical commands, letting a strategy trade after every one of them.
+    ///
+    /// Commands the book rejects are skipped, as they left the book unchanged when they
+    /// were recorded, and counted by `rejected_commands`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `commands`: The history to replay, oldest first
+    /// * `strategy`: The strategy to drive
+    pub fn run(
+        &mut self,
+        commands: impl IntoIterator<Item = Command>,
+        strategy: &mut impl Strategy,
+    ) {
+        for command in commands {
+            let Ok(events) = self.step(command) else {
+                self.rejected_commands += 1;
+                continue;
+            };
+            self.report_fills(strategy);
+            strategy.on_update(self, &events);
+        }
+        self.report_fills(strategy);
+    }
+
+    /// Returns the book the history is replayed into.
+    pub fn order_book(&self) -> &OrderBook {
+        &self.order_book
+    }
+
+    /// Returns every fill, oldest first.
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Returns the strategy's position.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns the strategy's orders resting next to the book, with the quantity left
+    /// to fill, by identifier.
+    pub fn open_orders(&self) -> impl Iterator<Item = (StrategyOrderId, &Order)> {
+        self.open_orders
+            .iter()
+            .map(|(order_id, open)| (*order_id, &open.order))
+    }
+
+    /// Returns the number of submissions and cancellations on their way to the book.
+    pub fn pending_requests(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns the number of historical commands `run` skipped for being rejected.
+    pub fn rejected_commands(&self) -> usize {
+        self.rejected_commands
+    }
+
+    /// Returns the realized P&L plus the unrealized P&L marked at the book's mid price,
+    /// or `None` if a position is held while the book has no mid price.
+    pub fn total_pnl(&self) -> Option<Decimal> {
+        if self.position.quantity == 0 {
+            return Some(self.position.realized_pnl);
+        }
+        let bbo = self.order_book.bbo();
+        let mid_price = (bbo.best_bid? + bbo.best_ask?) / Decimal::TWO;
+        Some(self.position.realized_pnl + self.position.unrealized_pnl(mid_price))
+    }
+
+    /// Returns the book, dropping the strategy's orders.
+    pub fn into_inner(self) -> OrderBook {
+        self.order_book
+    }
+
+    /// Sends a request to the book, delivering it at once if there is no latency.
+    fn send(&mut self, request: Request) {
+        let latency_nanos = u64::try_from(self.fill_model.latency.as_nanos()).unwrap_or(u64::MAX);
+        let arrival_nanos = self.order_book.now_nanos().saturating_add(latency_nanos);
+        self.requests.push_back((arrival_nanos, request));
+        self.deliver_due_requests();
+    }
+
+    /// Delivers the requests whose arrival time the book's clock has reached.
+    fn deliver_due_requests(&mut self) {
+        let now_nanos = self.order_book.now_nanos();
+        while self
+            .requests
+            .front()
+            .is_some_and(|(arrival_nanos, _)| *arrival_nanos <= now_nanos)
+        {
+            let (_, request) = self.requests.pop_front().expect("front must exist");
+            match request {
+                Request::Submit(order_id, order) => self.arrive(order_id, order),
+                Request::Cancel(order_id) => {
+                    self.open_orders.remove(&order_id);
+                }
+            }
+        }
+    }
+
+    /// Fills the crossing part of an arriving order against the opposite side, and rests
+    /// the remainder behind the queue the model assumes.
+    fn arrive(&mut self, order_id: StrategyOrderId, mut order: Order) {
+        let crossing: Vec<(Decimal, u64)> = self
+            .order_book
+            .iter(order.side.opposite())
+            .take_while(|(price, _)| match order.side {
+                Side::Bid => *price <= order.price,
+                Side::Ask => *price >= order.price,
+            })
+            .collect();
+        for (price, quantity) in crossing {
+            if order.quantity == 0 {
+                break;
+            }
+            let quantity = quantity.min(order.quantity);
+            order.quantity -= quantity;
+            self.record_fill(order_id, order.side, price, quantity, Liquidity::Taker);
+        }
+        if order.quantity == 0 {
+            return;
+        }
+        let queue_ahead = match self.fill_model.queue_model {
+            QueueModel::BackOfQueue | QueueModel::Proportional => {
+                self.order_book.quantity_at(order.price, order.side)
+            }
+            QueueModel::FrontOfQueue => 0,
+        };
+        self.open_orders
+            .insert(order_id, SimulatedOrder { order, queue_ahead });
+    }
+
+    /// Shrinks the queues ahead of the resting orders by the cancellations at their
+    /// prices, in proportion to the part of the level ahead of them.
+    ///
+    /// ## Arguments
+    ///
+    /// * `events`: The events of the command applied
+    /// * `trades`: The trades it printed
+    /// * `levels_before`: The quantity resting at each open order's price before the
+    ///   command, in the order of `open_orders`
+    fn advance_queues(&mut self, events: &[OrderEvent], trades: &[Trade], levels_before: &[u64]) {
+        for (open, level_before) in self.open_orders.values_mut().zip(levels_before) {
+            let (price, side) = (open.order.price, open.order.side);
+            let removed: u64 = events
+                .iter()
+                .filter(|event| event.side == side && event.price == price)
+                .map(|event| event.quantity_delta.min(0).unsigned_abs())
+                .sum();
+            let traded: u64 = trades
+                .iter()
+                .filter(|trade| trade.aggressor_side != side && trade.price == price)
+                .map(|trade| trade.quantity)
+                .sum();
+            let cancelled = removed.saturating_sub(traded);
+            if cancelled == 0 || *level_before == 0 {
+                continue;
+            }
+            let share =
+                u128::from(cancelled) * u128::from(open.queue_ahead) / u128::from(*level_before);
+            open.queue_ahead = open.queue_ahead.saturating_sub(share as u64);
+        }
+    }
+
+    /// Fills the resting orders from the trades printed through or at their prices.
+    ///
+    /// Each trade's quantity is shared between the orders it reaches, best price first
+    /// and then in submission order, like a level's priority.
+    fn fill_from_trades(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            let resting_side = trade.aggressor_side.opposite();
+            let mut reached: Vec<(StrategyOrderId, Decimal)> = self
+                .open_orders
+                .iter()
+                .filter(|(_, open)| {
+                    open.order.side == resting_side
+                        && match resting_side {
+                            Side::Bid => trade.price <= open.order.price,
+                            Side::Ask => trade.price >= open.order.price,
+                        }
+                })
+                .map(|(order_id, open)| (*order_id, open.order.price))
+                .collect();
+            // The sort is stable, so orders at the same price stay in submission order
+            match resting_side {
+                Side::Bid => reached.sort_by_key(|(_, price)| std::cmp::Reverse(*price)),
+                Side::Ask => reached.sort_by_key(|(_, price)| *price),
+            }
+
+            let mut available = trade.quantity;
+            for (order_id, price) in reached {
+                if available == 0 {
+                    break;
+                }
+                let open = self
+                    .open_orders
+                    .get_mut(&order_id)
+                    .expect("reached order must be open");
+                if trade.price == price {
+                    let consumed = open.queue_ahead.min(available);
+                    open.queue_ahead -= consumed;
+                    available -= consumed;
+                } else {
+                    // The trade went through the price, clearing the queue at it
+                    open.queue_ahead = 0;
+                }
+                let quantity = open.order.quantity.min(available);
+                if quantity == 0 {
+                    continue;
+                }
+                open.order.quantity -= quantity;
+                available -= quantity;
+                if open.order.quantity == 0 {
+                    self.open_orders.remove(&order_id);
+                }
+                self.record_fill(order_id, resting_side, price, quantity, Liquidity::Maker);
+            }
+        }
+    }
+
+    /// Appends a fill to the log and books it into the position.
+    fn record_fill(
+        &mut self,
+        order_id: StrategyOrderId,
+        side: Side,
+        price: Decimal,
+        quantity: u64,
+        liquidity: Liquidity,
+    ) {
+        let fill = Fill {
+            order_id,
+            side,
+            price,
+            quantity,
+            timestamp_nanos: self.order_book.now_nanos(),
+            liquidity,
+        };
+        self.position.apply(&fill);
+        self.fills.push(fill);
+    }
+
+    /// Hands the fills not reported yet to a strategy.
+    fn report_fills(&mut self, strategy: &mut impl Strategy) {
+        for fill in &self.fills[self.reported_fills..] {
+            strategy.on_fill(fill);
+        }
+        self.reported_fills = self.fills.len();
+    }
+}
@@ -0,0 +1,168 @@
+//! A read-only view of a book's levels, shared by the book and its copies.
+//!
+//! Analytics such as imbalance, impact, or VWAP only need the levels of each side, which
+//! the live `OrderBook`, a `BookSnapshot` of it, the `MarketDepthCache` (which may
+//! consolidate several venues), and a `DepthSnapshot` of the cache all have. `BookView`
+//! exposes them uniformly, so such code is written once, generic over the view, and
+//! runs unchanged on any of them.
+
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::types::{AggregatedDepthMap, Bbo, BookSnapshot, DepthSnapshot, Order, OrderId, Side};
+use rust_decimal::Decimal;
+use std::iter::Peekable;
+
+/// The levels of one side of a book, best first.
+pub type LevelIter<'a> = Box<dyn Iterator<Item = (Decimal, u64)> + 'a>;
+
+/// Read-only access to the levels of a book.
+///
+/// A level is a price with the total quantity resting at it: exact prices for
+/// `OrderBook` and `BookSnapshot`, aggregated levels for `MarketDepthCache` and
+/// `DepthSnapshot`.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{BookView, MarketDepthCache, Order, OrderBook, Side};
+/// use rust_decimal::Decimal;
+///
+/// /// The bid share of the quantity resting in the top `levels` levels of both sides.
+/// fn imbalance(view: &impl BookView, levels: usize) -> Option<Decimal> {
+///     let quantity = |side| -> u64 {
+///         view.top_levels(side, levels).iter().map(|level| level.1).sum()
+///     };
+///     let (bids, asks) = (quantity(Side::Bid), quantity(Side::Ask));
+///     (bids + asks > 0).then(|| Decimal::from(bids) / Decimal::from(bids + asks))
+/// }
+///
+/// let mut order_book = OrderBook::new();
+/// let cache = MarketDepthCache::new();
+/// for order in [Order::new(99.00, 30, Side::Bid), Order::new(101.00, 10, Side::Ask)] {
+///     cache.process_order_event(order_book.insert_order(order).event);
+/// }
+///
+/// let expected = Some(Decimal::new(75, 2));
+/// assert_eq!(imbalance(&order_book, 5), expected);
+/// assert_eq!(imbalance(&order_book.snapshot(), 5), expected);
+/// assert_eq!(imbalance(&cache, 5), expected);
+/// assert_eq!(imbalance(&cache.snapshot(), 5), expected);
+/// ```
+pub trait BookView {
+    /// Returns the levels of one side and their quantities, best first.
+    fn iter(&self, side: Side) -> LevelIter<'_>;
+
+    /// Returns the quantity resting at a level, or 0 if it is empty.
+    fn quantity_at(&self, price: Decimal, side: Side) -> u64;
+
+    /// Returns the best bid and ask prices.
+    fn bbo(&self) -> Bbo {
+        let best = |side| self.iter(side).next().map(|(price, _)| price);
+        Bbo {
+            best_bid: best(Side::Bid),
+            best_ask: best(Side::Ask),
+        }
+    }
+
+    /// Returns up to `max_levels` levels of one side and their quantities, best first.
+    fn top_levels(&self, side: Side, max_levels: usize) -> Vec<(Decimal, u64)> {
+        self.iter(side).take(max_levels).collect()
+    }
+}
+
+impl BookView for OrderBook {
+    fn iter(&self, side: Side) -> LevelIter<'_> {
+        match side {
+            Side::Bid => Box::new(self.level_quantities(side).rev()),
+            Side::Ask => Box::new(self.level_quantities(side)),
+        }
+    }
+
+    fn quantity_at(&self, price: Decimal, side: Side) -> u64 {
+        self.level(price, side)
+            .map_or(0, |price_level| price_level.total_quantity())
+    }
+
+    fn bbo(&self) -> Bbo {
+        OrderBook::bbo(self)
+    }
+}
+
+impl BookView for BookSnapshot {
+    fn iter(&self, side: Side) -> LevelIter<'_> {
+        let orders = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        Box::new(SnapshotLevels {
+            orders: orders.iter().peekable(),
+        })
+    }
+
+    fn quantity_at(&self, price: Decimal, side: Side) -> u64 {
+        self.iter(side)
+            .find(|(level_price, _)| *level_price == price)
+            .map_or(0, |(_, quantity)| quantity)
+    }
+}
+
+impl BookView for MarketDepthCache {
+    fn iter(&self, side: Side) -> LevelIter<'_> {
+        Box::new(self.top_levels_of(side, usize::MAX).into_iter())
+    }
+
+    fn quantity_at(&self, price: Decimal, side: Side) -> u64 {
+        self.get_quantity_at_level(price, side)
+    }
+
+    fn top_levels(&self, side: Side, max_levels: usize) -> Vec<(Decimal, u64)> {
+        self.top_levels_of(side, max_levels)
+    }
+}
+
+impl BookView for DepthSnapshot {
+    fn iter(&self, side: Side) -> LevelIter<'_> {
+        let levels = self
+            .side(side)
+            .iter()
+            .map(|(price, quantity)| (*price, *quantity));
+        match side {
+            Side::Bid => Box::new(levels.rev()),
+            Side::Ask => Box::new(levels),
+        }
+    }
+
+    fn quantity_at(&self, price: Decimal, side: Side) -> u64 {
+        self.side(side).get(&price).copied().unwrap_or(0)
+    }
+}
+
+impl DepthSnapshot {
+    /// Returns the levels of one side.
+    fn side(&self, side: Side) -> &AggregatedDepthMap {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+}
+
+/// The levels of one side of a `BookSnapshot`, summing the consecutive orders at each
+/// price.
+struct SnapshotLevels<'a> {
+    /// The side's orders, best price first
+    orders: Peekable<std::slice::Iter<'a, (OrderId, Order)>>,
+}
+
+impl Iterator for SnapshotLevels<'_> {
+    type Item = (Decimal, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, first) = self.orders.next()?;
+        let mut quantity = first.quantity;
+        while let Some((_, order)) = self.orders.next_if(|(_, order)| order.price == first.price) {
+            quantity = quantity.saturating_add(order.quantity);
+        }
+        Some((first.price, quantity))
+    }
+}
@@ -0,0 +1,185 @@
+use crate::types::OrderEvent;
+use parking_lot::RwLock;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// A consumer's position in a `BroadcastLog`: the number of events published before
+/// the next one it reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogCursor {
+    /// The position of the next event to read
+    position: u64,
+}
+
+impl LogCursor {
+    /// Returns the position of the next event the cursor reads.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+/// The error of reading a `BroadcastLog` with a cursor whose next event was already
+/// overwritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lapped {
+    /// How many events the consumer missed
+    pub missed_events: u64,
+}
+
+impl fmt::Display for Lapped {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "consumer was lapped and missed {} events",
+            self.missed_events
+        )
+    }
+}
+
+impl std::error::Error for Lapped {}
+
+/// The retained events and the position after the newest.
+#[derive(Debug)]
+struct Ring {
+    /// The retained events, oldest first
+    events: VecDeque<OrderEvent>,
+    /// The position the next published event takes
+    next_position: u64,
+}
+
+impl Ring {
+    /// Returns the position of the oldest retained event.
+    fn oldest_position(&self) -> u64 {
+        self.next_position - self.events.len() as u64
+    }
+}
+
+/// A log retaining the last events published to it, read by any number of consumers
+/// at their own pace.
+///
+/// Unlike an `event_channel`, the log does not deliver events: each consumer pulls them
+/// with its own `LogCursor`, so a slow consumer never holds back the producer or the
+/// other consumers. The log retains the last `capacity` events only, overwriting the
+/// oldest as new ones are published; a consumer that falls further behind is lapped,
+/// which its next `read` reports with the number of events it missed, after which it
+/// must resynchronize (e.g. with `MarketDepthCache::rebuild_from`) and reads on from
+/// the oldest retained event.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{BroadcastLog, Lapped, Order, OrderBook, Side};
+///
+/// let mut order_book = OrderBook::new();
+/// let log = BroadcastLog::new(2);
+/// let (mut fast, mut slow) = (log.cursor(), log.cursor());
+///
+/// let mut events = Vec::new();
+/// for price in [100.00, 101.00, 102.00] {
+///     log.publish(order_book.insert_order(Order::new(price, 10, Side::Ask)).event);
+///     // The fast consumer keeps up
+///     assert_eq!(log.read(&mut fast, &mut events, usize::MAX), Ok(1));
+/// }
+/// assert_eq!(events.len(), 3);
+///
+/// // The slow one fell three events behind a log retaining two
+/// assert_eq!(log.read(&mut slow, &mut events, usize::MAX), Err(Lapped { missed_events: 1 }));
+/// assert_eq!(log.read(&mut slow, &mut events, usize::MAX), Ok(2));
+/// ```
+#[derive(Debug)]
+pub struct BroadcastLog {
+    /// The number of events retained
+    capacity: usize,
+    /// The retained events
+    ring: RwLock<Ring>,
+}
+
+impl BroadcastLog {
+    /// Creates an empty log retaining the last `capacity` events, at least one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        BroadcastLog {
+            capacity,
+            ring: RwLock::new(Ring {
+                events: VecDeque::with_capacity(capacity),
+                next_position: 0,
+            }),
+        }
+    }
+
+    /// Returns the number of events the log retains.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Appends an event, overwriting the oldest retained one if the log is full.
+    ///
+    /// ## Returns
+    ///
+    /// The position of the event
+    pub fn publish(&self, event: OrderEvent) -> u64 {
+        let mut ring = self.ring.write();
+        if ring.events.len() == self.capacity {
+            ring.events.pop_front();
+        }
+        ring.events.push_back(event);
+        ring.next_position += 1;
+        ring.next_position - 1
+    }
+
+    /// Returns a cursor reading the events published from now on.
+    pub fn cursor(&self) -> LogCursor {
+        LogCursor {
+            position: self.ring.read().next_position,
+        }
+    }
+
+    /// Returns a cursor reading every retained event, oldest first, then those
+    /// published from now on.
+    pub fn cursor_at_oldest(&self) -> LogCursor {
+        LogCursor {
+            position: self.ring.read().oldest_position(),
+        }
+    }
+
+    /// Returns how many events a cursor has not read yet, or `None` if it was lapped.
+    pub fn backlog(&self, cursor: &LogCursor) -> Option<u64> {
+        let ring = self.ring.read();
+        (cursor.position >= ring.oldest_position()).then(|| ring.next_position - cursor.position)
+    }
+
+    /// Copies the events a cursor has not read yet, oldest first, and advances it past
+    /// them.
+    ///
+    /// ## Arguments
+    ///
+    /// * `cursor`: The consumer's cursor
+    /// * `events`: The buffer the events are appended to
+    /// * `max_events`: The maximum number of events to read
+    ///
+    /// ## Returns
+    ///
+    /// The number of events read, or `Lapped` if some of the events the cursor had not
+    /// read were overwritten; the cursor then moves to the oldest retained event,
+    /// where the next read continues
+    pub fn read(
+        &self,
+        cursor: &mut LogCursor,
+        events: &mut Vec<OrderEvent>,
+        max_events: usize,
+    ) -> Result<usize, Lapped> {
+        let ring = self.ring.read();
+        let oldest_position = ring.oldest_position();
+        if cursor.position < oldest_position {
+            let missed_events = oldest_position - cursor.position;
+            cursor.position = oldest_position;
+            return Err(Lapped { missed_events });
+        }
+
+        let start = (cursor.position - oldest_position) as usize;
+        let count = (ring.events.len() - start).min(max_events);
+        events.extend(ring.events.range(start..start + count).cloned());
+        cursor.position += count as u64;
+        Ok(count)
+    }
+}
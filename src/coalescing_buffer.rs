@@ -0,0 +1,124 @@
+use crate::order_book::OrderBook;
+use crate::types::{OrderEvent, Side};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+/// A buffer between an event source and a slow depth consumer that merges the pending
+/// deltas of each aggregated price level into one.
+///
+/// Events are bucketed like `MarketDepthCache` aggregates them, by side and by
+/// `OrderBook::aggregate_price_to_level`. While a consumer is busy, every further event
+/// for a bucket that is already pending only adjusts that bucket's cumulative delta,
+/// so the buffer holds at most one event per distinct level however fast events
+/// arrive. Applying the drained events to a depth view yields the same depth as
+/// applying every original event, since depth deltas commute.
+///
+/// Like the cache, the buffer takes `&self` and can be shared through an `Arc`
+/// between the producing and the consuming thread.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{CoalescingBuffer, MarketDepthCache, Order, OrderBook, Side};
+/// use rust_decimal::Decimal;
+///
+/// let mut order_book = OrderBook::new();
+/// let coalescing_buffer = CoalescingBuffer::new();
+///
+/// for price in [100.10, 100.20, 100.30] {
+///     coalescing_buffer.push(order_book.insert_order(Order::new(price, 10, Side::Bid)).event);
+/// }
+/// assert_eq!(coalescing_buffer.len(), 1);
+///
+/// let cache = MarketDepthCache::new();
+/// for event in coalescing_buffer.drain() {
+///     cache.process_order_event(event);
+/// }
+/// assert_eq!(cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid), 30);
+/// ```
+#[derive(Debug, Default)]
+pub struct CoalescingBuffer {
+    /// The pending events and the number of events merged into them
+    pending: Mutex<PendingEvents>,
+}
+
+/// The coalesced events waiting for the consumer.
+#[derive(Debug, Default)]
+struct PendingEvents {
+    /// One event per bucket, in the order the buckets first became pending
+    events: Vec<OrderEvent>,
+    /// Position in `events` of each pending bucket
+    positions: HashMap<(Side, Decimal), usize>,
+    /// Number of pushed events merged into an already pending one, since creation
+    coalesced_count: u64,
+}
+
+impl CoalescingBuffer {
+    /// Creates a new empty buffer.
+    pub fn new() -> Self {
+        CoalescingBuffer::default()
+    }
+
+    /// Adds an event, merging it into the pending event of its bucket if there is one.
+    ///
+    /// Pending events carry their bucket's aggregated level as their price. A merged
+    /// event keeps the timestamp of the oldest event of its bucket, so latency measured
+    /// downstream reflects how long the change has been waiting, and the sequence number
+    /// of the newest, as applying it brings a consumer up to date with that event.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The order event published by the order book
+    pub fn push(&self, event: OrderEvent) {
+        let bucket = (event.side, OrderBook::aggregate_price_to_level(event.price));
+        let mut pending = self.pending.lock();
+
+        if let Some(&position) = pending.positions.get(&bucket) {
+            let pending_event = &mut pending.events[position];
+            pending_event.quantity_delta += event.quantity_delta;
+            pending_event.order_count_delta += event.order_count_delta;
+            pending_event.sequence = pending_event.sequence.max(event.sequence);
+            pending.coalesced_count += 1;
+            return;
+        }
+
+        let position = pending.events.len();
+        pending.positions.insert(bucket, position);
+        pending.events.push(OrderEvent {
+            price: bucket.1,
+            ..event
+        });
+    }
+
+    /// Takes every pending event, one per bucket with its cumulative delta.
+    ///
+    /// Buckets whose deltas cancelled out are left out.
+    ///
+    /// ## Returns
+    ///
+    /// The coalesced events, in the order their buckets first became pending
+    pub fn drain(&self) -> Vec<OrderEvent> {
+        let mut pending = self.pending.lock();
+        pending.positions.clear();
+
+        let mut events = std::mem::take(&mut pending.events);
+        events.retain(|event| event.quantity_delta != 0 || event.order_count_delta != 0);
+        events
+    }
+
+    /// Returns the number of pending buckets.
+    pub fn len(&self) -> usize {
+        self.pending.lock().events.len()
+    }
+
+    /// Returns whether no event is pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many pushed events were merged into an already pending event.
+    pub fn coalesced_count(&self) -> u64 {
+        self.pending.lock().coalesced_count
+    }
+}
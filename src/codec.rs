@@ -0,0 +1,229 @@
+//! Compact binary codecs for streaming events and trades over sockets.
+//!
+//! `MessagePack` (with the `msgpack` feature) and `Bincode` (with the `bincode`
+//! feature) encode any value implementing serde's traits, which `OrderEvent` and
+//! `Trade` do with the `serde` feature. On a byte stream, `FrameWriter` prefixes every
+//! encoded value with its length, as a little-endian `u32`, and `FrameReader` splits
+//! the stream back into values, so messages survive the arbitrary chunking of TCP.
+//! Prices are encoded as decimal strings, so they cross the wire exactly.
+//!
+//! ## Examples
+//!
+//! ```
+//! # #[cfg(feature = "msgpack")]
+//! # {
+//! use order_book::codec::{FrameReader, FrameWriter, MessagePack};
+//! use order_book::{Order, OrderBook, OrderEvent, Side};
+//!
+//! let mut order_book = OrderBook::new();
+//! let mut socket = Vec::new();
+//! let mut writer = FrameWriter::new(&mut socket, MessagePack);
+//! for price in [100.00, 101.00] {
+//!     let event = order_book.insert_order(Order::new(price, 10, Side::Ask)).event;
+//!     writer.write(&event).unwrap();
+//! }
+//!
+//! let mut reader = FrameReader::new(&socket[..], MessagePack);
+//! let first: OrderEvent = reader.read().unwrap().unwrap();
+//! assert_eq!(first.sequence, 1);
+//! assert!(reader.read::<OrderEvent>().unwrap().is_some());
+//! assert!(reader.read::<OrderEvent>().unwrap().is_none());
+//! # }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// The largest frame `FrameReader` accepts, guarding against corrupt length prefixes.
+pub const MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Errors raised while encoding, decoding, or framing values.
+#[derive(Debug)]
+pub enum CodecError {
+    /// Reading from or writing to the stream failed
+    Io(io::Error),
+    /// The value could not be encoded
+    Encode(String),
+    /// The frame's contents could not be decoded as the requested type
+    Decode(String),
+    /// A frame is longer than `MAX_FRAME_LENGTH`
+    FrameTooLarge(usize),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(error) => write!(formatter, "i/o error: {error}"),
+            CodecError::Encode(reason) => write!(formatter, "cannot encode value: {reason}"),
+            CodecError::Decode(reason) => write!(formatter, "cannot decode frame: {reason}"),
+            CodecError::FrameTooLarge(length) => write!(
+                formatter,
+                "frame of {length} bytes exceeds the limit of {MAX_FRAME_LENGTH} bytes"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<io::Error> for CodecError {
+    fn from(error: io::Error) -> Self {
+        CodecError::Io(error)
+    }
+}
+
+/// A binary serialization format.
+pub trait Codec {
+    /// Appends the encoding of `value` to `buffer`.
+    fn encode<T: Serialize>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), CodecError>;
+
+    /// Decodes a value from the whole of `bytes`.
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// The MessagePack format, via `rmp-serde`. Only available with the `msgpack` feature.
+///
+/// Structs are encoded as maps keyed by field name, so consumers in other languages
+/// can decode them without a schema.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePack {
+    fn encode<T: Serialize>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), CodecError> {
+        value
+            .serialize(&mut rmp_serde::Serializer::new(buffer).with_struct_map())
+            .map_err(|error| CodecError::Encode(error.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        rmp_serde::from_slice(bytes).map_err(|error| CodecError::Decode(error.to_string()))
+    }
+}
+
+/// The bincode format, the most compact but Rust-specific. Only available with the
+/// `bincode` feature.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Codec for Bincode {
+    fn encode<T: Serialize>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), CodecError> {
+        bincode::serialize_into(buffer, value)
+            .map_err(|error| CodecError::Encode(error.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        bincode::deserialize(bytes).map_err(|error| CodecError::Decode(error.to_string()))
+    }
+}
+
+/// Writes length-prefixed frames of encoded values to a byte stream.
+#[derive(Debug)]
+pub struct FrameWriter<W, C> {
+    /// The underlying stream
+    writer: W,
+    /// The format of the frames' contents
+    codec: C,
+    /// The frame being assembled, reused across writes
+    buffer: Vec<u8>,
+}
+
+impl<W: Write, C: Codec> FrameWriter<W, C> {
+    /// Creates a writer encoding values with `codec` onto `writer`.
+    pub fn new(writer: W, codec: C) -> Self {
+        FrameWriter {
+            writer,
+            codec,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Encodes a value and writes it as one frame.
+    ///
+    /// ## Arguments
+    ///
+    /// * `value`: The value to send, e.g. an `OrderEvent` or a `Trade`
+    pub fn write<T: Serialize>(&mut self, value: &T) -> Result<(), CodecError> {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&[0; 4]);
+        self.codec.encode(value, &mut self.buffer)?;
+
+        let length = self.buffer.len() - 4;
+        if length > MAX_FRAME_LENGTH {
+            return Err(CodecError::FrameTooLarge(length));
+        }
+        self.buffer[..4].copy_from_slice(&(length as u32).to_le_bytes());
+        self.writer.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    /// Flushes the underlying stream.
+    pub fn flush(&mut self) -> Result<(), CodecError> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads length-prefixed frames written by `FrameWriter` and decodes their values.
+#[derive(Debug)]
+pub struct FrameReader<R, C> {
+    /// The underlying stream
+    reader: R,
+    /// The format of the frames' contents
+    codec: C,
+    /// The contents of the frame being read, reused across reads
+    buffer: Vec<u8>,
+}
+
+impl<R: Read, C: Codec> FrameReader<R, C> {
+    /// Creates a reader decoding values with `codec` from `reader`.
+    pub fn new(reader: R, codec: C) -> Self {
+        FrameReader {
+            reader,
+            codec,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Reads the next frame and decodes its value.
+    ///
+    /// ## Returns
+    ///
+    /// The value, `None` if the stream ended cleanly between frames, or an error; a
+    /// stream ending inside a frame is reported as an `UnexpectedEof` I/O error
+    pub fn read<T: DeserializeOwned>(&mut self) -> Result<Option<T>, CodecError> {
+        let mut prefix = [0; 4];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            match self.reader.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let length = u32::from_le_bytes(prefix) as usize;
+        if length > MAX_FRAME_LENGTH {
+            return Err(CodecError::FrameTooLarge(length));
+        }
+        self.buffer.resize(length, 0);
+        self.reader.read_exact(&mut self.buffer)?;
+        self.codec.decode(&self.buffer).map(Some)
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
@@ -0,0 +1,309 @@
+//! A conformance kit checking `OrderBook` against a trivially correct reference book.
+//!
+//! `ReferenceBook` keeps every resting order in a single `Vec` in arrival order and
+//! answers every question with a linear scan, which makes it slow but easy to verify
+//! by reading. `check_conformance` runs the same command sequence against both books
+//! and compares the trades, the depth of every exact price level, and the best bid and
+//! ask after every command, so that performance redesigns of `OrderBook` can be
+//! validated automatically against long generated sequences.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::conformance::{check_conformance, Command};
+//! use order_book::{Order, OrderId, Side};
+//!
+//! let commands = vec![
+//!     Command::Insert(Order::new(100.00, 10, Side::Ask)),
+//!     Command::Insert(Order::new(100.00, 5, Side::Ask)),
+//!     Command::Reduce(OrderId(1), 4),
+//!     Command::Match(Order::new(101.00, 8, Side::Bid)),
+//!     Command::Cancel(OrderId(2)),
+//! ];
+//! assert!(check_conformance(&commands).is_ok());
+//! ```
+
+use crate::error::{OrderBookError, Result};
+use crate::order_book::OrderBook;
+use crate::types::{ExactPriceLevelMap, Order, OrderId, Side, Trade, TradeId};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// An operation applied to both books by `check_conformance`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Rest an order without matching, as `OrderBook::insert_order` does
+    Insert(Order),
+    /// Match an order and rest the remainder, as `OrderBook::match_order` does
+    Match(Order),
+    /// Cancel a resting order, as `OrderBook::cancel_order` does
+    Cancel(OrderId),
+    /// Reduce a resting order, as `OrderBook::reduce_order` does
+    Reduce(OrderId, u64),
+}
+
+/// The first point at which `OrderBook` and `ReferenceBook` disagreed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceFailure {
+    /// Index of the command after which the books disagreed
+    pub step: usize,
+    /// The command after which the books disagreed
+    pub command: Command,
+    /// What differed, with both books' values
+    pub reason: String,
+}
+
+impl fmt::Display for ConformanceFailure {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "books diverged at step {} ({:?}): {}",
+            self.step, self.command, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ConformanceFailure {}
+
+/// A naive order book storing resting orders in arrival order in a `Vec`.
+///
+/// Orders are identified like in `OrderBook`, with identifiers assigned sequentially
+/// from 1, so the same command sequence addresses the same orders in both books.
+#[derive(Debug, Clone)]
+pub struct ReferenceBook {
+    /// Every resting order with its identifier, oldest first
+    orders: Vec<(OrderId, Order)>,
+    /// Identifier assigned to the next order
+    next_order_id: u64,
+    /// Identifier assigned to the next trade
+    next_trade_id: u64,
+    /// Every trade printed, oldest first
+    trades: Vec<Trade>,
+}
+
+impl ReferenceBook {
+    /// Creates a new empty reference book.
+    pub fn new() -> Self {
+        ReferenceBook {
+            orders: Vec::new(),
+            next_order_id: 1,
+            next_trade_id: 1,
+            trades: Vec::new(),
+        }
+    }
+
+    /// Rests an order without matching.
+    pub fn insert_order(&mut self, order: Order) -> OrderId {
+        let order_id = self.assign_order_id();
+        self.orders.push((order_id, order));
+        order_id
+    }
+
+    /// Matches an order against the oldest order at the best crossing price until it
+    /// no longer crosses, then rests the remainder.
+    pub fn match_order(&mut self, mut order: Order) -> OrderId {
+        let order_id = self.assign_order_id();
+
+        while order.quantity > 0 {
+            let crosses = |resting: &Order| match order.side {
+                Side::Bid => resting.side == Side::Ask && resting.price <= order.price,
+                Side::Ask => resting.side == Side::Bid && resting.price >= order.price,
+            };
+
+            // Strictly better prices replace the candidate, so ties keep the oldest order
+            let mut maker_index = None::<usize>;
+            for (index, (_, resting)) in self.orders.iter().enumerate() {
+                if !crosses(resting) {
+                    continue;
+                }
+                let better = maker_index.is_none_or(|maker_index| {
+                    let best = self.orders[maker_index].1.price;
+                    match order.side {
+                        Side::Bid => resting.price < best,
+                        Side::Ask => resting.price > best,
+                    }
+                });
+                if better {
+                    maker_index = Some(index);
+                }
+            }
+            let Some(maker_index) = maker_index else {
+                break;
+            };
+
+            let (maker_order_id, maker) = &mut self.orders[maker_index];
+            let quantity = order.quantity.min(maker.quantity);
+            self.trades.push(Trade {
+                price: maker.price,
+                quantity,
+                aggressor_side: order.side,
+                trade_id: TradeId(self.next_trade_id),
+                maker_order_id: *maker_order_id,
+                taker_order_id: order_id,
+            });
+            self.next_trade_id += 1;
+            maker.quantity -= quantity;
+            order.quantity -= quantity;
+            if maker.quantity == 0 {
+                self.orders.remove(maker_index);
+            }
+        }
+
+        if order.quantity > 0 {
+            self.orders.push((order_id, order));
+        }
+        order_id
+    }
+
+    /// Cancels a resting order.
+    pub fn cancel_order(&mut self, order_id: OrderId) -> Result<()> {
+        let index = self.index_of(order_id)?;
+        self.orders.remove(index);
+        Ok(())
+    }
+
+    /// Removes `quantity` from a resting order, cancelling it if nothing remains.
+    pub fn reduce_order(&mut self, order_id: OrderId, quantity: u64) -> Result<()> {
+        let index = self.index_of(order_id)?;
+        let remaining = self.orders[index].1.quantity;
+        if quantity == 0 || quantity > remaining {
+            return Err(OrderBookError::InvalidReduction {
+                order_id,
+                remaining,
+                requested: quantity,
+            });
+        }
+
+        if quantity == remaining {
+            self.orders.remove(index);
+        } else {
+            self.orders[index].1.quantity -= quantity;
+        }
+        Ok(())
+    }
+
+    /// Returns the best bid and best ask.
+    pub fn best_bid_and_ask(&self) -> (Option<Decimal>, Option<Decimal>) {
+        let prices = |side| {
+            self.orders
+                .iter()
+                .filter(move |(_, order)| order.side == side)
+                .map(|(_, order)| order.price)
+        };
+        (prices(Side::Bid).max(), prices(Side::Ask).min())
+    }
+
+    /// Returns the total quantity at every exact price on one side.
+    pub fn depth(&self, side: Side) -> ExactPriceLevelMap {
+        let mut depth = ExactPriceLevelMap::new();
+        for (_, order) in self.orders.iter().filter(|(_, order)| order.side == side) {
+            *depth.entry(order.price).or_insert(0) += order.quantity;
+        }
+        depth
+    }
+
+    /// Returns every trade printed, oldest first.
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
+    /// Returns the next unused order identifier.
+    fn assign_order_id(&mut self) -> OrderId {
+        let order_id = OrderId(self.next_order_id);
+        self.next_order_id += 1;
+        order_id
+    }
+
+    /// Returns the position of a resting order in `orders`.
+    fn index_of(&self, order_id: OrderId) -> Result<usize> {
+        self.orders
+            .iter()
+            .position(|(resting_id, _)| *resting_id == order_id)
+            .ok_or(OrderBookError::OrderNotFound(order_id))
+    }
+}
+
+impl Default for ReferenceBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a command sequence against a fresh `OrderBook` and a fresh `ReferenceBook`,
+/// comparing them after every command.
+///
+/// Prices are compared as written, so orders should not rely on price normalization
+/// (e.g. avoid quoting the same price with two different scales).
+///
+/// ## Arguments
+///
+/// * `commands`: The commands to apply, in order
+///
+/// ## Returns
+///
+/// `Ok(())` if the books agreed throughout, or the first `ConformanceFailure`
+pub fn check_conformance(commands: &[Command]) -> std::result::Result<(), ConformanceFailure> {
+    let mut order_book = OrderBook::new();
+    let mut reference_book = ReferenceBook::new();
+
+    for (step, command) in commands.iter().enumerate() {
+        let failure = |reason: String| ConformanceFailure {
+            step,
+            command: command.clone(),
+            reason,
+        };
+
+        let (result, expected) = match command {
+            Command::Insert(order) => {
+                order_book.insert_order(order.clone());
+                reference_book.insert_order(order.clone());
+                (Ok(()), Ok(()))
+            }
+            Command::Match(order) => {
+                order_book.match_order(order.clone());
+                reference_book.match_order(order.clone());
+                (Ok(()), Ok(()))
+            }
+            Command::Cancel(order_id) => (
+                order_book.cancel_order(*order_id).map(drop),
+                reference_book.cancel_order(*order_id),
+            ),
+            Command::Reduce(order_id, quantity) => (
+                order_book.reduce_order(*order_id, *quantity).map(drop),
+                reference_book.reduce_order(*order_id, *quantity),
+            ),
+        };
+        if result != expected {
+            return Err(failure(format!("result {result:?}, expected {expected:?}")));
+        }
+
+        if order_book.trades() != reference_book.trades() {
+            return Err(failure(format!(
+                "trades {:?}, expected {:?}",
+                order_book.trades(),
+                reference_book.trades()
+            )));
+        }
+
+        let (best_bid, best_ask, _) = order_book.compute_spread();
+        if (best_bid, best_ask) != reference_book.best_bid_and_ask() {
+            return Err(failure(format!(
+                "best bid and ask {:?}, expected {:?}",
+                (best_bid, best_ask),
+                reference_book.best_bid_and_ask()
+            )));
+        }
+
+        for side in [Side::Bid, Side::Ask] {
+            let depth = order_book.exact_price_levels(side);
+            let expected_depth = reference_book.depth(side);
+            if depth != expected_depth {
+                return Err(failure(format!(
+                    "{side:?} depth {depth:?}, expected {expected_depth:?}"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! The interface between a book's event stream and the depth it aggregates.
+//!
+//! `MarketDepthCache` is the crate's aggregator, but not the only sensible one: depth
+//! served to a UI may only need the top levels, a very active feed may want its levels
+//! sharded across locks, and a risk system may aggregate notional rather than
+//! quantity. `DepthAggregator` is what `SharedOrderBook` and `DepthDeltaPublisher`
+//! require of the depth they feed, so such an implementation is plugged into them in
+//! place of the cache, without changing the code calling them.
+
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::types::OrderEvent;
+use rust_decimal::Decimal;
+use std::time::{Duration, Instant};
+
+/// A consumer of a book's events maintaining aggregated depth.
+///
+/// Events are applied through a shared reference, as they are by the pipelines feeding
+/// the aggregator from the book's writer while readers query the depth, so
+/// implementations synchronize internally.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{
+///     DepthAggregator, MarketDepthCache, Order, OrderBook, OrderEvent, SharedOrderBook, Side,
+/// };
+/// use rust_decimal::Decimal;
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+///
+/// /// A cache counting the events applied to it.
+/// #[derive(Debug, Default)]
+/// struct CountingDepth {
+///     cache: MarketDepthCache,
+///     events: AtomicU64,
+/// }
+///
+/// impl DepthAggregator for CountingDepth {
+///     fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64) {
+///         self.events.fetch_add(1, Ordering::Relaxed);
+///         self.cache.apply_order_event(event)
+///     }
+///
+///     fn copy_top_levels_into(
+///         &self,
+///         bids: &mut Vec<(Decimal, u64)>,
+///         asks: &mut Vec<(Decimal, u64)>,
+///         n: usize,
+///     ) {
+///         self.cache.copy_top_levels_into(bids, asks, n);
+///     }
+///
+///     fn rebuild_from(&self, order_book: &OrderBook) {
+///         self.cache.rebuild_from(order_book);
+///     }
+///
+///     fn last_applied_sequence(&self) -> u64 {
+///         self.cache.last_applied_sequence()
+///     }
+///
+///     fn clear(&self) {
+///         self.cache.clear();
+///     }
+/// }
+///
+/// let depth = Arc::new(CountingDepth::default());
+/// let shared = SharedOrderBook::from_parts(Arc::default(), Arc::clone(&depth));
+/// shared.insert(Order::new(100.25, 10, Side::Bid));
+/// shared.insert(Order::new(101.00, 5, Side::Ask));
+///
+/// assert_eq!(shared.depth(1).0, vec![(Decimal::new(100, 0), 10)]);
+/// assert_eq!(depth.events.load(Ordering::Relaxed), 2);
+/// ```
+pub trait DepthAggregator: Send + Sync {
+    /// Applies an order event published by the book.
+    ///
+    /// ## Returns
+    ///
+    /// The level the event changed and the quantity now resting at it, zero when the
+    /// level is gone, as `DepthDeltaPublisher` publishes them
+    fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64);
+
+    /// Applies an order event published by the book, discarding the level it changed.
+    fn process_order_event(&self, event: OrderEvent) {
+        self.apply_order_event(&event);
+    }
+
+    /// Copies up to `n` levels closest to the touch on each side, best first, into
+    /// the given vectors, which are cleared first.
+    fn copy_top_levels_into(
+        &self,
+        bids: &mut Vec<(Decimal, u64)>,
+        asks: &mut Vec<(Decimal, u64)>,
+        n: usize,
+    );
+
+    /// Replaces the depth with that of the orders resting in a book.
+    fn rebuild_from(&self, order_book: &OrderBook);
+
+    /// Returns the highest sequence number among the events applied so far, or 0 if
+    /// none.
+    fn last_applied_sequence(&self) -> u64;
+
+    /// Removes every level.
+    fn clear(&self);
+
+    /// Blocks until the event with the given sequence number has been applied, or
+    /// `timeout` elapses, returning whether it was.
+    ///
+    /// Aggregators applying events as they receive them reflect an event as soon as
+    /// `apply_order_event` returns; those applying them asynchronously, e.g. from a
+    /// queue drained by another thread, should override this to wait without
+    /// polling. By default, `last_applied_sequence` is polled, yielding in between.
+    fn wait_for_sequence(&self, sequence: u64, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        while self.last_applied_sequence() < sequence {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            std::thread::yield_now();
+        }
+        true
+    }
+}
+
+impl DepthAggregator for MarketDepthCache {
+    fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64) {
+        MarketDepthCache::apply_order_event(self, event)
+    }
+
+    fn copy_top_levels_into(
+        &self,
+        bids: &mut Vec<(Decimal, u64)>,
+        asks: &mut Vec<(Decimal, u64)>,
+        n: usize,
+    ) {
+        MarketDepthCache::copy_top_levels_into(self, bids, asks, n);
+    }
+
+    fn rebuild_from(&self, order_book: &OrderBook) {
+        MarketDepthCache::rebuild_from(self, order_book);
+    }
+
+    fn last_applied_sequence(&self) -> u64 {
+        MarketDepthCache::last_applied_sequence(self)
+    }
+
+    fn clear(&self) {
+        MarketDepthCache::clear(self);
+    }
+
+    fn wait_for_sequence(&self, sequence: u64, timeout: Duration) -> bool {
+        MarketDepthCache::wait_for_sequence(self, sequence, timeout)
+    }
+}
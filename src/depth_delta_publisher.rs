@@ -0,0 +1,109 @@
+use crate::depth_aggregator::DepthAggregator;
+use crate::market_depth_cache::MarketDepthCache;
+use crate::types::{OrderEvent, Side};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+/// A level-2 update carrying the absolute quantity of an aggregated price level.
+///
+/// This is the format most downstream UIs and market data feeds expect: consumers
+/// overwrite their copy of the level instead of accumulating deltas, and a
+/// `new_quantity` of zero means the level should be deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthUpdate {
+    /// The aggregated price level that changed
+    pub price_bucket: Decimal,
+    /// The total quantity now available at the level (zero when the level is gone)
+    pub new_quantity: u64,
+    /// Whether the level is on the bid or ask side
+    pub side: Side,
+    /// Position of this update in the publisher's stream, starting at 1
+    pub sequence: u64,
+}
+
+/// A service that converts the changes applied to a `MarketDepthCache` into a
+/// sequenced stream of `DepthUpdate`s.
+///
+/// The publisher sits where the cache would otherwise be fed directly: every event it
+/// processes is applied to the shared cache, and the resulting absolute level quantity
+/// is returned as the next update in the stream. Sequence numbers are gap-free, so a
+/// consumer can detect missed updates and resynchronize from a cache snapshot. Any
+/// other `DepthAggregator` can stand in for the cache.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{DepthDeltaPublisher, MarketDepthCache, Order, OrderBook, Side};
+/// use rust_decimal::Decimal;
+/// use std::sync::Arc;
+///
+/// let mut order_book = OrderBook::new();
+/// let cache = Arc::new(MarketDepthCache::new());
+/// let publisher = DepthDeltaPublisher::new(Arc::clone(&cache));
+///
+/// let first = order_book.insert_order(Order::new(100.25, 10, Side::Bid)).event;
+/// let second = order_book.insert_order(Order::new(100.75, 5, Side::Bid)).event;
+/// publisher.process_order_event(first);
+/// let update = publisher.process_order_event(second);
+///
+/// assert_eq!(update.price_bucket, Decimal::new(100, 0));
+/// assert_eq!(update.new_quantity, 15);
+/// assert_eq!(update.sequence, 2);
+/// ```
+#[derive(Debug)]
+pub struct DepthDeltaPublisher<D = MarketDepthCache> {
+    /// The cache the events are applied to
+    market_depth_cache: Arc<D>,
+    /// Sequence number of the last published update
+    ///
+    /// Held while the cache is updated, so that sequence order always matches the
+    /// order in which levels changed, even with concurrent callers.
+    last_sequence: Mutex<u64>,
+}
+
+impl<D: DepthAggregator> DepthDeltaPublisher<D> {
+    /// Creates a publisher applying events to the given cache.
+    ///
+    /// ## Arguments
+    ///
+    /// * `market_depth_cache`: The cache that should reflect the published updates
+    pub fn new(market_depth_cache: Arc<D>) -> Self {
+        DepthDeltaPublisher {
+            market_depth_cache,
+            last_sequence: Mutex::new(0),
+        }
+    }
+
+    /// Applies an order event to the cache and returns the resulting level update.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The order event published by the order book
+    ///
+    /// ## Returns
+    ///
+    /// The `DepthUpdate` with the new absolute quantity of the affected level
+    pub fn process_order_event(&self, event: OrderEvent) -> DepthUpdate {
+        let mut last_sequence = self.last_sequence.lock();
+        let (price_bucket, new_quantity) = self.market_depth_cache.apply_order_event(&event);
+        *last_sequence += 1;
+
+        DepthUpdate {
+            price_bucket,
+            new_quantity,
+            side: event.side,
+            sequence: *last_sequence,
+        }
+    }
+
+    /// Returns the sequence number of the last published update, or 0 if none.
+    pub fn last_sequence(&self) -> u64 {
+        *self.last_sequence.lock()
+    }
+
+    /// Returns the cache the publisher applies events to.
+    pub fn market_depth_cache(&self) -> &Arc<D> {
+        &self.market_depth_cache
+    }
+}
@@ -0,0 +1,211 @@
+use crate::book_view::BookView;
+use crate::order_book::OrderBook;
+use crate::types::Side;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// When a `DepthHistory` takes a new frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SamplingSchedule {
+    /// Once at least this much time has passed since the last frame
+    Every(Duration),
+    /// Once at least this many events were published since the last frame
+    EveryNthEvent(u64),
+}
+
+/// The top levels of both sides at one point in time, recorded by a `DepthHistory`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthFrame {
+    /// When the frame was taken, in nanoseconds since the Unix epoch
+    pub timestamp_nanos: u64,
+    /// The sequence number of the last event reflected in the frame
+    pub sequence: u64,
+    /// The top bid levels and their quantities, best first
+    pub bids: Vec<(Decimal, u64)>,
+    /// The top ask levels and their quantities, best first
+    pub asks: Vec<(Decimal, u64)>,
+}
+
+/// Records the top levels of a book at a regular pace into a bounded history, so the
+/// short-horizon evolution of the book can be reconstructed afterwards, e.g. for
+/// transaction cost analysis.
+///
+/// The history keeps the latest `capacity` frames, dropping the oldest as new ones are
+/// taken, and answers queries by time: the frames taken within a range
+/// (`frames_between`) and the frame in effect at a given time (`frame_at`).
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{DepthHistory, Order, OrderBook, SamplingSchedule, Side};
+/// use rust_decimal::Decimal;
+/// use std::time::Duration;
+///
+/// let mut order_book = OrderBook::deterministic(7);
+/// let schedule = SamplingSchedule::Every(Duration::from_millis(100));
+/// let mut depth_history = DepthHistory::new(5, schedule, 1_000);
+///
+/// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+/// assert!(depth_history.observe(&order_book));
+///
+/// // Changes within the interval are not sampled
+/// order_book.set_time(50_000_000);
+/// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+/// assert!(!depth_history.observe(&order_book));
+///
+/// order_book.set_time(100_000_000);
+/// assert!(depth_history.observe(&order_book));
+/// let frame = depth_history.frame_at(150_000_000).unwrap();
+/// assert_eq!(frame.asks, vec![(Decimal::new(101, 0), 10)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DepthHistory {
+    /// The number of levels recorded per side
+    depth: usize,
+    /// When new frames are taken
+    schedule: SamplingSchedule,
+    /// The maximum number of frames kept
+    capacity: usize,
+    /// The frames kept, oldest first
+    frames: VecDeque<DepthFrame>,
+}
+
+impl DepthHistory {
+    /// Creates an empty history.
+    ///
+    /// ## Arguments
+    ///
+    /// * `depth`: The number of levels recorded per side
+    /// * `schedule`: When new frames are taken
+    /// * `capacity`: The maximum number of frames kept
+    pub fn new(depth: usize, schedule: SamplingSchedule, capacity: usize) -> Self {
+        DepthHistory {
+            depth,
+            schedule,
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Takes a frame of the book if the schedule calls for one.
+    ///
+    /// Call this after every change to the book or from a timer. The frame is timestamped
+    /// by the book's clock, so a deterministic book yields the same history on every
+    /// replay.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The order book to observe
+    ///
+    /// ## Returns
+    ///
+    /// `true` if a frame was taken
+    pub fn observe(&mut self, order_book: &OrderBook) -> bool {
+        self.record(
+            order_book,
+            order_book.now_nanos(),
+            order_book.last_event_sequence(),
+        )
+    }
+
+    /// Takes a frame of any view of a book, such as a `MarketDepthCache`, if the
+    /// schedule calls for one.
+    ///
+    /// The first observation is always recorded, and so is one whose sequence number is
+    /// lower than the last frame's, as after a session reset the sequence numbers of
+    /// books restart.
+    ///
+    /// ## Arguments
+    ///
+    /// * `view`: The view to record
+    /// * `timestamp_nanos`: When the observation is taken, in nanoseconds since the Unix
+    ///   epoch; timestamps are expected to be non-decreasing
+    /// * `sequence`: The sequence number of the last event reflected in the view
+    ///
+    /// ## Returns
+    ///
+    /// `true` if a frame was taken
+    pub fn record(&mut self, view: &impl BookView, timestamp_nanos: u64, sequence: u64) -> bool {
+        if let Some(latest) = self.frames.back() {
+            let due = match self.schedule {
+                SamplingSchedule::Every(interval) => {
+                    u128::from(timestamp_nanos.saturating_sub(latest.timestamp_nanos))
+                        >= interval.as_nanos()
+                }
+                SamplingSchedule::EveryNthEvent(events) => {
+                    sequence < latest.sequence || sequence - latest.sequence >= events
+                }
+            };
+            if !due {
+                return false;
+            }
+        }
+        if self.capacity == 0 {
+            return false;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(DepthFrame {
+            timestamp_nanos,
+            sequence,
+            bids: view.top_levels(Side::Bid, self.depth),
+            asks: view.top_levels(Side::Ask, self.depth),
+        });
+        true
+    }
+
+    /// Returns the frames kept, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &DepthFrame> {
+        self.frames.iter()
+    }
+
+    /// Returns the frames taken from `start_nanos` included to `end_nanos` excluded,
+    /// oldest first.
+    pub fn frames_between(
+        &self,
+        start_nanos: u64,
+        end_nanos: u64,
+    ) -> impl Iterator<Item = &DepthFrame> {
+        let start = self
+            .frames
+            .partition_point(|frame| frame.timestamp_nanos < start_nanos);
+        let end = self
+            .frames
+            .partition_point(|frame| frame.timestamp_nanos < end_nanos)
+            .max(start);
+        self.frames.range(start..end)
+    }
+
+    /// Returns the frame in effect at a given time, i.e. the latest one taken at or
+    /// before it, or `None` if the history starts after it.
+    pub fn frame_at(&self, timestamp_nanos: u64) -> Option<&DepthFrame> {
+        let index = self
+            .frames
+            .partition_point(|frame| frame.timestamp_nanos <= timestamp_nanos);
+        index
+            .checked_sub(1)
+            .and_then(|index| self.frames.get(index))
+    }
+
+    /// Returns the latest frame, if any.
+    pub fn latest(&self) -> Option<&DepthFrame> {
+        self.frames.back()
+    }
+
+    /// Returns the number of frames kept.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns whether no frame is kept.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Drops every frame.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
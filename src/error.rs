@@ -0,0 +1,191 @@
+use crate::types::{ClientOrderId, FloatRounding, OrderId, ParticipantId};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// Errors returned by `OrderBook` operations that target resting orders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBookError {
+    /// No resting order with this identifier exists in the book
+    OrderNotFound(OrderId),
+    /// The handle refers to an order that is no longer resting (cancelled or filled)
+    StaleHandle(OrderId),
+    /// The requested reduction is zero or exceeds the order's remaining quantity
+    InvalidReduction {
+        /// The order the reduction was requested for
+        order_id: OrderId,
+        /// The order's remaining quantity
+        remaining: u64,
+        /// The quantity the caller asked to remove
+        requested: u64,
+    },
+    /// The order's remaining quantity is not the one the caller expected, e.g. because
+    /// it was partially filled after the caller last read it
+    RemainingMismatch {
+        /// The order the operation was requested for
+        order_id: OrderId,
+        /// The remaining quantity the caller expected
+        expected: u64,
+        /// The order's remaining quantity
+        remaining: u64,
+    },
+    /// An order was already submitted under this client order identifier
+    DuplicateClientOrderId {
+        /// The identifier that was reused
+        client_order_id: ClientOrderId,
+        /// The order the identifier was first assigned to
+        order_id: OrderId,
+    },
+    /// A replacement order was requested with no quantity, which cancelling the order
+    /// expresses instead
+    ZeroReplacementQuantity(OrderId),
+    /// The quantity exceeds `i64::MAX`, the largest an `OrderEvent` can carry
+    QuantityTooLarge(u64),
+    /// The operation requires an empty book, but orders are resting in it
+    BookNotEmpty {
+        /// The number of resting orders
+        order_count: usize,
+    },
+    /// The price has more decimal places than the book's `PricePrecision` allows
+    PriceTooPrecise {
+        /// The rejected price
+        price: Decimal,
+        /// The maximum number of decimal places
+        max_scale: u32,
+    },
+    /// An `f64` price is not finite, is out of the range of `Decimal`, or needs more
+    /// precision than a strict `FloatPrecision` allows
+    UnrepresentablePrice {
+        /// The price as converted from `f64`, or `None` if it could not be
+        price: Option<Decimal>,
+        /// The precision the price had to be representable at
+        rounding: FloatRounding,
+    },
+    /// The tick size of a `FloatRounding::Tick` is zero or negative
+    InvalidTickSize(Decimal),
+    /// The order was inserted, but its event did not reach the depth in time
+    NotVisible {
+        /// The order that was inserted
+        order_id: OrderId,
+        /// The sequence number of its event
+        sequence: u64,
+    },
+    /// An iceberg order's display quantity allows no positive slice
+    InvalidDisplayQuantity {
+        /// The smallest slice allowed
+        min: u64,
+        /// The largest slice allowed
+        max: u64,
+    },
+    /// A market-by-order event arrived before some of the events preceding it
+    SequenceGap {
+        /// The sequence number of the next event the replica can apply
+        expected: u64,
+        /// The sequence number of the event that arrived
+        received: u64,
+    },
+    /// A market-by-order event did not apply to a replica, which no longer mirrors its
+    /// primary
+    ReplicaDiverged {
+        /// The sequence number of the event that did not apply
+        sequence: u64,
+    },
+    /// A command exceeded the order-entry rate limit of its participant or of the venue
+    Throttled {
+        /// The participant who sent the command
+        participant_id: ParticipantId,
+    },
+}
+
+impl fmt::Display for OrderBookError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderBookError::OrderNotFound(order_id) => {
+                write!(formatter, "order {order_id} is not resting in the book")
+            }
+            OrderBookError::StaleHandle(order_id) => {
+                write!(formatter, "handle for order {order_id} is no longer valid")
+            }
+            OrderBookError::InvalidReduction {
+                order_id,
+                remaining,
+                requested,
+            } => write!(
+                formatter,
+                "cannot reduce order {order_id} by {requested}, remaining quantity is {remaining}"
+            ),
+            OrderBookError::RemainingMismatch {
+                order_id,
+                expected,
+                remaining,
+            } => write!(
+                formatter,
+                "order {order_id} has {remaining} remaining, not the expected {expected}"
+            ),
+            OrderBookError::DuplicateClientOrderId {
+                client_order_id,
+                order_id,
+            } => write!(
+                formatter,
+                "client order id {client_order_id} was already used for order {order_id}"
+            ),
+            OrderBookError::BookNotEmpty { order_count } => {
+                write!(
+                    formatter,
+                    "the book is not empty, {order_count} orders are resting"
+                )
+            }
+            OrderBookError::PriceTooPrecise { price, max_scale } => write!(
+                formatter,
+                "price {price} has more than {max_scale} decimal places"
+            ),
+            OrderBookError::UnrepresentablePrice {
+                price: Some(price),
+                rounding,
+            } => write!(formatter, "price {price} is not representable at {rounding}"),
+            OrderBookError::UnrepresentablePrice {
+                price: None,
+                rounding: _,
+            } => write!(
+                formatter,
+                "price is not a finite number in the range of a decimal"
+            ),
+            OrderBookError::InvalidTickSize(tick_size) => {
+                write!(formatter, "tick size {tick_size} is not positive")
+            }
+            OrderBookError::NotVisible { order_id, sequence } => write!(
+                formatter,
+                "order {order_id} was inserted, but the depth did not reflect event {sequence} in time"
+            ),
+            OrderBookError::QuantityTooLarge(quantity) => write!(
+                formatter,
+                "quantity {quantity} exceeds the largest representable quantity {}",
+                i64::MAX
+            ),
+            OrderBookError::ZeroReplacementQuantity(order_id) => write!(
+                formatter,
+                "replacement for order {order_id} has no quantity"
+            ),
+            OrderBookError::InvalidDisplayQuantity { min, max } => write!(
+                formatter,
+                "display quantities from {min} to {max} allow no positive slice"
+            ),
+            OrderBookError::SequenceGap { expected, received } => write!(
+                formatter,
+                "expected market-by-order event {expected}, but received event {received}"
+            ),
+            OrderBookError::ReplicaDiverged { sequence } => write!(
+                formatter,
+                "market-by-order event {sequence} did not apply, the replica must catch up from a snapshot"
+            ),
+            OrderBookError::Throttled { participant_id } => write!(
+                formatter,
+                "participant {participant_id} exceeded the order-entry rate limit"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderBookError {}
+
+/// Convenience alias for results of `OrderBook` operations.
+pub type Result<T> = std::result::Result<T, OrderBookError>;
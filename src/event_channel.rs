@@ -0,0 +1,283 @@
+use crate::coalescing_buffer::CoalescingBuffer;
+use crate::types::OrderEvent;
+use parking_lot::{Condvar, Mutex};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// What a bounded `event_channel` does with an event sent while it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wait until the receiver makes room, applying backpressure to the producer
+    #[default]
+    Block,
+    /// Drop the event and deliver a `ChannelMessage::Gap` in its place, after which the
+    /// consumer must resynchronize (e.g. with `MarketDepthCache::rebuild_from`)
+    DropWithGap,
+    /// Merge the event into a `CoalescingBuffer` drained once the queue is empty, so
+    /// the channel holds at most its capacity plus one event per aggregated level
+    Coalesce,
+}
+
+/// A message received from an `event_channel`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelMessage {
+    /// An event, as sent or, under `OverflowPolicy::Coalesce`, merged
+    Event(OrderEvent),
+    /// Events were dropped at this point of the stream
+    Gap {
+        /// How many consecutive events were dropped
+        dropped_events: u64,
+    },
+}
+
+/// How often an `event_channel` overflowed and what became of the overflowing events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverflowCounts {
+    /// Number of sends that found the channel full
+    pub overflows: u64,
+    /// Number of events dropped under `OverflowPolicy::DropWithGap`
+    pub dropped_events: u64,
+    /// Number of events merged into another under `OverflowPolicy::Coalesce`
+    pub coalesced_events: u64,
+}
+
+/// The state shared by both ends of a channel.
+#[derive(Debug)]
+struct Shared {
+    /// The maximum number of queued messages before the channel overflows
+    capacity: usize,
+    /// What sends do while the channel is full
+    overflow_policy: OverflowPolicy,
+    /// The queue and its bookkeeping
+    state: Mutex<ChannelState>,
+    /// Signalled when a message is queued or the last sender is dropped
+    not_empty: Condvar,
+    /// Signalled when a message is received or the receiver is dropped
+    not_full: Condvar,
+    /// Overflowing events waiting for the queue to empty, under `Coalesce`
+    overflow: CoalescingBuffer,
+}
+
+#[derive(Debug)]
+struct ChannelState {
+    /// Messages waiting for the receiver, oldest first
+    queue: VecDeque<ChannelMessage>,
+    /// Events dropped since the last queued gap marker
+    pending_gap: u64,
+    /// Counts of overflows, dropped events, and merged events
+    overflow_counts: OverflowCounts,
+    /// Number of live senders
+    sender_count: usize,
+    /// Whether the receiver is still alive
+    receiver_alive: bool,
+}
+
+/// Creates a bounded channel for forwarding order events to a consumer thread.
+///
+/// Unlike an unbounded channel, the queue cannot grow without limit while the consumer
+/// falls behind during bursts; `overflow_policy` decides what happens instead, and the
+/// receiver's `overflow_counts` report how often it did, for monitoring.
+///
+/// ## Arguments
+///
+/// * `capacity`: The number of messages queued before the channel overflows
+/// * `overflow_policy`: What sends do while the channel is full
+///
+/// ## Returns
+///
+/// The sending and the receiving end of the channel
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{event_channel, ChannelMessage, Order, OrderBook, OverflowPolicy, Side};
+///
+/// let mut order_book = OrderBook::new();
+/// let (event_sender, event_receiver) = event_channel(2, OverflowPolicy::DropWithGap);
+///
+/// for price in [100.00, 101.00, 102.00, 103.00] {
+///     event_sender.send(order_book.insert_order(Order::new(price, 10, Side::Ask)).event);
+/// }
+///
+/// assert!(matches!(event_receiver.try_recv(), Some(ChannelMessage::Event(_))));
+/// assert!(matches!(event_receiver.try_recv(), Some(ChannelMessage::Event(_))));
+/// assert_eq!(
+///     event_receiver.try_recv(),
+///     Some(ChannelMessage::Gap { dropped_events: 2 })
+/// );
+/// assert_eq!(event_receiver.overflow_counts().overflows, 2);
+/// ```
+pub fn event_channel(
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+) -> (EventSender, EventReceiver) {
+    let shared = Arc::new(Shared {
+        capacity: capacity.max(1),
+        overflow_policy,
+        state: Mutex::new(ChannelState {
+            queue: VecDeque::with_capacity(capacity),
+            pending_gap: 0,
+            overflow_counts: OverflowCounts::default(),
+            sender_count: 1,
+            receiver_alive: true,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        overflow: CoalescingBuffer::new(),
+    });
+
+    (
+        EventSender {
+            shared: Arc::clone(&shared),
+        },
+        EventReceiver { shared },
+    )
+}
+
+/// The sending end of an `event_channel`, which can be cloned for several producers.
+#[derive(Debug)]
+pub struct EventSender {
+    shared: Arc<Shared>,
+}
+
+impl EventSender {
+    /// Sends an event, applying the channel's overflow policy if it is full.
+    ///
+    /// Events sent after the receiver was dropped are discarded.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The order event to forward
+    pub fn send(&self, event: OrderEvent) {
+        let shared = &*self.shared;
+        let mut state = shared.state.lock();
+        if !state.receiver_alive {
+            return;
+        }
+
+        let full = state.queue.len() >= shared.capacity;
+        if full {
+            state.overflow_counts.overflows += 1;
+        }
+        match shared.overflow_policy {
+            OverflowPolicy::Block if full => {
+                while state.queue.len() >= shared.capacity && state.receiver_alive {
+                    shared.not_full.wait(&mut state);
+                }
+                if !state.receiver_alive {
+                    return;
+                }
+            }
+            OverflowPolicy::DropWithGap if full => {
+                state.pending_gap += 1;
+                state.overflow_counts.dropped_events += 1;
+                return;
+            }
+            // Once events are coalescing, later ones must follow them rather than
+            // overtake them through the queue, as depth consumers clamp at zero
+            OverflowPolicy::Coalesce if full || !shared.overflow.is_empty() => {
+                let merged_before = shared.overflow.coalesced_count();
+                shared.overflow.push(event);
+                state.overflow_counts.coalesced_events +=
+                    shared.overflow.coalesced_count() - merged_before;
+                shared.not_empty.notify_one();
+                return;
+            }
+            _ => {}
+        }
+
+        // The gap marker goes where the dropped events would have been
+        if state.pending_gap > 0 {
+            let dropped_events = std::mem::take(&mut state.pending_gap);
+            state
+                .queue
+                .push_back(ChannelMessage::Gap { dropped_events });
+        }
+        state.queue.push_back(ChannelMessage::Event(event));
+        shared.not_empty.notify_one();
+    }
+}
+
+impl Clone for EventSender {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().sender_count += 1;
+        EventSender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for EventSender {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.sender_count -= 1;
+        if state.sender_count == 0 {
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+/// The receiving end of an `event_channel`.
+#[derive(Debug)]
+pub struct EventReceiver {
+    shared: Arc<Shared>,
+}
+
+impl EventReceiver {
+    /// Waits for the next message.
+    ///
+    /// ## Returns
+    ///
+    /// The next message, or `None` once every sender is dropped and nothing is left
+    pub fn recv(&self) -> Option<ChannelMessage> {
+        let mut state = self.shared.state.lock();
+        loop {
+            if let Some(message) = self.pop(&mut state) {
+                return Some(message);
+            }
+            if state.sender_count == 0 {
+                return None;
+            }
+            self.shared.not_empty.wait(&mut state);
+        }
+    }
+
+    /// Returns the next message if one is ready, without waiting.
+    pub fn try_recv(&self) -> Option<ChannelMessage> {
+        let mut state = self.shared.state.lock();
+        self.pop(&mut state)
+    }
+
+    /// Returns how often the channel overflowed so far.
+    pub fn overflow_counts(&self) -> OverflowCounts {
+        self.shared.state.lock().overflow_counts
+    }
+
+    /// Takes the oldest queued message, refilling the queue with coalesced events or
+    /// a trailing gap marker once it runs dry.
+    fn pop(&self, state: &mut ChannelState) -> Option<ChannelMessage> {
+        if state.queue.is_empty() {
+            let coalesced = self.shared.overflow.drain();
+            state
+                .queue
+                .extend(coalesced.into_iter().map(ChannelMessage::Event));
+            if state.pending_gap > 0 {
+                let dropped_events = std::mem::take(&mut state.pending_gap);
+                state
+                    .queue
+                    .push_back(ChannelMessage::Gap { dropped_events });
+            }
+        }
+
+        let message = state.queue.pop_front()?;
+        self.shared.not_full.notify_one();
+        Some(message)
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        self.shared.state.lock().receiver_alive = false;
+        self.shared.not_full.notify_all();
+    }
+}
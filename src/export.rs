@@ -0,0 +1,206 @@
+//! Export of depth snapshots and trades as Arrow record batches and Parquet files.
+//!
+//! Every batch shares one schema, so depth and trade batches can be written to the
+//! same file or concatenated by the consumer:
+//!
+//! | Column  | Type                        | Content                                      |
+//! |---------|-----------------------------|----------------------------------------------|
+//! | `ts`    | `Timestamp(ns, UTC)`, null  | Snapshot time; null for trades, whose time the book does not record |
+//! | `side`  | `Utf8`                      | `"bid"` or `"ask"`; the aggressor's side for trades |
+//! | `price` | `Decimal128(38, 18)`        | Aggregated level or execution price          |
+//! | `qty`   | `UInt64`                    | Level or executed quantity                   |
+//! | `seq`   | `UInt64`                    | Snapshot sequence, or position in the trade log |
+//!
+//! Prices keep their exact decimal value, rounded to 18 decimal places if they have
+//! more, so research pipelines (e.g. `pyarrow` or `polars` in Python) read them without
+//! going through binary floating point.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::export::{depth_batch, trades_batch};
+//! use order_book::{MarketDepthCache, Order, OrderBook, Side};
+//!
+//! let mut order_book = OrderBook::new();
+//! let cache = MarketDepthCache::new();
+//! cache.process_order_event(order_book.insert_order(Order::new(100.50, 10, Side::Ask)).event);
+//! for event in order_book.match_order(Order::new(100.50, 4, Side::Bid)).events {
+//!     cache.process_order_event(event);
+//! }
+//!
+//! let depth = depth_batch(&cache.snapshot(), 1_700_000_000_000_000_000).unwrap();
+//! assert_eq!(depth.num_rows(), 1);
+//! let trades = trades_batch(order_book.trades(), 1).unwrap();
+//! assert_eq!(trades.num_rows(), 1);
+//! assert_eq!(depth.schema(), trades.schema());
+//! ```
+
+use crate::types::{DepthSnapshot, Side, Trade};
+use arrow_array::{
+    ArrayRef, Decimal128Array, RecordBatch, StringArray, TimestampNanosecondArray, UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use rust_decimal::Decimal;
+use std::sync::{Arc, OnceLock};
+
+/// The number of decimal places of the `price` column.
+pub const PRICE_SCALE: i8 = 18;
+
+/// The precision of the `price` column, the largest `Decimal128` supports.
+const PRICE_PRECISION: u8 = 38;
+
+/// Returns the schema shared by every exported batch.
+pub fn schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| {
+            Arc::new(Schema::new(vec![
+                Field::new(
+                    "ts",
+                    DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+                    true,
+                ),
+                Field::new("side", DataType::Utf8, false),
+                Field::new(
+                    "price",
+                    DataType::Decimal128(PRICE_PRECISION, PRICE_SCALE),
+                    false,
+                ),
+                Field::new("qty", DataType::UInt64, false),
+                Field::new("seq", DataType::UInt64, false),
+            ]))
+        })
+        .clone()
+}
+
+/// Converts a depth snapshot into a batch with one row per aggregated level.
+///
+/// Bid levels come first, then ask levels, each in ascending price order.
+///
+/// ## Arguments
+///
+/// * `snapshot`: The snapshot to export, e.g. from `MarketDepthCache::snapshot`
+/// * `timestamp_nanos`: When the snapshot was taken, in nanoseconds since the Unix epoch
+///
+/// ## Returns
+///
+/// The batch, or an `ArrowError` if a price does not fit the `price` column
+pub fn depth_batch(
+    snapshot: &DepthSnapshot,
+    timestamp_nanos: u64,
+) -> Result<RecordBatch, ArrowError> {
+    let levels: Vec<(Side, Decimal, u64)> = snapshot
+        .bids
+        .iter()
+        .map(|(price, quantity)| (Side::Bid, *price, *quantity))
+        .chain(
+            snapshot
+                .asks
+                .iter()
+                .map(|(price, quantity)| (Side::Ask, *price, *quantity)),
+        )
+        .collect();
+    let timestamp = i64::try_from(timestamp_nanos).ok();
+
+    build_batch(
+        levels.iter().map(|_| timestamp).collect(),
+        &levels,
+        levels.iter().map(|_| snapshot.sequence).collect(),
+    )
+}
+
+/// Converts trades into a batch with one row per trade.
+///
+/// The book does not record when trades happened, so `ts` is null; `seq` numbers the
+/// trades consecutively, which lets a slice of `OrderBook::trades` be exported
+/// incrementally with the position of its first trade.
+///
+/// ## Arguments
+///
+/// * `trades`: The trades to export, oldest first
+/// * `first_sequence`: The `seq` of the first trade, e.g. its 1-based position in the
+///   book's trade log
+///
+/// ## Returns
+///
+/// The batch, or an `ArrowError` if a price does not fit the `price` column
+pub fn trades_batch(trades: &[Trade], first_sequence: u64) -> Result<RecordBatch, ArrowError> {
+    let rows: Vec<(Side, Decimal, u64)> = trades
+        .iter()
+        .map(|trade| (trade.aggressor_side, trade.price, trade.quantity))
+        .collect();
+
+    build_batch(
+        vec![None; rows.len()],
+        &rows,
+        (first_sequence..first_sequence + rows.len() as u64).collect(),
+    )
+}
+
+/// Writes batches to a Parquet file. Only available with the `parquet` feature.
+///
+/// ## Arguments
+///
+/// * `writer`: Where the file is written
+/// * `batches`: The batches to write, as built by `depth_batch` and `trades_batch`
+///
+/// ## Returns
+///
+/// The writer's error, if any
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(
+    writer: W,
+    batches: &[RecordBatch],
+) -> Result<(), parquet::errors::ParquetError> {
+    let mut parquet_writer = parquet::arrow::ArrowWriter::try_new(writer, schema(), None)?;
+    for batch in batches {
+        parquet_writer.write(batch)?;
+    }
+    parquet_writer.close()?;
+    Ok(())
+}
+
+/// Assembles a batch from its columns.
+fn build_batch(
+    timestamps: Vec<Option<i64>>,
+    rows: &[(Side, Decimal, u64)],
+    sequences: Vec<u64>,
+) -> Result<RecordBatch, ArrowError> {
+    let sides: StringArray = rows
+        .iter()
+        .map(|(side, _, _)| {
+            Some(match side {
+                Side::Bid => "bid",
+                Side::Ask => "ask",
+            })
+        })
+        .collect();
+    let prices = rows
+        .iter()
+        .map(|(_, price, _)| price_value(*price))
+        .collect::<Result<Vec<i128>, ArrowError>>()?;
+    let quantities: UInt64Array = rows.iter().map(|(_, _, quantity)| *quantity).collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(TimestampNanosecondArray::from(timestamps).with_timezone("UTC")),
+        Arc::new(sides),
+        Arc::new(
+            Decimal128Array::from(prices).with_precision_and_scale(PRICE_PRECISION, PRICE_SCALE)?,
+        ),
+        Arc::new(quantities),
+        Arc::new(UInt64Array::from(sequences)),
+    ];
+    RecordBatch::try_new(schema(), columns)
+}
+
+/// Returns the unscaled `Decimal128` value of a price at `PRICE_SCALE`.
+fn price_value(price: Decimal) -> Result<i128, ArrowError> {
+    let price = price.round_dp(PRICE_SCALE as u32);
+    10i128
+        .checked_pow(PRICE_SCALE as u32 - price.scale())
+        .and_then(|factor| price.mantissa().checked_mul(factor))
+        .filter(|value| value.unsigned_abs() < 10u128.pow(PRICE_PRECISION as u32))
+        .ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!("price {price} does not fit Decimal128"))
+        })
+}
@@ -0,0 +1,234 @@
+use crate::types::{BboChanged, OrderEvent, OrderRejected, Trade};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+/// A kind of update an `EventFanOut` delivers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// Level 2 changes, i.e. the book's `OrderEvent`s
+    Level,
+    /// Trades
+    Trade,
+    /// Changes of the best bid or ask
+    Bbo,
+    /// Incoming orders the book refused, e.g. from `OrderBook::take_rejection_events`
+    Rejection,
+}
+
+/// Identifier of a subscription to an `EventFanOut`, or of a watch registered with
+/// `MarketDepthCache::watch_price`, used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(pub u64);
+
+/// A subscriber of an `EventFanOut`.
+///
+/// Every method does nothing by default, so observers only implement those of the
+/// kinds they subscribe to. Methods are called on the publishing thread, and must not
+/// subscribe to or unsubscribe from the fan-out delivering to them.
+pub trait BookObserver: Send + Sync {
+    /// Receives a level 2 change.
+    fn on_level(&self, _event: &OrderEvent) {}
+
+    /// Receives a trade.
+    fn on_trade(&self, _trade: &Trade) {}
+
+    /// Receives a change of the best bid or ask.
+    fn on_bbo(&self, _change: &BboChanged) {}
+
+    /// Receives the rejection of an incoming order.
+    fn on_rejection(&self, _rejection: &OrderRejected) {}
+}
+
+/// The subscribers of one kind of update.
+type Subscribers = RwLock<Vec<(SubscriptionId, Weak<dyn BookObserver>)>>;
+
+/// A fan-out delivering each kind of update only to the observers subscribed to it.
+///
+/// Observers register for the kinds they need, e.g. a trade tape for trades alone, so
+/// publishing a level change never reaches it. The fan-out holds observers weakly: an
+/// observer dropped by its owner stops receiving updates and is removed at the next
+/// publication of a kind it was subscribed to, without having to unsubscribe.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{BookObserver, EventFanOut, EventKind, Order, OrderBook, Side, Trade};
+/// use parking_lot::Mutex;
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct Tape(Mutex<Vec<u64>>);
+///
+/// impl BookObserver for Tape {
+///     fn on_trade(&self, trade: &Trade) {
+///         self.0.lock().push(trade.quantity);
+///     }
+/// }
+///
+/// let fan_out = EventFanOut::new();
+/// let tape = Arc::new(Tape::default());
+/// fan_out.subscribe(&tape, &[EventKind::Trade]);
+///
+/// let mut order_book = OrderBook::new();
+/// fan_out.publish_level(&order_book.insert_order(Order::new(100.00, 10, Side::Ask)).event);
+/// order_book.match_order(Order::new(100.00, 4, Side::Bid));
+/// fan_out.publish_trade(order_book.trades().last().unwrap());
+/// assert_eq!(*tape.0.lock(), vec![4]);
+///
+/// // Dropping the observer is enough to stop the deliveries
+/// drop(tape);
+/// assert_eq!(fan_out.subscriber_count(EventKind::Trade), 1);
+/// fan_out.publish_trade(order_book.trades().last().unwrap());
+/// assert_eq!(fan_out.subscriber_count(EventKind::Trade), 0);
+/// ```
+#[derive(Default)]
+pub struct EventFanOut {
+    /// The subscribers of level 2 changes
+    level_subscribers: Subscribers,
+    /// The subscribers of trades
+    trade_subscribers: Subscribers,
+    /// The subscribers of best bid and ask changes
+    bbo_subscribers: Subscribers,
+    /// The subscribers of order rejections
+    rejection_subscribers: Subscribers,
+    /// The identifier of the next subscription
+    next_subscription_id: AtomicU64,
+}
+
+impl EventFanOut {
+    /// Creates a fan-out without subscribers.
+    pub fn new() -> Self {
+        EventFanOut::default()
+    }
+
+    /// Subscribes an observer to some kinds of updates.
+    ///
+    /// The fan-out only keeps a weak reference to the observer, so it is the caller
+    /// that keeps it alive.
+    ///
+    /// ## Arguments
+    ///
+    /// * `observer`: The observer to deliver to
+    /// * `kinds`: The kinds of updates it receives
+    ///
+    /// ## Returns
+    ///
+    /// The subscription's identifier, which `unsubscribe` takes
+    pub fn subscribe<O: BookObserver + 'static>(
+        &self,
+        observer: &Arc<O>,
+        kinds: &[EventKind],
+    ) -> SubscriptionId {
+        let subscription_id =
+            SubscriptionId(self.next_subscription_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let observer: Arc<dyn BookObserver> = observer.clone();
+        for (index, kind) in kinds.iter().enumerate() {
+            if kinds[..index].contains(kind) {
+                continue;
+            }
+            self.subscribers(*kind)
+                .write()
+                .push((subscription_id, Arc::downgrade(&observer)));
+        }
+        subscription_id
+    }
+
+    /// Ends a subscription, for every kind it covered.
+    ///
+    /// ## Returns
+    ///
+    /// Whether the subscription was still active
+    pub fn unsubscribe(&self, subscription_id: SubscriptionId) -> bool {
+        let mut found = false;
+        for kind in [
+            EventKind::Level,
+            EventKind::Trade,
+            EventKind::Bbo,
+            EventKind::Rejection,
+        ] {
+            let mut subscribers = self.subscribers(kind).write();
+            let count = subscribers.len();
+            subscribers.retain(|(id, _)| *id != subscription_id);
+            found |= subscribers.len() != count;
+        }
+        found
+    }
+
+    /// Returns the number of subscriptions to a kind of updates, including those whose
+    /// observer was dropped but not yet removed.
+    pub fn subscriber_count(&self, kind: EventKind) -> usize {
+        self.subscribers(kind).read().len()
+    }
+
+    /// Delivers a level 2 change to its subscribers.
+    pub fn publish_level(&self, event: &OrderEvent) {
+        self.publish(EventKind::Level, |observer| observer.on_level(event));
+    }
+
+    /// Delivers a trade to its subscribers.
+    pub fn publish_trade(&self, trade: &Trade) {
+        self.publish(EventKind::Trade, |observer| observer.on_trade(trade));
+    }
+
+    /// Delivers a change of the best bid or ask to its subscribers.
+    pub fn publish_bbo(&self, change: &BboChanged) {
+        self.publish(EventKind::Bbo, |observer| observer.on_bbo(change));
+    }
+
+    /// Delivers the rejection of an incoming order to its subscribers.
+    pub fn publish_rejection(&self, rejection: &OrderRejected) {
+        self.publish(EventKind::Rejection, |observer| {
+            observer.on_rejection(rejection)
+        });
+    }
+
+    /// Returns the subscribers of a kind of updates.
+    fn subscribers(&self, kind: EventKind) -> &Subscribers {
+        match kind {
+            EventKind::Level => &self.level_subscribers,
+            EventKind::Trade => &self.trade_subscribers,
+            EventKind::Bbo => &self.bbo_subscribers,
+            EventKind::Rejection => &self.rejection_subscribers,
+        }
+    }
+
+    /// Delivers an update to the live subscribers of its kind, then removes the dropped
+    /// ones.
+    fn publish(&self, kind: EventKind, deliver: impl Fn(&dyn BookObserver)) {
+        let subscribers = self.subscribers(kind);
+        let mut any_dropped = false;
+        for (_, observer) in subscribers.read().iter() {
+            match observer.upgrade() {
+                Some(observer) => deliver(observer.as_ref()),
+                None => any_dropped = true,
+            }
+        }
+        if any_dropped {
+            subscribers
+                .write()
+                .retain(|(_, observer)| observer.strong_count() > 0);
+        }
+    }
+}
+
+impl std::fmt::Debug for EventFanOut {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("EventFanOut")
+            .field(
+                "level_subscribers",
+                &self.subscriber_count(EventKind::Level),
+            )
+            .field(
+                "trade_subscribers",
+                &self.subscriber_count(EventKind::Trade),
+            )
+            .field("bbo_subscribers", &self.subscriber_count(EventKind::Bbo))
+            .field(
+                "rejection_subscribers",
+                &self.subscriber_count(EventKind::Rejection),
+            )
+            .finish()
+    }
+}
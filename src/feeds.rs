@@ -0,0 +1,236 @@
+//! Normalizers turning venues' L2 WebSocket payloads into `OrderEvent` streams.
+//!
+//! Venues publish the absolute quantity at a price, while the crate's event model
+//! carries changes of quantity, so each normalizer keeps the venue's levels and emits
+//! the difference every payload makes, numbering its events from 1 like a book does.
+//! The events can be applied to a `MarketDepthCache`, and the events of several
+//! normalizers applied to one cache build a book consolidated across venues.
+//!
+//! - `CoinbaseLevel2` reads the Coinbase Advanced Trade `level2` channel and checks
+//!   that no message was dropped from the connection's `sequence_num` numbering
+//! - `KrakenBook` reads the Kraken v2 `book` channel and checks every payload's CRC32
+//!   checksum of the top ten levels
+//!
+//! Venue quantities are decimal, so they are scaled by `10^quantity_decimals` into the
+//! integer quantities of events, e.g. with `quantity_decimals` of 8 a quantity of
+//! `0.5` BTC becomes 50 000 000 satoshis.
+//!
+//! When a check fails the normalizer stops applying updates and reports
+//! `FeedError::AwaitingSnapshot` until the venue sends a new snapshot, usually after
+//! resubscribing; the events emitted for that snapshot bring consumers from the levels
+//! they were last given to the snapshot's.
+
+mod coinbase;
+mod json;
+mod kraken;
+
+pub use coinbase::CoinbaseLevel2;
+pub use kraken::KrakenBook;
+
+use crate::types::{AggregatedDepthMap, DepthSnapshot, OrderEvent, Side};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Why a payload could not be normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedError {
+    /// The payload is not valid JSON or lacks a field the venue always sends
+    Malformed(String),
+    /// A quantity is negative or does not fit in `quantity_decimals` decimal places
+    InvalidQuantity(Decimal),
+    /// Messages were dropped between the last payload and this one
+    SequenceGap {
+        /// The sequence number the payload should have had
+        expected: u64,
+        /// The sequence number the payload had
+        received: u64,
+    },
+    /// The levels after applying the payload do not match the venue's checksum
+    ChecksumMismatch {
+        /// The venue's checksum
+        expected: u32,
+        /// The checksum of the normalizer's levels
+        computed: u32,
+    },
+    /// An update arrived before the first snapshot or after a failed check
+    AwaitingSnapshot,
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::Malformed(reason) => write!(formatter, "malformed payload: {reason}"),
+            FeedError::InvalidQuantity(quantity) => {
+                write!(formatter, "invalid quantity {quantity}")
+            }
+            FeedError::SequenceGap { expected, received } => write!(
+                formatter,
+                "sequence gap, expected {expected} and received {received}"
+            ),
+            FeedError::ChecksumMismatch { expected, computed } => write!(
+                formatter,
+                "checksum mismatch, expected {expected} and computed {computed}"
+            ),
+            FeedError::AwaitingSnapshot => write!(formatter, "awaiting a snapshot"),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+/// Builds a `FeedError::Malformed`.
+fn malformed(reason: &str) -> FeedError {
+    FeedError::Malformed(reason.to_string())
+}
+
+/// The levels of one instrument as last reported by a venue.
+#[derive(Debug, Clone)]
+struct VenueLevels {
+    /// The number of decimal places of venue quantities kept in event quantities
+    quantity_decimals: u32,
+    /// Bid prices and their venue quantities
+    bids: BTreeMap<Decimal, Decimal>,
+    /// Ask prices and their venue quantities
+    asks: BTreeMap<Decimal, Decimal>,
+    /// The sequence number of the last emitted event
+    last_sequence: u64,
+    /// Whether updates can be applied, i.e. a snapshot arrived and no check failed since
+    synchronized: bool,
+}
+
+impl VenueLevels {
+    /// Creates empty levels, awaiting a snapshot.
+    fn new(quantity_decimals: u32) -> Self {
+        VenueLevels {
+            quantity_decimals,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_sequence: 0,
+            synchronized: false,
+        }
+    }
+
+    /// Returns the levels of a side.
+    fn side(&self, side: Side) -> &BTreeMap<Decimal, Decimal> {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Converts a venue quantity into an event quantity.
+    fn units(&self, quantity: Decimal) -> Result<u64, FeedError> {
+        10u64
+            .checked_pow(self.quantity_decimals)
+            .and_then(|factor| Decimal::from(factor).checked_mul(quantity))
+            .filter(|units| units.fract().is_zero())
+            .and_then(|units| units.to_u64())
+            .ok_or(FeedError::InvalidQuantity(quantity))
+    }
+
+    /// Sets the venue quantity at a price, a zero quantity removing the level, and
+    /// emits the change if the event quantity changes.
+    fn set(
+        &mut self,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        timestamp_nanos: u64,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<(), FeedError> {
+        let new_units = self.units(quantity)?;
+        let levels = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        let old_quantity = if quantity.is_zero() {
+            levels.remove(&price)
+        } else {
+            levels.insert(price, quantity)
+        };
+        let old_units = old_quantity.map_or(Ok(0), |quantity| self.units(quantity))?;
+
+        if new_units != old_units {
+            self.last_sequence += 1;
+            events.push(OrderEvent {
+                price,
+                quantity_delta: (new_units as i128 - old_units as i128) as i64,
+                order_count_delta: 0,
+                side,
+                timestamp_nanos,
+                sequence: self.last_sequence,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replaces every level with a snapshot's and resynchronizes.
+    ///
+    /// The snapshot's quantities are validated before any level changes.
+    fn replace(
+        &mut self,
+        bids: Vec<(Decimal, Decimal)>,
+        asks: Vec<(Decimal, Decimal)>,
+        timestamp_nanos: u64,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<(), FeedError> {
+        for (_, quantity) in bids.iter().chain(&asks) {
+            self.units(*quantity)?;
+        }
+        for (side, levels) in [(Side::Bid, bids), (Side::Ask, asks)] {
+            let levels: BTreeMap<Decimal, Decimal> = levels.into_iter().collect();
+            let stale: Vec<Decimal> = self
+                .side(side)
+                .keys()
+                .filter(|price| !levels.contains_key(price))
+                .copied()
+                .collect();
+            for price in stale {
+                self.set(side, price, Decimal::ZERO, timestamp_nanos, events)?;
+            }
+            for (price, quantity) in levels {
+                self.set(side, price, quantity, timestamp_nanos, events)?;
+            }
+        }
+        self.synchronized = true;
+        Ok(())
+    }
+
+    /// Applies a payload's level changes, given as side, price, and new venue quantity.
+    ///
+    /// The quantities are validated before any level changes.
+    fn update(
+        &mut self,
+        changes: &[(Side, Decimal, Decimal)],
+        timestamp_nanos: u64,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<(), FeedError> {
+        if !self.synchronized {
+            return Err(FeedError::AwaitingSnapshot);
+        }
+        for (_, _, quantity) in changes {
+            self.units(*quantity)?;
+        }
+        for (side, price, quantity) in changes {
+            self.set(*side, *price, *quantity, timestamp_nanos, events)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the levels as depth at exact prices, with event quantities.
+    fn snapshot(&self) -> DepthSnapshot {
+        let convert = |levels: &BTreeMap<Decimal, Decimal>| -> AggregatedDepthMap {
+            levels
+                .iter()
+                .map(|(price, quantity)| (*price, self.units(*quantity).unwrap_or(0)))
+                .collect()
+        };
+        DepthSnapshot {
+            sequence: self.last_sequence,
+            bids: convert(&self.bids),
+            asks: convert(&self.asks),
+        }
+    }
+}
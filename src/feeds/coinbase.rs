@@ -0,0 +1,178 @@
+//! The Coinbase Advanced Trade `level2` channel.
+
+use super::json::{parse_timestamp, Json};
+use super::{malformed, FeedError, VenueLevels};
+use crate::types::{DepthSnapshot, OrderEvent, Side};
+use rust_decimal::Decimal;
+
+/// Normalizes the Coinbase `level2` channel of one product into `OrderEvent`s.
+///
+/// Every message of a Coinbase connection carries a `sequence_num` one greater than
+/// the previous message's, whatever its channel, so every message received on the
+/// connection should be passed to `normalize`; messages of other channels and
+/// products only advance the expected sequence number. A message that skips numbers
+/// is rejected with `FeedError::SequenceGap`, and updates are then rejected until the
+/// next snapshot, which Coinbase sends on resubscription.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::feeds::CoinbaseLevel2;
+/// use order_book::{MarketDepthCache, Side};
+/// use rust_decimal::Decimal;
+///
+/// let mut feed = CoinbaseLevel2::new("BTC-USD", 8);
+/// let mut events = Vec::new();
+/// feed.normalize(
+///     r#"{"channel":"l2_data","timestamp":"2023-02-09T20:32:50.714964855Z","sequence_num":0,
+///         "events":[{"type":"snapshot","product_id":"BTC-USD","updates":[
+///             {"side":"bid","price_level":"21921.73","new_quantity":"0.5"},
+///             {"side":"offer","price_level":"21921.74","new_quantity":"1.25"}]}]}"#,
+///     &mut events,
+/// )
+/// .unwrap();
+///
+/// let cache = MarketDepthCache::new();
+/// for event in events {
+///     cache.process_order_event(event);
+/// }
+/// assert_eq!(cache.get_quantity_at_level(Decimal::new(21921, 0), Side::Bid), 50_000_000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CoinbaseLevel2 {
+    /// The product whose levels are kept, e.g. `BTC-USD`
+    product_id: String,
+    /// The product's levels
+    levels: VenueLevels,
+    /// The `sequence_num` the next message should have, once a message was seen
+    next_sequence_num: Option<u64>,
+}
+
+impl CoinbaseLevel2 {
+    /// Creates a normalizer awaiting the product's snapshot.
+    ///
+    /// ## Arguments
+    ///
+    /// * `product_id`: The product to normalize, e.g. `BTC-USD`
+    /// * `quantity_decimals`: The decimal places of Coinbase quantities kept in event
+    ///   quantities, which are scaled by `10^quantity_decimals`
+    pub fn new(product_id: impl Into<String>, quantity_decimals: u32) -> Self {
+        CoinbaseLevel2 {
+            product_id: product_id.into(),
+            levels: VenueLevels::new(quantity_decimals),
+            next_sequence_num: None,
+        }
+    }
+
+    /// Applies a message received on the connection and appends the resulting events.
+    ///
+    /// Events are stamped with the message's `timestamp`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `payload`: The text of the WebSocket message
+    /// * `events`: Where the events are appended
+    ///
+    /// ## Returns
+    ///
+    /// An error if the message is malformed, skips sequence numbers, or is an update
+    /// while awaiting a snapshot; a rejected snapshot or update changes no level
+    pub fn normalize(
+        &mut self,
+        payload: &str,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<(), FeedError> {
+        let message = Json::parse(payload).map_err(FeedError::Malformed)?;
+
+        if let Some(sequence_num) = message.get("sequence_num").and_then(Json::as_u64) {
+            let next_sequence_num = sequence_num
+                .checked_add(1)
+                .ok_or_else(|| malformed("sequence_num overflows"))?;
+            let expected = self.next_sequence_num.replace(next_sequence_num);
+            if let Some(expected) = expected.filter(|expected| *expected != sequence_num) {
+                self.levels.synchronized = false;
+                return Err(FeedError::SequenceGap {
+                    expected,
+                    received: sequence_num,
+                });
+            }
+        }
+        if message.get("channel").and_then(Json::as_str) != Some("l2_data") {
+            return Ok(());
+        }
+
+        let timestamp_nanos = message
+            .get("timestamp")
+            .and_then(Json::as_str)
+            .and_then(parse_timestamp)
+            .unwrap_or(0);
+        let channel_events = message
+            .get("events")
+            .and_then(Json::as_array)
+            .ok_or_else(|| malformed("missing events"))?;
+        for channel_event in channel_events {
+            if channel_event.get("product_id").and_then(Json::as_str)
+                != Some(self.product_id.as_str())
+            {
+                continue;
+            }
+            let changes = parse_updates(channel_event)?;
+            match channel_event.get("type").and_then(Json::as_str) {
+                Some("snapshot") => {
+                    let (bids, asks) = changes
+                        .into_iter()
+                        .partition::<Vec<_>, _>(|(side, _, _)| *side == Side::Bid);
+                    let levels = |changes: Vec<(Side, Decimal, Decimal)>| {
+                        changes
+                            .into_iter()
+                            .map(|(_, price, quantity)| (price, quantity))
+                            .collect()
+                    };
+                    self.levels
+                        .replace(levels(bids), levels(asks), timestamp_nanos, events)?;
+                }
+                Some("update") => self.levels.update(&changes, timestamp_nanos, events)?,
+                _ => return Err(malformed("unknown event type")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether updates are being applied, i.e. a snapshot was received and no
+    /// message was missed since.
+    pub fn is_synchronized(&self) -> bool {
+        self.levels.synchronized
+    }
+
+    /// Returns the product's levels at exact prices, with event quantities.
+    pub fn snapshot(&self) -> DepthSnapshot {
+        self.levels.snapshot()
+    }
+}
+
+/// Reads the side, price, and new quantity of every update of a channel event.
+fn parse_updates(channel_event: &Json) -> Result<Vec<(Side, Decimal, Decimal)>, FeedError> {
+    let updates = channel_event
+        .get("updates")
+        .and_then(Json::as_array)
+        .ok_or_else(|| malformed("missing updates"))?;
+    updates
+        .iter()
+        .map(|update| {
+            let side = match update.get("side").and_then(Json::as_str) {
+                Some("bid") => Side::Bid,
+                Some("offer") | Some("ask") => Side::Ask,
+                _ => return Err(malformed("invalid side")),
+            };
+            let price = update
+                .get("price_level")
+                .and_then(Json::as_decimal)
+                .ok_or_else(|| malformed("invalid price_level"))?;
+            let quantity = update
+                .get("new_quantity")
+                .and_then(Json::as_decimal)
+                .ok_or_else(|| malformed("invalid new_quantity"))?;
+            Ok((side, price, quantity))
+        })
+        .collect()
+}
@@ -0,0 +1,263 @@
+//! A minimal JSON reader for venue payloads.
+
+use rust_decimal::Decimal;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// The deepest nesting of arrays and objects a document may have. Venue payloads nest
+/// a few levels, and bounding the depth keeps a hostile payload from exhausting the
+/// stack of the recursive parser.
+const MAX_DEPTH: usize = 64;
+
+/// A parsed JSON value.
+///
+/// Numbers keep the text they were written with, so prices and quantities are read
+/// exactly instead of through binary floating point.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a whole JSON document.
+    pub(super) fn parse(text: &str) -> Result<Json, String> {
+        let mut characters = text.chars().peekable();
+        let value = parse_value(&mut characters, 0)?;
+        skip_whitespace(&mut characters);
+        match characters.next() {
+            None => Ok(value),
+            Some(_) => Err("unexpected characters after the document".to_string()),
+        }
+    }
+
+    /// Returns the value of an object's field, if this is an object that has it.
+    pub(super) fn get(&self, name: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is one.
+    pub(super) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements, if this is an array.
+    pub(super) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an unsigned integer, if it is a number or a string holding
+    /// one.
+    pub(super) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(text) | Json::String(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an exact decimal, if it is a number or a string holding one.
+    pub(super) fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Json::Number(text) | Json::String(text) => Decimal::from_str_exact(text)
+                .or_else(|_| Decimal::from_scientific(text))
+                .ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the value starting at the next non-whitespace character, nested in `depth`
+/// arrays and objects.
+fn parse_value(characters: &mut Peekable<Chars<'_>>, depth: usize) -> Result<Json, String> {
+    skip_whitespace(characters);
+    if depth == MAX_DEPTH && matches!(characters.peek(), Some('{' | '[')) {
+        return Err(format!("nested deeper than {MAX_DEPTH} levels"));
+    }
+    match characters.peek().copied() {
+        Some('{') => {
+            characters.next();
+            let mut fields = Vec::new();
+            skip_whitespace(characters);
+            if characters.next_if_eq(&'}').is_some() {
+                return Ok(Json::Object(fields));
+            }
+            loop {
+                skip_whitespace(characters);
+                if characters.next() != Some('"') {
+                    return Err("expected a field name".to_string());
+                }
+                let name = parse_string(characters)?;
+                skip_whitespace(characters);
+                if characters.next() != Some(':') {
+                    return Err(format!("expected ':' after {name:?}"));
+                }
+                fields.push((name, parse_value(characters, depth + 1)?));
+                skip_whitespace(characters);
+                match characters.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(Json::Object(fields)),
+                    _ => return Err("expected ',' or '}'".to_string()),
+                }
+            }
+        }
+        Some('[') => {
+            characters.next();
+            let mut elements = Vec::new();
+            skip_whitespace(characters);
+            if characters.next_if_eq(&']').is_some() {
+                return Ok(Json::Array(elements));
+            }
+            loop {
+                elements.push(parse_value(characters, depth + 1)?);
+                skip_whitespace(characters);
+                match characters.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Json::Array(elements)),
+                    _ => return Err("expected ',' or ']'".to_string()),
+                }
+            }
+        }
+        Some('"') => {
+            characters.next();
+            parse_string(characters).map(Json::String)
+        }
+        Some(_) => {
+            let mut literal = String::new();
+            while let Some(character) =
+                characters.next_if(|character| !matches!(character, ',' | '}' | ']' | ':' | '"'))
+            {
+                if character.is_whitespace() {
+                    break;
+                }
+                literal.push(character);
+            }
+            match literal.as_str() {
+                "null" => Ok(Json::Null),
+                "true" => Ok(Json::Bool(true)),
+                "false" => Ok(Json::Bool(false)),
+                number
+                    if number.starts_with(|character: char| {
+                        character == '-' || character.is_ascii_digit()
+                    }) =>
+                {
+                    Ok(Json::Number(literal))
+                }
+                _ => Err(format!("invalid literal {literal:?}")),
+            }
+        }
+        None => Err("unexpected end of the document".to_string()),
+    }
+}
+
+/// Advances past whitespace.
+fn skip_whitespace(characters: &mut Peekable<Chars<'_>>) {
+    while characters
+        .next_if(|character| character.is_whitespace())
+        .is_some()
+    {}
+}
+
+/// Reads the rest of a string whose opening quote was consumed.
+fn parse_string(characters: &mut Peekable<Chars<'_>>) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match characters.next().ok_or("unterminated string")? {
+            '"' => return Ok(string),
+            '\\' => match characters.next().ok_or("unterminated string")? {
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                'r' => string.push('\r'),
+                'b' => string.push('\u{8}'),
+                'f' => string.push('\u{c}'),
+                'u' => {
+                    let code: String = characters.by_ref().take(4).collect();
+                    let character = u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or("invalid unicode escape")?;
+                    string.push(character);
+                }
+                escaped => string.push(escaped),
+            },
+            character => string.push(character),
+        }
+    }
+}
+
+/// Converts an RFC 3339 UTC timestamp, e.g. `2023-02-09T20:32:50.714964855Z`, into
+/// nanoseconds since the Unix epoch.
+pub(super) fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: i64 = date_parts.next()?.parse().ok()?;
+    let day: i64 = date_parts.next()?.parse().ok()?;
+
+    let (time, offset_seconds) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let split = time.rfind(['+', '-'])?;
+        let (hours, minutes) = time[split + 1..].split_once(':')?;
+        let hours: i64 = hours.parse().ok()?;
+        let minutes: i64 = minutes.parse().ok()?;
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        let offset = hours * 3600 + minutes * 60;
+        let sign = if time.as_bytes()[split] == b'-' {
+            -1
+        } else {
+            1
+        };
+        (&time[..split], sign * offset)
+    };
+    let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let second: i64 = clock_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    if fraction.len() > 9 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let nanos: i64 = format!("{fraction:0<9}").parse().ok()?;
+
+    // Days from the civil date, counting from 1970-01-01
+    let shifted_year = if month <= 2 { year - 1 } else { year };
+    let era = shifted_year.div_euclid(400);
+    let year_of_era = shifted_year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // Years far enough out overflow, and are rejected like any other invalid timestamp
+    let days = era
+        .checked_mul(146_097)?
+        .checked_add(day_of_era - 719_468)?;
+
+    let seconds = days
+        .checked_mul(86_400)?
+        .checked_add(hour * 3600 + minute * 60 + second - offset_seconds)?;
+    u64::try_from(seconds.checked_mul(1_000_000_000)?.checked_add(nanos)?).ok()
+}
@@ -0,0 +1,224 @@
+//! The Kraken v2 `book` channel.
+
+use super::json::{parse_timestamp, Json};
+use super::{malformed, FeedError, VenueLevels};
+use crate::types::{DepthSnapshot, OrderEvent, Side};
+use rust_decimal::Decimal;
+
+/// The number of levels per side covered by Kraken's checksum.
+const CHECKSUM_LEVELS: usize = 10;
+
+/// Normalizes the Kraken `book` channel of one symbol into `OrderEvent`s.
+///
+/// Kraken does not send removals for levels pushed out of the subscribed depth, so
+/// after every update the levels beyond `depth` are removed, as Kraken requires. Every
+/// payload then carries a CRC32 checksum of the top ten levels of each side, which is
+/// compared with the checksum of the normalizer's levels; on a mismatch the payload's
+/// events are still appended, so consumers stay in line with the normalizer, but
+/// updates are rejected until the next snapshot, which Kraken sends on resubscription.
+///
+/// The checksum is computed from prices and quantities as written in the payloads,
+/// which Kraken formats with the symbol's precisions.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::feeds::{FeedError, KrakenBook};
+///
+/// let mut feed = KrakenBook::new("BTC/USD", 10, 8);
+/// let mut events = Vec::new();
+/// let snapshot = r#"{"channel":"book","type":"snapshot","data":[{"symbol":"BTC/USD",
+///     "bids":[{"price":45283.5,"qty":0.10000000}],
+///     "asks":[{"price":45285.2,"qty":0.00100000}],
+///     "checksum":1}]}"#;
+///
+/// // The checksum does not match the levels, so the events are kept but updates stop
+/// let error = feed.normalize(snapshot, &mut events).unwrap_err();
+/// assert!(matches!(error, FeedError::ChecksumMismatch { expected: 1, .. }));
+/// assert_eq!(events.len(), 2);
+/// assert!(!feed.is_synchronized());
+/// ```
+#[derive(Debug, Clone)]
+pub struct KrakenBook {
+    /// The symbol whose levels are kept, e.g. `BTC/USD`
+    symbol: String,
+    /// The subscribed number of levels per side
+    depth: usize,
+    /// The symbol's levels
+    levels: VenueLevels,
+}
+
+impl KrakenBook {
+    /// Creates a normalizer awaiting the symbol's snapshot.
+    ///
+    /// ## Arguments
+    ///
+    /// * `symbol`: The symbol to normalize, e.g. `BTC/USD`
+    /// * `depth`: The depth of the subscription, e.g. 10 or 100
+    /// * `quantity_decimals`: The decimal places of Kraken quantities kept in event
+    ///   quantities, which are scaled by `10^quantity_decimals`
+    pub fn new(symbol: impl Into<String>, depth: usize, quantity_decimals: u32) -> Self {
+        KrakenBook {
+            symbol: symbol.into(),
+            depth,
+            levels: VenueLevels::new(quantity_decimals),
+        }
+    }
+
+    /// Applies a message received on the connection and appends the resulting events.
+    ///
+    /// Messages of other channels and symbols are ignored. Update events are stamped
+    /// with the update's `timestamp`, and snapshot events, which have none, with zero.
+    ///
+    /// ## Arguments
+    ///
+    /// * `payload`: The text of the WebSocket message
+    /// * `events`: Where the events are appended
+    ///
+    /// ## Returns
+    ///
+    /// An error if the message is malformed, fails its checksum, or is an update while
+    /// awaiting a snapshot; apart from checksum failures, a rejected snapshot or update
+    /// changes no level
+    pub fn normalize(
+        &mut self,
+        payload: &str,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<(), FeedError> {
+        let message = Json::parse(payload).map_err(FeedError::Malformed)?;
+        if message.get("channel").and_then(Json::as_str) != Some("book") {
+            return Ok(());
+        }
+        let is_snapshot = match message.get("type").and_then(Json::as_str) {
+            Some("snapshot") => true,
+            Some("update") => false,
+            _ => return Err(malformed("unknown message type")),
+        };
+
+        let data = message
+            .get("data")
+            .and_then(Json::as_array)
+            .ok_or_else(|| malformed("missing data"))?;
+        for book in data {
+            if book.get("symbol").and_then(Json::as_str) != Some(self.symbol.as_str()) {
+                continue;
+            }
+            let bids = parse_levels(book, "bids")?;
+            let asks = parse_levels(book, "asks")?;
+            let checksum = book
+                .get("checksum")
+                .and_then(Json::as_u64)
+                .and_then(|checksum| u32::try_from(checksum).ok())
+                .ok_or_else(|| malformed("invalid checksum"))?;
+            let timestamp_nanos = book
+                .get("timestamp")
+                .and_then(Json::as_str)
+                .and_then(parse_timestamp)
+                .unwrap_or(0);
+
+            if is_snapshot {
+                self.levels.replace(bids, asks, timestamp_nanos, events)?;
+            } else {
+                let changes: Vec<(Side, Decimal, Decimal)> = bids
+                    .into_iter()
+                    .map(|(price, quantity)| (Side::Bid, price, quantity))
+                    .chain(
+                        asks.into_iter()
+                            .map(|(price, quantity)| (Side::Ask, price, quantity)),
+                    )
+                    .collect();
+                self.levels.update(&changes, timestamp_nanos, events)?;
+            }
+            self.truncate(timestamp_nanos, events);
+
+            let computed = self.checksum();
+            if computed != checksum {
+                self.levels.synchronized = false;
+                return Err(FeedError::ChecksumMismatch {
+                    expected: checksum,
+                    computed,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether updates are being applied, i.e. a snapshot was received and no
+    /// checksum failed since.
+    pub fn is_synchronized(&self) -> bool {
+        self.levels.synchronized
+    }
+
+    /// Returns the symbol's levels at exact prices, with event quantities.
+    pub fn snapshot(&self) -> DepthSnapshot {
+        self.levels.snapshot()
+    }
+
+    /// Removes the levels beyond the subscribed depth.
+    fn truncate(&mut self, timestamp_nanos: u64, events: &mut Vec<OrderEvent>) {
+        let excess_bids: Vec<Decimal> = self
+            .levels
+            .bids
+            .keys()
+            .rev()
+            .skip(self.depth)
+            .copied()
+            .collect();
+        let excess_asks: Vec<Decimal> = self.levels.asks.keys().skip(self.depth).copied().collect();
+        for (side, prices) in [(Side::Bid, excess_bids), (Side::Ask, excess_asks)] {
+            for price in prices {
+                self.levels
+                    .set(side, price, Decimal::ZERO, timestamp_nanos, events)
+                    .expect("zero quantities are valid");
+            }
+        }
+    }
+
+    /// Computes Kraken's checksum of the top levels.
+    ///
+    /// The best asks, then the best bids, are concatenated as their price and quantity
+    /// without decimal point and leading zeros, and the string is hashed with CRC32.
+    fn checksum(&self) -> u32 {
+        let mut text = String::new();
+        let asks = self.levels.asks.iter().take(CHECKSUM_LEVELS);
+        let bids = self.levels.bids.iter().rev().take(CHECKSUM_LEVELS);
+        for (price, quantity) in asks.chain(bids) {
+            for value in [price, quantity] {
+                let digits = value.to_string().replace('.', "");
+                text.push_str(digits.trim_start_matches('0'));
+            }
+        }
+        crc32(text.as_bytes())
+    }
+}
+
+/// Reads the price and quantity of every level of one side of a book payload.
+fn parse_levels(book: &Json, side: &str) -> Result<Vec<(Decimal, Decimal)>, FeedError> {
+    let Some(levels) = book.get(side) else {
+        return Ok(Vec::new());
+    };
+    levels
+        .as_array()
+        .ok_or_else(|| malformed("levels are not an array"))?
+        .iter()
+        .map(|level| {
+            let price = level.get("price").and_then(Json::as_decimal);
+            let quantity = level.get("qty").and_then(Json::as_decimal);
+            price
+                .zip(quantity)
+                .ok_or_else(|| malformed("invalid level"))
+        })
+        .collect()
+}
+
+/// Computes the CRC32 (IEEE) of bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
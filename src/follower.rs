@@ -0,0 +1,143 @@
+use crate::error::{OrderBookError, Result};
+use crate::order_book::OrderBook;
+use crate::types::{ReplicationSnapshot, SequencedMarketByOrderEvent};
+
+/// A replica of an `OrderBook` maintained from the primary's market-by-order events.
+///
+/// The follower applies the primary's sequenced market-by-order events, as returned by
+/// `OrderBook::take_sequenced_market_by_order_events`, in sequence order. Events it
+/// already reflects are ignored, so a stream can be replayed from before the follower's
+/// position. An event after a missing one is rejected with
+/// `OrderBookError::SequenceGap` and leaves the replica where it stood, so the missing
+/// events can still be applied if they arrive late. An event that does not apply to the
+/// replica, e.g. the cancellation of an order it does not rest, means the replica no
+/// longer mirrors the primary: it is rejected with `OrderBookError::ReplicaDiverged`, as
+/// is every later event, until the follower catches up from a snapshot of the primary
+/// with `catch_up`.
+///
+/// The replica rests the primary's orders with their identifiers and time priority and
+/// prints the primary's trades into its own trade log, so it can serve reads such as
+/// depth and top of book as a warm standby or a remote read replica. Only what the
+/// market-by-order events describe is replicated: client order identifiers, metadata,
+/// quotes, and the primary's settings are not. The primary must record market-by-order
+/// events, with `OrderBook::set_market_by_order_events`, from the snapshot the follower
+/// starts from onwards.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{FollowerBook, Order, OrderBook, Side};
+///
+/// let mut primary = OrderBook::new();
+/// primary.set_market_by_order_events(true);
+/// let mut follower = FollowerBook::from_snapshot(&primary.replication_snapshot());
+///
+/// primary.insert_order(Order::new(100.00, 10, Side::Ask));
+/// primary.match_order(Order::new(100.00, 4, Side::Bid));
+/// for event in primary.take_sequenced_market_by_order_events() {
+///     follower.apply(&event).unwrap();
+/// }
+/// assert_eq!(follower.sequence(), 2);
+/// assert_eq!(follower.order_book().state_hash(), primary.state_hash());
+/// assert_eq!(follower.order_book().trades(), primary.trades());
+/// ```
+#[derive(Debug)]
+pub struct FollowerBook {
+    /// The replica
+    order_book: OrderBook,
+    /// The sequence number of the last event the replica reflects
+    sequence: u64,
+    /// The sequence number of the event that did not apply, until the next catch-up
+    diverged_at: Option<u64>,
+}
+
+impl FollowerBook {
+    /// Creates a follower of a primary that has not recorded any market-by-order event
+    /// yet, e.g. a new book.
+    pub fn new() -> Self {
+        FollowerBook::from_snapshot(&ReplicationSnapshot::default())
+    }
+
+    /// Creates a follower starting from a snapshot of the primary.
+    ///
+    /// ## Arguments
+    ///
+    /// * `snapshot`: The snapshot, taken with `OrderBook::replication_snapshot`
+    pub fn from_snapshot(snapshot: &ReplicationSnapshot) -> Self {
+        FollowerBook {
+            order_book: OrderBook::from_snapshot(&snapshot.book),
+            sequence: snapshot.market_by_order_sequence,
+            diverged_at: None,
+        }
+    }
+
+    /// Applies an event of the primary's market-by-order stream.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The event, with its sequence number
+    ///
+    /// ## Returns
+    ///
+    /// Whether the event was applied, `false` if the replica already reflected it,
+    /// `OrderBookError::SequenceGap` if events before it are missing, or
+    /// `OrderBookError::ReplicaDiverged` if the replica no longer mirrors the primary
+    pub fn apply(&mut self, event: &SequencedMarketByOrderEvent) -> Result<bool> {
+        if let Some(sequence) = self.diverged_at {
+            return Err(OrderBookError::ReplicaDiverged { sequence });
+        }
+        if event.sequence <= self.sequence {
+            return Ok(false);
+        }
+        if event.sequence != self.sequence + 1 {
+            return Err(OrderBookError::SequenceGap {
+                expected: self.sequence + 1,
+                received: event.sequence,
+            });
+        }
+        if !self.order_book.apply_market_by_order(&event.event) {
+            self.diverged_at = Some(event.sequence);
+            return Err(OrderBookError::ReplicaDiverged {
+                sequence: event.sequence,
+            });
+        }
+        self.sequence = event.sequence;
+        Ok(true)
+    }
+
+    /// Replaces the replica with a snapshot of the primary, e.g. after a gap or a
+    /// divergence.
+    ///
+    /// The events after the snapshot's position can then be applied, including any
+    /// already received that were rejected; those the snapshot reflects are ignored.
+    ///
+    /// ## Arguments
+    ///
+    /// * `snapshot`: The snapshot, taken with `OrderBook::replication_snapshot`
+    pub fn catch_up(&mut self, snapshot: &ReplicationSnapshot) {
+        *self = FollowerBook::from_snapshot(snapshot);
+    }
+
+    /// Returns the replica.
+    pub fn order_book(&self) -> &OrderBook {
+        &self.order_book
+    }
+
+    /// Returns the sequence number of the last market-by-order event the replica
+    /// reflects.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns whether the replica mirrors the primary as of `sequence`, i.e. whether no
+    /// event failed to apply since the last catch-up.
+    pub fn is_consistent(&self) -> bool {
+        self.diverged_at.is_none()
+    }
+}
+
+impl Default for FollowerBook {
+    fn default() -> Self {
+        FollowerBook::new()
+    }
+}
@@ -0,0 +1,60 @@
+/// Number of power-of-two buckets, enough to cover the whole `u64` range.
+const BUCKET_COUNT: usize = 64;
+
+/// A histogram of positive integers with power-of-two buckets.
+///
+/// Bucket `i` counts values in $[2^i, 2^{i+1})$, so the histogram stays fixed-size
+/// whatever the range of values, and adding or removing a value is $O(1)$. The order
+/// book uses it to keep the distribution of order sizes and of orders per level up to
+/// date as orders come and go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// Number of values per power-of-two bucket
+    buckets: [u64; BUCKET_COUNT],
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Histogram {
+            buckets: [0; BUCKET_COUNT],
+        }
+    }
+
+    /// Returns the non-empty buckets as `(lower_bound, count)` pairs, smallest first.
+    ///
+    /// A bucket with lower bound $b$ counts the values in $[b, 2b)$.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| (1u64 << bucket, *count))
+    }
+
+    /// Returns the number of values in the histogram.
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Counts one more occurrence of `value`.
+    pub(crate) fn add(&mut self, value: u64) {
+        self.buckets[Self::bucket_of(value)] += 1;
+    }
+
+    /// Counts one less occurrence of `value`, which must have been added before.
+    pub(crate) fn remove(&mut self, value: u64) {
+        self.buckets[Self::bucket_of(value)] -= 1;
+    }
+
+    /// Returns the bucket `value` falls into; zero shares the bucket of one.
+    fn bucket_of(value: u64) -> usize {
+        (u64::BITS - 1 - (value | 1).leading_zeros()) as usize
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
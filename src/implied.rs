@@ -0,0 +1,344 @@
+use crate::order_book::OrderBook;
+use crate::types::{MatchOutcome, Order, OrderHandle, Side, TradingState};
+use rust_decimal::Decimal;
+
+/// One of the three books of a two-legged spread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpreadMarket {
+    /// The spread, bought by buying the first leg and selling the second
+    Spread,
+    /// The first leg, e.g. the front month of a calendar spread
+    FirstLeg,
+    /// The second leg, e.g. the back month of a calendar spread
+    SecondLeg,
+}
+
+/// An order implied in one book by the best prices of the two others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpliedOrder {
+    /// The book the order is implied in
+    pub market: SpreadMarket,
+    /// The implied price, quantity, and side
+    pub order: Order,
+}
+
+/// The result of matching an order with `SpreadBook::match_order`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpreadMatchOutcome {
+    /// Every match made in the three books, in execution order, with its book
+    ///
+    /// Executions against implied orders appear as a pair of matches in the books the
+    /// implied order was derived from.
+    pub executions: Vec<(SpreadMarket, MatchOutcome)>,
+    /// The quantity of the order filled, directly or against implied orders
+    pub filled_quantity: u64,
+    /// Handle to the unfilled remainder, if any was left to rest in the order's book
+    pub handle: Option<OrderHandle>,
+}
+
+/// The books of a spread and of its two legs, with implied pricing between them.
+///
+/// The spread is the first leg minus the second leg, one lot of each. Its best prices
+/// and those of the legs imply orders in the third book:
+///
+/// - Implied in: the legs imply a spread bid at the first leg's bid minus the second
+///   leg's ask, and a spread ask at the first leg's ask minus the second leg's bid
+/// - Implied out: the spread and one leg imply orders in the other leg, e.g. a first
+///   leg bid at the spread bid plus the second leg's bid
+///
+/// An implied order's quantity is the smaller of the quantities at the two prices it
+/// is derived from, and only the best prices imply orders. Both kinds can be enabled
+/// or disabled independently, and are enabled by default. Books that are halted imply
+/// nothing.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Order, Side, SpreadBook, SpreadMarket};
+/// use rust_decimal::Decimal;
+///
+/// let mut spread_book = SpreadBook::new();
+/// spread_book
+///     .book_mut(SpreadMarket::FirstLeg)
+///     .insert_order(Order::new(101.00, 5, Side::Ask));
+/// spread_book
+///     .book_mut(SpreadMarket::SecondLeg)
+///     .insert_order(Order::new(100.00, 3, Side::Bid));
+///
+/// // Buying the first leg and selling the second costs 1.00
+/// let implied = spread_book.implied_orders();
+/// assert_eq!(implied[0].market, SpreadMarket::Spread);
+/// assert_eq!(implied[0].order.price, Decimal::ONE);
+///
+/// // A spread bid at 1.00 executes against both legs
+/// let outcome = spread_book.match_order(SpreadMarket::Spread, Order::new(1.00, 3, Side::Bid));
+/// assert_eq!(outcome.filled_quantity, 3);
+/// assert_eq!(spread_book.book(SpreadMarket::FirstLeg).total_volume(Side::Ask), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpreadBook {
+    /// The spread's own orders
+    spread: OrderBook,
+    /// The first leg's orders
+    first_leg: OrderBook,
+    /// The second leg's orders
+    second_leg: OrderBook,
+    /// Whether the legs imply orders in the spread
+    implied_in: bool,
+    /// Whether the spread and a leg imply orders in the other leg
+    implied_out: bool,
+}
+
+impl Default for SpreadBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpreadBook {
+    /// Creates three empty books with implied pricing enabled both ways.
+    pub fn new() -> Self {
+        Self::from_books(OrderBook::new(), OrderBook::new(), OrderBook::new())
+    }
+
+    /// Creates a spread book from existing books, with implied pricing enabled both ways.
+    ///
+    /// ## Arguments
+    ///
+    /// * `spread`: The spread's book
+    /// * `first_leg`: The first leg's book
+    /// * `second_leg`: The second leg's book
+    pub fn from_books(spread: OrderBook, first_leg: OrderBook, second_leg: OrderBook) -> Self {
+        SpreadBook {
+            spread,
+            first_leg,
+            second_leg,
+            implied_in: true,
+            implied_out: true,
+        }
+    }
+
+    /// Returns one of the books.
+    pub fn book(&self, market: SpreadMarket) -> &OrderBook {
+        match market {
+            SpreadMarket::Spread => &self.spread,
+            SpreadMarket::FirstLeg => &self.first_leg,
+            SpreadMarket::SecondLeg => &self.second_leg,
+        }
+    }
+
+    /// Returns one of the books for modification, e.g. to insert or cancel orders
+    /// without implied matching.
+    pub fn book_mut(&mut self, market: SpreadMarket) -> &mut OrderBook {
+        match market {
+            SpreadMarket::Spread => &mut self.spread,
+            SpreadMarket::FirstLeg => &mut self.first_leg,
+            SpreadMarket::SecondLeg => &mut self.second_leg,
+        }
+    }
+
+    /// Enables or disables orders implied in the spread by the legs.
+    pub fn set_implied_in(&mut self, enabled: bool) {
+        self.implied_in = enabled;
+    }
+
+    /// Enables or disables orders implied in a leg by the spread and the other leg.
+    pub fn set_implied_out(&mut self, enabled: bool) {
+        self.implied_out = enabled;
+    }
+
+    /// Returns the orders currently implied in the three books.
+    ///
+    /// ## Returns
+    ///
+    /// The implied orders of the spread, then of the first and second legs, bids first
+    pub fn implied_orders(&self) -> Vec<ImpliedOrder> {
+        let markets = [
+            SpreadMarket::Spread,
+            SpreadMarket::FirstLeg,
+            SpreadMarket::SecondLeg,
+        ];
+        markets
+            .into_iter()
+            .flat_map(|market| [(market, Side::Bid), (market, Side::Ask)])
+            .filter_map(|(market, side)| {
+                let (price, quantity) = self.implied_level(market, side)?;
+                Some(ImpliedOrder {
+                    market,
+                    order: Order {
+                        price,
+                        quantity,
+                        side,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Matches an order against its book and the orders implied in it, best price
+    /// first, and rests the remainder in its book.
+    ///
+    /// At equal prices, the book's own orders execute before the implied ones. An
+    /// execution against an implied order matches the orders it is derived from in
+    /// the two other books. Matching stops early if an execution halts one of the
+    /// books.
+    ///
+    /// ## Arguments
+    ///
+    /// * `market`: The book the order is submitted to
+    /// * `order`: The incoming order
+    ///
+    /// ## Returns
+    ///
+    /// The matches made in every book and the quantity filled
+    pub fn match_order(&mut self, market: SpreadMarket, order: Order) -> SpreadMatchOutcome {
+        let mut outcome = SpreadMatchOutcome {
+            executions: Vec::new(),
+            filled_quantity: 0,
+            handle: None,
+        };
+        let crosses = |price: Decimal| match order.side {
+            Side::Bid => price <= order.price,
+            Side::Ask => price >= order.price,
+        };
+        let mut remaining_quantity = order.quantity;
+        let trading_states = self.trading_states();
+
+        while remaining_quantity > 0
+            && self.book(market).trading_state() == TradingState::Continuous
+        {
+            let direct = self
+                .book(market)
+                .best_level(order.side.opposite())
+                .filter(|(price, _)| crosses(*price));
+            let implied = self
+                .implied_level(market, order.side.opposite())
+                .filter(|(price, _)| crosses(*price));
+            let direct_first = match (direct, implied) {
+                (Some((direct_price, _)), Some((implied_price, _))) => match order.side {
+                    Side::Bid => direct_price <= implied_price,
+                    Side::Ask => direct_price >= implied_price,
+                },
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+
+            let filled_quantity = if direct_first {
+                let (price, quantity) = direct.expect("checked above");
+                let slice = Order {
+                    price,
+                    quantity: quantity.min(remaining_quantity),
+                    side: order.side,
+                };
+                let match_outcome = self.book_mut(market).match_order(slice);
+                let filled_quantity = match_outcome.fills.filled_quantity;
+                outcome.executions.push((market, match_outcome));
+                filled_quantity
+            } else {
+                let (_, quantity) = implied.expect("checked above");
+                self.execute_implied(
+                    market,
+                    order.side,
+                    quantity.min(remaining_quantity),
+                    &mut outcome,
+                )
+            };
+
+            remaining_quantity -= filled_quantity;
+            outcome.filled_quantity += filled_quantity;
+            if self.trading_states() != trading_states {
+                return outcome;
+            }
+        }
+
+        if remaining_quantity > 0 {
+            let remainder = Order {
+                quantity: remaining_quantity,
+                ..order
+            };
+            let match_outcome = self.book_mut(market).match_order(remainder);
+            outcome.filled_quantity += match_outcome.fills.filled_quantity;
+            outcome.handle = match_outcome.handle;
+            outcome.executions.push((market, match_outcome));
+        }
+        outcome
+    }
+
+    /// Returns the trading state of each book.
+    fn trading_states(&self) -> [TradingState; 3] {
+        [
+            self.spread.trading_state(),
+            self.first_leg.trading_state(),
+            self.second_leg.trading_state(),
+        ]
+    }
+
+    /// Returns the price and quantity of the order implied on a side of a book, if the
+    /// implied pricing is enabled and the two other books quote the sides it needs.
+    fn implied_level(&self, market: SpreadMarket, side: Side) -> Option<(Decimal, u64)> {
+        let enabled = match market {
+            SpreadMarket::Spread => self.implied_in,
+            SpreadMarket::FirstLeg | SpreadMarket::SecondLeg => self.implied_out,
+        };
+        if !enabled {
+            return None;
+        }
+
+        let mut price = Decimal::ZERO;
+        let mut quantity = u64::MAX;
+        for (component, component_side, sign) in components(market, side) {
+            let book = self.book(component);
+            if book.trading_state() != TradingState::Continuous {
+                return None;
+            }
+            let (component_price, component_quantity) = book.best_level(component_side)?;
+            price += Decimal::from(sign) * component_price;
+            quantity = quantity.min(component_quantity);
+        }
+        Some((price, quantity))
+    }
+
+    /// Executes against the order implied on the side opposite an incoming order.
+    ///
+    /// ## Returns
+    ///
+    /// The quantity filled, the smaller of the fills in the two other books
+    fn execute_implied(
+        &mut self,
+        market: SpreadMarket,
+        side: Side,
+        quantity: u64,
+        outcome: &mut SpreadMatchOutcome,
+    ) -> u64 {
+        let mut filled_quantity = quantity;
+        for (component, component_side, _) in components(market, side.opposite()) {
+            let book = self.book_mut(component);
+            let (price, _) = book
+                .best_level(component_side)
+                .expect("implied orders are derived from quoted sides");
+            let match_outcome = book.match_order(Order {
+                price,
+                quantity,
+                side: component_side.opposite(),
+            });
+            filled_quantity = filled_quantity.min(match_outcome.fills.filled_quantity);
+            outcome.executions.push((component, match_outcome));
+        }
+        filled_quantity
+    }
+}
+
+/// Returns the resting sides an order implied on a side of a book is derived from,
+/// with the sign each price contributes to the implied price.
+fn components(market: SpreadMarket, side: Side) -> [(SpreadMarket, Side, i64); 2] {
+    use SpreadMarket::{FirstLeg, SecondLeg, Spread};
+    match market {
+        // Spread = first leg - second leg
+        Spread => [(FirstLeg, side, 1), (SecondLeg, side.opposite(), -1)],
+        // First leg = spread + second leg
+        FirstLeg => [(Spread, side, 1), (SecondLeg, side, 1)],
+        // Second leg = first leg - spread
+        SecondLeg => [(FirstLeg, side, 1), (Spread, side.opposite(), -1)],
+    }
+}
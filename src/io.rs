@@ -0,0 +1,302 @@
+//! Loading orders from historical datasets.
+//!
+//! `load_orders` turns a CSV or JSON Lines file of orders into the `Command` stream
+//! that rests them, so a book can be seeded with one call:
+//!
+//! ```
+//! use order_book::io::{load_orders, OrderFileFormat};
+//! use order_book::{OrderBook, Side};
+//! use rust_decimal::Decimal;
+//!
+//! let file = "price,qty,side\n100.25,10,bid\n101.50,4,ask\n";
+//! let commands = load_orders(file.as_bytes(), OrderFileFormat::Csv).unwrap();
+//!
+//! let mut order_book = OrderBook::new();
+//! for command in commands {
+//!     order_book.apply(command).unwrap();
+//! }
+//! assert_eq!(order_book.compute_spread().2, Some(Decimal::new(125, 2)));
+//! ```
+//!
+//! Each order has a `price`, a `qty` (or `quantity`), and a `side` (`bid` or `buy`,
+//! `ask` or `sell`, in any case), and optionally an `id` and a `ts`:
+//!
+//! - An `id` becomes the order's `ClientOrderId`, so the book can be queried by the
+//!   dataset's identifiers and rejects duplicates
+//! - A `ts`, in nanoseconds since the Unix epoch, sets the book's clock before the
+//!   order is inserted, so events carry the dataset's times
+//!
+//! Prices are parsed exactly as written, without going through binary floating point.
+
+use crate::types::{ClientOrderId, Command, Order, Side};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::io::BufRead;
+
+/// The layout of an order file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderFileFormat {
+    /// Comma-separated values with a header line naming the columns
+    ///
+    /// Fields may be wrapped in double quotes but may not contain commas.
+    Csv,
+    /// One JSON object per line, e.g. `{"price": "100.25", "qty": 10, "side": "bid"}`
+    ///
+    /// Prices may be written as numbers or strings.
+    JsonLines,
+}
+
+/// Why a line of an order file could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    /// The 1-based number of the offending line
+    pub line: usize,
+    /// What is wrong with the line
+    pub reason: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Parses an order file into the commands inserting its orders, in file order.
+///
+/// Blank lines are skipped.
+///
+/// ## Arguments
+///
+/// * `reader`: The file's contents
+/// * `format`: The layout of the file
+///
+/// ## Returns
+///
+/// The commands, or a `LoadError` locating the first line that could not be read or
+/// parsed
+pub fn load_orders<R: BufRead>(
+    reader: R,
+    format: OrderFileFormat,
+) -> Result<Vec<Command>, LoadError> {
+    let mut commands = Vec::new();
+    let mut columns: Option<Vec<String>> = None;
+    let mut last_timestamp = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |reason: String| LoadError {
+            line: line_number,
+            reason,
+        };
+        let line = line.map_err(|io_error| error(io_error.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields = match format {
+            OrderFileFormat::Csv => {
+                let values = line.split(',').map(|value| unquote(value.trim()));
+                let Some(columns) = &columns else {
+                    columns = Some(values.map(|name| name.to_ascii_lowercase()).collect());
+                    continue;
+                };
+                let values: Vec<String> = values.collect();
+                if values.len() != columns.len() {
+                    return Err(error(format!(
+                        "expected {} fields, found {}",
+                        columns.len(),
+                        values.len()
+                    )));
+                }
+                columns.iter().cloned().zip(values).collect()
+            }
+            OrderFileFormat::JsonLines => parse_flat_object(&line).map_err(error)?,
+        };
+
+        let record = OrderRecord::from_fields(fields).map_err(error)?;
+        if record.timestamp_nanos.is_some() && record.timestamp_nanos != last_timestamp {
+            last_timestamp = record.timestamp_nanos;
+            commands.extend(record.timestamp_nanos.map(Command::SetTime));
+        }
+        commands.push(match record.client_order_id {
+            Some(client_order_id) => Command::InsertWithClientId(record.order, client_order_id),
+            None => Command::Insert(record.order),
+        });
+    }
+
+    Ok(commands)
+}
+
+/// An order read from one line of a file.
+struct OrderRecord {
+    /// The order to insert
+    order: Order,
+    /// The dataset's identifier for the order, if given
+    client_order_id: Option<ClientOrderId>,
+    /// The dataset's time for the order, if given
+    timestamp_nanos: Option<u64>,
+}
+
+impl OrderRecord {
+    /// Builds the record from the named fields of a line, ignoring unknown fields.
+    fn from_fields(fields: Vec<(String, String)>) -> Result<Self, String> {
+        let (mut price, mut quantity, mut side, mut id, mut timestamp) =
+            (None, None, None, None, None);
+        for (name, value) in fields {
+            match name.as_str() {
+                "price" => price = Some(value),
+                "qty" | "quantity" => quantity = Some(value),
+                "side" => side = Some(value),
+                "id" => id = Some(value),
+                "ts" => timestamp = Some(value),
+                _ => {}
+            }
+        }
+
+        let price = price.ok_or("missing price")?;
+        let price = Decimal::from_str_exact(&price)
+            .or_else(|_| Decimal::from_scientific(&price))
+            .map_err(|_| format!("invalid price {price:?}"))?;
+        let quantity = quantity.ok_or("missing quantity")?;
+        let quantity = quantity
+            .parse()
+            .map_err(|_| format!("invalid quantity {quantity:?}"))?;
+        let side = match side.ok_or("missing side")?.to_ascii_lowercase().as_str() {
+            "bid" | "buy" => Side::Bid,
+            "ask" | "sell" => Side::Ask,
+            other => return Err(format!("invalid side {other:?}")),
+        };
+        let client_order_id = id
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map(ClientOrderId))
+            .transpose()
+            .map_err(|_| "invalid id".to_string())?;
+        let timestamp_nanos = timestamp
+            .filter(|timestamp| !timestamp.is_empty())
+            .map(|timestamp| timestamp.parse())
+            .transpose()
+            .map_err(|_| "invalid ts".to_string())?;
+
+        Ok(OrderRecord {
+            order: Order {
+                price,
+                quantity,
+                side,
+            },
+            client_order_id,
+            timestamp_nanos,
+        })
+    }
+}
+
+/// Removes the double quotes around a CSV field, if any.
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Parses a JSON object whose values are strings, numbers, booleans, or null.
+///
+/// String values are unescaped, and other values are returned as written, which keeps
+/// numbers exact. Fields set to null are left out.
+fn parse_flat_object(line: &str) -> Result<Vec<(String, String)>, String> {
+    let mut characters = line.trim().chars().peekable();
+    if characters.next() != Some('{') {
+        return Err("expected a JSON object".to_string());
+    }
+
+    let mut fields = Vec::new();
+    for position in 0.. {
+        skip_whitespace(&mut characters);
+        match characters.next() {
+            Some('}') if position == 0 => break,
+            Some('"') => {}
+            _ => return Err("expected a field name".to_string()),
+        }
+        let name = parse_string(&mut characters)?;
+        skip_whitespace(&mut characters);
+        if characters.next() != Some(':') {
+            return Err(format!("expected ':' after {name:?}"));
+        }
+        skip_whitespace(&mut characters);
+        let value = if characters.peek() == Some(&'"') {
+            characters.next();
+            Some(parse_string(&mut characters)?)
+        } else {
+            let mut literal = String::new();
+            while let Some(&character) = characters.peek() {
+                if character == ',' || character == '}' || character.is_whitespace() {
+                    break;
+                }
+                if matches!(character, '{' | '[') {
+                    return Err(format!("nested value for {name:?} is not supported"));
+                }
+                literal.push(character);
+                characters.next();
+            }
+            if literal.is_empty() {
+                return Err(format!("missing value for {name:?}"));
+            }
+            // A null value is the same as a missing field
+            Some(literal).filter(|literal| literal != "null")
+        };
+        if let Some(value) = value {
+            fields.push((name.to_ascii_lowercase(), value));
+        }
+
+        skip_whitespace(&mut characters);
+        match characters.next() {
+            Some(',') => {}
+            Some('}') => break,
+            _ => return Err("expected ',' or '}'".to_string()),
+        }
+    }
+
+    skip_whitespace(&mut characters);
+    if characters.next().is_some() {
+        return Err("unexpected characters after the object".to_string());
+    }
+    Ok(fields)
+}
+
+/// Advances past whitespace.
+fn skip_whitespace(characters: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while characters
+        .next_if(|character| character.is_whitespace())
+        .is_some()
+    {}
+}
+
+/// Reads the rest of a JSON string whose opening quote was consumed.
+fn parse_string(
+    characters: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match characters.next().ok_or("unterminated string")? {
+            '"' => return Ok(string),
+            '\\' => match characters.next().ok_or("unterminated string")? {
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                'r' => string.push('\r'),
+                'b' => string.push('\u{8}'),
+                'f' => string.push('\u{c}'),
+                'u' => {
+                    let code: String = characters.by_ref().take(4).collect();
+                    let character = u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or("invalid unicode escape")?;
+                    string.push(character);
+                }
+                escaped => string.push(escaped),
+            },
+            character => string.push(character),
+        }
+    }
+}
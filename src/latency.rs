@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of power-of-two buckets, enough to cover the whole `u64` range.
+const BUCKET_COUNT: usize = 64;
+
+/// A lock-free histogram of latencies with power-of-two buckets.
+///
+/// Bucket `i` counts samples in $[2^i, 2^{i+1})$ nanoseconds (bucket 0 also holds
+/// zero), which keeps recording to a handful of atomic increments while still
+/// resolving latencies from nanoseconds to seconds within a factor of two.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    /// Sample counts per power-of-two bucket
+    buckets: [AtomicU64; BUCKET_COUNT],
+    /// Total number of samples
+    count: AtomicU64,
+    /// Sum of all samples, used for the mean
+    sum_nanos: AtomicU64,
+    /// Largest sample seen
+    max_nanos: AtomicU64,
+}
+
+/// A point-in-time copy of a `LatencyHistogram`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    /// Sample counts per power-of-two bucket
+    buckets: [u64; BUCKET_COUNT],
+    /// Total number of samples
+    pub count: u64,
+    /// Sum of all samples in nanoseconds
+    pub sum_nanos: u64,
+    /// Largest sample in nanoseconds
+    pub max_nanos: u64,
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+            max_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a single latency sample.
+    ///
+    /// ## Arguments
+    ///
+    /// * `latency_nanos`: The latency in nanoseconds
+    pub fn record(&self, latency_nanos: u64) {
+        let bucket = (u64::BITS - 1 - (latency_nanos | 1).leading_zeros()) as usize;
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(latency_nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(latency_nanos, Ordering::Relaxed);
+    }
+
+    /// Returns a copy of the current counts.
+    ///
+    /// Samples recorded concurrently with the copy may be partially included.
+    pub fn snapshot(&self) -> LatencySnapshot {
+        LatencySnapshot {
+            buckets: std::array::from_fn(|bucket| self.buckets[bucket].load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            sum_nanos: self.sum_nanos.load(Ordering::Relaxed),
+            max_nanos: self.max_nanos.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Discards all recorded samples.
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.count.store(0, Ordering::Relaxed);
+        self.sum_nanos.store(0, Ordering::Relaxed);
+        self.max_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencySnapshot {
+    /// Returns the mean latency in nanoseconds, or 0 if no samples were recorded.
+    pub fn mean_nanos(&self) -> u64 {
+        self.sum_nanos.checked_div(self.count).unwrap_or(0)
+    }
+
+    /// Returns an upper bound for the given quantile of the recorded latencies.
+    ///
+    /// The bound is the upper edge of the bucket containing the quantile, capped by
+    /// the largest sample, so it overestimates the true value by less than a factor of two.
+    ///
+    /// ## Arguments
+    ///
+    /// * `quantile`: The quantile to compute, between 0.0 and 1.0 (e.g. 0.99)
+    ///
+    /// ## Returns
+    ///
+    /// The latency bound in nanoseconds, or 0 if no samples were recorded
+    pub fn quantile_nanos(&self, quantile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, bucket_count) in self.buckets.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= rank {
+                let upper_edge = 1u64.checked_shl(bucket as u32 + 1).unwrap_or(u64::MAX) - 1;
+                return upper_edge.min(self.max_nanos);
+            }
+        }
+
+        self.max_nanos
+    }
+}
@@ -0,0 +1,353 @@
+//! A test harness delaying the stages of a feed-to-book-to-cache pipeline, to measure
+//! how stale reads get under a given configuration.
+//!
+//! A `LatencyPipeline` connects a feed, standing for the adapter turning a venue's
+//! messages into `Command`s, to an `OrderBook`, and the book's events to a
+//! `MarketDepthCache`, and holds every update in flight on each hop for an artificial
+//! `Delay`. Time is the book's clock, which the harness advances (`advance_to`), so a
+//! configuration measures identically on every run, random delays included, as they
+//! are drawn from a seeded generator:
+//!
+//! - Commands sent to the feed reach the book `PipelineDelays::feed_to_book` later,
+//!   and are applied at their arrival time, which stamps their events
+//! - The events reach the cache `PipelineDelays::book_to_cache` after they were
+//!   published
+//! - Each hop delivers in the order it was given, like a channel: an update delayed
+//!   less than the one before it waits for it
+//!
+//! Every `read` of the cache then reports how far it lags: by how many of the book's
+//! events, and for how long the oldest update sent to the feed and not yet reflected
+//! has been in flight, both through the cache and up to the book. The staleness of
+//! every read is also recorded into histograms, whose quantiles compare
+//! configurations.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::latency_injection::{Delay, LatencyPipeline, PipelineDelays};
+//! use order_book::{Command, MarketDepthCache, Order, OrderBook, Side};
+//! use std::time::Duration;
+//!
+//! let delays = PipelineDelays {
+//!     feed_to_book: Delay::Fixed(Duration::from_micros(50)),
+//!     book_to_cache: Delay::Fixed(Duration::from_micros(20)),
+//! };
+//! let mut pipeline =
+//!     LatencyPipeline::new(OrderBook::deterministic(7), MarketDepthCache::new(), delays, 7);
+//!
+//! pipeline.send(Command::Insert(Order::new(100.00, 10, Side::Bid)));
+//! pipeline.advance_to(60_000);
+//! let read = pipeline.read();
+//! assert_eq!(read.sequence_lag, 1);
+//! assert_eq!(read.book_staleness_nanos, 0);
+//! assert_eq!(read.cache_staleness_nanos, 60_000);
+//!
+//! pipeline.advance_to(70_000);
+//! assert_eq!(pipeline.read().cache_staleness_nanos, 0);
+//! assert_eq!(pipeline.cache_staleness().max_nanos, 60_000);
+//! ```
+
+use crate::latency::{LatencyHistogram, LatencySnapshot};
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::rng::SeededRng;
+use crate::types::{Command, OrderEvent};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How long a `LatencyPipeline` holds every update on one hop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Delay {
+    /// Deliver at once
+    #[default]
+    None,
+    /// Hold every update for the same time
+    Fixed(Duration),
+    /// Hold every update for a time drawn uniformly between two bounds, included
+    Uniform {
+        /// The shortest delay
+        min: Duration,
+        /// The longest delay
+        max: Duration,
+    },
+}
+
+impl Delay {
+    /// Draws the delay of one update, in nanoseconds.
+    fn draw_nanos(&self, rng: &mut SeededRng) -> u64 {
+        let nanos = |duration: &Duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        match self {
+            Delay::None => 0,
+            Delay::Fixed(delay) => nanos(delay),
+            Delay::Uniform { min, max } => {
+                let (min, max) = (nanos(min), nanos(max).max(nanos(min)));
+                match (max - min).checked_add(1) {
+                    Some(span) => min + rng.next_u64() % span,
+                    None => rng.next_u64(),
+                }
+            }
+        }
+    }
+}
+
+/// The delays a `LatencyPipeline` injects on its two hops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PipelineDelays {
+    /// The delay between the feed adapter and the book
+    pub feed_to_book: Delay,
+    /// The delay between the book's publication of an event and its processing by the
+    /// cache
+    pub book_to_cache: Delay,
+}
+
+/// How stale a read of a `LatencyPipeline`'s cache is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaleRead {
+    /// When the read happened, by the book's clock
+    pub timestamp_nanos: u64,
+    /// The number of the book's events the cache has not processed yet
+    pub sequence_lag: u64,
+    /// For how long the oldest command sent to the feed and not yet applied to the book
+    /// has been in flight, 0 if none is
+    pub book_staleness_nanos: u64,
+    /// For how long the oldest command sent to the feed and not yet reflected in the
+    /// cache has been in flight, 0 if none is
+    pub cache_staleness_nanos: u64,
+}
+
+/// A command on its way to the book.
+#[derive(Debug, Clone)]
+struct InFlightCommand {
+    /// When the command was sent to the feed
+    sent_nanos: u64,
+    /// When the command reaches the book
+    due_nanos: u64,
+    /// The command
+    command: Command,
+}
+
+/// An event on its way to the cache.
+#[derive(Debug, Clone)]
+struct InFlightEvent {
+    /// When the command that produced the event was sent to the feed
+    sent_nanos: u64,
+    /// When the event reaches the cache
+    due_nanos: u64,
+    /// The event
+    event: OrderEvent,
+}
+
+/// A feed, a book, and a cache connected by hops with injected delays.
+///
+/// See the module documentation for how updates travel and how staleness is measured.
+#[derive(Debug)]
+pub struct LatencyPipeline {
+    /// The book the feed's commands are applied to
+    order_book: OrderBook,
+    /// The cache the book's events are processed by
+    cache: MarketDepthCache,
+    /// The delays of the two hops
+    delays: PipelineDelays,
+    /// The generator random delays are drawn from
+    rng: SeededRng,
+    /// The commands on their way to the book, in delivery order
+    commands: VecDeque<InFlightCommand>,
+    /// The events on their way to the cache, in delivery order
+    events: VecDeque<InFlightEvent>,
+    /// The number of commands the book rejected
+    rejected_commands: usize,
+    /// The largest sequence lag read so far
+    max_sequence_lag: u64,
+    /// The book staleness of every read
+    book_staleness: LatencyHistogram,
+    /// The cache staleness of every read
+    cache_staleness: LatencyHistogram,
+}
+
+impl LatencyPipeline {
+    /// Creates a pipeline around a book and a cache, with nothing in flight.
+    ///
+    /// The harness drives the book's clock from its current time: a book with a system
+    /// clock is switched to a manual one at the current time, so
+    /// `OrderBook::deterministic` is the usual choice.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book the feed's commands are applied to
+    /// * `cache`: The cache the book's events are processed by, usually empty
+    /// * `delays`: The delays of the two hops
+    /// * `seed`: The seed random delays are drawn from
+    pub fn new(
+        mut order_book: OrderBook,
+        cache: MarketDepthCache,
+        delays: PipelineDelays,
+        seed: u64,
+    ) -> Self {
+        let now_nanos = order_book.now_nanos();
+        order_book.set_time(now_nanos);
+        LatencyPipeline {
+            order_book,
+            cache,
+            delays,
+            rng: SeededRng::new(seed),
+            commands: VecDeque::new(),
+            events: VecDeque::new(),
+            rejected_commands: 0,
+            max_sequence_lag: 0,
+            book_staleness: LatencyHistogram::new(),
+            cache_staleness: LatencyHistogram::new(),
+        }
+    }
+
+    /// Sends a command to the feed at the current time.
+    ///
+    /// Without delays, the command is applied and its events processed before this
+    /// returns.
+    pub fn send(&mut self, command: Command) {
+        let sent_nanos = self.order_book.now_nanos();
+        let delay_nanos = self.delays.feed_to_book.draw_nanos(&mut self.rng);
+        let previous_due = self.commands.back().map_or(0, |command| command.due_nanos);
+        self.commands.push_back(InFlightCommand {
+            sent_nanos,
+            due_nanos: sent_nanos.saturating_add(delay_nanos).max(previous_due),
+            command,
+        });
+        self.advance_to(sent_nanos);
+    }
+
+    /// Advances the book's clock to a time, delivering everything due by then in time
+    /// order.
+    ///
+    /// A time before the current one delivers what is due and leaves the clock as is.
+    pub fn advance_to(&mut self, timestamp_nanos: u64) {
+        loop {
+            let command_due = self.commands.front().map(|command| command.due_nanos);
+            let event_due = self.events.front().map(|event| event.due_nanos);
+            match (command_due, event_due) {
+                // Events published before a command arrives are processed first
+                (Some(command_due), event_due)
+                    if command_due <= timestamp_nanos
+                        && event_due.is_none_or(|event_due| command_due < event_due) =>
+                {
+                    self.deliver_command();
+                }
+                (_, Some(event_due)) if event_due <= timestamp_nanos => {
+                    let in_flight = self.events.pop_front().expect("front must exist");
+                    self.cache.process_order_event(in_flight.event);
+                }
+                _ => break,
+            }
+        }
+        if timestamp_nanos > self.order_book.now_nanos() {
+            self.order_book.set_time(timestamp_nanos);
+        }
+    }
+
+    /// Delivers everything in flight, advancing the clock to the last arrival.
+    pub fn flush(&mut self) {
+        let last_due = self
+            .commands
+            .back()
+            .map(|command| command.due_nanos)
+            .into_iter()
+            .chain(self.events.back().map(|event| event.due_nanos))
+            .max();
+        if let Some(last_due) = last_due {
+            self.advance_to(last_due);
+        }
+        // Commands arriving last publish events that are still on their way
+        if !self.events.is_empty() {
+            self.flush();
+        }
+    }
+
+    /// Reads how stale the cache is now, and records the read into the histograms.
+    pub fn read(&mut self) -> StaleRead {
+        let now_nanos = self.order_book.now_nanos();
+        let oldest_command = self.commands.front().map(|command| command.sent_nanos);
+        let oldest_event = self.events.front().map(|event| event.sent_nanos);
+        let oldest_unreflected = oldest_command.into_iter().chain(oldest_event).min();
+        let staleness =
+            |sent_nanos: Option<u64>| sent_nanos.map_or(0, |sent| now_nanos.saturating_sub(sent));
+        let read = StaleRead {
+            timestamp_nanos: now_nanos,
+            sequence_lag: self.cache.lag(self.order_book.last_event_sequence()),
+            book_staleness_nanos: staleness(oldest_command),
+            cache_staleness_nanos: staleness(oldest_unreflected),
+        };
+        self.max_sequence_lag = self.max_sequence_lag.max(read.sequence_lag);
+        self.book_staleness.record(read.book_staleness_nanos);
+        self.cache_staleness.record(read.cache_staleness_nanos);
+        read
+    }
+
+    /// Returns the distribution of the book staleness of every read so far.
+    pub fn book_staleness(&self) -> LatencySnapshot {
+        self.book_staleness.snapshot()
+    }
+
+    /// Returns the distribution of the cache staleness of every read so far.
+    pub fn cache_staleness(&self) -> LatencySnapshot {
+        self.cache_staleness.snapshot()
+    }
+
+    /// Returns the largest sequence lag read so far.
+    pub fn max_sequence_lag(&self) -> u64 {
+        self.max_sequence_lag
+    }
+
+    /// Returns the number of commands on their way to the book.
+    pub fn pending_commands(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns the number of events on their way to the cache.
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns the number of commands the book rejected on arrival.
+    pub fn rejected_commands(&self) -> usize {
+        self.rejected_commands
+    }
+
+    /// Returns the book.
+    pub fn order_book(&self) -> &OrderBook {
+        &self.order_book
+    }
+
+    /// Returns the cache.
+    pub fn cache(&self) -> &MarketDepthCache {
+        &self.cache
+    }
+
+    /// Returns the book and the cache, dropping what is in flight.
+    pub fn into_inner(self) -> (OrderBook, MarketDepthCache) {
+        (self.order_book, self.cache)
+    }
+
+    /// Applies the next command to the book at its arrival time, and sends its events
+    /// on to the cache.
+    fn deliver_command(&mut self) {
+        let in_flight = self.commands.pop_front().expect("front must exist");
+        if in_flight.due_nanos > self.order_book.now_nanos() {
+            self.order_book.set_time(in_flight.due_nanos);
+        }
+        let Ok(events) = self.order_book.apply(in_flight.command) else {
+            self.rejected_commands += 1;
+            return;
+        };
+        for event in events {
+            let delay_nanos = self.delays.book_to_cache.draw_nanos(&mut self.rng);
+            let previous_due = self.events.back().map_or(0, |event| event.due_nanos);
+            self.events.push_back(InFlightEvent {
+                sent_nanos: in_flight.sent_nanos,
+                due_nanos: event
+                    .timestamp_nanos
+                    .saturating_add(delay_nanos)
+                    .max(previous_due),
+                event,
+            });
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::memory::{btree_set_bytes, hash_map_bytes};
+use crate::price_key::PriceKey;
+use crate::price_level::PriceLevel;
+use std::collections::{BTreeSet, HashMap};
+
+/// The price levels of one side of the book, indexed both by exact key and by order.
+///
+/// Levels live in a hash map, so operations on a known price (resting, reducing, or
+/// cancelling an order, and exact-level queries) are $O(1)$. A sorted set of the same
+/// keys serves ordered traversal, which the best price and top-of-book walks need;
+/// only adding or removing a level pays its $O(\log{N})$ cost.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LevelMap {
+    /// Every non-empty price level by key
+    levels: HashMap<PriceKey, PriceLevel>,
+    /// The keys of `levels`, in ascending order
+    keys: BTreeSet<PriceKey>,
+}
+
+impl LevelMap {
+    /// Returns the level at `key`, if any.
+    #[inline]
+    pub(crate) fn get(&self, key: &PriceKey) -> Option<&PriceLevel> {
+        self.levels.get(key)
+    }
+
+    /// Returns the level at `key`, if any.
+    #[inline]
+    pub(crate) fn get_mut(&mut self, key: &PriceKey) -> Option<&mut PriceLevel> {
+        self.levels.get_mut(key)
+    }
+
+    /// Returns the level at `key`, creating an empty one if there is none.
+    pub(crate) fn get_or_insert(&mut self, key: PriceKey) -> &mut PriceLevel {
+        self.levels.entry(key).or_insert_with(|| {
+            self.keys.insert(key);
+            PriceLevel::default()
+        })
+    }
+
+    /// Removes the level at `key`.
+    pub(crate) fn remove(&mut self, key: &PriceKey) {
+        if self.levels.remove(key).is_some() {
+            self.keys.remove(key);
+        }
+    }
+
+    /// Returns the keys of every level, in ascending order.
+    #[inline]
+    pub(crate) fn keys(&self) -> impl DoubleEndedIterator<Item = &PriceKey> {
+        self.keys.iter()
+    }
+
+    /// Returns every level with its key, in ascending key order.
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = (&PriceKey, &PriceLevel)> {
+        self.keys.iter().map(|key| (key, &self.levels[key]))
+    }
+
+    /// Returns every level, in no particular order.
+    pub(crate) fn levels_mut(&mut self) -> impl Iterator<Item = &mut PriceLevel> {
+        self.levels.values_mut()
+    }
+
+    /// Releases the spare capacity of the level index.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.levels.shrink_to_fit();
+    }
+
+    /// Returns the lowest level with its key.
+    pub(crate) fn first_key_value(&self) -> Option<(&PriceKey, &PriceLevel)> {
+        self.iter().next()
+    }
+
+    /// Returns the highest level with its key.
+    pub(crate) fn last_key_value(&self) -> Option<(&PriceKey, &PriceLevel)> {
+        self.iter().next_back()
+    }
+
+    /// Returns the number of levels.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Estimates the heap bytes held by the level index and the sorted keys.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        hash_map_bytes(&self.levels) + btree_set_bytes(&self.keys)
+    }
+}
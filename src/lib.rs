@@ -14,9 +14,7 @@
 //! ## Example Usage
 //!
 //! ```rust
-//! use order_book::{OrderBook, MarketDepthCache, Order, Side};
-//! use rust_decimal::Decimal;
-//! use parking_lot::RwLock;
+//! use order_book::prelude::*;
 //! use std::sync::Arc;
 //!
 //! // Create the order book and cache
@@ -29,7 +27,7 @@
 //! // 1. Acquire write lock briefly to insert order
 //! let event = {
 //!     let mut book = order_book.write();
-//!     book.insert_order(order)
+//!     book.insert_order(order).event
 //! }; // Write lock released immediately
 //!
 //! // 2. Update cache (uses its own lock)
@@ -47,19 +45,140 @@
 //!
 //! Also, on the performance side, order insertions only hold the lock for a brief period,
 //! that is a $O(\log{N})$, because we're relying on the `BTreeMap`'s efficient insertions.
+//! Levels that already exist are found through a hash index instead, so resting,
+//! reducing, or cancelling an order at an existing price is $O(1)$.
 //!
 //! Lastly, the cache is updated asynchronously, which means that it does not block the order book.
 //! This allows for high concurrency and responsiveness in the order book.
+//!
+//! `SharedOrderBook` bundles the two behind their locks and does this plumbing for the
+//! common operations.
+//!
+//! The `prelude` module gathers the types most programs need, together with the
+//! `Decimal` prices and `RwLock` the book is shared with, for glob import.
+//!
+//! ## Optional Features
+//!
+//! - `tui`: A terminal viewer (`tui::BookViewer`) rendering a live depth ladder and
+//!   event tape from the `OrderEvent` stream
+//! - `tick-prices`: Keys the book's price levels by `i64` ticks instead of `Decimal`
+//!   prices, converting only at the API boundary (see `OrderBookConfig::tick_size`)
+//! - `rayon`: Rebuilds the cache's aggregated depth from a book on the rayon thread
+//!   pool (`MarketDepthCache::par_rebuild_from`)
+//! - `rkyv`: Zero-copy archives of `BookSnapshot` and `DepthSnapshot` that can be
+//!   queried in place, e.g. memory-mapped (see the `archive` module)
+//! - `arrow`: Arrow record batches of depth snapshots and trades (see the `export`
+//!   module), and `parquet` to write them to Parquet files
+//! - `serde`: `Serialize` and `Deserialize` for `OrderEvent`, `Trade`, and `Side`
+//! - `msgpack` and `bincode`: Binary codecs for streaming events and trades, with
+//!   length-prefixed framing (see the `codec` module)
+//! - `sbe`: A Simple Binary Encoding codec with fixed message layouts for commands,
+//!   events, and trades (see the `sbe` module)
+//! - `redis`: Publishes top-of-book depth snapshots or deltas into Redis channels or
+//!   streams at a configurable cadence (see the `redis` module)
+//! - `rest`: HTTP endpoints serving a book's depth, top of book, and trades as JSON,
+//!   built on axum (see the `rest` module)
+//! - `root-reexports`: Re-exports `Decimal` and `RwLock` at the crate root, as earlier
+//!   versions did, for code that has not moved to the `prelude` yet
 
+mod book_view;
+mod broadcast_log;
+mod coalescing_buffer;
+mod depth_aggregator;
+mod depth_delta_publisher;
+mod depth_history;
+mod error;
+mod event_channel;
+mod fan_out;
+mod follower;
+mod histogram;
+mod implied;
+mod latency;
+mod level_map;
+mod liquidity;
 mod market_depth_cache;
+mod memory;
 mod order_book;
+mod order_flow_stats;
+mod price_key;
+mod price_level;
+mod price_watch;
+mod rng;
+mod shared;
+mod slab;
+mod spread_monitor;
+mod spread_tracker;
+mod throttle;
 mod types;
+mod units;
+mod update_notifier;
+
+#[cfg(feature = "rkyv")]
+pub mod archive;
+pub mod backtest;
+#[cfg(any(feature = "msgpack", feature = "bincode"))]
+pub mod codec;
+pub mod conformance;
+#[cfg(feature = "arrow")]
+pub mod export;
+pub mod feeds;
+pub mod io;
+pub mod latency_injection;
+pub mod multicast;
+pub mod prelude;
+pub mod recording;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "rest")]
+pub mod rest;
+#[cfg(feature = "sbe")]
+pub mod sbe;
+pub mod wal;
+pub mod wire;
+
+#[cfg(feature = "tui")]
+pub mod tui;
 
 // Re-export public API
+pub use book_view::{BookView, LevelIter};
+pub use broadcast_log::{BroadcastLog, Lapped, LogCursor};
+pub use coalescing_buffer::CoalescingBuffer;
+pub use depth_aggregator::DepthAggregator;
+pub use depth_delta_publisher::{DepthDeltaPublisher, DepthUpdate};
+pub use depth_history::{DepthFrame, DepthHistory, SamplingSchedule};
+pub use error::{OrderBookError, Result};
+pub use event_channel::{
+    event_channel, ChannelMessage, EventReceiver, EventSender, OverflowCounts, OverflowPolicy,
+};
+pub use fan_out::{BookObserver, EventFanOut, EventKind, SubscriptionId};
+pub use follower::FollowerBook;
+pub use histogram::Histogram;
+pub use implied::{ImpliedOrder, SpreadBook, SpreadMarket, SpreadMatchOutcome};
+pub use latency::{LatencyHistogram, LatencySnapshot};
 pub use market_depth_cache::MarketDepthCache;
-pub use order_book::OrderBook;
-pub use types::{AggregatedDepthMap, Order, OrderEvent, ExactPriceLevelMap, Side};
+pub use order_book::{LevelOrders, LevelView, OrderBook};
+pub use order_flow_stats::{DistanceBucket, DistanceStats, FlowCounts, OrderFlowStats};
+pub use shared::SharedOrderBook;
+pub use spread_monitor::{Crossing, SpreadAlert, SpreadMetric, SpreadMetrics, SpreadMonitor};
+pub use spread_tracker::{SpreadSample, SpreadTracker};
+pub use throttle::{Admission, RateLimit, Throttle, ThrottlePolicy};
+pub use types::{
+    AggregatedDepthMap, AggregatedLevelMap, AskPrice, Bbo, BboChanged, BidPrice, BookDiff,
+    BookSnapshot, BucketingPolicy, Checkpoint, ClientOrderId, Command, CompactionReport,
+    DepthSnapshot, DisplayQuantity, DriftReport, ExactPriceLevelMap, ExcessPrecision, FillSummary,
+    FloatPrecision, FloatRounding, IdGenerator, Impact, InsertOutcome, LevelAlert, LevelDiff,
+    LevelDrift, LevelInfo, LevelRemoved, LuldBands, MarketByOrderEvent, MarketDepthCacheConfig,
+    MatchOutcome, Order, OrderBookConfig, OrderEvent, OrderHandle, OrderId, OrderMetadata,
+    OrderRejected, OrderSpec, ParticipantId, PriceNormalization, PricePrecision, PriorityPolicy,
+    ProtectionTriggered, QueuePosition, QuoteOutcome, QuoteProtection, ReplaceOutcome,
+    ReplicationSnapshot, SequencePolicy, SequencedMarketByOrderEvent, SessionEvent, SessionSummary,
+    SettlementOutcome, Side, SubmitResult, Trade, TradeId, TradingState, TradingStateChange,
+};
+pub use units::{Price, Quantity};
 
-// Re-export commonly used external dependencies
+// Re-export commonly used external dependencies, which collide with the same imports
+// of dependent crates, only on request
+#[cfg(feature = "root-reexports")]
 pub use parking_lot::RwLock;
+#[cfg(feature = "root-reexports")]
 pub use rust_decimal::Decimal;
@@ -0,0 +1,22 @@
+use rust_decimal::Decimal;
+
+/// Accumulates price levels, given touch first, into a liquidity curve.
+///
+/// Each point is the distance between a level and the first (touch) level, paired with
+/// the quantity available up to and including that level.
+pub(crate) fn cumulative_curve(
+    levels: impl Iterator<Item = (Decimal, u64)>,
+    max_levels: usize,
+) -> Vec<(Decimal, u64)> {
+    let mut touch = None;
+    let mut cumulative_quantity = 0u64;
+
+    levels
+        .take(max_levels)
+        .map(|(price, quantity)| {
+            let touch = *touch.get_or_insert(price);
+            cumulative_quantity = cumulative_quantity.saturating_add(quantity);
+            ((price - touch).abs(), cumulative_quantity)
+        })
+        .collect()
+}
@@ -1,7 +1,22 @@
+use crate::fan_out::SubscriptionId;
+use crate::latency::{LatencyHistogram, LatencySnapshot};
+use crate::liquidity::cumulative_curve;
+use crate::memory::btree_map_bytes;
 use crate::order_book::OrderBook;
-use crate::types::{AggregatedDepthMap, OrderEvent, Side};
-use parking_lot::RwLock;
+use crate::price_watch::PriceWatches;
+use crate::types::{
+    unix_timestamp_nanos, AggregatedDepthMap, AggregatedLevelMap, BucketingPolicy, DepthSnapshot,
+    DriftReport, LevelAlert, LevelDrift, LevelInfo, MarketDepthCacheConfig, OrderEvent,
+    PriceNormalization, Side,
+};
+use crate::units::saturating_accumulate;
+use crate::update_notifier::UpdateNotifier;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// An external cache service that maintains aggregated market depth.
 ///
@@ -28,15 +43,51 @@ use std::collections::BTreeMap;
 /// across threads using `Arc<MarketDepthCache>`.
 #[derive(Debug)]
 pub struct MarketDepthCache {
-    /// Aggregated bid depth: maps aggregated price levels to total quantities
-    aggregated_bid_depth: RwLock<AggregatedDepthMap>,
-    /// Aggregated ask depth: maps aggregated price levels to total quantities
-    aggregated_ask_depth: RwLock<AggregatedDepthMap>,
+    /// Aggregated bid depth: maps aggregated price levels to total quantities and order counts
+    aggregated_bid_depth: RwLock<AggregatedLevelMap>,
+    /// Aggregated ask depth: maps aggregated price levels to total quantities and order counts
+    aggregated_ask_depth: RwLock<AggregatedLevelMap>,
+    /// Time between the creation of each processed event and its application
+    propagation_latency: LatencyHistogram,
+    /// How aggregated price levels are canonicalized before being stored
+    price_normalization: PriceNormalization,
+    /// How prices are assigned to aggregated levels
+    bucketing_policy: BucketingPolicy,
+    /// Maximum number of levels per side kept in the depth maps, if bounded
+    max_levels_per_side: Option<usize>,
+    /// Bid levels evicted for being too far from the touch
+    ///
+    /// Only locked while the bid depth's write lock is held, or by `clear`.
+    evicted_bid_depth: Mutex<AggregatedLevelMap>,
+    /// Ask levels evicted for being too far from the touch
+    ///
+    /// Only locked while the ask depth's write lock is held, or by `clear`.
+    evicted_ask_depth: Mutex<AggregatedLevelMap>,
+    /// Exact bid levels, if the cache was configured with `exact_levels`
+    ///
+    /// Only written while the bid depth's write lock is held, or by `clear`.
+    exact_bid_levels: Option<RwLock<AggregatedLevelMap>>,
+    /// Exact ask levels, if the cache was configured with `exact_levels`
+    ///
+    /// Only written while the ask depth's write lock is held, or by `clear`.
+    exact_ask_levels: Option<RwLock<AggregatedLevelMap>>,
+    /// Highest sequence number among the events applied so far
+    last_applied_sequence: AtomicU64,
+    /// Number of aggregated quantities and order counts saturated for leaving the
+    /// range of `u64`
+    aggregation_overflows: AtomicU64,
+    /// Counts the changes of the depth and wakes the threads waiting for one
+    updates: UpdateNotifier,
+    /// The levels watched with `watch_price` and their callbacks
+    price_watches: PriceWatches,
 }
 
 impl MarketDepthCache {
     /// Creates a new empty market depth cache.
     ///
+    /// The cache uses the default `MarketDepthCacheConfig`: it normalizes prices,
+    /// truncates them into aggregated levels, keeps every level, and no exact levels.
+    ///
     /// ## Examples
     ///
     /// ```
@@ -45,16 +96,99 @@ impl MarketDepthCache {
     /// let cache = MarketDepthCache::new();
     /// ```
     pub fn new() -> Self {
+        Self::with_config(MarketDepthCacheConfig::default())
+    }
+
+    /// Creates a new empty cache aggregating and bounding its depth as configured.
+    ///
+    /// The options of `MarketDepthCacheConfig` combine freely, e.g. a bounded cache can
+    /// also keep the exact levels and bucket prices by side.
+    ///
+    /// - `price_normalization`: Aggregated and exact levels are stored, and queried, in
+    ///   the representation the policy produces
+    /// - `bucketing_policy`: Under `BucketingPolicy::SideAware`, bids are rounded down
+    ///   and asks up, so the quantity of a level is always available at the level's
+    ///   price or better, e.g. a bid at 100.25 and an ask at 100.75 fall into the levels
+    ///   100 and 101 instead of both into 100
+    /// - `max_levels_per_side`: Levels beyond the limit, counted from the touch, are
+    ///   evicted from the depth maps, so snapshots, level counts, and curves only ever
+    ///   cover the closest levels, however many distinct levels a pathological feed
+    ///   produces. Evicted levels keep receiving their events in a compact side store
+    ///   and are restored, closest first, as soon as levels closer to the touch
+    ///   disappear; `get_quantity_at_level` still answers for them
+    /// - `exact_levels`: Next to the aggregated depth, the cache keeps the total quantity
+    ///   and order count of every exact price, so consumers needing exact level 2 data
+    ///   can read it from `exact_snapshot` and `get_exact_levels` instead of locking the
+    ///   book. Exact levels are never evicted, and each event then updates one more map,
+    ///   under the same lock
+    ///
+    /// ## Arguments
+    ///
+    /// * `config`: How the cache aggregates and bounds its depth
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{BucketingPolicy, MarketDepthCache, MarketDepthCacheConfig, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+    ///     bucketing_policy: BucketingPolicy::SideAware,
+    ///     max_levels_per_side: Some(2),
+    ///     exact_levels: true,
+    ///     ..MarketDepthCacheConfig::default()
+    /// });
+    ///
+    /// let touch = order_book.insert_order(Order::new(100.25, 10, Side::Bid));
+    /// cache.process_order_event(touch.event);
+    /// for price in [99.25, 98.25] {
+    ///     cache.process_order_event(order_book.insert_order(Order::new(price, 10, Side::Bid)).event);
+    /// }
+    /// assert_eq!(cache.bid_levels_count(), 2);
+    /// assert_eq!(cache.evicted_levels_count(Side::Bid), 1);
+    /// assert_eq!(cache.exact_snapshot().unwrap().bids.len(), 3);
+    ///
+    /// // Emptying the touch restores the evicted level
+    /// cache.process_order_event(order_book.cancel_by_handle(touch.handle).unwrap());
+    /// let (bid_depth, _) = cache.get_aggregated_market_depth();
+    /// assert_eq!(bid_depth.keys().next(), Some(&Decimal::new(98, 0)));
+    /// ```
+    pub fn with_config(config: MarketDepthCacheConfig) -> Self {
+        let exact_levels = || config.exact_levels.then(|| RwLock::new(BTreeMap::new()));
         MarketDepthCache {
             aggregated_bid_depth: RwLock::new(BTreeMap::new()),
             aggregated_ask_depth: RwLock::new(BTreeMap::new()),
+            propagation_latency: LatencyHistogram::new(),
+            price_normalization: config.price_normalization,
+            bucketing_policy: config.bucketing_policy,
+            max_levels_per_side: config.max_levels_per_side,
+            evicted_bid_depth: Mutex::new(BTreeMap::new()),
+            evicted_ask_depth: Mutex::new(BTreeMap::new()),
+            exact_bid_levels: exact_levels(),
+            exact_ask_levels: exact_levels(),
+            last_applied_sequence: AtomicU64::new(0),
+            aggregation_overflows: AtomicU64::new(0),
+            updates: UpdateNotifier::default(),
+            price_watches: PriceWatches::default(),
+        }
+    }
+
+    /// Returns how the cache aggregates and bounds its depth.
+    pub fn config(&self) -> MarketDepthCacheConfig {
+        MarketDepthCacheConfig {
+            price_normalization: self.price_normalization,
+            bucketing_policy: self.bucketing_policy,
+            max_levels_per_side: self.max_levels_per_side,
+            exact_levels: self.exact_bid_levels.is_some(),
         }
     }
 
     /// Processes an order event and updates the aggregated market depth.
     ///
-    /// This method is called after an order is inserted into the order book.
-    /// It aggregates the order price to its level and updates the cached quantity.
+    /// This method is called after an order is inserted into or removed from the order book.
+    /// It aggregates the order price to its level and updates the cached quantity and
+    /// order count, dropping the level once its quantity reaches zero.
     ///
     /// The operation is $O(\log{N})$ where $N$ is the number of aggregated price levels.
     /// The lock is held only for the duration of the `BTreeMap` update.
@@ -74,23 +208,371 @@ impl MarketDepthCache {
     ///
     /// let order = Order::new(100.50, 100, Side::Bid);
     ///
-    /// let event = order_book.insert_order(order);
+    /// let event = order_book.insert_order(order).event;
     /// cache.process_order_event(event);
     /// ```
     pub fn process_order_event(&self, event: OrderEvent) {
-        // Aggregate the price to its level using the core book's logic
-        let aggregated_price_level = OrderBook::aggregate_price_to_level(event.price);
+        self.apply_order_event(&event);
+    }
+
+    /// Applies an event and returns the affected aggregated level with its new quantity.
+    ///
+    /// A new quantity of zero means the level has been removed.
+    pub(crate) fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64) {
+        let aggregated_price_level = self.aggregated_level(event.price, event.side);
 
         // Select the appropriate depth map based on side
-        let mut depth_write_lock = match event.side {
-            Side::Bid => self.aggregated_bid_depth.write(),
-            Side::Ask => self.aggregated_ask_depth.write(),
+        let (mut depth_write_lock, evicted_depth) = match event.side {
+            Side::Bid => (self.aggregated_bid_depth.write(), &self.evicted_bid_depth),
+            Side::Ask => (self.aggregated_ask_depth.write(), &self.evicted_ask_depth),
+        };
+
+        let new_quantity = self.apply_locked(
+            &mut depth_write_lock,
+            evicted_depth,
+            aggregated_price_level,
+            event,
+        );
+
+        drop(depth_write_lock);
+        self.last_applied_sequence
+            .fetch_max(event.sequence, Ordering::Relaxed);
+
+        // Events without a creation time cannot be aged
+        if event.timestamp_nanos != 0 {
+            let age_nanos = unix_timestamp_nanos().saturating_sub(event.timestamp_nanos);
+            self.propagation_latency.record(age_nanos);
+        }
+        self.updates.notify();
+        self.price_watches
+            .level_changed(event.side, aggregated_price_level, new_quantity);
+
+        (aggregated_price_level, new_quantity)
+    }
+
+    /// Sets the absolute quantity of a level, as exchange-style level 2 feeds do.
+    ///
+    /// This drives the cache from venues whose updates carry the new quantity of a
+    /// price rather than a delta from a local book. A quantity of zero deletes the
+    /// level. The cache converts the update into a delta from the level's current
+    /// quantity and applies it like an event:
+    ///
+    /// - With `exact_levels` configured, `price` is an exact price: its exact level is set,
+    ///   and its aggregated level moves by the difference, so several prices of a feed
+    ///   can share a bucket
+    /// - Otherwise, the aggregated level containing `price` is set, which suits feeds
+    ///   whose levels already are the cache's buckets
+    ///
+    /// Order counts are left unchanged, as such feeds do not carry them, and the last
+    /// applied sequence and the propagation latency are not affected. A change too
+    /// large for the `i64` of `OrderEvent::quantity_delta` is saturated to it and counted
+    /// in `aggregation_overflow_count`, so the level then falls short of `quantity`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The price of the level
+    /// * `quantity`: The level's new total quantity
+    /// * `side`: The side (bid or ask) of the level
+    ///
+    /// ## Returns
+    ///
+    /// The delta applied, as an event without timestamp or sequence number that can be
+    /// forwarded to other consumers, or `None` if the level already had this quantity
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, MarketDepthCacheConfig, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+    ///     exact_levels: true,
+    ///     ..MarketDepthCacheConfig::default()
+    /// });
+    /// cache.apply_absolute_level(Decimal::new(10025, 2), 10, Side::Ask);
+    /// cache.apply_absolute_level(Decimal::new(10075, 2), 4, Side::Ask);
+    ///
+    /// let event = cache.apply_absolute_level(Decimal::new(10025, 2), 6, Side::Ask).unwrap();
+    /// assert_eq!(event.quantity_delta, -4);
+    /// assert_eq!(cache.get_quantity_at_level(Decimal::new(100, 0), Side::Ask), 10);
+    ///
+    /// cache.apply_absolute_level(Decimal::new(10075, 2), 0, Side::Ask);
+    /// assert_eq!(cache.get_quantity_at_level(Decimal::new(100, 0), Side::Ask), 6);
+    /// assert_eq!(cache.apply_absolute_level(Decimal::new(10075, 2), 0, Side::Ask), None);
+    /// ```
+    pub fn apply_absolute_level(
+        &self,
+        price: Decimal,
+        quantity: u64,
+        side: Side,
+    ) -> Option<OrderEvent> {
+        let aggregated_price_level = self.aggregated_level(price, side);
+        let (mut depth_write_lock, evicted_depth) = match side {
+            Side::Bid => (self.aggregated_bid_depth.write(), &self.evicted_bid_depth),
+            Side::Ask => (self.aggregated_ask_depth.write(), &self.evicted_ask_depth),
+        };
+
+        let current_quantity = match self.exact_levels(side) {
+            Some(exact_levels) => exact_levels
+                .read()
+                .get(&self.price_normalization.apply(price))
+                .map_or(0, |level_info| level_info.quantity),
+            None => depth_write_lock
+                .get(&aggregated_price_level)
+                .or(evicted_depth.lock().get(&aggregated_price_level))
+                .map_or(0, |level_info| level_info.quantity),
+        };
+        if current_quantity == quantity {
+            return None;
+        }
+
+        let delta = i128::from(quantity) - i128::from(current_quantity);
+        let quantity_delta = i64::try_from(delta).unwrap_or_else(|_| {
+            self.aggregation_overflows.fetch_add(1, Ordering::Relaxed);
+            if delta > 0 {
+                i64::MAX
+            } else {
+                i64::MIN
+            }
+        });
+        let event = OrderEvent {
+            timestamp_nanos: 0,
+            ..OrderEvent::new(price, quantity_delta, side)
+        };
+        let new_quantity = self.apply_locked(
+            &mut depth_write_lock,
+            evicted_depth,
+            aggregated_price_level,
+            &event,
+        );
+        drop(depth_write_lock);
+        self.updates.notify();
+        self.price_watches
+            .level_changed(side, aggregated_price_level, new_quantity);
+        Some(event)
+    }
+
+    /// Applies an event to the depth of its side, whose write lock the caller holds, and
+    /// to the exact levels, returning the aggregated level's new quantity.
+    fn apply_locked(
+        &self,
+        depth: &mut AggregatedLevelMap,
+        evicted_depth: &Mutex<AggregatedLevelMap>,
+        aggregated_price_level: Decimal,
+        event: &OrderEvent,
+    ) -> u64 {
+        let new_quantity = match self.max_levels_per_side {
+            None => apply_delta(
+                depth,
+                aggregated_price_level,
+                event,
+                &self.aggregation_overflows,
+            ),
+            Some(max_levels_per_side) => apply_bounded_delta(
+                depth,
+                &mut evicted_depth.lock(),
+                max_levels_per_side,
+                aggregated_price_level,
+                event,
+                &self.aggregation_overflows,
+            ),
+        };
+        if let Some(exact_levels) = self.exact_levels(event.side) {
+            let exact_price = self.price_normalization.apply(event.price);
+            apply_delta(
+                &mut exact_levels.write(),
+                exact_price,
+                event,
+                &self.aggregation_overflows,
+            );
+        }
+        new_quantity
+    }
+
+    /// Replaces the cached depth with a full re-aggregation of the book's resting orders.
+    ///
+    /// This recovers a cache that was cleared or fell out of sync with the book, e.g.
+    /// after a gap in its event stream. The book's exact levels are aggregated without
+    /// holding the cache's locks, and each side is then swapped in as a whole, so
+    /// readers see either the old or the new depth. Latency samples are kept, and the
+    /// last applied sequence becomes the book's `last_event_sequence`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book to aggregate, which should not change meanwhile
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    ///
+    /// // Events published while nobody was listening are recovered by the rebuild
+    /// order_book.insert_order(Order::new(100.25, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(100.75, 5, Side::Bid));
+    /// cache.rebuild_from(&order_book);
+    ///
+    /// assert_eq!(cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid), 15);
+    /// ```
+    pub fn rebuild_from(&self, order_book: &OrderBook) {
+        for side in [Side::Bid, Side::Ask] {
+            let mut depth = AggregatedLevelMap::new();
+            for (price, level_info) in order_book.level_infos(side) {
+                add_level(
+                    &mut depth,
+                    self.aggregated_level(price, side),
+                    level_info,
+                    &self.aggregation_overflows,
+                );
+            }
+            self.install_depth(side, depth, self.exact_levels_of(order_book, side));
+        }
+        self.last_applied_sequence
+            .store(order_book.last_event_sequence(), Ordering::Relaxed);
+        self.updates.notify();
+        self.report_watched_levels();
+    }
+
+    /// Replaces the cached depth like `rebuild_from`, aggregating in parallel.
+    ///
+    /// Only available with the `rayon` feature. Each side's exact levels are split into
+    /// contiguous price ranges, which are aggregated on the rayon thread pool into
+    /// partial maps; since the ranges are contiguous, partial maps only overlap on the
+    /// aggregated levels at range boundaries, whose quantities and order counts are
+    /// summed when merging. This cuts the recovery time of large books.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book to aggregate, which should not change meanwhile
+    #[cfg(feature = "rayon")]
+    pub fn par_rebuild_from(&self, order_book: &OrderBook) {
+        use rayon::prelude::*;
+
+        // Ranges smaller than this are not worth handing to another thread
+        const MIN_LEVELS_PER_RANGE: usize = 1_024;
+
+        let aggregate_side = |side| {
+            let levels: Vec<(Decimal, LevelInfo)> = order_book.level_infos(side).collect();
+            let range_length = levels
+                .len()
+                .div_ceil(rayon::current_num_threads())
+                .max(MIN_LEVELS_PER_RANGE);
+
+            levels
+                .par_chunks(range_length)
+                .map(|range| {
+                    let mut depth = AggregatedLevelMap::new();
+                    for (price, level_info) in range {
+                        add_level(
+                            &mut depth,
+                            self.aggregated_level(*price, side),
+                            *level_info,
+                            &self.aggregation_overflows,
+                        );
+                    }
+                    depth
+                })
+                .reduce(AggregatedLevelMap::new, |depth, other| {
+                    merge_depth(depth, other, &self.aggregation_overflows)
+                })
         };
 
-        // Update the aggregated quantity at this level
-        *depth_write_lock.entry(aggregated_price_level).or_insert(0) += event.quantity_delta;
+        let (bid_depth, ask_depth) =
+            rayon::join(|| aggregate_side(Side::Bid), || aggregate_side(Side::Ask));
+        self.install_depth(
+            Side::Bid,
+            bid_depth,
+            self.exact_levels_of(order_book, Side::Bid),
+        );
+        self.install_depth(
+            Side::Ask,
+            ask_depth,
+            self.exact_levels_of(order_book, Side::Ask),
+        );
+        self.last_applied_sequence
+            .store(order_book.last_event_sequence(), Ordering::Relaxed);
+        self.updates.notify();
+        self.report_watched_levels();
+    }
+
+    /// Returns the aggregated level of a price on a side, canonicalized like the stored
+    /// levels.
+    fn aggregated_level(&self, price: Decimal, side: Side) -> Decimal {
+        self.price_normalization
+            .apply(self.bucketing_policy.level(price, side))
+    }
+
+    /// Reports the current quantity of every watched level to its watches, after the
+    /// depth was replaced as a whole.
+    fn report_watched_levels(&self) {
+        self.price_watches
+            .all_changed(|side, price| self.get_quantity_at_level(price, side));
+    }
+
+    /// Returns the exact levels of one side, if the cache maintains them.
+    fn exact_levels(&self, side: Side) -> Option<&RwLock<AggregatedLevelMap>> {
+        match side {
+            Side::Bid => self.exact_bid_levels.as_ref(),
+            Side::Ask => self.exact_ask_levels.as_ref(),
+        }
+    }
+
+    /// Copies the exact levels of one side of a book, if the cache maintains them.
+    fn exact_levels_of(&self, order_book: &OrderBook, side: Side) -> Option<AggregatedLevelMap> {
+        self.exact_levels(side)?;
+        let mut exact_levels = AggregatedLevelMap::new();
+        for (price, level_info) in order_book.level_infos(side) {
+            add_level(
+                &mut exact_levels,
+                self.price_normalization.apply(price),
+                level_info,
+                &self.aggregation_overflows,
+            );
+        }
+        Some(exact_levels)
+    }
+
+    /// Swaps in the full depth of one side, evicting the levels beyond the limit, along
+    /// with its exact levels.
+    fn install_depth(
+        &self,
+        side: Side,
+        mut depth: AggregatedLevelMap,
+        exact: Option<AggregatedLevelMap>,
+    ) {
+        let (mut depth_write_lock, evicted_depth) = match side {
+            Side::Bid => (self.aggregated_bid_depth.write(), &self.evicted_bid_depth),
+            Side::Ask => (self.aggregated_ask_depth.write(), &self.evicted_ask_depth),
+        };
 
-        // Lock is automatically released here
+        let mut evicted = AggregatedLevelMap::new();
+        if let Some(max_levels) = self.max_levels_per_side {
+            if depth.len() > max_levels {
+                // The kept levels are the highest bids or the lowest asks
+                match side {
+                    Side::Bid => {
+                        let kept = match depth.keys().nth(depth.len() - max_levels).copied() {
+                            Some(first_kept) => depth.split_off(&first_kept),
+                            None => AggregatedLevelMap::new(),
+                        };
+                        evicted = std::mem::replace(&mut depth, kept);
+                    }
+                    Side::Ask => {
+                        let first_evicted = *depth.keys().nth(max_levels).expect("depth is full");
+                        evicted = depth.split_off(&first_evicted);
+                    }
+                }
+            }
+        }
+
+        *depth_write_lock = depth;
+        *evicted_depth.lock() = evicted;
+        if let (Some(exact_levels), Some(exact)) = (self.exact_levels(side), exact) {
+            *exact_levels.write() = exact;
+        }
     }
 
     /// Retrieves a snapshot of the current aggregated market depth.
@@ -118,21 +600,174 @@ impl MarketDepthCache {
     ///
     /// let order = Order::new(100.50, 100, Side::Bid);
     ///
-    /// let event = order_book.insert_order(order);
+    /// let event = order_book.insert_order(order).event;
     /// cache.process_order_event(event);
     ///
     /// let (bid_depth, ask_depth) = cache.get_aggregated_market_depth();
     /// assert_eq!(bid_depth.get(&Decimal::new(100, 0)), Some(&100));
     /// ```
     pub fn get_aggregated_market_depth(&self) -> (AggregatedDepthMap, AggregatedDepthMap) {
-        // Acquire read locks and clone the maps
-        let bid_depth_snapshot = self.aggregated_bid_depth.read().clone();
-        let ask_depth_snapshot = self.aggregated_ask_depth.read().clone();
+        // Acquire read locks and copy the quantities
+        let bid_depth_snapshot = quantities(&self.aggregated_bid_depth.read());
+        let ask_depth_snapshot = quantities(&self.aggregated_ask_depth.read());
 
         // Read locks are automatically released here
         (bid_depth_snapshot, ask_depth_snapshot)
     }
 
+    /// Retrieves a snapshot of the current aggregated levels with their order counts.
+    ///
+    /// This is `get_aggregated_market_depth` for consumers that also display or analyze
+    /// the number of orders per level.
+    ///
+    /// ## Returns
+    ///
+    /// A tuple of `(bid_levels, ask_levels)` where each is an `AggregatedLevelMap`
+    /// mapping aggregated price levels to their total quantity and order count.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{LevelInfo, MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// for price in [100.25, 100.75] {
+    ///     cache.process_order_event(order_book.insert_order(Order::new(price, 10, Side::Bid)).event);
+    /// }
+    ///
+    /// let (bid_levels, _) = cache.get_aggregated_levels();
+    /// assert_eq!(
+    ///     bid_levels.get(&Decimal::new(100, 0)),
+    ///     Some(&LevelInfo { quantity: 20, order_count: 2 })
+    /// );
+    /// ```
+    pub fn get_aggregated_levels(&self) -> (AggregatedLevelMap, AggregatedLevelMap) {
+        let bid_levels_snapshot = self.aggregated_bid_depth.read().clone();
+        let ask_levels_snapshot = self.aggregated_ask_depth.read().clone();
+        (bid_levels_snapshot, ask_levels_snapshot)
+    }
+
+    /// Takes a snapshot of the aggregated depth of both sides, with the sequence number
+    /// it reflects.
+    ///
+    /// Both sides are copied while holding both read locks, so the snapshot is
+    /// consistent across sides, unlike two separate queries.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// cache.process_order_event(order_book.insert_order(Order::new(100.50, 10, Side::Bid)).event);
+    ///
+    /// let snapshot = cache.snapshot();
+    /// assert_eq!(snapshot.sequence, 1);
+    /// assert_eq!(snapshot.bids.get(&Decimal::new(100, 0)), Some(&10));
+    /// assert!(snapshot.asks.is_empty());
+    /// ```
+    pub fn snapshot(&self) -> DepthSnapshot {
+        let bids = self.aggregated_bid_depth.read();
+        let asks = self.aggregated_ask_depth.read();
+        DepthSnapshot {
+            sequence: self.last_applied_sequence(),
+            bids: quantities(&bids),
+            asks: quantities(&asks),
+        }
+    }
+
+    /// Takes a snapshot of the exact levels of both sides, with the sequence number it
+    /// reflects.
+    ///
+    /// This is `snapshot` without aggregation: each level is an exact price with the
+    /// total quantity resting at it. Both sides are copied while holding both read
+    /// locks.
+    ///
+    /// ## Returns
+    ///
+    /// The snapshot, or `None` if the cache was not configured with `exact_levels`
+    pub fn exact_snapshot(&self) -> Option<DepthSnapshot> {
+        let bids = self.exact_bid_levels.as_ref()?.read();
+        let asks = self.exact_ask_levels.as_ref()?.read();
+        Some(DepthSnapshot {
+            sequence: self.last_applied_sequence(),
+            bids: quantities(&bids),
+            asks: quantities(&asks),
+        })
+    }
+
+    /// Retrieves a snapshot of the exact levels with their order counts.
+    ///
+    /// ## Returns
+    ///
+    /// A tuple of `(bid_levels, ask_levels)` mapping exact prices to their total
+    /// quantity and order count, or `None` if the cache was not configured with
+    /// `exact_levels`
+    pub fn get_exact_levels(&self) -> Option<(AggregatedLevelMap, AggregatedLevelMap)> {
+        let bid_levels_snapshot = self.exact_bid_levels.as_ref()?.read().clone();
+        let ask_levels_snapshot = self.exact_ask_levels.as_ref()?.read().clone();
+        Some((bid_levels_snapshot, ask_levels_snapshot))
+    }
+
+    /// Copies the `n` levels closest to the touch on each side into caller-provided buffers.
+    ///
+    /// The buffers are cleared first and then filled, best level first, so a poller
+    /// reusing the same buffers performs no allocation once they have grown to `n`
+    /// entries. Each side is copied under its own read lock.
+    ///
+    /// ## Arguments
+    ///
+    /// * `bids`: Receives up to `n` `(price_level, quantity)` bid levels, highest first
+    /// * `asks`: Receives up to `n` `(price_level, quantity)` ask levels, lowest first
+    /// * `n`: The maximum number of levels to copy per side
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// for price in [99.50, 98.50, 97.50] {
+    ///     cache.process_order_event(order_book.insert_order(Order::new(price, 10, Side::Bid)).event);
+    /// }
+    ///
+    /// let (mut bids, mut asks) = (Vec::with_capacity(2), Vec::with_capacity(2));
+    /// cache.copy_top_levels_into(&mut bids, &mut asks, 2);
+    /// assert_eq!(bids, vec![(Decimal::new(99, 0), 10), (Decimal::new(98, 0), 10)]);
+    /// assert!(asks.is_empty());
+    /// ```
+    pub fn copy_top_levels_into(
+        &self,
+        bids: &mut Vec<(Decimal, u64)>,
+        asks: &mut Vec<(Decimal, u64)>,
+        n: usize,
+    ) {
+        bids.clear();
+        bids.extend(
+            self.aggregated_bid_depth
+                .read()
+                .iter()
+                .rev()
+                .take(n)
+                .map(|(price, level_info)| (*price, level_info.quantity)),
+        );
+
+        asks.clear();
+        asks.extend(
+            self.aggregated_ask_depth
+                .read()
+                .iter()
+                .take(n)
+                .map(|(price, level_info)| (*price, level_info.quantity)),
+        );
+    }
+
     /// Returns the total quantity at a specific aggregated price level.
     ///
     /// ## Arguments
@@ -155,23 +790,449 @@ impl MarketDepthCache {
     ///
     /// let order = Order::new(100.50, 100, Side::Bid);
     ///
-    /// let event = order_book.insert_order(order);
+    /// let event = order_book.insert_order(order).event;
     /// cache.process_order_event(event);
     ///
     /// let quantity = cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid);
     /// assert_eq!(quantity, 100);
     /// ```
-    pub fn get_quantity_at_level(
-        &self,
-        aggregated_level: rust_decimal::Decimal,
-        side: Side,
-    ) -> u64 {
+    pub fn get_quantity_at_level(&self, aggregated_level: Decimal, side: Side) -> u64 {
+        self.get_level_info(aggregated_level, side).quantity
+    }
+
+    /// Returns the total quantity and order count at a specific aggregated price level.
+    ///
+    /// ## Arguments
+    ///
+    /// * `aggregated_level`: The aggregated price level to query
+    /// * `side`: The side (bid or ask) to query
+    ///
+    /// ## Returns
+    ///
+    /// The level's quantity and order count, both 0 if no orders exist
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    ///
+    /// let order_id = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).handle.order_id();
+    /// cache.process_order_event(order_book.insert_order(Order::new(100.25, 50, Side::Bid)).event);
+    /// cache.process_order_event(order_book.reduce_order(order_id, 30).unwrap());
+    ///
+    /// // Reductions change the quantity only
+    /// let level_info = cache.get_level_info(Decimal::new(100, 0), Side::Bid);
+    /// assert_eq!((level_info.quantity, level_info.order_count), (20, 1));
+    /// ```
+    pub fn get_level_info(&self, aggregated_level: Decimal, side: Side) -> LevelInfo {
         let depth_read_lock = match side {
             Side::Bid => self.aggregated_bid_depth.read(),
             Side::Ask => self.aggregated_ask_depth.read(),
         };
 
-        depth_read_lock.get(&aggregated_level).copied().unwrap_or(0)
+        let aggregated_level = self.price_normalization.apply(aggregated_level);
+        if let Some(level_info) = depth_read_lock.get(&aggregated_level) {
+            return *level_info;
+        }
+
+        // The level may have been evicted for being too far from the touch
+        let evicted_depth = match side {
+            Side::Bid => &self.evicted_bid_depth,
+            Side::Ask => &self.evicted_ask_depth,
+        };
+        evicted_depth
+            .lock()
+            .get(&aggregated_level)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the cumulative aggregated quantity versus price distance from the touch.
+    ///
+    /// This is the cache-side counterpart of `OrderBook::liquidity_curve`, computed on
+    /// aggregated levels so it can be served without touching the order book.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) whose liquidity is measured
+    /// * `max_levels`: The maximum number of aggregated levels to include
+    ///
+    /// ## Returns
+    ///
+    /// One `(distance, cumulative_quantity)` point per level, closest level first
+    pub fn liquidity_curve(&self, side: Side, max_levels: usize) -> Vec<(Decimal, u64)> {
+        match side {
+            Side::Bid => {
+                let depth_read_lock = self.aggregated_bid_depth.read();
+                let levels = depth_read_lock.iter().rev();
+                cumulative_curve(
+                    levels.map(|(price, level_info)| (*price, level_info.quantity)),
+                    max_levels,
+                )
+            }
+            Side::Ask => {
+                let depth_read_lock = self.aggregated_ask_depth.read();
+                let levels = depth_read_lock.iter();
+                cumulative_curve(
+                    levels.map(|(price, level_info)| (*price, level_info.quantity)),
+                    max_levels,
+                )
+            }
+        }
+    }
+
+    /// Compares one side of the cache with the venue's snapshot of the same side, e.g.
+    /// to alert when a book mirrored from a feed drifts from the venue's.
+    ///
+    /// The snapshot's prices are normalized like the cache's before the comparison, and
+    /// levels evicted under `max_levels_per_side` count as held by the cache.
+    ///
+    /// ## Arguments
+    ///
+    /// * `exchange_snapshot`: The venue's quantities by aggregated price level
+    /// * `side`: The side the snapshot describes
+    ///
+    /// ## Returns
+    ///
+    /// A `DriftReport` with the levels that differ, best first, and the totals of both
+    /// sides of the comparison
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{AggregatedDepthMap, MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// cache.process_order_event(order_book.insert_order(Order::new(100.00, 10, Side::Bid)).event);
+    /// cache.process_order_event(order_book.insert_order(Order::new(99.00, 5, Side::Bid)).event);
+    ///
+    /// // The venue reports 8 at the touch and nothing at 99
+    /// let exchange_snapshot = AggregatedDepthMap::from([(Decimal::new(100, 0), 8)]);
+    /// let report = cache.drift_report(&exchange_snapshot, Side::Bid);
+    /// assert_eq!(report.levels.len(), 2);
+    /// assert_eq!(report.levels[0].price, Decimal::new(100, 0));
+    /// assert_eq!(report.absolute_difference, 7);
+    /// assert!(report.drift_ratio() > 0.5);
+    /// ```
+    pub fn drift_report(&self, exchange_snapshot: &AggregatedDepthMap, side: Side) -> DriftReport {
+        let (depth, evicted_depth) = match side {
+            Side::Bid => (&self.aggregated_bid_depth, &self.evicted_bid_depth),
+            Side::Ask => (&self.aggregated_ask_depth, &self.evicted_ask_depth),
+        };
+        let cached = {
+            let depth_read_lock = depth.read();
+            let mut cached = quantities(&depth_read_lock);
+            cached.extend(quantities(&evicted_depth.lock()));
+            cached
+        };
+        let mut exchange = AggregatedDepthMap::new();
+        for (price, quantity) in exchange_snapshot {
+            let level = exchange
+                .entry(self.price_normalization.apply(*price))
+                .or_insert(0);
+            *level = level.saturating_add(*quantity);
+        }
+
+        let mut prices: Vec<Decimal> = cached.keys().chain(exchange.keys()).copied().collect();
+        prices.sort_unstable();
+        prices.dedup();
+        if side == Side::Bid {
+            prices.reverse();
+        }
+        let mut levels = Vec::new();
+        for &price in &prices {
+            let level = LevelDrift {
+                price,
+                cached_quantity: cached.get(&price).copied().unwrap_or(0),
+                exchange_quantity: exchange.get(&price).copied().unwrap_or(0),
+            };
+            if level.cached_quantity != level.exchange_quantity {
+                levels.push(level);
+            }
+        }
+
+        let total = |quantities: &AggregatedDepthMap| {
+            quantities
+                .values()
+                .fold(0u64, |total, quantity| total.saturating_add(*quantity))
+        };
+        DriftReport {
+            side,
+            compared_levels: prices.len(),
+            cached_quantity: total(&cached),
+            exchange_quantity: total(&exchange),
+            absolute_difference: levels.iter().fold(0, |total, level| {
+                total.saturating_add(level.absolute_difference())
+            }),
+            levels,
+        }
+    }
+
+    /// Registers a callback fired when the quantity of the level containing a price
+    /// changes by at least `threshold`, so a strategy can follow the levels it cares
+    /// about instead of diffing snapshots.
+    ///
+    /// The price is assigned to its aggregated level like an event's. The callback
+    /// receives a `LevelAlert` with the quantity when it last fired, or when it was
+    /// registered, and the new one; small changes accumulate until they reach the
+    /// threshold, and a threshold of 0 fires on every change. Rebuilding or clearing the
+    /// cache reports the watched levels' new quantities too. Callbacks run on the thread
+    /// updating the cache, once its locks are released, so they may query the cache or
+    /// register and remove watches; they should be quick, as they delay the next update.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: A price of the level to watch
+    /// * `side`: The side (bid or ask) of the level
+    /// * `threshold`: The smallest change of quantity reported
+    /// * `callback`: What to call with the changes
+    ///
+    /// ## Returns
+    ///
+    /// The identifier of the watch, to pass to `unwatch_price`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// let alerts = Arc::new(Mutex::new(Vec::new()));
+    /// let seen = Arc::clone(&alerts);
+    /// cache.watch_price(Decimal::new(100, 0), Side::Bid, 50, move |alert| {
+    ///     seen.lock().unwrap().push(alert.quantity);
+    /// });
+    ///
+    /// for _ in 0..3 {
+    ///     cache.process_order_event(order_book.insert_order(Order::new(100.25, 20, Side::Bid)).event);
+    /// }
+    /// // The level grew by 20, 40, then 60 since the watch started: only the last fires
+    /// assert_eq!(*alerts.lock().unwrap(), vec![60]);
+    /// ```
+    pub fn watch_price(
+        &self,
+        price: Decimal,
+        side: Side,
+        threshold: u64,
+        callback: impl Fn(&LevelAlert) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let aggregated_price_level = self.aggregated_level(price, side);
+        self.price_watches.watch(
+            side,
+            aggregated_price_level,
+            threshold,
+            self.get_quantity_at_level(aggregated_price_level, side),
+            Arc::new(callback),
+        )
+    }
+
+    /// Removes a watch registered with `watch_price`.
+    ///
+    /// ## Returns
+    ///
+    /// `true` if the watch was registered
+    pub fn unwatch_price(&self, watch_id: SubscriptionId) -> bool {
+        self.price_watches.unwatch(watch_id)
+    }
+
+    /// Returns the number of watches registered with `watch_price`.
+    pub fn watched_price_count(&self) -> usize {
+        self.price_watches.len()
+    }
+
+    /// Copies out up to `max_levels` aggregated levels of one side and their quantities,
+    /// best first.
+    pub(crate) fn top_levels_of(&self, side: Side, max_levels: usize) -> Vec<(Decimal, u64)> {
+        let quantities =
+            |(price, level_info): (&Decimal, &LevelInfo)| (*price, level_info.quantity);
+        match side {
+            Side::Bid => {
+                let depth_read_lock = self.aggregated_bid_depth.read();
+                depth_read_lock
+                    .iter()
+                    .rev()
+                    .take(max_levels)
+                    .map(quantities)
+                    .collect()
+            }
+            Side::Ask => {
+                let depth_read_lock = self.aggregated_ask_depth.read();
+                depth_read_lock
+                    .iter()
+                    .take(max_levels)
+                    .map(quantities)
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the highest sequence number among the events applied so far, or 0 if none.
+    ///
+    /// Events that were not published by a book carry no sequence number and leave it
+    /// unchanged. When events are applied by a single consumer in the order they were
+    /// published, every event up to this sequence number is reflected in the depth.
+    pub fn last_applied_sequence(&self) -> u64 {
+        self.last_applied_sequence.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of changes of the depth so far.
+    ///
+    /// Every applied event, absolute level, rebuild, and clear counts as one change. A
+    /// consumer reads the count before reading the depth, then passes it to
+    /// `wait_for_update_since` to sleep until the depth it read is stale.
+    pub fn update_count(&self) -> u64 {
+        self.updates.update_count()
+    }
+
+    /// Blocks until the depth changes, or `timeout` elapses.
+    ///
+    /// Changes made before the call do not wake it, so a poller alternating between
+    /// reading the depth and calling this can miss a change made in between; use
+    /// `update_count` and `wait_for_update_since` when that matters.
+    ///
+    /// ## Arguments
+    ///
+    /// * `timeout`: The longest time to wait
+    ///
+    /// ## Returns
+    ///
+    /// Whether the depth changed
+    pub fn wait_for_update(&self, timeout: Duration) -> bool {
+        self.wait_for_update_since(self.update_count(), timeout)
+            .is_some()
+    }
+
+    /// Blocks until the depth has changed since `update_count` returned `seen_update_count`,
+    /// or `timeout` elapses.
+    ///
+    /// This returns immediately if it already changed, so no change is missed between
+    /// reading the depth and waiting, and pollers wake exactly when there is something
+    /// new to read instead of spinning on the depth. Applying events only pays for the
+    /// wake-up while a thread is waiting.
+    ///
+    /// ## Arguments
+    ///
+    /// * `seen_update_count`: The update count when the depth was last read
+    /// * `timeout`: The longest time to wait
+    ///
+    /// ## Returns
+    ///
+    /// The new update count, or `None` if the depth did not change in time
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let cache = Arc::new(MarketDepthCache::new());
+    /// let seen_update_count = cache.update_count();
+    ///
+    /// let writer = Arc::clone(&cache);
+    /// let handle = std::thread::spawn(move || {
+    ///     let mut order_book = OrderBook::new();
+    ///     writer.process_order_event(order_book.insert_order(Order::new(100.00, 10, Side::Bid)).event);
+    /// });
+    ///
+    /// let update_count = cache.wait_for_update_since(seen_update_count, Duration::from_secs(10));
+    /// assert!(update_count.is_some());
+    /// assert_eq!(cache.bid_levels_count(), 1);
+    /// handle.join().unwrap();
+    /// ```
+    pub fn wait_for_update_since(&self, seen_update_count: u64, timeout: Duration) -> Option<u64> {
+        self.updates.wait_since(seen_update_count, timeout)
+    }
+
+    /// Blocks until the event with the given sequence number has been applied, or
+    /// `timeout` elapses.
+    ///
+    /// ## Arguments
+    ///
+    /// * `sequence`: The sequence number of the awaited event
+    /// * `timeout`: The longest time to wait
+    ///
+    /// ## Returns
+    ///
+    /// Whether `last_applied_sequence` reached `sequence`
+    pub fn wait_for_sequence(&self, sequence: u64, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let update_count = self.update_count();
+            if self.last_applied_sequence() >= sequence {
+                return true;
+            }
+            let remaining = deadline.map_or(Duration::MAX, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if self
+                .wait_for_update_since(update_count, remaining)
+                .is_none()
+            {
+                return self.last_applied_sequence() >= sequence;
+            }
+        }
+    }
+
+    /// Returns how many times an aggregated quantity or order count left the range of
+    /// `u64`, or a level change the range of `i64`, and was saturated instead.
+    ///
+    /// Totals are saturated rather than wrapped, so a feed whose quantities overflow,
+    /// or that removes more than it added, distorts the affected levels without
+    /// aborting; a nonzero count tells that the depth can no longer be trusted and
+    /// should be rebuilt. Clearing the cache does not reset the count.
+    pub fn aggregation_overflow_count(&self) -> u64 {
+        self.aggregation_overflows.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many events the cache is behind a book that has published up to
+    /// `book_sequence`.
+    ///
+    /// Monitoring can poll this with `OrderBook::last_event_sequence` and alert when the
+    /// cache falls behind during bursts.
+    ///
+    /// ## Arguments
+    ///
+    /// * `book_sequence`: The book's `last_event_sequence`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    ///
+    /// let first = order_book.insert_order(Order::new(100.00, 10, Side::Bid)).event;
+    /// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    /// cache.process_order_event(first);
+    ///
+    /// assert_eq!(cache.last_applied_sequence(), 1);
+    /// assert_eq!(cache.lag(order_book.last_event_sequence()), 1);
+    /// ```
+    pub fn lag(&self, book_sequence: u64) -> u64 {
+        book_sequence.saturating_sub(self.last_applied_sequence())
+    }
+
+    /// Returns the number of levels on one side evicted by the `max_levels_per_side` limit.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    pub fn evicted_levels_count(&self, side: Side) -> usize {
+        match side {
+            Side::Bid => self.evicted_bid_depth.lock().len(),
+            Side::Ask => self.evicted_ask_depth.lock().len(),
+        }
     }
 
     /// Returns the number of aggregated price levels on the bid side.
@@ -184,13 +1245,199 @@ impl MarketDepthCache {
         self.aggregated_ask_depth.read().len()
     }
 
-    /// Clears all cached market depth data.
+    /// Estimates the heap memory held by the cache, in bytes.
+    ///
+    /// The estimate covers the depth maps, the evicted levels, and the exact levels of
+    /// both sides; like `OrderBook::approx_memory_bytes`, it ignores allocator overhead.
+    pub fn approx_memory_bytes(&self) -> usize {
+        let exact_levels_bytes = [&self.exact_bid_levels, &self.exact_ask_levels]
+            .into_iter()
+            .flatten()
+            .map(|exact_levels| btree_map_bytes(&exact_levels.read()))
+            .sum::<usize>();
+        btree_map_bytes(&self.aggregated_bid_depth.read())
+            + btree_map_bytes(&self.evicted_bid_depth.lock())
+            + btree_map_bytes(&self.aggregated_ask_depth.read())
+            + btree_map_bytes(&self.evicted_ask_depth.lock())
+            + exact_levels_bytes
+    }
+
+    /// Returns the distribution of the book-to-cache propagation latency.
+    ///
+    /// Each processed event contributes the time elapsed between its creation by the
+    /// order book and its application to the cache. This quantifies how stale the
+    /// cache's view of the book is, that is, how "eventual" its consistency is in practice.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, MarketDepthCache, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    ///
+    /// let event = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).event;
+    /// cache.process_order_event(event);
+    ///
+    /// let latency = cache.propagation_latency();
+    /// assert_eq!(latency.count, 1);
+    ///
+    /// // With a single sample, every quantile is that sample
+    /// assert_eq!(latency.quantile_nanos(0.99), latency.max_nanos);
+    /// ```
+    pub fn propagation_latency(&self) -> LatencySnapshot {
+        self.propagation_latency.snapshot()
+    }
+
+    /// Clears all cached market depth data, latency samples, and the last applied sequence.
     ///
     /// This is useful for testing or resetting the cache state.
     pub fn clear(&self) {
         self.aggregated_bid_depth.write().clear();
+        self.evicted_bid_depth.lock().clear();
         self.aggregated_ask_depth.write().clear();
+        self.evicted_ask_depth.lock().clear();
+        for exact_levels in [&self.exact_bid_levels, &self.exact_ask_levels]
+            .into_iter()
+            .flatten()
+        {
+            exact_levels.write().clear();
+        }
+        self.propagation_latency.reset();
+        self.last_applied_sequence.store(0, Ordering::Relaxed);
+        self.updates.notify();
+        self.report_watched_levels();
+    }
+}
+
+/// Applies an event's deltas to a level, dropping the level once its quantity reaches zero.
+fn apply_delta(
+    depth: &mut AggregatedLevelMap,
+    price_level: Decimal,
+    event: &OrderEvent,
+    overflows: &AtomicU64,
+) -> u64 {
+    let level_info = depth.entry(price_level).or_default();
+    accumulate(
+        &mut level_info.quantity,
+        i128::from(event.quantity_delta),
+        overflows,
+    );
+    accumulate(
+        &mut level_info.order_count,
+        i128::from(event.order_count_delta),
+        overflows,
+    );
+    let new_quantity = level_info.quantity;
+    if new_quantity == 0 {
+        depth.remove(&price_level);
+    }
+    new_quantity
+}
+
+/// Adds the quantity and order count of a level to a depth map.
+fn add_level(
+    depth: &mut AggregatedLevelMap,
+    price_level: Decimal,
+    level_info: LevelInfo,
+    overflows: &AtomicU64,
+) {
+    let aggregated = depth.entry(price_level).or_default();
+    accumulate(
+        &mut aggregated.quantity,
+        i128::from(level_info.quantity),
+        overflows,
+    );
+    accumulate(
+        &mut aggregated.order_count,
+        i128::from(level_info.order_count),
+        overflows,
+    );
+}
+
+/// Adds a delta to an aggregated total, saturating it and counting the saturation in
+/// `overflows` if it leaves the range of `u64`.
+fn accumulate(total: &mut u64, delta: i128, overflows: &AtomicU64) {
+    if saturating_accumulate(total, delta) {
+        overflows.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Copies the quantities of a depth map, without the order counts.
+fn quantities(depth: &AggregatedLevelMap) -> AggregatedDepthMap {
+    depth
+        .iter()
+        .map(|(price_level, level_info)| (*price_level, level_info.quantity))
+        .collect()
+}
+
+/// Merges two partial depth maps, summing the levels present in both.
+#[cfg(feature = "rayon")]
+fn merge_depth(
+    mut depth: AggregatedLevelMap,
+    mut other: AggregatedLevelMap,
+    overflows: &AtomicU64,
+) -> AggregatedLevelMap {
+    if depth.len() < other.len() {
+        std::mem::swap(&mut depth, &mut other);
+    }
+    for (price_level, level_info) in other {
+        add_level(&mut depth, price_level, level_info, overflows);
+    }
+    depth
+}
+
+/// Applies an event to a side whose depth map is limited to `max_levels` levels.
+///
+/// Every evicted level is further from the touch than every level in `depth`, and
+/// levels are only evicted while `depth` is full, which the moves below preserve.
+fn apply_bounded_delta(
+    depth: &mut AggregatedLevelMap,
+    evicted_depth: &mut AggregatedLevelMap,
+    max_levels: usize,
+    price_level: Decimal,
+    event: &OrderEvent,
+    overflows: &AtomicU64,
+) -> u64 {
+    // The furthest level is the lowest bid or the highest ask
+    let furthest = |depth: &AggregatedLevelMap| match event.side {
+        Side::Bid => depth.keys().next().copied(),
+        Side::Ask => depth.keys().next_back().copied(),
+    };
+    let closest = |depth: &AggregatedLevelMap| match event.side {
+        Side::Bid => depth.keys().next_back().copied(),
+        Side::Ask => depth.keys().next().copied(),
+    };
+
+    if depth.contains_key(&price_level) {
+        let new_quantity = apply_delta(depth, price_level, event, overflows);
+        if new_quantity == 0 {
+            if let Some(restored) = closest(evicted_depth) {
+                let level_info = evicted_depth.remove(&restored).expect("key was just read");
+                depth.insert(restored, level_info);
+            }
+        }
+        return new_quantity;
+    }
+    if evicted_depth.contains_key(&price_level) {
+        return apply_delta(evicted_depth, price_level, event, overflows);
+    }
+
+    let further_than_kept = furthest(depth).is_some_and(|furthest| match event.side {
+        Side::Bid => price_level < furthest,
+        Side::Ask => price_level > furthest,
+    });
+    if depth.len() >= max_levels && further_than_kept {
+        return apply_delta(evicted_depth, price_level, event, overflows);
+    }
+
+    let new_quantity = apply_delta(depth, price_level, event, overflows);
+    if depth.len() > max_levels {
+        let demoted = furthest(depth).expect("depth is not empty");
+        let level_info = depth.remove(&demoted).expect("key was just read");
+        evicted_depth.insert(demoted, level_info);
     }
+    new_quantity
 }
 
 impl Default for MarketDepthCache {
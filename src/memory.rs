@@ -0,0 +1,41 @@
+//! Rough estimates of the heap memory held by the standard collections.
+//!
+//! The estimates only account for the space reserved for entries and the main
+//! per-entry bookkeeping, which is what dominates for large books; allocator headers
+//! and padding are ignored.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::mem::size_of;
+
+/// Estimates the heap bytes held by a `Vec`.
+pub(crate) fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+/// Estimates the heap bytes held by a `HashMap`.
+///
+/// Each reserved bucket stores an entry and one control byte.
+pub(crate) fn hash_map_bytes<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// Estimates the heap bytes held by a `HashSet`, with the same bucket model as
+/// `hash_map_bytes`.
+pub(crate) fn hash_set_bytes<T, S>(set: &HashSet<T, S>) -> usize {
+    set.capacity() * (size_of::<T>() + 1)
+}
+
+/// Estimates the heap bytes held by a `BTreeMap`.
+///
+/// B-tree nodes hold up to 11 entries and are typically about two thirds full, and
+/// each node carries a parent pointer and, for internal nodes, child pointers.
+pub(crate) fn btree_map_bytes<K, V>(map: &BTreeMap<K, V>) -> usize {
+    let entry_bytes = size_of::<K>() + size_of::<V>();
+    map.len() * (entry_bytes * 3 / 2 + size_of::<usize>())
+}
+
+/// Estimates the heap bytes held by a `BTreeSet`, with the same node model as
+/// `btree_map_bytes`.
+pub(crate) fn btree_set_bytes<T>(set: &BTreeSet<T>) -> usize {
+    set.len() * (size_of::<T>() * 3 / 2 + size_of::<usize>())
+}
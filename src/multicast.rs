@@ -0,0 +1,394 @@
+//! A sequenced, fixed-size datagram feed of the book's events, as venues multicast
+//! their market data, for testing feed handlers against the crate as a mini venue.
+//!
+//! A `FeedWriter` numbers the records it publishes from 1 and sends each as one
+//! datagram of `DATAGRAM_LENGTH` bytes to a `DatagramSink`: a connected `UdpSocket`,
+//! a `CaptureFile` kept for replays, or a `Vec` of datagrams in memory. Every integer
+//! is in little-endian byte order:
+//!
+//! | Bytes | Content                                                   |
+//! |-------|-----------------------------------------------------------|
+//! | 8     | Sequence number, as a `u64`                               |
+//! | 1     | Message type, the record kind of the `wire` format        |
+//! | 2     | Payload length in bytes, as a `u16`                       |
+//! | 117   | Payload, the record's version 1 `wire` frame, zero-padded |
+//!
+//! Events, trades, market-by-order events, and commands fit in a datagram, while
+//! snapshots and checkpoints do not and are rejected with
+//! `MulticastError::RecordTooLarge`. A capture file starts with the four bytes `OBMC`
+//! and the datagram length as a `u32`, followed by the datagrams in the order sent.
+//!
+//! A `FeedReader` consumes the datagrams as a feed handler would, in whatever order
+//! they arrive: it delivers each record once, drops duplicates, and keeps the ranges of
+//! sequence numbers still missing, which a datagram arriving late fills in.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::multicast::{FeedReader, FeedWriter};
+//! use order_book::wire::Record;
+//! use order_book::{Order, OrderBook, Side};
+//!
+//! let mut order_book = OrderBook::new();
+//! let mut writer = FeedWriter::new(Vec::new());
+//! for price in [100.00, 101.00, 102.00] {
+//!     let event = order_book.insert_order(Order::new(price, 10, Side::Ask)).event;
+//!     writer.publish(&Record::OrderEvent(event)).unwrap();
+//! }
+//! let datagrams = writer.into_sink();
+//!
+//! // The second datagram is lost, then arrives late
+//! let mut reader = FeedReader::new();
+//! assert_eq!(reader.read(&datagrams[0]).unwrap().unwrap().sequence, 1);
+//! assert_eq!(reader.read(&datagrams[2]).unwrap().unwrap().sequence, 3);
+//! assert_eq!(reader.gaps(), &[2..3]);
+//! assert_eq!(reader.read(&datagrams[1]).unwrap().unwrap().sequence, 2);
+//! assert!(reader.gaps().is_empty());
+//! assert!(reader.read(&datagrams[1]).unwrap().is_none());
+//! ```
+
+use crate::wire::{decode_v1, encode_v1, DecodeError, Record};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::UdpSocket;
+use std::ops::Range;
+
+/// The length of every datagram, in bytes.
+pub const DATAGRAM_LENGTH: usize = 128;
+
+/// The length of a datagram header: sequence number, message type, and payload length.
+const HEADER_LENGTH: usize = 11;
+
+/// The most payload bytes a datagram carries.
+pub const PAYLOAD_CAPACITY: usize = DATAGRAM_LENGTH - HEADER_LENGTH;
+
+/// The bytes a capture file starts with.
+const CAPTURE_MAGIC: &[u8; 4] = b"OBMC";
+
+/// Why a record could not be published or a datagram read.
+#[derive(Debug)]
+pub enum MulticastError {
+    /// Sending, writing, or reading datagrams failed
+    Io(io::Error),
+    /// The record's frame does not fit in a datagram's payload
+    RecordTooLarge {
+        /// The length of the record's frame, in bytes
+        length: usize,
+    },
+    /// The datagram is not laid out as this module writes them
+    InvalidDatagram(&'static str),
+    /// The datagram's payload cannot be decoded
+    Corrupt(DecodeError),
+    /// The input does not start with a capture file header
+    NotACapture,
+}
+
+impl fmt::Display for MulticastError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MulticastError::Io(error) => write!(formatter, "feed I/O failed: {error}"),
+            MulticastError::RecordTooLarge { length } => write!(
+                formatter,
+                "record of {length} bytes exceeds the datagram payload of {PAYLOAD_CAPACITY} bytes"
+            ),
+            MulticastError::InvalidDatagram(reason) => {
+                write!(formatter, "invalid datagram: {reason}")
+            }
+            MulticastError::Corrupt(error) => write!(formatter, "corrupt datagram: {error}"),
+            MulticastError::NotACapture => write!(formatter, "input is not a capture file"),
+        }
+    }
+}
+
+impl std::error::Error for MulticastError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MulticastError::Io(error) => Some(error),
+            MulticastError::Corrupt(error) => Some(error),
+            MulticastError::RecordTooLarge { .. }
+            | MulticastError::InvalidDatagram(_)
+            | MulticastError::NotACapture => None,
+        }
+    }
+}
+
+impl From<io::Error> for MulticastError {
+    fn from(error: io::Error) -> Self {
+        MulticastError::Io(error)
+    }
+}
+
+/// A destination of a feed's datagrams.
+pub trait DatagramSink {
+    /// Sends one datagram of `DATAGRAM_LENGTH` bytes.
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()>;
+}
+
+/// Sends every datagram to the address the socket is connected to, e.g. a multicast
+/// group.
+impl DatagramSink for UdpSocket {
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()> {
+        self.send(datagram).map(|_| ())
+    }
+}
+
+/// Collects the datagrams in memory, in the order sent.
+impl DatagramSink for Vec<Vec<u8>> {
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()> {
+        self.push(datagram.to_vec());
+        Ok(())
+    }
+}
+
+/// A capture of a feed's datagrams written to a file or any other writer, read back
+/// with `read_capture`.
+#[derive(Debug)]
+pub struct CaptureFile<W: Write> {
+    /// Where the capture is written
+    writer: W,
+}
+
+impl<W: Write> CaptureFile<W> {
+    /// Starts a capture by writing its header.
+    ///
+    /// ## Arguments
+    ///
+    /// * `writer`: Where the capture is written
+    ///
+    /// ## Returns
+    ///
+    /// The capture, or the error of writing its header
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(CAPTURE_MAGIC)?;
+        writer.write_all(&(DATAGRAM_LENGTH as u32).to_le_bytes())?;
+        Ok(CaptureFile { writer })
+    }
+
+    /// Flushes the capture and returns its writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> DatagramSink for CaptureFile<W> {
+    fn send_datagram(&mut self, datagram: &[u8]) -> io::Result<()> {
+        self.writer.write_all(datagram)
+    }
+}
+
+/// Reads the datagrams of a capture written by a `CaptureFile`, in the order sent.
+///
+/// ## Arguments
+///
+/// * `reader`: The capture
+///
+/// ## Returns
+///
+/// The datagrams, `MulticastError::NotACapture` if the input lacks the capture header,
+/// or `MulticastError::InvalidDatagram` if it ends in the middle of a datagram
+pub fn read_capture<R: Read>(mut reader: R) -> Result<Vec<Vec<u8>>, MulticastError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let header = bytes.get(..8).ok_or(MulticastError::NotACapture)?;
+    if &header[..4] != CAPTURE_MAGIC
+        || u32::from_le_bytes(header[4..8].try_into().expect("slice of four bytes"))
+            != DATAGRAM_LENGTH as u32
+    {
+        return Err(MulticastError::NotACapture);
+    }
+    let datagrams = &bytes[8..];
+    if datagrams.len() % DATAGRAM_LENGTH != 0 {
+        return Err(MulticastError::InvalidDatagram(
+            "capture ends in a datagram",
+        ));
+    }
+    Ok(datagrams
+        .chunks_exact(DATAGRAM_LENGTH)
+        .map(<[u8]>::to_vec)
+        .collect())
+}
+
+/// Publishes records as sequenced datagrams to a `DatagramSink`.
+#[derive(Debug)]
+pub struct FeedWriter<S: DatagramSink> {
+    /// Where the datagrams are sent
+    sink: S,
+    /// The sequence number of the next datagram
+    next_sequence: u64,
+    /// The datagram being built, reused between records
+    buffer: Vec<u8>,
+}
+
+impl<S: DatagramSink> FeedWriter<S> {
+    /// Creates a writer whose first datagram has sequence number 1.
+    ///
+    /// ## Arguments
+    ///
+    /// * `sink`: Where the datagrams are sent
+    pub fn new(sink: S) -> Self {
+        FeedWriter {
+            sink,
+            next_sequence: 1,
+            buffer: Vec::with_capacity(DATAGRAM_LENGTH),
+        }
+    }
+
+    /// Sends a record as the next datagram.
+    ///
+    /// ## Arguments
+    ///
+    /// * `record`: The record to publish
+    ///
+    /// ## Returns
+    ///
+    /// The sequence number of the datagram, `MulticastError::RecordTooLarge` if the
+    /// record does not fit in a datagram, or the error of sending it; no sequence
+    /// number is used up by a record that was not sent
+    pub fn publish(&mut self, record: &Record) -> Result<u64, MulticastError> {
+        self.buffer.clear();
+        self.buffer.resize(HEADER_LENGTH, 0);
+        encode_v1(record, &mut self.buffer);
+        let length = self.buffer.len() - HEADER_LENGTH;
+        if length > PAYLOAD_CAPACITY {
+            return Err(MulticastError::RecordTooLarge { length });
+        }
+        let message_type = self.buffer[HEADER_LENGTH + 1];
+        self.buffer[..8].copy_from_slice(&self.next_sequence.to_le_bytes());
+        self.buffer[8] = message_type;
+        self.buffer[9..HEADER_LENGTH].copy_from_slice(&(length as u16).to_le_bytes());
+        self.buffer.resize(DATAGRAM_LENGTH, 0);
+
+        self.sink.send_datagram(&self.buffer)?;
+        self.next_sequence += 1;
+        Ok(self.next_sequence - 1)
+    }
+
+    /// Returns the sequence number of the next datagram.
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Returns the sink.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns the sink, e.g. the datagrams collected in memory.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+}
+
+/// A record delivered by a `FeedReader`, with the sequence number of its datagram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedMessage {
+    /// The sequence number of the datagram
+    pub sequence: u64,
+    /// The record the datagram carried
+    pub record: Record,
+}
+
+/// Reads a feed's datagrams, delivering every record once and tracking the gaps.
+#[derive(Debug, Clone)]
+pub struct FeedReader {
+    /// The sequence number after the highest one received
+    next_sequence: u64,
+    /// The ranges of sequence numbers below `next_sequence` not received, ascending
+    gaps: Vec<Range<u64>>,
+}
+
+impl FeedReader {
+    /// Creates a reader expecting the first datagram of a feed, with sequence number 1.
+    pub fn new() -> Self {
+        FeedReader {
+            next_sequence: 1,
+            gaps: Vec::new(),
+        }
+    }
+
+    /// Reads a datagram.
+    ///
+    /// A datagram past the next sequence number opens a gap of the ones skipped, and a
+    /// datagram inside a gap closes its part of it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `datagram`: The datagram, as received
+    ///
+    /// ## Returns
+    ///
+    /// The record, `None` if the datagram was already received or holds a record kind
+    /// this version of the crate does not know, `MulticastError::InvalidDatagram` if it
+    /// is not laid out as a `FeedWriter` writes them, or `MulticastError::Corrupt` if
+    /// its payload cannot be decoded; a datagram that cannot be read is not counted as
+    /// received
+    pub fn read(&mut self, datagram: &[u8]) -> Result<Option<FeedMessage>, MulticastError> {
+        if datagram.len() != DATAGRAM_LENGTH {
+            return Err(MulticastError::InvalidDatagram("wrong datagram length"));
+        }
+        let sequence = u64::from_le_bytes(datagram[..8].try_into().expect("slice of eight bytes"));
+        let length = usize::from(u16::from_le_bytes([datagram[9], datagram[10]]));
+        let payload =
+            datagram[HEADER_LENGTH..]
+                .get(..length)
+                .ok_or(MulticastError::InvalidDatagram(
+                    "payload exceeds the datagram",
+                ))?;
+        if sequence == 0 {
+            return Err(MulticastError::InvalidDatagram("sequence number zero"));
+        }
+        if payload.get(1) != Some(&datagram[8]) {
+            return Err(MulticastError::InvalidDatagram(
+                "message type differs from the payload's",
+            ));
+        }
+        let (record, _) = decode_v1(payload).map_err(MulticastError::Corrupt)?;
+
+        if !self.mark_received(sequence)? {
+            return Ok(None);
+        }
+        Ok(record.map(|record| FeedMessage { sequence, record }))
+    }
+
+    /// Returns the ranges of sequence numbers skipped and not received since,
+    /// ascending.
+    pub fn gaps(&self) -> &[Range<u64>] {
+        &self.gaps
+    }
+
+    /// Returns the sequence number after the highest one received.
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Records a sequence number as received, returning `false` if it already was, or
+    /// `MulticastError::InvalidDatagram` for the last sequence number, which has no
+    /// successor to continue from.
+    fn mark_received(&mut self, sequence: u64) -> Result<bool, MulticastError> {
+        let successor = sequence
+            .checked_add(1)
+            .ok_or(MulticastError::InvalidDatagram("sequence number overflows"))?;
+        if sequence >= self.next_sequence {
+            if sequence > self.next_sequence {
+                self.gaps.push(self.next_sequence..sequence);
+            }
+            self.next_sequence = successor;
+            return Ok(true);
+        }
+        let Some(index) = self.gaps.iter().position(|gap| gap.contains(&sequence)) else {
+            return Ok(false);
+        };
+        let gap = self.gaps.remove(index);
+        let remainders = [gap.start..sequence, successor..gap.end];
+        let remainders = remainders.into_iter().filter(|range| !range.is_empty());
+        self.gaps.splice(index..index, remainders);
+        Ok(true)
+    }
+}
+
+impl Default for FeedReader {
+    fn default() -> Self {
+        FeedReader::new()
+    }
+}
@@ -1,6 +1,54 @@
-use crate::types::{Order, OrderEvent, ExactPriceLevelMap, Side};
+use crate::error::{OrderBookError, Result};
+use crate::histogram::Histogram;
+use crate::level_map::LevelMap;
+use crate::liquidity::cumulative_curve;
+use crate::memory::{hash_map_bytes, vec_bytes};
+use crate::order_flow_stats::DistanceStats;
+use crate::price_key::{PriceKey, PriceKeys};
+use crate::price_level::{OrderNode, PriceLevel};
+use crate::rng::SeededRng;
+use crate::slab::Slab;
+use crate::types::{
+    Bbo, BboChanged, ExactPriceLevelMap, ExcessPrecision, FillSummary, IdGenerator, Impact,
+    InsertOutcome, LevelInfo, LevelRemoved, LuldBands, MarketByOrderEvent, Order, OrderBookConfig,
+    OrderEvent, OrderHandle, OrderId, OrderRejected, ParticipantId, PriceNormalization,
+    PricePrecision, PriorityPolicy, QueuePosition, ReplaceOutcome, SequencedMarketByOrderEvent,
+    Side, Trade, TradingState,
+};
+use crate::units::saturating_accumulate;
+use bbo::BestLevel;
+use client_ids::ClientOrderIds;
+use determinism::BookClock;
+use iceberg::IcebergReserve;
+use quoting::{ProtectionState, QuoteHandles};
 use rust_decimal::Decimal;
-use std::collections::BTreeMap;
+use session::SessionState;
+use settlement::SettlementBucket;
+use std::collections::HashMap;
+use stops::StopLadder;
+
+pub use level_view::{LevelOrders, LevelView};
+
+mod bbo;
+mod checkpoint;
+mod circuit_breaker;
+mod client_ids;
+mod command;
+mod compaction;
+mod determinism;
+mod diff;
+mod iceberg;
+mod ids;
+mod level_view;
+mod matching;
+mod metadata;
+mod quoting;
+mod replication;
+mod session;
+mod settlement;
+mod snapshot;
+mod state_hash;
+mod stops;
 
 /// The core order book structure that maintains price-time priority.
 ///
@@ -8,7 +56,12 @@ use std::collections::BTreeMap;
 ///
 /// - Storing orders at each price level
 /// - Maintaining price priority (best bid/ask)
-/// - Publishing events when orders are inserted
+/// - Matching incoming orders against resting liquidity on request (`match_order`)
+/// - Publishing events when orders are inserted, executed, or cancelled
+///
+/// Orders are stored in a slab at stable slots, and each price level keeps its orders
+/// in a doubly-linked FIFO threaded through the slab. This keeps time priority while
+/// allowing any order to be unlinked in $O(1)$ once its slot is known.
 ///
 /// It does not maintain aggregated market depth, as that is handled by the external
 /// `MarketDepthCache` service to minimize lock contention.
@@ -17,12 +70,122 @@ use std::collections::BTreeMap;
 ///
 /// This structure is designed to be wrapped in a `RwLock` for concurrent access.
 /// The write lock should be held only briefly during order insertion.
-#[derive(Debug)]
+///
+/// ## What-If Analysis
+///
+/// Cloning the book produces an independent deep copy, which strategy code can use to
+/// apply hypothetical orders and inspect the outcome without touching the live book.
+/// The copy is a handful of vector and map clones, as orders are stored contiguously,
+/// and it preserves identifiers and slots, so handles of the live book remain valid on
+/// the copy.
+///
+/// ```
+/// use order_book::{Order, OrderBook, Side};
+///
+/// let mut order_book = OrderBook::new();
+/// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+///
+/// let mut what_if = order_book.clone();
+/// what_if.match_order(Order::new(100.00, 10, Side::Bid));
+///
+/// assert_eq!(what_if.order_count(), 0);
+/// assert_eq!(order_book.order_count(), 1);
+/// ```
+#[derive(Debug, Clone)]
 pub struct OrderBook {
     /// Ask side (sell orders): sorted by ascending price (lowest ask first)
-    asks: ExactPriceLevelMap,
+    asks: LevelMap,
     /// Bid side (buy orders): sorted by descending price (highest bid first)
-    bids: ExactPriceLevelMap,
+    bids: LevelMap,
+    /// Storage for every resting order, linked into the FIFO of its price level
+    orders: Slab<OrderNode>,
+    /// Maps each resting order's identifier to its slot in `orders`
+    order_slots: HashMap<OrderId, usize>,
+    /// One past the highest identifier assigned, which the counter assigns next
+    next_order_id: u64,
+    /// How identifiers are assigned to inserted orders
+    order_id_generator: IdGenerator,
+    /// One past the highest identifier assigned to a trade, which the counter assigns
+    /// next
+    next_trade_id: u64,
+    /// How identifiers are assigned to printed trades
+    trade_id_generator: IdGenerator,
+    /// Every trade printed by `match_order`, oldest first
+    trades: Vec<Trade>,
+    /// Whether market-by-order events are recorded into `market_by_order_events`
+    market_by_order_enabled: bool,
+    /// Market-by-order events recorded since they were last taken
+    market_by_order_events: Vec<MarketByOrderEvent>,
+    /// The number of market-by-order events recorded since the book was created
+    market_by_order_sequence: u64,
+    /// The order flow attributed to distances from the touch, if counted
+    distance_stats: Option<DistanceStats>,
+    /// Whether level removals are recorded into `level_removed_events`
+    level_removed_enabled: bool,
+    /// Level removals recorded since they were last taken
+    level_removed_events: Vec<LevelRemoved>,
+    /// Whether refused incoming orders are recorded into `rejection_events`
+    rejection_events_enabled: bool,
+    /// Rejections recorded since they were last taken
+    rejection_events: Vec<OrderRejected>,
+    /// The best bid level, kept up to date on every change to the bids
+    best_bid: Option<BestLevel>,
+    /// The best ask level, kept up to date on every change to the asks
+    best_ask: Option<BestLevel>,
+    /// The best bid and ask when `take_bbo_change` was last called
+    reported_bbo: Bbo,
+    /// Sequence number of the last published `OrderEvent`
+    last_event_sequence: u64,
+    /// How incoming prices are canonicalized before being stored
+    price_normalization: PriceNormalization,
+    /// The bound on the decimal places of incoming order prices, if any
+    price_precision: Option<PricePrecision>,
+    /// How canonical prices are converted to the keys of `asks` and `bids`
+    price_keys: PriceKeys,
+    /// Total resting quantity on the bid side
+    bid_volume: u64,
+    /// Total resting quantity on the ask side
+    ask_volume: u64,
+    /// Number of resting totals saturated for leaving the range of `u64`
+    aggregation_overflows: u64,
+    /// Distributions of order sizes and orders per level on the bid side
+    bid_histograms: SideHistograms,
+    /// Distributions of order sizes and orders per level on the ask side
+    ask_histograms: SideHistograms,
+    /// The resting orders of each participant's quote submitted with `submit_quote`
+    quotes: HashMap<ParticipantId, QuoteHandles>,
+    /// The participant owning each resting quote order
+    quote_owners: HashMap<OrderId, ParticipantId>,
+    /// The hidden reserve of each resting iceberg order that has one left
+    icebergs: HashMap<OrderId, IcebergReserve>,
+    /// The stop orders waiting for a trade to reach their trigger price
+    stops: StopLadder,
+    /// The orders waiting to trade at the next settlement price
+    settlement: SettlementBucket,
+    /// The protection limits and recent executions of each protected participant
+    quote_protections: HashMap<ParticipantId, ProtectionState>,
+    /// The client order identifiers of resting orders and recent submissions
+    client_order_ids: ClientOrderIds,
+    /// The limit-up/limit-down bands enforced by `match_order`, if any
+    luld_bands: Option<LuldBands>,
+    /// The price the bands are centered on: the last trade or an external reference
+    reference_price: Option<Decimal>,
+    /// Whether incoming orders are currently matched
+    trading_state: TradingState,
+    /// Which order of a level incoming orders execute against first
+    priority_policy: PriorityPolicy,
+    /// The current trading session and its day orders
+    session: SessionState,
+    /// Where event timestamps and time windows take the current time from
+    clock: BookClock,
+    /// The source of randomness for randomized policies
+    rng: SeededRng,
+    /// Number of storage reallocations and price level creations, for verifying that
+    /// a warmed-up book does not allocate
+    allocation_count: u64,
+    /// The largest capacity the identifier index has reported. Removals can lower the
+    /// reported capacity without freeing memory, so only growth past it is a reallocation
+    peak_index_capacity: usize,
 }
 
 impl OrderBook {
@@ -36,12 +199,170 @@ impl OrderBook {
     /// let order_book = OrderBook::new();
     /// ```
     pub fn new() -> Self {
+        Self::with_config(OrderBookConfig::default())
+    }
+
+    /// Creates a new empty order book canonicalizing prices and preallocating storage as
+    /// configured.
+    ///
+    /// The options of `OrderBookConfig` combine freely, e.g. a preallocated book can also
+    /// bound the precision of its prices.
+    ///
+    /// - `price_normalization`: Every price entering the book, through orders or
+    ///   queries, is converted with the policy first, so stored keys and published
+    ///   events use a single representation per price
+    /// - `price_precision`: Every price is stored at exactly `max_scale` decimal places,
+    ///   as with `PriceNormalization::Scale`, which then takes the place of
+    ///   `price_normalization`. The prices of incoming orders with more decimal places
+    ///   are rounded or rejected, as `excess_precision` says; prices passed to queries
+    ///   are always rounded. Only the fallible entry points (e.g. `try_insert_order`,
+    ///   `replace_order`, and `apply`) reject prices, returning
+    ///   `OrderBookError::PriceTooPrecise`; the infallible ones (e.g. `insert_order`
+    ///   and `match_order`) round them as under `ExcessPrecision::Round`
+    /// - `order_capacity`: The order slab, its free list, and the identifier index are
+    ///   allocated up front, and the slots of removed orders are recycled, so once the
+    ///   book has warmed up, inserting and cancelling orders at existing price levels
+    ///   performs no heap allocation as long as fewer than `order_capacity` orders rest
+    ///   at a time. Creating a new price level may still allocate a node of the level map
+    /// - `tick_size`: Takes effect with the `tick-prices` feature, which keys levels by
+    ///   an `i64` number of ticks rather than by `Decimal` price. Incoming prices that
+    ///   are not a multiple of the tick size, which must be positive, are rounded to the
+    ///   nearest tick (after the price normalization policy is applied), and prices
+    ///   reported by the book are converted back from ticks. Without the feature, the
+    ///   field is ignored and levels are keyed by `Decimal` price, so enabling the
+    ///   feature does not break code that sets it
+    ///
+    /// ## Arguments
+    ///
+    /// * `config`: How the book canonicalizes prices and preallocates storage
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{
+    ///     ExcessPrecision, Order, OrderBook, OrderBookConfig, OrderBookError, PricePrecision, Side,
+    /// };
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::with_config(OrderBookConfig {
+    ///     price_precision: Some(PricePrecision {
+    ///         max_scale: 2,
+    ///         excess_precision: ExcessPrecision::Reject,
+    ///     }),
+    ///     order_capacity: 1_024,
+    ///     ..OrderBookConfig::default()
+    /// });
+    /// let event = order_book.insert_order(Order::new(100.5, 10, Side::Bid)).event;
+    /// assert_eq!(event.price.to_string(), "100.50");
+    ///
+    /// let error = order_book.try_insert_order(Order::new(100.125, 10, Side::Bid)).unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     OrderBookError::PriceTooPrecise { price: Decimal::new(100125, 3), max_scale: 2 }
+    /// );
+    /// ```
+    pub fn with_config(config: OrderBookConfig) -> Self {
+        let price_normalization = match config.price_precision {
+            Some(price_precision) => PriceNormalization::Scale(price_precision.max_scale),
+            None => config.price_normalization,
+        };
+        #[cfg(feature = "tick-prices")]
+        let price_keys = match config.tick_size {
+            Some(tick_size) => PriceKeys::with_tick_size(tick_size),
+            None => PriceKeys::new(),
+        };
+        #[cfg(not(feature = "tick-prices"))]
+        let price_keys = PriceKeys::new();
+        let order_slots = HashMap::with_capacity(config.order_capacity);
         OrderBook {
-            asks: BTreeMap::new(),
-            bids: BTreeMap::new(),
+            asks: LevelMap::default(),
+            bids: LevelMap::default(),
+            orders: Slab::with_capacity(config.order_capacity),
+            peak_index_capacity: order_slots.capacity(),
+            order_slots,
+            next_order_id: 1,
+            order_id_generator: IdGenerator::Counter,
+            next_trade_id: 1,
+            trade_id_generator: IdGenerator::Counter,
+            trades: Vec::new(),
+            market_by_order_enabled: false,
+            market_by_order_events: Vec::new(),
+            market_by_order_sequence: 0,
+            distance_stats: None,
+            level_removed_enabled: false,
+            level_removed_events: Vec::new(),
+            rejection_events_enabled: false,
+            rejection_events: Vec::new(),
+            best_bid: None,
+            best_ask: None,
+            reported_bbo: Bbo::default(),
+            last_event_sequence: 0,
+            price_normalization,
+            price_precision: config.price_precision,
+            price_keys,
+            bid_volume: 0,
+            ask_volume: 0,
+            aggregation_overflows: 0,
+            bid_histograms: SideHistograms::default(),
+            ask_histograms: SideHistograms::default(),
+            quotes: HashMap::new(),
+            quote_owners: HashMap::new(),
+            icebergs: HashMap::new(),
+            stops: StopLadder::default(),
+            settlement: SettlementBucket::default(),
+            quote_protections: HashMap::new(),
+            client_order_ids: ClientOrderIds::new(),
+            session: SessionState::new(),
+            luld_bands: None,
+            reference_price: None,
+            trading_state: TradingState::Continuous,
+            priority_policy: PriorityPolicy::Fifo,
+            clock: BookClock::System,
+            rng: SeededRng::new(0),
+            allocation_count: 0,
         }
     }
 
+    /// Returns the bound on the decimal places of incoming prices, if any.
+    pub fn price_precision(&self) -> Option<PricePrecision> {
+        self.price_precision
+    }
+
+    /// Returns the price of one tick. Only available with the `tick-prices` feature.
+    #[cfg(feature = "tick-prices")]
+    pub fn tick_size(&self) -> Decimal {
+        self.price_keys.tick_size()
+    }
+
+    /// Returns how many times the book's storage reallocated or a price level was created.
+    ///
+    /// Comparing the count before and after a workload verifies that a warmed-up book,
+    /// e.g. one built with an `order_capacity`, stays off the allocator on its hot path.
+    /// The count only compares capacities the book reads anyway, so it is kept in
+    /// release builds too.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, OrderBookConfig, Side};
+    ///
+    /// let mut order_book = OrderBook::with_config(OrderBookConfig {
+    ///     order_capacity: 16,
+    ///     ..OrderBookConfig::default()
+    /// });
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    ///
+    /// let warmed_up = order_book.allocation_count();
+    /// for _ in 0..100 {
+    ///     let handle = order_book.insert_order(Order::new(100.00, 5, Side::Bid)).handle;
+    ///     order_book.cancel_by_handle(handle).unwrap();
+    /// }
+    /// assert_eq!(order_book.allocation_count(), warmed_up);
+    /// ```
+    pub fn allocation_count(&self) -> u64 {
+        self.allocation_count
+    }
+
     /// Aggregates a precise price to its integer price level.
     ///
     /// This function truncates the decimal portion of the price, effectively
@@ -70,14 +391,20 @@ impl OrderBook {
         price.trunc()
     }
 
-    /// Inserts a new order into the order book and returns an event.
+    /// Inserts a new order into the order book and returns its handle and event.
     ///
     /// This method:
-    /// 1. Adds the order to the appropriate price level (maintaining time priority)
-    /// 2. Returns an `OrderEvent` that downstream services can use to update their state
+    /// 1. Assigns the order a new `OrderId`
+    /// 2. Adds the order to the back of its price level (maintaining time priority)
+    /// 3. Returns an `OrderHandle` for later cancellation, together with an `OrderEvent`
+    ///    that downstream services can use to update their state
     ///
-    /// The write lock should be held only during this operation, which is $O(\log{N})$
-    /// where $N$ is the number of distinct price levels.
+    /// The write lock should be held only during this operation, which is $O(1)$ when
+    /// the price level already exists and $O(\log{N})$, where $N$ is the number of
+    /// distinct price levels, when it has to be created.
+    ///
+    /// A price the book's `PricePrecision` would reject is rounded to its `max_scale`
+    /// instead; `try_insert_order` returns the error.
     ///
     /// ## Arguments
     ///
@@ -85,7 +412,7 @@ impl OrderBook {
     ///
     /// ## Returns
     ///
-    /// An `OrderEvent` describing the change that occurred
+    /// An `InsertOutcome` holding the order's handle and the event describing the change
     ///
     /// ## Examples
     ///
@@ -96,41 +423,184 @@ impl OrderBook {
     /// let mut order_book = OrderBook::new();
     /// let order = Order::new(100.50, 100, Side::Bid);
     ///
-    /// let event = order_book.insert_order(order);
-    /// assert_eq!(event.quantity_delta, 100);
+    /// let outcome = order_book.insert_order(order);
+    /// assert_eq!(outcome.event.quantity_delta, 100);
+    /// assert_eq!(outcome.handle.side(), Side::Bid);
     /// ```
-    pub fn insert_order(&mut self, order: Order) -> OrderEvent {
-        // Select the appropriate price level map based on side
-        let price_level_map = match order.side {
-            Side::Bid => &mut self.bids,
-            Side::Ask => &mut self.asks,
-        };
+    pub fn insert_order(&mut self, mut order: Order) -> InsertOutcome {
+        order.price = self.canonical_price(order.price);
+        let order_id = self.assign_order_id();
+        let outcome = self.rest_order(order_id, order);
 
-        // Insert the order at its price level, maintaining time priority
-        price_level_map
-            .entry(order.price)
-            .or_insert_with(Vec::new)
-            .push(order.clone());
+        self.publish_added(&outcome);
+        outcome
+    }
 
-        // Publish the event for downstream consumers
-        OrderEvent {
-            price: order.price,
-            quantity_delta: order.quantity,
-            side: order.side,
+    /// Inserts a new order like `insert_order`, unless the book's `PricePrecision`
+    /// rejects its price or its quantity is too large for an `OrderEvent`.
+    ///
+    /// This is the only way to insert an order under `ExcessPrecision::Reject`:
+    /// `insert_order` rounds the same price instead, as `ExcessPrecision::Round` does.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to insert
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome`, or, leaving the book unchanged,
+    /// `OrderBookError::PriceTooPrecise` if the price was rejected or
+    /// `OrderBookError::QuantityTooLarge` if the quantity exceeds `i64::MAX`
+    pub fn try_insert_order(&mut self, mut order: Order) -> Result<InsertOutcome> {
+        order.price = self.checked_order(&order)?;
+        Ok(self.insert_order(order))
+    }
+
+    /// Cancels a resting order by its identifier and returns an event.
+    ///
+    /// The order is located through the identifier index, and then removed as in
+    /// `cancel_by_handle`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order to cancel
+    ///
+    /// ## Returns
+    ///
+    /// An `OrderEvent` with a negative quantity delta equal to the cancelled quantity,
+    /// or `OrderBookError::OrderNotFound` if no such order is resting
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let outcome = order_book.insert_order(Order::new(100.50, 100, Side::Bid));
+    ///
+    /// let event = order_book.cancel_order(outcome.handle.order_id()).unwrap();
+    /// assert_eq!(event.quantity_delta, -100);
+    /// assert_eq!(order_book.bid_levels_count(), 0);
+    /// ```
+    pub fn cancel_order(&mut self, order_id: OrderId) -> Result<OrderEvent> {
+        let slot = *self
+            .order_slots
+            .get(&order_id)
+            .ok_or(OrderBookError::OrderNotFound(order_id))?;
+
+        Ok(self.cancel_order_at(slot))
+    }
+
+    /// Cancels a resting order only if its remaining quantity is the expected one.
+    ///
+    /// A gateway that reads an order and then cancels it races with the fills that may
+    /// execute in between. Comparing the remaining quantity at the time of the cancel
+    /// lets it notice such a fill and decide again, e.g. whether the cancel is still
+    /// wanted for what is left, instead of cancelling more than it meant to.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order to cancel
+    /// * `expected_remaining`: The remaining quantity the caller last read
+    ///
+    /// ## Returns
+    ///
+    /// An `OrderEvent` with a negative quantity delta equal to the cancelled quantity,
+    /// `OrderBookError::RemainingMismatch` with the actual remaining quantity if it
+    /// differs, leaving the order resting, or `OrderBookError::OrderNotFound` if no such
+    /// order is resting, e.g. because it was fully filled
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, OrderBookError, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let order_id = order_book.insert_order(Order::new(100.00, 10, Side::Ask)).handle.order_id();
+    /// order_book.match_order(Order::new(100.00, 4, Side::Bid));
+    ///
+    /// assert_eq!(
+    ///     order_book.cancel_if_remaining(order_id, 10),
+    ///     Err(OrderBookError::RemainingMismatch { order_id, expected: 10, remaining: 6 })
+    /// );
+    /// let event = order_book.cancel_if_remaining(order_id, 6).unwrap();
+    /// assert_eq!(event.quantity_delta, -6);
+    /// ```
+    pub fn cancel_if_remaining(
+        &mut self,
+        order_id: OrderId,
+        expected_remaining: u64,
+    ) -> Result<OrderEvent> {
+        let slot = *self
+            .order_slots
+            .get(&order_id)
+            .ok_or(OrderBookError::OrderNotFound(order_id))?;
+        let remaining = self
+            .orders
+            .get(slot)
+            .expect("indexed slot must be occupied")
+            .order
+            .quantity;
+        if remaining != expected_remaining {
+            return Err(OrderBookError::RemainingMismatch {
+                order_id,
+                expected: expected_remaining,
+                remaining,
+            });
         }
+
+        Ok(self.cancel_order_at(slot))
     }
 
-    /// Computes the current best bid and best ask prices.
+    /// Cancels the resting order referenced by a handle and returns an event.
+    ///
+    /// The handle points directly at the order's slot, so no identifier lookup is
+    /// needed: unlinking the order is $O(1)$, plus a single lookup of its price level.
     ///
-    /// This operation acquires a read lock and is O(1) due to the BTreeMap structure:
+    /// ## Arguments
     ///
-    /// - Best bid is the highest price in the bid map (last key)
-    /// - Best ask is the lowest price in the ask map (first key)
+    /// * `handle`: The handle returned when the order was inserted
     ///
     /// ## Returns
     ///
-    /// A tuple of `(best_bid, best_ask)` where each is `Option<Decimal>`.
-    /// Returns `None` if there are no orders on that side.
+    /// An `OrderEvent` with a negative quantity delta equal to the cancelled quantity,
+    /// or `OrderBookError::StaleHandle` if the order has already left the book
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, OrderBookError, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let handle = order_book.insert_order(Order::new(100.50, 100, Side::Ask)).handle;
+    ///
+    /// assert!(order_book.cancel_by_handle(handle).is_ok());
+    /// assert_eq!(
+    ///     order_book.cancel_by_handle(handle),
+    ///     Err(OrderBookError::StaleHandle(handle.order_id()))
+    /// );
+    /// ```
+    pub fn cancel_by_handle(&mut self, handle: OrderHandle) -> Result<OrderEvent> {
+        if !self.orders.contains(handle.slot, handle.generation) {
+            return Err(OrderBookError::StaleHandle(handle.order_id));
+        }
+
+        Ok(self.cancel_order_at(handle.slot))
+    }
+
+    /// Cancels every resting order matching a predicate and returns their events.
+    ///
+    /// All matching orders are removed within a single call, so a caller holding the
+    /// book's write lock publishes the whole batch under one acquisition. Orders are
+    /// visited in storage order, not in price or time order.
+    ///
+    /// ## Arguments
+    ///
+    /// * `predicate`: Returns `true` for the orders to cancel
+    ///
+    /// ## Returns
+    ///
+    /// One `OrderEvent` per cancelled order, empty if nothing matched
     ///
     /// ## Examples
     ///
@@ -139,62 +609,1358 @@ impl OrderBook {
     /// use rust_decimal::Decimal;
     ///
     /// let mut order_book = OrderBook::new();
-    /// order_book.insert_order(Order::new(100.50, 100, Side::Bid));
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(95.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
     ///
-    /// let (best_bid, best_ask, spread) = order_book.compute_spread();
-    /// assert_eq!(best_bid, Some(Decimal::new(10050, 2)));
-    /// assert_eq!(best_ask, None);
+    /// let events = order_book
+    ///     .cancel_where(|order| order.side == Side::Bid && order.price < Decimal::new(98, 0));
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(order_book.order_count(), 2);
     /// ```
-    pub fn compute_spread(&self) -> (Option<Decimal>, Option<Decimal>, Option<Decimal>) {
-        // BTreeMap maintains sorted order:
-        // - For bids: higher prices come last (use `next_back` to get highest)
-        // - For asks: lower prices come first (use `next` to get lowest)
-        let best_bid = self.bids.keys().next_back().copied();
-        let best_ask = self.asks.keys().next().copied();
-        let spread = best_bid.and_then(|b| best_ask.map(|a| a - b));
+    pub fn cancel_where(&mut self, predicate: impl Fn(&Order) -> bool) -> Vec<OrderEvent> {
+        let slots: Vec<usize> = self
+            .orders
+            .iter()
+            .filter(|(_, node)| predicate(&node.order))
+            .map(|(slot, _)| slot)
+            .collect();
 
-        (best_bid, best_ask, spread)
+        slots
+            .into_iter()
+            .map(|slot| self.cancel_order_at(slot))
+            .collect()
     }
 
-    /// Returns the number of distinct price levels on the bid side.
+    /// Cancels every order on one side priced further than `band` from that side's best price.
+    ///
+    /// Books mirrored from noisy external data tend to accumulate stale orders far from
+    /// the touch; this removes them level by level without visiting the orders that stay.
+    /// Orders exactly `band` away are kept.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to trim
+    /// * `band`: The largest distance from the best price that is kept
     ///
     /// ## Returns
     ///
-    /// The count of unique bid price levels
-    pub fn bid_levels_count(&self) -> usize {
-        self.bids.len()
+    /// One `OrderEvent` per cancelled order, furthest levels last
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(102.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(150.00, 10, Side::Ask));
+    ///
+    /// let events = order_book.cancel_outside_band(Side::Ask, Decimal::new(5, 0));
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(order_book.worst_ask(), Some(Decimal::new(102, 0)));
+    /// ```
+    pub fn cancel_outside_band(&mut self, side: Side, band: Decimal) -> Vec<OrderEvent> {
+        // Levels are collected from the far end inwards, then visited closest first
+        let mut outside_levels: Vec<&PriceLevel> = match side {
+            Side::Bid => {
+                let Some((best_bid, _)) = self.best_level(Side::Bid) else {
+                    return Vec::new();
+                };
+                let threshold = best_bid - band;
+                self.bids
+                    .iter()
+                    .take_while(|(key, _)| self.price_of(**key) < threshold)
+                    .map(|(_, level)| level)
+                    .collect()
+            }
+            Side::Ask => {
+                let Some((best_ask, _)) = self.best_level(Side::Ask) else {
+                    return Vec::new();
+                };
+                let threshold = best_ask + band;
+                self.asks
+                    .iter()
+                    .rev()
+                    .take_while(|(key, _)| self.price_of(**key) > threshold)
+                    .map(|(_, level)| level)
+                    .collect()
+            }
+        };
+        outside_levels.reverse();
+
+        let mut slots = Vec::new();
+        for price_level in outside_levels {
+            let mut cursor = price_level.head;
+            while let Some(slot) = cursor {
+                slots.push(slot);
+                cursor = self.orders.get(slot).and_then(|node| node.next);
+            }
+        }
+
+        slots
+            .into_iter()
+            .map(|slot| self.cancel_order_at(slot))
+            .collect()
     }
 
-    /// Returns the number of distinct price levels on the ask side.
+    /// Reduces the remaining quantity of a resting order in place and returns an event.
+    ///
+    /// Unlike a cancel-and-replace, the order keeps its position in the queue. This
+    /// serves gateways that manage fills externally as well as "cancel down" requests.
+    /// Reducing an order by its whole remaining quantity removes it from the book.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order to reduce
+    /// * `quantity`: The quantity to remove from the order
     ///
     /// ## Returns
     ///
-    /// The count of unique ask price levels
-    pub fn ask_levels_count(&self) -> usize {
-        self.asks.len()
+    /// An `OrderEvent` with a negative quantity delta equal to `quantity`, or an error if
+    /// the order is not resting or `quantity` is zero or exceeds the remaining quantity
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let order_id = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).handle.order_id();
+    ///
+    /// let event = order_book.reduce_order(order_id, 40).unwrap();
+    /// assert_eq!(event.quantity_delta, -40);
+    /// assert_eq!(order_book.get_order(order_id).unwrap().quantity, 60);
+    /// ```
+    pub fn reduce_order(&mut self, order_id: OrderId, quantity: u64) -> Result<OrderEvent> {
+        let slot = *self
+            .order_slots
+            .get(&order_id)
+            .ok_or(OrderBookError::OrderNotFound(order_id))?;
+        let node = self
+            .orders
+            .get(slot)
+            .expect("indexed slot must be occupied");
+        let remaining = node.order.quantity;
+
+        if quantity == 0 || quantity > remaining {
+            return Err(OrderBookError::InvalidReduction {
+                order_id,
+                remaining,
+                requested: quantity,
+            });
+        }
+        let (price, side) = (node.order.price, node.order.side);
+        let event = self.decrease_order_at(slot, quantity);
+
+        self.publish_market_by_order(MarketByOrderEvent::Cancelled {
+            order_id,
+            price,
+            side,
+            cancelled_quantity: quantity,
+            remaining_quantity: remaining - quantity,
+        });
+        Ok(event)
     }
 
-    /// Returns the total number of orders at a specific price level.
+    /// Replaces a resting order with a new price and quantity.
+    ///
+    /// The original order is removed and a new order, with a new `OrderId`, is appended
+    /// to the back of its price level, so the replacement loses time priority. Like
+    /// `insert_order`, the replacement rests without being matched. Everything else the
+    /// order carries moves to the replacement: its metadata, its client order identifier,
+    /// its session, the quote it belongs to, and, for an iceberg order, its hidden
+    /// reserve, of which `quantity` becomes the displayed slice.
     ///
     /// ## Arguments
     ///
-    /// * `price`: The price level to query
-    /// * `side`: The side (bid or ask) to query
+    /// * `order_id`: The identifier of the order to replace
+    /// * `price`: The price of the replacement order
+    /// * `quantity`: The quantity of the replacement order
     ///
     /// ## Returns
     ///
-    /// The number of orders at that price level, or 0 if no orders exist
-    pub fn orders_at_exact_price_level(&self, price: Decimal, side: Side) -> usize {
-        let price_level_map = match side {
+    /// A `ReplaceOutcome` with the replacement's handle and the events for the removal
+    /// and the insertion, `OrderBookError::OrderNotFound` if no such order is resting,
+    /// `OrderBookError::ZeroReplacementQuantity` if `quantity` is 0,
+    /// `OrderBookError::QuantityTooLarge` if it exceeds `i64::MAX`, or
+    /// `OrderBookError::PriceTooPrecise` if the book's `PricePrecision` rejects `price`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let order_id = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).handle.order_id();
+    ///
+    /// let outcome = order_book.replace_order(order_id, Decimal::new(10075, 2), 80).unwrap();
+    /// assert_eq!(outcome.removed.quantity_delta, -100);
+    /// assert_eq!(outcome.added.quantity_delta, 80);
+    /// assert!(order_book.get_order(order_id).is_none());
+    /// ```
+    pub fn replace_order(
+        &mut self,
+        order_id: OrderId,
+        price: Decimal,
+        quantity: u64,
+    ) -> Result<ReplaceOutcome> {
+        let slot = *self
+            .order_slots
+            .get(&order_id)
+            .ok_or(OrderBookError::OrderNotFound(order_id))?;
+        if quantity == 0 {
+            return Err(OrderBookError::ZeroReplacementQuantity(order_id));
+        }
+        Self::checked_quantity(quantity)?;
+
+        let price = self.checked_price(price)?;
+        let is_day_order = self.is_day_order(order_id);
+        let reserve = self.icebergs.get(&order_id).copied();
+        let client_order_id = self.client_order_id(order_id);
+        let quote_owner = self.quote_owners.get(&order_id).copied();
+        let old_bbo = self.bbo();
+        let (node, removed) = self.take_order_at(slot);
+        let new_order_id = self.assign_order_id();
+        let replacement = Order {
+            price,
+            quantity,
+            ..node.order
+        };
+        let outcome = self.rest_order(new_order_id, replacement);
+        if let Some(metadata) = node.metadata {
+            self.attach_metadata(outcome.handle, metadata);
+        }
+        if is_day_order {
+            self.session.insert_day_order(new_order_id);
+        }
+        if let Some(reserve) = reserve {
+            self.icebergs.insert(new_order_id, reserve);
+        }
+        if let Some(client_order_id) = client_order_id {
+            self.client_order_ids
+                .restore_resting(new_order_id, client_order_id);
+        }
+        if let Some(participant_id) = quote_owner {
+            self.quote_owners.insert(new_order_id, participant_id);
+            if let Some(quote) = self.quotes.get_mut(&participant_id) {
+                quote.replace(order_id, outcome.handle);
+            }
+        }
+
+        self.publish_market_by_order(MarketByOrderEvent::Replaced {
+            order_id,
+            new_order_id,
+            price,
+            quantity,
+            side: node.order.side,
+        });
+        Ok(ReplaceOutcome {
+            handle: outcome.handle,
+            removed,
+            added: outcome.event,
+            bbo_changed: BboChanged::between(old_bbo, self.bbo()),
+        })
+    }
+
+    /// Returns the resting order with the given identifier, if any.
+    ///
+    /// The returned order reflects its current remaining quantity; `order_fills` reports
+    /// how much of it has executed.
+    pub fn get_order(&self, order_id: OrderId) -> Option<&Order> {
+        let slot = *self.order_slots.get(&order_id)?;
+        self.orders.get(slot).map(|node| &node.order)
+    }
+
+    /// Returns the cumulative executions of a resting order across its partial fills.
+    ///
+    /// For the resting remainder of an order submitted with `match_order`, this includes
+    /// the executions on arrival. Reducing an order leaves its fills untouched, while a
+    /// replacement starts without any.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order
+    ///
+    /// ## Returns
+    ///
+    /// The order's `FillSummary`, or `None` if the order is not resting
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let order_id = order_book.insert_order(Order::new(100.00, 30, Side::Ask)).handle.order_id();
+    /// order_book.match_order(Order::new(100.00, 10, Side::Bid));
+    /// order_book.match_order(Order::new(100.00, 5, Side::Bid));
+    ///
+    /// let fills = order_book.order_fills(order_id).unwrap();
+    /// assert_eq!(fills.filled_quantity, 15);
+    /// assert_eq!(fills.average_fill_price(), Some(Decimal::new(100, 0)));
+    /// assert_eq!(order_book.get_order(order_id).unwrap().quantity, 15);
+    /// ```
+    pub fn order_fills(&self, order_id: OrderId) -> Option<FillSummary> {
+        let slot = *self.order_slots.get(&order_id)?;
+        self.orders.get(slot).map(|node| node.fills)
+    }
+
+    /// Returns how many orders, and how much quantity, rest ahead of an order at its price.
+    ///
+    /// The queue is walked outwards from the order in both directions at once. As soon
+    /// as either end is reached, the position follows from the level's running totals,
+    /// so the cost is proportional to the distance to the nearer end of the queue.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order to locate
+    ///
+    /// ## Returns
+    ///
+    /// The order's `QueuePosition`, or `None` if the order is not resting
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.50, 100, Side::Bid));
+    /// let order_id = order_book.insert_order(Order::new(100.50, 25, Side::Bid)).handle.order_id();
+    ///
+    /// let position = order_book.queue_position(order_id).unwrap();
+    /// assert_eq!(position.orders_ahead, 1);
+    /// assert_eq!(position.quantity_ahead, 100);
+    /// ```
+    pub fn queue_position(&self, order_id: OrderId) -> Option<QueuePosition> {
+        let slot = *self.order_slots.get(&order_id)?;
+        let node = self.orders.get(slot)?;
+        let price_level_map = match node.order.side {
             Side::Bid => &self.bids,
             Side::Ask => &self.asks,
         };
+        let price_level = price_level_map.get(&self.key(node.order.price))?;
 
-        price_level_map
-            .get(&price)
-            .map(|orders| orders.len())
-            .unwrap_or(0)
+        let (mut ahead_cursor, mut behind_cursor) = (node.previous, node.next);
+        let (mut orders_ahead, mut quantity_ahead) = (0, 0);
+        let (mut orders_behind, mut quantity_behind) = (0, 0);
+
+        loop {
+            let Some(ahead_slot) = ahead_cursor else {
+                return Some(QueuePosition {
+                    orders_ahead,
+                    quantity_ahead,
+                });
+            };
+            let Some(behind_slot) = behind_cursor else {
+                // Everything that is neither this order nor behind it is ahead of it
+                return Some(QueuePosition {
+                    orders_ahead: price_level.order_count - orders_behind - 1,
+                    quantity_ahead: price_level.total_quantity
+                        - quantity_behind
+                        - node.order.quantity,
+                });
+            };
+
+            let ahead_node = self.orders.get(ahead_slot)?;
+            orders_ahead += 1;
+            quantity_ahead += ahead_node.order.quantity;
+            ahead_cursor = ahead_node.previous;
+
+            let behind_node = self.orders.get(behind_slot)?;
+            orders_behind += 1;
+            quantity_behind += behind_node.order.quantity;
+            behind_cursor = behind_node.next;
+        }
+    }
+
+    /// Returns the total number of resting orders on both sides.
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Estimates the heap memory held by the book, in bytes.
+    ///
+    /// The estimate covers the price level maps, the order slab (including vacant
+    /// slots kept for reuse), the identifier index, the trade log, buffered
+    /// market-by-order events, the quoting state, and the client order identifiers. It
+    /// is meant for capacity planning and eviction policies, not exact accounting:
+    /// allocator overhead is ignored.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let empty_bytes = order_book.approx_memory_bytes();
+    ///
+    /// for index in 0..1_000 {
+    ///     order_book.insert_order(Order::new(100.0 + index as f64, 10, Side::Ask));
+    /// }
+    /// assert!(order_book.approx_memory_bytes() > empty_bytes);
+    /// ```
+    pub fn approx_memory_bytes(&self) -> usize {
+        self.bids.memory_bytes()
+            + self.asks.memory_bytes()
+            + self.orders.memory_bytes()
+            + hash_map_bytes(&self.order_slots)
+            + vec_bytes(&self.trades)
+            + vec_bytes(&self.market_by_order_events)
+            + vec_bytes(&self.level_removed_events)
+            + vec_bytes(&self.rejection_events)
+            + hash_map_bytes(&self.quotes)
+            + hash_map_bytes(&self.quote_owners)
+            + hash_map_bytes(&self.icebergs)
+            + self.stops.memory_bytes()
+            + self.settlement.memory_bytes()
+            + hash_map_bytes(&self.quote_protections)
+            + self.client_order_ids.memory_bytes()
+            + self.session.memory_bytes()
+    }
+
+    /// Returns the sequence number of the last `OrderEvent` the book published, or 0 if none.
+    ///
+    /// Comparing it with `MarketDepthCache::last_applied_sequence` tells how far a
+    /// consumer of the event stream is behind the book (see `MarketDepthCache::lag`).
+    pub fn last_event_sequence(&self) -> u64 {
+        self.last_event_sequence
+    }
+
+    /// Returns every trade printed by `match_order` so far, oldest first.
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
     }
+
+    /// Enables or disables the recording of market-by-order events.
+    ///
+    /// Market-by-order (level 3) events identify the individual order affected by every
+    /// change, which lets consumers maintain an order-by-order replica of the book, as
+    /// ITCH consumers do. They are recorded alongside the aggregate `OrderEvent`s and
+    /// retrieved with `take_market_by_order_events`. Recording is disabled by default,
+    /// so books that only feed aggregate depth pay nothing for it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `enabled`: Whether subsequent changes should be recorded
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketByOrderEvent, OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_market_by_order_events(true);
+    ///
+    /// let order_id = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).handle.order_id();
+    ///
+    /// let events = order_book.take_market_by_order_events();
+    /// assert!(matches!(
+    ///     events[0],
+    ///     MarketByOrderEvent::Added { order_id: added, quantity: 100, .. } if added == order_id
+    /// ));
+    /// ```
+    pub fn set_market_by_order_events(&mut self, enabled: bool) {
+        self.market_by_order_enabled = enabled;
+    }
+
+    /// Returns and clears the market-by-order events recorded so far, oldest first.
+    pub fn take_market_by_order_events(&mut self) -> Vec<MarketByOrderEvent> {
+        std::mem::take(&mut self.market_by_order_events)
+    }
+
+    /// Returns and clears the market-by-order events recorded so far, oldest first, each
+    /// with its sequence number.
+    ///
+    /// Recorded events are numbered from 1 since the book was created, whether they are
+    /// taken with this method or with `take_market_by_order_events`, so a consumer such
+    /// as a `FollowerBook` can tell when some of them did not reach it.
+    pub fn take_sequenced_market_by_order_events(&mut self) -> Vec<SequencedMarketByOrderEvent> {
+        let events = std::mem::take(&mut self.market_by_order_events);
+        let first_sequence = self.market_by_order_sequence + 1 - events.len() as u64;
+        events
+            .into_iter()
+            .zip(first_sequence..)
+            .map(|(event, sequence)| SequencedMarketByOrderEvent { sequence, event })
+            .collect()
+    }
+
+    /// Returns the sequence number of the last market-by-order event recorded, or 0 if
+    /// none was.
+    pub fn market_by_order_sequence(&self) -> u64 {
+        self.market_by_order_sequence
+    }
+
+    /// Starts or stops attributing order flow to distances from the touch.
+    ///
+    /// While enabled, every change the book describes with a market-by-order event,
+    /// whether or not such events are recorded, is counted in a `DistanceStats` by side
+    /// and by how many ticks behind the best price of its side it happened: at the
+    /// touch, one to five ticks behind, or further. The counts accumulate until the
+    /// statistics are disabled or enabled again, which restarts them.
+    ///
+    /// ## Arguments
+    ///
+    /// * `tick_size`: The price of the ticks distances are measured in, usually the
+    ///   venue's tick size, or `None` to stop counting
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `tick_size` is not positive.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{DistanceBucket, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_distance_stats(Some(Decimal::new(1, 2)));
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// let behind = order_book.insert_order(Order::new(99.97, 10, Side::Bid)).handle;
+    /// order_book.cancel_by_handle(behind).unwrap();
+    ///
+    /// let near = order_book.distance_stats().unwrap().counts(Side::Bid, DistanceBucket::NearTouch);
+    /// assert_eq!((near.adds, near.cancels), (1, 1));
+    /// ```
+    pub fn set_distance_stats(&mut self, tick_size: Option<Decimal>) {
+        self.distance_stats = tick_size.map(DistanceStats::new);
+    }
+
+    /// Returns the order flow attributed to distances from the touch, or `None` unless
+    /// `set_distance_stats` enabled it.
+    pub fn distance_stats(&self) -> Option<&DistanceStats> {
+        self.distance_stats.as_ref()
+    }
+
+    /// Enables or disables the recording of a `LevelRemoved` whenever the last order at
+    /// an exact price leaves the book.
+    ///
+    /// Removals are recorded in addition to the `OrderEvent` bringing the level to zero,
+    /// and retrieved with `take_level_removed_events`. Recording is disabled by default.
+    ///
+    /// ## Arguments
+    ///
+    /// * `enabled`: Whether subsequent level removals should be recorded
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{LevelRemoved, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_level_removed_events(true);
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(100.00, 5, Side::Ask));
+    ///
+    /// let outcome = order_book.match_order(Order::new(100.00, 15, Side::Bid));
+    /// assert_eq!(
+    ///     order_book.take_level_removed_events(),
+    ///     vec![LevelRemoved {
+    ///         price: Decimal::new(100, 0),
+    ///         side: Side::Ask,
+    ///         sequence: outcome.events[1].sequence,
+    ///     }]
+    /// );
+    /// ```
+    pub fn set_level_removed_events(&mut self, enabled: bool) {
+        self.level_removed_enabled = enabled;
+    }
+
+    /// Returns and clears the level removals recorded so far, oldest first.
+    pub fn take_level_removed_events(&mut self) -> Vec<LevelRemoved> {
+        std::mem::take(&mut self.level_removed_events)
+    }
+
+    /// Enables or disables the recording of an `OrderRejected` whenever the book refuses
+    /// an incoming order.
+    ///
+    /// Every entry point submitting a new order records its rejections in addition to
+    /// returning the error: the fallible insertions and matches, iceberg, stop, and day
+    /// orders, client order identifier checks, `apply`, and a `Throttle`'s rate limits.
+    /// Rejections are retrieved with `take_rejection_events`. Recording is disabled by
+    /// default.
+    ///
+    /// ## Arguments
+    ///
+    /// * `enabled`: Whether subsequent rejections should be recorded
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{
+    ///     ExcessPrecision, Order, OrderBook, OrderBookConfig, OrderBookError, PricePrecision, Side,
+    /// };
+    ///
+    /// let mut order_book = OrderBook::with_config(OrderBookConfig {
+    ///     price_precision: Some(PricePrecision {
+    ///         max_scale: 2,
+    ///         excess_precision: ExcessPrecision::Reject,
+    ///     }),
+    ///     ..OrderBookConfig::default()
+    /// });
+    /// order_book.set_rejection_events(true);
+    /// let order = Order::new(100.125, 10, Side::Bid);
+    /// assert!(order_book.try_insert_order(order.clone()).is_err());
+    ///
+    /// let rejections = order_book.take_rejection_events();
+    /// assert_eq!(rejections[0].order, order);
+    /// assert!(matches!(rejections[0].reason, OrderBookError::PriceTooPrecise { .. }));
+    /// ```
+    pub fn set_rejection_events(&mut self, enabled: bool) {
+        self.rejection_events_enabled = enabled;
+    }
+
+    /// Returns and clears the rejections recorded so far, oldest first.
+    pub fn take_rejection_events(&mut self) -> Vec<OrderRejected> {
+        std::mem::take(&mut self.rejection_events)
+    }
+
+    /// Records the rejection of an incoming order if rejections are recorded, and
+    /// returns its reason for the caller to return.
+    pub(crate) fn reject(&mut self, reason: OrderBookError, order: &Order) -> OrderBookError {
+        if self.rejection_events_enabled {
+            self.rejection_events.push(OrderRejected {
+                reason: reason.clone(),
+                order: order.clone(),
+                sequence: self.last_event_sequence,
+            });
+        }
+        reason
+    }
+
+    /// Stores an order under `order_id` and links it at the back of its price level.
+    fn rest_order(&mut self, order_id: OrderId, mut order: Order) -> InsertOutcome {
+        let old_bbo = self.bbo();
+        // Events carry the quantity as an `i64`, so a larger order rests saturated to
+        // it, keeping the book and its consumers in agreement
+        let quantity_delta = self.quantity_delta(order.quantity);
+        let saturated_from =
+            (order.quantity != quantity_delta.unsigned_abs()).then_some(order.quantity);
+        order.quantity = quantity_delta.unsigned_abs();
+        let event = self.publish_event(order.price, quantity_delta, 1, order.side);
+        let (price, quantity, side) = (order.price, order.quantity, order.side);
+        let key = self.key(price);
+        let capacity_before = self.storage_capacity();
+
+        let (slot, generation) = self.orders.insert(OrderNode {
+            order_id,
+            order,
+            fills: FillSummary::default(),
+            metadata: None,
+            previous: None,
+            next: None,
+        });
+        self.order_slots.insert(order_id, slot);
+        self.add_volume(side, i128::from(quantity));
+
+        let price_level_map = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        let price_level = price_level_map.get_or_insert(key);
+        if price_level.push_back(slot, &mut self.orders) {
+            self.aggregation_overflows += 1;
+        }
+
+        let order_count = price_level.order_count as u64;
+        self.refresh_best(side, key);
+        let histograms = self.histograms_mut(side);
+        histograms.order_sizes.add(quantity);
+        if order_count > 1 {
+            histograms.level_order_counts.remove(order_count - 1);
+        }
+        histograms.level_order_counts.add(order_count);
+
+        self.count_allocations(capacity_before, order_count == 1);
+
+        InsertOutcome {
+            handle: OrderHandle {
+                order_id,
+                price,
+                side,
+                slot,
+                generation,
+            },
+            event,
+            bbo_changed: BboChanged::between(old_bbo, self.bbo()),
+            saturated_from,
+        }
+    }
+
+    /// Removes the order at `slot` and records its cancellation.
+    ///
+    /// The slot must be occupied.
+    fn cancel_order_at(&mut self, slot: usize) -> OrderEvent {
+        let (node, event) = self.take_order_at(slot);
+
+        self.publish_market_by_order(MarketByOrderEvent::Cancelled {
+            order_id: node.order_id,
+            price: node.order.price,
+            side: node.order.side,
+            cancelled_quantity: node.order.quantity,
+            remaining_quantity: 0,
+        });
+        event
+    }
+
+    /// Removes `quantity` from the order at `slot`, freeing the order once it reaches zero.
+    ///
+    /// The slot must be occupied and `quantity` must not exceed the remaining quantity.
+    fn decrease_order_at(&mut self, slot: usize, quantity: u64) -> OrderEvent {
+        let node = self.orders.get_mut(slot).expect("slot must be occupied");
+        if quantity == node.order.quantity {
+            return self.take_order_at(slot).1;
+        }
+
+        let remaining = node.order.quantity;
+        node.order.quantity -= quantity;
+        let (price, side) = (node.order.price, node.order.side);
+        let key = self.key(price);
+        self.add_volume(side, -i128::from(quantity));
+
+        let order_sizes = &mut self.histograms_mut(side).order_sizes;
+        order_sizes.remove(remaining);
+        order_sizes.add(remaining - quantity);
+
+        let price_level_map = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        if let Some(price_level) = price_level_map.get_mut(&key) {
+            price_level.total_quantity = price_level.total_quantity.saturating_sub(quantity);
+        }
+        self.refresh_best(side, key);
+
+        let quantity_delta = self.quantity_delta(quantity);
+        self.publish_event(price, -quantity_delta, 0, side)
+    }
+
+    /// Unlinks and frees the order at `slot`, dropping its price level if emptied.
+    ///
+    /// The slot must be occupied.
+    fn take_order_at(&mut self, slot: usize) -> (OrderNode, OrderEvent) {
+        let (price, side) = {
+            let node = self.orders.get(slot).expect("slot must be occupied");
+            (node.order.price, node.order.side)
+        };
+        let key = self.key(price);
+
+        let price_level_map = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        let mut order_count = 0;
+        let mut level_removed = false;
+        if let Some(price_level) = price_level_map.get_mut(&key) {
+            price_level.unlink(slot, &mut self.orders);
+            order_count = price_level.order_count as u64;
+            if price_level.is_empty() {
+                price_level_map.remove(&key);
+                level_removed = true;
+            }
+        }
+        self.refresh_best(side, key);
+
+        let capacity_before = self.storage_capacity();
+        let node = self.orders.remove(slot).expect("slot must be occupied");
+        self.order_slots.remove(&node.order_id);
+        self.count_allocations(capacity_before, false);
+
+        let histograms = self.histograms_mut(side);
+        histograms.order_sizes.remove(node.order.quantity);
+        histograms.level_order_counts.remove(order_count + 1);
+        if order_count > 0 {
+            histograms.level_order_counts.add(order_count);
+        }
+        self.quote_owners.remove(&node.order_id);
+        self.icebergs.remove(&node.order_id);
+        self.client_order_ids.remove_resting(node.order_id);
+        self.session.remove_resting(node.order_id);
+        self.add_volume(side, -i128::from(node.order.quantity));
+
+        let quantity_delta = self.quantity_delta(node.order.quantity);
+        let event = self.publish_event(price, -quantity_delta, -1, side);
+        if level_removed && self.level_removed_enabled {
+            self.level_removed_events.push(LevelRemoved {
+                price,
+                side,
+                sequence: event.sequence,
+            });
+        }
+        (node, event)
+    }
+
+    /// Returns the capacity of the order slab, which changes only on reallocation.
+    fn storage_capacity(&self) -> usize {
+        self.orders.capacity()
+    }
+
+    /// Counts the reallocations since `capacity_before`, and a level creation if any.
+    fn count_allocations(&mut self, capacity_before: usize, level_created: bool) {
+        let index_capacity = self.order_slots.capacity();
+        let index_grown = index_capacity > self.peak_index_capacity;
+        self.peak_index_capacity = self.peak_index_capacity.max(index_capacity);
+        self.allocation_count += u64::from(self.storage_capacity() != capacity_before)
+            + u64::from(index_grown)
+            + u64::from(level_created);
+    }
+
+    /// Adds a delta to the running total of resting quantity for `side`, saturating it
+    /// if it leaves the range of `u64`.
+    fn add_volume(&mut self, side: Side, delta: i128) {
+        let volume = match side {
+            Side::Bid => &mut self.bid_volume,
+            Side::Ask => &mut self.ask_volume,
+        };
+        if saturating_accumulate(volume, delta) {
+            self.aggregation_overflows += 1;
+        }
+    }
+
+    /// Converts a quantity into the magnitude of an event's quantity delta, saturating it
+    /// to `i64::MAX` and counting an aggregation overflow if it does not fit.
+    ///
+    /// Resting orders never exceed `i64::MAX`, as `rest_order` saturates them, so only
+    /// incoming quantities are ever saturated.
+    fn quantity_delta(&mut self, quantity: u64) -> i64 {
+        i64::try_from(quantity).unwrap_or_else(|_| {
+            self.aggregation_overflows += 1;
+            i64::MAX
+        })
+    }
+
+    /// Returns the histograms of `side`.
+    fn histograms_mut(&mut self, side: Side) -> &mut SideHistograms {
+        match side {
+            Side::Bid => &mut self.bid_histograms,
+            Side::Ask => &mut self.ask_histograms,
+        }
+    }
+
+    /// Canonicalizes an incoming price: normalizes it, then moves it onto the key grid.
+    fn canonical_price(&self, price: Decimal) -> Decimal {
+        let price = self.price_normalization.apply(price);
+        self.price_keys.snap(price, self.price_normalization)
+    }
+
+    /// Canonicalizes the price of an incoming order, unless the book's `PricePrecision`
+    /// rejects it.
+    fn checked_price(&self, price: Decimal) -> Result<Decimal> {
+        match self.price_precision {
+            Some(price_precision)
+                if price_precision.excess_precision == ExcessPrecision::Reject
+                    && price_precision.is_exceeded_by(price) =>
+            {
+                Err(OrderBookError::PriceTooPrecise {
+                    price,
+                    max_scale: price_precision.max_scale,
+                })
+            }
+            _ => Ok(self.canonical_price(price)),
+        }
+    }
+
+    /// Checks that an incoming quantity fits in an event's `i64` quantity delta, so it
+    /// can rest without being saturated.
+    fn checked_quantity(quantity: u64) -> Result<u64> {
+        match i64::try_from(quantity) {
+            Ok(_) => Ok(quantity),
+            Err(_) => Err(OrderBookError::QuantityTooLarge(quantity)),
+        }
+    }
+
+    /// Canonicalizes the price of an incoming order like `checked_price` and checks its
+    /// quantity like `checked_quantity`, recording the order's rejection if either is
+    /// refused.
+    fn checked_order(&mut self, order: &Order) -> Result<Decimal> {
+        Self::checked_quantity(order.quantity)
+            .and_then(|_| self.checked_price(order.price))
+            .map_err(|reason| self.reject(reason, order))
+    }
+
+    /// Returns the level key of a canonical price.
+    fn key(&self, price: Decimal) -> PriceKey {
+        self.price_keys.key(price)
+    }
+
+    /// Returns the canonical price of a level key.
+    fn price_of(&self, key: PriceKey) -> Decimal {
+        self.price_keys.price(key, self.price_normalization)
+    }
+
+    /// Creates an order event stamped with the next sequence number.
+    fn publish_event(
+        &mut self,
+        price: Decimal,
+        quantity_delta: i64,
+        order_count_delta: i64,
+        side: Side,
+    ) -> OrderEvent {
+        self.last_event_sequence += 1;
+        OrderEvent {
+            price,
+            quantity_delta,
+            order_count_delta,
+            side,
+            timestamp_nanos: self.now_nanos(),
+            sequence: self.last_event_sequence,
+        }
+    }
+
+    /// Records the market-by-order event for a newly resting order.
+    fn publish_added(&mut self, outcome: &InsertOutcome) {
+        self.publish_market_by_order(MarketByOrderEvent::Added {
+            order_id: outcome.handle.order_id,
+            price: outcome.handle.price,
+            quantity: outcome.event.quantity_delta.unsigned_abs(),
+            side: outcome.handle.side,
+        });
+    }
+
+    /// Records a market-by-order event if recording is enabled.
+    fn publish_market_by_order(&mut self, event: MarketByOrderEvent) {
+        if self.distance_stats.is_some() {
+            let (best_bid, best_ask, _) = self.compute_spread();
+            if let Some(distance_stats) = &mut self.distance_stats {
+                distance_stats.record(&event, best_bid, best_ask);
+            }
+        }
+        if self.market_by_order_enabled {
+            self.market_by_order_sequence += 1;
+            self.market_by_order_events.push(event);
+        }
+    }
+
+    /// Computes the current best bid and best ask prices.
+    ///
+    /// This operation acquires a read lock and is O(1), as the book keeps its best bid
+    /// and ask levels cached and updates them as orders rest, fill, and leave.
+    ///
+    /// ## Returns
+    ///
+    /// A tuple of `(best_bid, best_ask)` where each is `Option<Decimal>`.
+    /// Returns `None` if there are no orders on that side.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.50, 100, Side::Bid));
+    ///
+    /// let (best_bid, best_ask, spread) = order_book.compute_spread();
+    /// assert_eq!(best_bid, Some(Decimal::new(10050, 2)));
+    /// assert_eq!(best_ask, None);
+    /// ```
+    pub fn compute_spread(&self) -> (Option<Decimal>, Option<Decimal>, Option<Decimal>) {
+        let Bbo { best_bid, best_ask } = self.bbo();
+        let spread = best_bid.and_then(|b| best_ask.map(|a| a - b));
+
+        (best_bid, best_ask, spread)
+    }
+
+    /// Returns the lowest bid price, i.e. the bid furthest from the touch.
+    ///
+    /// ## Returns
+    ///
+    /// The worst bid, or `None` if there are no bids
+    pub fn worst_bid(&self) -> Option<Decimal> {
+        self.bids.keys().next().map(|key| self.price_of(*key))
+    }
+
+    /// Returns the highest ask price, i.e. the ask furthest from the touch.
+    ///
+    /// ## Returns
+    ///
+    /// The worst ask, or `None` if there are no asks
+    pub fn worst_ask(&self) -> Option<Decimal> {
+        self.asks.keys().next_back().map(|key| self.price_of(*key))
+    }
+
+    /// Returns how many times a resting total, i.e. a level's total quantity or a side's
+    /// total volume, left the range of `u64` and was saturated instead, or an order's
+    /// quantity exceeded the `i64` range of `OrderEvent::quantity_delta` and the order
+    /// rested with `i64::MAX` instead.
+    ///
+    /// Such totals only overflow with quantities far beyond real markets, e.g. from
+    /// synthetic feeds, and are saturated rather than wrapped so the book keeps working
+    /// and its events keep describing it; a nonzero count tells that `total_volume`, the
+    /// level quantities, or the saturated orders are no longer exact.
+    pub fn aggregation_overflow_count(&self) -> u64 {
+        self.aggregation_overflows
+    }
+
+    /// Returns the total quantity resting on one side of the book.
+    ///
+    /// The total is maintained as orders are inserted, executed, reduced, and cancelled,
+    /// so this is $O(1)$.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    ///
+    /// ## Returns
+    ///
+    /// The sum of the remaining quantities of every order on that side
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(99.00, 5, Side::Bid));
+    /// assert_eq!(order_book.total_volume(Side::Bid), 15);
+    /// assert_eq!(order_book.total_volume(Side::Ask), 0);
+    /// ```
+    pub fn total_volume(&self, side: Side) -> u64 {
+        match side {
+            Side::Bid => self.bid_volume,
+            Side::Ask => self.ask_volume,
+        }
+    }
+
+    /// Returns the distribution of the remaining sizes of the orders resting on one side.
+    ///
+    /// The histogram is maintained as orders are inserted, executed, reduced, and
+    /// cancelled, so it is available without walking the book.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.00, 1, Side::Bid));
+    /// order_book.insert_order(Order::new(100.00, 5, Side::Bid));
+    /// order_book.insert_order(Order::new(99.00, 7, Side::Bid));
+    ///
+    /// // One order in [1, 2) and two in [4, 8)
+    /// let buckets: Vec<_> = order_book.order_size_histogram(Side::Bid).buckets().collect();
+    /// assert_eq!(buckets, vec![(1, 1), (4, 2)]);
+    /// ```
+    pub fn order_size_histogram(&self, side: Side) -> &Histogram {
+        match side {
+            Side::Bid => &self.bid_histograms.order_sizes,
+            Side::Ask => &self.ask_histograms.order_sizes,
+        }
+    }
+
+    /// Returns the distribution of the number of orders per price level on one side.
+    ///
+    /// Like `order_size_histogram`, it is maintained incrementally.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    pub fn level_order_count_histogram(&self, side: Side) -> &Histogram {
+        match side {
+            Side::Bid => &self.bid_histograms.level_order_counts,
+            Side::Ask => &self.ask_histograms.level_order_counts,
+        }
+    }
+
+    /// Returns the lowest and highest prices resting on one side of the book.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    ///
+    /// ## Returns
+    ///
+    /// A tuple of `(lowest, highest)`, or `None` if that side is empty
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(101.50, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(103.00, 5, Side::Ask));
+    ///
+    /// let range = order_book.price_range(Side::Ask);
+    /// assert_eq!(range, Some((Decimal::new(1015, 1), Decimal::new(103, 0))));
+    /// ```
+    pub fn price_range(&self, side: Side) -> Option<(Decimal, Decimal)> {
+        let price_level_map = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+
+        let lowest = self.price_of(*price_level_map.first_key_value()?.0);
+        let highest = self.price_of(*price_level_map.last_key_value()?.0);
+        Some((lowest, highest))
+    }
+
+    /// Returns the cumulative quantity available versus price distance from the touch.
+    ///
+    /// Levels are walked from the best price outwards; each point pairs the distance of
+    /// a level from the best price with the total quantity resting up to that level,
+    /// which is the quantity an aggressive order could fill without moving further.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) whose liquidity is measured
+    /// * `max_levels`: The maximum number of price levels to include
+    ///
+    /// ## Returns
+    ///
+    /// One `(distance, cumulative_quantity)` point per level, closest level first
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(100.50, 20, Side::Ask));
+    ///
+    /// let curve = order_book.liquidity_curve(Side::Ask, 10);
+    /// assert_eq!(curve, vec![(Decimal::ZERO, 10), (Decimal::new(5, 1), 30)]);
+    /// ```
+    pub fn liquidity_curve(&self, side: Side, max_levels: usize) -> Vec<(Decimal, u64)> {
+        let levels = |(key, price_level): (&PriceKey, &PriceLevel)| {
+            (self.price_of(*key), price_level.total_quantity)
+        };
+
+        match side {
+            Side::Bid => cumulative_curve(self.bids.iter().rev().map(levels), max_levels),
+            Side::Ask => cumulative_curve(self.asks.iter().map(levels), max_levels),
+        }
+    }
+
+    /// Estimates the cost of an order executing immediately against the book.
+    ///
+    /// The order is walked through the opposite side level by level, as `match_order`
+    /// would fill it, without modifying the book. The resulting average price is then
+    /// compared with the mid and the touch. Slippage is relative to the reference price,
+    /// so it is not reported against a reference of 0, which books of spreads or other
+    /// instruments priced around zero can have.
+    ///
+    /// ## Arguments
+    ///
+    /// * `quantity`: The quantity to execute
+    /// * `side`: The side of the incoming order (`Bid` buys from the asks)
+    ///
+    /// ## Returns
+    ///
+    /// The estimated `Impact`, or `None` if `quantity` is zero or the opposite side is empty
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(102.00, 10, Side::Ask));
+    ///
+    /// let impact = order_book.estimate_impact(20, Side::Bid).unwrap();
+    /// assert_eq!(impact.avg_fill_price, Decimal::new(1015, 1));
+    /// assert_eq!(impact.slippage_bps_vs_mid, Some(Decimal::new(150, 0)));
+    /// assert_eq!(impact.depth_consumed_levels, 2);
+    /// ```
+    pub fn estimate_impact(&self, quantity: u64, side: Side) -> Option<Impact> {
+        if quantity == 0 {
+            return None;
+        }
+
+        let opposite_levels: Box<dyn Iterator<Item = (&PriceKey, &PriceLevel)>> = match side {
+            Side::Bid => Box::new(self.asks.iter()),
+            Side::Ask => Box::new(self.bids.iter().rev()),
+        };
+
+        let mut touch = None;
+        let mut filled_quantity = 0u64;
+        let mut notional = Decimal::ZERO;
+        let mut depth_consumed_levels = 0;
+        for (key, price_level) in opposite_levels {
+            if filled_quantity == quantity {
+                break;
+            }
+            let price = self.price_of(*key);
+            touch.get_or_insert(price);
+
+            let level_fill = price_level.total_quantity.min(quantity - filled_quantity);
+            filled_quantity += level_fill;
+            notional += price * Decimal::from(level_fill);
+            depth_consumed_levels += 1;
+        }
+
+        let touch = touch?;
+        let avg_fill_price = notional / Decimal::from(filled_quantity);
+        let slippage_bps = |reference: Decimal| {
+            let slippage = match side {
+                Side::Bid => avg_fill_price - reference,
+                Side::Ask => reference - avg_fill_price,
+            };
+            if reference.is_zero() {
+                return None;
+            }
+            Some(slippage / reference * Decimal::from(10_000))
+        };
+
+        let (best_bid, best_ask, _) = self.compute_spread();
+        let mid = best_bid
+            .zip(best_ask)
+            .map(|(bid, ask)| (bid + ask) / Decimal::TWO);
+
+        Some(Impact {
+            avg_fill_price,
+            slippage_bps_vs_mid: mid.and_then(slippage_bps),
+            slippage_bps_vs_touch: slippage_bps(touch),
+            depth_consumed_levels,
+            filled_quantity,
+        })
+    }
+
+    /// Returns the price and total quantity of every level on one side, in ascending price order.
+    pub(crate) fn level_quantities(
+        &self,
+        side: Side,
+    ) -> impl DoubleEndedIterator<Item = (Decimal, u64)> + '_ {
+        let price_level_map = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        price_level_map
+            .iter()
+            .map(|(key, price_level)| (self.price_of(*key), price_level.total_quantity))
+    }
+
+    /// Returns the price, total quantity, and order count of every level on one side, in
+    /// ascending price order.
+    pub(crate) fn level_infos(
+        &self,
+        side: Side,
+    ) -> impl Iterator<Item = (Decimal, LevelInfo)> + '_ {
+        let price_level_map = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        price_level_map.iter().map(|(key, price_level)| {
+            let level_info = LevelInfo {
+                quantity: price_level.total_quantity,
+                order_count: price_level.order_count as u64,
+            };
+            (self.price_of(*key), level_info)
+        })
+    }
+
+    /// Returns the total quantity resting at every exact price of one side.
+    ///
+    /// These are the book's own levels, as maintained by every insertion, fill, and
+    /// cancellation, copied out in $O(N)$ in the number of levels. Unlike the
+    /// `AggregatedDepthMap` of a `MarketDepthCache`, nothing is bucketed: orders at
+    /// 100.25 and 100.75 are reported apart.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side to copy
+    ///
+    /// ## Returns
+    ///
+    /// An `ExactPriceLevelMap` from every price with resting orders to their total
+    /// quantity
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{ExactPriceLevelMap, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.25, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(100.25, 5, Side::Bid));
+    /// order_book.insert_order(Order::new(100.75, 4, Side::Bid));
+    ///
+    /// assert_eq!(
+    ///     order_book.exact_price_levels(Side::Bid),
+    ///     ExactPriceLevelMap::from([(Decimal::new(10025, 2), 15), (Decimal::new(10075, 2), 4)])
+    /// );
+    /// assert_eq!(order_book.exact_quantity_at(Decimal::new(10075, 2), Side::Bid), 4);
+    /// ```
+    pub fn exact_price_levels(&self, side: Side) -> ExactPriceLevelMap {
+        self.level_quantities(side).collect()
+    }
+
+    /// Returns the total quantity resting at an exact price, or 0 if no order rests
+    /// there.
+    ///
+    /// The price is normalized like those of incoming orders. The lookup is
+    /// $O(\log{N})$, without copying the side like `exact_price_levels`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The exact price to query
+    /// * `side`: The side (bid or ask) to query
+    pub fn exact_quantity_at(&self, price: Decimal, side: Side) -> u64 {
+        self.level(price, side)
+            .map_or(0, |price_level| price_level.total_quantity())
+    }
+
+    /// Returns the number of distinct price levels on the bid side.
+    ///
+    /// ## Returns
+    ///
+    /// The count of unique bid price levels
+    pub fn bid_levels_count(&self) -> usize {
+        self.bids.len()
+    }
+
+    /// Returns the number of distinct price levels on the ask side.
+    ///
+    /// ## Returns
+    ///
+    /// The count of unique ask price levels
+    pub fn ask_levels_count(&self) -> usize {
+        self.asks.len()
+    }
+
+    /// Returns the total number of orders at a specific price level.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The price level to query
+    /// * `side`: The side (bid or ask) to query
+    ///
+    /// ## Returns
+    ///
+    /// The number of orders at that price level, or 0 if no orders exist
+    #[deprecated(note = "use `level`, whose view also has the quantity and the orders")]
+    pub fn orders_at_exact_price_level(&self, price: Decimal, side: Side) -> usize {
+        self.level(price, side)
+            .map_or(0, |price_level| price_level.order_count())
+    }
+}
+
+/// The incrementally maintained distributions of one side of the book.
+#[derive(Debug, Clone, Default)]
+struct SideHistograms {
+    /// Remaining size of every resting order
+    order_sizes: Histogram,
+    /// Number of orders at every price level
+    level_order_counts: Histogram,
 }
 
 impl Default for OrderBook {
@@ -0,0 +1,125 @@
+use super::OrderBook;
+use crate::price_key::PriceKey;
+use crate::types::{Bbo, BboChanged, Side};
+use rust_decimal::Decimal;
+
+/// The best level of one side, kept up to date by every change to that side so the
+/// top of book is read without walking the level map.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BestLevel {
+    /// The level's key
+    key: PriceKey,
+    /// The level's canonical price
+    price: Decimal,
+    /// The total quantity resting at the level
+    quantity: u64,
+}
+
+impl OrderBook {
+    /// Returns the best bid and ask prices.
+    pub fn bbo(&self) -> Bbo {
+        Bbo {
+            best_bid: self.best_bid.map(|best| best.price),
+            best_ask: self.best_ask.map(|best| best.price),
+        }
+    }
+
+    /// Returns and clears the change of the best bid and ask prices since it was last
+    /// taken.
+    ///
+    /// Inserts, matches, and replacements report the change they cause in their
+    /// outcome; this covers the operations returning only events, such as
+    /// `cancel_order`, `reduce_order`, or `apply`, as well as sequences of operations.
+    /// Changes that were undone before being taken are not reported.
+    ///
+    /// ## Returns
+    ///
+    /// The best bid and ask when this was last called, or when the book was created,
+    /// and now, or `None` if they are the same
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Bbo, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let outcome = order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// assert!(outcome.bbo_changed.is_some());
+    /// order_book.take_bbo_change();
+    ///
+    /// order_book.cancel_order(outcome.handle.order_id()).unwrap();
+    /// let change = order_book.take_bbo_change().unwrap();
+    /// assert_eq!(change.old.best_bid, Some(Decimal::new(100, 0)));
+    /// assert_eq!(change.new, Bbo::default());
+    /// assert_eq!(order_book.take_bbo_change(), None);
+    /// ```
+    pub fn take_bbo_change(&mut self) -> Option<BboChanged> {
+        let new = self.bbo();
+        let old = std::mem::replace(&mut self.reported_bbo, new);
+        BboChanged::between(old, new)
+    }
+
+    /// Returns the best price of a side and the total quantity resting at it.
+    pub(crate) fn best_level(&self, side: Side) -> Option<(Decimal, u64)> {
+        self.best(side).map(|best| (best.price, best.quantity))
+    }
+
+    /// Returns the best price of a side and the key of its level.
+    pub(super) fn best_price_and_key(&self, side: Side) -> Option<(Decimal, PriceKey)> {
+        self.best(side).map(|best| (best.price, best.key))
+    }
+
+    /// Updates the cached best level of a side after the level at `key` changed.
+    ///
+    /// Every change of a level's orders calls this, so the cache only moves when the
+    /// changed level is at or better than the cached one, and only walks the level map
+    /// when the best level itself was removed.
+    pub(super) fn refresh_best(&mut self, side: Side, key: PriceKey) {
+        let price_level_map = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        let cached = self.best(side);
+        let best = match price_level_map.get(&key) {
+            Some(price_level) => {
+                let at_or_better = cached.is_none_or(|best| match side {
+                    Side::Bid => key >= best.key,
+                    Side::Ask => key <= best.key,
+                });
+                if !at_or_better {
+                    return;
+                }
+                Some((key, price_level))
+            }
+            None if cached.is_some_and(|best| best.key == key) => match side {
+                Side::Bid => price_level_map.last_key_value(),
+                Side::Ask => price_level_map.first_key_value(),
+            }
+            .map(|(key, price_level)| (*key, price_level)),
+            None => return,
+        };
+        let best = best.map(|(key, price_level)| BestLevel {
+            key,
+            price: self.price_of(key),
+            quantity: price_level.total_quantity,
+        });
+        *self.best_mut(side) = best;
+    }
+
+    /// Returns the cached best level of a side.
+    fn best(&self, side: Side) -> Option<BestLevel> {
+        match side {
+            Side::Bid => self.best_bid,
+            Side::Ask => self.best_ask,
+        }
+    }
+
+    /// Returns the cached best level of a side for updating.
+    fn best_mut(&mut self, side: Side) -> &mut Option<BestLevel> {
+        match side {
+            Side::Bid => &mut self.best_bid,
+            Side::Ask => &mut self.best_ask,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use super::{BookClock, OrderBook};
+use crate::types::Checkpoint;
+
+impl OrderBook {
+    /// Captures the book's state for a write-ahead log checkpoint.
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            book: self.snapshot(),
+            trading_state: self.trading_state,
+            manual_time_nanos: match self.clock {
+                BookClock::System => None,
+                BookClock::Manual(timestamp_nanos) => Some(timestamp_nanos),
+            },
+            session: self.session(),
+            day_orders: self.session.day_orders(),
+            client_order_ids: self.client_order_ids.resting_orders(),
+            next_trade_id: self.next_trade_id,
+        }
+    }
+
+    /// Restores a checkpoint into this empty book, keeping the book's settings.
+    pub(crate) fn restore_checkpoint(&mut self, checkpoint: &Checkpoint) {
+        self.rest_snapshot(&checkpoint.book);
+        self.trading_state = checkpoint.trading_state;
+        self.next_trade_id = checkpoint.next_trade_id;
+        if let Some(timestamp_nanos) = checkpoint.manual_time_nanos {
+            self.set_time(timestamp_nanos);
+        }
+        self.session
+            .restore(checkpoint.session, &checkpoint.day_orders);
+        for (order_id, client_order_id) in &checkpoint.client_order_ids {
+            self.client_order_ids
+                .restore_resting(*order_id, *client_order_id);
+        }
+    }
+}
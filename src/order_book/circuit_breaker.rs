@@ -0,0 +1,82 @@
+use super::OrderBook;
+use crate::types::{LuldBands, TradingState, TradingStateChange};
+use rust_decimal::Decimal;
+
+impl OrderBook {
+    /// Configures or removes the limit-up/limit-down bands enforced by `match_order`.
+    ///
+    /// While bands are set and a reference price is known, an execution that would print
+    /// outside the bands is not performed: the book transitions to `TradingState::Halted`
+    /// and the `match_order` call reports the breach in its `state_change`. The reference
+    /// price follows the last trade and can also be set externally.
+    ///
+    /// ## Arguments
+    ///
+    /// * `luld_bands`: The bands to enforce, or `None` to disable the circuit breaker
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{LuldBands, Order, OrderBook, Side, TradingState};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_luld_bands(Some(LuldBands { band_fraction: Decimal::new(5, 2) }));
+    /// order_book.set_reference_price(Decimal::new(100, 0));
+    /// order_book.insert_order(Order::new(106.00, 10, Side::Ask));
+    ///
+    /// // 106 is outside 100 ± 5%, so the book halts instead of trading
+    /// let outcome = order_book.match_order(Order::new(110.00, 10, Side::Bid));
+    /// assert_eq!(outcome.state_change.unwrap().state, TradingState::Halted);
+    /// assert!(order_book.trades().is_empty());
+    /// ```
+    pub fn set_luld_bands(&mut self, luld_bands: Option<LuldBands>) {
+        self.luld_bands = luld_bands;
+    }
+
+    /// Sets the reference price the bands are computed from, e.g. an official close.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The new reference price
+    pub fn set_reference_price(&mut self, price: Decimal) {
+        self.reference_price = Some(self.price_normalization.apply(price));
+    }
+
+    /// Returns the reference price: the last trade price or the last externally set one.
+    pub fn reference_price(&self) -> Option<Decimal> {
+        self.reference_price
+    }
+
+    /// Returns whether the book is currently matching incoming orders.
+    pub fn trading_state(&self) -> TradingState {
+        self.trading_state
+    }
+
+    /// Resumes continuous trading after a halt.
+    ///
+    /// Orders that were submitted while halted rest in the book as they are; the book
+    /// does not run an uncrossing auction, so the caller is responsible for clearing a
+    /// crossed book (for instance by setting a new reference price and re-submitting).
+    pub fn resume_trading(&mut self) {
+        self.trading_state = TradingState::Continuous;
+    }
+
+    /// Halts the book if a trade at `price` would print outside the bands.
+    pub(super) fn check_price_bands(&mut self, price: Decimal) -> Option<TradingStateChange> {
+        let reference_price = self.reference_price?;
+        let (lower_band, upper_band) = self.luld_bands?.around(reference_price);
+        if (lower_band..=upper_band).contains(&price) {
+            return None;
+        }
+
+        self.trading_state = TradingState::Halted;
+        Some(TradingStateChange {
+            state: TradingState::Halted,
+            reference_price,
+            breach_price: price,
+            lower_band,
+            upper_band,
+        })
+    }
+}
@@ -0,0 +1,260 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::memory::hash_map_bytes;
+use crate::types::{ClientOrderId, InsertOutcome, MatchOutcome, Order, OrderId};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// How long a client order identifier is remembered by default after its submission.
+const DEFAULT_CLIENT_ORDER_ID_WINDOW: Duration = Duration::from_secs(60);
+
+/// The client order identifiers of resting orders and of recent submissions.
+#[derive(Debug, Clone)]
+pub(super) struct ClientOrderIds {
+    /// How long a submitted identifier is rejected as a duplicate
+    window: Duration,
+    /// The resting order submitted under each identifier
+    resting: HashMap<ClientOrderId, OrderId>,
+    /// The identifier each resting order was submitted under
+    by_order: HashMap<OrderId, ClientOrderId>,
+    /// The order each identifier submitted within the window was assigned
+    recent: HashMap<ClientOrderId, OrderId>,
+    /// Submission time of every identifier in `recent`, oldest first
+    submissions: VecDeque<(u64, ClientOrderId)>,
+}
+
+impl ClientOrderIds {
+    pub(super) fn new() -> Self {
+        ClientOrderIds {
+            window: DEFAULT_CLIENT_ORDER_ID_WINDOW,
+            resting: HashMap::new(),
+            by_order: HashMap::new(),
+            recent: HashMap::new(),
+            submissions: VecDeque::new(),
+        }
+    }
+
+    /// Forgets the identifier of an order leaving the book.
+    pub(super) fn remove_resting(&mut self, order_id: OrderId) {
+        if let Some(client_order_id) = self.by_order.remove(&order_id) {
+            self.resting.remove(&client_order_id);
+        }
+    }
+
+    /// Returns the identifier of each resting order submitted with one, in order
+    /// identifier order.
+    pub(super) fn resting_orders(&self) -> Vec<(OrderId, ClientOrderId)> {
+        let mut resting_orders: Vec<_> = self
+            .by_order
+            .iter()
+            .map(|(order_id, client_order_id)| (*order_id, *client_order_id))
+            .collect();
+        resting_orders.sort_unstable();
+        resting_orders
+    }
+
+    /// Records the identifier of a resting order restored from a checkpoint or carried
+    /// over to a replacement.
+    pub(super) fn restore_resting(&mut self, order_id: OrderId, client_order_id: ClientOrderId) {
+        self.resting.insert(client_order_id, order_id);
+        self.by_order.insert(order_id, client_order_id);
+    }
+
+    /// Releases the spare capacity of the identifier maps.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.resting.shrink_to_fit();
+        self.by_order.shrink_to_fit();
+        self.recent.shrink_to_fit();
+        self.submissions.shrink_to_fit();
+    }
+
+    /// Estimates the heap bytes held by the identifier maps.
+    pub(super) fn memory_bytes(&self) -> usize {
+        hash_map_bytes(&self.resting)
+            + hash_map_bytes(&self.by_order)
+            + hash_map_bytes(&self.recent)
+            + self.submissions.capacity() * std::mem::size_of::<(u64, ClientOrderId)>()
+    }
+
+    /// Returns the order already submitted under `client_order_id`, if it is still
+    /// resting or was submitted within the window ending at `now_nanos`.
+    fn duplicate_of(&mut self, client_order_id: ClientOrderId, now_nanos: u64) -> Option<OrderId> {
+        let window_nanos = u64::try_from(self.window.as_nanos()).unwrap_or(u64::MAX);
+        let window_start = now_nanos.saturating_sub(window_nanos);
+        while let Some(&(submitted_nanos, expired)) = self.submissions.front() {
+            if submitted_nanos >= window_start {
+                break;
+            }
+            self.recent.remove(&expired);
+            self.submissions.pop_front();
+        }
+
+        self.recent
+            .get(&client_order_id)
+            .or_else(|| self.resting.get(&client_order_id))
+            .copied()
+    }
+
+    /// Records a submission, and the resting order if part of it rests.
+    fn record(
+        &mut self,
+        client_order_id: ClientOrderId,
+        order_id: OrderId,
+        resting: bool,
+        now_nanos: u64,
+    ) {
+        self.recent.insert(client_order_id, order_id);
+        self.submissions.push_back((now_nanos, client_order_id));
+        if resting {
+            self.resting.insert(client_order_id, order_id);
+            self.by_order.insert(order_id, client_order_id);
+        }
+    }
+}
+
+impl OrderBook {
+    /// Inserts an order like `insert_order`, unless its client order identifier was
+    /// already used.
+    ///
+    /// A submission is a duplicate if an order submitted under the same identifier is
+    /// still resting, or was submitted within the deduplication window (see
+    /// `set_client_order_id_window`) even if it has left the book since. Retrying a
+    /// submission whose acknowledgement was lost over a flaky transport therefore never
+    /// inserts the order twice.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to insert
+    /// * `client_order_id`: The caller's identifier for the order
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome`, or `OrderBookError::DuplicateClientOrderId` with the order
+    /// the identifier was first assigned, or `OrderBookError::PriceTooPrecise` if the
+    /// book's `PricePrecision` rejects the order's price
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{ClientOrderId, Order, OrderBook, OrderBookError, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let client_order_id = ClientOrderId(42);
+    ///
+    /// let order_id = order_book
+    ///     .insert_order_with_client_id(Order::new(100.00, 10, Side::Bid), client_order_id)
+    ///     .unwrap()
+    ///     .handle
+    ///     .order_id();
+    ///
+    /// // The retry is rejected, pointing at the original order
+    /// let retry = order_book.insert_order_with_client_id(Order::new(100.00, 10, Side::Bid), client_order_id);
+    /// assert_eq!(
+    ///     retry.unwrap_err(),
+    ///     OrderBookError::DuplicateClientOrderId { client_order_id, order_id }
+    /// );
+    /// assert_eq!(order_book.order_id_by_client_id(client_order_id), Some(order_id));
+    /// ```
+    pub fn insert_order_with_client_id(
+        &mut self,
+        order: Order,
+        client_order_id: ClientOrderId,
+    ) -> Result<InsertOutcome> {
+        let now_nanos = self.now_nanos();
+        self.reject_duplicate(&order, client_order_id, now_nanos)?;
+
+        let outcome = self.try_insert_order(order)?;
+        self.client_order_ids
+            .record(client_order_id, outcome.handle.order_id, true, now_nanos);
+        Ok(outcome)
+    }
+
+    /// Matches an order like `match_order`, unless its client order identifier was
+    /// already used.
+    ///
+    /// Duplicates are detected as in `insert_order_with_client_id`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    /// * `client_order_id`: The caller's identifier for the order
+    ///
+    /// ## Returns
+    ///
+    /// The `MatchOutcome`, or `OrderBookError::DuplicateClientOrderId` with the order
+    /// the identifier was first assigned, or `OrderBookError::PriceTooPrecise` if the
+    /// book's `PricePrecision` rejects the order's price
+    pub fn match_order_with_client_id(
+        &mut self,
+        order: Order,
+        client_order_id: ClientOrderId,
+    ) -> Result<MatchOutcome> {
+        let now_nanos = self.now_nanos();
+        self.reject_duplicate(&order, client_order_id, now_nanos)?;
+
+        let outcome = self.try_match_order(order)?;
+        self.client_order_ids.record(
+            client_order_id,
+            outcome.order_id,
+            outcome.handle.is_some(),
+            now_nanos,
+        );
+        Ok(outcome)
+    }
+
+    /// Sets how long a client order identifier is rejected after its submission.
+    ///
+    /// Identifiers of resting orders are rejected regardless of the window. The default
+    /// window is one minute.
+    ///
+    /// ## Arguments
+    ///
+    /// * `window`: How long submitted identifiers are remembered
+    pub fn set_client_order_id_window(&mut self, window: Duration) {
+        self.client_order_ids.window = window;
+    }
+
+    /// Returns the resting order submitted under a client order identifier, if any.
+    ///
+    /// Replacing an order drops its client order identifier, as the replacement is a
+    /// new order.
+    ///
+    /// ## Arguments
+    ///
+    /// * `client_order_id`: The caller's identifier for the order
+    pub fn order_id_by_client_id(&self, client_order_id: ClientOrderId) -> Option<OrderId> {
+        self.client_order_ids.resting.get(&client_order_id).copied()
+    }
+
+    /// Returns the client order identifier a resting order was submitted under, if any.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier assigned by the book
+    pub fn client_order_id(&self, order_id: OrderId) -> Option<ClientOrderId> {
+        self.client_order_ids.by_order.get(&order_id).copied()
+    }
+
+    /// Returns an error, recording the order's rejection, if `client_order_id` was
+    /// already used.
+    fn reject_duplicate(
+        &mut self,
+        order: &Order,
+        client_order_id: ClientOrderId,
+        now_nanos: u64,
+    ) -> Result<()> {
+        match self
+            .client_order_ids
+            .duplicate_of(client_order_id, now_nanos)
+        {
+            Some(order_id) => Err(self.reject(
+                OrderBookError::DuplicateClientOrderId {
+                    client_order_id,
+                    order_id,
+                },
+                order,
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::types::{Command, OrderEvent};
+
+impl OrderBook {
+    /// Applies a command and returns the events describing its effect on the book.
+    ///
+    /// This is the single entry point behind which every state change of the book can
+    /// be scripted, journaled, and replayed uniformly: applying the same commands to two
+    /// fresh books produces the same events and the same books.
+    ///
+    /// ## Arguments
+    ///
+    /// * `command`: The command to apply
+    ///
+    /// ## Returns
+    ///
+    /// The events of every level change caused by the command, in order, or the error of
+    /// the underlying operation (e.g. `OrderBookError::OrderNotFound` for an unknown order,
+    /// or `OrderBookError::PriceTooPrecise` for a price the book's `PricePrecision`
+    /// rejects)
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Command, Order, OrderBook, OrderId, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.apply(Command::Insert(Order::new(100.00, 10, Side::Bid))).unwrap();
+    ///
+    /// // Lowering the quantity at the same price keeps the order in place
+    /// let events = order_book
+    ///     .apply(Command::Amend { order_id: OrderId(1), price: Decimal::new(100, 0), quantity: 4 })
+    ///     .unwrap();
+    /// assert_eq!(events[0].quantity_delta, -6);
+    /// assert!(order_book.get_order(OrderId(1)).is_some());
+    ///
+    /// order_book.apply(Command::Clear).unwrap();
+    /// assert_eq!(order_book.order_count(), 0);
+    /// ```
+    pub fn apply(&mut self, command: Command) -> Result<Vec<OrderEvent>> {
+        let mut events = Vec::new();
+        self.apply_into(command, &mut events)?;
+        Ok(events)
+    }
+
+    /// Applies a command like `apply`, appending the events to a buffer of the caller's
+    /// instead of returning them.
+    ///
+    /// A gateway applying commands at a high rate can reuse the same buffer across
+    /// calls, clearing it once the events are forwarded, so inserting, matching,
+    /// cancelling, and amending allocate no event vector of their own.
+    ///
+    /// ## Arguments
+    ///
+    /// * `command`: The command to apply
+    /// * `events`: The buffer the events are appended to, in order
+    ///
+    /// ## Returns
+    ///
+    /// The error of the underlying operation, as for `apply`, in which case the events
+    /// of a failed command are not appended
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Command, Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let mut events = Vec::with_capacity(16);
+    ///
+    /// for command in [
+    ///     Command::Insert(Order::new(100.00, 10, Side::Ask)),
+    ///     Command::Match(Order::new(100.00, 4, Side::Bid)),
+    /// ] {
+    ///     events.clear();
+    ///     order_book.apply_into(command, &mut events).unwrap();
+    ///     assert_eq!(events.len(), 1);
+    /// }
+    /// assert_eq!(events[0].quantity_delta, -4);
+    /// ```
+    pub fn apply_into(&mut self, command: Command, events: &mut Vec<OrderEvent>) -> Result<()> {
+        match command {
+            Command::Insert(order) => events.push(self.try_insert_order(order)?.event),
+            Command::InsertWithClientId(order, client_order_id) => events.push(
+                self.insert_order_with_client_id(order, client_order_id)?
+                    .event,
+            ),
+            Command::Match(order) => {
+                self.match_into(order, events)?;
+            }
+            Command::Cancel(order_id) => events.push(self.cancel_order(order_id)?),
+            Command::Amend {
+                order_id,
+                price,
+                quantity,
+            } => {
+                let order = self
+                    .get_order(order_id)
+                    .ok_or(OrderBookError::OrderNotFound(order_id))?;
+                let remaining = order.quantity;
+
+                if quantity == 0 {
+                    events.push(self.cancel_order(order_id)?);
+                } else if self.canonical_price(price) != order.price || quantity > remaining {
+                    let outcome = self.replace_order(order_id, price, quantity)?;
+                    events.extend([outcome.removed, outcome.added]);
+                } else if quantity < remaining {
+                    events.push(self.reduce_order(order_id, remaining - quantity)?);
+                }
+            }
+            Command::Clear => events.extend(self.cancel_where(|_| true)),
+            Command::SetState(trading_state) => self.trading_state = trading_state,
+            Command::SetTime(timestamp_nanos) => self.set_time(timestamp_nanos),
+            Command::InsertDay(order) => {
+                self.checked_order(&order)?;
+                events.push(self.insert_day_order(order).event);
+            }
+            Command::MatchDay(order) => {
+                if let Some(handle) = self.match_into(order, events)?.handle {
+                    self.session.insert_day_order(handle.order_id);
+                }
+            }
+            Command::EndSession => events.extend(self.end_session().expired),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use super::OrderBook;
+use crate::types::{CompactionReport, OrderHandle, OrderId};
+use std::collections::HashMap;
+
+impl OrderBook {
+    /// Releases the memory a long session left unused, e.g. during a quiet period.
+    ///
+    /// Cancelled and filled orders leave vacant slots behind them, and the book's maps
+    /// and logs keep the capacity of their largest size. Compaction moves the orders
+    /// resting past the first vacant slots into them, releases the vacant slots, and
+    /// shrinks every container to what it holds, including the capacity reserved by
+    /// an `order_capacity`. Orders keep their identifiers, queue positions, fills, and
+    /// metadata, and quotes keep their orders, but the handles of the moved orders
+    /// become stale: operations taking a handle report them with
+    /// `OrderBookError::StaleHandle`, while those taking an `OrderId` are unaffected.
+    /// Compaction is $O(N)$ in the number of orders and publishes no events.
+    ///
+    /// ## Returns
+    ///
+    /// A `CompactionReport` with the estimated heap usage before and after, and the
+    /// number of slots released and orders moved
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let handles: Vec<_> = (0..1_000)
+    ///     .map(|_| order_book.insert_order(Order::new(100.00, 10, Side::Bid)).handle)
+    ///     .collect();
+    /// for handle in &handles[..999] {
+    ///     order_book.cancel_by_handle(*handle).unwrap();
+    /// }
+    ///
+    /// let report = order_book.compact();
+    /// assert_eq!(report.released_slots, 999);
+    /// assert_eq!(report.relocated_orders, 1);
+    /// assert!(report.bytes_reclaimed() > 0);
+    /// assert!(order_book.cancel_by_handle(handles[999]).is_err());
+    /// assert!(order_book.cancel_order(handles[999].order_id()).is_ok());
+    /// ```
+    pub fn compact(&mut self) -> CompactionReport {
+        let bytes_before = self.approx_memory_bytes();
+        let slots_before = self.orders.slot_count();
+
+        let relocations = self.orders.compact();
+        if !relocations.is_empty() {
+            self.relink(&relocations.iter().copied().collect());
+        }
+
+        self.bids.shrink_to_fit();
+        self.asks.shrink_to_fit();
+        self.order_slots.shrink_to_fit();
+        self.peak_index_capacity = self.order_slots.capacity();
+        self.trades.shrink_to_fit();
+        self.market_by_order_events.shrink_to_fit();
+        self.level_removed_events.shrink_to_fit();
+        self.quotes.shrink_to_fit();
+        self.quote_owners.shrink_to_fit();
+        self.icebergs.shrink_to_fit();
+        self.stops.shrink_to_fit();
+        self.settlement.shrink_to_fit();
+        self.quote_protections.shrink_to_fit();
+        self.client_order_ids.shrink_to_fit();
+        self.session.shrink_to_fit();
+
+        CompactionReport {
+            bytes_before,
+            bytes_after: self.approx_memory_bytes(),
+            released_slots: slots_before - self.orders.slot_count(),
+            relocated_orders: relocations.len(),
+        }
+    }
+
+    /// Points every reference to the previous slot of a moved order at its new slot.
+    ///
+    /// ## Arguments
+    ///
+    /// * `relocated`: The new slot of each moved order, by its previous slot
+    fn relink(&mut self, relocated: &HashMap<usize, usize>) {
+        let relocate =
+            |slot: Option<usize>| slot.map(|slot| relocated.get(&slot).copied().unwrap_or(slot));
+        for (_, node) in self.orders.iter_mut() {
+            node.previous = relocate(node.previous);
+            node.next = relocate(node.next);
+        }
+        for price_level in self.bids.levels_mut().chain(self.asks.levels_mut()) {
+            price_level.head = relocate(price_level.head);
+            price_level.tail = relocate(price_level.tail);
+        }
+        for &slot in relocated.values() {
+            let order_id = self
+                .orders
+                .get(slot)
+                .expect("moved order must be stored")
+                .order_id;
+            self.order_slots.insert(order_id, slot);
+        }
+
+        let mut quotes = std::mem::take(&mut self.quotes);
+        for quote in quotes.values_mut() {
+            quote.refresh(|order_id| self.resting_handle(order_id));
+        }
+        self.quotes = quotes;
+    }
+
+    /// Returns a handle to the resting order with the given identifier, if any.
+    fn resting_handle(&self, order_id: OrderId) -> Option<OrderHandle> {
+        let slot = *self.order_slots.get(&order_id)?;
+        let node = self.orders.get(slot)?;
+        Some(OrderHandle {
+            order_id,
+            price: node.order.price,
+            side: node.order.side,
+            slot,
+            generation: self.orders.generation(slot)?,
+        })
+    }
+}
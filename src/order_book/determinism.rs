@@ -0,0 +1,97 @@
+use super::OrderBook;
+use crate::rng::SeededRng;
+use crate::types::unix_timestamp_nanos;
+
+/// Where the book takes the current time from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BookClock {
+    /// The system's wall clock
+    System,
+    /// A time set explicitly with `OrderBook::set_time`, in nanoseconds since the epoch
+    Manual(u64),
+}
+
+impl OrderBook {
+    /// Creates a new empty order book whose output depends only on the commands applied.
+    ///
+    /// Two books created with the same seed and fed the same command stream publish
+    /// byte-identical event logs, as replay-based certification requires:
+    ///
+    /// - Randomized policies draw from a generator seeded with `seed` rather than from
+    ///   a global source
+    /// - The book's clock is manual, starting at zero and moved only by `set_time` (or
+    ///   `Command::SetTime`), so event timestamps and time windows such as the client
+    ///   order identifier window do not depend on when the commands run
+    ///
+    /// As a zero timestamp means an unknown creation time, a `MarketDepthCache` does
+    /// not record propagation latency for events published before the clock is set.
+    ///
+    /// ## Arguments
+    ///
+    /// * `seed`: The seed of the book's pseudo-random number generator
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Command, Order, OrderBook, Side};
+    ///
+    /// let commands = [
+    ///     Command::SetTime(1_000),
+    ///     Command::Insert(Order::new(100.00, 10, Side::Ask)),
+    ///     Command::SetTime(2_000),
+    ///     Command::Match(Order::new(100.00, 4, Side::Bid)),
+    /// ];
+    ///
+    /// let run = |seed| {
+    ///     let mut order_book = OrderBook::deterministic(seed);
+    ///     let mut events = Vec::new();
+    ///     for command in commands.clone() {
+    ///         events.extend(order_book.apply(command).unwrap());
+    ///     }
+    ///     events
+    /// };
+    ///
+    /// let events = run(42);
+    /// assert_eq!(events, run(42));
+    /// assert_eq!(events[1].timestamp_nanos, 2_000);
+    /// ```
+    pub fn deterministic(seed: u64) -> Self {
+        OrderBook {
+            clock: BookClock::Manual(0),
+            rng: SeededRng::new(seed),
+            ..Self::new()
+        }
+    }
+
+    /// Sets the book's current time, switching it to a manual clock.
+    ///
+    /// Events published afterwards are stamped with this time until it is set again.
+    ///
+    /// ## Arguments
+    ///
+    /// * `timestamp_nanos`: The current time, in nanoseconds since the Unix epoch
+    pub fn set_time(&mut self, timestamp_nanos: u64) {
+        self.clock = BookClock::Manual(timestamp_nanos);
+    }
+
+    /// Returns whether the book's clock is manual rather than the system's wall clock.
+    pub fn has_manual_clock(&self) -> bool {
+        matches!(self.clock, BookClock::Manual(_))
+    }
+
+    /// Returns the book's current time, in nanoseconds since the Unix epoch.
+    pub(crate) fn now_nanos(&self) -> u64 {
+        match self.clock {
+            BookClock::System => unix_timestamp_nanos(),
+            BookClock::Manual(timestamp_nanos) => timestamp_nanos,
+        }
+    }
+
+    /// Draws the next number from the book's seeded generator.
+    ///
+    /// Randomized policies, such as the slice sizes of `DisplayQuantity::Random`, must
+    /// draw from here so seeded books stay reproducible.
+    pub(crate) fn next_random(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+}
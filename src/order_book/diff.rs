@@ -0,0 +1,93 @@
+use super::OrderBook;
+use crate::types::{BookDiff, LevelDiff, Side};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+
+impl OrderBook {
+    /// Lists the price levels that differ between this book and `other`.
+    ///
+    /// Levels are compared by price and total quantity, and every difference is
+    /// expressed as the change turning this book into `other`. This makes it easy to
+    /// check a book against an exchange snapshot, or to find where two replays diverged.
+    ///
+    /// ## Arguments
+    ///
+    /// * `other`: The book to compare against
+    ///
+    /// ## Returns
+    ///
+    /// A `BookDiff`, empty if both books have identical levels
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{LevelDiff, Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    ///
+    /// let mut other = order_book.clone();
+    /// other.insert_order(Order::new(100.00, 5, Side::Bid));
+    ///
+    /// let diff = order_book.diff(&other);
+    /// assert_eq!(
+    ///     diff.bids,
+    ///     vec![LevelDiff::Changed { price: Decimal::new(100, 0), from: 10, to: 15 }]
+    /// );
+    /// assert!(diff.asks.is_empty());
+    /// ```
+    pub fn diff(&self, other: &OrderBook) -> BookDiff {
+        BookDiff {
+            bids: diff_levels(
+                self.level_quantities(Side::Bid),
+                other.level_quantities(Side::Bid),
+            ),
+            asks: diff_levels(
+                self.level_quantities(Side::Ask),
+                other.level_quantities(Side::Ask),
+            ),
+        }
+    }
+}
+
+/// Merges two sides of a book in price order, collecting the levels that differ.
+///
+/// Both sides are given as `(price, total_quantity)` pairs in ascending price order.
+fn diff_levels(
+    before: impl Iterator<Item = (Decimal, u64)>,
+    after: impl Iterator<Item = (Decimal, u64)>,
+) -> Vec<LevelDiff> {
+    let mut level_diffs = Vec::new();
+    let mut before_levels = before.peekable();
+    let mut after_levels = after.peekable();
+
+    loop {
+        let ordering = match (before_levels.peek(), after_levels.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((before_price, _)), Some((after_price, _))) => before_price.cmp(after_price),
+        };
+
+        match ordering {
+            Ordering::Less => {
+                let (price, quantity) = before_levels.next().expect("peeked");
+                level_diffs.push(LevelDiff::Removed { price, quantity });
+            }
+            Ordering::Greater => {
+                let (price, quantity) = after_levels.next().expect("peeked");
+                level_diffs.push(LevelDiff::Added { price, quantity });
+            }
+            Ordering::Equal => {
+                let (price, from) = before_levels.next().expect("peeked");
+                let (_, to) = after_levels.next().expect("peeked");
+                if from != to {
+                    level_diffs.push(LevelDiff::Changed { price, from, to });
+                }
+            }
+        }
+    }
+
+    level_diffs
+}
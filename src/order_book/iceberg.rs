@@ -0,0 +1,189 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::types::{
+    ClientOrderId, DisplayQuantity, FillSummary, InsertOutcome, Order, OrderEvent, OrderId,
+    OrderMetadata,
+};
+
+/// The hidden reserve of a resting iceberg order.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct IcebergReserve {
+    /// The quantity not yet displayed
+    hidden_quantity: u64,
+    /// How much every reload displays
+    display: DisplayQuantity,
+}
+
+/// What an iceberg order carries from its executed slice to the next one.
+#[derive(Debug)]
+pub(super) struct Reload {
+    /// The identifier the order keeps across slices
+    order_id: OrderId,
+    /// The order's price and side
+    order: Order,
+    /// The reserve the next slice is drawn from
+    reserve: IcebergReserve,
+    /// The executions of the order across its slices
+    fills: FillSummary,
+    /// The payload attached to the order
+    metadata: Option<OrderMetadata>,
+    /// The identifier the order was submitted under
+    client_order_id: Option<ClientOrderId>,
+    /// Whether the order expires at the end of the session
+    is_day_order: bool,
+}
+
+impl OrderBook {
+    /// Inserts an iceberg order, which displays only a slice of its quantity at a time.
+    ///
+    /// The displayed slice rests like any order and the rest is held in a hidden
+    /// reserve. Whenever `match_order` executes a slice in full, the next one is drawn
+    /// from the reserve and rests at the back of the level under the same identifier,
+    /// with the events of any new order: an `OrderEvent` adding the slice and, if
+    /// recorded, a `MarketByOrderEvent::Added`. The slice sizes follow `display`:
+    /// a `DisplayQuantity::Random` slice is drawn from the book's seeded generator, so
+    /// a book created with `OrderBook::deterministic` reloads identically on every run,
+    /// while the events look the same as those of fixed reloads. The last slice is
+    /// capped by what remains of the reserve.
+    ///
+    /// Cancelling the order drops its reserve, as do snapshots and checkpoints, which
+    /// only hold the displayed slice, while replacing it keeps the reserve behind the
+    /// new slice.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order, with its total quantity
+    /// * `display`: How much each slice displays
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome` of the first slice,
+    /// `OrderBookError::InvalidDisplayQuantity` if `display` allows no positive slice,
+    /// or `OrderBookError::PriceTooPrecise` if the book's `PricePrecision` rejects the
+    /// order's price, in which case nothing is inserted
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{DisplayQuantity, Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::deterministic(7);
+    /// let display = DisplayQuantity::Random { min: 10, max: 20 };
+    /// let outcome = order_book.insert_iceberg(Order::new(100.00, 100, Side::Ask), display).unwrap();
+    /// let order_id = outcome.handle.order_id();
+    /// let shown = order_book.get_order(order_id).unwrap().quantity;
+    /// assert!((10..=20).contains(&shown));
+    /// assert_eq!(order_book.hidden_quantity(order_id), Some(100 - shown));
+    ///
+    /// // Executing the slice in full reloads the next one under the same identifier
+    /// order_book.match_order(Order::new(100.00, shown, Side::Bid));
+    /// let reloaded = order_book.get_order(order_id).unwrap().quantity;
+    /// assert!((10..=20).contains(&reloaded));
+    /// assert_eq!(order_book.hidden_quantity(order_id), Some(100 - shown - reloaded));
+    /// ```
+    pub fn insert_iceberg(
+        &mut self,
+        mut order: Order,
+        display: DisplayQuantity,
+    ) -> Result<InsertOutcome> {
+        let (min, max) = match display {
+            DisplayQuantity::Fixed(quantity) => (quantity, quantity),
+            DisplayQuantity::Random { min, max } => (min, max),
+        };
+        if min == 0 || min > max {
+            return Err(self.reject(OrderBookError::InvalidDisplayQuantity { min, max }, &order));
+        }
+        order.price = self.checked_order(&order)?;
+
+        let total_quantity = order.quantity;
+        order.quantity = self.draw_display_quantity(display).min(total_quantity);
+        let hidden_quantity = total_quantity - order.quantity;
+        let order_id = self.assign_order_id();
+        let outcome = self.rest_order(order_id, order);
+        self.publish_added(&outcome);
+        if hidden_quantity > 0 {
+            self.icebergs.insert(
+                order_id,
+                IcebergReserve {
+                    hidden_quantity,
+                    display,
+                },
+            );
+        }
+        Ok(outcome)
+    }
+
+    /// Returns the quantity an iceberg order still holds in reserve, or `None` if no
+    /// resting order with the given identifier has a reserve left.
+    pub fn hidden_quantity(&self, order_id: OrderId) -> Option<u64> {
+        self.icebergs
+            .get(&order_id)
+            .map(|reserve| reserve.hidden_quantity)
+    }
+
+    /// Captures what the iceberg order at `slot` carries to its next slice, before its
+    /// current slice is executed in full.
+    ///
+    /// The slot must be occupied.
+    pub(super) fn take_reload(&self, slot: usize) -> Option<Reload> {
+        let node = self.orders.get(slot).expect("slot must be occupied");
+        let reserve = *self.icebergs.get(&node.order_id)?;
+        Some(Reload {
+            order_id: node.order_id,
+            order: node.order.clone(),
+            reserve,
+            fills: node.fills,
+            metadata: node.metadata.clone(),
+            client_order_id: self.client_order_id(node.order_id),
+            is_day_order: self.is_day_order(node.order_id),
+        })
+    }
+
+    /// Rests the next slice of an iceberg order whose slice was executed in full.
+    pub(super) fn reload(&mut self, reload: Reload) -> OrderEvent {
+        let Reload {
+            order_id,
+            order,
+            mut reserve,
+            fills,
+            metadata,
+            client_order_id,
+            is_day_order,
+        } = reload;
+        let quantity = self
+            .draw_display_quantity(reserve.display)
+            .min(reserve.hidden_quantity);
+        reserve.hidden_quantity -= quantity;
+
+        let outcome = self.rest_order(order_id, Order { quantity, ..order });
+        if let Some(node) = self.orders.get_mut(outcome.handle.slot) {
+            node.fills = fills;
+        }
+        if let Some(metadata) = metadata {
+            self.attach_metadata(outcome.handle, metadata);
+        }
+        if let Some(client_order_id) = client_order_id {
+            self.client_order_ids
+                .restore_resting(order_id, client_order_id);
+        }
+        if is_day_order {
+            self.session.insert_day_order(order_id);
+        }
+        if reserve.hidden_quantity > 0 {
+            self.icebergs.insert(order_id, reserve);
+        }
+        self.publish_added(&outcome);
+        outcome.event
+    }
+
+    /// Returns the size of the next slice an iceberg order displays.
+    fn draw_display_quantity(&mut self, display: DisplayQuantity) -> u64 {
+        match display {
+            DisplayQuantity::Fixed(quantity) => quantity,
+            DisplayQuantity::Random { min, max } => {
+                let draw = self.next_random();
+                min + (max - min).checked_add(1).map_or(draw, |span| draw % span)
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use super::OrderBook;
+use crate::types::{IdGenerator, OrderId, TradeId};
+use std::sync::atomic::Ordering;
+
+/// The number of low bits of a snowflake identifier holding its sequence.
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+
+/// The number of bits of a snowflake identifier holding its node identifier.
+const SNOWFLAKE_NODE_BITS: u32 = 10;
+
+impl OrderBook {
+    /// Sets how identifiers are assigned to the orders inserted from now on.
+    ///
+    /// Orders already resting keep their identifiers. The default, `IdGenerator::Counter`,
+    /// numbers orders from 1.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id_generator`: The generator of order identifiers
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{IdGenerator, Order, OrderBook, OrderId, Side};
+    ///
+    /// let mut order_book = OrderBook::deterministic(0);
+    /// order_book.set_order_id_generator(IdGenerator::Snowflake { node_id: 3, epoch_millis: 0 });
+    /// order_book.set_time(5_000_000);
+    ///
+    /// let first = order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// let second = order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    /// assert_eq!(first.handle.order_id(), OrderId(5 << 22 | 3 << 12));
+    /// assert_eq!(second.handle.order_id(), OrderId(5 << 22 | 3 << 12 | 1));
+    /// ```
+    pub fn set_order_id_generator(&mut self, order_id_generator: IdGenerator) {
+        self.order_id_generator = order_id_generator;
+    }
+
+    /// Returns how identifiers are assigned to inserted orders.
+    pub fn order_id_generator(&self) -> &IdGenerator {
+        &self.order_id_generator
+    }
+
+    /// Sets how identifiers are assigned to the trades printed from now on.
+    ///
+    /// The default, `IdGenerator::Counter`, numbers trades from 1.
+    ///
+    /// ## Arguments
+    ///
+    /// * `trade_id_generator`: The generator of trade identifiers
+    pub fn set_trade_id_generator(&mut self, trade_id_generator: IdGenerator) {
+        self.trade_id_generator = trade_id_generator;
+    }
+
+    /// Returns how identifiers are assigned to printed trades.
+    pub fn trade_id_generator(&self) -> &IdGenerator {
+        &self.trade_id_generator
+    }
+
+    /// Returns the identifier of the next inserted order.
+    pub(super) fn assign_order_id(&mut self) -> OrderId {
+        let now_nanos = self.now_nanos();
+        let order_id = generate(&self.order_id_generator, &mut self.next_order_id, now_nanos);
+        debug_assert!(
+            !self.order_slots.contains_key(&OrderId(order_id)),
+            "order identifier {order_id} is already resting"
+        );
+        OrderId(order_id)
+    }
+
+    /// Returns the identifier of the next printed trade.
+    pub(super) fn assign_trade_id(&mut self) -> TradeId {
+        let now_nanos = self.now_nanos();
+        TradeId(generate(
+            &self.trade_id_generator,
+            &mut self.next_trade_id,
+            now_nanos,
+        ))
+    }
+}
+
+/// Generates an identifier and raises the counter of the identifiers it follows.
+///
+/// ## Arguments
+///
+/// * `generator`: The generator to use
+/// * `next`: One past the highest identifier generated so far, updated
+/// * `now_nanos`: The book's current time
+fn generate(generator: &IdGenerator, next: &mut u64, now_nanos: u64) -> u64 {
+    let id = match generator {
+        IdGenerator::Counter => *next,
+        IdGenerator::Shared(counter) => counter.fetch_add(1, Ordering::Relaxed),
+        IdGenerator::Snowflake {
+            node_id,
+            epoch_millis,
+        } => {
+            let node_id = u64::from(*node_id) & ((1 << SNOWFLAKE_NODE_BITS) - 1);
+            let millis = (now_nanos / 1_000_000).saturating_sub(*epoch_millis);
+            let earliest = millis << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS)
+                | node_id << SNOWFLAKE_SEQUENCE_BITS;
+            snowflake_after(next.saturating_sub(1), earliest, node_id)
+        }
+        IdGenerator::Custom(generate) => generate(),
+    };
+    *next = (*next).max(id.wrapping_add(1));
+    id
+}
+
+/// Returns the smallest snowflake identifier of a node above both `last` and `earliest`.
+fn snowflake_after(last: u64, earliest: u64, node_id: u64) -> u64 {
+    let sequence_mask = (1 << SNOWFLAKE_SEQUENCE_BITS) - 1;
+    if earliest > last {
+        earliest
+    } else if last & sequence_mask < sequence_mask {
+        last + 1
+    } else {
+        // The millisecond's sequence is exhausted, so borrow the next millisecond
+        let millis = (last >> (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS)) + 1;
+        millis << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS)
+            | node_id << SNOWFLAKE_SEQUENCE_BITS
+    }
+}
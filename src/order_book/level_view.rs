@@ -0,0 +1,133 @@
+use super::OrderBook;
+use crate::price_level::{OrderNode, PriceLevel};
+use crate::slab::Slab;
+use crate::types::{Order, OrderId, Side};
+use rust_decimal::Decimal;
+
+/// A read-only view of the orders resting at one exact price, returned by
+/// `OrderBook::level`.
+///
+/// The view borrows the book, so it reflects the level as it is when taken and the book
+/// cannot change while it is held.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelView<'a> {
+    /// The level's canonical price
+    price: Decimal,
+    /// The side the level rests on
+    side: Side,
+    /// The level itself
+    price_level: &'a PriceLevel,
+    /// The book's order storage, which the level's FIFO is threaded through
+    orders: &'a Slab<OrderNode>,
+}
+
+impl<'a> LevelView<'a> {
+    /// Returns the level's price, as stored by the book.
+    pub fn price(&self) -> Decimal {
+        self.price
+    }
+
+    /// Returns the side the level rests on.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// Returns the number of orders resting at the level.
+    pub fn order_count(&self) -> usize {
+        self.price_level.order_count
+    }
+
+    /// Returns the total quantity resting at the level.
+    pub fn total_quantity(&self) -> u64 {
+        self.price_level.total_quantity
+    }
+
+    /// Returns the orders resting at the level with their identifiers, in time priority
+    /// (the next to be filled first).
+    pub fn orders(&self) -> LevelOrders<'a> {
+        LevelOrders {
+            cursor: self.price_level.head,
+            remaining: self.price_level.order_count,
+            orders: self.orders,
+        }
+    }
+}
+
+/// An iterator over the orders of a `LevelView`, in time priority.
+#[derive(Debug, Clone)]
+pub struct LevelOrders<'a> {
+    /// Slot of the next order to yield
+    cursor: Option<usize>,
+    /// Number of orders not yet yielded
+    remaining: usize,
+    /// The book's order storage
+    orders: &'a Slab<OrderNode>,
+}
+
+impl<'a> Iterator for LevelOrders<'a> {
+    type Item = (OrderId, &'a Order);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.orders.get(self.cursor?)?;
+        self.cursor = node.next;
+        self.remaining -= 1;
+        Some((node.order_id, &node.order))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for LevelOrders<'_> {}
+
+impl OrderBook {
+    /// Returns a view of the level resting at an exact price.
+    ///
+    /// The price is normalized like those of incoming orders, so it matches the level
+    /// an order at the same price rests at.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The exact price of the level
+    /// * `side`: The side of the level
+    ///
+    /// ## Returns
+    ///
+    /// The level's view, or `None` if no order rests at that price on that side
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let first = order_book.insert_order(Order::new(100.00, 10, Side::Bid)).handle;
+    /// order_book.insert_order(Order::new(100.00, 5, Side::Bid));
+    ///
+    /// let level = order_book.level(Decimal::new(100, 0), Side::Bid).unwrap();
+    /// assert_eq!((level.order_count(), level.total_quantity()), (2, 15));
+    /// let quantities: Vec<u64> = level.orders().map(|(_, order)| order.quantity).collect();
+    /// assert_eq!(quantities, vec![10, 5]);
+    /// assert_eq!(level.orders().next().unwrap().0, first.order_id());
+    ///
+    /// assert!(order_book.level(Decimal::new(100, 0), Side::Ask).is_none());
+    /// ```
+    pub fn level(&self, price: Decimal, side: Side) -> Option<LevelView<'_>> {
+        let price = self.price_normalization.apply(price);
+        let key = self.price_keys.exact_key(price)?;
+        let price_level_map = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        let price_level = price_level_map.get(&key)?;
+
+        Some(LevelView {
+            price: self.price_of(key),
+            side,
+            price_level,
+            orders: &self.orders,
+        })
+    }
+}
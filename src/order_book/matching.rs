@@ -0,0 +1,387 @@
+use super::OrderBook;
+use crate::error::Result;
+use crate::price_key::PriceKey;
+use crate::types::{
+    BboChanged, FillSummary, MarketByOrderEvent, MatchOutcome, Order, OrderEvent, OrderId,
+    PriorityPolicy, Side, SubmitResult, Trade, TradingState,
+};
+use rust_decimal::Decimal;
+
+impl OrderBook {
+    /// Matches an incoming limit order against the opposite side and rests the remainder.
+    ///
+    /// While the order crosses the best opposite price, it executes against the oldest
+    /// order at that price (price-time priority), or the one `set_priority_policy`
+    /// selects, at the resting order's price. Every
+    /// execution is appended to the trade log (`trades`) and, if enabled, recorded as a
+    /// `MarketByOrderEvent::Executed`. Whatever quantity is left once the order no
+    /// longer crosses is inserted into the book like `insert_order` would.
+    ///
+    /// Executions against quotes count towards their participant's protection (see
+    /// `set_quote_protection`), which may pull the participant's quotes mid-match.
+    /// While the book is halted (see `set_luld_bands`), the order rests without matching.
+    ///
+    /// Unlike `insert_order`, which appends orders unconditionally, this method never
+    /// leaves the book crossed.
+    ///
+    /// A price the book's `PricePrecision` would reject is rounded to its `max_scale`
+    /// instead; `try_match_order` returns the error.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    ///
+    /// ## Returns
+    ///
+    /// A `MatchOutcome` with the incoming order's identifier, the handle of its resting
+    /// remainder (if any), and the events for downstream consumers
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Order, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.50, 30, Side::Ask));
+    ///
+    /// let outcome = order_book.match_order(Order::new(101.00, 50, Side::Bid));
+    /// assert_eq!(order_book.trades()[0].quantity, 30);
+    ///
+    /// // The unfilled 20 rest as the new best bid
+    /// assert!(outcome.handle.is_some());
+    /// assert_eq!(outcome.events.last().unwrap().quantity_delta, 20);
+    /// ```
+    pub fn match_order(&mut self, order: Order) -> MatchOutcome {
+        let mut events = Vec::new();
+        let outcome = self.match_rounded(order, &mut events);
+        MatchOutcome { events, ..outcome }
+    }
+
+    /// Matches an incoming order like `match_order`, unless the book's
+    /// `PricePrecision` rejects its price.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    ///
+    /// ## Returns
+    ///
+    /// The `MatchOutcome`, or `OrderBookError::PriceTooPrecise` if the price was
+    /// rejected, leaving the book unchanged
+    pub fn try_match_order(&mut self, order: Order) -> Result<MatchOutcome> {
+        let mut events = Vec::new();
+        let outcome = self.match_into(order, &mut events)?;
+        Ok(MatchOutcome { events, ..outcome })
+    }
+
+    /// Matches an incoming order like `match_order`, appending the events to a buffer of
+    /// the caller's instead of returning them.
+    ///
+    /// A gateway matching at a high rate can reuse the same buffer across calls,
+    /// clearing it once the events are forwarded, so matching allocates no event vector
+    /// of its own. The returned outcome's `events` are left empty.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    /// * `events`: The buffer the events are appended to, in order
+    ///
+    /// ## Returns
+    ///
+    /// The `MatchOutcome`, without its events
+    ///
+    /// A price the book's `PricePrecision` would reject is rounded as in `match_order`;
+    /// `apply_into` returns the error.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let mut events = Vec::with_capacity(16);
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    ///
+    /// for _ in 0..2 {
+    ///     events.clear();
+    ///     let outcome = order_book.match_order_into(Order::new(100.00, 5, Side::Bid), &mut events);
+    ///     assert!(outcome.events.is_empty());
+    ///     assert_eq!(events[0].quantity_delta, -5);
+    /// }
+    /// ```
+    pub fn match_order_into(&mut self, order: Order, events: &mut Vec<OrderEvent>) -> MatchOutcome {
+        self.match_rounded(order, events)
+    }
+
+    /// Matches an incoming order like `try_match_order` and returns its fills directly.
+    ///
+    /// Synchronous gateways and backtests that need to know at once what an order
+    /// executed would otherwise have to correlate the trade log or the event stream
+    /// with the order's identifier; the result carries the trades themselves and the
+    /// quantity left resting.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    ///
+    /// ## Returns
+    ///
+    /// A `SubmitResult` with the order's identifier, fills, and resting remainder, or
+    /// `OrderBookError::PriceTooPrecise` if the book's `PricePrecision` rejects the
+    /// price, leaving the book unchanged
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(100.50, 10, Side::Ask));
+    ///
+    /// let result = order_book.submit(Order::new(100.50, 25, Side::Bid)).unwrap();
+    /// let fills: Vec<(Decimal, u64)> = result.fills.iter().map(|trade| (trade.price, trade.quantity)).collect();
+    /// assert_eq!(fills, vec![(Decimal::new(100, 0), 10), (Decimal::new(1005, 1), 10)]);
+    /// assert_eq!(result.resting_remainder, 5);
+    /// assert!(result.handle.is_some());
+    /// ```
+    pub fn submit(&mut self, order: Order) -> Result<SubmitResult> {
+        let trades_before = self.trades.len();
+        let outcome = self.try_match_order(order)?;
+        let order_id = outcome.order_id;
+        let fills = self.trades[trades_before..]
+            .iter()
+            .filter(|trade| trade.taker_order_id == order_id || trade.maker_order_id == order_id)
+            .cloned()
+            .collect();
+        // The remainder may have traded since it rested, against the stops it released
+        let resting = outcome
+            .handle
+            .and_then(|handle| Some((handle, self.get_order(order_id)?.quantity)));
+        Ok(SubmitResult {
+            order_id,
+            fills,
+            resting_remainder: resting.map_or(0, |(_, quantity)| quantity),
+            handle: resting.map(|(handle, _)| handle),
+            events: outcome.events,
+        })
+    }
+
+    /// Sets which order of a price level incoming orders execute against first.
+    ///
+    /// Price priority always comes first; the policy only breaks ties between the orders
+    /// resting at the same price. `PriorityPolicy::Fifo`, the default, executes the
+    /// oldest first, `PriorityPolicy::Lifo` the newest, and
+    /// `PriorityPolicy::SizePriority` the largest, as some crossing systems allocate.
+    /// Orders keep resting in arrival order, so the policy can be changed at any time
+    /// and applies from the next execution. Under `SizePriority`, every execution scans
+    /// the level for its largest order.
+    ///
+    /// ## Arguments
+    ///
+    /// * `priority_policy`: The policy to apply
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, OrderId, PriorityPolicy, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_priority_policy(PriorityPolicy::SizePriority);
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(100.00, 30, Side::Ask));
+    ///
+    /// order_book.match_order(Order::new(100.00, 5, Side::Bid));
+    /// assert_eq!(order_book.trades()[0].maker_order_id, OrderId(2));
+    /// ```
+    pub fn set_priority_policy(&mut self, priority_policy: PriorityPolicy) {
+        self.priority_policy = priority_policy;
+    }
+
+    /// Returns which order of a price level incoming orders execute against first.
+    pub fn priority_policy(&self) -> PriorityPolicy {
+        self.priority_policy
+    }
+
+    /// Matches an incoming order, appending its events to `events`, unless the book's
+    /// `PricePrecision` rejects its price.
+    pub(super) fn match_into(
+        &mut self,
+        mut order: Order,
+        events: &mut Vec<OrderEvent>,
+    ) -> Result<MatchOutcome> {
+        order.price = self.checked_order(&order)?;
+        Ok(self.match_rounded(order, events))
+    }
+
+    /// Matches an incoming order, rounding a price the book's `PricePrecision` would
+    /// reject.
+    fn match_rounded(&mut self, mut order: Order, events: &mut Vec<OrderEvent>) -> MatchOutcome {
+        order.price = self.canonical_price(order.price);
+        let order_id = self.assign_order_id();
+        let old_bbo = self.bbo();
+        let trades_before = self.trades.len();
+        let mut outcome = self.match_assigned(order_id, order, events);
+        self.release_stops(trades_before, events, &mut outcome);
+        outcome.bbo_changed = BboChanged::between(old_bbo, self.bbo());
+        outcome
+    }
+
+    /// Matches an incoming order whose price was checked and identifier assigned,
+    /// without releasing the stop orders its trades trigger.
+    ///
+    /// The outcome's `bbo_changed` is left to the caller.
+    pub(super) fn match_assigned(
+        &mut self,
+        order_id: OrderId,
+        order: Order,
+        events: &mut Vec<OrderEvent>,
+    ) -> MatchOutcome {
+        let mut remaining_quantity = order.quantity;
+        let mut protections_triggered = Vec::new();
+        let mut state_change = None;
+        let mut fills = FillSummary::default();
+
+        // Levels are swept one at a time: the best crossing level is found once, then its
+        // orders are consumed in priority order through the level's hash index alone, so
+        // the sorted keys are only descended once per level and only rebalanced when the
+        // level is emptied
+        while remaining_quantity > 0 && self.trading_state == TradingState::Continuous {
+            let Some((price, key)) = self.best_crossing_level(order.side, order.price) else {
+                break;
+            };
+            // Every execution at a level prints at the same price, so the bands are
+            // checked once per level
+            if let Some(change) = self.check_price_bands(price) {
+                state_change = Some(change);
+                break;
+            }
+
+            while remaining_quantity > 0 {
+                let Some(slot) = self.next_maker(order.side.opposite(), key) else {
+                    break;
+                };
+                let (maker_order_id, maker_quantity) = {
+                    let maker = self.orders.get(slot).expect("level head must be occupied");
+                    (maker.order_id, maker.order.quantity)
+                };
+                let executed_quantity = remaining_quantity.min(maker_quantity);
+                let quote_owner = self.quote_owners.get(&maker_order_id).copied();
+                if let Some(maker) = self.orders.get_mut(slot) {
+                    maker.fills.record(price, executed_quantity);
+                }
+                fills.record(price, executed_quantity);
+                let reload = if executed_quantity == maker_quantity {
+                    self.take_reload(slot)
+                } else {
+                    None
+                };
+
+                events.push(self.decrease_order_at(slot, executed_quantity));
+                remaining_quantity -= executed_quantity;
+                self.reference_price = Some(price);
+
+                let trade_id = self.assign_trade_id();
+                self.trades.push(Trade {
+                    price,
+                    quantity: executed_quantity,
+                    aggressor_side: order.side,
+                    trade_id,
+                    maker_order_id,
+                    taker_order_id: order_id,
+                });
+                self.publish_market_by_order(MarketByOrderEvent::Executed {
+                    order_id: maker_order_id,
+                    price,
+                    side: order.side.opposite(),
+                    executed_quantity,
+                    remaining_quantity: maker_quantity - executed_quantity,
+                    trade_id,
+                    taker_order_id: order_id,
+                });
+                if let Some(reload) = reload {
+                    events.push(self.reload(reload));
+                }
+
+                if let Some(participant_id) = quote_owner {
+                    if let Some((triggered, cancelled)) =
+                        self.record_quote_execution(participant_id, executed_quantity)
+                    {
+                        events.extend(cancelled);
+                        protections_triggered.push(triggered);
+                    }
+                }
+            }
+        }
+
+        let (mut handle, mut saturated_from) = (None, None);
+        if remaining_quantity > 0 {
+            let remainder = Order {
+                quantity: remaining_quantity,
+                ..order
+            };
+            let outcome = self.rest_order(order_id, remainder);
+            if let Some(node) = self.orders.get_mut(outcome.handle.slot) {
+                node.fills = fills;
+            }
+            self.publish_added(&outcome);
+
+            handle = Some(outcome.handle);
+            saturated_from = outcome.saturated_from;
+            events.push(outcome.event);
+        }
+
+        MatchOutcome {
+            order_id,
+            handle,
+            events: Vec::new(),
+            protections_triggered,
+            state_change,
+            fills,
+            bbo_changed: None,
+            stops_triggered: Vec::new(),
+            saturated_from,
+        }
+    }
+
+    /// Returns the price and key of the best opposite level an incoming order would
+    /// execute against, or `None` if the incoming price does not cross the opposite side.
+    fn best_crossing_level(&self, side: Side, price: Decimal) -> Option<(Decimal, PriceKey)> {
+        let key = self.key(price);
+        let (level_price, level_key) = self.best_price_and_key(side.opposite())?;
+        let crosses = match side {
+            Side::Bid => level_key <= key,
+            Side::Ask => level_key >= key,
+        };
+
+        crosses.then_some((level_price, level_key))
+    }
+
+    /// Returns the slot of the order of a level that executes first under the book's
+    /// `PriorityPolicy`, or `None` if the level is gone.
+    #[inline]
+    fn next_maker(&self, side: Side, key: PriceKey) -> Option<usize> {
+        let levels = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        let price_level = levels.get(&key)?;
+        match self.priority_policy {
+            PriorityPolicy::Fifo => price_level.head,
+            PriorityPolicy::Lifo => price_level.tail,
+            PriorityPolicy::SizePriority => {
+                let mut largest: Option<(usize, u64)> = None;
+                let mut cursor = price_level.head;
+                while let Some(slot) = cursor {
+                    let node = self.orders.get(slot)?;
+                    if largest.is_none_or(|(_, quantity)| node.order.quantity > quantity) {
+                        largest = Some((slot, node.order.quantity));
+                    }
+                    cursor = node.next;
+                }
+                largest.map(|(slot, _)| slot)
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::OrderBook;
+use crate::types::{
+    InsertOutcome, MatchOutcome, Order, OrderHandle, OrderId, OrderMetadata, OrderSpec,
+};
+
+impl OrderBook {
+    /// Inserts an order like `insert_order`, attaching a payload of the caller's to it.
+    ///
+    /// The payload (e.g. routing information, a strategy tag, or an account reference)
+    /// stays with the order while it rests, through partial fills and replacements, so
+    /// embedders need no side table keyed by order identifier. It is dropped when the
+    /// order leaves the book, and is not part of snapshots or checkpoints.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to insert
+    /// * `metadata`: The payload to attach
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    /// use std::sync::Arc;
+    ///
+    /// struct Route {
+    ///     venue: &'static str,
+    /// }
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let order = Order::new(100.00, 10, Side::Bid);
+    /// let order_id = order_book
+    ///     .insert_order_with_metadata(order, Arc::new(Route { venue: "primary" }))
+    ///     .handle
+    ///     .order_id();
+    ///
+    /// // The payload follows the replacement
+    /// let new_order_id = order_book
+    ///     .replace_order(order_id, Decimal::new(101, 0), 10)
+    ///     .unwrap()
+    ///     .handle
+    ///     .order_id();
+    /// let route = order_book.order_metadata_as::<Route>(new_order_id).unwrap();
+    /// assert_eq!(route.venue, "primary");
+    /// ```
+    pub fn insert_order_with_metadata(
+        &mut self,
+        order: Order,
+        metadata: OrderMetadata,
+    ) -> InsertOutcome {
+        let outcome = self.insert_order(order);
+        self.attach_metadata(outcome.handle, metadata);
+        outcome
+    }
+
+    /// Inserts an order like `insert_order`, building it from borrowed data.
+    ///
+    /// The spec's payload, if any, is attached as with `insert_order_with_metadata`;
+    /// attaching it only increments its reference count, so a payload shared by many
+    /// messages is never copied.
+    ///
+    /// ## Arguments
+    ///
+    /// * `spec`: The order to insert
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, OrderMetadata, OrderSpec, Side};
+    /// use rust_decimal::Decimal;
+    /// use std::sync::Arc;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let account: OrderMetadata = Arc::new("account-1");
+    ///
+    /// let mut spec = OrderSpec {
+    ///     price: Decimal::new(100, 0),
+    ///     quantity: 10,
+    ///     side: Side::Bid,
+    ///     metadata: Some(&account),
+    /// };
+    /// // The same spec is refilled from every decoded message
+    /// for quantity in [10, 20] {
+    ///     spec.quantity = quantity;
+    ///     order_book.insert_order_ref(&spec);
+    /// }
+    /// assert_eq!(order_book.total_volume(Side::Bid), 30);
+    /// assert_eq!(Arc::strong_count(&account), 3);
+    /// ```
+    pub fn insert_order_ref(&mut self, spec: &OrderSpec<'_>) -> InsertOutcome {
+        let outcome = self.insert_order(Order {
+            price: spec.price,
+            quantity: spec.quantity,
+            side: spec.side,
+        });
+        if let Some(metadata) = spec.metadata {
+            self.attach_metadata(outcome.handle, metadata.clone());
+        }
+        outcome
+    }
+
+    /// Matches an order like `match_order`, attaching a payload of the caller's to its
+    /// resting remainder, if any.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    /// * `metadata`: The payload to attach
+    ///
+    /// ## Returns
+    ///
+    /// The `MatchOutcome`
+    pub fn match_order_with_metadata(
+        &mut self,
+        order: Order,
+        metadata: OrderMetadata,
+    ) -> MatchOutcome {
+        let outcome = self.match_order(order);
+        if let Some(handle) = outcome.handle {
+            self.attach_metadata(handle, metadata);
+        }
+        outcome
+    }
+
+    /// Returns the payload attached to a resting order, if any.
+    pub fn order_metadata(&self, order_id: OrderId) -> Option<&OrderMetadata> {
+        let slot = *self.order_slots.get(&order_id)?;
+        self.orders.get(slot)?.metadata.as_ref()
+    }
+
+    /// Returns the payload attached to a resting order, if it is a `T`.
+    pub fn order_metadata_as<T: 'static>(&self, order_id: OrderId) -> Option<&T> {
+        self.order_metadata(order_id)?.downcast_ref()
+    }
+
+    /// Attaches a payload to the order a fresh handle refers to.
+    pub(super) fn attach_metadata(&mut self, handle: OrderHandle, metadata: OrderMetadata) {
+        if let Some(node) = self.orders.get_mut(handle.slot) {
+            node.metadata = Some(metadata);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use super::OrderBook;
+use crate::types::{
+    AskPrice, BidPrice, Order, OrderEvent, OrderHandle, OrderId, ParticipantId,
+    ProtectionTriggered, QuoteOutcome, QuoteProtection,
+};
+use std::collections::VecDeque;
+
+/// The orders currently making up a participant's quote.
+#[derive(Debug, Clone, Default)]
+pub(super) struct QuoteHandles {
+    /// The resting bid, if the quote has one
+    bid: Option<OrderHandle>,
+    /// The resting ask, if the quote has one
+    ask: Option<OrderHandle>,
+}
+
+impl QuoteHandles {
+    /// Resolves the handles again from the identifiers of their orders, e.g. after the
+    /// orders moved to other slots, dropping those of orders that left the book.
+    pub(super) fn refresh(&mut self, resolve: impl Fn(OrderId) -> Option<OrderHandle>) {
+        for handle in [&mut self.bid, &mut self.ask] {
+            *handle = handle.and_then(|handle| resolve(handle.order_id));
+        }
+    }
+
+    /// Points the handle of a replaced order at its replacement.
+    pub(super) fn replace(&mut self, order_id: OrderId, replacement: OrderHandle) {
+        for handle in [&mut self.bid, &mut self.ask] {
+            if handle.is_some_and(|handle| handle.order_id == order_id) {
+                *handle = Some(replacement);
+            }
+        }
+    }
+}
+
+/// The recent executions against a protected participant's quotes.
+#[derive(Debug, Clone)]
+pub(super) struct ProtectionState {
+    /// The limits to enforce
+    protection: QuoteProtection,
+    /// Timestamp and quantity of every execution within the window, oldest first
+    executions: VecDeque<(u64, u64)>,
+    /// Sum of the quantities in `executions`
+    executed_in_window: u64,
+}
+
+impl OrderBook {
+    /// Replaces a participant's two-sided quote with a new one.
+    ///
+    /// Whatever remains of the participant's previous quote is cancelled and the new
+    /// bid and ask are inserted, all within the same call, so no reader holding the
+    /// book's lock ever observes one side updated without the other. Orders of the
+    /// previous quote that already left the book (filled or cancelled) are skipped.
+    ///
+    /// Like `insert_order`, the new orders are appended without matching. A side with
+    /// a zero quantity is left unquoted. The prices are typed by side, so the bid and
+    /// the ask cannot be swapped by mistake.
+    ///
+    /// ## Arguments
+    ///
+    /// * `participant_id`: The participant submitting the quote
+    /// * `bid`: The price of the new bid
+    /// * `bid_quantity`: The quantity of the new bid, or 0 for no bid
+    /// * `ask`: The price of the new ask
+    /// * `ask_quantity`: The quantity of the new ask, or 0 for no ask
+    ///
+    /// ## Returns
+    ///
+    /// A `QuoteOutcome` with handles to the new orders and the events for downstream consumers
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{AskPrice, BidPrice, OrderBook, ParticipantId};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let market_maker = ParticipantId(7);
+    /// let (bid, ask) = (BidPrice(Decimal::new(99, 0)), AskPrice(Decimal::new(101, 0)));
+    ///
+    /// order_book.submit_quote(market_maker, bid, 10, ask, 10);
+    /// let (bid, ask) = (BidPrice(Decimal::new(100, 0)), AskPrice(Decimal::new(102, 0)));
+    /// let outcome = order_book.submit_quote(market_maker, bid, 5, ask, 5);
+    ///
+    /// // Two cancellations followed by two additions
+    /// assert_eq!(outcome.events.len(), 4);
+    /// assert_eq!(order_book.order_count(), 2);
+    /// ```
+    pub fn submit_quote(
+        &mut self,
+        participant_id: ParticipantId,
+        bid: BidPrice,
+        bid_quantity: u64,
+        ask: AskPrice,
+        ask_quantity: u64,
+    ) -> QuoteOutcome {
+        let mut events = self.cancel_quote(participant_id);
+
+        let mut quote_side = |order: Order| {
+            if order.quantity == 0 {
+                return None;
+            }
+            let outcome = self.insert_order(order);
+            events.push(outcome.event);
+            self.quote_owners
+                .insert(outcome.handle.order_id(), participant_id);
+            Some(outcome.handle)
+        };
+        let bid = quote_side(bid.order(bid_quantity));
+        let ask = quote_side(ask.order(ask_quantity));
+
+        if bid.is_some() || ask.is_some() {
+            self.quotes
+                .insert(participant_id, QuoteHandles { bid, ask });
+        }
+        QuoteOutcome { bid, ask, events }
+    }
+
+    /// Cancels whatever remains of a participant's quote.
+    ///
+    /// ## Arguments
+    ///
+    /// * `participant_id`: The participant whose quote should be pulled
+    ///
+    /// ## Returns
+    ///
+    /// The events removing the quote's resting orders, empty if nothing was resting
+    pub fn cancel_quote(&mut self, participant_id: ParticipantId) -> Vec<OrderEvent> {
+        self.pull_quote(participant_id).1
+    }
+
+    /// Configures or removes the protection of a participant's quotes.
+    ///
+    /// Executions against the participant's quotes are tracked from the moment the
+    /// protection is set. When the quantity executed within the protection's window
+    /// reaches its limit, every remaining quote of the participant is cancelled and the
+    /// `match_order` call that caused it reports a `ProtectionTriggered`. The execution
+    /// history is then cleared, so the participant can quote again right away.
+    ///
+    /// ## Arguments
+    ///
+    /// * `participant_id`: The participant to protect
+    /// * `protection`: The limits to enforce, or `None` to stop protecting the participant
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{AskPrice, BidPrice, Order, OrderBook, ParticipantId, QuoteProtection};
+    /// use rust_decimal::Decimal;
+    /// use std::time::Duration;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let market_maker = ParticipantId(7);
+    /// let protection = QuoteProtection {
+    ///     executed_quantity_limit: 10,
+    ///     window: Duration::from_secs(1),
+    /// };
+    /// order_book.set_quote_protection(market_maker, Some(protection));
+    /// let (bid, ask) = (BidPrice(Decimal::new(99, 0)), AskPrice(Decimal::new(101, 0)));
+    /// order_book.submit_quote(market_maker, bid, 50, ask, 50);
+    ///
+    /// let outcome = order_book.match_order(Order::new(101.00, 10, order_book::Side::Bid));
+    /// assert_eq!(outcome.protections_triggered[0].participant_id, market_maker);
+    /// assert_eq!(order_book.order_count(), 0);
+    /// ```
+    pub fn set_quote_protection(
+        &mut self,
+        participant_id: ParticipantId,
+        protection: Option<QuoteProtection>,
+    ) {
+        match protection {
+            Some(protection) => {
+                self.quote_protections
+                    .insert(participant_id, ProtectionState::new(protection));
+            }
+            None => {
+                self.quote_protections.remove(&participant_id);
+            }
+        }
+    }
+
+    /// Records an execution against a quote of `participant_id`, pulling the
+    /// participant's quotes if this breaches their protection.
+    pub(super) fn record_quote_execution(
+        &mut self,
+        participant_id: ParticipantId,
+        executed_quantity: u64,
+    ) -> Option<(ProtectionTriggered, Vec<OrderEvent>)> {
+        let now_nanos = self.now_nanos();
+        let state = self.quote_protections.get_mut(&participant_id)?;
+        let executed_in_window = state.record(executed_quantity, now_nanos);
+        if executed_in_window < state.protection.executed_quantity_limit {
+            return None;
+        }
+        state.clear();
+
+        let (pulled_orders, events) = self.pull_quote(participant_id);
+        let triggered = ProtectionTriggered {
+            participant_id,
+            executed_quantity: executed_in_window,
+            pulled_orders,
+        };
+        Some((triggered, events))
+    }
+
+    /// Cancels the participant's resting quotes, returning their identifiers and events.
+    fn pull_quote(&mut self, participant_id: ParticipantId) -> (Vec<OrderId>, Vec<OrderEvent>) {
+        let Some(quote) = self.quotes.remove(&participant_id) else {
+            return (Vec::new(), Vec::new());
+        };
+
+        [quote.bid, quote.ask]
+            .into_iter()
+            .flatten()
+            .filter_map(|handle| {
+                let event = self.cancel_by_handle(handle).ok()?;
+                Some((handle.order_id(), event))
+            })
+            .unzip()
+    }
+}
+
+impl ProtectionState {
+    /// Creates an empty execution history enforcing `protection`.
+    fn new(protection: QuoteProtection) -> Self {
+        ProtectionState {
+            protection,
+            executions: VecDeque::new(),
+            executed_in_window: 0,
+        }
+    }
+
+    /// Records an execution and returns the quantity executed within the window.
+    fn record(&mut self, executed_quantity: u64, timestamp_nanos: u64) -> u64 {
+        let window_nanos = u64::try_from(self.protection.window.as_nanos()).unwrap_or(u64::MAX);
+        let window_start = timestamp_nanos.saturating_sub(window_nanos);
+        while let Some(&(oldest_timestamp, oldest_quantity)) = self.executions.front() {
+            if oldest_timestamp >= window_start {
+                break;
+            }
+            self.executed_in_window -= oldest_quantity;
+            self.executions.pop_front();
+        }
+
+        self.executions
+            .push_back((timestamp_nanos, executed_quantity));
+        self.executed_in_window += executed_quantity;
+        self.executed_in_window
+    }
+
+    /// Forgets every recorded execution.
+    fn clear(&mut self) {
+        self.executions.clear();
+        self.executed_in_window = 0;
+    }
+}
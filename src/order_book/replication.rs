@@ -0,0 +1,157 @@
+use super::OrderBook;
+use crate::types::{MarketByOrderEvent, Order, OrderId, ReplicationSnapshot, Side, Trade};
+
+impl OrderBook {
+    /// Copies the resting orders of the book with the position of its market-by-order
+    /// stream, from which a `FollowerBook` can start replicating the book.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{FollowerBook, Order, OrderBook, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.set_market_by_order_events(true);
+    /// order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    ///
+    /// let snapshot = order_book.replication_snapshot();
+    /// assert_eq!(snapshot.market_by_order_sequence, 1);
+    ///
+    /// let follower = FollowerBook::from_snapshot(&snapshot);
+    /// assert_eq!(follower.order_book().state_hash(), order_book.state_hash());
+    /// ```
+    pub fn replication_snapshot(&self) -> ReplicationSnapshot {
+        ReplicationSnapshot {
+            book: self.snapshot(),
+            market_by_order_sequence: self.market_by_order_sequence,
+        }
+    }
+
+    /// Applies a market-by-order event of another book to this one, as a replica of it.
+    ///
+    /// Executions are printed into the trade log and recorded in the maker's fills, and
+    /// the event is recorded again if market-by-order recording is enabled, so replicas
+    /// can be chained. Nothing is changed if the event does not apply, i.e. if it adds an
+    /// order that is already resting, or changes one that is not resting or whose side or
+    /// remaining quantity differ from the event's.
+    ///
+    /// ## Returns
+    ///
+    /// Whether the event applied
+    pub(crate) fn apply_market_by_order(&mut self, event: &MarketByOrderEvent) -> bool {
+        match *event {
+            MarketByOrderEvent::Added {
+                order_id,
+                price,
+                quantity,
+                side,
+            } => {
+                if quantity == 0 || self.order_slots.contains_key(&order_id) {
+                    return false;
+                }
+                let price = self.canonical_price(price);
+                self.rest_order(
+                    order_id,
+                    Order {
+                        price,
+                        quantity,
+                        side,
+                    },
+                );
+                self.next_order_id = self.next_order_id.max(order_id.0 + 1);
+            }
+            MarketByOrderEvent::Executed {
+                order_id,
+                price,
+                side,
+                executed_quantity,
+                remaining_quantity,
+                trade_id,
+                taker_order_id,
+            } => {
+                let Some(slot) =
+                    self.resting_slot(order_id, side, executed_quantity, remaining_quantity)
+                else {
+                    return false;
+                };
+                if let Some(maker) = self.orders.get_mut(slot) {
+                    maker.fills.record(price, executed_quantity);
+                }
+                self.decrease_order_at(slot, executed_quantity);
+                self.reference_price = Some(price);
+                self.trades.push(Trade {
+                    price,
+                    quantity: executed_quantity,
+                    aggressor_side: side.opposite(),
+                    trade_id,
+                    maker_order_id: order_id,
+                    taker_order_id,
+                });
+                self.next_trade_id = self.next_trade_id.max(trade_id.0 + 1);
+                self.next_order_id = self.next_order_id.max(taker_order_id.0 + 1);
+            }
+            MarketByOrderEvent::Cancelled {
+                order_id,
+                side,
+                cancelled_quantity,
+                remaining_quantity,
+                ..
+            } => {
+                let Some(slot) =
+                    self.resting_slot(order_id, side, cancelled_quantity, remaining_quantity)
+                else {
+                    return false;
+                };
+                self.decrease_order_at(slot, cancelled_quantity);
+            }
+            MarketByOrderEvent::Replaced {
+                order_id,
+                new_order_id,
+                price,
+                quantity,
+                side,
+            } => {
+                let Some(&slot) = self.order_slots.get(&order_id) else {
+                    return false;
+                };
+                let resting_side = self.orders.get(slot).map(|node| node.order.side);
+                if resting_side != Some(side)
+                    || quantity == 0
+                    || self.order_slots.contains_key(&new_order_id)
+                {
+                    return false;
+                }
+                let (node, _) = self.take_order_at(slot);
+                let price = self.canonical_price(price);
+                self.rest_order(
+                    new_order_id,
+                    Order {
+                        price,
+                        quantity,
+                        ..node.order
+                    },
+                );
+                self.next_order_id = self.next_order_id.max(new_order_id.0 + 1);
+            }
+        }
+        self.publish_market_by_order(event.clone());
+        true
+    }
+
+    /// Returns the slot of a resting order if it is on `side` and `removed` plus
+    /// `remaining` is its remaining quantity, with `removed` not zero.
+    fn resting_slot(
+        &self,
+        order_id: OrderId,
+        side: Side,
+        removed: u64,
+        remaining: u64,
+    ) -> Option<usize> {
+        let slot = *self.order_slots.get(&order_id)?;
+        let node = self.orders.get(slot)?;
+        let matches = node.order.side == side
+            && removed > 0
+            && removed.checked_add(remaining) == Some(node.order.quantity);
+        matches.then_some(slot)
+    }
+}
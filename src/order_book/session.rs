@@ -0,0 +1,196 @@
+use super::OrderBook;
+use crate::memory::hash_set_bytes;
+use crate::types::{
+    InsertOutcome, MatchOutcome, Order, OrderId, SequencePolicy, SessionEvent, SessionSummary,
+};
+use std::collections::HashSet;
+
+/// The trading session and the orders that expire at its close.
+#[derive(Debug, Clone)]
+pub(super) struct SessionState {
+    /// The number of the current session, starting from 1
+    number: u64,
+    /// What `end_session` does with event sequence numbers
+    sequence_policy: SequencePolicy,
+    /// The resting orders that are cancelled when the session ends
+    day_orders: HashSet<OrderId>,
+    /// Session events recorded since they were last taken
+    events: Vec<SessionEvent>,
+}
+
+impl SessionState {
+    pub(super) fn new() -> Self {
+        SessionState {
+            number: 1,
+            sequence_policy: SequencePolicy::default(),
+            day_orders: HashSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Forgets an order leaving the book.
+    pub(super) fn remove_resting(&mut self, order_id: OrderId) {
+        self.day_orders.remove(&order_id);
+    }
+
+    /// Marks a resting order as a day order.
+    pub(super) fn insert_day_order(&mut self, order_id: OrderId) {
+        self.day_orders.insert(order_id);
+    }
+
+    /// Returns the resting day orders, in identifier order.
+    pub(super) fn day_orders(&self) -> Vec<OrderId> {
+        let mut day_orders: Vec<OrderId> = self.day_orders.iter().copied().collect();
+        day_orders.sort_unstable();
+        day_orders
+    }
+
+    /// Restores the session number and the day orders of a checkpoint.
+    pub(super) fn restore(&mut self, number: u64, day_orders: &[OrderId]) {
+        self.number = number;
+        self.day_orders = day_orders.iter().copied().collect();
+    }
+
+    /// Releases the spare capacity of the day order set and the recorded events.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.day_orders.shrink_to_fit();
+        self.events.shrink_to_fit();
+    }
+
+    /// Estimates the heap bytes held by the day order set.
+    pub(super) fn memory_bytes(&self) -> usize {
+        hash_set_bytes(&self.day_orders)
+            + self.events.capacity() * std::mem::size_of::<SessionEvent>()
+    }
+}
+
+impl OrderBook {
+    /// Inserts an order like `insert_order`, as a day order that expires when the
+    /// session ends.
+    ///
+    /// Orders inserted any other way are good till cancelled and survive the close.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to insert
+    ///
+    /// ## Returns
+    ///
+    /// The `InsertOutcome`
+    pub fn insert_day_order(&mut self, order: Order) -> InsertOutcome {
+        let outcome = self.insert_order(order);
+        self.session.insert_day_order(outcome.handle.order_id);
+        outcome
+    }
+
+    /// Matches an order like `match_order`, resting any remainder as a day order.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The incoming order
+    ///
+    /// ## Returns
+    ///
+    /// The `MatchOutcome`
+    pub fn match_day_order(&mut self, order: Order) -> MatchOutcome {
+        let outcome = self.match_order(order);
+        if let Some(handle) = outcome.handle {
+            self.session.insert_day_order(handle.order_id);
+        }
+        outcome
+    }
+
+    /// Returns whether a resting order is a day order.
+    ///
+    /// A day order keeps expiring at the close when it is replaced.
+    pub fn is_day_order(&self, order_id: OrderId) -> bool {
+        self.session.day_orders.contains(&order_id)
+    }
+
+    /// Returns the number of the current trading session, starting from 1.
+    pub fn session(&self) -> u64 {
+        self.session.number
+    }
+
+    /// Sets what `end_session` does with event sequence numbers.
+    ///
+    /// With the default, `SequencePolicy::Roll`, consumers tracking sequence numbers,
+    /// such as a `MarketDepthCache`, follow the book across sessions unchanged. With
+    /// `SequencePolicy::Reset`, they must be cleared or rebuilt when a new session
+    /// opens.
+    pub fn set_sequence_policy(&mut self, sequence_policy: SequencePolicy) {
+        self.session.sequence_policy = sequence_policy;
+    }
+
+    /// Closes the current trading session and opens the next one.
+    ///
+    /// Day orders are cancelled while good-till-cancelled orders keep resting, the
+    /// trade log is handed over to the caller and emptied, and event sequence numbers
+    /// are reset or rolled according to the sequence policy. A `SessionEvent::Closed`
+    /// and a `SessionEvent::Opened` are recorded for `take_session_events`.
+    ///
+    /// ## Returns
+    ///
+    /// The closed session's summary
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, SessionEvent, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_day_order(Order::new(100.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+    /// order_book.match_order(Order::new(100.00, 4, Side::Ask));
+    ///
+    /// let summary = order_book.end_session();
+    /// assert_eq!(summary.expired[0].quantity_delta, -6);
+    /// assert_eq!(summary.trades.len(), 1);
+    /// assert_eq!(order_book.order_count(), 1);
+    /// assert_eq!(order_book.session(), 2);
+    /// assert!(matches!(
+    ///     order_book.take_session_events()[..],
+    ///     [SessionEvent::Closed { session: 1, .. }, SessionEvent::Opened { session: 2, .. }]
+    /// ));
+    /// ```
+    pub fn end_session(&mut self) -> SessionSummary {
+        let expired: Vec<_> = self
+            .session
+            .day_orders()
+            .into_iter()
+            .filter_map(|order_id| self.cancel_order(order_id).ok())
+            .collect();
+
+        let trades = std::mem::take(&mut self.trades);
+        let last_event_sequence = self.last_event_sequence;
+        if self.session.sequence_policy == SequencePolicy::Reset {
+            self.last_event_sequence = 0;
+        }
+
+        let timestamp_nanos = self.now_nanos();
+        let session = self.session.number;
+        self.session.number += 1;
+        self.session.events.push(SessionEvent::Closed {
+            session,
+            timestamp_nanos,
+            expired_orders: expired.len(),
+            trade_count: trades.len(),
+        });
+        self.session.events.push(SessionEvent::Opened {
+            session: self.session.number,
+            timestamp_nanos,
+        });
+
+        SessionSummary {
+            session,
+            expired,
+            trades,
+            last_event_sequence,
+        }
+    }
+
+    /// Returns and clears the session events recorded so far, oldest first.
+    pub fn take_session_events(&mut self) -> Vec<SessionEvent> {
+        std::mem::take(&mut self.session.events)
+    }
+}
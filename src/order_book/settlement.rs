@@ -0,0 +1,196 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::types::{OrderId, SettlementOutcome, Side, Trade};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+
+/// The orders waiting to trade at a settlement price that is not known yet, kept apart
+/// from the priced orders of the bids and asks.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SettlementBucket {
+    /// Buy orders, in arrival order
+    buys: VecDeque<SettlementOrder>,
+    /// Sell orders, in arrival order
+    sells: VecDeque<SettlementOrder>,
+    /// The arrival number of the next order
+    next_arrival: u64,
+}
+
+/// An order waiting in the settlement bucket.
+#[derive(Debug, Clone, Copy)]
+struct SettlementOrder {
+    /// The identifier of the order
+    order_id: OrderId,
+    /// The quantity left to trade
+    quantity: u64,
+    /// The position of the order among all settlement orders entered, on either side
+    arrival: u64,
+}
+
+impl SettlementBucket {
+    /// Returns the orders of one side.
+    fn side_mut(&mut self, side: Side) -> &mut VecDeque<SettlementOrder> {
+        match side {
+            Side::Bid => &mut self.buys,
+            Side::Ask => &mut self.sells,
+        }
+    }
+
+    /// Estimates the heap bytes held by the bucket.
+    pub(super) fn memory_bytes(&self) -> usize {
+        (self.buys.capacity() + self.sells.capacity()) * std::mem::size_of::<SettlementOrder>()
+    }
+
+    /// Releases the spare capacity of the bucket.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.buys.shrink_to_fit();
+        self.sells.shrink_to_fit();
+    }
+}
+
+impl OrderBook {
+    /// Enters an order that trades at the settlement price, e.g. the closing auction
+    /// price, once `settle_reference_price` publishes it.
+    ///
+    /// Settlement orders carry no price of their own. They wait in a bucket of their own,
+    /// away from the bids and asks, so they neither move the depth nor match against
+    /// priced orders, and publish no events.
+    ///
+    /// ## Arguments
+    ///
+    /// * `quantity`: The quantity to trade
+    /// * `side`: The side of the order
+    ///
+    /// ## Returns
+    ///
+    /// The identifier of the order
+    pub fn insert_settlement_order(&mut self, quantity: u64, side: Side) -> OrderId {
+        let order_id = self.assign_order_id();
+        let arrival = self.settlement.next_arrival;
+        self.settlement.next_arrival += 1;
+        self.settlement.side_mut(side).push_back(SettlementOrder {
+            order_id,
+            quantity,
+            arrival,
+        });
+        order_id
+    }
+
+    /// Withdraws a settlement order before the settlement price is published.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier returned by `insert_settlement_order`
+    ///
+    /// ## Returns
+    ///
+    /// The quantity withdrawn, or `OrderBookError::OrderNotFound` if no such
+    /// settlement order is waiting
+    pub fn cancel_settlement_order(&mut self, order_id: OrderId) -> Result<u64> {
+        for side in [Side::Bid, Side::Ask] {
+            let orders = self.settlement.side_mut(side);
+            if let Some(index) = orders.iter().position(|order| order.order_id == order_id) {
+                return Ok(orders
+                    .remove(index)
+                    .expect("position must be in range")
+                    .quantity);
+            }
+        }
+        Err(OrderBookError::OrderNotFound(order_id))
+    }
+
+    /// Returns the total quantity of the settlement orders waiting on one side.
+    pub fn settlement_quantity(&self, side: Side) -> u64 {
+        let orders = match side {
+            Side::Bid => &self.settlement.buys,
+            Side::Ask => &self.settlement.sells,
+        };
+        orders.iter().map(|order| order.quantity).sum()
+    }
+
+    /// Publishes the settlement price and crosses the settlement orders at it.
+    ///
+    /// Buy and sell settlement orders are matched against each other in arrival order,
+    /// every execution printing a trade at `price` into the trade log. The order entered
+    /// first of each execution is its maker, and the other its taker and aggressor.
+    /// Whatever cannot be matched, as one side ran out, expires: the bucket is empty
+    /// afterwards.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The settlement price, e.g. the closing auction price
+    ///
+    /// ## Returns
+    ///
+    /// A `SettlementOutcome` with the trades and the unfilled remainders, or
+    /// `OrderBookError::PriceTooPrecise` if the book's `PricePrecision` rejects `price`,
+    /// leaving the bucket untouched
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let buyer = order_book.insert_settlement_order(30, Side::Bid);
+    /// let seller = order_book.insert_settlement_order(20, Side::Ask);
+    ///
+    /// let outcome = order_book.settle_reference_price(Decimal::new(10125, 2)).unwrap();
+    /// assert_eq!(outcome.trades[0].quantity, 20);
+    /// assert_eq!(outcome.trades[0].price, Decimal::new(10125, 2));
+    /// assert_eq!((outcome.trades[0].maker_order_id, outcome.trades[0].taker_order_id), (buyer, seller));
+    /// assert_eq!(outcome.unfilled, vec![(buyer, Side::Bid, 10)]);
+    /// assert_eq!(order_book.settlement_quantity(Side::Bid), 0);
+    /// ```
+    pub fn settle_reference_price(&mut self, price: Decimal) -> Result<SettlementOutcome> {
+        let price = self.checked_price(price)?;
+        let mut buys = std::mem::take(&mut self.settlement.buys);
+        let mut sells = std::mem::take(&mut self.settlement.sells);
+
+        let mut trades = Vec::new();
+        while let (Some(buy), Some(sell)) = (buys.front_mut(), sells.front_mut()) {
+            let quantity = buy.quantity.min(sell.quantity);
+            let (maker, taker, aggressor_side) = if buy.arrival < sell.arrival {
+                (buy.order_id, sell.order_id, Side::Ask)
+            } else {
+                (sell.order_id, buy.order_id, Side::Bid)
+            };
+            if quantity > 0 {
+                trades.push(Trade {
+                    price,
+                    quantity,
+                    aggressor_side,
+                    trade_id: self.assign_trade_id(),
+                    maker_order_id: maker,
+                    taker_order_id: taker,
+                });
+            }
+            buy.quantity -= quantity;
+            sell.quantity -= quantity;
+            if buy.quantity == 0 {
+                buys.pop_front();
+            }
+            if sell.quantity == 0 {
+                sells.pop_front();
+            }
+        }
+        self.trades.extend(trades.iter().cloned());
+
+        let unfilled = buys
+            .into_iter()
+            .map(|order| (order.order_id, Side::Bid, order.quantity))
+            .chain(
+                sells
+                    .into_iter()
+                    .map(|order| (order.order_id, Side::Ask, order.quantity)),
+            )
+            .filter(|(_, _, quantity)| *quantity > 0)
+            .collect();
+        Ok(SettlementOutcome {
+            price,
+            trades,
+            unfilled,
+        })
+    }
+}
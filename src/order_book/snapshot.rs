@@ -0,0 +1,160 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::price_key::PriceKey;
+use crate::price_level::PriceLevel;
+use crate::types::{BookSnapshot, Order, OrderBookConfig, OrderEvent, OrderId, Side};
+use rust_decimal::Decimal;
+
+impl OrderBook {
+    /// Copies the resting orders of the book into a `BookSnapshot`.
+    ///
+    /// The snapshot holds what is needed to restore the resting orders with
+    /// `from_snapshot`; fills, client order identifiers, quotes, the trade log, and the
+    /// book's settings are not part of it.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, OrderId, Side};
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+    /// order_book.insert_order(Order::new(100.00, 5, Side::Bid));
+    ///
+    /// let snapshot = order_book.snapshot();
+    /// assert_eq!(snapshot.sequence, 2);
+    /// assert_eq!(snapshot.bids[0].0, OrderId(2));
+    ///
+    /// let restored = OrderBook::from_snapshot(&snapshot);
+    /// assert!(order_book.diff(&restored).is_empty());
+    /// assert_eq!(restored.snapshot(), snapshot);
+    /// ```
+    pub fn snapshot(&self) -> BookSnapshot {
+        BookSnapshot {
+            sequence: self.last_event_sequence,
+            next_order_id: self.next_order_id,
+            bids: self.side_orders(self.bids.iter().rev()),
+            asks: self.side_orders(self.asks.iter()),
+        }
+    }
+
+    /// Creates a book resting the orders of a snapshot.
+    ///
+    /// Orders keep their identifiers and time priority, and the new book continues
+    /// numbering orders and events where the snapshotted book stood. Restoring publishes
+    /// no events.
+    ///
+    /// ## Arguments
+    ///
+    /// * `snapshot`: The snapshot to restore, e.g. taken with `snapshot`
+    pub fn from_snapshot(snapshot: &BookSnapshot) -> Self {
+        let mut order_book = OrderBook::with_config(OrderBookConfig {
+            order_capacity: snapshot.bids.len() + snapshot.asks.len(),
+            ..OrderBookConfig::default()
+        });
+        order_book.rest_snapshot(snapshot);
+        order_book
+    }
+
+    /// Initializes an empty book from an external level 2 snapshot, e.g. of an exchange
+    /// whose deltas the book will then mirror.
+    ///
+    /// Each level becomes one synthetic resting order carrying the level's quantity, so
+    /// later deltas can reduce, cancel, or add to it. Levels with a zero quantity are
+    /// skipped. The orders are inserted like any other, bids first, in the given order,
+    /// and their events are returned for downstream consumers such as the
+    /// `MarketDepthCache`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `bids`: The `(price, quantity)` bid levels of the snapshot
+    /// * `asks`: The `(price, quantity)` ask levels of the snapshot
+    ///
+    /// ## Returns
+    ///
+    /// The events of the inserted orders, `OrderBookError::BookNotEmpty` if orders are
+    /// already resting, `OrderBookError::PriceTooPrecise` if the book's
+    /// `PricePrecision` rejects a level's price, or `OrderBookError::QuantityTooLarge`
+    /// if a level's quantity exceeds `i64::MAX`, in which case nothing is inserted
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{MarketDepthCache, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// let cache = MarketDepthCache::new();
+    /// let bids = [(Decimal::new(9950, 2), 12), (Decimal::new(9900, 2), 30)];
+    /// let asks = [(Decimal::new(10050, 2), 8)];
+    ///
+    /// for event in order_book.load_l2_snapshot(&bids, &asks).unwrap() {
+    ///     cache.process_order_event(event);
+    /// }
+    /// assert_eq!(order_book.bbo().best_bid, Some(Decimal::new(995, 1)));
+    /// assert_eq!(cache.get_quantity_at_level(Decimal::new(99, 0), Side::Bid), 42);
+    /// assert!(order_book.load_l2_snapshot(&bids, &asks).is_err());
+    /// ```
+    pub fn load_l2_snapshot(
+        &mut self,
+        bids: &[(Decimal, u64)],
+        asks: &[(Decimal, u64)],
+    ) -> Result<Vec<OrderEvent>> {
+        let order_count = self.order_count();
+        if order_count > 0 {
+            return Err(OrderBookError::BookNotEmpty { order_count });
+        }
+        for &(price, quantity) in bids.iter().chain(asks) {
+            self.checked_price(price)?;
+            Self::checked_quantity(quantity)?;
+        }
+
+        let levels = bids
+            .iter()
+            .map(|level| (level, Side::Bid))
+            .chain(asks.iter().map(|level| (level, Side::Ask)));
+        let mut events = Vec::with_capacity(bids.len() + asks.len());
+        for (&(price, quantity), side) in levels {
+            if quantity == 0 {
+                continue;
+            }
+            let order = Order {
+                price,
+                quantity,
+                side,
+            };
+            events.push(self.insert_order(order).event);
+        }
+        Ok(events)
+    }
+
+    /// Rests the orders of a snapshot in this empty book and continues its counters.
+    pub(super) fn rest_snapshot(&mut self, snapshot: &BookSnapshot) {
+        for (order_id, order) in snapshot.bids.iter().chain(&snapshot.asks) {
+            let order = Order {
+                price: self.canonical_price(order.price),
+                ..order.clone()
+            };
+            self.rest_order(*order_id, order);
+        }
+        self.next_order_id = snapshot.next_order_id;
+        self.last_event_sequence = snapshot.sequence;
+    }
+
+    /// Lists the orders of the given levels, in level order and time priority within a
+    /// level.
+    fn side_orders<'a>(
+        &self,
+        levels: impl Iterator<Item = (&'a PriceKey, &'a PriceLevel)>,
+    ) -> Vec<(OrderId, Order)> {
+        let mut orders = Vec::new();
+        for (_, price_level) in levels {
+            let mut cursor = price_level.head;
+            while let Some(node) = cursor.and_then(|slot| self.orders.get(slot)) {
+                orders.push((node.order_id, node.order.clone()));
+                cursor = node.next;
+            }
+        }
+        orders
+    }
+}
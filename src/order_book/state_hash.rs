@@ -0,0 +1,87 @@
+use super::OrderBook;
+use crate::price_key::PriceKey;
+use crate::price_level::PriceLevel;
+use crate::types::Side;
+
+/// The FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl OrderBook {
+    /// Computes a digest of the book's resting orders.
+    ///
+    /// The digest covers every resting order's identifier, side, price, and remaining
+    /// quantity, in price-time priority, so two books have the same digest exactly when
+    /// they rest the same orders in the same queues, whatever sequence of operations
+    /// led there. Prices are hashed by value, so `100.5` and `100.50` hash alike; fills,
+    /// the trade log, counters, and settings are not covered.
+    ///
+    /// The digest is a 64-bit FNV-1a over a fixed little-endian encoding, and does not
+    /// depend on the process, the platform, or the Rust version, so a primary and its
+    /// replicas, or a replay and its recording, can exchange digests to check they
+    /// agree without shipping snapshots. As with any 64-bit digest, distinct states
+    /// collide with negligible but nonzero probability.
+    ///
+    /// ## Returns
+    ///
+    /// The digest of the resting orders
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    ///
+    /// let mut primary = OrderBook::new();
+    /// let mut replica = OrderBook::new();
+    /// for order_book in [&mut primary, &mut replica] {
+    ///     order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    ///     order_book.insert_order(Order::new(101.00, 5, Side::Ask));
+    /// }
+    /// assert_eq!(primary.state_hash(), replica.state_hash());
+    ///
+    /// replica.match_order(Order::new(101.00, 1, Side::Bid));
+    /// assert_ne!(primary.state_hash(), replica.state_hash());
+    /// ```
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        self.hash_side(&mut hash, Side::Bid, self.bids.iter().rev());
+        self.hash_side(&mut hash, Side::Ask, self.asks.iter());
+        hash
+    }
+
+    /// Hashes the orders of one side, best price first, followed by their count.
+    fn hash_side<'a>(
+        &self,
+        hash: &mut u64,
+        side: Side,
+        levels: impl Iterator<Item = (&'a PriceKey, &'a PriceLevel)>,
+    ) {
+        let side_tag = match side {
+            Side::Bid => 0u8,
+            Side::Ask => 1u8,
+        };
+        let mut order_count = 0u64;
+        for (_, price_level) in levels {
+            let mut cursor = price_level.head;
+            while let Some(node) = cursor.and_then(|slot| self.orders.get(slot)) {
+                fnv1a(hash, &[side_tag]);
+                fnv1a(hash, &node.order_id.0.to_le_bytes());
+                fnv1a(hash, &node.order.price.normalize().serialize());
+                fnv1a(hash, &node.order.quantity.to_le_bytes());
+                order_count += 1;
+                cursor = node.next;
+            }
+        }
+        fnv1a(hash, &order_count.to_le_bytes());
+    }
+}
+
+/// Folds bytes into an FNV-1a hash.
+fn fnv1a(hash: &mut u64, bytes: &[u8]) {
+    for byte in bytes {
+        *hash ^= u64::from(*byte);
+        *hash = hash.wrapping_mul(FNV_PRIME);
+    }
+}
@@ -0,0 +1,185 @@
+use super::OrderBook;
+use crate::error::{OrderBookError, Result};
+use crate::memory::{btree_map_bytes, hash_map_bytes};
+use crate::types::{MatchOutcome, Order, OrderEvent, OrderId, Side};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+/// The position of a stop order in its side of the ladder: its trigger price, then its
+/// arrival among the stops of that price.
+type LadderKey = (Decimal, u64);
+
+/// The stop orders waiting for their trigger, ordered by trigger price on each side.
+///
+/// A trade can only release a prefix of each side, the buy stops triggering at or below
+/// its price and the sell stops triggering at or above it, so releasing splits the
+/// ordered maps at the trade prices instead of scanning every waiting stop.
+#[derive(Debug, Clone, Default)]
+pub(super) struct StopLadder {
+    /// Buy stops, released once a trade prints at or above their trigger
+    buys: BTreeMap<LadderKey, (OrderId, Order)>,
+    /// Sell stops, released once a trade prints at or below their trigger
+    sells: BTreeMap<LadderKey, (OrderId, Order)>,
+    /// The side and ladder key of every waiting stop, by identifier
+    keys: HashMap<OrderId, (Side, LadderKey)>,
+    /// The arrival number of the next stop
+    next_arrival: u64,
+}
+
+impl StopLadder {
+    /// Returns the ladder of one side.
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<LadderKey, (OrderId, Order)> {
+        match side {
+            Side::Bid => &mut self.buys,
+            Side::Ask => &mut self.sells,
+        }
+    }
+
+    /// Adds a stop order.
+    fn insert(&mut self, order_id: OrderId, order: Order, trigger_price: Decimal) {
+        let key = (trigger_price, self.next_arrival);
+        self.next_arrival += 1;
+        self.keys.insert(order_id, (order.side, key));
+        self.side_mut(order.side).insert(key, (order_id, order));
+    }
+
+    /// Removes a waiting stop order, returning it with its trigger price.
+    fn remove(&mut self, order_id: OrderId) -> Option<(Order, Decimal)> {
+        let (side, key) = self.keys.remove(&order_id)?;
+        let (_, order) = self.side_mut(side).remove(&key)?;
+        Some((order, key.0))
+    }
+
+    /// Removes the stops triggered by trades printed between `low` and `high`, buys by
+    /// ascending trigger then sells by descending trigger, each in arrival order within a
+    /// trigger price.
+    fn release(&mut self, low: Decimal, high: Decimal) -> Vec<(OrderId, Order)> {
+        let waiting_buys = self.buys.split_off(&(high, u64::MAX));
+        let released_buys = std::mem::replace(&mut self.buys, waiting_buys);
+        let released_sells = self.sells.split_off(&(low, 0));
+
+        let mut released = Vec::with_capacity(released_buys.len() + released_sells.len());
+        released.extend(released_buys.into_values());
+        let mut sells: Vec<_> = released_sells.into_iter().collect();
+        sells.sort_by(|((price, arrival), _), ((other_price, other_arrival), _)| {
+            other_price.cmp(price).then(arrival.cmp(other_arrival))
+        });
+        released.extend(sells.into_iter().map(|(_, stop)| stop));
+        for (order_id, _) in &released {
+            self.keys.remove(order_id);
+        }
+        released
+    }
+
+    /// Estimates the heap bytes held by the ladder.
+    pub(super) fn memory_bytes(&self) -> usize {
+        btree_map_bytes(&self.buys) + btree_map_bytes(&self.sells) + hash_map_bytes(&self.keys)
+    }
+
+    /// Releases the spare capacity of the identifier index.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.keys.shrink_to_fit();
+    }
+}
+
+impl OrderBook {
+    /// Holds a stop order until a trade reaches its trigger price, then matches it.
+    ///
+    /// A buy stop is released by the first trade printed at or above its trigger price,
+    /// and a sell stop by the first trade printed at or below it. Only trades printed
+    /// after the stop is inserted count. Once released, the order is matched like an
+    /// incoming `match_order` order under the identifier returned here, at its own
+    /// limit price, and rests whatever does not execute. Its trades may release further
+    /// stops in turn; the `MatchOutcome` of the call whose trades started the cascade
+    /// lists every stop released, and its events include theirs.
+    ///
+    /// Waiting stops are kept apart from the resting orders, ordered by trigger price,
+    /// so a trade only visits the stops it releases. They publish no events until they
+    /// are released, and snapshots and checkpoints do not hold them.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to match once triggered, with its limit price
+    /// * `trigger_price`: The trade price that releases it
+    ///
+    /// ## Returns
+    ///
+    /// The identifier the order will be matched under, or
+    /// `OrderBookError::PriceTooPrecise` if the book's `PricePrecision` rejects the
+    /// limit or the trigger price
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, OrderBook, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut order_book = OrderBook::new();
+    /// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    /// order_book.insert_order(Order::new(102.00, 10, Side::Ask));
+    /// let stop = order_book
+    ///     .insert_stop(Order::new(102.00, 5, Side::Bid), Decimal::new(101, 0))
+    ///     .unwrap();
+    ///
+    /// let outcome = order_book.match_order(Order::new(101.00, 10, Side::Bid));
+    /// assert_eq!(outcome.stops_triggered, vec![stop]);
+    /// assert_eq!(order_book.trades().last().unwrap().taker_order_id, stop);
+    /// assert_eq!(order_book.stop_order_count(), 0);
+    /// ```
+    pub fn insert_stop(&mut self, mut order: Order, trigger_price: Decimal) -> Result<OrderId> {
+        order.price = self.checked_order(&order)?;
+        let trigger_price = self
+            .checked_price(trigger_price)
+            .map_err(|reason| self.reject(reason, &order))?;
+        let order_id = self.assign_order_id();
+        self.stops.insert(order_id, order, trigger_price);
+        Ok(order_id)
+    }
+
+    /// Cancels a stop order that was not released yet.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier returned by `insert_stop`
+    ///
+    /// ## Returns
+    ///
+    /// The cancelled order, or `OrderBookError::OrderNotFound` if no such stop is
+    /// waiting
+    pub fn cancel_stop(&mut self, order_id: OrderId) -> Result<Order> {
+        self.stops
+            .remove(order_id)
+            .map(|(order, _)| order)
+            .ok_or(OrderBookError::OrderNotFound(order_id))
+    }
+
+    /// Returns the number of stop orders waiting for their trigger.
+    pub fn stop_order_count(&self) -> usize {
+        self.stops.keys.len()
+    }
+
+    /// Releases and matches the stops triggered by the trades printed since
+    /// `trades_before`, then those triggered by their own trades, until none is.
+    pub(super) fn release_stops(
+        &mut self,
+        trades_before: usize,
+        events: &mut Vec<OrderEvent>,
+        outcome: &mut MatchOutcome,
+    ) {
+        let mut scanned = trades_before;
+        while let Some((low, high)) = self.trades[scanned..].iter().fold(None, |range, trade| {
+            let (low, high) = range.unwrap_or((trade.price, trade.price));
+            Some((low.min(trade.price), high.max(trade.price)))
+        }) {
+            scanned = self.trades.len();
+            for (order_id, order) in self.stops.release(low, high) {
+                let released = self.match_assigned(order_id, order, events);
+                outcome.stops_triggered.push(order_id);
+                outcome
+                    .protections_triggered
+                    .extend(released.protections_triggered);
+                outcome.state_change = outcome.state_change.take().or(released.state_change);
+            }
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use crate::types::{unix_timestamp_nanos, MarketByOrderEvent, Side};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Counts of order flow on one side of the book within an `OrderFlowStats` window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlowCounts {
+    /// Number of orders added
+    pub adds: u64,
+    /// Number of cancellations, including partial reductions
+    pub cancels: u64,
+    /// Number of executions against resting orders
+    pub executions: u64,
+    /// Number of orders replaced
+    pub replaces: u64,
+    /// Total quantity added, including the new quantity of replaced orders
+    pub added_quantity: u64,
+    /// Total quantity cancelled
+    pub cancelled_quantity: u64,
+    /// Total quantity executed
+    pub executed_quantity: u64,
+}
+
+/// How far from the touch of its side an order event happened, as counted by
+/// `DistanceStats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DistanceBucket {
+    /// At the best price of the side, or better
+    AtTouch,
+    /// One to five ticks behind the best price
+    NearTouch,
+    /// More than five ticks behind the best price
+    AwayFromTouch,
+}
+
+impl DistanceBucket {
+    /// The buckets, closest to the touch first.
+    pub const ALL: [DistanceBucket; 3] = [
+        DistanceBucket::AtTouch,
+        DistanceBucket::NearTouch,
+        DistanceBucket::AwayFromTouch,
+    ];
+
+    /// Returns the bucket of a distance behind the touch, in whole ticks.
+    pub fn of_ticks(ticks: u64) -> Self {
+        match ticks {
+            0 => DistanceBucket::AtTouch,
+            1..=5 => DistanceBucket::NearTouch,
+            _ => DistanceBucket::AwayFromTouch,
+        }
+    }
+
+    /// Returns the position of the bucket in `ALL`.
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Order flow attributed to the distance from the touch at which it happened, as
+/// maintained by a book (see `OrderBook::set_distance_stats`).
+///
+/// Each add, cancel, execution, and replacement is counted in the `FlowCounts` of its
+/// side and of the `DistanceBucket` of its price, measured in ticks behind the best
+/// price of its side when the book publishes it: an order resting behind the best bid
+/// or ask, or cancelled from there, is counted by how far behind it is, while orders
+/// improving or joining the touch, executions, and cancellations of the last order at
+/// the touch are counted at the touch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceStats {
+    /// The price of one tick
+    tick_size: Decimal,
+    /// The counts of the bid side, by bucket
+    bids: [FlowCounts; 3],
+    /// The counts of the ask side, by bucket
+    asks: [FlowCounts; 3],
+}
+
+impl DistanceStats {
+    /// Creates empty statistics measuring distances in ticks of `tick_size`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `tick_size` is not positive.
+    pub fn new(tick_size: Decimal) -> Self {
+        assert!(tick_size > Decimal::ZERO, "tick size must be positive");
+        DistanceStats {
+            tick_size,
+            bids: [FlowCounts::default(); 3],
+            asks: [FlowCounts::default(); 3],
+        }
+    }
+
+    /// Returns the price of the ticks distances are measured in.
+    pub fn tick_size(&self) -> Decimal {
+        self.tick_size
+    }
+
+    /// Returns the counts of one side within one bucket.
+    pub fn counts(&self, side: Side, bucket: DistanceBucket) -> FlowCounts {
+        match side {
+            Side::Bid => self.bids[bucket.index()],
+            Side::Ask => self.asks[bucket.index()],
+        }
+    }
+
+    /// Counts a market-by-order event against the best price of its side.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The event
+    /// * `best_bid`: The best bid when the event is published, if any
+    /// * `best_ask`: The best ask when the event is published, if any
+    pub(crate) fn record(
+        &mut self,
+        event: &MarketByOrderEvent,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+    ) {
+        let (price, side) = match *event {
+            MarketByOrderEvent::Added { price, side, .. }
+            | MarketByOrderEvent::Executed { price, side, .. }
+            | MarketByOrderEvent::Cancelled { price, side, .. }
+            | MarketByOrderEvent::Replaced { price, side, .. } => (price, side),
+        };
+        let behind = match side {
+            Side::Bid => best_bid.map(|best_bid| best_bid - price),
+            Side::Ask => best_ask.map(|best_ask| price - best_ask),
+        }
+        .unwrap_or(Decimal::ZERO);
+        let ticks = (behind / self.tick_size)
+            .floor()
+            .to_u64()
+            .unwrap_or(if behind > Decimal::ZERO { u64::MAX } else { 0 });
+        let counts = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        counts[DistanceBucket::of_ticks(ticks).index()].count(event);
+    }
+}
+
+/// What a recorded market-by-order event did to the book.
+#[derive(Debug, Clone, Copy)]
+enum FlowKind {
+    Add,
+    Cancel,
+    Execution,
+    Replace,
+}
+
+/// A market-by-order event reduced to what the statistics need.
+#[derive(Debug, Clone, Copy)]
+struct FlowEntry {
+    timestamp_nanos: u64,
+    kind: FlowKind,
+    side: Side,
+    quantity: u64,
+}
+
+impl FlowEntry {
+    /// Reduces a market-by-order event to an entry.
+    fn new(event: &MarketByOrderEvent, timestamp_nanos: u64) -> Self {
+        let (kind, side, quantity) = match *event {
+            MarketByOrderEvent::Added { side, quantity, .. } => (FlowKind::Add, side, quantity),
+            MarketByOrderEvent::Executed {
+                side,
+                executed_quantity,
+                ..
+            } => (FlowKind::Execution, side, executed_quantity),
+            MarketByOrderEvent::Cancelled {
+                side,
+                cancelled_quantity,
+                ..
+            } => (FlowKind::Cancel, side, cancelled_quantity),
+            MarketByOrderEvent::Replaced { side, quantity, .. } => {
+                (FlowKind::Replace, side, quantity)
+            }
+        };
+        FlowEntry {
+            timestamp_nanos,
+            kind,
+            side,
+            quantity,
+        }
+    }
+}
+
+impl FlowCounts {
+    /// Counts a market-by-order event.
+    pub(crate) fn count(&mut self, event: &MarketByOrderEvent) {
+        self.apply(&FlowEntry::new(event, 0), 1);
+    }
+
+    /// Adds (`sign` = 1) or removes (`sign` = -1) an entry from the counts.
+    fn apply(&mut self, entry: &FlowEntry, sign: i64) {
+        let (count, quantity) = match entry.kind {
+            FlowKind::Add => (&mut self.adds, &mut self.added_quantity),
+            FlowKind::Cancel => (&mut self.cancels, &mut self.cancelled_quantity),
+            FlowKind::Execution => (&mut self.executions, &mut self.executed_quantity),
+            FlowKind::Replace => (&mut self.replaces, &mut self.added_quantity),
+        };
+        *count = count.wrapping_add_signed(sign);
+        *quantity = quantity.wrapping_add_signed(sign * entry.quantity as i64);
+    }
+}
+
+/// Counts adds, cancels, and executions per side over a rolling time window.
+///
+/// The statistics are driven purely by the market-by-order event stream (see
+/// `OrderBook::set_market_by_order_events`), so they can run next to the book or on
+/// a consumer replaying the feed. Counts are attributed to the side of the order an
+/// event describes: an execution is counted on the resting order's side.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Order, OrderBook, OrderFlowStats, Side};
+/// use std::time::Duration;
+///
+/// let mut order_book = OrderBook::new();
+/// order_book.set_market_by_order_events(true);
+/// let mut order_flow_stats = OrderFlowStats::new(Duration::from_secs(60));
+///
+/// let resting = order_book.insert_order(Order::new(100.00, 10, Side::Ask)).handle;
+/// order_book.match_order(Order::new(100.00, 4, Side::Bid));
+/// order_book.cancel_by_handle(resting).unwrap();
+///
+/// for event in order_book.take_market_by_order_events() {
+///     order_flow_stats.process_market_by_order_event(&event);
+/// }
+///
+/// assert_eq!(order_flow_stats.counts(Side::Ask).adds, 1);
+/// assert_eq!(order_flow_stats.cancel_to_trade_ratio(), Some(1.0));
+/// assert_eq!(order_flow_stats.net_order_flow(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderFlowStats {
+    /// How far back the counts look
+    window: Duration,
+    /// Entries recorded within the window, oldest first
+    entries: VecDeque<FlowEntry>,
+    /// Running counts of the entries on the bid side
+    bid_counts: FlowCounts,
+    /// Running counts of the entries on the ask side
+    ask_counts: FlowCounts,
+}
+
+impl OrderFlowStats {
+    /// Creates statistics counting over the given window.
+    ///
+    /// ## Arguments
+    ///
+    /// * `window`: How far back from the latest event the counts look
+    pub fn new(window: Duration) -> Self {
+        OrderFlowStats {
+            window,
+            entries: VecDeque::new(),
+            bid_counts: FlowCounts::default(),
+            ask_counts: FlowCounts::default(),
+        }
+    }
+
+    /// Records a market-by-order event as happening now.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The market-by-order event published by the order book
+    pub fn process_market_by_order_event(&mut self, event: &MarketByOrderEvent) {
+        self.record(event, unix_timestamp_nanos());
+    }
+
+    /// Records a market-by-order event that happened at the given time.
+    ///
+    /// Timestamps are expected to be non-decreasing; entries older than the window
+    /// ending at `timestamp_nanos` are dropped from the counts.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The market-by-order event published by the order book
+    /// * `timestamp_nanos`: When the event happened, in nanoseconds since the Unix epoch
+    pub fn record(&mut self, event: &MarketByOrderEvent, timestamp_nanos: u64) {
+        let entry = FlowEntry::new(event, timestamp_nanos);
+        self.counts_mut(entry.side).apply(&entry, 1);
+        self.entries.push_back(entry);
+
+        let window_nanos = u64::try_from(self.window.as_nanos()).unwrap_or(u64::MAX);
+        let window_start = timestamp_nanos.saturating_sub(window_nanos);
+        while let Some(oldest) = self.entries.front().copied() {
+            if oldest.timestamp_nanos >= window_start {
+                break;
+            }
+            self.counts_mut(oldest.side).apply(&oldest, -1);
+            self.entries.pop_front();
+        }
+    }
+
+    /// Returns the counts for one side of the book within the window.
+    ///
+    /// ## Arguments
+    ///
+    /// * `side`: The side (bid or ask) to query
+    pub fn counts(&self, side: Side) -> FlowCounts {
+        match side {
+            Side::Bid => self.bid_counts,
+            Side::Ask => self.ask_counts,
+        }
+    }
+
+    /// Returns the number of cancellations per execution within the window.
+    ///
+    /// ## Returns
+    ///
+    /// The ratio over both sides, or `None` if nothing was executed
+    pub fn cancel_to_trade_ratio(&self) -> Option<f64> {
+        let cancels = self.bid_counts.cancels + self.ask_counts.cancels;
+        let executions = self.bid_counts.executions + self.ask_counts.executions;
+        (executions > 0).then(|| cancels as f64 / executions as f64)
+    }
+
+    /// Returns the aggressive buy quantity minus the aggressive sell quantity.
+    ///
+    /// Executions against resting asks are buyer-initiated and executions against
+    /// resting bids are seller-initiated, so a positive value means buyers have been
+    /// lifting offers more than sellers have been hitting bids.
+    pub fn net_order_flow(&self) -> i64 {
+        self.ask_counts.executed_quantity as i64 - self.bid_counts.executed_quantity as i64
+    }
+
+    /// Returns the running counts of `side`.
+    fn counts_mut(&mut self, side: Side) -> &mut FlowCounts {
+        match side {
+            Side::Bid => &mut self.bid_counts,
+            Side::Ask => &mut self.ask_counts,
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! The types most programs using the crate need, for glob import.
+//!
+//! Besides the book, the cache, and the types passed to and returned by them, the
+//! prelude re-exports `Decimal`, in which prices are expressed, and the `RwLock` that
+//! books are shared with. As with any glob import, names imported explicitly
+//! by the importing module take precedence, so the prelude never collides with
+//! a crate's own `Decimal` or `RwLock`.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::prelude::*;
+//!
+//! let order_book = RwLock::new(OrderBook::new());
+//! let market_depth_cache = MarketDepthCache::new();
+//!
+//! let event = order_book.write().insert_order(Order::new(100.50, 10, Side::Bid)).event;
+//! market_depth_cache.process_order_event(event);
+//! assert_eq!(
+//!     market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid),
+//!     10
+//! );
+//! ```
+
+pub use crate::error::{OrderBookError, Result};
+pub use crate::market_depth_cache::MarketDepthCache;
+pub use crate::order_book::OrderBook;
+pub use crate::shared::SharedOrderBook;
+pub use crate::types::{
+    Command, InsertOutcome, MatchOutcome, Order, OrderEvent, OrderHandle, OrderId, Side, Trade,
+};
+pub use parking_lot::RwLock;
+pub use rust_decimal::Decimal;
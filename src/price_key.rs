@@ -0,0 +1,110 @@
+//! The keys of the order book's price level maps.
+//!
+//! By default levels are keyed by the (normalized) `Decimal` price itself. With the
+//! `tick-prices` feature, levels are keyed by an `i64` number of ticks instead, so the
+//! comparisons performed on every map operation are integer comparisons; prices are
+//! converted to and from ticks only where they cross the book's API.
+
+use crate::types::PriceNormalization;
+use rust_decimal::Decimal;
+
+/// The key of a price level in the book's level maps.
+#[cfg(not(feature = "tick-prices"))]
+pub(crate) type PriceKey = Decimal;
+
+/// The key of a price level in the book's level maps, in ticks.
+#[cfg(feature = "tick-prices")]
+pub(crate) type PriceKey = i64;
+
+/// The tick size used by books created without an explicit one.
+#[cfg(feature = "tick-prices")]
+pub(crate) const DEFAULT_TICK_SIZE: Decimal = Decimal::from_parts(1, 0, 0, false, 8);
+
+/// Converts prices to level keys and back.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PriceKeys {
+    /// The price of one tick
+    #[cfg(feature = "tick-prices")]
+    tick_size: Decimal,
+}
+
+impl PriceKeys {
+    /// Creates keys for the default representation.
+    pub(crate) fn new() -> Self {
+        PriceKeys {
+            #[cfg(feature = "tick-prices")]
+            tick_size: DEFAULT_TICK_SIZE,
+        }
+    }
+
+    /// Creates tick keys with the given tick size.
+    ///
+    /// Panics if `tick_size` is not positive.
+    #[cfg(feature = "tick-prices")]
+    pub(crate) fn with_tick_size(tick_size: Decimal) -> Self {
+        assert!(tick_size > Decimal::ZERO, "tick size must be positive");
+        PriceKeys { tick_size }
+    }
+
+    /// Returns the price of one tick.
+    #[cfg(feature = "tick-prices")]
+    pub(crate) fn tick_size(&self) -> Decimal {
+        self.tick_size
+    }
+
+    /// Returns the key of a price already on the key grid.
+    #[cfg(not(feature = "tick-prices"))]
+    #[inline]
+    pub(crate) fn key(&self, price: Decimal) -> PriceKey {
+        price
+    }
+
+    /// Returns the key of a price, rounded to the nearest tick.
+    ///
+    /// Panics if the price is more than `i64::MAX` ticks away from zero.
+    #[cfg(feature = "tick-prices")]
+    #[inline]
+    pub(crate) fn key(&self, price: Decimal) -> PriceKey {
+        use rust_decimal::prelude::ToPrimitive;
+
+        (price / self.tick_size)
+            .round()
+            .to_i64()
+            .expect("price out of range for the tick size")
+    }
+
+    /// Returns the key of a price, or `None` if it does not lie on the key grid.
+    #[inline]
+    pub(crate) fn exact_key(&self, price: Decimal) -> Option<PriceKey> {
+        #[cfg(feature = "tick-prices")]
+        if !(price % self.tick_size).is_zero() {
+            return None;
+        }
+        Some(self.key(price))
+    }
+
+    /// Returns the price of a key, which is already canonical.
+    #[cfg(not(feature = "tick-prices"))]
+    #[inline]
+    pub(crate) fn price(&self, key: PriceKey, _: PriceNormalization) -> Decimal {
+        key
+    }
+
+    /// Returns the price of a key, canonicalized with `price_normalization`.
+    #[cfg(feature = "tick-prices")]
+    #[inline]
+    pub(crate) fn price(&self, key: PriceKey, price_normalization: PriceNormalization) -> Decimal {
+        price_normalization.apply(Decimal::from(key) * self.tick_size)
+    }
+
+    /// Moves an already normalized price onto the key grid.
+    ///
+    /// Prices on the grid are returned unchanged, so their representation is kept.
+    #[inline]
+    pub(crate) fn snap(&self, price: Decimal, price_normalization: PriceNormalization) -> Decimal {
+        match self.exact_key(price) {
+            Some(_) => price,
+            None => self.price(self.key(price), price_normalization),
+        }
+    }
+}
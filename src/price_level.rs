@@ -0,0 +1,98 @@
+use crate::slab::Slab;
+use crate::types::{FillSummary, Order, OrderId, OrderMetadata};
+use crate::units::saturating_accumulate;
+
+/// A resting order stored in the order slab.
+///
+/// Nodes are linked into a doubly-linked FIFO per price level, so an order can be
+/// unlinked in $O(1)$ once its slot is known.
+#[derive(Debug, Clone)]
+pub(crate) struct OrderNode {
+    /// The identifier assigned by the book at insertion
+    pub(crate) order_id: OrderId,
+    /// The order itself, with its remaining quantity
+    pub(crate) order: Order,
+    /// The executions of the order so far
+    pub(crate) fills: FillSummary,
+    /// The caller's payload attached to the order, if any
+    pub(crate) metadata: Option<OrderMetadata>,
+    /// Slot of the order ahead of this one at the same price
+    pub(crate) previous: Option<usize>,
+    /// Slot of the order behind this one at the same price
+    pub(crate) next: Option<usize>,
+}
+
+/// All orders resting at a single exact price, in time priority.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PriceLevel {
+    /// Slot of the oldest order (first to be filled)
+    pub(crate) head: Option<usize>,
+    /// Slot of the newest order
+    pub(crate) tail: Option<usize>,
+    /// Sum of the remaining quantities of all orders at this level
+    pub(crate) total_quantity: u64,
+    /// Number of orders at this level
+    pub(crate) order_count: usize,
+}
+
+impl PriceLevel {
+    /// Appends the order stored at `slot` to the back of the queue.
+    ///
+    /// Returns whether the level's total quantity left the range of `u64` and was
+    /// saturated.
+    pub(crate) fn push_back(&mut self, slot: usize, orders: &mut Slab<OrderNode>) -> bool {
+        let quantity = orders
+            .get(slot)
+            .map(|node| node.order.quantity)
+            .unwrap_or(0);
+
+        if let Some(tail) = self.tail {
+            if let Some(tail_node) = orders.get_mut(tail) {
+                tail_node.next = Some(slot);
+            }
+        } else {
+            self.head = Some(slot);
+        }
+        if let Some(node) = orders.get_mut(slot) {
+            node.previous = self.tail;
+            node.next = None;
+        }
+
+        self.tail = Some(slot);
+        self.order_count += 1;
+        saturating_accumulate(&mut self.total_quantity, i128::from(quantity))
+    }
+
+    /// Unlinks the order stored at `slot` from the queue without freeing its slot.
+    pub(crate) fn unlink(&mut self, slot: usize, orders: &mut Slab<OrderNode>) {
+        let Some(node) = orders.get(slot) else {
+            return;
+        };
+        let (previous, next, quantity) = (node.previous, node.next, node.order.quantity);
+
+        match previous {
+            Some(previous) => {
+                if let Some(previous_node) = orders.get_mut(previous) {
+                    previous_node.next = next;
+                }
+            }
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => {
+                if let Some(next_node) = orders.get_mut(next) {
+                    next_node.previous = previous;
+                }
+            }
+            None => self.tail = previous,
+        }
+
+        self.total_quantity = self.total_quantity.saturating_sub(quantity);
+        self.order_count -= 1;
+    }
+
+    /// Returns whether no orders rest at this level.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.order_count == 0
+    }
+}
@@ -0,0 +1,132 @@
+use crate::fan_out::SubscriptionId;
+use crate::types::{LevelAlert, Side};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A callback registered with `MarketDepthCache::watch_price`.
+type WatchCallback = dyn Fn(&LevelAlert) + Send + Sync;
+
+/// A level watched by a callback.
+struct PriceWatch {
+    /// The identifier returned to the watcher
+    watch_id: SubscriptionId,
+    /// The side of the level
+    side: Side,
+    /// The aggregated level
+    price: Decimal,
+    /// The smallest change of quantity reported
+    threshold: u64,
+    /// The quantity of the level when it was last reported, or when the watch started
+    reported_quantity: u64,
+    /// What to call with the changes
+    callback: Arc<WatchCallback>,
+}
+
+/// The levels of a `MarketDepthCache` watched by callbacks.
+///
+/// The number of watches is kept apart from the list, so a cache nobody watches pays
+/// one atomic load per update.
+#[derive(Default)]
+pub(crate) struct PriceWatches {
+    /// The number of watches in `watches`
+    active: AtomicUsize,
+    /// The identifier of the last watch registered
+    last_watch_id: AtomicU64,
+    /// The watches, in registration order
+    watches: Mutex<Vec<PriceWatch>>,
+}
+
+impl fmt::Debug for PriceWatches {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("PriceWatches")
+            .field("active", &self.active.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl PriceWatches {
+    /// Registers a callback watching a level whose quantity is currently `quantity`.
+    pub(crate) fn watch(
+        &self,
+        side: Side,
+        price: Decimal,
+        threshold: u64,
+        quantity: u64,
+        callback: Arc<WatchCallback>,
+    ) -> SubscriptionId {
+        let watch_id = SubscriptionId(self.last_watch_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let mut watches = self.watches.lock();
+        watches.push(PriceWatch {
+            watch_id,
+            side,
+            price,
+            threshold: threshold.max(1),
+            reported_quantity: quantity,
+            callback,
+        });
+        self.active.store(watches.len(), Ordering::Relaxed);
+        watch_id
+    }
+
+    /// Removes a watch, returning whether it was registered.
+    pub(crate) fn unwatch(&self, watch_id: SubscriptionId) -> bool {
+        let mut watches = self.watches.lock();
+        let count = watches.len();
+        watches.retain(|watch| watch.watch_id != watch_id);
+        self.active.store(watches.len(), Ordering::Relaxed);
+        watches.len() < count
+    }
+
+    /// Returns the number of watches.
+    pub(crate) fn len(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Reports the new quantity of a level to the watches it moved far enough.
+    pub(crate) fn level_changed(&self, side: Side, price: Decimal, quantity: u64) {
+        if self.len() == 0 {
+            return;
+        }
+        self.report(|watch| (watch.side == side && watch.price == price).then_some(quantity));
+    }
+
+    /// Reports the quantity of every watched level, e.g. after the depth was replaced.
+    pub(crate) fn all_changed(&self, quantity_of: impl Fn(Side, Decimal) -> u64) {
+        if self.len() == 0 {
+            return;
+        }
+        self.report(|watch| Some(quantity_of(watch.side, watch.price)));
+    }
+
+    /// Fires the callbacks of the watches whose new quantity, if any, moved at least
+    /// their threshold since they last fired.
+    ///
+    /// The callbacks are called once the watches are unlocked, so they may watch or
+    /// unwatch levels themselves.
+    fn report(&self, new_quantity: impl Fn(&PriceWatch) -> Option<u64>) {
+        let mut alerts = Vec::new();
+        for watch in self.watches.lock().iter_mut() {
+            let Some(quantity) = new_quantity(watch) else {
+                continue;
+            };
+            if quantity.abs_diff(watch.reported_quantity) < watch.threshold {
+                continue;
+            }
+            let alert = LevelAlert {
+                price: watch.price,
+                side: watch.side,
+                previous_quantity: watch.reported_quantity,
+                quantity,
+            };
+            watch.reported_quantity = quantity;
+            alerts.push((Arc::clone(&watch.callback), alert));
+        }
+        for (callback, alert) in alerts {
+            callback(&alert);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Recordings of sessions and golden-file regression checks replaying them.
+//!
+//! A `Recorder` applies commands to a book and writes everything that happened to a
+//! compact file of version 1 `wire` frames: a `Checkpoint` of the book when recording
+//! started, then each command followed by the events and the trades it produced, and a
+//! final `Checkpoint` written by `Recorder::finish`. Kept as a golden file, a recording
+//! of a real session gates changes to the matching logic: `verify_against_golden`
+//! replays its commands on a fresh book and reports the first command whose events or
+//! trades differ from the recorded ones, or the first difference in the final state.
+//!
+//! Event timestamps are not compared, as they come from the system's wall clock unless
+//! the session sets the book's time. The book's settings, such as its tick size or its
+//! seed, are not recorded either; a recording of a book created otherwise than with
+//! `OrderBook::new()` is verified with `verify_recording` on a book set up the same way.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::recording::{verify_recording, Recorder};
+//! use order_book::{Command, Order, OrderBook, Side};
+//!
+//! let mut order_book = OrderBook::new();
+//! let mut recorder = Recorder::new(Vec::new(), &order_book).unwrap();
+//! recorder.apply(&mut order_book, Command::Insert(Order::new(100.00, 10, Side::Bid))).unwrap();
+//! recorder.apply(&mut order_book, Command::Match(Order::new(100.00, 4, Side::Ask))).unwrap();
+//! let golden = recorder.finish(&order_book).unwrap();
+//!
+//! assert_eq!(verify_recording(&golden, &mut OrderBook::new()).unwrap(), 2);
+//! ```
+
+use crate::error::OrderBookError;
+use crate::order_book::OrderBook;
+use crate::types::{Checkpoint, Command, OrderEvent, Trade};
+use crate::wire::{decode_v1, encode_v1, DecodeError, Record};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Why a session could not be recorded or a recording verified.
+#[derive(Debug)]
+pub enum RecordingError {
+    /// Reading or writing the recording failed
+    Io(io::Error),
+    /// The recording holds a frame that cannot be decoded
+    Corrupt {
+        /// The offset of the frame in the recording, in bytes
+        offset: u64,
+        /// Why the frame cannot be decoded
+        error: DecodeError,
+    },
+    /// The recording does not start with a checkpoint or does not end with one, e.g.
+    /// because it was never finished or was cut off
+    Incomplete,
+    /// The command was recorded, but the book rejected it, leaving the book unchanged
+    ///
+    /// The command is recorded without events, which its replay is expected to
+    /// produce again.
+    Rejected(OrderBookError),
+    /// Replaying the recording gave different results than recording it
+    Diverged {
+        /// The index of the first command whose results differ, or the number of
+        /// commands if only the final state differs
+        step: usize,
+        /// What differed, with the recorded and the replayed values
+        reason: String,
+    },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(formatter, "recording I/O failed: {error}"),
+            RecordingError::Corrupt { offset, error } => {
+                write!(formatter, "recording is corrupt at byte {offset}: {error}")
+            }
+            RecordingError::Incomplete => {
+                write!(formatter, "recording lacks its initial or final checkpoint")
+            }
+            RecordingError::Rejected(error) => {
+                write!(formatter, "recorded command was rejected: {error}")
+            }
+            RecordingError::Diverged { step, reason } => {
+                write!(formatter, "replay diverged at step {step}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecordingError::Io(error) => Some(error),
+            RecordingError::Corrupt { error, .. } => Some(error),
+            RecordingError::Rejected(error) => Some(error),
+            RecordingError::Incomplete | RecordingError::Diverged { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+/// Writes the commands applied to a book, with their events and trades, to a
+/// recording.
+///
+/// Commands are applied through `apply`, which records them. The recorder does not
+/// watch the book, so a book being recorded should not be changed any other way.
+#[derive(Debug)]
+pub struct Recorder<W: Write = BufWriter<File>> {
+    /// Where the recording is written
+    writer: W,
+    /// The frames of the command being recorded, reused between commands
+    buffer: Vec<u8>,
+    /// The number of commands recorded
+    command_count: usize,
+}
+
+impl Recorder<BufWriter<File>> {
+    /// Creates a recording file at a path, replacing any file there, and records the
+    /// book's current state as the recording's starting point.
+    ///
+    /// ## Arguments
+    ///
+    /// * `path`: The path of the recording
+    /// * `order_book`: The book about to be recorded
+    ///
+    /// ## Returns
+    ///
+    /// The recorder, or an error if the file cannot be created or written
+    pub fn create(path: impl AsRef<Path>, order_book: &OrderBook) -> Result<Self, RecordingError> {
+        Self::new(BufWriter::new(File::create(path)?), order_book)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a recording in a writer, recording the book's current state as its
+    /// starting point.
+    ///
+    /// ## Arguments
+    ///
+    /// * `writer`: Where the recording is written
+    /// * `order_book`: The book about to be recorded
+    ///
+    /// ## Returns
+    ///
+    /// The recorder, or an error if the writer fails
+    pub fn new(mut writer: W, order_book: &OrderBook) -> Result<Self, RecordingError> {
+        let mut buffer = Vec::new();
+        encode_v1(&Record::Checkpoint(order_book.checkpoint()), &mut buffer);
+        writer.write_all(&buffer)?;
+        Ok(Recorder {
+            writer,
+            buffer,
+            command_count: 0,
+        })
+    }
+
+    /// Applies a command to the book and records it with its events and trades.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book being recorded
+    /// * `command`: The command to apply
+    ///
+    /// ## Returns
+    ///
+    /// The events of the command, or an error if it could not be recorded or was
+    /// rejected by the book, in which case it is recorded without events
+    pub fn apply(
+        &mut self,
+        order_book: &mut OrderBook,
+        command: Command,
+    ) -> Result<Vec<OrderEvent>, RecordingError> {
+        let trade_count = order_book.trades().len();
+        self.buffer.clear();
+        encode_v1(&Record::Command(command.clone()), &mut self.buffer);
+        let result = order_book.apply(command);
+
+        for event in result.iter().flatten() {
+            encode_v1(&Record::OrderEvent(event.clone()), &mut self.buffer);
+        }
+        for trade in printed_trades(order_book, trade_count) {
+            encode_v1(&Record::Trade(trade.clone()), &mut self.buffer);
+        }
+        self.writer.write_all(&self.buffer)?;
+        self.command_count += 1;
+        result.map_err(RecordingError::Rejected)
+    }
+
+    /// Records the book's final state and flushes the recording.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book being recorded
+    ///
+    /// ## Returns
+    ///
+    /// The writer, or an error if it fails
+    pub fn finish(mut self, order_book: &OrderBook) -> Result<W, RecordingError> {
+        self.buffer.clear();
+        encode_v1(
+            &Record::Checkpoint(order_book.checkpoint()),
+            &mut self.buffer,
+        );
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Returns the number of commands recorded.
+    pub fn command_count(&self) -> usize {
+        self.command_count
+    }
+}
+
+/// Returns the trades a command printed, given the length of the trade log before it.
+///
+/// Ending a session hands the trade log over, so a log shorter than before printed none.
+fn printed_trades(order_book: &OrderBook, trade_count: usize) -> &[Trade] {
+    order_book.trades().get(trade_count..).unwrap_or(&[])
+}
+
+/// A command of a recording with the results recorded for it.
+struct RecordedStep {
+    /// The command
+    command: Command,
+    /// The events it produced
+    events: Vec<OrderEvent>,
+    /// The trades it printed
+    trades: Vec<Trade>,
+}
+
+/// Replays the recording file at a path on an `OrderBook::new()` and checks that it
+/// gives the recorded results.
+///
+/// ## Arguments
+///
+/// * `path`: The path of the recording
+///
+/// ## Returns
+///
+/// The number of commands verified, or `RecordingError::Diverged` describing the first
+/// difference, or an error if the recording cannot be read or is corrupt or incomplete
+///
+/// ## Examples
+///
+/// ```no_run
+/// use order_book::recording::verify_against_golden;
+///
+/// let command_count = verify_against_golden("tests/golden/session.rec").unwrap();
+/// assert!(command_count > 0);
+/// ```
+pub fn verify_against_golden(path: impl AsRef<Path>) -> Result<usize, RecordingError> {
+    verify_recording(&std::fs::read(path)?, &mut OrderBook::new())
+}
+
+/// Replays a recording on a book and checks that it gives the recorded results.
+///
+/// The recording's starting state is restored into the book, then each command is
+/// applied and its events, ignoring their timestamps, and its trades are compared to
+/// the recorded ones. Finally, the book's state is compared to the recorded final
+/// state.
+///
+/// ## Arguments
+///
+/// * `bytes`: The recording
+/// * `order_book`: The empty book to replay into, configured as the recorded book was
+///
+/// ## Returns
+///
+/// The number of commands verified, or `RecordingError::Diverged` describing the first
+/// difference, or an error if the recording is corrupt or incomplete
+pub fn verify_recording(bytes: &[u8], order_book: &mut OrderBook) -> Result<usize, RecordingError> {
+    let (initial, steps, last) = read_recording(bytes)?;
+    order_book.restore_checkpoint(&initial);
+
+    for (step, recorded) in steps.iter().enumerate() {
+        let trade_count = order_book.trades().len();
+        let events = order_book
+            .apply(recorded.command.clone())
+            .unwrap_or_default();
+        let diverged = |reason: String| RecordingError::Diverged {
+            step,
+            reason: format!("{:?}: {reason}", recorded.command),
+        };
+
+        if events.len() != recorded.events.len() {
+            return Err(diverged(format!(
+                "recorded {} events, replayed {}",
+                recorded.events.len(),
+                events.len()
+            )));
+        }
+        for (index, (expected, actual)) in recorded.events.iter().zip(&events).enumerate() {
+            let actual = OrderEvent {
+                timestamp_nanos: expected.timestamp_nanos,
+                ..actual.clone()
+            };
+            if actual != *expected {
+                return Err(diverged(format!(
+                    "event {index} was recorded as {expected:?}, replayed as {actual:?}"
+                )));
+            }
+        }
+        let trades = printed_trades(order_book, trade_count);
+        if trades != recorded.trades {
+            return Err(diverged(format!(
+                "recorded trades {:?}, replayed {trades:?}",
+                recorded.trades
+            )));
+        }
+    }
+
+    let replayed = order_book.checkpoint();
+    if let Some(reason) = checkpoint_difference(&last, &replayed) {
+        return Err(RecordingError::Diverged {
+            step: steps.len(),
+            reason,
+        });
+    }
+    Ok(steps.len())
+}
+
+/// Describes the first difference between a recorded and a replayed final state.
+fn checkpoint_difference(expected: &Checkpoint, actual: &Checkpoint) -> Option<String> {
+    let difference = |name: &str, expected: &dyn fmt::Debug, actual: &dyn fmt::Debug| {
+        Some(format!(
+            "final {name} was recorded as {expected:?}, replayed as {actual:?}"
+        ))
+    };
+    let (expected_book, actual_book) = (&expected.book, &actual.book);
+    for (name, expected_orders, actual_orders) in [
+        ("bids", &expected_book.bids, &actual_book.bids),
+        ("asks", &expected_book.asks, &actual_book.asks),
+    ] {
+        if expected_orders != actual_orders {
+            let position = expected_orders
+                .iter()
+                .zip(actual_orders)
+                .position(|(expected, actual)| expected != actual)
+                .unwrap_or(expected_orders.len().min(actual_orders.len()));
+            return difference(
+                &format!("{name} from position {position}"),
+                &expected_orders.get(position),
+                &actual_orders.get(position),
+            );
+        }
+    }
+
+    if expected_book.sequence != actual_book.sequence {
+        difference("sequence", &expected_book.sequence, &actual_book.sequence)
+    } else if expected_book.next_order_id != actual_book.next_order_id {
+        difference(
+            "next order identifier",
+            &expected_book.next_order_id,
+            &actual_book.next_order_id,
+        )
+    } else if expected.trading_state != actual.trading_state {
+        difference(
+            "trading state",
+            &expected.trading_state,
+            &actual.trading_state,
+        )
+    } else if expected.manual_time_nanos != actual.manual_time_nanos {
+        difference(
+            "manual time",
+            &expected.manual_time_nanos,
+            &actual.manual_time_nanos,
+        )
+    } else if expected.session != actual.session {
+        difference("session", &expected.session, &actual.session)
+    } else if expected.day_orders != actual.day_orders {
+        difference("day orders", &expected.day_orders, &actual.day_orders)
+    } else if expected.client_order_ids != actual.client_order_ids {
+        difference(
+            "client order identifiers",
+            &expected.client_order_ids,
+            &actual.client_order_ids,
+        )
+    } else {
+        None
+    }
+}
+
+/// Decodes a recording into its initial state, its commands, and its final state.
+fn read_recording(
+    bytes: &[u8],
+) -> Result<(Checkpoint, Vec<RecordedStep>, Checkpoint), RecordingError> {
+    let mut checkpoints = Vec::new();
+    let mut steps: Vec<RecordedStep> = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (record, frame_length) = decode_v1(&bytes[offset..]).map_err(|error| match error {
+            DecodeError::Truncated => RecordingError::Incomplete,
+            error => RecordingError::Corrupt {
+                offset: offset as u64,
+                error,
+            },
+        })?;
+        match (record, steps.last_mut()) {
+            // Only the first and the last frames are checkpoints
+            _ if checkpoints.len() == 2 => return Err(RecordingError::Incomplete),
+            (Some(Record::Checkpoint(checkpoint)), _) => checkpoints.push(checkpoint),
+            (_, _) if checkpoints.is_empty() => return Err(RecordingError::Incomplete),
+            (Some(Record::Command(command)), _) => steps.push(RecordedStep {
+                command,
+                events: Vec::new(),
+                trades: Vec::new(),
+            }),
+            (Some(Record::OrderEvent(event)), Some(step)) => step.events.push(event),
+            (Some(Record::Trade(trade)), Some(step)) => step.trades.push(trade),
+            _ => {}
+        }
+        offset += frame_length;
+    }
+
+    let mut checkpoints = checkpoints.into_iter();
+    match (checkpoints.next(), checkpoints.next()) {
+        (Some(initial), Some(last)) => Ok((initial, steps, last)),
+        _ => Err(RecordingError::Incomplete),
+    }
+}
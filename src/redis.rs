@@ -0,0 +1,401 @@
+//! A publisher of top-of-book depth into Redis, for consumers that do not link Rust.
+//!
+//! `RedisPublisher` reads the top levels of a `MarketDepthCache` at a configurable
+//! cadence and writes them to Redis as JSON, either on a pub/sub channel (`PUBLISH`)
+//! or appended to a stream (`XADD`), so dashboards and services in any language can
+//! follow the book with a stock Redis client. It speaks the Redis serialization
+//! protocol (RESP) itself over any `Read + Write` connection, usually the
+//! `TcpStream` opened by `RedisPublisher::connect`.
+//!
+//! Each publication is a JSON object of one of two types. A `snapshot` carries every
+//! level of the top `depth` of each side, best first, while a `delta` carries only the
+//! levels whose quantity changed since the previous publication, with a quantity of
+//! zero for levels that left the top; a delta with no changes is not published.
+//! Prices are strings, so they keep their exact decimal value:
+//!
+//! ```text
+//! {"type":"snapshot","publication":1,"sequence":2,"bids":[["100",10]],"asks":[["101",5]]}
+//! {"type":"delta","publication":2,"sequence":3,"bids":[],"asks":[["101",0]]}
+//! ```
+//!
+//! `publication` numbers publications from 1 without gaps, so a delta consumer that
+//! misses one can wait for the next snapshot, e.g. by reading the stream back to the
+//! last one; `sequence` is the cache's `last_applied_sequence`. Streams hold the JSON
+//! in a field named `depth`.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use order_book::redis::{DepthPayload, RedisPublisher, RedisTarget};
+//! use order_book::{MarketDepthCache, Order, OrderBook, Side};
+//! use std::time::Duration;
+//!
+//! let mut order_book = OrderBook::new();
+//! let cache = MarketDepthCache::new();
+//! let target = RedisTarget::Stream { key: "book:BTC-USD".to_string(), max_length: Some(10_000) };
+//! let mut publisher = RedisPublisher::connect("127.0.0.1:6379", target, 10).unwrap();
+//! publisher.set_payload(DepthPayload::Deltas);
+//! publisher.set_cadence(Duration::from_millis(100));
+//!
+//! cache.process_order_event(order_book.insert_order(Order::new(100.00, 10, Side::Bid)).event);
+//! publisher.publish(&cache).unwrap();
+//! ```
+
+use crate::market_depth_cache::MarketDepthCache;
+use crate::types::Side;
+use rust_decimal::Decimal;
+use std::cmp::Reverse;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Where a `RedisPublisher` writes its publications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedisTarget {
+    /// A pub/sub channel, reaching the clients subscribed when a publication is sent
+    Channel(String),
+    /// A stream, keeping publications for clients that read them later
+    Stream {
+        /// The key of the stream
+        key: String,
+        /// The approximate number of entries the stream is trimmed to, if any
+        max_length: Option<usize>,
+    },
+}
+
+/// What a `RedisPublisher` publishes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthPayload {
+    /// The top levels of both sides, in full
+    #[default]
+    Snapshots,
+    /// A snapshot first, then the levels that changed since the previous publication
+    Deltas,
+}
+
+/// Why a publication failed.
+#[derive(Debug)]
+pub enum RedisError {
+    /// Reading from or writing to the connection failed
+    Io(io::Error),
+    /// Redis answered with an error, e.g. `WRONGTYPE` for a key that is not a stream
+    Server(String),
+    /// Redis answered with something that is not a RESP reply
+    Protocol(&'static str),
+}
+
+impl fmt::Display for RedisError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedisError::Io(error) => write!(formatter, "redis connection failed: {error}"),
+            RedisError::Server(message) => write!(formatter, "redis error: {message}"),
+            RedisError::Protocol(reason) => write!(formatter, "invalid redis reply: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RedisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RedisError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RedisError {
+    fn from(error: io::Error) -> Self {
+        RedisError::Io(error)
+    }
+}
+
+/// Publishes the top levels of a depth cache into Redis.
+///
+/// The publisher does not watch the cache: call `publish` whenever the cache may have
+/// changed, and it publishes if the cadence allows.
+#[derive(Debug)]
+pub struct RedisPublisher<S: Read + Write = TcpStream> {
+    /// The connection, buffered for reading replies
+    connection: BufReader<S>,
+    /// Where publications are written
+    target: RedisTarget,
+    /// What is published
+    payload: DepthPayload,
+    /// The number of levels per side published
+    depth: usize,
+    /// The minimum time between two publications
+    cadence: Duration,
+    /// When the last publication was sent, if any
+    last_published: Option<Instant>,
+    /// The number of the last publication
+    publication: u64,
+    /// The bid levels of the last publication, best first
+    published_bids: Vec<(Decimal, u64)>,
+    /// The ask levels of the last publication, best first
+    published_asks: Vec<(Decimal, u64)>,
+    /// The bid levels being published, reused between publications
+    bids: Vec<(Decimal, u64)>,
+    /// The ask levels being published, reused between publications
+    asks: Vec<(Decimal, u64)>,
+    /// The command being sent, reused between publications
+    buffer: Vec<u8>,
+}
+
+impl RedisPublisher<TcpStream> {
+    /// Connects to a Redis server.
+    ///
+    /// ## Arguments
+    ///
+    /// * `address`: The server's address, e.g. `127.0.0.1:6379`
+    /// * `target`: Where publications are written
+    /// * `depth`: The number of levels per side published
+    pub fn connect(
+        address: impl ToSocketAddrs,
+        target: RedisTarget,
+        depth: usize,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream, target, depth))
+    }
+}
+
+impl<S: Read + Write> RedisPublisher<S> {
+    /// Creates a publisher writing to an established connection, publishing snapshots
+    /// on every call to `publish`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `connection`: The connection to the Redis server
+    /// * `target`: Where publications are written
+    /// * `depth`: The number of levels per side published
+    pub fn new(connection: S, target: RedisTarget, depth: usize) -> Self {
+        RedisPublisher {
+            connection: BufReader::new(connection),
+            target,
+            payload: DepthPayload::default(),
+            depth,
+            cadence: Duration::ZERO,
+            last_published: None,
+            publication: 0,
+            published_bids: Vec::with_capacity(depth),
+            published_asks: Vec::with_capacity(depth),
+            bids: Vec::with_capacity(depth),
+            asks: Vec::with_capacity(depth),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Sets whether snapshots or deltas are published.
+    pub fn set_payload(&mut self, payload: DepthPayload) {
+        self.payload = payload;
+    }
+
+    /// Sets the minimum time between two publications.
+    ///
+    /// Changes made to the cache in between are published together by the next call
+    /// to `publish` once the cadence has elapsed.
+    pub fn set_cadence(&mut self, cadence: Duration) {
+        self.cadence = cadence;
+    }
+
+    /// Returns the number of the last publication, or 0 if none.
+    pub fn publication(&self) -> u64 {
+        self.publication
+    }
+
+    /// Publishes the cache's top levels, unless the cadence has not elapsed since the
+    /// last publication.
+    ///
+    /// ## Arguments
+    ///
+    /// * `market_depth_cache`: The cache to publish
+    ///
+    /// ## Returns
+    ///
+    /// Whether a publication was sent, or the error of the connection or of Redis
+    pub fn publish(&mut self, market_depth_cache: &MarketDepthCache) -> Result<bool, RedisError> {
+        let due = self
+            .last_published
+            .is_none_or(|last_published| last_published.elapsed() >= self.cadence);
+        if !due {
+            return Ok(false);
+        }
+        self.publish_now(market_depth_cache)
+    }
+
+    /// Publishes the cache's top levels regardless of the cadence.
+    ///
+    /// ## Arguments
+    ///
+    /// * `market_depth_cache`: The cache to publish
+    ///
+    /// ## Returns
+    ///
+    /// Whether a publication was sent, which only a delta without changes is not, or
+    /// the error of the connection or of Redis
+    pub fn publish_now(
+        &mut self,
+        market_depth_cache: &MarketDepthCache,
+    ) -> Result<bool, RedisError> {
+        market_depth_cache.copy_top_levels_into(&mut self.bids, &mut self.asks, self.depth);
+        let sequence = market_depth_cache.last_applied_sequence();
+
+        let publication = self.publication + 1;
+        let json = if self.payload == DepthPayload::Snapshots || self.publication == 0 {
+            depth_json("snapshot", publication, sequence, &self.bids, &self.asks)
+        } else {
+            let bids = changed_levels(&self.published_bids, &self.bids, Side::Bid);
+            let asks = changed_levels(&self.published_asks, &self.asks, Side::Ask);
+            if bids.is_empty() && asks.is_empty() {
+                return Ok(false);
+            }
+            depth_json("delta", publication, sequence, &bids, &asks)
+        };
+        let max_length;
+        let arguments: Vec<&[u8]> = match &self.target {
+            RedisTarget::Channel(channel) => {
+                vec![b"PUBLISH", channel.as_bytes(), json.as_bytes()]
+            }
+            RedisTarget::Stream {
+                key,
+                max_length: None,
+            } => {
+                vec![b"XADD", key.as_bytes(), b"*", b"depth", json.as_bytes()]
+            }
+            RedisTarget::Stream {
+                key,
+                max_length: Some(length),
+            } => {
+                max_length = length.to_string();
+                vec![
+                    b"XADD",
+                    key.as_bytes(),
+                    b"MAXLEN",
+                    b"~",
+                    max_length.as_bytes(),
+                    b"*",
+                    b"depth",
+                    json.as_bytes(),
+                ]
+            }
+        };
+        encode_command(&arguments, &mut self.buffer);
+        self.connection.get_mut().write_all(&self.buffer)?;
+        self.connection.get_mut().flush()?;
+        read_reply(&mut self.connection)?;
+
+        self.publication = publication;
+        self.last_published = Some(Instant::now());
+        std::mem::swap(&mut self.published_bids, &mut self.bids);
+        std::mem::swap(&mut self.published_asks, &mut self.asks);
+        Ok(true)
+    }
+}
+
+/// Returns the levels of `current` whose quantity differs from `published`, with
+/// those only in `published` at a quantity of zero, best first.
+///
+/// ## Arguments
+///
+/// * `published`: The levels of the last publication
+/// * `current`: The levels being published
+/// * `side`: The side of the levels
+fn changed_levels(
+    published: &[(Decimal, u64)],
+    current: &[(Decimal, u64)],
+    side: Side,
+) -> Vec<(Decimal, u64)> {
+    let mut changes: Vec<(Decimal, u64)> = current
+        .iter()
+        .filter(|level| !published.contains(level))
+        .copied()
+        .collect();
+    changes.extend(
+        published
+            .iter()
+            .filter(|(price, _)| {
+                current
+                    .iter()
+                    .all(|(current_price, _)| current_price != price)
+            })
+            .map(|(price, _)| (*price, 0)),
+    );
+    match side {
+        Side::Bid => changes.sort_by_key(|(price, _)| Reverse(*price)),
+        Side::Ask => changes.sort_by_key(|(price, _)| *price),
+    }
+    changes
+}
+
+/// Formats a publication as JSON.
+fn depth_json(
+    kind: &str,
+    publication: u64,
+    sequence: u64,
+    bids: &[(Decimal, u64)],
+    asks: &[(Decimal, u64)],
+) -> String {
+    let levels = |levels: &[(Decimal, u64)]| {
+        let levels: Vec<String> = levels
+            .iter()
+            .map(|(price, quantity)| format!("[\"{price}\",{quantity}]"))
+            .collect();
+        levels.join(",")
+    };
+    format!(
+        "{{\"type\":\"{kind}\",\"publication\":{publication},\"sequence\":{sequence},\"bids\":[{}],\"asks\":[{}]}}",
+        levels(bids),
+        levels(asks)
+    )
+}
+
+/// Encodes a command as a RESP array of bulk strings into `buffer`, replacing its
+/// contents.
+fn encode_command(arguments: &[&[u8]], buffer: &mut Vec<u8>) {
+    buffer.clear();
+    buffer.extend_from_slice(format!("*{}\r\n", arguments.len()).as_bytes());
+    for argument in arguments {
+        buffer.extend_from_slice(format!("${}\r\n", argument.len()).as_bytes());
+        buffer.extend_from_slice(argument);
+        buffer.extend_from_slice(b"\r\n");
+    }
+}
+
+/// Reads one RESP reply, failing on error replies.
+fn read_reply<R: BufRead>(reader: &mut R) -> Result<(), RedisError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(RedisError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    let line = line
+        .strip_suffix("\r\n")
+        .ok_or(RedisError::Protocol("reply line not terminated"))?;
+    let (kind, content) = line.split_at(line.len().min(1));
+    match kind {
+        "+" | ":" => Ok(()),
+        "-" => Err(RedisError::Server(content.to_string())),
+        "$" => {
+            let length: i64 = content
+                .parse()
+                .map_err(|_| RedisError::Protocol("invalid bulk string length"))?;
+            if length >= 0 {
+                // The string is followed by its own line terminator
+                let mut bulk = vec![0; length as usize + 2];
+                reader.read_exact(&mut bulk)?;
+            }
+            Ok(())
+        }
+        "*" => {
+            let count: i64 = content
+                .parse()
+                .map_err(|_| RedisError::Protocol("invalid array length"))?;
+            for _ in 0..count.max(0) {
+                read_reply(reader)?;
+            }
+            Ok(())
+        }
+        _ => Err(RedisError::Protocol("unknown reply type")),
+    }
+}
@@ -0,0 +1,189 @@
+//! HTTP endpoints exposing a running book's depth, top of book, and trades.
+//!
+//! `router` builds an axum `Router` answering three `GET` requests with JSON, so a
+//! monitoring UI or a script can be pointed at a book without any other integration:
+//!
+//! - `/depth?levels=N`: the top `N` aggregated levels of each side of the
+//!   `MarketDepthCache`, best first (10 without `levels`), with the cache's last
+//!   applied sequence, as a `DepthResponse`
+//! - `/bbo`: the book's best bid and ask with their quantities, and the spread, as a
+//!   `BboResponse`
+//! - `/trades?since=S`: the trades of the book's trade log whose identifier is greater
+//!   than `S` (all of them without `since`), as a `TradesResponse`
+//!
+//! A client polling for new trades passes the `next_since` of the previous response,
+//! the identifier of the last trade it received. Trade identifiers keep increasing
+//! across sessions, so no trade is skipped when `OrderBook::end_session` hands the
+//! trade log over and a new session starts with an empty one; trades printed before
+//! the end of a session and not yet polled are only in the closed session's summary.
+//! Prices are strings, so they keep their exact decimal value.
+//!
+//! Each request holds the book's read lock only while it copies what it returns.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use order_book::rest::{router, RestState};
+//! use order_book::SharedOrderBook;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let shared = SharedOrderBook::new();
+//! let state = RestState {
+//!     order_book: shared.order_book().clone(),
+//!     market_depth_cache: shared.market_depth_cache().clone(),
+//! };
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+//! axum::serve(listener, router(state)).await
+//! # }
+//! ```
+
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::types::{Side, Trade, TradeId};
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The number of levels per side returned by `/depth` without `levels`.
+const DEFAULT_DEPTH_LEVELS: usize = 10;
+
+/// The book and cache served by the endpoints.
+#[derive(Debug, Clone)]
+pub struct RestState {
+    /// The book whose top of book and trades are served
+    pub order_book: Arc<RwLock<OrderBook>>,
+    /// The cache whose aggregated depth is served
+    pub market_depth_cache: Arc<MarketDepthCache>,
+}
+
+/// The body of a `/depth` response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthResponse {
+    /// The cache's last applied sequence number
+    pub sequence: u64,
+    /// The best aggregated bid levels and their quantities, highest first
+    pub bids: Vec<(Decimal, u64)>,
+    /// The best aggregated ask levels and their quantities, lowest first
+    pub asks: Vec<(Decimal, u64)>,
+}
+
+/// The best price of one side and the quantity resting at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BestLevel {
+    /// The best price
+    pub price: Decimal,
+    /// The total quantity resting at the best price
+    pub quantity: u64,
+}
+
+/// The body of a `/bbo` response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BboResponse {
+    /// The best bid, if any bid rests
+    pub best_bid: Option<BestLevel>,
+    /// The best ask, if any ask rests
+    pub best_ask: Option<BestLevel>,
+    /// The best ask minus the best bid, if both sides rest
+    pub spread: Option<Decimal>,
+    /// The book's last event sequence number
+    pub sequence: u64,
+}
+
+/// The body of a `/trades` response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradesResponse {
+    /// The trades after the requested one, oldest first
+    pub trades: Vec<Trade>,
+    /// The `since` to pass to receive only the trades printed after this response, or
+    /// `None` while no trade has been received, in which case `since` is omitted
+    pub next_since: Option<u64>,
+}
+
+/// The query of a `/depth` request.
+#[derive(Debug, Deserialize)]
+struct DepthQuery {
+    /// The number of levels per side
+    levels: Option<usize>,
+}
+
+/// The query of a `/trades` request.
+#[derive(Debug, Deserialize)]
+struct TradesQuery {
+    /// The identifier of the last trade already received
+    since: Option<u64>,
+}
+
+/// Builds the router serving `/depth`, `/bbo`, and `/trades` from a book and its cache.
+///
+/// ## Arguments
+///
+/// * `state`: The book and cache to serve
+///
+/// ## Returns
+///
+/// The router, which can be served with `axum::serve` or nested into a larger one
+pub fn router(state: RestState) -> Router {
+    Router::new()
+        .route("/depth", get(depth))
+        .route("/bbo", get(bbo))
+        .route("/trades", get(trades))
+        .with_state(state)
+}
+
+/// Answers `/depth` from the cache.
+async fn depth(
+    State(state): State<RestState>,
+    Query(query): Query<DepthQuery>,
+) -> Json<DepthResponse> {
+    let levels = query.levels.unwrap_or(DEFAULT_DEPTH_LEVELS);
+    let (mut bids, mut asks) = (Vec::new(), Vec::new());
+    state
+        .market_depth_cache
+        .copy_top_levels_into(&mut bids, &mut asks, levels);
+    Json(DepthResponse {
+        sequence: state.market_depth_cache.last_applied_sequence(),
+        bids,
+        asks,
+    })
+}
+
+/// Answers `/bbo` from the book.
+async fn bbo(State(state): State<RestState>) -> Json<BboResponse> {
+    let order_book = state.order_book.read();
+    let best_level = |side| {
+        order_book
+            .best_level(side)
+            .map(|(price, quantity)| BestLevel { price, quantity })
+    };
+    let (best_bid, best_ask) = (best_level(Side::Bid), best_level(Side::Ask));
+    Json(BboResponse {
+        best_bid,
+        best_ask,
+        spread: best_bid
+            .zip(best_ask)
+            .map(|(best_bid, best_ask)| best_ask.price - best_bid.price),
+        sequence: order_book.last_event_sequence(),
+    })
+}
+
+/// Answers `/trades` from the book's trade log.
+async fn trades(
+    State(state): State<RestState>,
+    Query(query): Query<TradesQuery>,
+) -> Json<TradesResponse> {
+    let order_book = state.order_book.read();
+    let log = order_book.trades();
+    // Trade identifiers increase along the log, so the new trades are a suffix of it
+    let first = query.since.map_or(0, |since| {
+        log.partition_point(|trade| trade.trade_id <= TradeId(since))
+    });
+    let trades = log[first..].to_vec();
+    Json(TradesResponse {
+        next_since: trades.last().map(|trade| trade.trade_id.0).or(query.since),
+        trades,
+    })
+}
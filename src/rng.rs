@@ -0,0 +1,26 @@
+/// A small seeded pseudo-random number generator (SplitMix64).
+///
+/// Policies that break ties or randomize quantities draw from the book's generator
+/// rather than from a global source, so a book seeded with `OrderBook::deterministic`
+/// makes the same choices on every run of the same commands.
+#[derive(Debug, Clone)]
+pub(crate) struct SeededRng {
+    /// The generator state, advanced on every draw
+    state: u64,
+}
+
+impl SeededRng {
+    /// Creates a generator whose sequence is fully determined by `seed`.
+    pub(crate) fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    /// Returns the next pseudo-random number.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut mixed = self.state;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        mixed ^ (mixed >> 31)
+    }
+}
@@ -0,0 +1,426 @@
+//! A Simple Binary Encoding (SBE) codec for order commands, events, and trades.
+//!
+//! Messages follow the SBE layout used by low-latency gateways: an 8-byte message
+//! header followed by a fixed-length block, every field at a fixed offset and every
+//! integer little-endian, so a message is encoded into or decoded from a caller's
+//! buffer without allocation or parsing.
+//!
+//! The message header holds four `u16`: the block length, the template id, the schema
+//! id (`SCHEMA_ID`), and the schema version (`SCHEMA_VERSION`). Decoders use the block
+//! length from the header, so blocks extended by later schema versions can still be
+//! read. Prices are the SBE `decimal` composite, an `i64` mantissa followed by an
+//! `i8` exponent; sides are a `u8` enum (`0` bid, `1` ask) and trading states a `u8`
+//! enum (`0` continuous, `1` halted).
+//!
+//! Version 1 of the schema appended the identifiers to the `Trade` block, and version 2
+//! the order count delta to the `OrderEvent` block; they decode as `0` from blocks of
+//! earlier versions.
+//!
+//! | Template | Message              | Block fields                                          |
+//! |----------|----------------------|-------------------------------------------------------|
+//! | 1        | `OrderEvent`         | price, quantity delta `i64`, side, timestamp `u64`, sequence `u64`, order count delta `i64` |
+//! | 2        | `Trade`              | price, quantity `u64`, aggressor side, trade id `u64`, maker order id `u64`, taker order id `u64` |
+//! | 10       | `Command::Insert`    | price, quantity `u64`, side                           |
+//! | 11       | `Command::Match`     | price, quantity `u64`, side                           |
+//! | 12       | `Command::Cancel`    | order id `u64`                                        |
+//! | 13       | `Command::Amend`     | order id `u64`, price, quantity `u64`                 |
+//! | 14       | `Command::InsertWithClientId` | price, quantity `u64`, side, client order id `u64` |
+//! | 15       | `Command::Clear`     | none                                                  |
+//! | 16       | `Command::SetState`  | trading state                                         |
+//! | 17       | `Command::SetTime`   | timestamp `u64`                                       |
+//! | 18       | `Command::InsertDay` | price, quantity `u64`, side                           |
+//! | 19       | `Command::MatchDay`  | price, quantity `u64`, side                           |
+//! | 20       | `Command::EndSession` | none                                                 |
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::sbe::{decode, encode, SbeMessage};
+//! use order_book::{Command, Order, Side};
+//!
+//! let command = SbeMessage::Command(Command::Insert(Order::new(100.25, 10, Side::Bid)));
+//! let mut buffer = [0u8; 64];
+//! let length = encode(&command, &mut buffer).unwrap();
+//! assert_eq!(length, 8 + 18);
+//!
+//! let (decoded, decoded_length) = decode(&buffer).unwrap();
+//! assert_eq!((decoded, decoded_length), (command, length));
+//! ```
+
+use crate::types::{
+    ClientOrderId, Command, Order, OrderEvent, OrderId, Side, Trade, TradeId, TradingState,
+};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// The id of the schema implemented by this codec.
+pub const SCHEMA_ID: u16 = 1;
+
+/// The version of the schema implemented by this codec.
+pub const SCHEMA_VERSION: u16 = 2;
+
+/// The length of the message header.
+pub const HEADER_LENGTH: usize = 8;
+
+/// The length of the `decimal` price composite.
+const PRICE_LENGTH: usize = 9;
+
+/// A message of the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SbeMessage {
+    /// A change of the aggregate quantity at a price
+    OrderEvent(OrderEvent),
+    /// An execution
+    Trade(Trade),
+    /// An operation on a book
+    Command(Command),
+}
+
+/// Why a message could not be encoded or decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SbeError {
+    /// The buffer is shorter than the message
+    BufferTooShort {
+        /// The length the message needs
+        required: usize,
+    },
+    /// The message belongs to another schema
+    SchemaMismatch {
+        /// The schema id in the message header
+        schema_id: u16,
+    },
+    /// The template id is not part of the schema
+    UnknownTemplate(u16),
+    /// A price does not fit the `decimal` composite
+    PriceOutOfRange(Decimal),
+    /// A field holds a value outside its enum or range
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for SbeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SbeError::BufferTooShort { required } => {
+                write!(
+                    formatter,
+                    "buffer too short, message needs {required} bytes"
+                )
+            }
+            SbeError::SchemaMismatch { schema_id } => {
+                write!(
+                    formatter,
+                    "message of schema {schema_id}, expected {SCHEMA_ID}"
+                )
+            }
+            SbeError::UnknownTemplate(template_id) => {
+                write!(formatter, "unknown template {template_id}")
+            }
+            SbeError::PriceOutOfRange(price) => {
+                write!(formatter, "price {price} does not fit an i64 mantissa")
+            }
+            SbeError::InvalidField(field) => write!(formatter, "invalid value for {field}"),
+        }
+    }
+}
+
+impl std::error::Error for SbeError {}
+
+/// Returns the template id and block length of a message.
+fn template(message: &SbeMessage) -> (u16, usize) {
+    match message {
+        SbeMessage::OrderEvent(_) => (1, PRICE_LENGTH + 33),
+        SbeMessage::Trade(_) => (2, PRICE_LENGTH + 33),
+        SbeMessage::Command(command) => match command {
+            Command::Insert(_) => (10, PRICE_LENGTH + 9),
+            Command::Match(_) => (11, PRICE_LENGTH + 9),
+            Command::Cancel(_) => (12, 8),
+            Command::Amend { .. } => (13, PRICE_LENGTH + 16),
+            Command::InsertWithClientId(..) => (14, PRICE_LENGTH + 17),
+            Command::Clear => (15, 0),
+            Command::SetState(_) => (16, 1),
+            Command::SetTime(_) => (17, 8),
+            Command::InsertDay(_) => (18, PRICE_LENGTH + 9),
+            Command::MatchDay(_) => (19, PRICE_LENGTH + 9),
+            Command::EndSession => (20, 0),
+        },
+    }
+}
+
+/// Returns the encoded length of a message, header included.
+pub fn encoded_length(message: &SbeMessage) -> usize {
+    HEADER_LENGTH + template(message).1
+}
+
+/// Encodes a message at the start of `buffer`.
+///
+/// ## Arguments
+///
+/// * `message`: The message to encode
+/// * `buffer`: The buffer the message is written to
+///
+/// ## Returns
+///
+/// The encoded length, or an error if the buffer is too short or a price does not fit
+/// the `decimal` composite
+pub fn encode(message: &SbeMessage, buffer: &mut [u8]) -> Result<usize, SbeError> {
+    let (template_id, block_length) = template(message);
+    let required = HEADER_LENGTH + block_length;
+    let buffer = buffer
+        .get_mut(..required)
+        .ok_or(SbeError::BufferTooShort { required })?;
+
+    let mut writer = BlockWriter { buffer, offset: 0 };
+    writer.u16(block_length as u16);
+    writer.u16(template_id);
+    writer.u16(SCHEMA_ID);
+    writer.u16(SCHEMA_VERSION);
+
+    match message {
+        SbeMessage::OrderEvent(event) => {
+            writer.price(event.price)?;
+            writer.bytes(&event.quantity_delta.to_le_bytes());
+            writer.side(event.side);
+            writer.u64(event.timestamp_nanos);
+            writer.u64(event.sequence);
+            writer.bytes(&event.order_count_delta.to_le_bytes());
+        }
+        SbeMessage::Trade(trade) => {
+            writer.price(trade.price)?;
+            writer.u64(trade.quantity);
+            writer.side(trade.aggressor_side);
+            writer.u64(trade.trade_id.0);
+            writer.u64(trade.maker_order_id.0);
+            writer.u64(trade.taker_order_id.0);
+        }
+        SbeMessage::Command(command) => match command {
+            Command::Insert(order)
+            | Command::Match(order)
+            | Command::InsertDay(order)
+            | Command::MatchDay(order) => writer.order(order)?,
+            Command::Cancel(order_id) => writer.u64(order_id.0),
+            Command::Amend {
+                order_id,
+                price,
+                quantity,
+            } => {
+                writer.u64(order_id.0);
+                writer.price(*price)?;
+                writer.u64(*quantity);
+            }
+            Command::InsertWithClientId(order, client_order_id) => {
+                writer.order(order)?;
+                writer.u64(client_order_id.0);
+            }
+            Command::Clear | Command::EndSession => {}
+            Command::SetState(trading_state) => writer.bytes(&[match trading_state {
+                TradingState::Continuous => 0,
+                TradingState::Halted => 1,
+            }]),
+            Command::SetTime(timestamp_nanos) => writer.u64(*timestamp_nanos),
+        },
+    }
+    Ok(required)
+}
+
+/// Decodes the message at the start of `buffer`.
+///
+/// ## Arguments
+///
+/// * `buffer`: The buffer, starting with a message header
+///
+/// ## Returns
+///
+/// The message and its encoded length, which is where the next message starts, or an
+/// error if the buffer does not hold a valid message of the schema
+pub fn decode(buffer: &[u8]) -> Result<(SbeMessage, usize), SbeError> {
+    let header = buffer
+        .get(..HEADER_LENGTH)
+        .ok_or(SbeError::BufferTooShort {
+            required: HEADER_LENGTH,
+        })?;
+    let field = |index: usize| u16::from_le_bytes([header[2 * index], header[2 * index + 1]]);
+    let (block_length, template_id, schema_id) = (field(0) as usize, field(1), field(2));
+    if schema_id != SCHEMA_ID {
+        return Err(SbeError::SchemaMismatch { schema_id });
+    }
+    let required = HEADER_LENGTH + block_length;
+    let block = buffer
+        .get(HEADER_LENGTH..required)
+        .ok_or(SbeError::BufferTooShort { required })?;
+
+    let mut reader = BlockReader { block, offset: 0 };
+    let message = match template_id {
+        1 => SbeMessage::OrderEvent(OrderEvent {
+            price: reader.price()?,
+            quantity_delta: reader.u64()? as i64,
+            side: reader.side()?,
+            timestamp_nanos: reader.u64()?,
+            sequence: reader.u64()?,
+            order_count_delta: reader.appended_u64()? as i64,
+        }),
+        2 => SbeMessage::Trade(Trade {
+            price: reader.price()?,
+            quantity: reader.u64()?,
+            aggressor_side: reader.side()?,
+            trade_id: TradeId(reader.appended_u64()?),
+            maker_order_id: OrderId(reader.appended_u64()?),
+            taker_order_id: OrderId(reader.appended_u64()?),
+        }),
+        10 => SbeMessage::Command(Command::Insert(reader.order()?)),
+        11 => SbeMessage::Command(Command::Match(reader.order()?)),
+        12 => SbeMessage::Command(Command::Cancel(OrderId(reader.u64()?))),
+        13 => SbeMessage::Command(Command::Amend {
+            order_id: OrderId(reader.u64()?),
+            price: reader.price()?,
+            quantity: reader.u64()?,
+        }),
+        14 => SbeMessage::Command(Command::InsertWithClientId(
+            reader.order()?,
+            ClientOrderId(reader.u64()?),
+        )),
+        15 => SbeMessage::Command(Command::Clear),
+        16 => SbeMessage::Command(Command::SetState(match reader.u8()? {
+            0 => TradingState::Continuous,
+            1 => TradingState::Halted,
+            _ => return Err(SbeError::InvalidField("trading state")),
+        })),
+        17 => SbeMessage::Command(Command::SetTime(reader.u64()?)),
+        18 => SbeMessage::Command(Command::InsertDay(reader.order()?)),
+        19 => SbeMessage::Command(Command::MatchDay(reader.order()?)),
+        20 => SbeMessage::Command(Command::EndSession),
+        _ => return Err(SbeError::UnknownTemplate(template_id)),
+    };
+    Ok((message, required))
+}
+
+/// Writes the fields of a block in order.
+struct BlockWriter<'a> {
+    /// The message being written, exactly as long as it
+    buffer: &'a mut [u8],
+    /// Where the next field goes
+    offset: usize,
+}
+
+impl BlockWriter<'_> {
+    /// Writes raw bytes.
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buffer[self.offset..self.offset + bytes.len()].copy_from_slice(bytes);
+        self.offset += bytes.len();
+    }
+
+    /// Writes a `u16`.
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Writes a `u64`.
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Writes a side enum.
+    fn side(&mut self, side: Side) {
+        self.bytes(&[match side {
+            Side::Bid => 0,
+            Side::Ask => 1,
+        }]);
+    }
+
+    /// Writes a price as a `decimal` composite, dropping trailing zeros if the mantissa
+    /// would not fit otherwise.
+    fn price(&mut self, price: Decimal) -> Result<(), SbeError> {
+        let (mantissa, scale) = match i64::try_from(price.mantissa()) {
+            Ok(mantissa) => (mantissa, price.scale()),
+            Err(_) => {
+                let normalized = price.normalize();
+                let mantissa = i64::try_from(normalized.mantissa())
+                    .map_err(|_| SbeError::PriceOutOfRange(price))?;
+                (mantissa, normalized.scale())
+            }
+        };
+        self.bytes(&mantissa.to_le_bytes());
+        self.bytes(&[(-(scale as i8)) as u8]);
+        Ok(())
+    }
+
+    /// Writes the price, quantity, and side of an order.
+    fn order(&mut self, order: &Order) -> Result<(), SbeError> {
+        self.price(order.price)?;
+        self.u64(order.quantity);
+        self.side(order.side);
+        Ok(())
+    }
+}
+
+/// Reads the fields of a block in order.
+struct BlockReader<'a> {
+    /// The block, which may be longer than the fields read from it
+    block: &'a [u8],
+    /// Where the next field starts
+    offset: usize,
+}
+
+impl BlockReader<'_> {
+    /// Reads `N` raw bytes.
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], SbeError> {
+        let bytes = self
+            .block
+            .get(self.offset..self.offset + N)
+            .ok_or(SbeError::InvalidField("block length"))?;
+        self.offset += N;
+        Ok(bytes.try_into().expect("slice of N bytes"))
+    }
+
+    /// Reads a `u8`.
+    fn u8(&mut self) -> Result<u8, SbeError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    /// Reads a `u64`.
+    fn u64(&mut self) -> Result<u64, SbeError> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    /// Reads a `u64` field added by a later schema version, or `0` from a block of an
+    /// earlier version that ends before it.
+    fn appended_u64(&mut self) -> Result<u64, SbeError> {
+        if self.offset == self.block.len() {
+            Ok(0)
+        } else {
+            self.u64()
+        }
+    }
+
+    /// Reads a side enum.
+    fn side(&mut self) -> Result<Side, SbeError> {
+        match self.u8()? {
+            0 => Ok(Side::Bid),
+            1 => Ok(Side::Ask),
+            _ => Err(SbeError::InvalidField("side")),
+        }
+    }
+
+    /// Reads a `decimal` composite.
+    fn price(&mut self) -> Result<Decimal, SbeError> {
+        let mantissa = i64::from_le_bytes(self.bytes()?);
+        let exponent = self.u8()? as i8;
+        if exponent <= 0 {
+            Decimal::try_from_i128_with_scale(mantissa as i128, (-(exponent as i32)) as u32)
+                .map_err(|_| SbeError::InvalidField("price exponent"))
+        } else {
+            10i64
+                .checked_pow(exponent as u32)
+                .and_then(|factor| Decimal::from(mantissa).checked_mul(Decimal::from(factor)))
+                .ok_or(SbeError::InvalidField("price exponent"))
+        }
+    }
+
+    /// Reads the price, quantity, and side of an order.
+    fn order(&mut self) -> Result<Order, SbeError> {
+        Ok(Order {
+            price: self.price()?,
+            quantity: self.u64()?,
+            side: self.side()?,
+        })
+    }
+}
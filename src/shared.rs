@@ -0,0 +1,221 @@
+use crate::depth_aggregator::DepthAggregator;
+use crate::error::{OrderBookError, Result};
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::types::{Bbo, InsertOutcome, Order, OrderEvent, OrderId};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The `(price_level, quantity)` levels of one side, best first.
+type Levels = Vec<(Decimal, u64)>;
+
+/// An `OrderBook` and its `MarketDepthCache`, shared between threads.
+///
+/// This bundles the usual `Arc<RwLock<OrderBook>>` and `Arc<MarketDepthCache>` pair
+/// and does the plumbing between them: `insert` and `cancel` take the book's write
+/// lock, apply the change, and forward the resulting event to the cache, while `bbo`
+/// reads the book and `depth` reads the cache only. Events are applied to the cache
+/// before the book's write lock is released, so the cache receives them in the order
+/// the book published them, whichever threads are writing.
+///
+/// Clones share the same book and cache. Anything the wrapper does not expose is
+/// reachable through `order_book` and `market_depth_cache`; events of changes made
+/// directly on the book are not forwarded to the cache.
+///
+/// The cache is a `MarketDepthCache` unless another `DepthAggregator` is given to
+/// `from_parts`, in which case every method forwards to that one instead.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Order, OrderBook, SharedOrderBook, Side};
+/// use rust_decimal::Decimal;
+///
+/// let shared = SharedOrderBook::new();
+/// let writer = shared.clone();
+/// std::thread::spawn(move || {
+///     writer.insert(Order::new(100.25, 10, Side::Bid));
+///     writer.insert(Order::new(101.00, 5, Side::Ask));
+/// })
+/// .join()
+/// .unwrap();
+///
+/// assert_eq!(shared.bbo().best_bid, Some(Decimal::new(10025, 2)));
+/// let (bids, asks) = shared.depth(10);
+/// assert_eq!(bids, vec![(Decimal::new(100, 0), 10)]);
+/// assert_eq!(asks, vec![(Decimal::new(101, 0), 5)]);
+/// ```
+#[derive(Debug)]
+pub struct SharedOrderBook<D = MarketDepthCache> {
+    /// The book
+    order_book: Arc<RwLock<OrderBook>>,
+    /// The cache following the book's events
+    market_depth_cache: Arc<D>,
+}
+
+impl SharedOrderBook {
+    /// Creates an empty book with an empty cache.
+    pub fn new() -> Self {
+        SharedOrderBook::default()
+    }
+}
+
+impl<D: DepthAggregator> SharedOrderBook<D> {
+    /// Shares a book with a cache, which must already reflect the book, e.g. a cache
+    /// rebuilt from it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book
+    /// * `market_depth_cache`: The cache following the book's events
+    pub fn from_parts(order_book: Arc<RwLock<OrderBook>>, market_depth_cache: Arc<D>) -> Self {
+        SharedOrderBook {
+            order_book,
+            market_depth_cache,
+        }
+    }
+
+    /// Inserts an order into the book and its event into the cache.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to rest
+    ///
+    /// ## Returns
+    ///
+    /// The outcome of `OrderBook::insert_order`
+    pub fn insert(&self, order: Order) -> InsertOutcome {
+        let mut order_book = self.order_book.write();
+        let outcome = order_book.insert_order(order);
+        self.market_depth_cache
+            .process_order_event(outcome.event.clone());
+        outcome
+    }
+
+    /// Inserts an order like `insert`, then blocks until the cache reflects it.
+    ///
+    /// The event is forwarded as with `insert`, and the book's write lock released
+    /// before waiting, so other writers are not held up; only this caller waits for
+    /// the cache to apply the event, through `DepthAggregator::wait_for_sequence`. This
+    /// gives a read-your-writes guarantee to the occasional caller that needs one, such
+    /// as a test or a workflow reading depth right after its own insert, while every
+    /// other call keeps the decoupled path. A `MarketDepthCache` applies events as it
+    /// receives them, so it never makes the caller wait.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order`: The order to rest
+    /// * `timeout`: The longest time to wait for the cache
+    ///
+    /// ## Returns
+    ///
+    /// The outcome of `OrderBook::insert_order`, or `OrderBookError::NotVisible` if the
+    /// cache did not reflect the insertion within `timeout`, in which case the order
+    /// still rests in the book
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, SharedOrderBook, Side};
+    /// use rust_decimal::Decimal;
+    /// use std::time::Duration;
+    ///
+    /// let shared = SharedOrderBook::new();
+    /// shared
+    ///     .insert_and_wait_visible(Order::new(100.25, 10, Side::Bid), Duration::from_secs(1))
+    ///     .unwrap();
+    /// assert_eq!(shared.depth(1).0, vec![(Decimal::new(100, 0), 10)]);
+    /// ```
+    pub fn insert_and_wait_visible(
+        &self,
+        order: Order,
+        timeout: Duration,
+    ) -> Result<InsertOutcome> {
+        let outcome = self.insert(order);
+        let sequence = outcome.event.sequence;
+        if self.market_depth_cache.wait_for_sequence(sequence, timeout) {
+            return Ok(outcome);
+        }
+        Err(OrderBookError::NotVisible {
+            order_id: outcome.handle.order_id(),
+            sequence,
+        })
+    }
+
+    /// Cancels a resting order and applies the cancellation to the cache.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_id`: The identifier of the order to cancel
+    ///
+    /// ## Returns
+    ///
+    /// The cancellation event, or `OrderBookError::OrderNotFound` if no such order is
+    /// resting
+    pub fn cancel(&self, order_id: OrderId) -> Result<OrderEvent> {
+        let mut order_book = self.order_book.write();
+        let event = order_book.cancel_order(order_id)?;
+        self.market_depth_cache.process_order_event(event.clone());
+        Ok(event)
+    }
+
+    /// Returns the book's best bid and ask prices.
+    pub fn bbo(&self) -> Bbo {
+        self.order_book.read().bbo()
+    }
+
+    /// Returns the `n` aggregated levels closest to the touch on each side, from the cache.
+    ///
+    /// ## Returns
+    ///
+    /// A tuple of `(bids, asks)` with up to `n` `(price_level, quantity)` levels each,
+    /// best first
+    pub fn depth(&self, n: usize) -> (Levels, Levels) {
+        let (mut bids, mut asks) = (Vec::with_capacity(n), Vec::with_capacity(n));
+        self.market_depth_cache
+            .copy_top_levels_into(&mut bids, &mut asks, n);
+        (bids, asks)
+    }
+
+    /// Returns the shared book.
+    pub fn order_book(&self) -> &Arc<RwLock<OrderBook>> {
+        &self.order_book
+    }
+
+    /// Returns the shared cache.
+    pub fn market_depth_cache(&self) -> &Arc<D> {
+        &self.market_depth_cache
+    }
+}
+
+impl<D> Clone for SharedOrderBook<D> {
+    fn clone(&self) -> Self {
+        SharedOrderBook {
+            order_book: Arc::clone(&self.order_book),
+            market_depth_cache: Arc::clone(&self.market_depth_cache),
+        }
+    }
+}
+
+impl<D: Default> Default for SharedOrderBook<D> {
+    fn default() -> Self {
+        SharedOrderBook {
+            order_book: Arc::default(),
+            market_depth_cache: Arc::default(),
+        }
+    }
+}
+
+impl From<OrderBook> for SharedOrderBook {
+    /// Shares a book with a cache rebuilt from it.
+    fn from(order_book: OrderBook) -> Self {
+        let market_depth_cache = MarketDepthCache::new();
+        market_depth_cache.rebuild_from(&order_book);
+        SharedOrderBook::from_parts(
+            Arc::new(RwLock::new(order_book)),
+            Arc::new(market_depth_cache),
+        )
+    }
+}
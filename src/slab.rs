@@ -0,0 +1,178 @@
+use crate::memory::vec_bytes;
+
+/// A generational slab used to store resting orders at stable slots.
+///
+/// Removed slots are recycled through a free list, and every removal bumps the
+/// slot's generation so that references to the previous occupant (such as an
+/// `OrderHandle`) can be detected as stale instead of silently aliasing a new order.
+#[derive(Debug, Clone)]
+pub(crate) struct Slab<T> {
+    /// Backing storage, indexed by slot
+    entries: Vec<SlabEntry<T>>,
+    /// Slots that are currently vacant and can be reused
+    free_slots: Vec<usize>,
+    /// Number of occupied slots
+    len: usize,
+    /// The generation of slots appended to `entries`, past the generations of every
+    /// slot released by `compact`
+    base_generation: u64,
+}
+
+#[derive(Debug, Clone)]
+struct SlabEntry<T> {
+    /// Incremented every time the slot is vacated
+    generation: u64,
+    /// The stored value, `None` when the slot is vacant
+    value: Option<T>,
+}
+
+impl<T> Slab<T> {
+    /// Creates a slab that can hold `capacity` values without reallocating.
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Slab {
+            entries: Vec::with_capacity(capacity),
+            free_slots: Vec::with_capacity(capacity),
+            len: 0,
+            base_generation: 0,
+        }
+    }
+
+    /// Stores a value and returns its `(slot, generation)` pair.
+    pub(crate) fn insert(&mut self, value: T) -> (usize, u64) {
+        self.len += 1;
+
+        match self.free_slots.pop() {
+            Some(slot) => {
+                let entry = &mut self.entries[slot];
+                entry.value = Some(value);
+                (slot, entry.generation)
+            }
+            None => {
+                self.entries.push(SlabEntry {
+                    generation: self.base_generation,
+                    value: Some(value),
+                });
+                (self.entries.len() - 1, self.base_generation)
+            }
+        }
+    }
+
+    /// Returns the value at `slot`, regardless of its generation.
+    pub(crate) fn get(&self, slot: usize) -> Option<&T> {
+        self.entries
+            .get(slot)
+            .and_then(|entry| entry.value.as_ref())
+    }
+
+    /// Returns the value at `slot`, regardless of its generation.
+    pub(crate) fn get_mut(&mut self, slot: usize) -> Option<&mut T> {
+        self.entries
+            .get_mut(slot)
+            .and_then(|entry| entry.value.as_mut())
+    }
+
+    /// Returns whether `slot` is occupied by the value stored with `generation`.
+    pub(crate) fn contains(&self, slot: usize, generation: u64) -> bool {
+        self.entries
+            .get(slot)
+            .is_some_and(|entry| entry.generation == generation && entry.value.is_some())
+    }
+
+    /// Removes and returns the value at `slot`, invalidating its generation.
+    pub(crate) fn remove(&mut self, slot: usize) -> Option<T> {
+        let entry = self.entries.get_mut(slot)?;
+        let value = entry.value.take()?;
+
+        entry.generation += 1;
+        self.free_slots.push(slot);
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    /// Returns every occupied slot with its value, in slot order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, entry)| entry.value.as_ref().map(|value| (slot, value)))
+    }
+
+    /// Returns every occupied slot with its value, in slot order.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.entries
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(slot, entry)| entry.value.as_mut().map(|value| (slot, value)))
+    }
+
+    /// Returns the generation of the value at `slot`, if the slot is occupied.
+    pub(crate) fn generation(&self, slot: usize) -> Option<u64> {
+        self.entries
+            .get(slot)
+            .filter(|entry| entry.value.is_some())
+            .map(|entry| entry.generation)
+    }
+
+    /// Moves the values stored past the first `len` slots into the vacant slots among
+    /// them, then releases the slots past them and the spare capacity.
+    ///
+    /// A moved value takes the generation of its new slot, which no reference was
+    /// issued with since the slot was vacated, and slots appended later start past the
+    /// generations of the released ones, so references to the previous slots of moved
+    /// values are detected as stale instead of aliasing another value.
+    ///
+    /// ## Returns
+    ///
+    /// The previous and the new slot of every moved value
+    pub(crate) fn compact(&mut self) -> Vec<(usize, usize)> {
+        let vacant: Vec<usize> = (0..self.len)
+            .filter(|&slot| self.entries[slot].value.is_none())
+            .collect();
+        let mut relocations = Vec::with_capacity(vacant.len());
+        let mut targets = vacant.into_iter();
+        for from in self.len..self.entries.len() {
+            if let Some(value) = self.entries[from].value.take() {
+                let to = targets
+                    .next()
+                    .expect("a vacant slot must remain for every value");
+                self.entries[to].value = Some(value);
+                relocations.push((from, to));
+            }
+        }
+
+        if let Some(generation) = self.entries[self.len..]
+            .iter()
+            .map(|entry| entry.generation + 1)
+            .max()
+        {
+            self.base_generation = self.base_generation.max(generation);
+        }
+        self.entries.truncate(self.len);
+        self.entries.shrink_to_fit();
+        self.free_slots.clear();
+        self.free_slots.shrink_to_fit();
+        relocations
+    }
+
+    /// Returns the number of slots, occupied or vacant.
+    pub(crate) fn slot_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the combined capacity of the entry storage and the free list, which
+    /// only changes when one of them reallocates.
+    pub(crate) fn capacity(&self) -> usize {
+        self.entries.capacity() + self.free_slots.capacity()
+    }
+
+    /// Estimates the heap bytes held by the slab, vacant slots included.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        vec_bytes(&self.entries) + vec_bytes(&self.free_slots)
+    }
+
+    /// Returns the number of occupied slots.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
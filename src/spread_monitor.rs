@@ -0,0 +1,247 @@
+use crate::order_book::OrderBook;
+use crate::spread_tracker::SpreadSample;
+use rust_decimal::Decimal;
+
+/// A synthetic spread metric between two legs, A and B.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpreadMetric {
+    /// A's best bid minus B's best ask, what selling A and buying B earns per unit
+    BidMinusAsk,
+    /// A's best ask minus B's best bid, what buying A and selling B costs per unit
+    AskMinusBid,
+    /// A's mid price minus B's mid price
+    MidDifference,
+    /// A's mid price divided by B's mid price
+    MidRatio,
+}
+
+/// Which way a metric has to cross a threshold for an alert to be raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Crossing {
+    /// The metric rises above the threshold
+    Above,
+    /// The metric falls below the threshold
+    Below,
+}
+
+/// The synthetic spread metrics of two legs at one point in time.
+///
+/// A metric is `None` when a side it needs is empty on either leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadMetrics {
+    /// When the metrics were computed, in nanoseconds since the Unix epoch
+    pub timestamp_nanos: u64,
+    /// A's best bid minus B's best ask
+    pub bid_minus_ask: Option<Decimal>,
+    /// A's best ask minus B's best bid
+    pub ask_minus_bid: Option<Decimal>,
+    /// A's mid price minus B's mid price
+    pub mid_difference: Option<Decimal>,
+    /// A's mid price divided by B's mid price, `None` if B's mid price is zero
+    pub mid_ratio: Option<Decimal>,
+}
+
+impl SpreadMetrics {
+    /// Computes the metrics from the top of book of each leg.
+    ///
+    /// ## Arguments
+    ///
+    /// * `leg_a`: The top of book of leg A
+    /// * `leg_b`: The top of book of leg B
+    ///
+    /// ## Returns
+    ///
+    /// The metrics, stamped with the later of the two samples' timestamps
+    pub fn between(leg_a: &SpreadSample, leg_b: &SpreadSample) -> Self {
+        let mid = |sample: &SpreadSample| {
+            sample
+                .best_bid
+                .zip(sample.best_ask)
+                .map(|(best_bid, best_ask)| (best_bid + best_ask) / Decimal::TWO)
+        };
+        let (mid_a, mid_b) = (mid(leg_a), mid(leg_b));
+
+        SpreadMetrics {
+            timestamp_nanos: leg_a.timestamp_nanos.max(leg_b.timestamp_nanos),
+            bid_minus_ask: leg_a
+                .best_bid
+                .zip(leg_b.best_ask)
+                .map(|(best_bid, best_ask)| best_bid - best_ask),
+            ask_minus_bid: leg_a
+                .best_ask
+                .zip(leg_b.best_bid)
+                .map(|(best_ask, best_bid)| best_ask - best_bid),
+            mid_difference: mid_a.zip(mid_b).map(|(mid_a, mid_b)| mid_a - mid_b),
+            mid_ratio: mid_a
+                .zip(mid_b)
+                .and_then(|(mid_a, mid_b)| mid_a.checked_div(mid_b)),
+        }
+    }
+
+    /// Returns the value of a metric.
+    pub fn value(&self, metric: SpreadMetric) -> Option<Decimal> {
+        match metric {
+            SpreadMetric::BidMinusAsk => self.bid_minus_ask,
+            SpreadMetric::AskMinusBid => self.ask_minus_bid,
+            SpreadMetric::MidDifference => self.mid_difference,
+            SpreadMetric::MidRatio => self.mid_ratio,
+        }
+    }
+}
+
+/// A metric crossing one of a `SpreadMonitor`'s thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadAlert {
+    /// The metric that crossed the threshold
+    pub metric: SpreadMetric,
+    /// The direction of the crossing
+    pub crossing: Crossing,
+    /// The threshold that was crossed
+    pub threshold: Decimal,
+    /// The metric's value after the crossing
+    pub value: Decimal,
+    /// When the crossing was observed, in nanoseconds since the Unix epoch
+    pub timestamp_nanos: u64,
+}
+
+/// A threshold registered with a `SpreadMonitor`.
+#[derive(Debug, Clone)]
+struct Threshold {
+    /// The metric compared with the threshold
+    metric: SpreadMetric,
+    /// The direction that raises an alert
+    crossing: Crossing,
+    /// The threshold's value
+    level: Decimal,
+    /// Whether the metric was beyond the threshold at the last observation
+    beyond: bool,
+}
+
+/// Computes synthetic spread metrics between the books of two legs, e.g. the two
+/// instruments of a pair or the two expiries of a calendar spread, and raises alerts
+/// when they cross thresholds.
+///
+/// Alerts are raised on crossings, not while a metric stays beyond its threshold: a
+/// threshold raises its next alert only after the metric has come back. When a metric
+/// cannot be computed because a side is empty, its thresholds keep their state.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Crossing, Order, OrderBook, Side, SpreadMetric, SpreadMonitor};
+/// use rust_decimal::Decimal;
+///
+/// let mut front_month = OrderBook::new();
+/// let mut back_month = OrderBook::new();
+/// let mut spread_monitor = SpreadMonitor::new();
+/// spread_monitor.alert_when(SpreadMetric::BidMinusAsk, Crossing::Above, Decimal::ZERO);
+///
+/// front_month.insert_order(Order::new(100.00, 10, Side::Bid));
+/// front_month.insert_order(Order::new(100.50, 10, Side::Ask));
+/// back_month.insert_order(Order::new(100.25, 10, Side::Bid));
+/// back_month.insert_order(Order::new(100.75, 10, Side::Ask));
+/// assert!(spread_monitor.observe(&front_month, &back_month).is_empty());
+///
+/// // The front month can now be sold above where the back month is bought
+/// front_month.insert_order(Order::new(101.00, 10, Side::Bid));
+/// let alerts = spread_monitor.observe(&front_month, &back_month);
+/// assert_eq!(alerts[0].value, Decimal::new(25, 2));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SpreadMonitor {
+    /// Registered thresholds, in registration order
+    thresholds: Vec<Threshold>,
+    /// The metrics at the last observation
+    latest: Option<SpreadMetrics>,
+}
+
+impl SpreadMonitor {
+    /// Creates a monitor without thresholds.
+    pub fn new() -> Self {
+        SpreadMonitor::default()
+    }
+
+    /// Registers a threshold raising an alert whenever a metric crosses it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `metric`: The metric to watch
+    /// * `crossing`: Whether rising above or falling below the threshold raises the alert
+    /// * `threshold`: The threshold
+    pub fn alert_when(&mut self, metric: SpreadMetric, crossing: Crossing, threshold: Decimal) {
+        self.thresholds.push(Threshold {
+            metric,
+            crossing,
+            level: threshold,
+            beyond: false,
+        });
+    }
+
+    /// Computes the metrics from the books' current top of book.
+    ///
+    /// Call this after every change to either book. The metrics are stamped with leg
+    /// A's clock.
+    ///
+    /// ## Arguments
+    ///
+    /// * `leg_a`: The book of leg A
+    /// * `leg_b`: The book of leg B
+    ///
+    /// ## Returns
+    ///
+    /// The alerts raised by the observation, in threshold registration order
+    pub fn observe(&mut self, leg_a: &OrderBook, leg_b: &OrderBook) -> Vec<SpreadAlert> {
+        let timestamp_nanos = leg_a.now_nanos();
+        let sample = |order_book: &OrderBook| {
+            let (best_bid, best_ask, _) = order_book.compute_spread();
+            SpreadSample {
+                timestamp_nanos,
+                best_bid,
+                best_ask,
+            }
+        };
+        self.record(&sample(leg_a), &sample(leg_b))
+    }
+
+    /// Computes the metrics from a top-of-book observation of each leg.
+    ///
+    /// ## Arguments
+    ///
+    /// * `leg_a`: The top of book of leg A
+    /// * `leg_b`: The top of book of leg B
+    ///
+    /// ## Returns
+    ///
+    /// The alerts raised by the observation, in threshold registration order
+    pub fn record(&mut self, leg_a: &SpreadSample, leg_b: &SpreadSample) -> Vec<SpreadAlert> {
+        let metrics = SpreadMetrics::between(leg_a, leg_b);
+        self.latest = Some(metrics);
+
+        let mut alerts = Vec::new();
+        for threshold in &mut self.thresholds {
+            let Some(value) = metrics.value(threshold.metric) else {
+                continue;
+            };
+            let beyond = match threshold.crossing {
+                Crossing::Above => value > threshold.level,
+                Crossing::Below => value < threshold.level,
+            };
+            if beyond && !threshold.beyond {
+                alerts.push(SpreadAlert {
+                    metric: threshold.metric,
+                    crossing: threshold.crossing,
+                    threshold: threshold.level,
+                    value,
+                    timestamp_nanos: metrics.timestamp_nanos,
+                });
+            }
+            threshold.beyond = beyond;
+        }
+        alerts
+    }
+
+    /// Returns the metrics at the last observation, if any.
+    pub fn latest(&self) -> Option<&SpreadMetrics> {
+        self.latest.as_ref()
+    }
+}
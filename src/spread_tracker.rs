@@ -0,0 +1,225 @@
+use crate::order_book::OrderBook;
+use crate::types::unix_timestamp_nanos;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// A top-of-book observation recorded by a `SpreadTracker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadSample {
+    /// When the top of book changed, in nanoseconds since the Unix epoch
+    pub timestamp_nanos: u64,
+    /// The best bid after the change
+    pub best_bid: Option<Decimal>,
+    /// The best ask after the change
+    pub best_ask: Option<Decimal>,
+}
+
+impl SpreadSample {
+    /// Returns the spread, or `None` unless both sides are quoted.
+    pub fn spread(&self) -> Option<Decimal> {
+        self.best_bid
+            .zip(self.best_ask)
+            .map(|(best_bid, best_ask)| best_ask - best_bid)
+    }
+}
+
+/// Records the best bid and ask every time the top of book changes and computes
+/// rolling spread statistics over a time window.
+///
+/// The tracker keeps every sample in effect during the last `window`: the samples
+/// recorded within it, plus the latest sample recorded before it, whose spread was
+/// still standing when the window opened. Statistics ignore samples where one side
+/// of the book was empty.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Order, OrderBook, Side, SpreadTracker};
+/// use rust_decimal::Decimal;
+/// use std::time::Duration;
+///
+/// let mut order_book = OrderBook::new();
+/// let mut spread_tracker = SpreadTracker::new(Duration::from_secs(60));
+///
+/// order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+/// order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+/// assert!(spread_tracker.observe(&order_book));
+///
+/// // Orders away from the touch leave the top of book unchanged
+/// order_book.insert_order(Order::new(98.00, 10, Side::Bid));
+/// assert!(!spread_tracker.observe(&order_book));
+///
+/// assert_eq!(spread_tracker.max_spread(), Some(Decimal::new(2, 0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpreadTracker {
+    /// How far back statistics look
+    window: Duration,
+    /// Samples in effect during the window, oldest first
+    samples: VecDeque<SpreadSample>,
+}
+
+impl SpreadTracker {
+    /// Creates a tracker computing statistics over the given window.
+    ///
+    /// ## Arguments
+    ///
+    /// * `window`: How far back from the latest sample statistics look
+    pub fn new(window: Duration) -> Self {
+        SpreadTracker {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records the book's current top of book if it changed since the last sample.
+    ///
+    /// Call this after every change to the book; calls that leave the best prices
+    /// untouched are ignored.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The order book to observe
+    ///
+    /// ## Returns
+    ///
+    /// `true` if a new sample was recorded
+    pub fn observe(&mut self, order_book: &OrderBook) -> bool {
+        let (best_bid, best_ask, _) = order_book.compute_spread();
+        self.record(best_bid, best_ask, unix_timestamp_nanos())
+    }
+
+    /// Records a top-of-book observation taken at the given time.
+    ///
+    /// Timestamps are expected to be non-decreasing. Observations equal to the latest
+    /// sample are ignored.
+    ///
+    /// ## Arguments
+    ///
+    /// * `best_bid`: The best bid, if any
+    /// * `best_ask`: The best ask, if any
+    /// * `timestamp_nanos`: When the observation was taken, in nanoseconds since the Unix epoch
+    ///
+    /// ## Returns
+    ///
+    /// `true` if a new sample was recorded
+    pub fn record(
+        &mut self,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+        timestamp_nanos: u64,
+    ) -> bool {
+        if let Some(latest) = self.samples.back() {
+            if latest.best_bid == best_bid && latest.best_ask == best_ask {
+                return false;
+            }
+        }
+
+        self.samples.push_back(SpreadSample {
+            timestamp_nanos,
+            best_bid,
+            best_ask,
+        });
+        self.evict_before(self.window_start(timestamp_nanos));
+        true
+    }
+
+    /// Returns the samples in effect during the window, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &SpreadSample> {
+        self.samples.iter()
+    }
+
+    /// Returns the latest recorded sample, if any.
+    pub fn latest(&self) -> Option<&SpreadSample> {
+        self.samples.back()
+    }
+
+    /// Returns the mean of the recorded spreads, or `None` if no spread was recorded.
+    pub fn mean_spread(&self) -> Option<Decimal> {
+        let (sum, count) = self
+            .spreads()
+            .fold((Decimal::ZERO, 0u64), |(sum, count), spread| {
+                (sum + spread, count + 1)
+            });
+        (count > 0).then(|| sum / Decimal::from(count))
+    }
+
+    /// Returns the median of the recorded spreads, or `None` if no spread was recorded.
+    ///
+    /// With an even number of spreads, this is the mean of the two middle values.
+    pub fn median_spread(&self) -> Option<Decimal> {
+        let mut spreads: Vec<Decimal> = self.spreads().collect();
+        spreads.sort_unstable();
+
+        let middle = spreads.len() / 2;
+        match spreads.len() {
+            0 => None,
+            length if length % 2 == 1 => Some(spreads[middle]),
+            _ => Some((spreads[middle - 1] + spreads[middle]) / Decimal::TWO),
+        }
+    }
+
+    /// Returns the widest recorded spread, or `None` if no spread was recorded.
+    pub fn max_spread(&self) -> Option<Decimal> {
+        self.spreads().max()
+    }
+
+    /// Returns the time-weighted average spread over the window ending at `now_nanos`.
+    ///
+    /// Each spread is weighted by how long it stood within the window, so a spread
+    /// that flickered wide for a microsecond barely moves the average. Periods where
+    /// one side of the book was empty are left out.
+    ///
+    /// ## Arguments
+    ///
+    /// * `now_nanos`: The end of the window, in nanoseconds since the Unix epoch
+    ///
+    /// ## Returns
+    ///
+    /// The average spread, or `None` if no spread stood for any time within the window
+    pub fn time_weighted_average_spread(&self, now_nanos: u64) -> Option<Decimal> {
+        let window_start = self.window_start(now_nanos);
+        let mut weighted_sum = Decimal::ZERO;
+        let mut total_nanos = 0u64;
+
+        for (index, sample) in self.samples.iter().enumerate() {
+            let start = sample.timestamp_nanos.max(window_start);
+            let end = self
+                .samples
+                .get(index + 1)
+                .map_or(now_nanos, |next| next.timestamp_nanos)
+                .min(now_nanos);
+            let Some(spread) = sample.spread().filter(|_| end > start) else {
+                continue;
+            };
+
+            weighted_sum += spread * Decimal::from(end - start);
+            total_nanos += end - start;
+        }
+
+        (total_nanos > 0).then(|| weighted_sum / Decimal::from(total_nanos))
+    }
+
+    /// Returns every recorded spread, skipping samples with an empty side.
+    fn spreads(&self) -> impl Iterator<Item = Decimal> + '_ {
+        self.samples.iter().filter_map(SpreadSample::spread)
+    }
+
+    /// Returns the start of the window ending at `end_nanos`.
+    fn window_start(&self, end_nanos: u64) -> u64 {
+        let window_nanos = u64::try_from(self.window.as_nanos()).unwrap_or(u64::MAX);
+        end_nanos.saturating_sub(window_nanos)
+    }
+
+    /// Drops samples superseded before `window_start`, keeping the one still standing then.
+    fn evict_before(&mut self, window_start: u64) {
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|next| next.timestamp_nanos <= window_start)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use crate::error::{OrderBookError, Result};
+use crate::order_book::OrderBook;
+use crate::types::{Command, Order, OrderEvent, ParticipantId};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The number of token fractions a bucket counts per token, so refills over elapsed
+/// nanoseconds are exact.
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// A token-bucket rate limit enforced by a `Throttle`.
+///
+/// Every command admitted takes one token from the bucket, which holds at most `burst`
+/// tokens and is refilled at `rate_per_second` tokens per second of the book's clock.
+/// A participant can therefore send `burst` commands at once, then `rate_per_second`
+/// per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    /// The sustained number of commands per second
+    pub rate_per_second: u64,
+    /// The number of commands that can be sent at once after a quiet period
+    pub burst: u64,
+}
+
+/// What a `Throttle` does with a command whose participant, or the venue, is out of
+/// tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ThrottlePolicy {
+    /// Reject the command with `OrderBookError::Throttled`
+    #[default]
+    Reject,
+    /// Hold the command until tokens are available, up to a number of commands held at
+    /// once, beyond which commands are rejected
+    Queue {
+        /// The maximum number of commands held, across participants
+        capacity: usize,
+    },
+}
+
+/// How a `Throttle` handled a command it did not reject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// The command was applied to the book, with the events it produced
+    Applied(Vec<OrderEvent>),
+    /// The command is held until `Throttle::release_queued` applies it
+    Queued,
+}
+
+/// The tokens left in a rate limit's bucket.
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    /// The limit the bucket enforces
+    limit: RateLimit,
+    /// The tokens held, in billionths of a token
+    fractions: u128,
+    /// The time of the last refill, in nanoseconds since the Unix epoch
+    refilled_at: u64,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    fn new(limit: RateLimit, now_nanos: u64) -> Self {
+        TokenBucket {
+            limit,
+            fractions: u128::from(limit.burst) * NANOS_PER_SECOND,
+            refilled_at: now_nanos,
+        }
+    }
+
+    /// Adds the tokens accrued since the last refill, up to the burst.
+    fn refill(&mut self, now_nanos: u64) {
+        let elapsed = u128::from(now_nanos.saturating_sub(self.refilled_at));
+        let capacity = u128::from(self.limit.burst) * NANOS_PER_SECOND;
+        self.fractions = self
+            .fractions
+            .saturating_add(elapsed * u128::from(self.limit.rate_per_second))
+            .min(capacity);
+        self.refilled_at = self.refilled_at.max(now_nanos);
+    }
+
+    /// Returns whether the bucket holds a whole token.
+    fn has_token(&self) -> bool {
+        self.fractions >= NANOS_PER_SECOND
+    }
+}
+
+/// An order-entry gateway applying participants' commands to an `OrderBook` under
+/// venue-style rate limits.
+///
+/// The throttle enforces a token-bucket `RateLimit` per participant, a global one
+/// across all participants, or both: a command is applied only if every limit it is
+/// subject to has a token left, and then takes one from each. Tokens are refilled by
+/// the book's clock, so a book with a manual clock, such as one created with
+/// `OrderBook::deterministic`, throttles identically on every replay. A command that
+/// finds no token is rejected with `OrderBookError::Throttled` under
+/// `ThrottlePolicy::Reject`, or held under `ThrottlePolicy::Queue` until
+/// `release_queued` applies it. Held commands keep their order: a participant's later
+/// commands queue behind their held ones, and, under a global limit, every command
+/// queues behind any held one.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{
+///     Admission, Command, Order, OrderBook, OrderBookError, ParticipantId, RateLimit, Side,
+///     Throttle, ThrottlePolicy,
+/// };
+///
+/// let mut throttle = Throttle::new(OrderBook::deterministic(7), ThrottlePolicy::Reject);
+/// throttle.set_participant_limit(Some(RateLimit { rate_per_second: 10, burst: 2 }));
+///
+/// let trader = ParticipantId(1);
+/// for _ in 0..2 {
+///     let admission = throttle.apply(trader, Command::Insert(Order::new(100.00, 10, Side::Bid)));
+///     assert!(matches!(admission, Ok(Admission::Applied(_))));
+/// }
+/// assert_eq!(
+///     throttle.apply(trader, Command::Insert(Order::new(100.00, 10, Side::Bid))),
+///     Err(OrderBookError::Throttled { participant_id: trader })
+/// );
+///
+/// // A tenth of a second refills one token
+/// throttle.order_book_mut().set_time(100_000_000);
+/// assert!(throttle.apply(trader, Command::Insert(Order::new(100.00, 10, Side::Bid))).is_ok());
+/// ```
+#[derive(Debug)]
+pub struct Throttle {
+    /// The book commands are applied to
+    order_book: OrderBook,
+    /// What happens to commands that find no token
+    policy: ThrottlePolicy,
+    /// The limit every participant is subject to, if any
+    participant_limit: Option<RateLimit>,
+    /// The bucket of each participant that sent a command under the participant limit
+    participants: HashMap<ParticipantId, TokenBucket>,
+    /// The bucket shared by all participants, if any
+    global: Option<TokenBucket>,
+    /// The commands held under `ThrottlePolicy::Queue`, in arrival order
+    queue: VecDeque<(ParticipantId, Command)>,
+}
+
+impl Throttle {
+    /// Creates a throttle around a book, with no limit set yet.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book commands are applied to
+    /// * `policy`: What happens to commands that find no token
+    pub fn new(order_book: OrderBook, policy: ThrottlePolicy) -> Self {
+        Throttle {
+            order_book,
+            policy,
+            participant_limit: None,
+            participants: HashMap::new(),
+            global: None,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Sets the limit every participant is subject to, or removes it with `None`.
+    ///
+    /// Every participant's bucket starts full again.
+    pub fn set_participant_limit(&mut self, limit: Option<RateLimit>) {
+        self.participant_limit = limit;
+        self.participants.clear();
+    }
+
+    /// Sets the limit shared by all participants, or removes it with `None`.
+    ///
+    /// The shared bucket starts full.
+    pub fn set_global_limit(&mut self, limit: Option<RateLimit>) {
+        let now_nanos = self.order_book.now_nanos();
+        self.global = limit.map(|limit| TokenBucket::new(limit, now_nanos));
+    }
+
+    /// Applies a participant's command to the book if their limits allow it.
+    ///
+    /// ## Arguments
+    ///
+    /// * `participant_id`: The participant sending the command
+    /// * `command`: The command to apply
+    ///
+    /// ## Returns
+    ///
+    /// `Admission::Applied` with the command's events, `Admission::Queued` if the
+    /// command is held, `OrderBookError::Throttled` if it is rejected for lack of
+    /// tokens, which the book records like its own rejections if the command submits an
+    /// order, or the error of the command itself, as for `OrderBook::apply`
+    pub fn apply(&mut self, participant_id: ParticipantId, command: Command) -> Result<Admission> {
+        let behind_queue = self
+            .queue
+            .iter()
+            .any(|(queued, _)| *queued == participant_id)
+            || (self.global.is_some() && !self.queue.is_empty());
+        if !behind_queue && self.admit(participant_id) {
+            return self.order_book.apply(command).map(Admission::Applied);
+        }
+        match self.policy {
+            ThrottlePolicy::Queue { capacity } if self.queue.len() < capacity => {
+                self.queue.push_back((participant_id, command));
+                Ok(Admission::Queued)
+            }
+            _ => {
+                let reason = OrderBookError::Throttled { participant_id };
+                Err(match submitted_order(&command) {
+                    Some(order) => self.order_book.reject(reason, order),
+                    None => reason,
+                })
+            }
+        }
+    }
+
+    /// Applies the held commands whose limits have tokens again, in arrival order.
+    ///
+    /// Embedders call this as the book's clock advances, e.g. from a timer or before
+    /// every new command.
+    ///
+    /// ## Returns
+    ///
+    /// The participant and outcome of every command applied, in the order applied
+    pub fn release_queued(&mut self) -> Vec<(ParticipantId, Result<Vec<OrderEvent>>)> {
+        let mut released = Vec::new();
+        let mut blocked = HashSet::new();
+        let mut held = VecDeque::with_capacity(self.queue.len());
+        while let Some((participant_id, command)) = self.queue.pop_front() {
+            if blocked.contains(&participant_id) || !self.admit(participant_id) {
+                blocked.insert(participant_id);
+                held.push_back((participant_id, command));
+                if self.global.is_some_and(|bucket| !bucket.has_token()) {
+                    held.append(&mut self.queue);
+                }
+                continue;
+            }
+            released.push((participant_id, self.order_book.apply(command)));
+        }
+        self.queue = held;
+        released
+    }
+
+    /// Returns the number of commands held.
+    pub fn queued_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the book.
+    pub fn order_book(&self) -> &OrderBook {
+        &self.order_book
+    }
+
+    /// Returns the book for changes that bypass the throttle, e.g. advancing its manual
+    /// clock or administrative actions.
+    pub fn order_book_mut(&mut self) -> &mut OrderBook {
+        &mut self.order_book
+    }
+
+    /// Returns the book, dropping the held commands.
+    pub fn into_inner(self) -> OrderBook {
+        self.order_book
+    }
+
+    /// Takes a token from every limit the participant is subject to, if all have one.
+    fn admit(&mut self, participant_id: ParticipantId) -> bool {
+        let now_nanos = self.order_book.now_nanos();
+        let participant = self.participant_limit.map(|limit| {
+            self.participants
+                .entry(participant_id)
+                .or_insert_with(|| TokenBucket::new(limit, now_nanos))
+        });
+        let buckets = participant.into_iter().chain(self.global.as_mut());
+        let mut buckets: Vec<&mut TokenBucket> = buckets.collect();
+        for bucket in buckets.iter_mut() {
+            bucket.refill(now_nanos);
+        }
+        if !buckets.iter().all(|bucket| bucket.has_token()) {
+            return false;
+        }
+        for bucket in buckets {
+            bucket.fractions -= NANOS_PER_SECOND;
+        }
+        true
+    }
+}
+
+/// Returns the new order a command submits, if any.
+fn submitted_order(command: &Command) -> Option<&Order> {
+    match command {
+        Command::Insert(order)
+        | Command::InsertWithClientId(order, _)
+        | Command::Match(order)
+        | Command::InsertDay(order)
+        | Command::MatchDay(order) => Some(order),
+        Command::Cancel(_)
+        | Command::Amend { .. }
+        | Command::Clear
+        | Command::SetState(_)
+        | Command::SetTime(_)
+        | Command::EndSession => None,
+    }
+}
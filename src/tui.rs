@@ -0,0 +1,208 @@
+//! Terminal viewer rendering a live depth ladder and event tape (feature `tui`).
+//!
+//! The viewer is just another subscriber of the `OrderEvent` stream: it keeps its own
+//! `MarketDepthCache` and a bounded tape of the most recent events, and redraws them
+//! on every refresh. It never touches the `OrderBook`, so attaching it to a running
+//! book costs nothing more than forwarding the events it already publishes.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use order_book::tui::BookViewer;
+//! use order_book::{Order, OrderBook, Side};
+//! use std::sync::mpsc;
+//! use std::thread;
+//!
+//! let (event_sender, event_receiver) = mpsc::channel();
+//!
+//! thread::spawn(move || {
+//!     let mut order_book = OrderBook::new();
+//!     let event = order_book.insert_order(Order::new(100.50, 100, Side::Bid)).event;
+//!     event_sender.send(event).unwrap();
+//! });
+//!
+//! // Blocks until the user presses `q` or `Esc`
+//! BookViewer::new(10, 50).run(event_receiver).unwrap();
+//! ```
+
+use crate::market_depth_cache::MarketDepthCache;
+use crate::types::{OrderEvent, Side};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+/// How long the event loop waits for keyboard input before redrawing.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A terminal viewer for the aggregated market depth and the most recent order events.
+///
+/// The ladder shows the `ladder_levels` aggregated levels closest to the touch on each
+/// side, asks above and bids below, while the tape lists the last `tape_capacity`
+/// events with the newest on top.
+#[derive(Debug)]
+pub struct BookViewer {
+    /// Depth aggregated from the events seen so far
+    market_depth_cache: MarketDepthCache,
+    /// Most recent events, newest at the front
+    event_tape: VecDeque<OrderEvent>,
+    /// Maximum number of events retained on the tape
+    tape_capacity: usize,
+    /// Number of aggregated levels displayed per side
+    ladder_levels: usize,
+}
+
+impl BookViewer {
+    /// Creates a viewer displaying `ladder_levels` levels per side and retaining
+    /// the last `tape_capacity` events.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::tui::BookViewer;
+    ///
+    /// let viewer = BookViewer::new(10, 50);
+    /// ```
+    pub fn new(ladder_levels: usize, tape_capacity: usize) -> Self {
+        BookViewer {
+            market_depth_cache: MarketDepthCache::new(),
+            event_tape: VecDeque::with_capacity(tape_capacity),
+            tape_capacity,
+            ladder_levels,
+        }
+    }
+
+    /// Applies an event to the ladder and pushes it onto the tape.
+    ///
+    /// ## Arguments
+    ///
+    /// * `event`: The order event published by the order book
+    pub fn process_order_event(&mut self, event: OrderEvent) {
+        self.market_depth_cache.process_order_event(event.clone());
+
+        if self.tape_capacity == 0 {
+            return;
+        }
+        if self.event_tape.len() == self.tape_capacity {
+            self.event_tape.pop_back();
+        }
+        self.event_tape.push_front(event);
+    }
+
+    /// Draws the depth ladder and the event tape side by side into the frame.
+    ///
+    /// This is exposed separately from `run` so that the viewer can be embedded
+    /// into a larger terminal application.
+    pub fn render(&self, frame: &mut Frame) {
+        let [ladder_area, tape_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(frame.area());
+
+        frame.render_widget(self.depth_ladder(), ladder_area);
+        frame.render_widget(self.event_list(), tape_area);
+    }
+
+    /// Runs the viewer until the user presses `q` or `Esc`.
+    ///
+    /// Events are drained from the receiver before every redraw. The viewer keeps
+    /// displaying the last known state once the sending side disconnects.
+    ///
+    /// ## Arguments
+    ///
+    /// * `events`: The receiving end of the channel the order events are forwarded to
+    ///
+    /// ## Returns
+    ///
+    /// An error if drawing to or reading from the terminal fails
+    pub fn run(mut self, events: Receiver<OrderEvent>) -> io::Result<()> {
+        let mut terminal = ratatui::init();
+        let result = self.event_loop(&mut terminal, &events);
+        ratatui::restore();
+
+        result
+    }
+
+    fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        events: &Receiver<OrderEvent>,
+    ) -> io::Result<()> {
+        loop {
+            while let Ok(order_event) = events.try_recv() {
+                self.process_order_event(order_event);
+            }
+
+            terminal.draw(|frame| self.render(frame))?;
+
+            if event::poll(REFRESH_INTERVAL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press
+                        && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn depth_ladder(&self) -> Table<'static> {
+        let (bid_depth, ask_depth) = self.market_depth_cache.get_aggregated_market_depth();
+
+        // Asks are listed from the furthest displayed level down to the touch,
+        // so that the best ask sits right above the best bid
+        let mut ask_rows: Vec<Row> = ask_depth
+            .iter()
+            .take(self.ladder_levels)
+            .map(|(price, quantity)| {
+                Row::new(vec![String::new(), price.to_string(), quantity.to_string()])
+                    .style(Style::default().fg(Color::Red))
+            })
+            .collect();
+        ask_rows.reverse();
+
+        let bid_rows = bid_depth
+            .iter()
+            .rev()
+            .take(self.ladder_levels)
+            .map(|(price, quantity)| {
+                Row::new(vec![quantity.to_string(), price.to_string(), String::new()])
+                    .style(Style::default().fg(Color::Green))
+            });
+
+        let header = Row::new(vec!["Bid", "Price", "Ask"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+
+        Table::new(
+            ask_rows.into_iter().chain(bid_rows),
+            [
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+            ],
+        )
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Depth"))
+    }
+
+    fn event_list(&self) -> List<'static> {
+        let items = self.event_tape.iter().map(|event| {
+            let (label, color) = match event.side {
+                Side::Bid => ("BID", Color::Green),
+                Side::Ask => ("ASK", Color::Red),
+            };
+            ListItem::new(format!(
+                "{label} {} {:+}",
+                event.price, event.quantity_delta
+            ))
+            .style(Style::default().fg(color))
+        });
+
+        List::new(items).block(Block::default().borders(Borders::ALL).title("Events"))
+    }
+}
@@ -1,11 +1,23 @@
+use crate::error::OrderBookError;
 use rust_decimal::Decimal;
+use std::any::Any;
 use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Represents the side of an order in the order book.
 ///
 /// - `Bid` represents buy orders (demand side)
 /// - `Ask` represents sell orders (supply side)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 pub enum Side {
     /// Buy side: traders willing to purchase at a given price
     Bid,
@@ -13,10 +25,310 @@ pub enum Side {
     Ask,
 }
 
+impl Side {
+    /// Returns the side that orders on this side trade against.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Bid => Side::Ask,
+            Side::Ask => Side::Bid,
+        }
+    }
+}
+
+/// How prices are canonicalized before being used as map keys and in events.
+///
+/// `Decimal` compares numerically, so `100.5` and `100.50` already address the same
+/// level, but the two representations still differ when displayed, serialized, or
+/// hashed into a digest. Canonicalizing prices on the way in makes every key, event,
+/// and snapshot use one representation per price, whatever the caller passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PriceNormalization {
+    /// Strip trailing zeros, so `100.50` is stored as `100.5`
+    #[default]
+    Normalize,
+    /// Use exactly this many decimal places, rounding half to even if more are given,
+    /// so `100.5` is stored as `100.50` with a scale of 2
+    Scale(u32),
+    /// Keep prices exactly as provided
+    Preserve,
+}
+
+impl PriceNormalization {
+    /// Returns the canonical representation of `price` under this policy.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::PriceNormalization;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let price = Decimal::new(10050, 2); // 100.50
+    /// assert_eq!(PriceNormalization::Normalize.apply(price).to_string(), "100.5");
+    /// assert_eq!(PriceNormalization::Scale(3).apply(price).to_string(), "100.500");
+    /// ```
+    pub fn apply(self, price: Decimal) -> Decimal {
+        match self {
+            PriceNormalization::Normalize => price.normalize(),
+            PriceNormalization::Scale(scale) => {
+                let mut scaled = price.round_dp(scale);
+                scaled.rescale(scale);
+                scaled
+            }
+            PriceNormalization::Preserve => price,
+        }
+    }
+}
+
+/// How a `MarketDepthCache` assigns prices to aggregated levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BucketingPolicy {
+    /// Truncate every price to its integer level, as `OrderBook::aggregate_price_to_level`
+    /// does, whatever its side
+    #[default]
+    Symmetric,
+    /// Round bids down and asks up to the next integer level, so a level never shows
+    /// liquidity at a better price than its orders offer
+    SideAware,
+}
+
+impl BucketingPolicy {
+    /// Returns the integer level of a price on the given side under this policy.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{BucketingPolicy, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let price = Decimal::new(10025, 2); // 100.25
+    /// assert_eq!(BucketingPolicy::Symmetric.level(price, Side::Ask), Decimal::new(100, 0));
+    /// assert_eq!(BucketingPolicy::SideAware.level(price, Side::Bid), Decimal::new(100, 0));
+    /// assert_eq!(BucketingPolicy::SideAware.level(price, Side::Ask), Decimal::new(101, 0));
+    /// ```
+    pub fn level(self, price: Decimal, side: Side) -> Decimal {
+        match (self, side) {
+            (BucketingPolicy::Symmetric, _) => price.trunc(),
+            (BucketingPolicy::SideAware, Side::Bid) => price.floor(),
+            (BucketingPolicy::SideAware, Side::Ask) => price.ceil(),
+        }
+    }
+}
+
+/// How a `MarketDepthCache` aggregates and bounds its depth, passed to
+/// `MarketDepthCache::with_config`.
+///
+/// The default normalizes prices and truncates them into aggregated levels, keeping
+/// every level and no exact levels, as `MarketDepthCache::new` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MarketDepthCacheConfig {
+    /// How aggregated and exact price levels are canonicalized
+    pub price_normalization: PriceNormalization,
+    /// How prices are assigned to aggregated levels
+    pub bucketing_policy: BucketingPolicy,
+    /// The maximum number of aggregated levels kept per side, counted from the touch,
+    /// or `None` to keep them all
+    pub max_levels_per_side: Option<usize>,
+    /// Whether the exact, un-aggregated levels are maintained too
+    pub exact_levels: bool,
+}
+
+/// What a book does with an incoming price having more decimal places than it allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExcessPrecision {
+    /// Round the price half to even to the allowed number of decimal places
+    #[default]
+    Round,
+    /// Reject the order with `OrderBookError::PriceTooPrecise`
+    Reject,
+}
+
+/// A bound on the decimal places of the prices a book accepts.
+///
+/// Prices converted from `f64` can carry long tails of digits, and every such price
+/// would otherwise become a key of its own. A book configured with a `price_precision`
+/// (see `OrderBook::with_config`) stores every price at exactly `max_scale` decimal
+/// places, so keys stay predictable, and handles more precise prices as
+/// `excess_precision` says. Trailing zeros do not count as precision: `100.500` is
+/// accepted with a `max_scale` of 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PricePrecision {
+    /// The maximum number of decimal places of a price
+    pub max_scale: u32,
+    /// What happens to prices with more decimal places
+    pub excess_precision: ExcessPrecision,
+}
+
+impl PricePrecision {
+    /// Returns whether `price` has more significant decimal places than allowed.
+    pub fn is_exceeded_by(self, price: Decimal) -> bool {
+        price.normalize().scale() > self.max_scale
+    }
+}
+
+/// How an `OrderBook` canonicalizes prices and preallocates storage, passed to
+/// `OrderBook::with_config`.
+///
+/// The default normalizes prices without bounding their precision and preallocates
+/// nothing, as `OrderBook::new` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OrderBookConfig {
+    /// How incoming prices are canonicalized, unless `price_precision` is set
+    pub price_normalization: PriceNormalization,
+    /// The bound on the decimal places of incoming prices, if any
+    pub price_precision: Option<PricePrecision>,
+    /// The number of resting orders to preallocate for
+    pub order_capacity: usize,
+    /// The price of one tick, or `None` for `0.00000001`. Ignored without the
+    /// `tick-prices` feature
+    pub tick_size: Option<Decimal>,
+}
+
+/// The decimal precision an `f64` price is rounded to by `FloatPrecision`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatRounding {
+    /// A number of decimal places
+    Scale(u32),
+    /// A multiple of the instrument's tick size, e.g. `OrderBook::tick_size`, which
+    /// must be positive
+    Tick(Decimal),
+}
+
+impl fmt::Display for FloatRounding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FloatRounding::Scale(scale) => write!(formatter, "{scale} decimal places"),
+            FloatRounding::Tick(tick_size) => write!(formatter, "a tick of {tick_size}"),
+        }
+    }
+}
+
+/// How an `f64` price is turned into a `Decimal`, see `Order::with_f64_price`.
+///
+/// Binary floating-point numbers cannot represent most decimal prices, so a price
+/// computed in `f64` can carry noise in its last digits, e.g. `100.1 + 0.2`. The price
+/// is first converted to the shortest `Decimal` `f64` precision supports, which
+/// removes noise within that precision, then rounded half to even to `rounding`.
+/// Under `ExcessPrecision::Reject`, the strict mode, prices that the rounding would
+/// change are rejected instead, so a gateway learns that a client sent 100.123 to an
+/// instrument quoted in cents rather than trading at 100.12.
+///
+/// The default, used by `Order::new`, rounds to 8 decimal places, the finest price
+/// grid of the book's default tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatPrecision {
+    /// The precision prices are rounded to
+    pub rounding: FloatRounding,
+    /// What happens to prices that are not representable at that precision
+    pub excess_precision: ExcessPrecision,
+}
+
+impl Default for FloatPrecision {
+    fn default() -> Self {
+        FloatPrecision {
+            rounding: FloatRounding::Scale(8),
+            excess_precision: ExcessPrecision::Round,
+        }
+    }
+}
+
+impl FloatPrecision {
+    /// Converts an `f64` price to a `Decimal` at this precision.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The price to convert
+    ///
+    /// ## Returns
+    ///
+    /// The rounded price, `OrderBookError::UnrepresentablePrice` if `price` is not
+    /// finite, is out of the range of `Decimal` before or after rounding, or, in strict
+    /// mode, needs more precision than `rounding`, or `OrderBookError::InvalidTickSize`
+    /// if the tick size of `FloatRounding::Tick` is not positive
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{ExcessPrecision, FloatPrecision, FloatRounding};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let mut precision = FloatPrecision {
+    ///     rounding: FloatRounding::Tick(Decimal::new(5, 2)),
+    ///     excess_precision: ExcessPrecision::Round,
+    /// };
+    /// assert_eq!(precision.convert(100.1 + 0.2).unwrap(), Decimal::new(10030, 2));
+    /// assert_eq!(precision.convert(100.32).unwrap(), Decimal::new(10030, 2));
+    ///
+    /// precision.excess_precision = ExcessPrecision::Reject;
+    /// assert!(precision.convert(100.1 + 0.2).is_ok());
+    /// assert!(precision.convert(100.32).is_err());
+    /// ```
+    pub fn convert(self, price: f64) -> Result<Decimal, OrderBookError> {
+        let unrepresentable = |price| OrderBookError::UnrepresentablePrice {
+            price,
+            rounding: self.rounding,
+        };
+        let converted = Decimal::try_from(price).map_err(|_| unrepresentable(None))?;
+        let rounded = match self.rounding {
+            FloatRounding::Scale(scale) => converted.round_dp(scale),
+            FloatRounding::Tick(tick_size) => {
+                if tick_size <= Decimal::ZERO {
+                    return Err(OrderBookError::InvalidTickSize(tick_size));
+                }
+                // A price far larger than the tick has more ticks than `Decimal` holds
+                converted
+                    .checked_div(tick_size)
+                    .and_then(|ticks| ticks.round().checked_mul(tick_size))
+                    .ok_or_else(|| unrepresentable(Some(converted)))?
+            }
+        };
+        if self.excess_precision == ExcessPrecision::Reject && rounded != converted {
+            return Err(unrepresentable(Some(converted)));
+        }
+        Ok(rounded)
+    }
+}
+
+/// A payload attached by the caller to a resting order, see
+/// `OrderBook::insert_order_with_metadata`.
+pub type OrderMetadata = Arc<dyn Any + Send + Sync>;
+
+/// An order described from borrowed data, for `OrderBook::insert_order_ref`.
+///
+/// Gateways decoding orders from wire messages can fill a spec in place from the
+/// decoded fields and pass it by reference, and a payload shared across messages is
+/// borrowed rather than moved, so nothing owned has to be built per message.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderSpec<'a> {
+    /// The price level at which the order is placed
+    pub price: Decimal,
+    /// The quantity of the asset being bought or sold
+    pub quantity: u64,
+    /// Whether this is a buy (`Bid`) or sell (`Ask`) order
+    pub side: Side,
+    /// The payload to attach to the order, if any
+    pub metadata: Option<&'a OrderMetadata>,
+}
+
+impl<'a> From<&'a Order> for OrderSpec<'a> {
+    fn from(order: &'a Order) -> Self {
+        OrderSpec {
+            price: order.price,
+            quantity: order.quantity,
+            side: order.side,
+            metadata: None,
+        }
+    }
+}
+
 /// Represents a single order in the order book.
 ///
 /// Each order contains a price, quantity, and side (bid or ask).
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
 pub struct Order {
     /// The price level at which this order is placed (using fixed-point arithmetic)
     pub price: Decimal,
@@ -28,37 +340,1199 @@ pub struct Order {
 
 impl Order {
     /// Creates a new order with the given price, quantity, and side.
+    ///
+    /// The price is converted like `with_f64_price` does under the default
+    /// `FloatPrecision`, i.e. rounded half to even to 8 decimal places, so noise in the
+    /// digits beyond them never reaches the book. Prices from untrusted input, or for
+    /// an instrument with a coarser precision, should go through `with_f64_price`,
+    /// which returns an error instead of panicking and rounds to the instrument's
+    /// precision.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `price` is not finite or is out of the range of `Decimal`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let order = Order::new(0.1 + 0.2, 10, Side::Bid);
+    /// assert_eq!(order.price, Decimal::new(3, 1));
+    /// ```
     pub fn new(price: f64, quantity: u64, side: Side) -> Self {
+        Self::with_f64_price(price, quantity, side, FloatPrecision::default())
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a new order with an `f64` price rounded to a given precision.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The price, which may carry floating-point noise
+    /// * `quantity`: The quantity of the order
+    /// * `side`: The side of the order
+    /// * `precision`: How the price is rounded, and whether prices needing more
+    ///   precision are rejected
+    ///
+    /// ## Returns
+    ///
+    /// The order, or `OrderBookError::UnrepresentablePrice` if `precision` does not
+    /// accept `price`
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{ExcessPrecision, FloatPrecision, FloatRounding, Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let cents = FloatPrecision {
+    ///     rounding: FloatRounding::Scale(2),
+    ///     excess_precision: ExcessPrecision::Round,
+    /// };
+    /// let order = Order::with_f64_price(100.125, 10, Side::Bid, cents).unwrap();
+    /// assert_eq!(order.price, Decimal::new(10012, 2));
+    /// ```
+    pub fn with_f64_price(
+        price: f64,
+        quantity: u64,
+        side: Side,
+        precision: FloatPrecision,
+    ) -> Result<Self, OrderBookError> {
+        Ok(Self {
+            price: precision.convert(price)?,
+            quantity,
+            side,
+        })
+    }
+
+    /// Creates a new order from a decimal price string, without going through `f64`.
+    ///
+    /// The string is parsed exactly, so the order's price is precisely the one written.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The price, written as a decimal number (e.g. `"100.25"`)
+    /// * `quantity`: The quantity of the order
+    /// * `side`: The side of the order
+    ///
+    /// ## Returns
+    ///
+    /// The order, or an error if `price` is not a valid decimal number
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let order = Order::with_price_str("100.25", 10, Side::Bid).unwrap();
+    /// assert_eq!(order.price, Decimal::new(10025, 2));
+    /// assert!(Order::with_price_str("one hundred", 10, Side::Bid).is_err());
+    /// ```
+    pub fn with_price_str(
+        price: &str,
+        quantity: u64,
+        side: Side,
+    ) -> Result<Self, rust_decimal::Error> {
+        Ok(Self {
+            price: Decimal::from_str_exact(price)?,
+            quantity,
+            side,
+        })
+    }
+
+    /// Creates a new order priced at an integer number of ticks.
+    ///
+    /// The price is computed as `ticks × tick_size` in decimal arithmetic, which is the
+    /// natural representation for feeds and gateways that transmit integer prices.
+    ///
+    /// ## Arguments
+    ///
+    /// * `ticks`: The price expressed in ticks
+    /// * `tick_size`: The value of one tick (e.g. `0.01`)
+    /// * `quantity`: The quantity of the order
+    /// * `side`: The side of the order
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the product overflows the range of `Decimal`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let order = Order::from_ticks(10025, Decimal::new(1, 2), 10, Side::Ask);
+    /// assert_eq!(order.price, Decimal::new(10025, 2)); // 100.25
+    /// ```
+    pub fn from_ticks(ticks: i64, tick_size: Decimal, quantity: u64, side: Side) -> Self {
         Self {
-            price: Decimal::try_from(price).unwrap(),
+            price: Decimal::from(ticks) * tick_size,
             quantity,
             side,
         }
     }
 }
 
+/// Unique identifier assigned by the `OrderBook` to every inserted order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+pub struct OrderId(pub u64);
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+/// Unique identifier assigned by the `OrderBook` to every trade it prints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeId(pub u64);
+
+impl fmt::Display for TradeId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+/// An identifier chosen by the submitter of an order, used to detect resubmissions.
+///
+/// See `OrderBook::insert_order_with_client_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClientOrderId(pub u64);
+
+impl fmt::Display for ClientOrderId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+/// Identifier of a market participant, e.g. quoting through `OrderBook::submit_quote` or
+/// sending commands through a `Throttle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParticipantId(pub u64);
+
+impl fmt::Display for ParticipantId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+/// An opaque reference to a resting order, returned by `OrderBook::insert_order`.
+///
+/// The handle encodes the order's price, side, and storage slot, so that the book can
+/// cancel the order without looking its identifier up first. Once the order leaves
+/// the book (cancelled or filled), the handle becomes stale and operations using it
+/// fail instead of affecting whichever order reuses the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrderHandle {
+    pub(crate) order_id: OrderId,
+    pub(crate) price: Decimal,
+    pub(crate) side: Side,
+    pub(crate) slot: usize,
+    pub(crate) generation: u64,
+}
+
+impl OrderHandle {
+    /// Returns the identifier of the referenced order.
+    pub fn order_id(&self) -> OrderId {
+        self.order_id
+    }
+
+    /// Returns the exact price the referenced order was inserted at.
+    pub fn price(&self) -> Decimal {
+        self.price
+    }
+
+    /// Returns the side of the referenced order.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+}
+
 /// Represents an event published by the `OrderBook` when its state changes.
 ///
 /// This event is consumed by downstream services (like `MarketDepthCache`) to update
 /// their own state without blocking the core order book operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderEvent {
     /// The exact price level where the change occurred
+    #[cfg_attr(feature = "serde", serde(with = "rust_decimal::serde::str"))]
     pub price: Decimal,
-    /// The change in quantity at this price level (positive for additions)
-    pub quantity_delta: u64,
+    /// The change in quantity at this price level (positive for additions, negative for removals)
+    pub quantity_delta: i64,
+    /// The change in the number of orders at this price level
+    ///
+    /// `1` when an order starts resting, `-1` when an order leaves the book, and `0`
+    /// when a resting order is only reduced or partially executed, or when the event
+    /// was not published by a book that tracks orders.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub order_count_delta: i64,
     /// Whether this event affects the bid or ask side
     pub side: Side,
+    /// When the event was created, in nanoseconds since the Unix epoch
+    ///
+    /// Consumers compare it with the time they process the event to measure how far
+    /// behind the book they are. Zero means the creation time is unknown.
+    pub timestamp_nanos: u64,
+    /// Position of the event in the publishing book's event stream, starting from 1
+    ///
+    /// Zero means the event was not published by a book.
+    pub sequence: u64,
+}
+
+impl OrderEvent {
+    /// Creates an event stamped with the current time, without a sequence number or a
+    /// change in the number of orders.
+    ///
+    /// ## Arguments
+    ///
+    /// * `price`: The exact price level where the change occurred
+    /// * `quantity_delta`: The change in quantity at this price level
+    /// * `side`: The side the change affects
+    pub fn new(price: Decimal, quantity_delta: i64, side: Side) -> Self {
+        OrderEvent {
+            price,
+            quantity_delta,
+            order_count_delta: 0,
+            side,
+            timestamp_nanos: unix_timestamp_nanos(),
+            sequence: 0,
+        }
+    }
+}
+
+/// The collapse of a price level, when the last order resting at its exact price left
+/// the book.
+///
+/// The `OrderEvent` removing that order already brings the level's quantity to zero;
+/// this event spares consumers, such as level 2 displays deleting rows, from tracking
+/// the remaining quantity of every level to notice it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelRemoved {
+    /// The price of the removed level
+    pub price: Decimal,
+    /// The side of the removed level
+    pub side: Side,
+    /// The sequence number of the `OrderEvent` that emptied the level
+    pub sequence: u64,
+}
+
+/// An incoming order the book refused, recorded when rejections are enabled with
+/// `OrderBook::set_rejection_events`.
+///
+/// The error returned to the submitter only reaches them; this event lets audit logs
+/// and gateways see every attempted order, accepted or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRejected {
+    /// Why the order was refused
+    pub reason: OrderBookError,
+    /// The order as submitted
+    pub order: Order,
+    /// The sequence number of the last `OrderEvent` published before the rejection,
+    /// which places it in the book's event stream without consuming a number of its own
+    pub sequence: u64,
+}
+
+/// Returns the current time in nanoseconds since the Unix epoch.
+pub(crate) fn unix_timestamp_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// The best bid and ask prices of a book.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bbo {
+    /// The highest bid price, or `None` if there are no bids
+    pub best_bid: Option<Decimal>,
+    /// The lowest ask price, or `None` if there are no asks
+    pub best_ask: Option<Decimal>,
+}
+
+impl Bbo {
+    /// Returns the best bid as a `BidPrice`, if there are bids.
+    pub fn bid_price(&self) -> Option<BidPrice> {
+        self.best_bid.map(BidPrice)
+    }
+
+    /// Returns the best ask as an `AskPrice`, if there are asks.
+    pub fn ask_price(&self) -> Option<AskPrice> {
+        self.best_ask.map(AskPrice)
+    }
+}
+
+/// A price on the bid side.
+///
+/// `BidPrice` and `AskPrice` carry their side in their type, so strategy code cannot
+/// compare a bid with an ask as if they were on the same side, nor pass one where the
+/// other is expected: neither converts implicitly from a `Decimal`, and comparisons
+/// between the two sides have dedicated names (`crosses`, `spread_to`). A better bid
+/// is a higher one.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{AskPrice, BidPrice, Side};
+/// use rust_decimal::Decimal;
+///
+/// let bid = BidPrice(Decimal::new(9950, 2));
+/// assert!(BidPrice(Decimal::new(9975, 2)).is_better_than(bid));
+/// assert!(!bid.crosses(AskPrice(Decimal::new(100, 0))));
+/// assert_eq!(bid.spread_to(AskPrice(Decimal::new(100, 0))), Decimal::new(50, 2));
+///
+/// let order = bid.order(10);
+/// assert_eq!((order.price, order.side), (Decimal::new(9950, 2), Side::Bid));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BidPrice(pub Decimal);
+
+impl BidPrice {
+    /// Returns whether this bid is strictly better, i.e. higher, than `other`.
+    pub fn is_better_than(self, other: BidPrice) -> bool {
+        self.0 > other.0
+    }
+
+    /// Returns whether this bid would execute against `ask`, i.e. is at or above it.
+    pub fn crosses(self, ask: AskPrice) -> bool {
+        self.0 >= ask.0
+    }
+
+    /// Returns `ask` minus this bid, negative if they cross.
+    pub fn spread_to(self, ask: AskPrice) -> Decimal {
+        ask.0 - self.0
+    }
+
+    /// Returns a bid order of `quantity` at this price.
+    pub fn order(self, quantity: u64) -> Order {
+        Order {
+            price: self.0,
+            quantity,
+            side: Side::Bid,
+        }
+    }
+}
+
+impl fmt::Display for BidPrice {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl From<BidPrice> for Decimal {
+    fn from(price: BidPrice) -> Self {
+        price.0
+    }
+}
+
+/// A price on the ask side.
+///
+/// The ask side's counterpart of `BidPrice`: a better ask is a lower one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AskPrice(pub Decimal);
+
+impl AskPrice {
+    /// Returns whether this ask is strictly better, i.e. lower, than `other`.
+    pub fn is_better_than(self, other: AskPrice) -> bool {
+        self.0 < other.0
+    }
+
+    /// Returns whether this ask would execute against `bid`, i.e. is at or below it.
+    pub fn crosses(self, bid: BidPrice) -> bool {
+        bid.crosses(self)
+    }
+
+    /// Returns this ask minus `bid`, negative if they cross.
+    pub fn spread_to(self, bid: BidPrice) -> Decimal {
+        bid.spread_to(self)
+    }
+
+    /// Returns an ask order of `quantity` at this price.
+    pub fn order(self, quantity: u64) -> Order {
+        Order {
+            price: self.0,
+            quantity,
+            side: Side::Ask,
+        }
+    }
+}
+
+impl fmt::Display for AskPrice {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl From<AskPrice> for Decimal {
+    fn from(price: AskPrice) -> Self {
+        price.0
+    }
+}
+
+/// A change of the best bid or ask price, i.e. of the touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BboChanged {
+    /// The best bid and ask before the change
+    pub old: Bbo,
+    /// The best bid and ask after the change
+    pub new: Bbo,
 }
 
-/// Type alias for a price level in the order book.
+impl BboChanged {
+    /// Returns the change from `old` to `new`, or `None` if they are the same.
+    pub fn between(old: Bbo, new: Bbo) -> Option<Self> {
+        (old != new).then_some(BboChanged { old, new })
+    }
+}
+
+/// The result of inserting an order into the `OrderBook`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsertOutcome {
+    /// Handle to the newly resting order, usable for $O(1)$ cancellation
+    pub handle: OrderHandle,
+    /// The event to forward to downstream consumers such as the `MarketDepthCache`
+    pub event: OrderEvent,
+    /// The change of the best bid or ask caused by the insertion, if any
+    pub bbo_changed: Option<BboChanged>,
+    /// The quantity the order was submitted with, if it exceeded `i64::MAX`, the largest
+    /// an `OrderEvent` can carry, and the order rests with `i64::MAX` instead
+    ///
+    /// Only the infallible entry points, e.g. `insert_order`, saturate quantities; the
+    /// fallible ones, e.g. `try_insert_order`, return `OrderBookError::QuantityTooLarge`.
+    pub saturated_from: Option<u64>,
+}
+
+/// The result of replacing a resting order with `OrderBook::replace_order`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceOutcome {
+    /// Handle to the replacement order
+    pub handle: OrderHandle,
+    /// The event removing the original order's quantity
+    pub removed: OrderEvent,
+    /// The event adding the replacement order's quantity
+    pub added: OrderEvent,
+    /// The change of the best bid or ask caused by the replacement, if any
+    pub bbo_changed: Option<BboChanged>,
+}
+
+/// The result of submitting a two-sided quote with `OrderBook::submit_quote`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteOutcome {
+    /// Handle to the new bid, or `None` if the quote has no bid
+    pub bid: Option<OrderHandle>,
+    /// Handle to the new ask, or `None` if the quote has no ask
+    pub ask: Option<OrderHandle>,
+    /// The events removing the previous quote, followed by those adding the new one
+    pub events: Vec<OrderEvent>,
+}
+
+/// The result of matching an incoming order with `OrderBook::match_order`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchOutcome {
+    /// The identifier assigned to the incoming order
+    pub order_id: OrderId,
+    /// Handle to the unfilled remainder, if any of the order was left to rest
+    pub handle: Option<OrderHandle>,
+    /// The events for every resting order consumed and for the resting remainder, in order
+    ///
+    /// Quotes pulled by a triggered protection contribute their cancellations as well.
+    /// Empty when the order was matched with `OrderBook::match_order_into`, which appends
+    /// the events to the caller's buffer instead.
+    pub events: Vec<OrderEvent>,
+    /// The quote protections triggered by the executions of this order
+    pub protections_triggered: Vec<ProtectionTriggered>,
+    /// Set when this order would have traded outside the price bands and halted the book
+    pub state_change: Option<TradingStateChange>,
+    /// The cumulative executions of the incoming order
+    pub fills: FillSummary,
+    /// The change of the best bid or ask caused by the order, if any
+    pub bbo_changed: Option<BboChanged>,
+    /// The stop orders released by the trades of this order, and by those of the stops
+    /// it released in turn, in release order
+    ///
+    /// Their events follow the incoming order's, and the protections and state change
+    /// their executions trigger are reported with the incoming order's.
+    pub stops_triggered: Vec<OrderId>,
+    /// The unfilled quantity left to rest, if it exceeded `i64::MAX` and the remainder
+    /// rests with `i64::MAX` instead, as in `InsertOutcome::saturated_from`
+    pub saturated_from: Option<u64>,
+}
+
+/// The result of submitting an order with `OrderBook::submit`, with its fills.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitResult {
+    /// The identifier assigned to the order
+    pub order_id: OrderId,
+    /// Every trade the order took part in during the submission, in execution order
+    ///
+    /// These include the executions of its resting remainder against the stop orders
+    /// the submission released, in which the order is the maker.
+    pub fills: Vec<Trade>,
+    /// The quantity of the order left resting once the submission completed, 0 if it
+    /// was filled in full
+    pub resting_remainder: u64,
+    /// Handle to the resting remainder, if any
+    pub handle: Option<OrderHandle>,
+    /// The events for downstream consumers, as in `MatchOutcome::events`
+    pub events: Vec<OrderEvent>,
+}
+
+/// The cumulative executions of an order across its partial fills.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FillSummary {
+    /// Total quantity executed so far
+    pub filled_quantity: u64,
+    /// Total value executed so far, the sum of price times quantity over all fills
+    pub filled_notional: Decimal,
+}
+
+impl FillSummary {
+    /// Returns the quantity-weighted average execution price, or `None` if nothing has
+    /// been filled.
+    pub fn average_fill_price(&self) -> Option<Decimal> {
+        (self.filled_quantity > 0)
+            .then(|| self.filled_notional / Decimal::from(self.filled_quantity))
+    }
+
+    /// Adds a fill of `quantity` at `price`.
+    pub(crate) fn record(&mut self, price: Decimal, quantity: u64) {
+        self.filled_quantity += quantity;
+        self.filled_notional += price * Decimal::from(quantity);
+    }
+}
+
+/// Whether the book is matching incoming orders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TradingState {
+    /// Incoming orders are matched against resting liquidity
+    #[default]
+    Continuous,
+    /// Matching is suspended: incoming orders rest until trading resumes, as in an auction call
+    Halted,
+}
+
+/// An operation on an `OrderBook`, applied with `OrderBook::apply`.
 ///
-/// Maps each price (`Decimal`) to a list of orders at that price.
-/// Orders within a price level maintain time priority (FIFO).
-pub type ExactPriceLevelMap = BTreeMap<Decimal, Vec<Order>>;
+/// Every state change the book supports can be expressed as a command, so a sequence
+/// of commands is enough to journal, replay, or fuzz a book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Rest an order without matching, as `OrderBook::insert_order` does
+    Insert(Order),
+    /// Rest an order under a client order identifier, as
+    /// `OrderBook::insert_order_with_client_id` does
+    InsertWithClientId(Order, ClientOrderId),
+    /// Match an order and rest the remainder, as `OrderBook::match_order` does
+    Match(Order),
+    /// Cancel a resting order, as `OrderBook::cancel_order` does
+    Cancel(OrderId),
+    /// Change the price and quantity of a resting order
+    ///
+    /// Lowering the quantity at the same price reduces the order in place, keeping its
+    /// time priority; any other change replaces it, as `OrderBook::replace_order` does.
+    /// A quantity of zero cancels the order.
+    Amend {
+        /// The identifier of the order to amend
+        order_id: OrderId,
+        /// The new price
+        price: Decimal,
+        /// The new remaining quantity
+        quantity: u64,
+    },
+    /// Cancel every resting order
+    Clear,
+    /// Switch between continuous trading and a halt
+    SetState(TradingState),
+    /// Set the book's clock, as `OrderBook::set_time` does
+    SetTime(u64),
+    /// Rest a day order without matching, as `OrderBook::insert_day_order` does
+    InsertDay(Order),
+    /// Match a day order and rest the remainder, as `OrderBook::match_day_order` does
+    MatchDay(Order),
+    /// Close the trading session, as `OrderBook::end_session` does
+    EndSession,
+}
 
-/// Type alias for aggregated market depth cache.
+/// How much of an iceberg order inserted with `OrderBook::insert_iceberg` is displayed,
+/// initially and after every reload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayQuantity {
+    /// Every slice displays the same quantity
+    Fixed(u64),
+    /// Every slice displays a quantity drawn uniformly between the bounds, inclusive,
+    /// so the reloads are harder to detect
+    Random {
+        /// The smallest slice
+        min: u64,
+        /// The largest slice
+        max: u64,
+    },
+}
+
+/// Which order at a price level an incoming order executes against first.
 ///
-/// Maps each aggregated price level (`Decimal`) to the total quantity (`u64`)
-/// available at that level across all individual orders.
+/// Orders always rest at their level in arrival order, which snapshots and queue
+/// positions report; the policy only decides the order in which `match_order` allocates
+/// an incoming order's quantity among them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PriorityPolicy {
+    /// The oldest order first, i.e. price-time priority
+    #[default]
+    Fifo,
+    /// The order with the largest remaining quantity first, the oldest among equals
+    SizePriority,
+    /// The newest order first
+    Lifo,
+}
+
+/// What happens to event sequence numbers when a trading session ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SequencePolicy {
+    /// The next session's events are numbered from 1 again
+    ///
+    /// Consumers tracking sequence numbers, such as a `MarketDepthCache`, only move
+    /// forward, so they must be cleared or rebuilt when a new session opens.
+    Reset,
+    /// The next session's events continue the previous session's numbering
+    #[default]
+    Roll,
+}
+
+/// How the book assigns identifiers to the orders it accepts or to the trades it prints.
+///
+/// Order and trade identifiers are generated separately, each by its own generator.
+/// Identifiers only need to be unique within the book, but replays and distributed
+/// deployments often need more: identifiers unique across several books, identifiers
+/// that sort by time, or identifiers chosen by an external sequencer.
+#[derive(Clone, Default)]
+pub enum IdGenerator {
+    /// Sequential identifiers from a counter of the book's own, starting from 1
+    ///
+    /// The counter is part of checkpoints, so a restored book keeps numbering where
+    /// the original left off.
+    #[default]
+    Counter,
+    /// Sequential identifiers drawn from an atomic counter shared with other books, so
+    /// identifiers are unique across all of them
+    ///
+    /// Each identifier is the counter's value before it is incremented.
+    Shared(Arc<AtomicU64>),
+    /// Time-ordered identifiers in the snowflake layout: from the most significant
+    /// bit, 41 bits of milliseconds since `epoch_millis` read from the book's clock, 10
+    /// bits of `node_id`, and 12 bits of sequence within the millisecond
+    ///
+    /// Books with distinct node identifiers generate distinct identifiers. Identifiers
+    /// always increase, even if the clock goes back or more than 4096 identifiers are
+    /// needed within a millisecond, in which case generation runs ahead of the clock. With a
+    /// manual clock, generated identifiers are reproducible.
+    Snowflake {
+        /// The identifier of the generating book, of which the low 10 bits are used
+        node_id: u16,
+        /// The Unix time, in milliseconds, that timestamps are counted from
+        epoch_millis: u64,
+    },
+    /// Identifiers returned by a function supplied by the caller, e.g. one allocating
+    /// them from an external sequencer
+    ///
+    /// A function generating order identifiers must not return the identifier of an
+    /// order resting in the book, and any function must return the same identifiers
+    /// again for a replay to reproduce the book.
+    Custom(Arc<dyn Fn() -> u64 + Send + Sync>),
+}
+
+impl fmt::Debug for IdGenerator {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdGenerator::Counter => formatter.write_str("Counter"),
+            IdGenerator::Shared(counter) => formatter.debug_tuple("Shared").field(counter).finish(),
+            IdGenerator::Snowflake {
+                node_id,
+                epoch_millis,
+            } => formatter
+                .debug_struct("Snowflake")
+                .field("node_id", node_id)
+                .field("epoch_millis", epoch_millis)
+                .finish(),
+            IdGenerator::Custom(_) => formatter.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A change of the book's trading session, recorded for observers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A session started
+    Opened {
+        /// The number of the session, starting from 1
+        session: u64,
+        /// When the session started, in nanoseconds since the Unix epoch
+        timestamp_nanos: u64,
+    },
+    /// A session ended
+    Closed {
+        /// The number of the session
+        session: u64,
+        /// When the session ended, in nanoseconds since the Unix epoch
+        timestamp_nanos: u64,
+        /// The number of day orders cancelled at the close
+        expired_orders: usize,
+        /// The number of trades printed during the session
+        trade_count: usize,
+    },
+}
+
+/// The result of closing a trading session with `OrderBook::end_session`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    /// The number of the closed session
+    pub session: u64,
+    /// The cancellation events of the day orders that expired at the close
+    pub expired: Vec<OrderEvent>,
+    /// The session's trade log, oldest first, which the book no longer holds
+    pub trades: Vec<Trade>,
+    /// The sequence number of the session's last event
+    pub last_event_sequence: u64,
+}
+
+/// The result of publishing a settlement price with `OrderBook::settle_reference_price`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementOutcome {
+    /// The settlement price every trade printed at
+    pub price: Decimal,
+    /// The trades between settlement orders, in execution order
+    pub trades: Vec<Trade>,
+    /// The identifier, side, and unfilled quantity of every settlement order that
+    /// expired without a full fill, buys first, each side in arrival order
+    pub unfilled: Vec<(OrderId, Side, u64)>,
+}
+
+/// What `OrderBook::compact` released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// The book's estimated heap usage before compaction, as `approx_memory_bytes`
+    /// reports it
+    pub bytes_before: usize,
+    /// The book's estimated heap usage after compaction
+    pub bytes_after: usize,
+    /// The number of vacant order slots released
+    pub released_slots: usize,
+    /// The number of resting orders moved to another slot, whose handles are now stale
+    pub relocated_orders: usize,
+}
+
+impl CompactionReport {
+    /// Returns the estimated number of heap bytes compaction released.
+    pub fn bytes_reclaimed(&self) -> usize {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Limit-up/limit-down bands around the reference price, outside which trades may not print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LuldBands {
+    /// Half-width of the band as a fraction of the reference price (e.g. 0.05 for ±5%)
+    pub band_fraction: Decimal,
+}
+
+impl LuldBands {
+    /// Returns the `(lower, upper)` band around `reference_price`.
+    pub fn around(&self, reference_price: Decimal) -> (Decimal, Decimal) {
+        let half_width = reference_price * self.band_fraction;
+        (reference_price - half_width, reference_price + half_width)
+    }
+}
+
+/// Published when the book changes its `TradingState` because of a band breach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradingStateChange {
+    /// The state the book is now in
+    pub state: TradingState,
+    /// The reference price the bands were computed from
+    pub reference_price: Decimal,
+    /// The price the rejected trade would have printed at
+    pub breach_price: Decimal,
+    /// The lowest price allowed by the bands
+    pub lower_band: Decimal,
+    /// The highest price allowed by the bands
+    pub upper_band: Decimal,
+}
+
+/// Limits protecting a market maker from being run over while quoting.
+///
+/// Configured per participant with `OrderBook::set_quote_protection`: once the
+/// quantity executed against the participant's quotes within `window` reaches
+/// `executed_quantity_limit`, all of the participant's remaining quotes are pulled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteProtection {
+    /// The executed quantity at which the participant's quotes are pulled
+    pub executed_quantity_limit: u64,
+    /// How far back executions count towards the limit
+    pub window: Duration,
+}
+
+/// Published when a participant's quote protection pulls their quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectionTriggered {
+    /// The participant whose quotes were pulled
+    pub participant_id: ParticipantId,
+    /// The quantity executed against the participant's quotes within the window
+    pub executed_quantity: u64,
+    /// The orders that were still resting and have been cancelled
+    pub pulled_orders: Vec<OrderId>,
+}
+
+/// A difference between the same price level of two books, from `OrderBook::diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelDiff {
+    /// The level only exists in the other book
+    Added {
+        /// The price of the level
+        price: Decimal,
+        /// The total quantity of the level in the other book
+        quantity: u64,
+    },
+    /// The level only exists in this book
+    Removed {
+        /// The price of the level
+        price: Decimal,
+        /// The total quantity of the level in this book
+        quantity: u64,
+    },
+    /// The level exists in both books with different total quantities
+    Changed {
+        /// The price of the level
+        price: Decimal,
+        /// The total quantity of the level in this book
+        from: u64,
+        /// The total quantity of the level in the other book
+        to: u64,
+    },
+}
+
+/// The level differences between two books, per side and in ascending price order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDiff {
+    /// Differences between the bid sides
+    pub bids: Vec<LevelDiff>,
+    /// Differences between the ask sides
+    pub asks: Vec<LevelDiff>,
+}
+
+impl BookDiff {
+    /// Returns `true` if both books have identical levels.
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+}
+
+/// A trade printed when an incoming order executes against a resting order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    /// The execution price, which is always the resting order's price
+    #[cfg_attr(feature = "serde", serde(with = "rust_decimal::serde::str"))]
+    pub price: Decimal,
+    /// The executed quantity
+    pub quantity: u64,
+    /// The side of the incoming (aggressive) order
+    pub aggressor_side: Side,
+    /// The trade's identifier
+    pub trade_id: TradeId,
+    /// The identifier of the resting (passive) order
+    pub maker_order_id: OrderId,
+    /// The identifier of the incoming (aggressive) order
+    pub taker_order_id: OrderId,
+}
+
+/// The estimated cost of executing an order against the currently resting liquidity.
+///
+/// Slippage is expressed in basis points and is positive when the execution is worse
+/// than the reference price: above it for buys, below it for sells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impact {
+    /// Volume-weighted average price of the simulated fills
+    pub avg_fill_price: Decimal,
+    /// Slippage of the average fill price versus the mid price, if both sides are quoted
+    /// and the mid is not 0
+    pub slippage_bps_vs_mid: Option<Decimal>,
+    /// Slippage of the average fill price versus the best opposite price, if it is not 0
+    pub slippage_bps_vs_touch: Option<Decimal>,
+    /// Number of price levels the order would trade through, fully or partially
+    pub depth_consumed_levels: usize,
+    /// The quantity that could be filled, lower than requested if the book is too thin
+    pub filled_quantity: u64,
+}
+
+/// A market-by-order (level 3) event describing a change to an individual order.
+///
+/// Unlike `OrderEvent`, which describes the change of the aggregate quantity at a price,
+/// these events identify the order affected, so consumers can maintain a full
+/// order-by-order replica of the book the way consumers of ITCH-style feeds do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketByOrderEvent {
+    /// A new order started resting in the book
+    Added {
+        /// The identifier of the new order
+        order_id: OrderId,
+        /// The order's price
+        price: Decimal,
+        /// The order's quantity
+        quantity: u64,
+        /// The order's side
+        side: Side,
+    },
+    /// A resting order executed against an incoming order
+    Executed {
+        /// The identifier of the resting order
+        order_id: OrderId,
+        /// The execution price
+        price: Decimal,
+        /// The resting order's side
+        side: Side,
+        /// The quantity executed
+        executed_quantity: u64,
+        /// The quantity left resting after the execution
+        remaining_quantity: u64,
+        /// The identifier of the trade printed by the execution
+        trade_id: TradeId,
+        /// The identifier of the incoming order
+        taker_order_id: OrderId,
+    },
+    /// A resting order was cancelled, fully or partially
+    Cancelled {
+        /// The identifier of the resting order
+        order_id: OrderId,
+        /// The order's price
+        price: Decimal,
+        /// The order's side
+        side: Side,
+        /// The quantity cancelled
+        cancelled_quantity: u64,
+        /// The quantity left resting after the cancellation
+        remaining_quantity: u64,
+    },
+    /// A resting order was replaced by a new order, losing its time priority
+    Replaced {
+        /// The identifier of the replaced order
+        order_id: OrderId,
+        /// The identifier of the replacement order
+        new_order_id: OrderId,
+        /// The replacement's price
+        price: Decimal,
+        /// The replacement's quantity
+        quantity: u64,
+        /// The side of both orders
+        side: Side,
+    },
+}
+
+/// The position of a resting order within the queue of its price level.
+///
+/// Used by market makers to estimate how much volume has to trade at the price
+/// before their order can be filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuePosition {
+    /// Number of orders at the same price with higher time priority
+    pub orders_ahead: usize,
+    /// Total remaining quantity of those orders
+    pub quantity_ahead: u64,
+}
+
+/// Type alias for the exact-price depth maintained by the order book.
+///
+/// Maps each exact price (`Decimal`) at which orders rest to the total remaining
+/// quantity (`u64`) of those orders, as returned by `OrderBook::exact_price_levels`.
+/// Prices are the book's own, so two orders share an entry only if they rest at
+/// the same price.
+pub type ExactPriceLevelMap = BTreeMap<Decimal, u64>;
+
+/// Type alias for the bucketed market depth of the cache.
+///
+/// Maps each aggregated price level (`Decimal`) of a `MarketDepthCache` to the total
+/// quantity (`u64`) available at that level across all individual orders, whose
+/// exact prices may differ; see `ExactPriceLevelMap` for the book's exact prices.
 pub type AggregatedDepthMap = BTreeMap<Decimal, u64>;
+
+/// The total quantity and number of orders resting at an aggregated price level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelInfo {
+    /// Total remaining quantity of the orders at the level
+    pub quantity: u64,
+    /// Number of orders at the level
+    pub order_count: u64,
+}
+
+/// Type alias for aggregated market depth with order counts.
+///
+/// Maps each aggregated price level (`Decimal`) to the `LevelInfo` of the orders
+/// available at that level.
+pub type AggregatedLevelMap = BTreeMap<Decimal, LevelInfo>;
+
+/// A change of the quantity at a level watched with `MarketDepthCache::watch_price`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelAlert {
+    /// The aggregated price level
+    pub price: Decimal,
+    /// The side of the level
+    pub side: Side,
+    /// The quantity when the watch last fired, or when it was registered
+    pub previous_quantity: u64,
+    /// The quantity now
+    pub quantity: u64,
+}
+
+/// A level at which a mirrored book and the venue's snapshot disagree, as reported by
+/// `MarketDepthCache::drift_report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelDrift {
+    /// The aggregated price level
+    pub price: Decimal,
+    /// The quantity the cache holds at the level, 0 if it holds none
+    pub cached_quantity: u64,
+    /// The quantity the venue reports at the level, 0 if it reports none
+    pub exchange_quantity: u64,
+}
+
+impl LevelDrift {
+    /// Returns how far the cached quantity is from the venue's, in either direction.
+    pub fn absolute_difference(&self) -> u64 {
+        self.cached_quantity.abs_diff(self.exchange_quantity)
+    }
+}
+
+/// How far one side of a `MarketDepthCache` has drifted from the venue's snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriftReport {
+    /// The side compared
+    pub side: Side,
+    /// The levels whose quantities differ, best first
+    pub levels: Vec<LevelDrift>,
+    /// The number of levels present on either side of the comparison
+    pub compared_levels: usize,
+    /// The total quantity the cache holds on the side
+    pub cached_quantity: u64,
+    /// The total quantity the venue reports on the side
+    pub exchange_quantity: u64,
+    /// The sum of the absolute differences of every level
+    pub absolute_difference: u64,
+}
+
+impl DriftReport {
+    /// Returns whether the cache matches the venue's snapshot at every level.
+    pub fn is_consistent(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Returns the sum of the absolute level differences relative to the venue's total
+    /// quantity, e.g. to alert once it exceeds a threshold.
+    ///
+    /// `0.0` means no drift. A side the venue reports empty yields `0.0` if the cache is
+    /// empty too, and infinity otherwise.
+    pub fn drift_ratio(&self) -> f64 {
+        match (self.absolute_difference, self.exchange_quantity) {
+            (0, _) => 0.0,
+            (_, 0) => f64::INFINITY,
+            (difference, total) => difference as f64 / total as f64,
+        }
+    }
+}
+
+/// A copy of a `MarketDepthCache`'s aggregated depth, as returned by
+/// `MarketDepthCache::snapshot`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+pub struct DepthSnapshot {
+    /// The cache's last applied sequence number when the snapshot was taken
+    pub sequence: u64,
+    /// Aggregated bid levels and their quantities
+    #[cfg_attr(feature = "rkyv", with(rkyv::with::AsVec))]
+    pub bids: AggregatedDepthMap,
+    /// Aggregated ask levels and their quantities
+    #[cfg_attr(feature = "rkyv", with(rkyv::with::AsVec))]
+    pub asks: AggregatedDepthMap,
+}
+
+/// A market-by-order event with its position in the stream of a book's market-by-order
+/// events, as returned by `OrderBook::take_sequenced_market_by_order_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencedMarketByOrderEvent {
+    /// The position of the event in the stream, starting from 1
+    pub sequence: u64,
+    /// The event
+    pub event: MarketByOrderEvent,
+}
+
+/// A copy of the orders resting in an `OrderBook`, as returned by `OrderBook::snapshot`.
+///
+/// `OrderBook::from_snapshot` restores a book resting the same orders, with the same
+/// identifiers and time priority.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    archive(check_bytes)
+)]
+pub struct BookSnapshot {
+    /// The book's last event sequence number when the snapshot was taken
+    pub sequence: u64,
+    /// The identifier the book would assign to its next order
+    pub next_order_id: u64,
+    /// Resting bids, best price first and in time priority within a price
+    pub bids: Vec<(OrderId, Order)>,
+    /// Resting asks, best price first and in time priority within a price
+    pub asks: Vec<(OrderId, Order)>,
+}
+
+/// The resting orders of an `OrderBook` with the position of its market-by-order stream,
+/// as returned by `OrderBook::replication_snapshot`.
+///
+/// A `FollowerBook` restored from it applies the stream's events after
+/// `market_by_order_sequence` and ignores those up to it, which the snapshot already
+/// reflects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationSnapshot {
+    /// The resting orders and the order and event counters
+    pub book: BookSnapshot,
+    /// The sequence number of the last market-by-order event the orders reflect
+    pub market_by_order_sequence: u64,
+}
+
+/// The state of an `OrderBook` at a checkpoint of its write-ahead log.
+///
+/// Beyond the resting orders of a `BookSnapshot`, a checkpoint holds the state that the
+/// commands logged after it depend on, so that replaying them on the restored book
+/// gives the same book as replaying the whole log. The trade log and the client order
+/// identifiers of orders that have left the book are not part of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The resting orders and the order and event counters
+    pub book: BookSnapshot,
+    /// Whether incoming orders were being matched
+    pub trading_state: TradingState,
+    /// The time of the book's manual clock, or `None` for the system's wall clock
+    pub manual_time_nanos: Option<u64>,
+    /// The number of the trading session
+    pub session: u64,
+    /// The resting day orders, in identifier order
+    pub day_orders: Vec<OrderId>,
+    /// The client order identifier of each resting order submitted with one, in order
+    /// identifier order
+    pub client_order_ids: Vec<(OrderId, ClientOrderId)>,
+    /// One past the highest trade identifier assigned, which the counter assigns next
+    pub next_trade_id: u64,
+}
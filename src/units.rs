@@ -0,0 +1,225 @@
+//! Typed prices and quantities.
+//!
+//! The book's signatures take prices as `Decimal` and quantities as `u64`, and as the
+//! API grows, calls passing several of them positionally get easier to get wrong.
+//! `Price` and `Quantity` give each its own type, convert from and into the raw
+//! representations with `From`, and only offer arithmetic that reports overflow: the
+//! operators panic on it, like integer arithmetic in debug builds but in every build,
+//! and the `checked_` methods return `None` instead.
+
+use crate::types::{AskPrice, BidPrice, Order, Side};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Sub};
+
+/// A price, in the book's fixed-point representation.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::{Price, Quantity};
+/// use rust_decimal::Decimal;
+///
+/// let price = Price::from(Decimal::new(10050, 2));
+/// assert_eq!(price + Price::from(Decimal::new(25, 2)), Price::from(Decimal::new(10075, 2)));
+/// assert_eq!(price.notional(Quantity(4)), Some(Decimal::new(402, 0)));
+/// assert_eq!(price.to_string(), "100.50");
+/// assert_eq!(Price::from(Decimal::MAX).checked_add(price), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Price(pub Decimal);
+
+impl Price {
+    /// The price zero.
+    pub const ZERO: Price = Price(Decimal::ZERO);
+
+    /// Returns the sum of two prices, or `None` if it overflows the range of `Decimal`.
+    pub fn checked_add(self, other: Price) -> Option<Price> {
+        self.0.checked_add(other.0).map(Price)
+    }
+
+    /// Returns the difference of two prices, or `None` if it overflows the range of
+    /// `Decimal`.
+    pub fn checked_sub(self, other: Price) -> Option<Price> {
+        self.0.checked_sub(other.0).map(Price)
+    }
+
+    /// Returns the value of `quantity` at this price, or `None` if it overflows the
+    /// range of `Decimal`.
+    pub fn notional(self, quantity: Quantity) -> Option<Decimal> {
+        self.0.checked_mul(Decimal::from(quantity.0))
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+
+    /// Panics if the sum overflows the range of `Decimal`.
+    fn add(self, other: Price) -> Price {
+        self.checked_add(other).expect("price overflow")
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    /// Panics if the difference overflows the range of `Decimal`.
+    fn sub(self, other: Price) -> Price {
+        self.checked_sub(other).expect("price overflow")
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl From<Decimal> for Price {
+    fn from(price: Decimal) -> Self {
+        Price(price)
+    }
+}
+
+impl From<Price> for Decimal {
+    fn from(price: Price) -> Self {
+        price.0
+    }
+}
+
+impl From<BidPrice> for Price {
+    fn from(price: BidPrice) -> Self {
+        Price(price.0)
+    }
+}
+
+impl From<AskPrice> for Price {
+    fn from(price: AskPrice) -> Self {
+        Price(price.0)
+    }
+}
+
+/// A quantity of the traded asset.
+///
+/// ## Examples
+///
+/// ```
+/// use order_book::Quantity;
+///
+/// let quantities = [Quantity(10), Quantity(20)];
+/// assert_eq!(quantities.into_iter().sum::<Quantity>(), Quantity(30));
+/// assert_eq!(Quantity(10) - Quantity(4), Quantity(6));
+/// assert_eq!(Quantity(4).checked_sub(Quantity(10)), None);
+/// assert_eq!(Quantity(u64::MAX).saturating_add(Quantity(1)), Quantity(u64::MAX));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Quantity(pub u64);
+
+impl Quantity {
+    /// The quantity zero.
+    pub const ZERO: Quantity = Quantity(0);
+
+    /// Returns the sum of two quantities, or `None` if it overflows.
+    pub fn checked_add(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_add(other.0).map(Quantity)
+    }
+
+    /// Returns the difference of two quantities, or `None` if `other` is larger.
+    pub fn checked_sub(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_sub(other.0).map(Quantity)
+    }
+
+    /// Returns the sum of two quantities, capped at `u64::MAX`.
+    pub fn saturating_add(self, other: Quantity) -> Quantity {
+        Quantity(self.0.saturating_add(other.0))
+    }
+
+    /// Returns the difference of two quantities, floored at zero.
+    pub fn saturating_sub(self, other: Quantity) -> Quantity {
+        Quantity(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Add for Quantity {
+    type Output = Quantity;
+
+    /// Panics if the sum overflows.
+    fn add(self, other: Quantity) -> Quantity {
+        self.checked_add(other).expect("quantity overflow")
+    }
+}
+
+impl Sub for Quantity {
+    type Output = Quantity;
+
+    /// Panics if `other` is larger.
+    fn sub(self, other: Quantity) -> Quantity {
+        self.checked_sub(other).expect("quantity underflow")
+    }
+}
+
+impl Sum for Quantity {
+    /// Panics if the total overflows.
+    fn sum<I: Iterator<Item = Quantity>>(quantities: I) -> Quantity {
+        quantities.fold(Quantity::ZERO, Add::add)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl From<u64> for Quantity {
+    fn from(quantity: u64) -> Self {
+        Quantity(quantity)
+    }
+}
+
+impl From<Quantity> for u64 {
+    fn from(quantity: Quantity) -> Self {
+        quantity.0
+    }
+}
+
+impl Order {
+    /// Creates a new order from a typed price and quantity.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use order_book::{Order, Price, Quantity, Side};
+    /// use rust_decimal::Decimal;
+    ///
+    /// let order = Order::priced(Price(Decimal::new(100, 0)), Quantity(10), Side::Ask);
+    /// assert_eq!(order.price, Decimal::new(100, 0));
+    /// assert_eq!(order.quantity, 10);
+    /// ```
+    pub fn priced(price: Price, quantity: Quantity, side: Side) -> Self {
+        Order {
+            price: price.0,
+            quantity: quantity.0,
+            side,
+        }
+    }
+}
+
+/// Adds a signed delta to an aggregated total, saturating at the bounds of `u64`.
+///
+/// Returns whether the total saturated, which callers count so an overflow is
+/// observable instead of silently wrapping or aborting.
+pub(crate) fn saturating_accumulate(total: &mut u64, delta: i128) -> bool {
+    let sum = i128::from(*total) + delta;
+    match u64::try_from(sum) {
+        Ok(sum) => {
+            *total = sum;
+            false
+        }
+        Err(_) => {
+            *total = if sum < 0 { 0 } else { u64::MAX };
+            true
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counts the updates of a structure and wakes the threads waiting for the next one.
+///
+/// Publishing an update is a single atomic increment while nobody waits, so structures
+/// updated on a hot path only pay for the condition variable once a thread blocks on
+/// it.
+#[derive(Debug, Default)]
+pub(crate) struct UpdateNotifier {
+    /// The number of updates so far
+    update_count: AtomicU64,
+    /// The number of threads blocked in `wait_since`
+    waiters: AtomicUsize,
+    /// Held by waiters between checking the count and blocking, and by `notify` while
+    /// waking them, so no update falls in between
+    lock: Mutex<()>,
+    /// Signalled on every update while threads wait
+    updated: Condvar,
+}
+
+impl UpdateNotifier {
+    /// Returns the number of updates so far.
+    pub(crate) fn update_count(&self) -> u64 {
+        self.update_count.load(Ordering::SeqCst)
+    }
+
+    /// Records an update and wakes the waiting threads.
+    pub(crate) fn notify(&self) {
+        self.update_count.fetch_add(1, Ordering::SeqCst);
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            let _lock = self.lock.lock();
+            self.updated.notify_all();
+        }
+    }
+
+    /// Blocks until the update count differs from `seen_update_count`, or `timeout`
+    /// elapses.
+    ///
+    /// Returns the new update count, or `None` on timeout.
+    pub(crate) fn wait_since(&self, seen_update_count: u64, timeout: Duration) -> Option<u64> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut lock = self.lock.lock();
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let update_count = loop {
+            let update_count = self.update_count();
+            if update_count != seen_update_count {
+                break Some(update_count);
+            }
+            let timed_out = match deadline {
+                Some(deadline) => self.updated.wait_until(&mut lock, deadline).timed_out(),
+                None => {
+                    self.updated.wait(&mut lock);
+                    false
+                }
+            };
+            if timed_out {
+                let update_count = self.update_count();
+                break (update_count != seen_update_count).then_some(update_count);
+            }
+        };
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        update_count
+    }
+}
@@ -0,0 +1,490 @@
+//! A durable write-ahead log of commands, for embedding a book as a matching engine.
+//!
+//! Every command is appended to the log as a version 1 `wire` frame before it is
+//! applied, so after a crash `recover` rebuilds the book by replaying the log: its
+//! resting orders and queue priorities, client order identifiers, order identifier
+//! counter, and event sequence numbers all come out as they were, together with a
+//! `MarketDepthCache` aggregating the recovered depth.
+//!
+//! How often the log is flushed to stable storage is a trade-off between latency and
+//! durability, chosen with an `FsyncPolicy`. Commands written but not yet synced
+//! survive a crash of the process, as the operating system holds them, but not the
+//! loss of the machine.
+//!
+//! A crash in the middle of an append leaves a partial frame at the end of the log.
+//! Recovery ignores it, as the command it held was never applied, and `open` cuts it
+//! off before appending after it.
+//!
+//! So that recovery time stays bounded, the log can be checkpointed, manually or on a
+//! `CheckpointSchedule`: the book's state is written as a `Checkpoint` record that
+//! replaces the log, which then holds the checkpoint followed by the commands applied
+//! since. The new log is written to a temporary file and renamed over the old one, so
+//! a crash during a checkpoint leaves one of the two complete logs.
+//!
+//! Logs live in local files by default, and in any other `Storage` with
+//! `WriteAheadLog::with_storage` and `recover_from`: `MemoryStorage` keeps them in
+//! memory, and embedders implement the trait to keep them in an object store or their
+//! own infrastructure.
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::wal::{recover, FsyncPolicy, WriteAheadLog};
+//! use order_book::{Command, Order, OrderBook, Side};
+//!
+//! let path = std::env::temp_dir().join(format!("wal-doctest-{}.log", std::process::id()));
+//! let mut order_book = OrderBook::new();
+//! let mut wal = WriteAheadLog::open(&path, FsyncPolicy::EveryCommand).unwrap();
+//! wal.apply(&mut order_book, Command::Insert(Order::new(100.00, 10, Side::Bid))).unwrap();
+//! wal.apply(&mut order_book, Command::Match(Order::new(100.00, 4, Side::Ask))).unwrap();
+//! drop(wal);
+//!
+//! let recovery = recover(&path).unwrap();
+//! assert_eq!(recovery.command_count, 2);
+//! assert_eq!(recovery.order_book.total_volume(Side::Bid), 6);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use crate::error::OrderBookError;
+use crate::market_depth_cache::MarketDepthCache;
+use crate::order_book::OrderBook;
+use crate::types::{Checkpoint, Command, OrderEvent};
+use crate::wire::{decode_v1, encode_v1, DecodeError, Record};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+mod storage;
+
+pub use storage::{FileStorage, MemoryStorage, Storage};
+
+/// When a `WriteAheadLog` flushes appended commands to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Every command is synced before it is applied, so no applied command is lost
+    EveryCommand,
+    /// Commands are synced in batches of this many, amortizing the cost of a sync over
+    /// the batch; up to a batch of applied commands can be lost with the machine
+    ///
+    /// A batch of zero commands syncs every command.
+    Batch(usize),
+    /// Commands are synced only by `WriteAheadLog::sync`
+    Manual,
+}
+
+/// When a `WriteAheadLog` checkpoints the book it logs.
+///
+/// A checkpoint is due once either limit is reached, and is written before the next
+/// command is logged. The default schedule never checkpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckpointSchedule {
+    /// The number of events published since the last checkpoint that makes one due
+    pub events: Option<u64>,
+    /// The time since the last checkpoint, or since the log was opened, that makes one
+    /// due
+    pub interval: Option<Duration>,
+}
+
+/// Why the log could not be written or recovered.
+#[derive(Debug)]
+pub enum WalError {
+    /// Reading, writing, or syncing the log file failed
+    Io(io::Error),
+    /// The log holds a frame that cannot be decoded before its end
+    Corrupt {
+        /// The offset of the frame in the log, in bytes
+        offset: u64,
+        /// Why the frame cannot be decoded
+        error: DecodeError,
+    },
+    /// The command was logged, but the book rejected it, leaving the book unchanged
+    ///
+    /// Recovery replays the command and the book rejects it again, so the log stays
+    /// consistent with the book.
+    Rejected(OrderBookError),
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalError::Io(error) => write!(formatter, "write-ahead log I/O failed: {error}"),
+            WalError::Corrupt { offset, error } => {
+                write!(
+                    formatter,
+                    "write-ahead log is corrupt at byte {offset}: {error}"
+                )
+            }
+            WalError::Rejected(error) => write!(formatter, "logged command was rejected: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for WalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalError::Io(error) => Some(error),
+            WalError::Corrupt { error, .. } => Some(error),
+            WalError::Rejected(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for WalError {
+    fn from(error: io::Error) -> Self {
+        WalError::Io(error)
+    }
+}
+
+/// An append-only log of the commands applied to a book.
+///
+/// Commands are applied through `apply`, which logs them first. The log does not
+/// watch the book, so a book behind a write-ahead log should not be changed any other
+/// way.
+#[derive(Debug)]
+pub struct WriteAheadLog<S: Storage = FileStorage> {
+    /// Where the log's bytes are kept
+    storage: S,
+    /// When appended commands are synced
+    fsync_policy: FsyncPolicy,
+    /// The number of commands in the log
+    command_count: usize,
+    /// The number of commands appended since the last sync
+    unsynced_count: usize,
+    /// The frame being appended, reused between commands
+    buffer: Vec<u8>,
+    /// When the book is checkpointed
+    checkpoint_schedule: CheckpointSchedule,
+    /// The number of events published since the last checkpoint
+    events_since_checkpoint: u64,
+    /// When the last checkpoint was written, or the log opened
+    last_checkpoint: Instant,
+}
+
+impl WriteAheadLog<FileStorage> {
+    /// Opens the log at a path for appending, creating it if it does not exist.
+    ///
+    /// A partial frame left at the end by a crash is removed. The log does not
+    /// checkpoint until a schedule is set with `set_checkpoint_schedule`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `path`: The path of the log file
+    /// * `fsync_policy`: When appended commands are synced
+    ///
+    /// ## Returns
+    ///
+    /// The log, or an error if the file cannot be opened or holds a frame that cannot
+    /// be decoded
+    pub fn open(path: impl AsRef<Path>, fsync_policy: FsyncPolicy) -> Result<Self, WalError> {
+        Self::with_storage(FileStorage::open(path)?, fsync_policy)
+    }
+}
+
+impl<S: Storage> WriteAheadLog<S> {
+    /// Opens the log kept in a storage for appending.
+    ///
+    /// A partial frame left at the end by a crash is removed. The log does not
+    /// checkpoint until a schedule is set with `set_checkpoint_schedule`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `storage`: Where the log is kept
+    /// * `fsync_policy`: When appended commands are synced
+    ///
+    /// ## Returns
+    ///
+    /// The log, or an error if the storage cannot be read or holds a frame that cannot
+    /// be decoded
+    pub fn with_storage(mut storage: S, fsync_policy: FsyncPolicy) -> Result<Self, WalError> {
+        let bytes = storage.read()?;
+        let log = read_log(&bytes)?;
+        if log.valid_length < bytes.len() {
+            storage.truncate(log.valid_length as u64)?;
+        }
+
+        Ok(WriteAheadLog {
+            storage,
+            fsync_policy,
+            command_count: log.commands.len(),
+            unsynced_count: 0,
+            buffer: Vec::new(),
+            checkpoint_schedule: CheckpointSchedule::default(),
+            events_since_checkpoint: 0,
+            last_checkpoint: Instant::now(),
+        })
+    }
+
+    /// Sets when the book is checkpointed.
+    ///
+    /// ## Arguments
+    ///
+    /// * `checkpoint_schedule`: The event count and time limits making a checkpoint due
+    pub fn set_checkpoint_schedule(&mut self, checkpoint_schedule: CheckpointSchedule) {
+        self.checkpoint_schedule = checkpoint_schedule;
+    }
+
+    /// Appends a command to the log, syncing it according to the fsync policy.
+    ///
+    /// ## Arguments
+    ///
+    /// * `command`: The command about to be applied
+    pub fn append(&mut self, command: &Command) -> Result<(), WalError> {
+        self.buffer.clear();
+        encode_v1(&Record::Command(command.clone()), &mut self.buffer);
+        self.storage.append(&self.buffer)?;
+        self.command_count += 1;
+        self.unsynced_count += 1;
+
+        let sync_due = match self.fsync_policy {
+            FsyncPolicy::EveryCommand => true,
+            FsyncPolicy::Batch(batch_size) => self.unsynced_count >= batch_size,
+            FsyncPolicy::Manual => false,
+        };
+        if sync_due {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Logs a command, then applies it to the book.
+    ///
+    /// If a checkpoint is due, the book is checkpointed before the command is logged.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book the log belongs to
+    /// * `command`: The command to apply
+    ///
+    /// ## Returns
+    ///
+    /// The events of the command, or an error if it could not be logged or the due
+    /// checkpoint written, in which case it was not applied, or was rejected by the
+    /// book
+    pub fn apply(
+        &mut self,
+        order_book: &mut OrderBook,
+        command: Command,
+    ) -> Result<Vec<OrderEvent>, WalError> {
+        if self.checkpoint_due() {
+            self.checkpoint(order_book)?;
+        }
+        self.append(&command)?;
+        let events = order_book.apply(command).map_err(WalError::Rejected)?;
+        self.events_since_checkpoint += events.len() as u64;
+        Ok(events)
+    }
+
+    /// Replaces the log with a checkpoint of the book, so recovery starts from it.
+    ///
+    /// The new log is durable once this returns, whatever the fsync policy.
+    ///
+    /// ## Arguments
+    ///
+    /// * `order_book`: The book the log belongs to, reflecting every logged command
+    pub fn checkpoint(&mut self, order_book: &OrderBook) -> Result<(), WalError> {
+        self.buffer.clear();
+        encode_v1(
+            &Record::Checkpoint(order_book.checkpoint()),
+            &mut self.buffer,
+        );
+        self.storage.replace(&self.buffer)?;
+        self.command_count = 0;
+        self.unsynced_count = 0;
+        self.events_since_checkpoint = 0;
+        self.last_checkpoint = Instant::now();
+        Ok(())
+    }
+
+    /// Flushes the commands appended since the last sync to stable storage.
+    pub fn sync(&mut self) -> Result<(), WalError> {
+        if self.unsynced_count > 0 {
+            self.storage.sync()?;
+            self.unsynced_count = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of commands in the log after its checkpoint, if any,
+    /// including those not synced yet.
+    pub fn command_count(&self) -> usize {
+        self.command_count
+    }
+
+    /// Returns whether the checkpoint schedule makes a checkpoint due.
+    fn checkpoint_due(&self) -> bool {
+        let CheckpointSchedule { events, interval } = self.checkpoint_schedule;
+        events.is_some_and(|events| self.events_since_checkpoint >= events)
+            || interval.is_some_and(|interval| self.last_checkpoint.elapsed() >= interval)
+    }
+
+    /// Returns the number of commands appended since the last sync.
+    pub fn unsynced_count(&self) -> usize {
+        self.unsynced_count
+    }
+
+    /// Returns the storage the log is kept in.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+}
+
+/// The state rebuilt from a write-ahead log by `recover`.
+#[derive(Debug)]
+pub struct Recovery {
+    /// The book after every logged command
+    pub order_book: OrderBook,
+    /// A cache aggregating the recovered book's depth
+    pub market_depth_cache: MarketDepthCache,
+    /// Whether the book was restored from a checkpoint before the commands were replayed
+    pub restored_checkpoint: bool,
+    /// The number of commands replayed
+    pub command_count: usize,
+}
+
+/// Rebuilds a book and its depth cache from the write-ahead log at a path.
+///
+/// The log's checkpoint, if any, is restored into an `OrderBook::new()`, and the
+/// commands logged after it are replayed; books created otherwise, e.g. with a tick
+/// size or a seed, are recovered with `replay`.
+///
+/// ## Arguments
+///
+/// * `path`: The path of the log file
+///
+/// ## Returns
+///
+/// The recovered state, or an error if the file cannot be read or holds a frame that
+/// cannot be decoded
+pub fn recover(path: impl AsRef<Path>) -> Result<Recovery, WalError> {
+    Ok(recover_log(read_log(&std::fs::read(path)?)?))
+}
+
+/// Rebuilds a book and its depth cache from the write-ahead log kept in a storage, as
+/// `recover` does from a file.
+///
+/// ## Arguments
+///
+/// * `storage`: Where the log is kept
+///
+/// ## Returns
+///
+/// The recovered state, or an error if the storage cannot be read or holds a frame
+/// that cannot be decoded
+pub fn recover_from(storage: &mut impl Storage) -> Result<Recovery, WalError> {
+    Ok(recover_log(read_log(&storage.read()?)?))
+}
+
+/// Replays a decoded log into a fresh book and cache.
+fn recover_log(log: Log) -> Recovery {
+    let mut order_book = OrderBook::new();
+    let restored_checkpoint = log.checkpoint.is_some();
+    let command_count = log.replay(&mut order_book);
+    let market_depth_cache = MarketDepthCache::new();
+    market_depth_cache.rebuild_from(&order_book);
+    Recovery {
+        order_book,
+        market_depth_cache,
+        restored_checkpoint,
+        command_count,
+    }
+}
+
+/// Restores the checkpoint of the write-ahead log at a path into a book, if the log
+/// has one, then applies the commands logged after it, in order.
+///
+/// Commands the book rejects are skipped, as they were when they were logged, and a
+/// partial frame at the end of the log is ignored.
+///
+/// ## Arguments
+///
+/// * `path`: The path of the log file
+/// * `order_book`: The empty book to replay into, configured as the logged book was
+///
+/// ## Returns
+///
+/// The number of commands replayed, or an error if the file cannot be read or holds a
+/// frame that cannot be decoded
+pub fn replay(path: impl AsRef<Path>, order_book: &mut OrderBook) -> Result<usize, WalError> {
+    Ok(read_log(&std::fs::read(path)?)?.replay(order_book))
+}
+
+/// Replays the write-ahead log kept in a storage into a book, as `replay` does from a
+/// file.
+///
+/// ## Arguments
+///
+/// * `storage`: Where the log is kept
+/// * `order_book`: The empty book to replay into, configured as the logged book was
+///
+/// ## Returns
+///
+/// The number of commands replayed, or an error if the storage cannot be read or holds
+/// a frame that cannot be decoded
+pub fn replay_from(
+    storage: &mut impl Storage,
+    order_book: &mut OrderBook,
+) -> Result<usize, WalError> {
+    Ok(read_log(&storage.read()?)?.replay(order_book))
+}
+
+/// The decoded contents of a log.
+struct Log {
+    /// The latest checkpoint, if any
+    checkpoint: Option<Checkpoint>,
+    /// The commands logged after the checkpoint
+    commands: Vec<Command>,
+    /// The length of the log up to the partial frame at its end, if any
+    valid_length: usize,
+}
+
+impl Log {
+    /// Restores the checkpoint into an empty book and applies the commands.
+    ///
+    /// ## Returns
+    ///
+    /// The number of commands applied
+    fn replay(self, order_book: &mut OrderBook) -> usize {
+        if let Some(checkpoint) = &self.checkpoint {
+            order_book.restore_checkpoint(checkpoint);
+        }
+        let command_count = self.commands.len();
+        for command in self.commands {
+            // Rejections were rejections when the command was logged too
+            let _ = order_book.apply(command);
+        }
+        command_count
+    }
+}
+
+/// Decodes the frames of a log.
+fn read_log(bytes: &[u8]) -> Result<Log, WalError> {
+    let mut log = Log {
+        checkpoint: None,
+        commands: Vec::new(),
+        valid_length: 0,
+    };
+    while log.valid_length < bytes.len() {
+        match decode_v1(&bytes[log.valid_length..]) {
+            Ok((record, frame_length)) => {
+                match record {
+                    Some(Record::Command(command)) => log.commands.push(command),
+                    Some(Record::Checkpoint(checkpoint)) => {
+                        log.checkpoint = Some(checkpoint);
+                        log.commands.clear();
+                    }
+                    _ => {}
+                }
+                log.valid_length += frame_length;
+            }
+            Err(DecodeError::Truncated) => break,
+            Err(error) => {
+                return Err(WalError::Corrupt {
+                    offset: log.valid_length as u64,
+                    error,
+                })
+            }
+        }
+    }
+    Ok(log)
+}
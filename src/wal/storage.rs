@@ -0,0 +1,154 @@
+//! Where a write-ahead log keeps its bytes.
+
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The durable medium holding a write-ahead log and its checkpoint.
+///
+/// A log is a single sequence of bytes that only grows by appending, except when a
+/// checkpoint replaces it in full. Implementing this trait backs a `WriteAheadLog`
+/// with other infrastructure, e.g. an object store keeping the checkpoint and the
+/// appended segments as objects: `replace` uploads a new checkpoint object and
+/// discards the segments, `append` writes a segment, and `read` concatenates them.
+pub trait Storage {
+    /// Returns the whole log, empty if nothing was ever written.
+    fn read(&mut self) -> io::Result<Vec<u8>>;
+
+    /// Appends bytes at the end of the log.
+    ///
+    /// The bytes need not be durable until the next `sync`.
+    fn append(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Makes every appended byte durable.
+    fn sync(&mut self) -> io::Result<()>;
+
+    /// Durably cuts the log down to its first `length` bytes, removing a partial frame
+    /// left at its end by a crash.
+    fn truncate(&mut self, length: u64) -> io::Result<()>;
+
+    /// Durably replaces the whole log with `bytes`.
+    ///
+    /// The replacement must be atomic: after a crash, the log holds either its old or
+    /// its new contents in full.
+    fn replace(&mut self, bytes: &[u8]) -> io::Result<()>;
+}
+
+/// A log kept in a local file.
+///
+/// Checkpoints are written to a temporary file next to the log, which is then renamed
+/// over it.
+#[derive(Debug)]
+pub struct FileStorage {
+    /// The path of the log file
+    path: PathBuf,
+    /// The log file, opened for appending
+    file: File,
+}
+
+impl FileStorage {
+    /// Opens the log file at a path, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().append(true).create(true).open(&path)?;
+        Ok(FileStorage { path, file })
+    }
+
+    /// Returns the path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Storage for FileStorage {
+    fn read(&mut self) -> io::Result<Vec<u8>> {
+        std::fs::read(&self.path)
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    fn truncate(&mut self, length: u64) -> io::Result<()> {
+        self.file.set_len(length)?;
+        self.file.sync_all()
+    }
+
+    fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".checkpoint");
+        let mut temporary = File::create(&temporary_path)?;
+        temporary.write_all(bytes)?;
+        temporary.sync_all()?;
+        drop(temporary);
+        std::fs::rename(&temporary_path, &self.path)?;
+        // The rename is durable once the directory is synced, which not every platform
+        // supports; without it, a crash can only bring the previous log back
+        if let Some(directory) = self.path.parent() {
+            let directory = if directory.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                directory
+            };
+            let _ = File::open(directory).and_then(|directory| directory.sync_all());
+        }
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// A log kept in memory, e.g. for tests or for books that only need recovery from
+/// failures of the code driving them.
+///
+/// Clones share the same bytes, so a clone kept aside sees everything written through
+/// the log and can recover from it.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    /// The log's bytes
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+
+    /// Returns a copy of the log's bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.lock().clone()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&mut self) -> io::Result<Vec<u8>> {
+        Ok(self.to_bytes())
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.bytes.lock().extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn truncate(&mut self, length: u64) -> io::Result<()> {
+        let length = usize::try_from(length).unwrap_or(usize::MAX);
+        self.bytes.lock().truncate(length);
+        Ok(())
+    }
+
+    fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        *self.bytes.lock() = bytes.to_vec();
+        Ok(())
+    }
+}
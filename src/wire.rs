@@ -0,0 +1,668 @@
+//! A versioned binary encoding of the book's events and snapshots, for journals.
+//!
+//! Records are written as self-delimiting frames, so a journal is simply frames
+//! concatenated in order. Version 1 frames are laid out as follows, with every integer
+//! in little-endian byte order:
+//!
+//! | Bytes | Content                                  |
+//! |-------|------------------------------------------|
+//! | 1     | Format version, `1`                      |
+//! | 1     | Record kind                              |
+//! | 4     | Body length in bytes, as a `u32`         |
+//! | ...   | Body                                     |
+//!
+//! Bodies encode the fields of their record in declaration order. A `Decimal` takes 16
+//! bytes (`Decimal::serialize`), a `Side` one byte (`0` for bids, `1` for asks), and
+//! identifiers their `u64`. The record kinds are:
+//!
+//! - `1`, `OrderEvent`: price, quantity delta (`i64`), side, timestamp, sequence, then
+//!   the appended order count delta (`i64`), read as `0` when absent
+//! - `2`, `MarketByOrderEvent`: a variant tag (`0` added, `1` executed, `2` cancelled,
+//!   `3` replaced), then the variant's fields; the trade and taker order identifiers
+//!   of an execution were appended later and read as `0` when absent
+//! - `3`, `Trade`: price, quantity, aggressor side, then the appended trade, maker
+//!   order, and taker order identifiers, read as `0` when absent
+//! - `4`, `DepthSnapshot`: sequence, then the bid and the ask levels, each as a `u32`
+//!   count followed by that many price and quantity pairs in ascending price order
+//! - `5`, `Command`: a variant tag, then the variant's fields, with an `Order` as its
+//!   price, quantity, and side, and a `TradingState` as one byte (`0` continuous, `1`
+//!   halted); the tags are `0` insert, `1` insert with client id, `2` match, `3`
+//!   cancel, `4` amend, `5` clear, `6` set state, `7` set time, `8` insert day, `9`
+//!   match day, and `10` end session
+//! - `6`, `Checkpoint`: the book's sequence and next order identifier, the bid and
+//!   the ask orders, each as a `u32` count followed by that many identifier, price,
+//!   quantity, and side tuples in priority order, the trading state, the manual clock
+//!   as a byte (`0` for none, `1` followed by its time), the session, the day orders as
+//!   a `u32` count of identifiers, the client order identifiers as a `u32` count of
+//!   order and client identifier pairs, and the appended next trade identifier, read
+//!   as `1` when absent
+//!
+//! So that journals written today stay readable as the crate's types evolve, the
+//! format only changes under these rules:
+//!
+//! - New fields are appended at the end of a body, and decoders ignore body bytes
+//!   past the fields they know; a decoder reading an older, shorter body falls back to
+//!   the new field's default
+//! - New records and new variants get new tags, and decoders skip frames they do not
+//!   know, which `decode_v1` reports as `None` next to the frame length
+//! - Any other change, such as re-encoding or removing a field, bumps the format
+//!   version; `decode_v1` keeps decoding version 1 frames and rejects other versions
+//!   with `DecodeError::UnsupportedVersion`
+//!
+//! ## Examples
+//!
+//! ```
+//! use order_book::wire::{decode_v1, encode_v1, Record};
+//! use order_book::{Order, OrderBook, Side};
+//!
+//! let mut order_book = OrderBook::new();
+//! let mut journal = Vec::new();
+//! for price in [100.00, 101.00] {
+//!     let event = order_book.insert_order(Order::new(price, 10, Side::Ask)).event;
+//!     encode_v1(&Record::OrderEvent(event), &mut journal);
+//! }
+//!
+//! let mut remaining = &journal[..];
+//! let mut records = Vec::new();
+//! while !remaining.is_empty() {
+//!     let (record, frame_length) = decode_v1(remaining).unwrap();
+//!     records.extend(record);
+//!     remaining = &remaining[frame_length..];
+//! }
+//! assert_eq!(records.len(), 2);
+//! ```
+
+use crate::types::{
+    AggregatedDepthMap, BookSnapshot, Checkpoint, ClientOrderId, Command, DepthSnapshot,
+    MarketByOrderEvent, Order, OrderEvent, OrderId, Side, Trade, TradeId, TradingState,
+};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// The format version written by `encode_v1`.
+pub const VERSION_1: u8 = 1;
+
+/// The length of a frame header: version, kind, and body length.
+const HEADER_LENGTH: usize = 6;
+
+/// Record kind of an `OrderEvent`.
+const KIND_ORDER_EVENT: u8 = 1;
+/// Record kind of a `MarketByOrderEvent`.
+const KIND_MARKET_BY_ORDER: u8 = 2;
+/// Record kind of a `Trade`.
+const KIND_TRADE: u8 = 3;
+/// Record kind of a `DepthSnapshot`.
+const KIND_DEPTH_SNAPSHOT: u8 = 4;
+/// Record kind of a `Command`.
+const KIND_COMMAND: u8 = 5;
+/// Record kind of a `Checkpoint`.
+const KIND_CHECKPOINT: u8 = 6;
+
+/// A record that can be written to a journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    /// A change of the aggregate quantity at a price
+    OrderEvent(OrderEvent),
+    /// A change to an individual order
+    MarketByOrder(MarketByOrderEvent),
+    /// An execution
+    Trade(Trade),
+    /// The aggregated depth of both sides
+    DepthSnapshot(DepthSnapshot),
+    /// An operation on a book, as written to a write-ahead log
+    Command(Command),
+    /// The state of a book at a checkpoint of a write-ahead log
+    Checkpoint(Checkpoint),
+}
+
+/// Why a frame could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ends before the frame does
+    Truncated,
+    /// The frame was written in a format version this decoder does not read
+    UnsupportedVersion(u8),
+    /// The body of a known record kind is invalid
+    Malformed {
+        /// The kind of the record
+        kind: u8,
+        /// What is wrong with its body
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(formatter, "input ends in the middle of a frame"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported format version {version}")
+            }
+            DecodeError::Malformed { kind, reason } => {
+                write!(formatter, "malformed record of kind {kind}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Appends a record to `buffer` as a version 1 frame.
+///
+/// ## Arguments
+///
+/// * `record`: The record to encode
+/// * `buffer`: The buffer the frame is appended to
+pub fn encode_v1(record: &Record, buffer: &mut Vec<u8>) {
+    let kind = match record {
+        Record::OrderEvent(_) => KIND_ORDER_EVENT,
+        Record::MarketByOrder(_) => KIND_MARKET_BY_ORDER,
+        Record::Trade(_) => KIND_TRADE,
+        Record::DepthSnapshot(_) => KIND_DEPTH_SNAPSHOT,
+        Record::Command(_) => KIND_COMMAND,
+        Record::Checkpoint(_) => KIND_CHECKPOINT,
+    };
+    buffer.extend_from_slice(&[VERSION_1, kind, 0, 0, 0, 0]);
+    let body_start = buffer.len();
+
+    match record {
+        Record::OrderEvent(event) => {
+            put_decimal(buffer, event.price);
+            buffer.extend_from_slice(&event.quantity_delta.to_le_bytes());
+            put_side(buffer, event.side);
+            put_u64(buffer, event.timestamp_nanos);
+            put_u64(buffer, event.sequence);
+            buffer.extend_from_slice(&event.order_count_delta.to_le_bytes());
+        }
+        Record::MarketByOrder(event) => put_market_by_order(buffer, event),
+        Record::Trade(trade) => {
+            put_decimal(buffer, trade.price);
+            put_u64(buffer, trade.quantity);
+            put_side(buffer, trade.aggressor_side);
+            put_u64(buffer, trade.trade_id.0);
+            put_u64(buffer, trade.maker_order_id.0);
+            put_u64(buffer, trade.taker_order_id.0);
+        }
+        Record::DepthSnapshot(snapshot) => {
+            put_u64(buffer, snapshot.sequence);
+            put_depth(buffer, &snapshot.bids);
+            put_depth(buffer, &snapshot.asks);
+        }
+        Record::Command(command) => put_command(buffer, command),
+        Record::Checkpoint(checkpoint) => put_checkpoint(buffer, checkpoint),
+    }
+
+    let body_length = u32::try_from(buffer.len() - body_start).expect("record too large");
+    buffer[body_start - 4..body_start].copy_from_slice(&body_length.to_le_bytes());
+}
+
+/// Decodes the version 1 frame at the start of `bytes`.
+///
+/// ## Arguments
+///
+/// * `bytes`: The input, starting with a frame and possibly followed by more
+///
+/// ## Returns
+///
+/// The record, or `None` if the frame holds a record kind or variant this version of
+/// the crate does not know, together with the length of the frame, so the next frame
+/// starts at `bytes[frame_length..]`
+pub fn decode_v1(bytes: &[u8]) -> Result<(Option<Record>, usize), DecodeError> {
+    let header = bytes.get(..HEADER_LENGTH).ok_or(DecodeError::Truncated)?;
+    if header[0] != VERSION_1 {
+        return Err(DecodeError::UnsupportedVersion(header[0]));
+    }
+    let kind = header[1];
+    let body_length = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    let frame_length = HEADER_LENGTH + body_length;
+    let body = bytes
+        .get(HEADER_LENGTH..frame_length)
+        .ok_or(DecodeError::Truncated)?;
+
+    let mut reader = BodyReader { kind, body };
+    let record = match kind {
+        KIND_ORDER_EVENT => Some(Record::OrderEvent(OrderEvent {
+            price: reader.decimal()?,
+            quantity_delta: reader.u64()? as i64,
+            side: reader.side()?,
+            timestamp_nanos: reader.u64()?,
+            sequence: reader.u64()?,
+            order_count_delta: reader.appended_u64(0)? as i64,
+        })),
+        KIND_MARKET_BY_ORDER => reader.market_by_order()?.map(Record::MarketByOrder),
+        KIND_TRADE => Some(Record::Trade(Trade {
+            price: reader.decimal()?,
+            quantity: reader.u64()?,
+            aggressor_side: reader.side()?,
+            trade_id: TradeId(reader.appended_u64(0)?),
+            maker_order_id: OrderId(reader.appended_u64(0)?),
+            taker_order_id: OrderId(reader.appended_u64(0)?),
+        })),
+        KIND_DEPTH_SNAPSHOT => Some(Record::DepthSnapshot(DepthSnapshot {
+            sequence: reader.u64()?,
+            bids: reader.depth()?,
+            asks: reader.depth()?,
+        })),
+        KIND_COMMAND => reader.command()?.map(Record::Command),
+        KIND_CHECKPOINT => Some(Record::Checkpoint(reader.checkpoint()?)),
+        _ => None,
+    };
+    Ok((record, frame_length))
+}
+
+/// Appends a `u64`.
+fn put_u64(buffer: &mut Vec<u8>, value: u64) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a `Decimal` in its 16-byte serialized form.
+fn put_decimal(buffer: &mut Vec<u8>, value: Decimal) {
+    buffer.extend_from_slice(&value.serialize());
+}
+
+/// Appends a side as a single byte.
+fn put_side(buffer: &mut Vec<u8>, side: Side) {
+    buffer.push(match side {
+        Side::Bid => 0,
+        Side::Ask => 1,
+    });
+}
+
+/// Appends the levels of one side of a depth snapshot.
+fn put_depth(buffer: &mut Vec<u8>, depth: &AggregatedDepthMap) {
+    let level_count = u32::try_from(depth.len()).expect("too many levels");
+    buffer.extend_from_slice(&level_count.to_le_bytes());
+    for (price, quantity) in depth {
+        put_decimal(buffer, *price);
+        put_u64(buffer, *quantity);
+    }
+}
+
+/// Appends the variant tag and the fields of a market-by-order event.
+fn put_market_by_order(buffer: &mut Vec<u8>, event: &MarketByOrderEvent) {
+    match *event {
+        MarketByOrderEvent::Added {
+            order_id,
+            price,
+            quantity,
+            side,
+        } => {
+            buffer.push(0);
+            put_u64(buffer, order_id.0);
+            put_decimal(buffer, price);
+            put_u64(buffer, quantity);
+            put_side(buffer, side);
+        }
+        MarketByOrderEvent::Executed {
+            order_id,
+            price,
+            side,
+            executed_quantity,
+            remaining_quantity,
+            trade_id,
+            taker_order_id,
+        } => {
+            buffer.push(1);
+            put_u64(buffer, order_id.0);
+            put_decimal(buffer, price);
+            put_side(buffer, side);
+            put_u64(buffer, executed_quantity);
+            put_u64(buffer, remaining_quantity);
+            put_u64(buffer, trade_id.0);
+            put_u64(buffer, taker_order_id.0);
+        }
+        MarketByOrderEvent::Cancelled {
+            order_id,
+            price,
+            side,
+            cancelled_quantity,
+            remaining_quantity,
+        } => {
+            buffer.push(2);
+            put_u64(buffer, order_id.0);
+            put_decimal(buffer, price);
+            put_side(buffer, side);
+            put_u64(buffer, cancelled_quantity);
+            put_u64(buffer, remaining_quantity);
+        }
+        MarketByOrderEvent::Replaced {
+            order_id,
+            new_order_id,
+            price,
+            quantity,
+            side,
+        } => {
+            buffer.push(3);
+            put_u64(buffer, order_id.0);
+            put_u64(buffer, new_order_id.0);
+            put_decimal(buffer, price);
+            put_u64(buffer, quantity);
+            put_side(buffer, side);
+        }
+    }
+}
+
+/// Appends the price, quantity, and side of an order.
+fn put_order(buffer: &mut Vec<u8>, order: &Order) {
+    put_decimal(buffer, order.price);
+    put_u64(buffer, order.quantity);
+    put_side(buffer, order.side);
+}
+
+/// Appends the variant tag and the fields of a command.
+fn put_command(buffer: &mut Vec<u8>, command: &Command) {
+    match command {
+        Command::Insert(order) => {
+            buffer.push(0);
+            put_order(buffer, order);
+        }
+        Command::InsertWithClientId(order, client_order_id) => {
+            buffer.push(1);
+            put_order(buffer, order);
+            put_u64(buffer, client_order_id.0);
+        }
+        Command::Match(order) => {
+            buffer.push(2);
+            put_order(buffer, order);
+        }
+        Command::Cancel(order_id) => {
+            buffer.push(3);
+            put_u64(buffer, order_id.0);
+        }
+        Command::Amend {
+            order_id,
+            price,
+            quantity,
+        } => {
+            buffer.push(4);
+            put_u64(buffer, order_id.0);
+            put_decimal(buffer, *price);
+            put_u64(buffer, *quantity);
+        }
+        Command::Clear => buffer.push(5),
+        Command::SetState(trading_state) => {
+            buffer.push(6);
+            put_trading_state(buffer, *trading_state);
+        }
+        Command::SetTime(timestamp_nanos) => {
+            buffer.push(7);
+            put_u64(buffer, *timestamp_nanos);
+        }
+        Command::InsertDay(order) => {
+            buffer.push(8);
+            put_order(buffer, order);
+        }
+        Command::MatchDay(order) => {
+            buffer.push(9);
+            put_order(buffer, order);
+        }
+        Command::EndSession => buffer.push(10),
+    }
+}
+
+/// Appends a trading state as a single byte.
+fn put_trading_state(buffer: &mut Vec<u8>, trading_state: TradingState) {
+    buffer.push(match trading_state {
+        TradingState::Continuous => 0,
+        TradingState::Halted => 1,
+    });
+}
+
+/// Appends a `u32` count of items.
+fn put_count(buffer: &mut Vec<u8>, count: usize) {
+    let count = u32::try_from(count).expect("too many items");
+    buffer.extend_from_slice(&count.to_le_bytes());
+}
+
+/// Appends the fields of a checkpoint.
+fn put_checkpoint(buffer: &mut Vec<u8>, checkpoint: &Checkpoint) {
+    put_u64(buffer, checkpoint.book.sequence);
+    put_u64(buffer, checkpoint.book.next_order_id);
+    for orders in [&checkpoint.book.bids, &checkpoint.book.asks] {
+        put_count(buffer, orders.len());
+        for (order_id, order) in orders {
+            put_u64(buffer, order_id.0);
+            put_order(buffer, order);
+        }
+    }
+    put_trading_state(buffer, checkpoint.trading_state);
+    match checkpoint.manual_time_nanos {
+        None => buffer.push(0),
+        Some(timestamp_nanos) => {
+            buffer.push(1);
+            put_u64(buffer, timestamp_nanos);
+        }
+    }
+    put_u64(buffer, checkpoint.session);
+    put_count(buffer, checkpoint.day_orders.len());
+    for order_id in &checkpoint.day_orders {
+        put_u64(buffer, order_id.0);
+    }
+    put_count(buffer, checkpoint.client_order_ids.len());
+    for (order_id, client_order_id) in &checkpoint.client_order_ids {
+        put_u64(buffer, order_id.0);
+        put_u64(buffer, client_order_id.0);
+    }
+    put_u64(buffer, checkpoint.next_trade_id);
+}
+
+/// Reads the fields of a body in order.
+struct BodyReader<'a> {
+    /// The kind of the record, for error reports
+    kind: u8,
+    /// The body bytes not read yet
+    body: &'a [u8],
+}
+
+impl<'a> BodyReader<'a> {
+    /// Returns the error for an invalid body of this record.
+    fn malformed(&self, reason: &'static str) -> DecodeError {
+        DecodeError::Malformed {
+            kind: self.kind,
+            reason,
+        }
+    }
+
+    /// Reads the next `N` bytes.
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        if self.body.len() < N {
+            return Err(self.malformed("body too short"));
+        }
+        let (field, rest) = self.body.split_at(N);
+        self.body = rest;
+        Ok(field.try_into().expect("split at N"))
+    }
+
+    /// Reads a byte.
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    /// Reads a `u32`.
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    /// Reads a `u64`.
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    /// Reads a `u64` appended to the body by a later revision of the format, or returns
+    /// `default` if the body ends before it.
+    fn appended_u64(&mut self, default: u64) -> Result<u64, DecodeError> {
+        if self.body.is_empty() {
+            Ok(default)
+        } else {
+            self.u64()
+        }
+    }
+
+    /// Reads an order identifier.
+    fn order_id(&mut self) -> Result<OrderId, DecodeError> {
+        self.u64().map(OrderId)
+    }
+
+    /// Reads a `Decimal`, rejecting invalid scales.
+    fn decimal(&mut self) -> Result<Decimal, DecodeError> {
+        let bytes = self.take::<16>()?;
+        // The scale lives in the third byte of the flags and may not exceed 28
+        if bytes[2] > 28 {
+            return Err(self.malformed("decimal scale out of range"));
+        }
+        Ok(Decimal::deserialize(bytes))
+    }
+
+    /// Reads a side.
+    fn side(&mut self) -> Result<Side, DecodeError> {
+        match self.u8()? {
+            0 => Ok(Side::Bid),
+            1 => Ok(Side::Ask),
+            _ => Err(self.malformed("invalid side")),
+        }
+    }
+
+    /// Reads the levels of one side of a depth snapshot.
+    fn depth(&mut self) -> Result<AggregatedDepthMap, DecodeError> {
+        let level_count = self.u32()?;
+        let mut depth = AggregatedDepthMap::new();
+        for _ in 0..level_count {
+            depth.insert(self.decimal()?, self.u64()?);
+        }
+        Ok(depth)
+    }
+
+    /// Reads the price, quantity, and side of an order.
+    fn order(&mut self) -> Result<Order, DecodeError> {
+        Ok(Order {
+            price: self.decimal()?,
+            quantity: self.u64()?,
+            side: self.side()?,
+        })
+    }
+
+    /// Reads a trading state.
+    fn trading_state(&mut self) -> Result<TradingState, DecodeError> {
+        match self.u8()? {
+            0 => Ok(TradingState::Continuous),
+            1 => Ok(TradingState::Halted),
+            _ => Err(self.malformed("invalid trading state")),
+        }
+    }
+
+    /// Reads a `u32` count of items, each taking at least `item_length` bytes.
+    fn count(&mut self, item_length: usize) -> Result<usize, DecodeError> {
+        let count = self.u32()? as usize;
+        // Rejects counts the body cannot hold before anything is allocated for them
+        if count.saturating_mul(item_length) > self.body.len() {
+            return Err(self.malformed("count exceeds body"));
+        }
+        Ok(count)
+    }
+
+    /// Reads a checkpoint.
+    fn checkpoint(&mut self) -> Result<Checkpoint, DecodeError> {
+        let sequence = self.u64()?;
+        let next_order_id = self.u64()?;
+        let mut sides = [Vec::new(), Vec::new()];
+        for orders in &mut sides {
+            // An identifier, a price, a quantity, and a side
+            let order_count = self.count(33)?;
+            orders.reserve(order_count);
+            for _ in 0..order_count {
+                orders.push((self.order_id()?, self.order()?));
+            }
+        }
+        let [bids, asks] = sides;
+        let trading_state = self.trading_state()?;
+        let manual_time_nanos = match self.u8()? {
+            0 => None,
+            1 => Some(self.u64()?),
+            _ => return Err(self.malformed("invalid clock")),
+        };
+        let session = self.u64()?;
+        let day_order_count = self.count(8)?;
+        let day_orders = (0..day_order_count)
+            .map(|_| self.order_id())
+            .collect::<Result<_, _>>()?;
+        let client_order_id_count = self.count(16)?;
+        let client_order_ids = (0..client_order_id_count)
+            .map(|_| Ok((self.order_id()?, ClientOrderId(self.u64()?))))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Checkpoint {
+            book: BookSnapshot {
+                sequence,
+                next_order_id,
+                bids,
+                asks,
+            },
+            trading_state,
+            manual_time_nanos,
+            session,
+            day_orders,
+            client_order_ids,
+            next_trade_id: self.appended_u64(1)?,
+        })
+    }
+
+    /// Reads a command, or `None` for a variant tag it does not know.
+    fn command(&mut self) -> Result<Option<Command>, DecodeError> {
+        let command = match self.u8()? {
+            0 => Command::Insert(self.order()?),
+            1 => Command::InsertWithClientId(self.order()?, ClientOrderId(self.u64()?)),
+            2 => Command::Match(self.order()?),
+            3 => Command::Cancel(self.order_id()?),
+            4 => Command::Amend {
+                order_id: self.order_id()?,
+                price: self.decimal()?,
+                quantity: self.u64()?,
+            },
+            5 => Command::Clear,
+            6 => Command::SetState(self.trading_state()?),
+            7 => Command::SetTime(self.u64()?),
+            8 => Command::InsertDay(self.order()?),
+            9 => Command::MatchDay(self.order()?),
+            10 => Command::EndSession,
+            _ => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+
+    /// Reads a market-by-order event, or `None` for a variant tag it does not know.
+    fn market_by_order(&mut self) -> Result<Option<MarketByOrderEvent>, DecodeError> {
+        let event = match self.u8()? {
+            0 => MarketByOrderEvent::Added {
+                order_id: self.order_id()?,
+                price: self.decimal()?,
+                quantity: self.u64()?,
+                side: self.side()?,
+            },
+            1 => MarketByOrderEvent::Executed {
+                order_id: self.order_id()?,
+                price: self.decimal()?,
+                side: self.side()?,
+                executed_quantity: self.u64()?,
+                remaining_quantity: self.u64()?,
+                trade_id: TradeId(self.appended_u64(0)?),
+                taker_order_id: OrderId(self.appended_u64(0)?),
+            },
+            2 => MarketByOrderEvent::Cancelled {
+                order_id: self.order_id()?,
+                price: self.decimal()?,
+                side: self.side()?,
+                cancelled_quantity: self.u64()?,
+                remaining_quantity: self.u64()?,
+            },
+            3 => MarketByOrderEvent::Replaced {
+                order_id: self.order_id()?,
+                new_order_id: self.order_id()?,
+                price: self.decimal()?,
+                quantity: self.u64()?,
+                side: self.side()?,
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
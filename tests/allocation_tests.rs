@@ -0,0 +1,59 @@
+use order_book::{Order, OrderBook, OrderBookConfig, Side};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the heap allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations() -> u64 {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+/// Test that a preallocated, warmed-up book inserts and cancels without heap allocations.
+fn test_warm_book_does_not_allocate() {
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        order_capacity: 256,
+        ..OrderBookConfig::default()
+    });
+
+    // Warm up: one long-lived order per level keeps every level alive
+    let prices: Vec<f64> = (0..10).map(|index| 100.0 + index as f64 * 0.25).collect();
+    for price in &prices {
+        order_book.insert_order(Order::new(*price, 1, Side::Bid));
+    }
+    let orders: Vec<Order> = (0..1_000)
+        .map(|index| Order::new(prices[index % prices.len()], 5, Side::Bid))
+        .collect();
+    let mut handles = Vec::with_capacity(100);
+
+    let before = allocations();
+    for chunk in orders.chunks(100) {
+        for order in chunk {
+            handles.push(order_book.insert_order(order.clone()).handle);
+        }
+        for handle in handles.drain(..) {
+            order_book.cancel_by_handle(handle).unwrap();
+        }
+    }
+    assert_eq!(allocations() - before, 0);
+    assert_eq!(order_book.order_count(), prices.len());
+}
@@ -0,0 +1,71 @@
+#![cfg(all(feature = "msgpack", feature = "bincode"))]
+
+use order_book::codec::{Bincode, Codec, CodecError, FrameReader, FrameWriter, MessagePack};
+use order_book::{Order, OrderBook, OrderEvent, Side, Trade};
+
+#[test]
+/// Test that framed MessagePack and bincode streams carry events and trades intact.
+fn test_framed_codecs() {
+    fn round_trip<C: Codec + Copy>(codec: C, events: &[OrderEvent], trades: &[Trade]) -> usize {
+        let mut writer = FrameWriter::new(Vec::new(), codec);
+        for (event, trade) in events.iter().zip(trades) {
+            writer.write(event).unwrap();
+            writer.write(trade).unwrap();
+        }
+        let stream = writer.into_inner();
+
+        // Feed the reader one byte at a time, as a socket might
+        struct Trickle<'a>(&'a [u8]);
+        impl std::io::Read for Trickle<'_> {
+            fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+                let Some((first, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+                buffer[0] = *first;
+                self.0 = rest;
+                Ok(1)
+            }
+        }
+        let mut reader = FrameReader::new(Trickle(&stream), codec);
+        for (event, trade) in events.iter().zip(trades) {
+            assert_eq!(reader.read::<OrderEvent>().unwrap().as_ref(), Some(event));
+            assert_eq!(reader.read::<Trade>().unwrap().as_ref(), Some(trade));
+        }
+        assert!(reader.read::<Trade>().unwrap().is_none());
+
+        let mut truncated = FrameReader::new(&stream[..stream.len() - 1], codec);
+        for _ in 1..events.len() {
+            truncated.read::<OrderEvent>().unwrap();
+            truncated.read::<Trade>().unwrap();
+        }
+        truncated.read::<OrderEvent>().unwrap();
+        assert!(matches!(
+            truncated.read::<Trade>(),
+            Err(CodecError::Io(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        stream.len()
+    }
+
+    let mut order_book = OrderBook::new();
+    let mut events = Vec::new();
+    for index in 0..20u64 {
+        events.push(
+            order_book
+                .insert_order(Order::new(100.0 + index as f64 / 8.0, index + 1, Side::Ask))
+                .event,
+        );
+    }
+    order_book.match_order(Order::new(110.00, 1_000, Side::Bid));
+    let trades = order_book.trades().to_vec();
+    assert_eq!(trades.len(), 20);
+
+    let message_pack_length = round_trip(MessagePack, &events, &trades);
+    let bincode_length = round_trip(Bincode, &events, &trades);
+    assert!(message_pack_length > 0 && bincode_length > 0);
+
+    let mut oversized = FrameReader::new(&[0xFF, 0xFF, 0xFF, 0xFF][..], Bincode);
+    assert!(matches!(
+        oversized.read::<Trade>(),
+        Err(CodecError::FrameTooLarge(_))
+    ));
+}
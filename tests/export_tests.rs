@@ -0,0 +1,82 @@
+#![cfg(feature = "arrow")]
+
+use arrow_array::{Array, Decimal128Array, StringArray, TimestampNanosecondArray, UInt64Array};
+use order_book::export::{depth_batch, schema, trades_batch};
+use order_book::{MarketDepthCache, Order, OrderBook, Side};
+
+#[test]
+/// Test that depth snapshots and trades export to record batches with the shared schema.
+fn test_arrow_export() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    for order in [
+        Order::new(99.25, 10, Side::Bid),
+        Order::new(101.75, 6, Side::Ask),
+        Order::new(102.00, 2, Side::Ask),
+    ] {
+        cache.process_order_event(order_book.insert_order(order).event);
+    }
+    for event in order_book
+        .match_order(Order::new(101.75, 4, Side::Bid))
+        .events
+    {
+        cache.process_order_event(event);
+    }
+
+    let depth = depth_batch(&cache.snapshot(), 42).unwrap();
+    assert_eq!(depth.schema(), schema());
+    assert_eq!(depth.num_rows(), 3);
+    let column = |name: &str| depth.column_by_name(name).unwrap().clone();
+    let sides = column("side");
+    let sides = sides.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!((sides.value(0), sides.value(2)), ("bid", "ask"));
+    let prices = column("price");
+    let prices = prices.as_any().downcast_ref::<Decimal128Array>().unwrap();
+    assert_eq!(prices.value_as_string(1), "101.000000000000000000");
+    let quantities = column("qty");
+    let quantities = quantities.as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(quantities.values().to_vec(), vec![10, 2, 2]);
+    let timestamps = column("ts");
+    let timestamps = timestamps
+        .as_any()
+        .downcast_ref::<TimestampNanosecondArray>()
+        .unwrap();
+    assert_eq!(timestamps.value(0), 42);
+
+    order_book.match_order(Order::new(102.00, 3, Side::Bid));
+    let trades = trades_batch(&order_book.trades()[1..], 2).unwrap();
+    assert_eq!(trades.num_rows(), 2);
+    let trade_prices = trades.column(2);
+    let trade_prices = trade_prices
+        .as_any()
+        .downcast_ref::<Decimal128Array>()
+        .unwrap();
+    assert_eq!(trade_prices.value_as_string(0), "101.750000000000000000");
+    let sequences = trades.column(4);
+    let sequences = sequences.as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(sequences.values().to_vec(), vec![2, 3]);
+    assert_eq!(trades.column(0).null_count(), 2);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+/// Test that exported batches are written as a Parquet file.
+fn test_parquet_export() {
+    use order_book::export::write_parquet;
+
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    for order in [
+        Order::new(99.25, 10, Side::Bid),
+        Order::new(101.75, 6, Side::Ask),
+    ] {
+        cache.process_order_event(order_book.insert_order(order).event);
+    }
+    order_book.match_order(Order::new(101.75, 4, Side::Bid));
+
+    let depth = depth_batch(&cache.snapshot(), 42).unwrap();
+    let trades = trades_batch(order_book.trades(), 1).unwrap();
+    let mut file = Vec::new();
+    write_parquet(&mut file, &[depth, trades]).unwrap();
+    assert!(file.starts_with(b"PAR1") && file.ends_with(b"PAR1"));
+}
@@ -1,6 +1,33 @@
-use order_book::{Decimal, MarketDepthCache, Order, OrderBook, Side};
-use parking_lot::RwLock;
+use order_book::backtest::{
+    Backtest, Fill, FillModel, Liquidity, QueueModel, Strategy, StrategyOrderId,
+};
+use order_book::latency_injection::{Delay, LatencyPipeline, PipelineDelays};
+use order_book::multicast::{
+    read_capture, CaptureFile, FeedReader, FeedWriter, MulticastError, DATAGRAM_LENGTH,
+};
+use order_book::wal::{
+    self, CheckpointSchedule, FsyncPolicy, MemoryStorage, Storage, WalError, WriteAheadLog,
+};
+use order_book::wire::{decode_v1, encode_v1, DecodeError, Record, VERSION_1};
+use order_book::{
+    event_channel, Admission, AggregatedDepthMap, AskPrice, Bbo, BboChanged, BidPrice,
+    BookObserver, BookSnapshot, BookView, BroadcastLog, BucketingPolicy, ChannelMessage,
+    Checkpoint, ClientOrderId, CoalescingBuffer, Command, Crossing, DepthAggregator,
+    DepthDeltaPublisher, DepthHistory, DepthSnapshot, DisplayQuantity, DistanceBucket, EventFanOut,
+    EventKind, ExactPriceLevelMap, ExcessPrecision, FillSummary, FloatPrecision, FloatRounding,
+    FlowCounts, FollowerBook, IdGenerator, Lapped, LevelAlert, LevelDiff, LevelInfo, LevelRemoved,
+    LuldBands, MarketByOrderEvent, MarketDepthCache, MarketDepthCacheConfig, Order, OrderBook,
+    OrderBookConfig, OrderBookError, OrderEvent, OrderFlowStats, OrderId, OrderMetadata,
+    OrderRejected, OrderSpec, OverflowCounts, OverflowPolicy, ParticipantId, Price,
+    PriceNormalization, PricePrecision, PriorityPolicy, Quantity, QuoteProtection, RateLimit,
+    SamplingSchedule, SequencePolicy, SequencedMarketByOrderEvent, SessionEvent, SharedOrderBook,
+    Side, SpreadAlert, SpreadBook, SpreadMarket, SpreadMetric, SpreadMonitor, SpreadSample,
+    SpreadTracker, Throttle, ThrottlePolicy, Trade, TradeId, TradingState,
+};
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[test]
 /// Test the order of insertion and the computation of spread by running a workflow.
@@ -10,7 +37,7 @@ fn test_order_insertion_and_spread_computation() {
 
     // Test 1: Insert a Bid (Buy) order
     let order = Order::new(99.50, 10, Side::Bid);
-    let event = order_book.insert_order(order);
+    let event = order_book.insert_order(order).event;
     market_depth_cache.process_order_event(event);
     let (best_bid, best_ask, _) = order_book.compute_spread();
     assert_eq!(
@@ -25,7 +52,7 @@ fn test_order_insertion_and_spread_computation() {
 
     // Insert another Bid at a lower price
     let order = Order::new(99.00, 5, Side::Bid);
-    let event = order_book.insert_order(order);
+    let event = order_book.insert_order(order).event;
     market_depth_cache.process_order_event(event);
     let (best_bid, _, _) = order_book.compute_spread();
     assert_eq!(
@@ -36,7 +63,7 @@ fn test_order_insertion_and_spread_computation() {
 
     // Insert an ask (sell) order
     let order = Order::new(100.25, 20, Side::Ask);
-    let event = order_book.insert_order(order);
+    let event = order_book.insert_order(order).event;
     market_depth_cache.process_order_event(event);
     let (best_bid, best_ask, _) = order_book.compute_spread();
     assert_eq!(
@@ -52,7 +79,7 @@ fn test_order_insertion_and_spread_computation() {
 
     // Insert another ask at a lower price (becomes new best ask)
     let order = Order::new(100.10, 30, Side::Ask);
-    let event = order_book.insert_order(order);
+    let event = order_book.insert_order(order).event;
     market_depth_cache.process_order_event(event);
     let (_, best_ask, _) = order_book.compute_spread();
     assert_eq!(
@@ -63,12 +90,108 @@ fn test_order_insertion_and_spread_computation() {
 
     // Verify time priority within a price level
     assert_eq!(
-        order_book.orders_at_exact_price_level(Decimal::try_from(100.25).unwrap().normalize(), Side::Ask),
+        order_book
+            .level(Decimal::try_from(100.25).unwrap().normalize(), Side::Ask)
+            .map_or(0, |level| level.order_count()),
         1,
         "Should have one order at 100.25"
     );
 }
 
+#[test]
+/// Test that level views report the count, quantity, and orders of an exact price level.
+fn test_level_view() {
+    let mut order_book = OrderBook::new();
+    let first = order_book
+        .insert_order(Order::new(100.50, 10, Side::Ask))
+        .handle;
+    let second = order_book
+        .insert_order(Order::new(100.50, 20, Side::Ask))
+        .handle;
+    let third = order_book
+        .insert_order(Order::new(100.50, 30, Side::Ask))
+        .handle;
+    order_book.insert_order(Order::new(101.00, 5, Side::Ask));
+
+    // Prices are normalized like those of incoming orders
+    let level = order_book.level(Decimal::new(10050, 2), Side::Ask).unwrap();
+    assert_eq!(level.price(), Decimal::new(1005, 1));
+    assert_eq!(level.side(), Side::Ask);
+    assert_eq!((level.order_count(), level.total_quantity()), (3, 60));
+    assert_eq!(level.orders().len(), 3);
+    assert!(order_book.level(Decimal::new(1005, 1), Side::Bid).is_none());
+    assert!(order_book.level(Decimal::new(1004, 1), Side::Ask).is_none());
+
+    // The orders follow time priority through fills and cancellations
+    order_book.match_order(Order::new(100.50, 4, Side::Bid));
+    order_book.cancel_order(second.order_id()).unwrap();
+    let level = order_book.level(Decimal::new(1005, 1), Side::Ask).unwrap();
+    let orders: Vec<(OrderId, u64)> = level
+        .orders()
+        .map(|(order_id, order)| (order_id, order.quantity))
+        .collect();
+    assert_eq!(orders, vec![(first.order_id(), 6), (third.order_id(), 30)]);
+    assert_eq!(level.total_quantity(), 36);
+
+    // Emptied levels have no view
+    order_book.cancel_order(first.order_id()).unwrap();
+    order_book.cancel_order(third.order_id()).unwrap();
+    assert!(order_book.level(Decimal::new(1005, 1), Side::Ask).is_none());
+}
+
+#[test]
+/// Test that the book's exact price levels stay apart from the cache's buckets
+fn test_exact_price_levels() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    let mut resting = Vec::new();
+    for order in [
+        Order::new(100.25, 10, Side::Bid),
+        Order::new(100.75, 5, Side::Bid),
+        Order::new(100.75, 3, Side::Bid),
+        Order::new(101.50, 7, Side::Ask),
+    ] {
+        let outcome = order_book.insert_order(order);
+        cache.process_order_event(outcome.event);
+        resting.push(outcome.handle);
+    }
+
+    assert_eq!(
+        order_book.exact_price_levels(Side::Bid),
+        ExactPriceLevelMap::from([(Decimal::new(10025, 2), 10), (Decimal::new(10075, 2), 8)])
+    );
+    assert_eq!(
+        order_book.exact_price_levels(Side::Ask),
+        ExactPriceLevelMap::from([(Decimal::new(10150, 2), 7)])
+    );
+    // The cache buckets both bid prices into one level
+    let (bid_depth, _) = cache.get_aggregated_market_depth();
+    assert_eq!(
+        bid_depth,
+        AggregatedDepthMap::from([(Decimal::new(100, 0), 18)])
+    );
+
+    // Fills and cancellations keep the exact levels up to date
+    order_book.cancel_by_handle(resting[1]).unwrap();
+    order_book.match_order(Order::new(100.00, 4, Side::Ask));
+    assert_eq!(
+        order_book.exact_quantity_at(Decimal::new(10075, 2), Side::Bid),
+        0
+    );
+    assert_eq!(
+        order_book.exact_quantity_at(Decimal::new(10025, 2), Side::Bid),
+        9
+    );
+    assert_eq!(
+        order_book.exact_price_levels(Side::Bid),
+        ExactPriceLevelMap::from([(Decimal::new(10025, 2), 9)])
+    );
+    assert_eq!(
+        order_book.exact_quantity_at(Decimal::new(100, 0), Side::Ask),
+        0
+    );
+}
+
 #[test]
 /// Test market depth aggregation logic by inserting orders at different price levels.
 fn test_market_depth_aggregation_logic() {
@@ -79,7 +202,7 @@ fn test_market_depth_aggregation_logic() {
     // Insert order at 99.50 (aggregates to 99)
     for (price, quantity) in [(99.50, 10), (99.01, 5)] {
         let order = Order::new(price, quantity, Side::Bid);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
     // Total bid level 99 should be 10 + 5 = 15
@@ -87,14 +210,14 @@ fn test_market_depth_aggregation_logic() {
     // Aggregating asks at level 100
     for (price, quantity) in [(100.25, 20), (100.99, 3)] {
         let order = Order::new(price, quantity, Side::Ask);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
     // Total ask level 100 should be 20 + 3 = 23
 
     // Running a cross-level check at level 101
     let order = Order::new(101.00, 50, Side::Ask);
-    let event = order_book.insert_order(order);
+    let event = order_book.insert_order(order).event;
     market_depth_cache.process_order_event(event);
 
     let (bid_depth, ask_depth) = market_depth_cache.get_aggregated_market_depth();
@@ -108,9 +231,7 @@ fn test_market_depth_aggregation_logic() {
         "Bid depth at 99.0 should be 15"
     );
     assert!(
-        bid_depth
-            .get(&Decimal::try_from(100.0).unwrap().normalize())
-            .is_none(),
+        !bid_depth.contains_key(&Decimal::try_from(100.0).unwrap().normalize()),
         "No bids should be aggregated at level 100"
     );
 
@@ -131,9 +252,7 @@ fn test_market_depth_aggregation_logic() {
     );
 
     // Ensure no other unexpected levels exist
-    assert!(bid_depth
-        .get(&Decimal::try_from(98.0).unwrap().normalize())
-        .is_none());
+    assert!(!bid_depth.contains_key(&Decimal::try_from(98.0).unwrap().normalize()));
 }
 
 #[test]
@@ -145,7 +264,7 @@ fn test_decimal_precision() {
     // Test prices that might cause f64 issues but must be precise with `Decimal`
     for (price, quantity) in [(100.00, 1), (100.01, 2), (99.99, 3)] {
         let order = Order::new(price, quantity, Side::Bid);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
 
@@ -209,7 +328,7 @@ fn test_concurrent_access_smoke_test() {
                 let event = {
                     let mut book = book_clone.write();
                     let order = Order::new(price, quantity, side);
-                    book.insert_order(order)
+                    book.insert_order(order).event
                 }; // Book write lock released
 
                 // 2. Writer acquires cache lock
@@ -243,6 +362,252 @@ fn test_concurrent_access_smoke_test() {
     );
 }
 
+#[test]
+/// Test that a shared book, used through the prelude, forwards its events to its cache.
+fn test_shared_order_book() {
+    use order_book::prelude::*;
+
+    let shared = SharedOrderBook::new();
+    let writers: Vec<_> = [Side::Bid, Side::Ask]
+        .into_iter()
+        .map(|side| {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                let price = match side {
+                    Side::Bid => 99.50,
+                    Side::Ask => 100.50,
+                };
+                let order_ids: Vec<OrderId> = (0..100)
+                    .map(|_| shared.insert(Order::new(price, 1, side)).handle.order_id())
+                    .collect();
+                for order_id in &order_ids[..50] {
+                    shared.cancel(*order_id).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    assert_eq!(
+        shared.bbo(),
+        Bbo {
+            best_bid: Some(Decimal::new(995, 1)),
+            best_ask: Some(Decimal::new(1005, 1)),
+        }
+    );
+    assert_eq!(
+        shared.depth(5),
+        (
+            vec![(Decimal::new(99, 0), 50)],
+            vec![(Decimal::new(100, 0), 50)]
+        )
+    );
+    assert_eq!(
+        shared.market_depth_cache().last_applied_sequence(),
+        shared.order_book().read().last_event_sequence()
+    );
+    assert_eq!(
+        shared.cancel(OrderId(0)),
+        Err(OrderBookError::OrderNotFound(OrderId(0)))
+    );
+
+    // A book shared after the fact gets a cache rebuilt from it
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(101.25, 7, Side::Ask));
+    let shared = SharedOrderBook::from(order_book);
+    assert_eq!(shared.depth(1).1, vec![(Decimal::new(101, 0), 7)]);
+}
+
+#[test]
+/// Test that an alternative depth aggregator can replace the cache in the pipelines.
+fn test_custom_depth_aggregator() {
+    /// Keeps only the quantity of each exact price, without any aggregation.
+    #[derive(Debug, Default)]
+    struct ExactDepth {
+        levels: Mutex<[std::collections::BTreeMap<Decimal, u64>; 2]>,
+        last_sequence: Mutex<u64>,
+    }
+
+    impl DepthAggregator for ExactDepth {
+        fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64) {
+            let mut levels = self.levels.lock();
+            let side = &mut levels[event.side as usize];
+            let quantity = side.entry(event.price).or_default();
+            *quantity = quantity.saturating_add_signed(event.quantity_delta);
+            let quantity = *quantity;
+            if quantity == 0 {
+                side.remove(&event.price);
+            }
+            let mut last_sequence = self.last_sequence.lock();
+            *last_sequence = (*last_sequence).max(event.sequence);
+            (event.price, quantity)
+        }
+
+        fn copy_top_levels_into(
+            &self,
+            bids: &mut Vec<(Decimal, u64)>,
+            asks: &mut Vec<(Decimal, u64)>,
+            n: usize,
+        ) {
+            let levels = self.levels.lock();
+            bids.clear();
+            bids.extend(
+                levels[Side::Bid as usize]
+                    .iter()
+                    .rev()
+                    .take(n)
+                    .map(|(price, quantity)| (*price, *quantity)),
+            );
+            asks.clear();
+            asks.extend(
+                levels[Side::Ask as usize]
+                    .iter()
+                    .take(n)
+                    .map(|(price, quantity)| (*price, *quantity)),
+            );
+        }
+
+        fn rebuild_from(&self, order_book: &OrderBook) {
+            let mut levels = self.levels.lock();
+            for side in [Side::Bid, Side::Ask] {
+                levels[side as usize] = order_book.iter(side).collect();
+            }
+        }
+
+        fn last_applied_sequence(&self) -> u64 {
+            *self.last_sequence.lock()
+        }
+
+        fn clear(&self) {
+            *self.levels.lock() = Default::default();
+        }
+    }
+
+    // The shared book feeds it like the cache
+    let depth = Arc::new(ExactDepth::default());
+    let shared = SharedOrderBook::from_parts(Arc::default(), Arc::clone(&depth));
+    shared.insert(Order::new(100.25, 10, Side::Bid));
+    let order_id = shared
+        .insert(Order::new(100.75, 5, Side::Bid))
+        .handle
+        .order_id();
+    shared.insert(Order::new(101.00, 8, Side::Ask));
+    shared.cancel(order_id).unwrap();
+    assert_eq!(
+        shared.depth(5),
+        (
+            vec![(Decimal::new(10025, 2), 10)],
+            vec![(Decimal::new(101, 0), 8)]
+        )
+    );
+    assert_eq!(depth.last_applied_sequence(), 4);
+
+    // So does the delta publisher
+    let publisher = DepthDeltaPublisher::new(Arc::clone(&depth));
+    let event = shared
+        .order_book()
+        .write()
+        .insert_order(Order::new(100.25, 2, Side::Bid))
+        .event;
+    let update = publisher.process_order_event(event);
+    assert_eq!(
+        (update.price_bucket, update.new_quantity),
+        (Decimal::new(10025, 2), 12)
+    );
+
+    // And a rebuild recovers the book's levels
+    depth.clear();
+    depth.rebuild_from(&shared.order_book().read());
+    assert_eq!(shared.depth(5).0, vec![(Decimal::new(10025, 2), 12)]);
+}
+
+#[test]
+/// Test that inserting and waiting returns only once the depth reflects the insertion.
+fn test_insert_and_wait_visible() {
+    // The cache applies events as they are forwarded
+    let shared = SharedOrderBook::new();
+    let outcome = shared
+        .insert_and_wait_visible(Order::new(100.25, 10, Side::Bid), Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(
+        shared.market_depth_cache().last_applied_sequence(),
+        outcome.event.sequence
+    );
+
+    /// Queues events for another thread to apply later.
+    #[derive(Debug, Default)]
+    struct QueuedDepth {
+        cache: MarketDepthCache,
+        queue: Mutex<Vec<OrderEvent>>,
+    }
+
+    impl QueuedDepth {
+        fn drain(&self) {
+            for event in std::mem::take(&mut *self.queue.lock()) {
+                self.cache.process_order_event(event);
+            }
+        }
+    }
+
+    impl DepthAggregator for QueuedDepth {
+        fn apply_order_event(&self, event: &OrderEvent) -> (Decimal, u64) {
+            self.queue.lock().push(event.clone());
+            (event.price, 0)
+        }
+
+        fn copy_top_levels_into(
+            &self,
+            bids: &mut Vec<(Decimal, u64)>,
+            asks: &mut Vec<(Decimal, u64)>,
+            n: usize,
+        ) {
+            self.cache.copy_top_levels_into(bids, asks, n);
+        }
+
+        fn rebuild_from(&self, order_book: &OrderBook) {
+            self.cache.rebuild_from(order_book);
+        }
+
+        fn last_applied_sequence(&self) -> u64 {
+            self.cache.last_applied_sequence()
+        }
+
+        fn clear(&self) {
+            self.cache.clear();
+        }
+    }
+
+    // Nothing drains the queue, so the wait times out, but the order rests
+    let depth = Arc::new(QueuedDepth::default());
+    let shared = SharedOrderBook::from_parts(Arc::default(), Arc::clone(&depth));
+    let error = shared
+        .insert_and_wait_visible(Order::new(101.00, 5, Side::Ask), Duration::from_millis(10))
+        .unwrap_err();
+    let OrderBookError::NotVisible { order_id, sequence } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(sequence, 1);
+    assert!(shared.order_book().read().get_order(order_id).is_some());
+
+    // A draining thread makes the next insertion visible before the call returns
+    let drainer = {
+        let depth = Arc::clone(&depth);
+        std::thread::spawn(move || {
+            while depth.last_applied_sequence() < 2 {
+                std::thread::sleep(Duration::from_millis(1));
+                depth.drain();
+            }
+        })
+    };
+    shared
+        .insert_and_wait_visible(Order::new(99.00, 7, Side::Bid), Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(shared.depth(1).0, vec![(Decimal::new(99, 0), 7)]);
+    drainer.join().unwrap();
+}
+
 #[test]
 /// Test what happens when the order book is empty.
 fn test_empty_order_book() {
@@ -270,7 +635,7 @@ fn test_cache_level_queries() {
 
     for (price, quantity, side) in [(99.50, 10, Side::Bid), (100.25, 20, Side::Ask)] {
         let order = Order::new(price, quantity, side);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
 
@@ -304,13 +669,15 @@ fn test_multiple_orders_same_price_level() {
     // Insert multiple orders at the same price level
     for quantity in [10, 20, 30] {
         let order = Order::new(100.00, quantity, Side::Bid);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
 
     // Verify the order book maintains all orders
     assert_eq!(
-        order_book.orders_at_exact_price_level(Decimal::try_from(100.00).unwrap().normalize(), Side::Bid),
+        order_book
+            .level(Decimal::try_from(100.00).unwrap().normalize(), Side::Bid)
+            .map_or(0, |level| level.order_count()),
         3,
         "Should have 3 orders at price level 100.00"
     );
@@ -324,6 +691,478 @@ fn test_multiple_orders_same_price_level() {
     );
 }
 
+#[test]
+/// Test that the cache counts the orders of each aggregated level from events.
+fn test_cache_level_order_counts() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    let level = Decimal::new(100, 0);
+
+    let mut order_ids = Vec::new();
+    for (price, quantity) in [(100.25, 10), (100.50, 20), (100.75, 30)] {
+        let outcome = order_book.insert_order(Order::new(price, quantity, Side::Ask));
+        assert_eq!(outcome.event.order_count_delta, 1);
+        market_depth_cache.process_order_event(outcome.event);
+        order_ids.push(outcome.handle.order_id());
+    }
+    assert_eq!(
+        market_depth_cache.get_level_info(level, Side::Ask),
+        LevelInfo {
+            quantity: 60,
+            order_count: 3,
+        }
+    );
+
+    // Partial executions and reductions keep the order, full executions remove it
+    let outcome = order_book.match_order(Order::new(100.50, 15, Side::Bid));
+    let order_count_deltas: Vec<i64> = outcome
+        .events
+        .iter()
+        .map(|event| event.order_count_delta)
+        .collect();
+    assert_eq!(order_count_deltas, vec![-1, 0]);
+    for event in outcome.events {
+        market_depth_cache.process_order_event(event);
+    }
+    market_depth_cache.process_order_event(order_book.reduce_order(order_ids[2], 5).unwrap());
+    assert_eq!(
+        market_depth_cache.get_level_info(level, Side::Ask),
+        LevelInfo {
+            quantity: 40,
+            order_count: 2,
+        }
+    );
+
+    // Coalesced events carry the net change in orders, even when quantities cancel out
+    let coalescing_buffer = CoalescingBuffer::new();
+    coalescing_buffer.push(order_book.cancel_order(order_ids[1]).unwrap());
+    for quantity in [10, 5] {
+        let outcome = order_book.insert_order(Order::new(100.00, quantity, Side::Ask));
+        coalescing_buffer.push(outcome.event);
+    }
+    let coalesced = coalescing_buffer.drain();
+    assert_eq!(coalesced.len(), 1);
+    assert_eq!(
+        (coalesced[0].quantity_delta, coalesced[0].order_count_delta),
+        (0, 1)
+    );
+    for event in coalesced {
+        market_depth_cache.process_order_event(event);
+    }
+
+    let (_, ask_levels) = market_depth_cache.get_aggregated_levels();
+    assert_eq!(
+        ask_levels.get(&level),
+        Some(&LevelInfo {
+            quantity: 40,
+            order_count: 3,
+        })
+    );
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(level, Side::Ask),
+        40
+    );
+    assert_eq!(
+        market_depth_cache.get_level_info(Decimal::new(99, 0), Side::Ask),
+        LevelInfo::default()
+    );
+}
+
+#[test]
+/// Test that a cache with exact levels keeps un-aggregated depth next to the buckets.
+fn test_cache_exact_levels() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        exact_levels: true,
+        ..MarketDepthCacheConfig::default()
+    });
+    assert_eq!(MarketDepthCache::new().exact_snapshot(), None);
+
+    let mut order_ids = Vec::new();
+    for (price, quantity, side) in [
+        (99.25, 10, Side::Bid),
+        (99.75, 20, Side::Bid),
+        (99.75, 5, Side::Bid),
+        (100.50, 30, Side::Ask),
+    ] {
+        let outcome = order_book.insert_order(Order::new(price, quantity, side));
+        market_depth_cache.process_order_event(outcome.event);
+        order_ids.push(outcome.handle.order_id());
+    }
+    market_depth_cache.process_order_event(order_book.cancel_order(order_ids[0]).unwrap());
+
+    let snapshot = market_depth_cache.exact_snapshot().unwrap();
+    assert_eq!(snapshot.sequence, order_book.last_event_sequence());
+    assert_eq!(
+        snapshot.bids.into_iter().collect::<Vec<_>>(),
+        vec![(Decimal::new(9975, 2), 25)]
+    );
+    assert_eq!(
+        snapshot.asks.into_iter().collect::<Vec<_>>(),
+        vec![(Decimal::new(1005, 1), 30)]
+    );
+    let (bid_levels, _) = market_depth_cache.get_exact_levels().unwrap();
+    assert_eq!(bid_levels[&Decimal::new(9975, 2)].order_count, 2);
+
+    // The aggregated depth is unaffected by the exact levels
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(99, 0), Side::Bid),
+        25
+    );
+
+    // Rebuilding from the book restores the exact levels as well
+    let expected_levels = market_depth_cache.get_exact_levels();
+    market_depth_cache.clear();
+    assert_eq!(
+        market_depth_cache.exact_snapshot().unwrap(),
+        DepthSnapshot::default()
+    );
+    market_depth_cache.rebuild_from(&order_book);
+    assert_eq!(market_depth_cache.get_exact_levels(), expected_levels);
+}
+
+#[test]
+/// Test driving the cache with absolute level quantities from an external feed
+fn test_cache_absolute_levels() {
+    // Without exact levels, each update sets the aggregated level of its price
+    let market_depth_cache = MarketDepthCache::new();
+    let event = market_depth_cache
+        .apply_absolute_level(Decimal::new(9950, 2), 12, Side::Bid)
+        .unwrap();
+    assert_eq!((event.quantity_delta, event.order_count_delta), (12, 0));
+    market_depth_cache.apply_absolute_level(Decimal::new(9925, 2), 7, Side::Bid);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(99, 0), Side::Bid),
+        7
+    );
+    assert_eq!(
+        market_depth_cache.apply_absolute_level(Decimal::new(99, 0), 7, Side::Bid),
+        None
+    );
+    market_depth_cache.apply_absolute_level(Decimal::new(99, 0), 0, Side::Bid);
+    assert_eq!(market_depth_cache.get_aggregated_market_depth().0.len(), 0);
+    assert_eq!(market_depth_cache.last_applied_sequence(), 0);
+
+    // With exact levels, each update sets its exact price within the bucket
+    let market_depth_cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        exact_levels: true,
+        ..MarketDepthCacheConfig::default()
+    });
+    for (price, quantity) in [(10010, 5), (10090, 8), (10010, 3)] {
+        market_depth_cache.apply_absolute_level(Decimal::new(price, 2), quantity, Side::Ask);
+    }
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Ask),
+        11
+    );
+    let snapshot = market_depth_cache.exact_snapshot().unwrap();
+    assert_eq!(
+        snapshot.asks.into_iter().collect::<Vec<_>>(),
+        vec![(Decimal::new(10010, 2), 3), (Decimal::new(10090, 2), 8)]
+    );
+
+    // Deleting the last exact price of a bucket removes the bucket
+    market_depth_cache.apply_absolute_level(Decimal::new(10010, 2), 0, Side::Ask);
+    market_depth_cache.apply_absolute_level(Decimal::new(10090, 2), 0, Side::Ask);
+    assert_eq!(market_depth_cache.get_aggregated_market_depth().1.len(), 0);
+    assert_eq!(
+        market_depth_cache.exact_snapshot().unwrap(),
+        DepthSnapshot::default()
+    );
+}
+
+#[test]
+/// Test that the cache's drift report quantifies per-level and total divergence from a venue snapshot
+fn test_cache_drift_report() {
+    let market_depth_cache = MarketDepthCache::new();
+    for (price, quantity) in [(9900, 10), (10000, 20), (10100, 5)] {
+        market_depth_cache.apply_absolute_level(Decimal::new(price, 2), quantity, Side::Ask);
+    }
+
+    // An identical snapshot reports no drift
+    let mirrored = market_depth_cache.get_aggregated_market_depth().1;
+    let report = market_depth_cache.drift_report(&mirrored, Side::Ask);
+    assert!(report.is_consistent());
+    assert_eq!(report.compared_levels, 3);
+    assert_eq!((report.cached_quantity, report.exchange_quantity), (35, 35));
+    assert_eq!(report.drift_ratio(), 0.0);
+
+    // Differing, missing, and extra levels are listed best first, and the snapshot's
+    // prices are normalized like the cache's
+    let exchange_snapshot = AggregatedDepthMap::from([
+        (Decimal::new(99000, 3), 10),
+        (Decimal::new(10000, 2), 12),
+        (Decimal::new(10200, 2), 4),
+    ]);
+    let report = market_depth_cache.drift_report(&exchange_snapshot, Side::Ask);
+    let levels: Vec<_> = report
+        .levels
+        .iter()
+        .map(|level| (level.price, level.cached_quantity, level.exchange_quantity))
+        .collect();
+    assert_eq!(
+        levels,
+        vec![
+            (Decimal::new(100, 0), 20, 12),
+            (Decimal::new(101, 0), 5, 0),
+            (Decimal::new(102, 0), 0, 4),
+        ]
+    );
+    assert_eq!(report.compared_levels, 4);
+    assert_eq!((report.cached_quantity, report.exchange_quantity), (35, 26));
+    assert_eq!(report.absolute_difference, 8 + 5 + 4);
+    assert_eq!(report.drift_ratio(), 17.0 / 26.0);
+
+    // Bids are reported from the highest price, and an empty venue side drifts infinitely
+    market_depth_cache.apply_absolute_level(Decimal::new(97, 0), 3, Side::Bid);
+    market_depth_cache.apply_absolute_level(Decimal::new(98, 0), 2, Side::Bid);
+    let report = market_depth_cache.drift_report(&AggregatedDepthMap::new(), Side::Bid);
+    assert_eq!(report.levels[0].price, Decimal::new(98, 0));
+    assert_eq!(report.levels[1].absolute_difference(), 3);
+    assert_eq!(report.drift_ratio(), f64::INFINITY);
+}
+
+#[test]
+/// Test that side-aware bucketing rounds bids down and asks up, incrementally and on rebuild
+fn test_cache_bucketing_policy() {
+    let mut order_book = OrderBook::new();
+    let symmetric = MarketDepthCache::new();
+    let side_aware = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        bucketing_policy: BucketingPolicy::SideAware,
+        ..MarketDepthCacheConfig::default()
+    });
+    for order in [
+        Order::new(99.75, 10, Side::Bid),
+        Order::new(99.00, 5, Side::Bid),
+        Order::new(100.25, 4, Side::Ask),
+        Order::new(101.00, 6, Side::Ask),
+    ] {
+        let event = order_book.insert_order(order).event;
+        symmetric.process_order_event(event.clone());
+        side_aware.process_order_event(event);
+    }
+
+    // Truncation folds the bid at 99.75 and the ask at 100.25 onto the levels below them
+    let (bids, asks) = symmetric.get_aggregated_market_depth();
+    assert_eq!(bids, AggregatedDepthMap::from([(Decimal::new(99, 0), 15)]));
+    assert_eq!(
+        asks,
+        AggregatedDepthMap::from([(Decimal::new(100, 0), 4), (Decimal::new(101, 0), 6)])
+    );
+
+    // Side-aware bucketing never shows an ask below its price, and integer prices stay put
+    let (bids, asks) = side_aware.get_aggregated_market_depth();
+    assert_eq!(bids, AggregatedDepthMap::from([(Decimal::new(99, 0), 15)]));
+    assert_eq!(asks, AggregatedDepthMap::from([(Decimal::new(101, 0), 10)]));
+
+    // Absolute levels and rebuilds bucket the same way
+    assert_eq!(
+        side_aware.apply_absolute_level(Decimal::new(10050, 2), 7, Side::Ask),
+        Some(OrderEvent {
+            price: Decimal::new(10050, 2),
+            quantity_delta: -3,
+            side: Side::Ask,
+            timestamp_nanos: 0,
+            sequence: 0,
+            order_count_delta: 0,
+        })
+    );
+    side_aware.rebuild_from(&order_book);
+    assert_eq!(
+        side_aware.get_quantity_at_level(Decimal::new(101, 0), Side::Ask),
+        10
+    );
+    assert_eq!(side_aware.get_aggregated_market_depth().0, bids);
+}
+
+#[test]
+/// Test that price watches fire once a level moved by their threshold
+fn test_cache_watch_price() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.50, 5, Side::Bid))
+            .event,
+    );
+
+    let alerts = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let every_change = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = Arc::clone(&alerts);
+    cache.watch_price(Decimal::new(10099, 2), Side::Bid, 10, move |alert| {
+        seen.lock().unwrap().push(*alert);
+    });
+    let seen = Arc::clone(&every_change);
+    let watch_id = cache.watch_price(Decimal::new(100, 0), Side::Bid, 0, move |alert| {
+        seen.lock().unwrap().push(alert.quantity);
+    });
+    assert_eq!(cache.watched_price_count(), 2);
+
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.25, 4, Side::Bid))
+            .event,
+    );
+    let outcome = order_book.insert_order(Order::new(100.75, 8, Side::Bid));
+    cache.process_order_event(outcome.event);
+    // Other sides and levels are not watched
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.00, 3, Side::Ask))
+            .event,
+    );
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(99.00, 3, Side::Bid))
+            .event,
+    );
+    cache.process_order_event(order_book.cancel_order(outcome.handle.order_id()).unwrap());
+
+    // The level grew from 5 to 9 and 17, then shrank back to 9
+    assert_eq!(*every_change.lock().unwrap(), vec![9, 17, 9]);
+    assert_eq!(
+        *alerts.lock().unwrap(),
+        vec![LevelAlert {
+            price: Decimal::new(100, 0),
+            side: Side::Bid,
+            previous_quantity: 5,
+            quantity: 17,
+        }]
+    );
+
+    assert!(cache.unwatch_price(watch_id));
+    assert!(!cache.unwatch_price(watch_id));
+    assert_eq!(cache.watched_price_count(), 1);
+
+    // Clearing and rebuilding report the watched level, and absolute levels move it
+    cache.clear();
+    cache.rebuild_from(&order_book);
+    cache.apply_absolute_level(Decimal::new(100, 0), 25, Side::Bid);
+    let quantities: Vec<(u64, u64)> = alerts
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|alert| (alert.previous_quantity, alert.quantity))
+        .collect();
+    assert_eq!(quantities, vec![(5, 17), (17, 0), (0, 25)]);
+    assert_eq!(every_change.lock().unwrap().len(), 3);
+}
+
+#[test]
+/// Test that aggregated totals saturate and count their overflows
+fn test_aggregation_overflow() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    let quantity = i64::MAX as u64;
+    for _ in 0..2 {
+        let event = order_book
+            .insert_order(Order::new(100.00, quantity, Side::Bid))
+            .event;
+        market_depth_cache.process_order_event(event);
+    }
+    assert_eq!(order_book.aggregation_overflow_count(), 0);
+    assert_eq!(market_depth_cache.aggregation_overflow_count(), 0);
+
+    // The third order pushes the level and the side's volume past u64::MAX
+    let event = order_book
+        .insert_order(Order::new(100.00, quantity, Side::Bid))
+        .event;
+    market_depth_cache.process_order_event(event);
+    assert_eq!(order_book.aggregation_overflow_count(), 2);
+    assert_eq!(order_book.total_volume(Side::Bid), u64::MAX);
+    assert_eq!(order_book.liquidity_curve(Side::Bid, 1)[0].1, u64::MAX);
+    assert_eq!(market_depth_cache.aggregation_overflow_count(), 1);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid),
+        u64::MAX
+    );
+
+    // Removing more than the cache holds saturates at zero instead of wrapping
+    market_depth_cache.process_order_event(OrderEvent::new(Decimal::new(101, 0), -5, Side::Ask));
+    assert_eq!(market_depth_cache.aggregation_overflow_count(), 2);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(101, 0), Side::Ask),
+        0
+    );
+}
+
+#[test]
+/// Test that quantities beyond the range of a quantity delta are rejected or saturated
+fn test_quantity_delta_overflow() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    let quantity = i64::MAX as u64 + 10;
+
+    // The fallible entry points refuse the order
+    let order = Order::new(100.00, quantity, Side::Ask);
+    assert_eq!(
+        order_book.try_insert_order(order.clone()),
+        Err(OrderBookError::QuantityTooLarge(quantity))
+    );
+    assert_eq!(
+        order_book.apply(Command::Match(order.clone())),
+        Err(OrderBookError::QuantityTooLarge(quantity))
+    );
+    assert_eq!(order_book.order_count(), 0);
+    assert_eq!(order_book.last_event_sequence(), 0);
+
+    // The infallible ones rest it saturated to what its event can carry, and say so
+    let outcome = order_book.insert_order(order);
+    assert_eq!(outcome.event.quantity_delta, i64::MAX);
+    assert_eq!(outcome.saturated_from, Some(quantity));
+    market_depth_cache.process_order_event(outcome.event);
+    let order_id = outcome.handle.order_id();
+    assert_eq!(order_book.aggregation_overflow_count(), 1);
+    assert_eq!(
+        order_book.get_order(order_id).unwrap().quantity,
+        i64::MAX as u64
+    );
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Ask),
+        order_book.total_volume(Side::Ask)
+    );
+
+    assert_eq!(
+        order_book.replace_order(order_id, Decimal::new(101, 0), quantity),
+        Err(OrderBookError::QuantityTooLarge(quantity))
+    );
+
+    // Executions and cancellations then remove what the events added
+    let outcome = order_book.match_order(Order::new(100.00, quantity, Side::Bid));
+    assert_eq!(outcome.saturated_from, None);
+    for event in outcome.events {
+        market_depth_cache.process_order_event(event);
+    }
+    assert!(order_book.get_order(order_id).is_none());
+    let resting_bid = outcome.handle.unwrap();
+    assert_eq!(order_book.aggregation_overflow_count(), 1);
+    assert_eq!(order_book.total_volume(Side::Ask), 0);
+    assert_eq!(market_depth_cache.ask_levels_count(), 0);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid),
+        order_book.total_volume(Side::Bid)
+    );
+    let event = order_book.cancel_by_handle(resting_bid).unwrap();
+    assert_eq!(event.quantity_delta, -10);
+    market_depth_cache.process_order_event(event);
+    assert_eq!(market_depth_cache.bid_levels_count(), 0);
+    assert_eq!(market_depth_cache.aggregation_overflow_count(), 0);
+
+    // An absolute update too far from the current quantity saturates its delta
+    let exact = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        exact_levels: true,
+        ..MarketDepthCacheConfig::default()
+    });
+    exact.apply_absolute_level(Decimal::new(100, 0), u64::MAX, Side::Bid);
+    assert_eq!(exact.aggregation_overflow_count(), 1);
+    let event = exact
+        .apply_absolute_level(Decimal::new(100, 0), 0, Side::Bid)
+        .unwrap();
+    assert_eq!(event.quantity_delta, -i64::MAX);
+    assert_eq!(exact.aggregation_overflow_count(), 1);
+    assert_eq!(exact.bid_levels_count(), 0);
+}
+
 #[test]
 /// Test if clearing the cache works as expected.
 fn test_cache_clear() {
@@ -332,7 +1171,7 @@ fn test_cache_clear() {
 
     for (price, quantity, side) in [(99.50, 10, Side::Bid), (100.25, 20, Side::Ask)] {
         let order = Order::new(price, quantity, side);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
 
@@ -345,6 +1184,69 @@ fn test_cache_clear() {
     assert_eq!(market_depth_cache.ask_levels_count(), 0);
 }
 
+#[test]
+/// Test that waiting for a cache update wakes on changes and times out without them.
+fn test_cache_wait_for_update() {
+    let cache = Arc::new(MarketDepthCache::new());
+    let mut order_book = OrderBook::new();
+
+    // Nothing changes, so both waits time out
+    assert!(!cache.wait_for_update(Duration::from_millis(10)));
+    let update_count = cache.update_count();
+    assert_eq!(
+        cache.wait_for_update_since(update_count, Duration::from_millis(10)),
+        None
+    );
+
+    // A change made before waiting is not missed
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.00, 10, Side::Bid))
+            .event,
+    );
+    assert_eq!(
+        cache.wait_for_update_since(update_count, Duration::ZERO),
+        Some(update_count + 1)
+    );
+
+    // Waiters block until a writer changes the depth
+    let seen_update_count = cache.update_count();
+    let events: Vec<OrderEvent> = [101.00, 102.00]
+        .into_iter()
+        .map(|price| {
+            order_book
+                .insert_order(Order::new(price, 5, Side::Ask))
+                .event
+        })
+        .collect();
+    let waiters: Vec<_> = (0..3)
+        .map(|_| {
+            let cache = Arc::clone(&cache);
+            std::thread::spawn(move || {
+                let update_count =
+                    cache.wait_for_update_since(seen_update_count, Duration::from_secs(10));
+                (update_count, cache.ask_levels_count())
+            })
+        })
+        .collect();
+    std::thread::sleep(Duration::from_millis(20));
+    for event in events {
+        cache.process_order_event(event);
+    }
+    for waiter in waiters {
+        let (update_count, ask_levels_count) = waiter.join().unwrap();
+        assert!(update_count.unwrap() > seen_update_count);
+        assert!(ask_levels_count > 0);
+    }
+
+    // Absolute levels, rebuilds, and clears are changes too
+    let update_count = cache.update_count();
+    cache.apply_absolute_level(Decimal::new(99, 0), 4, Side::Bid);
+    cache.rebuild_from(&order_book);
+    cache.clear();
+    assert_eq!(cache.update_count(), update_count + 3);
+}
+
 #[test]
 /// Test the price aggregation by taking some boundary cases.
 fn test_price_aggregation_boundary_cases() {
@@ -354,7 +1256,7 @@ fn test_price_aggregation_boundary_cases() {
     // Test boundary cases for aggregation
     for (price, quantity) in [(99.00, 1), (99.99, 2), (100.00, 3), (100.01, 4)] {
         let order = Order::new(price, quantity, Side::Bid);
-        let event = order_book.insert_order(order);
+        let event = order_book.insert_order(order).event;
         market_depth_cache.process_order_event(event);
     }
 
@@ -378,3 +1280,4997 @@ fn test_price_aggregation_boundary_cases() {
         "Level 100 should have 3 + 4 = 7"
     );
 }
+
+#[test]
+/// Test that cancelling by identifier and by handle removes the order and updates the cache.
+fn test_order_cancellation() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    let mut handles = vec![];
+    for quantity in [10, 20, 30] {
+        let outcome = order_book.insert_order(Order::new(100.50, quantity, Side::Bid));
+        market_depth_cache.process_order_event(outcome.event);
+        handles.push(outcome.handle);
+    }
+    let level = Decimal::try_from(100.0).unwrap().normalize();
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(level, Side::Bid),
+        60
+    );
+
+    // Cancel the middle order by identifier
+    let event = order_book.cancel_order(handles[1].order_id()).unwrap();
+    assert_eq!(event.quantity_delta, -20);
+    market_depth_cache.process_order_event(event);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(level, Side::Bid),
+        40
+    );
+    assert!(order_book.get_order(handles[1].order_id()).is_none());
+
+    // Cancel the remaining orders by handle
+    for handle in [handles[0], handles[2]] {
+        let event = order_book.cancel_by_handle(handle).unwrap();
+        market_depth_cache.process_order_event(event);
+    }
+
+    assert_eq!(order_book.order_count(), 0);
+    assert_eq!(order_book.bid_levels_count(), 0);
+    assert_eq!(
+        market_depth_cache.bid_levels_count(),
+        0,
+        "Emptied aggregated level should be removed from the cache"
+    );
+    assert_eq!(order_book.compute_spread().0, None);
+}
+
+#[test]
+/// Test that handles become stale once their order has left the book, even if the slot is reused.
+fn test_stale_order_handle() {
+    let mut order_book = OrderBook::new();
+
+    let handle = order_book
+        .insert_order(Order::new(101.00, 10, Side::Ask))
+        .handle;
+    order_book.cancel_by_handle(handle).unwrap();
+
+    // The freed slot is reused by the next order
+    let replacement = order_book
+        .insert_order(Order::new(101.00, 15, Side::Ask))
+        .handle;
+    assert_ne!(handle.order_id(), replacement.order_id());
+
+    assert_eq!(
+        order_book.cancel_by_handle(handle),
+        Err(OrderBookError::StaleHandle(handle.order_id()))
+    );
+    assert_eq!(
+        order_book.cancel_order(handle.order_id()),
+        Err(OrderBookError::OrderNotFound(handle.order_id()))
+    );
+    assert_eq!(
+        order_book
+            .get_order(replacement.order_id())
+            .map(|order| order.quantity),
+        Some(15),
+        "The order reusing the slot must be untouched"
+    );
+}
+
+#[test]
+/// Test that reducing an order keeps its time priority and rejects invalid quantities.
+fn test_reduce_order_keeps_priority() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    let mut order_ids = vec![];
+    for quantity in [50, 30] {
+        let outcome = order_book.insert_order(Order::new(99.75, quantity, Side::Ask));
+        market_depth_cache.process_order_event(outcome.event);
+        order_ids.push(outcome.handle.order_id());
+    }
+
+    let event = order_book.reduce_order(order_ids[0], 20).unwrap();
+    market_depth_cache.process_order_event(event);
+
+    let level = Decimal::try_from(99.0).unwrap().normalize();
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(level, Side::Ask),
+        60
+    );
+    assert_eq!(order_book.get_order(order_ids[0]).unwrap().quantity, 30);
+    assert_eq!(
+        order_book
+            .level(Decimal::try_from(99.75).unwrap(), Side::Ask)
+            .map_or(0, |level| level.order_count()),
+        2,
+        "A reduced order must stay in the book"
+    );
+
+    assert_eq!(
+        order_book.reduce_order(order_ids[1], 31),
+        Err(OrderBookError::InvalidReduction {
+            order_id: order_ids[1],
+            remaining: 30,
+            requested: 31,
+        })
+    );
+    assert!(order_book.reduce_order(order_ids[1], 0).is_err());
+
+    // Reducing by the whole remaining quantity removes the order
+    let event = order_book.reduce_order(order_ids[1], 30).unwrap();
+    assert_eq!(event.quantity_delta, -30);
+    assert!(order_book.get_order(order_ids[1]).is_none());
+    assert_eq!(order_book.order_count(), 1);
+}
+
+#[test]
+/// Test that compare-and-cancel only cancels orders whose remaining quantity is unchanged.
+fn test_cancel_if_remaining() {
+    let mut order_book = OrderBook::new();
+    let order_id = order_book
+        .insert_order(Order::new(100.00, 10, Side::Ask))
+        .handle
+        .order_id();
+
+    // A fill between the gateway's read and its cancel leaves the order resting
+    let read_remaining = order_book.get_order(order_id).unwrap().quantity;
+    order_book.match_order(Order::new(100.00, 3, Side::Bid));
+    assert_eq!(
+        order_book.cancel_if_remaining(order_id, read_remaining),
+        Err(OrderBookError::RemainingMismatch {
+            order_id,
+            expected: 10,
+            remaining: 7,
+        })
+    );
+    assert_eq!(order_book.total_volume(Side::Ask), 7);
+
+    // Reading again and cancelling what is left succeeds
+    let event = order_book.cancel_if_remaining(order_id, 7).unwrap();
+    assert_eq!(event.quantity_delta, -7);
+    assert_eq!(order_book.order_count(), 0);
+
+    // Fully filled orders are no longer found
+    let order_id = order_book
+        .insert_order(Order::new(100.00, 2, Side::Ask))
+        .handle
+        .order_id();
+    order_book.match_order(Order::new(100.00, 2, Side::Bid));
+    assert_eq!(
+        order_book.cancel_if_remaining(order_id, 2),
+        Err(OrderBookError::OrderNotFound(order_id))
+    );
+    assert_eq!(
+        OrderBookError::RemainingMismatch {
+            order_id,
+            expected: 2,
+            remaining: 1,
+        }
+        .to_string(),
+        format!("order {order_id} has 1 remaining, not the expected 2")
+    );
+}
+
+#[test]
+/// Test queue position estimation as orders ahead are reduced and cancelled.
+fn test_queue_position() {
+    let mut order_book = OrderBook::new();
+
+    let order_ids: Vec<_> = [10, 20, 30, 40, 50]
+        .into_iter()
+        .map(|quantity| {
+            order_book
+                .insert_order(Order::new(100.25, quantity, Side::Bid))
+                .handle
+                .order_id()
+        })
+        .collect();
+
+    let expected = [(0, 0), (1, 10), (2, 30), (3, 60), (4, 100)];
+    for (order_id, (orders_ahead, quantity_ahead)) in order_ids.iter().zip(expected) {
+        let position = order_book.queue_position(*order_id).unwrap();
+        assert_eq!(position.orders_ahead, orders_ahead);
+        assert_eq!(position.quantity_ahead, quantity_ahead);
+    }
+
+    // Orders ahead leaving or shrinking move the last order forward
+    order_book.cancel_order(order_ids[1]).unwrap();
+    order_book.reduce_order(order_ids[3], 15).unwrap();
+    let position = order_book.queue_position(order_ids[4]).unwrap();
+    assert_eq!(position.orders_ahead, 3);
+    assert_eq!(position.quantity_ahead, 10 + 30 + 25);
+
+    assert!(order_book.queue_position(order_ids[1]).is_none());
+}
+
+#[test]
+/// Test that an aggressive order executes in price-time priority and rests its remainder.
+fn test_match_order_price_time_priority() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    let mut ask_ids = vec![];
+    for (price, quantity) in [(100.10, 10), (100.05, 5), (100.10, 20)] {
+        let outcome = order_book.insert_order(Order::new(price, quantity, Side::Ask));
+        market_depth_cache.process_order_event(outcome.event);
+        ask_ids.push(outcome.handle.order_id());
+    }
+
+    let outcome = order_book.match_order(Order::new(100.10, 25, Side::Bid));
+    for event in outcome.events {
+        market_depth_cache.process_order_event(event);
+    }
+
+    // 5 @ 100.05 first, then 10 @ 100.10 (older), then 10 of the 20 @ 100.10
+    let fills: Vec<_> = order_book
+        .trades()
+        .iter()
+        .map(|trade| (trade.price, trade.quantity))
+        .collect();
+    assert_eq!(
+        fills,
+        vec![
+            (Decimal::try_from(100.05).unwrap(), 5),
+            (Decimal::try_from(100.10).unwrap(), 10),
+            (Decimal::try_from(100.10).unwrap(), 10),
+        ]
+    );
+    assert!(order_book
+        .trades()
+        .iter()
+        .all(|trade| trade.aggressor_side == Side::Bid));
+    assert!(
+        outcome.handle.is_none(),
+        "A fully filled order must not rest"
+    );
+
+    assert!(order_book.get_order(ask_ids[0]).is_none());
+    assert!(order_book.get_order(ask_ids[1]).is_none());
+    assert_eq!(order_book.get_order(ask_ids[2]).unwrap().quantity, 10);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::try_from(100.0).unwrap(), Side::Ask),
+        10
+    );
+
+    // A non-crossing order simply rests
+    let outcome = order_book.match_order(Order::new(100.00, 7, Side::Bid));
+    assert!(outcome.handle.is_some());
+    assert_eq!(order_book.trades().len(), 3);
+    let (best_bid, best_ask, _) = order_book.compute_spread();
+    assert_eq!(best_bid, Some(Decimal::try_from(100.00).unwrap()));
+    assert_eq!(best_ask, Some(Decimal::try_from(100.10).unwrap()));
+}
+
+#[test]
+/// Test that the priority policy decides which order of a level executes first
+fn test_priority_policies() {
+    let fills = |priority_policy| {
+        let mut order_book = OrderBook::new();
+        order_book.set_priority_policy(priority_policy);
+        assert_eq!(order_book.priority_policy(), priority_policy);
+        for quantity in [20, 50, 10, 50] {
+            order_book.insert_order(Order::new(100.00, quantity, Side::Ask));
+        }
+        // A better price still executes first, whatever the policy
+        order_book.insert_order(Order::new(99.00, 5, Side::Ask));
+        order_book.match_order(Order::new(100.00, 100, Side::Bid));
+        let makers: Vec<(u64, u64)> = order_book
+            .trades()
+            .iter()
+            .map(|trade| (trade.maker_order_id.0, trade.quantity))
+            .collect();
+        (makers, order_book.snapshot().asks)
+    };
+
+    let (makers, asks) = fills(PriorityPolicy::Fifo);
+    assert_eq!(makers, vec![(5, 5), (1, 20), (2, 50), (3, 10), (4, 15)]);
+    assert_eq!(asks[0].0, OrderId(4));
+    let (makers, asks) = fills(PriorityPolicy::Lifo);
+    assert_eq!(makers, vec![(5, 5), (4, 50), (3, 10), (2, 35)]);
+    assert_eq!((asks[0].0, asks[1].0), (OrderId(1), OrderId(2)));
+    // The largest first, the oldest among equals, and the next largest after a partial
+    // fill shrinks one below it
+    let (makers, asks) = fills(PriorityPolicy::SizePriority);
+    assert_eq!(makers, vec![(5, 5), (2, 50), (4, 45)]);
+    assert_eq!(
+        asks.iter()
+            .map(|(order_id, order)| (order_id.0, order.quantity))
+            .collect::<Vec<_>>(),
+        vec![(1, 20), (3, 10), (4, 5)]
+    );
+
+    // Orders rest in arrival order, so switching policies mid-book applies at once
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(100.00, 30, Side::Bid));
+    order_book.set_priority_policy(PriorityPolicy::SizePriority);
+    order_book.match_order(Order::new(100.00, 5, Side::Ask));
+    order_book.set_priority_policy(PriorityPolicy::Fifo);
+    order_book.match_order(Order::new(100.00, 5, Side::Ask));
+    let makers: Vec<OrderId> = order_book
+        .trades()
+        .iter()
+        .map(|trade| trade.maker_order_id)
+        .collect();
+    assert_eq!(makers, vec![OrderId(2), OrderId(1)]);
+}
+
+#[test]
+/// Test that iceberg orders reload fixed or randomized slices from their reserve
+fn test_iceberg_reloads() {
+    let mut order_book = OrderBook::new();
+    order_book.set_market_by_order_events(true);
+    let iceberg = order_book
+        .insert_iceberg(
+            Order::new(100.00, 25, Side::Ask),
+            DisplayQuantity::Fixed(10),
+        )
+        .unwrap()
+        .handle
+        .order_id();
+    let behind = order_book
+        .insert_order(Order::new(100.00, 5, Side::Ask))
+        .handle
+        .order_id();
+    assert_eq!(order_book.get_order(iceberg).unwrap().quantity, 10);
+    assert_eq!(order_book.hidden_quantity(iceberg), Some(15));
+
+    // Executing the slice in full reloads the next one at the back of the level, with
+    // the events of a new order
+    order_book.take_market_by_order_events();
+    let outcome = order_book.match_order(Order::new(100.00, 12, Side::Bid));
+    let deltas: Vec<(i64, i64)> = outcome
+        .events
+        .iter()
+        .map(|event| (event.quantity_delta, event.order_count_delta))
+        .collect();
+    assert_eq!(deltas, vec![(-10, -1), (10, 1), (-2, 0)]);
+    let events = order_book.take_market_by_order_events();
+    assert!(matches!(
+        events[..2],
+        [
+            MarketByOrderEvent::Executed { order_id: executed, remaining_quantity: 0, .. },
+            MarketByOrderEvent::Added { order_id: added, quantity: 10, .. },
+        ] if executed == iceberg && added == iceberg
+    ));
+    assert_eq!(order_book.trades()[1].maker_order_id, behind);
+    assert_eq!(order_book.hidden_quantity(iceberg), Some(5));
+    assert_eq!(order_book.order_fills(iceberg).unwrap().filled_quantity, 10);
+
+    // The last slice is capped by the reserve, which is then exhausted
+    order_book.match_order(Order::new(100.00, 13, Side::Bid));
+    assert_eq!(order_book.get_order(iceberg).unwrap().quantity, 5);
+    assert_eq!(order_book.hidden_quantity(iceberg), None);
+    order_book.match_order(Order::new(100.00, 5, Side::Bid));
+    assert!(order_book.get_order(iceberg).is_none());
+    let executed: u64 = order_book
+        .trades()
+        .iter()
+        .filter(|trade| trade.maker_order_id == iceberg)
+        .map(|trade| trade.quantity)
+        .sum();
+    assert_eq!(executed, 25);
+
+    // Randomized slices stay within their bounds and are reproducible from the seed
+    let slices = |seed| {
+        let mut order_book = OrderBook::deterministic(seed);
+        let display = DisplayQuantity::Random { min: 3, max: 9 };
+        let order_id = order_book
+            .insert_iceberg(Order::new(100.00, 1_000, Side::Bid), display)
+            .unwrap()
+            .handle
+            .order_id();
+        let mut slices = Vec::new();
+        while let Some(order) = order_book.get_order(order_id) {
+            let quantity = order.quantity;
+            slices.push(quantity);
+            order_book.match_order(Order::new(100.00, quantity, Side::Ask));
+        }
+        slices
+    };
+    let first = slices(11);
+    assert_eq!(first, slices(11));
+    assert_ne!(first, slices(12));
+    assert_eq!(first.iter().sum::<u64>(), 1_000);
+    assert!(first[..first.len() - 1]
+        .iter()
+        .all(|slice| (3..=9).contains(slice)));
+    assert!(first.iter().any(|slice| *slice != first[0]));
+
+    // Cancelling drops the reserve, and a display with no positive slice is rejected
+    let mut order_book = OrderBook::new();
+    let order_id = order_book
+        .insert_iceberg(
+            Order::new(100.00, 50, Side::Bid),
+            DisplayQuantity::Fixed(10),
+        )
+        .unwrap()
+        .handle
+        .order_id();
+    assert_eq!(
+        order_book.cancel_order(order_id).unwrap().quantity_delta,
+        -10
+    );
+    assert_eq!(order_book.hidden_quantity(order_id), None);
+    assert_eq!(order_book.total_volume(Side::Bid), 0);
+    for display in [
+        DisplayQuantity::Fixed(0),
+        DisplayQuantity::Random { min: 5, max: 4 },
+    ] {
+        assert!(matches!(
+            order_book.insert_iceberg(Order::new(100.00, 50, Side::Bid), display),
+            Err(OrderBookError::InvalidDisplayQuantity { .. })
+        ));
+    }
+    assert_eq!(order_book.order_count(), 0);
+}
+
+#[test]
+/// Test that a replacement keeps the reserve, client order identifier, and quote of the order
+fn test_replace_order_carries_order_state() {
+    let mut order_book = OrderBook::new();
+    let order_id = order_book
+        .insert_order(Order::new(99.00, 5, Side::Bid))
+        .handle
+        .order_id();
+    assert_eq!(
+        order_book.replace_order(order_id, Decimal::new(99, 0), 0),
+        Err(OrderBookError::ZeroReplacementQuantity(order_id))
+    );
+    assert_eq!(order_book.get_order(order_id).unwrap().quantity, 5);
+
+    // The reserve stays behind the new slice
+    let iceberg = order_book
+        .insert_iceberg(
+            Order::new(100.00, 25, Side::Ask),
+            DisplayQuantity::Fixed(10),
+        )
+        .unwrap()
+        .handle
+        .order_id();
+    let replacement = order_book
+        .replace_order(iceberg, Decimal::new(101, 0), 8)
+        .unwrap()
+        .handle
+        .order_id();
+    assert_eq!(order_book.hidden_quantity(iceberg), None);
+    assert_eq!(order_book.hidden_quantity(replacement), Some(15));
+    order_book.match_order(Order::new(101.00, 8, Side::Bid));
+    assert_eq!(order_book.get_order(replacement).unwrap().quantity, 10);
+
+    // The client order identifier now refers to the replacement
+    let client_order_id = ClientOrderId(42);
+    let order_id = order_book
+        .insert_order_with_client_id(Order::new(98.00, 5, Side::Bid), client_order_id)
+        .unwrap()
+        .handle
+        .order_id();
+    let replacement = order_book
+        .replace_order(order_id, Decimal::new(97, 0), 6)
+        .unwrap()
+        .handle
+        .order_id();
+    assert_eq!(
+        order_book.order_id_by_client_id(client_order_id),
+        Some(replacement)
+    );
+    assert_eq!(
+        order_book.client_order_id(replacement),
+        Some(client_order_id)
+    );
+
+    // Pulling the quote cancels the replaced side too
+    let market_maker = ParticipantId(7);
+    let quote = order_book.submit_quote(
+        market_maker,
+        BidPrice(Decimal::new(96, 0)),
+        5,
+        AskPrice(Decimal::new(110, 0)),
+        5,
+    );
+    let replacement = order_book
+        .replace_order(quote.bid.unwrap().order_id(), Decimal::new(95, 0), 3)
+        .unwrap()
+        .handle
+        .order_id();
+    let order_count = order_book.order_count();
+    assert_eq!(order_book.cancel_quote(market_maker).len(), 2);
+    assert!(order_book.get_order(replacement).is_none());
+    assert_eq!(order_book.order_count(), order_count - 2);
+}
+
+#[test]
+/// Test that stop orders are released by the trades reaching their trigger, in trigger
+/// order and in cascade
+fn test_stop_orders() {
+    let mut order_book = OrderBook::new();
+    for (price, quantity) in [(101.00, 10), (102.00, 10), (103.00, 10)] {
+        order_book.insert_order(Order::new(price, quantity, Side::Ask));
+    }
+    let stop = |order_book: &mut OrderBook, price: f64, quantity, side, trigger: i64| {
+        order_book
+            .insert_stop(Order::new(price, quantity, side), Decimal::new(trigger, 0))
+            .unwrap()
+    };
+    let far = stop(&mut order_book, 110.00, 1, Side::Bid, 105);
+    let second = stop(&mut order_book, 103.00, 2, Side::Bid, 102);
+    let first = stop(&mut order_book, 103.00, 3, Side::Bid, 101);
+    let cancelled = stop(&mut order_book, 103.00, 3, Side::Bid, 101);
+    let sell = stop(&mut order_book, 90.00, 5, Side::Ask, 95);
+    assert_eq!(order_book.stop_order_count(), 5);
+    assert_eq!(order_book.cancel_stop(cancelled).unwrap().quantity, 3);
+    assert_eq!(
+        order_book.cancel_stop(cancelled),
+        Err(OrderBookError::OrderNotFound(cancelled))
+    );
+    // Waiting stops are not part of the book
+    assert_eq!(order_book.total_volume(Side::Bid), 0);
+    assert_eq!(order_book.order_count(), 3);
+
+    // A sweep printing up to 102 releases the stops triggered at or below it, lowest
+    // trigger first, and their events follow the incoming order's
+    let mut events = Vec::new();
+    let outcome = order_book.match_order_into(Order::new(102.00, 15, Side::Bid), &mut events);
+    assert_eq!(outcome.stops_triggered, vec![first, second]);
+    let takers: Vec<OrderId> = order_book
+        .trades()
+        .iter()
+        .map(|trade| trade.taker_order_id)
+        .collect();
+    assert_eq!(
+        takers,
+        vec![outcome.order_id, outcome.order_id, first, second]
+    );
+    assert_eq!(events.len(), 4);
+    assert_eq!(order_book.stop_order_count(), 2);
+    assert_eq!(order_book.get_order(second), None);
+    assert!(order_book.cancel_stop(far).is_ok());
+    assert!(order_book.cancel_stop(sell).is_ok());
+
+    // A cascade: a released sell stop prints the trades that release the next one
+    let mut order_book = OrderBook::new();
+    for (price, quantity) in [(99.00, 5), (97.00, 5), (95.00, 5)] {
+        order_book.insert_order(Order::new(price, quantity, Side::Bid));
+    }
+    let low = stop(&mut order_book, 90.00, 5, Side::Ask, 97);
+    let high = stop(&mut order_book, 96.00, 5, Side::Ask, 99);
+    let untouched = stop(&mut order_book, 90.00, 5, Side::Ask, 94);
+    let outcome = order_book.match_order(Order::new(99.00, 5, Side::Ask));
+    assert_eq!(outcome.stops_triggered, vec![high, low]);
+    assert_eq!(order_book.trades().len(), 3);
+    assert_eq!(
+        order_book.cancel_stop(untouched).unwrap().price,
+        Decimal::new(90, 0)
+    );
+    assert!(order_book.bbo().best_bid.is_none());
+    // Trades alone release stops: a match that prints nothing leaves the ladder alone
+    let waiting = stop(&mut order_book, 90.00, 1, Side::Bid, 50);
+    let outcome = order_book.match_order(Order::new(80.00, 1, Side::Ask));
+    assert!(outcome.stops_triggered.is_empty());
+    assert_eq!(order_book.stop_order_count(), 1);
+    assert_eq!(
+        order_book.cancel_stop(waiting).map(|order| order.quantity),
+        Ok(1)
+    );
+}
+
+#[test]
+/// Test that submitting an order returns its fills and resting remainder
+fn test_submit() {
+    let mut order_book = OrderBook::new();
+    let maker = order_book
+        .insert_order(Order::new(100.00, 10, Side::Ask))
+        .handle
+        .order_id();
+    order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+
+    let result = order_book
+        .submit(Order::new(101.00, 14, Side::Bid))
+        .unwrap();
+    assert_eq!(result.fills.len(), 2);
+    assert_eq!(result.fills[0].maker_order_id, maker);
+    assert!(result
+        .fills
+        .iter()
+        .all(|trade| trade.taker_order_id == result.order_id));
+    assert_eq!(result.resting_remainder, 0);
+    assert_eq!(result.handle, None);
+    assert_eq!(result.events.len(), 2);
+
+    // A remainder rests, and nothing crossing leaves the fills empty
+    let result = order_book.submit(Order::new(99.00, 7, Side::Bid)).unwrap();
+    assert!(result.fills.is_empty());
+    assert_eq!(result.resting_remainder, 7);
+    assert_eq!(
+        result.handle.map(|handle| handle.order_id()),
+        Some(result.order_id)
+    );
+
+    // The remainder can trade against the stops the submission released, as their maker
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    let stop = order_book
+        .insert_stop(Order::new(99.00, 8, Side::Ask), Decimal::new(100, 0))
+        .unwrap();
+    let result = order_book
+        .submit(Order::new(100.00, 15, Side::Bid))
+        .unwrap();
+    let fills: Vec<(u64, OrderId, OrderId)> = result
+        .fills
+        .iter()
+        .map(|trade| (trade.quantity, trade.maker_order_id, trade.taker_order_id))
+        .collect();
+    assert_eq!(
+        fills,
+        vec![
+            (10, OrderId(1), result.order_id),
+            (5, result.order_id, stop)
+        ]
+    );
+    assert_eq!(result.resting_remainder, 0);
+    assert_eq!(result.handle, None);
+
+    // Rejected orders leave the book unchanged
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        price_precision: Some(PricePrecision {
+            max_scale: 1,
+            excess_precision: ExcessPrecision::Reject,
+        }),
+        ..OrderBookConfig::default()
+    });
+    assert!(matches!(
+        order_book.submit(Order::new(100.25, 1, Side::Bid)),
+        Err(OrderBookError::PriceTooPrecise { .. })
+    ));
+    assert_eq!(order_book.order_count(), 0);
+}
+
+#[test]
+/// Test that a backtest fills a strategy's orders under its latency and queue model
+fn test_backtest() {
+    let fill_model = FillModel {
+        latency: Duration::from_millis(1),
+        queue_model: QueueModel::Proportional,
+    };
+    let mut backtest = Backtest::new(OrderBook::deterministic(7), fill_model);
+    backtest
+        .step(Command::Insert(Order::new(99.00, 10, Side::Bid)))
+        .unwrap();
+    backtest
+        .step(Command::Insert(Order::new(101.00, 10, Side::Ask)))
+        .unwrap();
+
+    // A crossing order takes the book's depth once it arrives
+    let seller = backtest.submit(Order::new(99.00, 4, Side::Ask));
+    assert_eq!(backtest.pending_requests(), 1);
+    assert!(backtest.fills().is_empty());
+    backtest.step(Command::SetTime(1_000_000)).unwrap();
+    assert_eq!(
+        backtest.fills(),
+        &[Fill {
+            order_id: seller,
+            side: Side::Ask,
+            price: Decimal::new(99, 0),
+            quantity: 4,
+            timestamp_nanos: 1_000_000,
+            liquidity: Liquidity::Taker,
+        }]
+    );
+    assert_eq!(
+        backtest
+            .order_book()
+            .quantity_at(Decimal::new(99, 0), Side::Bid),
+        10
+    );
+
+    // Cancellations ahead of a resting order shrink its queue in proportion
+    for _ in 0..2 {
+        backtest
+            .step(Command::Insert(Order::new(98.00, 10, Side::Bid)))
+            .unwrap();
+    }
+    let buyer = backtest.submit(Order::new(98.00, 5, Side::Bid));
+    backtest.step(Command::SetTime(2_000_000)).unwrap();
+    backtest.step(Command::Cancel(OrderId(4))).unwrap();
+    backtest
+        .step(Command::Insert(Order::new(98.00, 10, Side::Bid)))
+        .unwrap();
+    backtest
+        .step(Command::Match(Order::new(98.00, 25, Side::Ask)))
+        .unwrap();
+    let fill = backtest.fills()[1];
+    assert_eq!((fill.order_id, fill.quantity), (buyer, 5));
+    assert_eq!(fill.liquidity, Liquidity::Maker);
+    assert_eq!(backtest.open_orders().count(), 0);
+
+    // Buying 5 at 98 closes the short of 4 at 99 and opens a long of 1
+    let position = backtest.position();
+    assert_eq!(position.quantity, 1);
+    assert_eq!(position.average_price, Decimal::new(98, 0));
+    assert_eq!(position.realized_pnl, Decimal::new(4, 0));
+    assert_eq!(backtest.total_pnl(), Some(Decimal::new(55, 1)));
+
+    // A cancellation in flight still removes the order once both arrive
+    let order_id = backtest.submit(Order::new(50.00, 1, Side::Bid));
+    assert!(backtest.cancel(order_id));
+    assert!(!backtest.cancel(StrategyOrderId(99)));
+    backtest.step(Command::SetTime(3_000_000)).unwrap();
+    assert_eq!(backtest.pending_requests(), 0);
+    assert_eq!(backtest.open_orders().count(), 0);
+}
+
+#[test]
+/// Test that a backtest drives a strategy and fills it from trades through its price
+fn test_backtest_strategy() {
+    struct Joiner {
+        fills: Vec<Fill>,
+    }
+
+    impl Strategy for Joiner {
+        fn on_update(&mut self, backtest: &mut Backtest, _events: &[OrderEvent]) {
+            if backtest.open_orders().count() == 0 && self.fills.is_empty() {
+                backtest.submit(Order::new(103.00, 3, Side::Ask));
+            }
+        }
+
+        fn on_fill(&mut self, fill: &Fill) {
+            self.fills.push(*fill);
+        }
+    }
+
+    let history = vec![
+        Command::Insert(Order::new(103.00, 5, Side::Ask)),
+        Command::Insert(Order::new(104.00, 5, Side::Ask)),
+        Command::Match(Order::new(104.00, 10, Side::Bid)),
+        Command::Cancel(OrderId(999)),
+    ];
+    let mut backtest = Backtest::new(OrderBook::deterministic(7), FillModel::default());
+    let mut strategy = Joiner { fills: Vec::new() };
+    backtest.run(history, &mut strategy);
+
+    // The trades at 103 only consumed the queue, and the one at 104 went through
+    assert_eq!(strategy.fills.len(), 1);
+    assert_eq!(strategy.fills[0].price, Decimal::new(103, 0));
+    assert_eq!(strategy.fills[0].quantity, 3);
+    assert_eq!(strategy.fills[0].liquidity, Liquidity::Maker);
+    assert_eq!(backtest.position().quantity, -3);
+    assert_eq!(backtest.rejected_commands(), 1);
+
+    // The short cannot be marked while the book has no bids
+    assert_eq!(backtest.total_pnl(), None);
+}
+
+#[test]
+/// Test that settlement orders wait apart from the book and cross at the published
+/// settlement price
+fn test_settlement_orders() {
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    let first_sell = order_book.insert_settlement_order(15, Side::Ask);
+    let buy = order_book.insert_settlement_order(40, Side::Bid);
+    let withdrawn = order_book.insert_settlement_order(5, Side::Bid);
+    let second_sell = order_book.insert_settlement_order(10, Side::Ask);
+    assert_eq!(order_book.cancel_settlement_order(withdrawn), Ok(5));
+    assert_eq!(
+        order_book.cancel_settlement_order(withdrawn),
+        Err(OrderBookError::OrderNotFound(withdrawn))
+    );
+    assert_eq!(order_book.settlement_quantity(Side::Bid), 40);
+    assert_eq!(order_book.settlement_quantity(Side::Ask), 25);
+
+    // Settlement orders neither rest in the book nor match priced orders
+    order_book.match_order(Order::new(101.00, 5, Side::Bid));
+    assert_eq!(order_book.trades().len(), 1);
+    assert_eq!(order_book.total_volume(Side::Bid), 10);
+    assert_eq!(order_book.bbo().best_ask, Some(Decimal::new(101, 0)));
+
+    let price = Decimal::new(10050, 2);
+    let outcome = order_book.settle_reference_price(price).unwrap();
+    let trades: Vec<(u64, OrderId, OrderId, Side)> = outcome
+        .trades
+        .iter()
+        .map(|trade| {
+            assert_eq!(trade.price, price);
+            (
+                trade.quantity,
+                trade.maker_order_id,
+                trade.taker_order_id,
+                trade.aggressor_side,
+            )
+        })
+        .collect();
+    assert_eq!(
+        trades,
+        vec![
+            (15, first_sell, buy, Side::Bid),
+            (10, buy, second_sell, Side::Ask)
+        ]
+    );
+    assert_eq!(outcome.unfilled, vec![(buy, Side::Bid, 15)]);
+    assert_eq!(order_book.trades()[1..], outcome.trades[..]);
+    assert_eq!(order_book.settlement_quantity(Side::Bid), 0);
+    // The book itself is untouched
+    assert_eq!(order_book.order_count(), 2);
+    assert!(order_book
+        .settle_reference_price(price)
+        .unwrap()
+        .trades
+        .is_empty());
+}
+
+#[test]
+/// Test that the market-by-order feed reports every order-level change when enabled.
+fn test_market_by_order_events() {
+    let mut order_book = OrderBook::new();
+
+    // Nothing is recorded until the feed is enabled
+    order_book.insert_order(Order::new(99.00, 1, Side::Bid));
+    assert!(order_book.take_market_by_order_events().is_empty());
+    order_book.set_market_by_order_events(true);
+
+    let price = Decimal::try_from(100.50).unwrap();
+    let maker_id = order_book
+        .insert_order(Order::new(100.50, 40, Side::Ask))
+        .handle
+        .order_id();
+    order_book.match_order(Order::new(100.50, 15, Side::Bid));
+    order_book.reduce_order(maker_id, 5).unwrap();
+    let replacement = order_book
+        .replace_order(maker_id, Decimal::try_from(100.75).unwrap(), 20)
+        .unwrap();
+    order_book.cancel_by_handle(replacement.handle).unwrap();
+
+    let new_order_id = replacement.handle.order_id();
+    assert_eq!(
+        order_book.take_market_by_order_events(),
+        vec![
+            MarketByOrderEvent::Added {
+                order_id: maker_id,
+                price,
+                quantity: 40,
+                side: Side::Ask,
+            },
+            MarketByOrderEvent::Executed {
+                order_id: maker_id,
+                price,
+                side: Side::Ask,
+                executed_quantity: 15,
+                remaining_quantity: 25,
+                trade_id: TradeId(1),
+                taker_order_id: OrderId(3),
+            },
+            MarketByOrderEvent::Cancelled {
+                order_id: maker_id,
+                price,
+                side: Side::Ask,
+                cancelled_quantity: 5,
+                remaining_quantity: 20,
+            },
+            MarketByOrderEvent::Replaced {
+                order_id: maker_id,
+                new_order_id,
+                price: Decimal::try_from(100.75).unwrap(),
+                quantity: 20,
+                side: Side::Ask,
+            },
+            MarketByOrderEvent::Cancelled {
+                order_id: new_order_id,
+                price: Decimal::try_from(100.75).unwrap(),
+                side: Side::Ask,
+                cancelled_quantity: 20,
+                remaining_quantity: 0,
+            },
+        ]
+    );
+    assert!(order_book.take_market_by_order_events().is_empty());
+}
+
+#[test]
+/// Test that the removal of the last order at a price is reported as a level removal.
+fn test_level_removed_events() {
+    let mut order_book = OrderBook::new();
+    let first = order_book
+        .insert_order(Order::new(100.00, 10, Side::Bid))
+        .handle
+        .order_id();
+    order_book.cancel_order(first).unwrap();
+    assert!(order_book.take_level_removed_events().is_empty());
+    order_book.set_level_removed_events(true);
+
+    // Levels are removed with their last order only
+    let first = order_book
+        .insert_order(Order::new(100.00, 10, Side::Bid))
+        .handle
+        .order_id();
+    let second = order_book
+        .insert_order(Order::new(100.00, 5, Side::Bid))
+        .handle
+        .order_id();
+    order_book.cancel_order(first).unwrap();
+    order_book.reduce_order(second, 4).unwrap();
+    assert!(order_book.take_level_removed_events().is_empty());
+    let event = order_book.cancel_order(second).unwrap();
+    assert_eq!(
+        order_book.take_level_removed_events(),
+        vec![LevelRemoved {
+            price: Decimal::new(100, 0),
+            side: Side::Bid,
+            sequence: event.sequence,
+        }]
+    );
+
+    // Sweeps report every level they exhaust, and replacements the level they leave
+    order_book.insert_order(Order::new(101.00, 2, Side::Ask));
+    order_book.insert_order(Order::new(102.00, 2, Side::Ask));
+    let moved = order_book
+        .insert_order(Order::new(103.00, 2, Side::Ask))
+        .handle
+        .order_id();
+    order_book.match_order(Order::new(102.00, 3, Side::Bid));
+    order_book
+        .replace_order(moved, Decimal::new(104, 0), 2)
+        .unwrap();
+    let removed: Vec<_> = order_book
+        .take_level_removed_events()
+        .into_iter()
+        .map(|removed| (removed.price, removed.side))
+        .collect();
+    assert_eq!(
+        removed,
+        vec![
+            (Decimal::new(101, 0), Side::Ask),
+            (Decimal::new(103, 0), Side::Ask)
+        ]
+    );
+}
+
+#[test]
+/// Test that refused incoming orders are recorded and can be fanned out
+fn test_rejection_events() {
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        price_precision: Some(PricePrecision {
+            max_scale: 2,
+            excess_precision: ExcessPrecision::Reject,
+        }),
+        ..OrderBookConfig::default()
+    });
+    let too_precise = Order::new(100.125, 10, Side::Bid);
+    assert!(order_book.try_insert_order(too_precise.clone()).is_err());
+    assert!(order_book.take_rejection_events().is_empty());
+    order_book.set_rejection_events(true);
+
+    let accepted = order_book
+        .insert_order(Order::new(100.00, 10, Side::Ask))
+        .event;
+    assert!(order_book.try_match_order(too_precise.clone()).is_err());
+    let client_order_id = ClientOrderId(7);
+    let order = Order::new(99.00, 5, Side::Bid);
+    order_book
+        .insert_order_with_client_id(order.clone(), client_order_id)
+        .unwrap();
+    let duplicate = order_book
+        .insert_order_with_client_id(order.clone(), client_order_id)
+        .unwrap_err();
+    assert!(order_book
+        .insert_iceberg(order.clone(), DisplayQuantity::Fixed(0))
+        .is_err());
+    assert!(order_book
+        .insert_stop(order.clone(), Decimal::new(101125, 3))
+        .is_err());
+    assert!(order_book
+        .apply(Command::InsertDay(too_precise.clone()))
+        .is_err());
+    // Errors about resting orders are not rejections of incoming orders
+    assert!(order_book.cancel_order(OrderId(99)).is_err());
+
+    let rejections = order_book.take_rejection_events();
+    let reasons: Vec<_> = rejections
+        .iter()
+        .map(|rejection| (&rejection.order, &rejection.reason))
+        .collect();
+    let price_too_precise = OrderBookError::PriceTooPrecise {
+        price: Decimal::new(100125, 3),
+        max_scale: 2,
+    };
+    assert_eq!(
+        reasons,
+        vec![
+            (&too_precise, &price_too_precise),
+            (&order, &duplicate),
+            (
+                &order,
+                &OrderBookError::InvalidDisplayQuantity { min: 0, max: 0 }
+            ),
+            (
+                &order,
+                &OrderBookError::PriceTooPrecise {
+                    price: Decimal::new(101125, 3),
+                    max_scale: 2,
+                }
+            ),
+            (&too_precise, &price_too_precise),
+        ]
+    );
+    // Rejections take the place of the last event, without consuming a sequence number
+    assert_eq!(rejections[0].sequence, accepted.sequence);
+    assert_eq!(rejections[1].sequence, order_book.last_event_sequence());
+    assert!(order_book.take_rejection_events().is_empty());
+
+    // A throttle's rejections go to the same log, and from there to observers
+    #[derive(Default)]
+    struct AuditLog(parking_lot::Mutex<Vec<OrderRejected>>);
+    impl BookObserver for AuditLog {
+        fn on_rejection(&self, rejection: &OrderRejected) {
+            self.0.lock().push(rejection.clone());
+        }
+    }
+    let fan_out = EventFanOut::new();
+    let audit_log = Arc::new(AuditLog::default());
+    fan_out.subscribe(&audit_log, &[EventKind::Rejection]);
+
+    let mut throttle = Throttle::new(order_book, ThrottlePolicy::Reject);
+    throttle.set_participant_limit(Some(RateLimit {
+        rate_per_second: 1,
+        burst: 1,
+    }));
+    let trader = ParticipantId(3);
+    let insert = Order::new(98.00, 1, Side::Bid);
+    throttle
+        .apply(trader, Command::Insert(insert.clone()))
+        .unwrap();
+    throttle
+        .apply(trader, Command::Insert(insert.clone()))
+        .unwrap_err();
+    throttle
+        .apply(trader, Command::Cancel(OrderId(1)))
+        .unwrap_err();
+    for rejection in throttle.order_book_mut().take_rejection_events() {
+        fan_out.publish_rejection(&rejection);
+    }
+    let audited = audit_log.0.lock();
+    assert_eq!(audited.len(), 1);
+    assert_eq!(audited[0].order, insert);
+    assert_eq!(
+        audited[0].reason,
+        OrderBookError::Throttled {
+            participant_id: trader
+        }
+    );
+}
+
+#[test]
+/// Test that mutations report changes of the best bid and ask
+fn test_bbo_changes() {
+    let mut order_book = OrderBook::new();
+    let bbo = |best_bid: Option<i64>, best_ask: Option<i64>| Bbo {
+        best_bid: best_bid.map(|price| Decimal::new(price, 0)),
+        best_ask: best_ask.map(|price| Decimal::new(price, 0)),
+    };
+
+    // Only inserts at or through the touch change it
+    let outcome = order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    assert_eq!(
+        outcome.bbo_changed,
+        Some(BboChanged {
+            old: Bbo::default(),
+            new: bbo(Some(100), None),
+        })
+    );
+    let best_bid = outcome.handle.order_id();
+    assert_eq!(
+        order_book
+            .insert_order(Order::new(99.00, 10, Side::Bid))
+            .bbo_changed,
+        None
+    );
+    assert_eq!(
+        order_book
+            .insert_order(Order::new(100.00, 5, Side::Bid))
+            .bbo_changed,
+        None
+    );
+    let outcome = order_book.insert_order(Order::new(102.00, 10, Side::Ask));
+    assert_eq!(outcome.bbo_changed.unwrap().new, bbo(Some(100), Some(102)));
+    assert_eq!(order_book.bbo(), bbo(Some(100), Some(102)));
+
+    // Matches report the touch they leave, not the levels they pass through
+    let outcome = order_book.match_order(Order::new(102.00, 4, Side::Bid));
+    assert_eq!(outcome.bbo_changed, None);
+    let outcome = order_book.match_order(Order::new(102.00, 8, Side::Bid));
+    assert_eq!(
+        outcome.bbo_changed,
+        Some(BboChanged {
+            old: bbo(Some(100), Some(102)),
+            new: bbo(Some(102), None),
+        })
+    );
+
+    // Replacements compare the touch before the removal and after the insertion
+    let outcome = order_book
+        .replace_order(best_bid, Decimal::new(100, 0), 20)
+        .unwrap();
+    assert_eq!(outcome.bbo_changed, None);
+    let outcome = order_book
+        .replace_order(outcome.handle.order_id(), Decimal::new(103, 0), 20)
+        .unwrap();
+    assert_eq!(outcome.bbo_changed.unwrap().new, bbo(Some(103), None));
+    let best_bid = outcome.handle.order_id();
+
+    // Operations returning only events are covered by the change since the last take
+    assert_eq!(
+        order_book.take_bbo_change(),
+        Some(BboChanged {
+            old: Bbo::default(),
+            new: bbo(Some(103), None),
+        })
+    );
+    order_book.cancel_order(best_bid).unwrap();
+    assert_eq!(
+        order_book.take_bbo_change(),
+        Some(BboChanged {
+            old: bbo(Some(103), None),
+            new: bbo(Some(102), None),
+        })
+    );
+    let ask = order_book
+        .insert_order(Order::new(104.00, 1, Side::Ask))
+        .handle
+        .order_id();
+    order_book.cancel_order(ask).unwrap();
+    assert_eq!(order_book.take_bbo_change(), None);
+}
+
+#[test]
+/// Test that the cached best bid and ask agree with the levels after every operation.
+fn test_cached_bbo_never_diverges() {
+    let mut state: u64 = 0x7f4a_7c15_9e37_79b9;
+    let mut next = |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+
+    let mut order_book = OrderBook::new();
+    let mut order_ids = Vec::new();
+    for _ in 0..5_000 {
+        let side = if next(2) == 0 { Side::Bid } else { Side::Ask };
+        let order = Order {
+            price: Decimal::new(9_950 + next(100) as i64, 2),
+            quantity: 1 + next(50),
+            side,
+        };
+        match next(10) {
+            0..=3 => order_ids.push(order_book.insert_order(order).handle.order_id()),
+            4 | 5 => {
+                order_book.match_order(order);
+            }
+            6 | 7 if !order_ids.is_empty() => {
+                let order_id = order_ids.swap_remove(next(order_ids.len() as u64) as usize);
+                let _ = order_book.cancel_order(order_id);
+            }
+            8 if !order_ids.is_empty() => {
+                let order_id = order_ids[next(order_ids.len() as u64) as usize];
+                let _ = order_book.reduce_order(order_id, 1 + next(10));
+            }
+            _ => {
+                order_book.cancel_outside_band(side, Decimal::new(next(50) as i64, 2));
+            }
+        }
+
+        // The price range walks the levels, bypassing the cache
+        let best_bid = order_book
+            .price_range(Side::Bid)
+            .map(|(_, highest)| highest);
+        let best_ask = order_book.price_range(Side::Ask).map(|(lowest, _)| lowest);
+        assert_eq!(order_book.bbo(), Bbo { best_bid, best_ask });
+        let (cached_bid, cached_ask, spread) = order_book.compute_spread();
+        assert_eq!((cached_bid, cached_ask), (best_bid, best_ask));
+        assert_eq!(spread, best_bid.zip(best_ask).map(|(bid, ask)| ask - bid));
+    }
+
+    // Copies keep their own cache
+    let mut what_if = order_book.clone();
+    what_if.cancel_where(|_| true);
+    assert_eq!(what_if.bbo(), Bbo::default());
+    assert_ne!(order_book.bbo(), Bbo::default());
+}
+
+#[test]
+/// Test that the depth publisher reports absolute level quantities in sequence.
+fn test_depth_delta_publisher() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = Arc::new(MarketDepthCache::new());
+    let publisher = DepthDeltaPublisher::new(Arc::clone(&market_depth_cache));
+
+    let first = order_book.insert_order(Order::new(101.20, 8, Side::Ask));
+    let second = order_book.insert_order(Order::new(101.90, 2, Side::Ask));
+
+    let mut updates = vec![
+        publisher.process_order_event(first.event),
+        publisher.process_order_event(second.event),
+    ];
+    for handle in [first.handle, second.handle] {
+        let event = order_book.cancel_by_handle(handle).unwrap();
+        updates.push(publisher.process_order_event(event));
+    }
+
+    let level = Decimal::try_from(101.0).unwrap();
+    let expected_quantities = [8, 10, 2, 0];
+    for (index, update) in updates.iter().enumerate() {
+        assert_eq!(update.price_bucket, level);
+        assert_eq!(update.side, Side::Ask);
+        assert_eq!(update.new_quantity, expected_quantities[index]);
+        assert_eq!(update.sequence, index as u64 + 1);
+    }
+
+    assert_eq!(publisher.last_sequence(), 4);
+    assert_eq!(
+        market_depth_cache.ask_levels_count(),
+        0,
+        "The shared cache must reflect every published update"
+    );
+}
+
+/// An observer recording what it receives.
+#[derive(Default)]
+struct RecordingObserver {
+    levels: Mutex<Vec<OrderEvent>>,
+    trades: Mutex<Vec<Trade>>,
+    bbo_changes: Mutex<Vec<BboChanged>>,
+}
+
+impl BookObserver for RecordingObserver {
+    fn on_level(&self, event: &OrderEvent) {
+        self.levels.lock().push(event.clone());
+    }
+
+    fn on_trade(&self, trade: &Trade) {
+        self.trades.lock().push(trade.clone());
+    }
+
+    fn on_bbo(&self, change: &BboChanged) {
+        self.bbo_changes.lock().push(*change);
+    }
+}
+
+#[test]
+/// Test that the fan-out only delivers the kinds each observer subscribed to.
+fn test_event_fan_out() {
+    let fan_out = EventFanOut::new();
+    let everything = Arc::new(RecordingObserver::default());
+    let trades_only = Arc::new(RecordingObserver::default());
+    let bbo_only = Arc::new(RecordingObserver::default());
+    fan_out.subscribe(
+        &everything,
+        &[EventKind::Level, EventKind::Trade, EventKind::Bbo],
+    );
+    let trades_subscription =
+        fan_out.subscribe(&trades_only, &[EventKind::Trade, EventKind::Trade]);
+    fan_out.subscribe(&bbo_only, &[EventKind::Bbo]);
+    assert_eq!(fan_out.subscriber_count(EventKind::Trade), 2);
+
+    let mut order_book = OrderBook::new();
+    let outcome = order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    fan_out.publish_level(&outcome.event);
+    fan_out.publish_bbo(&outcome.bbo_changed.unwrap());
+    let outcome = order_book.match_order(Order::new(100.00, 10, Side::Bid));
+    for event in &outcome.events {
+        fan_out.publish_level(event);
+    }
+    fan_out.publish_trade(&order_book.trades()[0]);
+    fan_out.publish_bbo(&outcome.bbo_changed.unwrap());
+
+    assert_eq!(everything.levels.lock().len(), 2);
+    assert_eq!(everything.trades.lock().len(), 1);
+    assert_eq!(everything.bbo_changes.lock().len(), 2);
+    assert!(trades_only.levels.lock().is_empty());
+    assert_eq!(*trades_only.trades.lock(), order_book.trades().to_vec());
+    assert!(trades_only.bbo_changes.lock().is_empty());
+    assert_eq!(*bbo_only.bbo_changes.lock(), *everything.bbo_changes.lock());
+    assert!(bbo_only.trades.lock().is_empty());
+
+    // Unsubscribed observers receive nothing more, and dropped ones are cleaned up
+    assert!(fan_out.unsubscribe(trades_subscription));
+    assert!(!fan_out.unsubscribe(trades_subscription));
+    drop(bbo_only);
+    order_book.insert_order(Order::new(99.00, 5, Side::Bid));
+    order_book.match_order(Order::new(99.00, 5, Side::Ask));
+    fan_out.publish_trade(&order_book.trades()[1]);
+    fan_out.publish_bbo(&BboChanged {
+        old: Bbo::default(),
+        new: order_book.bbo(),
+    });
+    assert_eq!(trades_only.trades.lock().len(), 1);
+    assert_eq!(everything.trades.lock().len(), 2);
+    assert_eq!(fan_out.subscriber_count(EventKind::Trade), 1);
+    assert_eq!(fan_out.subscriber_count(EventKind::Bbo), 1);
+}
+
+#[test]
+/// Test that the cache measures how long events take to propagate from the book.
+fn test_event_propagation_latency() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    let event = order_book
+        .insert_order(Order::new(100.00, 1, Side::Bid))
+        .event;
+    assert!(
+        event.timestamp_nanos > 0,
+        "Events must be stamped on creation"
+    );
+
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    market_depth_cache.process_order_event(event);
+
+    // Events without a creation time are applied but not measured
+    let mut unstamped = OrderEvent::new(Decimal::try_from(100.00).unwrap(), 1, Side::Bid);
+    unstamped.timestamp_nanos = 0;
+    market_depth_cache.process_order_event(unstamped);
+
+    let latency = market_depth_cache.propagation_latency();
+    assert_eq!(latency.count, 1);
+    assert!(latency.max_nanos >= 5_000_000);
+    assert!(latency.quantile_nanos(0.5) >= 5_000_000);
+    assert!(latency.quantile_nanos(0.5) <= latency.max_nanos);
+    assert_eq!(latency.mean_nanos(), latency.sum_nanos);
+
+    market_depth_cache.clear();
+    assert_eq!(market_depth_cache.propagation_latency().count, 0);
+}
+
+#[test]
+/// Test that equivalent price representations are canonicalized on the way in.
+fn test_price_normalization_policies() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    // 100.50 and 100.5 are the same price and must share a canonical key
+    for price in [Decimal::new(10050, 2), Decimal::new(1005, 1)] {
+        let order = Order {
+            price,
+            quantity: 1,
+            side: Side::Ask,
+        };
+        let event = order_book.insert_order(order).event;
+        assert_eq!(event.price.to_string(), "100.5");
+        market_depth_cache.process_order_event(event);
+    }
+    assert_eq!(order_book.ask_levels_count(), 1);
+    assert_eq!(
+        order_book
+            .level(Decimal::new(100500, 3), Side::Ask)
+            .map_or(0, |level| level.order_count()),
+        2
+    );
+    let (bid_depth, ask_depth) = market_depth_cache.get_aggregated_market_depth();
+    assert!(bid_depth.is_empty());
+    assert_eq!(ask_depth.keys().next().unwrap().to_string(), "100");
+
+    // A fixed scale pads and rounds prices to the configured number of places
+    let mut scaled_book = OrderBook::with_config(OrderBookConfig {
+        price_normalization: PriceNormalization::Scale(2),
+        ..OrderBookConfig::default()
+    });
+    let outcome = scaled_book.match_order(Order {
+        price: Decimal::new(1002549, 4), // 100.2549
+        quantity: 1,
+        side: Side::Bid,
+    });
+    assert_eq!(outcome.handle.unwrap().price().to_string(), "100.25");
+
+    let mut preserving_book = OrderBook::with_config(OrderBookConfig {
+        price_normalization: PriceNormalization::Preserve,
+        ..OrderBookConfig::default()
+    });
+    let event = preserving_book
+        .insert_order(Order {
+            price: Decimal::new(10050, 2),
+            quantity: 1,
+            side: Side::Bid,
+        })
+        .event;
+    assert_eq!(event.price.to_string(), "100.50");
+}
+
+#[test]
+/// Test bounding the decimal places of incoming prices
+fn test_price_precision_guard() {
+    let rounding = PricePrecision {
+        max_scale: 2,
+        excess_precision: ExcessPrecision::Round,
+    };
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        price_precision: Some(rounding),
+        ..OrderBookConfig::default()
+    });
+    assert_eq!(order_book.price_precision(), Some(rounding));
+
+    // Every price is stored at the canonical scale, rounding the excess digits
+    let event = order_book
+        .insert_order(Order::new(100.125, 10, Side::Bid))
+        .event;
+    assert_eq!(event.price.to_string(), "100.12");
+    let event = order_book
+        .insert_order(Order::new(100.1, 5, Side::Bid))
+        .event;
+    assert_eq!(event.price.to_string(), "100.10");
+
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        price_precision: Some(PricePrecision {
+            excess_precision: ExcessPrecision::Reject,
+            ..rounding
+        }),
+        ..OrderBookConfig::default()
+    });
+    let expected_error = OrderBookError::PriceTooPrecise {
+        price: Decimal::new(100125, 3),
+        max_scale: 2,
+    };
+
+    // Trailing zeros are not precision
+    let order_id = order_book
+        .try_insert_order(Order {
+            price: Decimal::new(1001000, 4),
+            quantity: 10,
+            side: Side::Ask,
+        })
+        .unwrap()
+        .handle
+        .order_id();
+    assert_eq!(
+        order_book.get_order(order_id).unwrap().price.to_string(),
+        "100.10"
+    );
+
+    // Rejected prices leave the book unchanged, whichever entry point they come through
+    let too_precise = Order::new(100.125, 10, Side::Bid);
+    assert_eq!(
+        order_book.try_insert_order(too_precise.clone()),
+        Err(expected_error.clone())
+    );
+    assert_eq!(
+        order_book.try_match_order(too_precise.clone()).unwrap_err(),
+        expected_error
+    );
+    assert_eq!(
+        order_book.apply(Command::InsertDay(too_precise)),
+        Err(expected_error.clone())
+    );
+    assert_eq!(
+        order_book.replace_order(order_id, Decimal::new(100125, 3), 10),
+        Err(expected_error)
+    );
+    assert_eq!(order_book.order_count(), 1);
+    assert_eq!(order_book.last_event_sequence(), 1);
+    assert!(order_book.get_order(order_id).is_some());
+
+    // The infallible entry points round the price instead
+    let event = order_book
+        .insert_order(Order::new(100.125, 10, Side::Bid))
+        .event;
+    assert_eq!(event.price.to_string(), "100.12");
+    let aggressive = Order {
+        price: Decimal::new(100115, 3),
+        quantity: 4,
+        side: Side::Ask,
+    };
+    order_book.match_order(aggressive.clone());
+    assert_eq!(order_book.trades()[0].price.to_string(), "100.12");
+    let mut events = Vec::new();
+    order_book.match_order_into(aggressive, &mut events);
+    assert_eq!(events[0].price.to_string(), "100.12");
+    assert_eq!(order_book.trades().len(), 2);
+}
+
+#[test]
+/// Test that the book options compose: bounded precision and a preallocated slab.
+fn test_order_book_combined_config() {
+    let price_precision = PricePrecision {
+        max_scale: 2,
+        excess_precision: ExcessPrecision::Round,
+    };
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        // The precision takes the place of the normalization policy
+        price_normalization: PriceNormalization::Preserve,
+        price_precision: Some(price_precision),
+        order_capacity: 64,
+        ..OrderBookConfig::default()
+    });
+    assert_eq!(order_book.price_precision(), Some(price_precision));
+
+    let outcome = order_book.insert_order(Order::new(100.1, 10, Side::Bid));
+    assert_eq!(outcome.event.price.to_string(), "100.10");
+
+    // The preallocated slots absorb the orders without growing the book's storage
+    let warmed_up = order_book.allocation_count();
+    let handles: Vec<_> = (0..63)
+        .map(|_| {
+            order_book
+                .insert_order(Order::new(100.1, 1, Side::Bid))
+                .handle
+        })
+        .collect();
+    for handle in handles {
+        order_book.cancel_by_handle(handle).unwrap();
+    }
+    assert_eq!(order_book.allocation_count(), warmed_up);
+}
+
+#[test]
+/// Test that f64 prices are rounded to a scale or tick, or rejected in strict mode
+fn test_f64_price_rounding() {
+    let cents = FloatPrecision {
+        rounding: FloatRounding::Scale(2),
+        excess_precision: ExcessPrecision::Round,
+    };
+    // Floating-point noise is removed, and extra digits are rounded half to even
+    let order = Order::with_f64_price(0.1 + 0.2, 10, Side::Bid, cents).unwrap();
+    assert_eq!(order.price, Decimal::new(30, 2));
+    assert_eq!(cents.convert(100.125).unwrap(), Decimal::new(10012, 2));
+    assert_eq!(cents.convert(100.135).unwrap(), Decimal::new(10014, 2));
+
+    let nickels = FloatPrecision {
+        rounding: FloatRounding::Tick(Decimal::new(5, 2)),
+        excess_precision: ExcessPrecision::Round,
+    };
+    assert_eq!(nickels.convert(100.07).unwrap(), Decimal::new(10005, 2));
+    assert_eq!(nickels.convert(100.08).unwrap(), Decimal::new(10010, 2));
+
+    let strict = FloatPrecision {
+        excess_precision: ExcessPrecision::Reject,
+        ..nickels
+    };
+    assert_eq!(strict.convert(100.1 + 0.2).unwrap(), Decimal::new(10030, 2));
+    assert_eq!(
+        Order::with_f64_price(100.07, 10, Side::Ask, strict),
+        Err(OrderBookError::UnrepresentablePrice {
+            price: Some(Decimal::new(10007, 2)),
+            rounding: FloatRounding::Tick(Decimal::new(5, 2)),
+        })
+    );
+    let error = strict.convert(f64::NAN).unwrap_err();
+    assert_eq!(
+        error,
+        OrderBookError::UnrepresentablePrice {
+            price: None,
+            rounding: FloatRounding::Tick(Decimal::new(5, 2)),
+        }
+    );
+    assert!(cents.convert(f64::INFINITY).is_err());
+
+    // Prices with more ticks than a decimal holds, and ticks that are not positive,
+    // are errors rather than panics
+    let tiny_ticks = FloatPrecision {
+        rounding: FloatRounding::Tick(Decimal::new(1, 10)),
+        excess_precision: ExcessPrecision::Round,
+    };
+    assert_eq!(
+        tiny_ticks.convert(1e20),
+        Err(OrderBookError::UnrepresentablePrice {
+            price: Some(Decimal::from(100_000_000_000_000_000_000u128)),
+            rounding: FloatRounding::Tick(Decimal::new(1, 10)),
+        })
+    );
+    for tick_size in [Decimal::ZERO, Decimal::new(-5, 2)] {
+        let precision = FloatPrecision {
+            rounding: FloatRounding::Tick(tick_size),
+            ..nickels
+        };
+        assert_eq!(
+            precision.convert(100.0),
+            Err(OrderBookError::InvalidTickSize(tick_size))
+        );
+    }
+    assert_eq!(
+        strict.convert(100.07).unwrap_err().to_string(),
+        "price 100.07 is not representable at a tick of 0.05"
+    );
+
+    // The convenience constructor rounds like the default precision
+    assert_eq!(
+        Order::new(0.1 + 0.2, 10, Side::Bid),
+        Order::with_f64_price(0.1 + 0.2, 10, Side::Bid, FloatPrecision::default()).unwrap()
+    );
+    assert_eq!(
+        Order::new(100.123456789, 10, Side::Bid).price,
+        Decimal::new(10012345679, 8)
+    );
+    let panic = std::panic::catch_unwind(|| Order::new(f64::NAN, 10, Side::Bid));
+    assert!(panic.is_err());
+}
+
+#[test]
+/// Test that string and tick constructors produce exact prices.
+fn test_exact_price_constructors() {
+    let mut order_book = OrderBook::new();
+
+    let from_string = Order::with_price_str("100.10", 5, Side::Bid).unwrap();
+    let from_ticks = Order::from_ticks(10010, Decimal::new(1, 2), 7, Side::Bid);
+    assert_eq!(from_string.price, from_ticks.price);
+    assert_eq!(from_string.price, Decimal::new(10010, 2));
+
+    order_book.insert_order(from_string);
+    order_book.insert_order(from_ticks);
+    assert_eq!(
+        order_book
+            .level(Decimal::new(1001, 1), Side::Bid)
+            .map_or(0, |level| level.order_count()),
+        2,
+        "Both constructors must address the same exact level"
+    );
+
+    // Prices more precise than `f64` can represent survive the string path
+    let precise = Order::with_price_str("0.1000000000000000055511151231", 1, Side::Ask).unwrap();
+    assert_eq!(precise.price.to_string(), "0.1000000000000000055511151231");
+    assert!(Order::with_price_str("", 1, Side::Ask).is_err());
+}
+
+#[test]
+/// Test that side summaries track inserts, executions, reductions, and cancellations.
+fn test_side_summaries() {
+    let mut order_book = OrderBook::new();
+    assert_eq!(order_book.worst_bid(), None);
+    assert_eq!(order_book.price_range(Side::Ask), None);
+
+    let low_bid = order_book
+        .insert_order(Order::new(98.00, 10, Side::Bid))
+        .handle;
+    order_book.insert_order(Order::new(99.50, 20, Side::Bid));
+    order_book.insert_order(Order::new(101.00, 5, Side::Ask));
+    order_book.insert_order(Order::new(104.00, 7, Side::Ask));
+
+    assert_eq!(order_book.worst_bid(), Some(Decimal::new(98, 0)));
+    assert_eq!(order_book.worst_ask(), Some(Decimal::new(104, 0)));
+    assert_eq!(
+        order_book.price_range(Side::Bid),
+        Some((Decimal::new(98, 0), Decimal::new(995, 1)))
+    );
+    assert_eq!(order_book.total_volume(Side::Bid), 30);
+    assert_eq!(order_book.total_volume(Side::Ask), 12);
+
+    // Execute 8 against the asks: 5 at 101 and 3 at 104
+    order_book.match_order(Order::new(105.00, 8, Side::Bid));
+    assert_eq!(order_book.total_volume(Side::Ask), 4);
+    assert_eq!(
+        order_book.price_range(Side::Ask),
+        Some((Decimal::new(104, 0), Decimal::new(104, 0)))
+    );
+
+    order_book
+        .reduce_order(low_bid.order_id(), 4)
+        .expect("order should be resting");
+    assert_eq!(order_book.total_volume(Side::Bid), 26);
+
+    order_book
+        .cancel_by_handle(low_bid)
+        .expect("order should be resting");
+    assert_eq!(order_book.total_volume(Side::Bid), 20);
+    assert_eq!(order_book.worst_bid(), Some(Decimal::new(995, 1)));
+}
+
+#[test]
+/// Test that book and cache liquidity curves accumulate from the touch outwards.
+fn test_liquidity_curve() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+
+    for (price, quantity) in [(99.75, 10), (99.25, 5), (98.50, 20), (97.10, 1)] {
+        let event = order_book
+            .insert_order(Order::new(price, quantity, Side::Bid))
+            .event;
+        market_depth_cache.process_order_event(event);
+    }
+
+    assert_eq!(
+        order_book.liquidity_curve(Side::Bid, 3),
+        vec![
+            (Decimal::ZERO, 10),
+            (Decimal::new(5, 1), 15),
+            (Decimal::new(125, 2), 35),
+        ]
+    );
+    assert_eq!(
+        market_depth_cache.liquidity_curve(Side::Bid, 10),
+        vec![
+            (Decimal::ZERO, 15),
+            (Decimal::new(1, 0), 35),
+            (Decimal::new(2, 0), 36),
+        ]
+    );
+    assert!(order_book.liquidity_curve(Side::Ask, 10).is_empty());
+    assert!(market_depth_cache.liquidity_curve(Side::Bid, 0).is_empty());
+}
+
+#[test]
+/// Test that impact estimates walk the opposite side without modifying the book.
+fn test_estimate_impact() {
+    let mut order_book = OrderBook::new();
+    assert!(order_book.estimate_impact(10, Side::Ask).is_none());
+
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(99.00, 20, Side::Bid));
+    order_book.insert_order(Order::new(98.00, 30, Side::Bid));
+
+    // A sell of 25 fills 10 at 100 and 15 at 99, without an ask to form a mid
+    let impact = order_book.estimate_impact(25, Side::Ask).unwrap();
+    assert_eq!(impact.avg_fill_price, Decimal::new(994, 1));
+    assert_eq!(impact.slippage_bps_vs_mid, None);
+    assert_eq!(impact.slippage_bps_vs_touch, Some(Decimal::new(60, 0)));
+    assert_eq!(impact.depth_consumed_levels, 2);
+    assert_eq!(impact.filled_quantity, 25);
+
+    // Orders larger than the book report what could be filled
+    let impact = order_book.estimate_impact(100, Side::Ask).unwrap();
+    assert_eq!(impact.filled_quantity, 60);
+    assert_eq!(impact.depth_consumed_levels, 3);
+
+    assert_eq!(order_book.total_volume(Side::Bid), 60);
+    assert!(order_book.trades().is_empty());
+    assert!(order_book.estimate_impact(0, Side::Ask).is_none());
+
+    // Slippage is not relative to a reference of 0, as a spread's mid or touch can be
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(-0.50, 10, Side::Bid));
+    order_book.insert_order(Order::new(0.50, 10, Side::Ask));
+    let impact = order_book.estimate_impact(5, Side::Bid).unwrap();
+    assert_eq!(impact.slippage_bps_vs_mid, None);
+    assert_eq!(impact.slippage_bps_vs_touch, Some(Decimal::ZERO));
+    order_book.insert_order(Order::new(0.00, 10, Side::Ask));
+    let impact = order_book.estimate_impact(5, Side::Bid).unwrap();
+    assert_eq!(impact.avg_fill_price, Decimal::ZERO);
+    assert_eq!(impact.slippage_bps_vs_touch, None);
+}
+
+#[test]
+/// Test rolling and time-weighted spread statistics over a window.
+fn test_spread_tracker() {
+    let mut spread_tracker = SpreadTracker::new(Duration::from_nanos(100));
+    let price = |units| Some(Decimal::new(units, 0));
+
+    assert!(spread_tracker.record(price(99), price(101), 0)); // spread 2
+    assert!(!spread_tracker.record(price(99), price(101), 10));
+    assert!(spread_tracker.record(price(99), price(103), 50)); // spread 4
+    assert!(spread_tracker.record(price(100), None, 70)); // no spread
+    assert!(spread_tracker.record(price(100), price(101), 80)); // spread 1
+
+    assert_eq!(
+        spread_tracker.mean_spread(),
+        Some(Decimal::new(7, 0) / Decimal::new(3, 0))
+    );
+    assert_eq!(spread_tracker.median_spread(), Some(Decimal::new(2, 0)));
+    assert_eq!(spread_tracker.max_spread(), Some(Decimal::new(4, 0)));
+
+    // Over (0, 100]: 2 for 50ns, 4 for 20ns, nothing for 10ns, 1 for 20ns
+    assert_eq!(
+        spread_tracker.time_weighted_average_spread(100),
+        Some(Decimal::new(200, 0) / Decimal::new(90, 0))
+    );
+
+    // Samples superseded before the window opened are dropped
+    assert!(spread_tracker.record(price(100), price(102), 180));
+    assert_eq!(spread_tracker.samples().count(), 2);
+    assert_eq!(spread_tracker.max_spread(), Some(Decimal::new(2, 0)));
+
+    // Observing a book only records top-of-book changes
+    let mut order_book = OrderBook::new();
+    let mut spread_tracker = SpreadTracker::new(Duration::from_secs(1));
+    order_book.insert_order(Order::new(100.00, 1, Side::Bid));
+    assert!(spread_tracker.observe(&order_book));
+    order_book.insert_order(Order::new(99.00, 1, Side::Bid));
+    assert!(!spread_tracker.observe(&order_book));
+    assert_eq!(spread_tracker.latest().unwrap().spread(), None);
+}
+
+#[test]
+/// Test that the depth history samples the top levels on schedule into a ring buffer queryable by time
+fn test_depth_history() {
+    // Time-based sampling, timestamped by the book's clock
+    let mut order_book = OrderBook::deterministic(1);
+    let mut depth_history =
+        DepthHistory::new(2, SamplingSchedule::Every(Duration::from_millis(10)), 3);
+    for (step, price) in [100.00, 99.00, 98.00, 97.00, 96.00].into_iter().enumerate() {
+        order_book.set_time(step as u64 * 5_000_000);
+        order_book.insert_order(Order::new(price, 10, Side::Bid));
+        depth_history.observe(&order_book);
+    }
+    // Frames were taken at 0, 10, and 20 ms, each with the two best bids
+    let timestamps: Vec<u64> = depth_history
+        .frames()
+        .map(|frame| frame.timestamp_nanos)
+        .collect();
+    assert_eq!(timestamps, vec![0, 10_000_000, 20_000_000]);
+    let latest = depth_history.latest().unwrap();
+    assert_eq!(
+        latest.bids,
+        vec![(Decimal::new(100, 0), 10), (Decimal::new(99, 0), 10)]
+    );
+    assert!(latest.asks.is_empty());
+    assert_eq!(latest.sequence, 5);
+
+    // Range queries include the start and exclude the end
+    let range: Vec<u64> = depth_history
+        .frames_between(10_000_000, 20_000_000)
+        .map(|frame| frame.sequence)
+        .collect();
+    assert_eq!(range, vec![3]);
+    assert_eq!(
+        depth_history.frames_between(30_000_000, 10_000_000).count(),
+        0
+    );
+    assert_eq!(depth_history.frame_at(19_999_999).unwrap().sequence, 3);
+    assert!(depth_history.frame_at(0).is_some());
+
+    // The ring buffer drops the oldest frame
+    order_book.set_time(30_000_000);
+    assert!(depth_history.observe(&order_book));
+    assert_eq!(depth_history.len(), 3);
+    assert!(depth_history.frame_at(9_999_999).is_none());
+
+    // Event-count sampling works on any view, such as a cache
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    let mut depth_history = DepthHistory::new(1, SamplingSchedule::EveryNthEvent(2), 10);
+    for (timestamp, price) in [101.00, 102.00, 103.00, 104.00, 105.00]
+        .into_iter()
+        .enumerate()
+    {
+        let event = order_book
+            .insert_order(Order::new(price, 10, Side::Ask))
+            .event;
+        market_depth_cache.process_order_event(event);
+        depth_history.record(
+            &market_depth_cache,
+            timestamp as u64,
+            market_depth_cache.last_applied_sequence(),
+        );
+    }
+    let sequences: Vec<u64> = depth_history.frames().map(|frame| frame.sequence).collect();
+    assert_eq!(sequences, vec![1, 3, 5]);
+    assert_eq!(
+        depth_history.latest().unwrap().asks,
+        vec![(Decimal::new(101, 0), 10)]
+    );
+    depth_history.clear();
+    assert!(depth_history.is_empty());
+}
+
+#[test]
+/// Test that synthetic spread metrics raise alerts on threshold crossings only.
+fn test_spread_monitor() {
+    let sample = |timestamp_nanos, best_bid: Option<i64>, best_ask: Option<i64>| SpreadSample {
+        timestamp_nanos,
+        best_bid: best_bid.map(Decimal::from),
+        best_ask: best_ask.map(Decimal::from),
+    };
+    let mut spread_monitor = SpreadMonitor::new();
+    spread_monitor.alert_when(SpreadMetric::AskMinusBid, Crossing::Below, Decimal::ZERO);
+    spread_monitor.alert_when(SpreadMetric::MidRatio, Crossing::Above, Decimal::new(11, 1));
+
+    let alerts = spread_monitor.record(
+        &sample(1, Some(99), Some(101)),
+        &sample(2, Some(98), Some(100)),
+    );
+    assert!(alerts.is_empty());
+    let metrics = spread_monitor.latest().unwrap();
+    assert_eq!(metrics.timestamp_nanos, 2);
+    assert_eq!(metrics.bid_minus_ask, Some(Decimal::from(-1)));
+    assert_eq!(metrics.ask_minus_bid, Some(Decimal::from(3)));
+    assert_eq!(metrics.mid_difference, Some(Decimal::from(1)));
+
+    // A's ask below B's bid crosses the first threshold, once
+    let leg_b = sample(3, Some(102), Some(104));
+    let alerts = spread_monitor.record(&sample(3, Some(99), Some(101)), &leg_b);
+    assert_eq!(
+        alerts,
+        vec![SpreadAlert {
+            metric: SpreadMetric::AskMinusBid,
+            crossing: Crossing::Below,
+            threshold: Decimal::ZERO,
+            value: Decimal::from(-1),
+            timestamp_nanos: 3,
+        }]
+    );
+    assert!(spread_monitor
+        .record(&sample(4, Some(99), Some(100)), &leg_b)
+        .is_empty());
+
+    // An empty side keeps the threshold's state, and coming back re-arms it
+    assert!(spread_monitor
+        .record(&sample(5, Some(99), None), &leg_b)
+        .is_empty());
+    assert_eq!(spread_monitor.latest().unwrap().mid_ratio, None);
+    assert!(spread_monitor
+        .record(&sample(6, Some(99), Some(100)), &leg_b)
+        .is_empty());
+    assert!(spread_monitor
+        .record(&sample(7, Some(99), Some(103)), &leg_b)
+        .is_empty());
+    let alerts = spread_monitor.record(&sample(8, Some(99), Some(101)), &leg_b);
+    assert_eq!(alerts.len(), 1);
+
+    // Mid ratio of 115 / 50 = 2.3 crosses the second threshold
+    let alerts = spread_monitor.record(
+        &sample(9, Some(114), Some(116)),
+        &sample(9, Some(49), Some(51)),
+    );
+    assert_eq!(alerts[0].metric, SpreadMetric::MidRatio);
+    assert_eq!(alerts[0].value, Decimal::new(23, 1));
+
+    // Observing books reads their top of book
+    let mut leg_a = OrderBook::deterministic(0);
+    leg_a.set_time(42);
+    let mut leg_b = OrderBook::new();
+    leg_a.insert_order(Order::new(10.0, 1, Side::Ask));
+    leg_b.insert_order(Order::new(11.0, 1, Side::Bid));
+    let mut spread_monitor = SpreadMonitor::new();
+    spread_monitor.alert_when(SpreadMetric::AskMinusBid, Crossing::Below, Decimal::ZERO);
+    let alerts = spread_monitor.observe(&leg_a, &leg_b);
+    assert_eq!(alerts[0].timestamp_nanos, 42);
+    assert_eq!(alerts[0].value, Decimal::from(-1));
+}
+
+#[test]
+/// Test that a spread and its legs imply orders in each other and match through them.
+fn test_implied_spread_book() {
+    let mut spread_book = SpreadBook::new();
+    for (market, price, quantity, side) in [
+        (SpreadMarket::FirstLeg, 100.0, 5, Side::Bid),
+        (SpreadMarket::FirstLeg, 101.0, 5, Side::Ask),
+        (SpreadMarket::SecondLeg, 99.0, 3, Side::Bid),
+        (SpreadMarket::SecondLeg, 100.0, 4, Side::Ask),
+        (SpreadMarket::Spread, 0.5, 2, Side::Bid),
+        (SpreadMarket::Spread, 2.5, 2, Side::Ask),
+    ] {
+        spread_book
+            .book_mut(market)
+            .insert_order(Order::new(price, quantity, side));
+    }
+
+    let implied: Vec<(SpreadMarket, Decimal, u64, Side)> = spread_book
+        .implied_orders()
+        .into_iter()
+        .map(|implied| {
+            let order = implied.order;
+            (implied.market, order.price, order.quantity, order.side)
+        })
+        .collect();
+    assert_eq!(
+        implied,
+        vec![
+            (SpreadMarket::Spread, Decimal::ZERO, 4, Side::Bid),
+            (SpreadMarket::Spread, Decimal::TWO, 3, Side::Ask),
+            (SpreadMarket::FirstLeg, Decimal::new(995, 1), 2, Side::Bid),
+            (SpreadMarket::FirstLeg, Decimal::new(1025, 1), 2, Side::Ask),
+            (SpreadMarket::SecondLeg, Decimal::new(975, 1), 2, Side::Bid),
+            (SpreadMarket::SecondLeg, Decimal::new(1005, 1), 2, Side::Ask),
+        ]
+    );
+
+    // The better implied ask executes first, then the spread's own ask
+    let outcome = spread_book.match_order(SpreadMarket::Spread, Order::new(2.5, 4, Side::Bid));
+    assert_eq!(outcome.filled_quantity, 4);
+    assert!(outcome.handle.is_none());
+    let markets: Vec<SpreadMarket> = outcome
+        .executions
+        .iter()
+        .map(|(market, _)| *market)
+        .collect();
+    assert_eq!(
+        markets,
+        vec![
+            SpreadMarket::FirstLeg,
+            SpreadMarket::SecondLeg,
+            SpreadMarket::Spread
+        ]
+    );
+    let first_leg = spread_book.book(SpreadMarket::FirstLeg);
+    assert_eq!(first_leg.trades()[0].price, Decimal::from(101));
+    assert_eq!(first_leg.total_volume(Side::Ask), 2);
+    assert_eq!(
+        spread_book
+            .book(SpreadMarket::SecondLeg)
+            .total_volume(Side::Bid),
+        0
+    );
+    assert_eq!(
+        spread_book
+            .book(SpreadMarket::Spread)
+            .total_volume(Side::Ask),
+        1
+    );
+
+    // Without implied pricing, orders only match their own book and rest
+    spread_book.set_implied_in(false);
+    spread_book.set_implied_out(false);
+    assert!(spread_book.implied_orders().is_empty());
+    let outcome = spread_book.match_order(SpreadMarket::Spread, Order::new(0.0, 3, Side::Ask));
+    assert_eq!(outcome.filled_quantity, 2);
+    assert!(outcome.handle.is_some());
+    assert_eq!(
+        spread_book
+            .book(SpreadMarket::Spread)
+            .total_volume(Side::Ask),
+        2
+    );
+    assert_eq!(
+        spread_book
+            .book(SpreadMarket::FirstLeg)
+            .total_volume(Side::Bid),
+        5
+    );
+}
+
+#[test]
+/// Test that order flow counts follow the market-by-order stream over a rolling window.
+fn test_order_flow_stats() {
+    let mut order_flow_stats = OrderFlowStats::new(Duration::from_nanos(100));
+    let price = Decimal::new(100, 0);
+    let added = |side| MarketByOrderEvent::Added {
+        order_id: OrderId(1),
+        price,
+        quantity: 10,
+        side,
+    };
+    let executed = |side, executed_quantity| MarketByOrderEvent::Executed {
+        order_id: OrderId(1),
+        price,
+        side,
+        executed_quantity,
+        remaining_quantity: 0,
+        trade_id: TradeId(1),
+        taker_order_id: OrderId(2),
+    };
+    let cancelled = MarketByOrderEvent::Cancelled {
+        order_id: OrderId(2),
+        price,
+        side: Side::Bid,
+        cancelled_quantity: 3,
+        remaining_quantity: 7,
+    };
+
+    order_flow_stats.record(&added(Side::Bid), 0);
+    order_flow_stats.record(&added(Side::Ask), 10);
+    order_flow_stats.record(&executed(Side::Ask, 6), 20);
+    order_flow_stats.record(&executed(Side::Bid, 2), 30);
+    order_flow_stats.record(&cancelled, 40);
+    order_flow_stats.record(&cancelled, 50);
+    assert_eq!(order_flow_stats.cancel_to_trade_ratio(), Some(1.0));
+    assert_eq!(order_flow_stats.net_order_flow(), 4);
+
+    let bid_counts = order_flow_stats.counts(Side::Bid);
+    assert_eq!(bid_counts.adds, 1);
+    assert_eq!(bid_counts.cancels, 2);
+    assert_eq!(bid_counts.cancelled_quantity, 6);
+    assert_eq!(bid_counts.executed_quantity, 2);
+
+    // At 125 the window opens at 25, dropping both adds and the ask execution
+    order_flow_stats.record(&added(Side::Ask), 125);
+    assert_eq!(order_flow_stats.counts(Side::Bid).adds, 0);
+    assert_eq!(order_flow_stats.counts(Side::Ask).adds, 1);
+    assert_eq!(order_flow_stats.cancel_to_trade_ratio(), Some(2.0));
+    assert_eq!(order_flow_stats.net_order_flow(), -2);
+
+    // Once everything has aged out, the ratio is undefined again
+    order_flow_stats.record(&added(Side::Ask), 1_000);
+    assert_eq!(order_flow_stats.cancel_to_trade_ratio(), None);
+    assert_eq!(order_flow_stats.net_order_flow(), 0);
+}
+
+#[test]
+/// Test that the book attributes adds, cancels, and executions to distance-from-touch buckets
+fn test_distance_stats() {
+    let mut order_book = OrderBook::new();
+    assert!(order_book.distance_stats().is_none());
+    order_book.set_distance_stats(Some(Decimal::new(1, 2)));
+
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    let near = order_book
+        .insert_order(Order::new(99.98, 5, Side::Bid))
+        .handle;
+    let away = order_book
+        .insert_order(Order::new(99.90, 5, Side::Bid))
+        .handle;
+    // Improving the touch counts at the touch
+    order_book.insert_order(Order::new(100.01, 3, Side::Bid));
+    order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+
+    // Executions happen at the touch, even when they empty it
+    order_book.match_order(Order::new(100.00, 12, Side::Ask));
+    order_book.cancel_by_handle(away).unwrap();
+    order_book.reduce_order(near.order_id(), 2).unwrap();
+
+    let distance_stats = order_book.distance_stats().unwrap();
+    assert_eq!(distance_stats.tick_size(), Decimal::new(1, 2));
+    let at_touch = distance_stats.counts(Side::Bid, DistanceBucket::AtTouch);
+    assert_eq!((at_touch.adds, at_touch.added_quantity), (2, 13));
+    assert_eq!((at_touch.executions, at_touch.executed_quantity), (2, 12));
+    let near_touch = distance_stats.counts(Side::Bid, DistanceBucket::NearTouch);
+    assert_eq!((near_touch.adds, near_touch.cancels), (1, 1));
+    assert_eq!(near_touch.cancelled_quantity, 2);
+    let away_from_touch = distance_stats.counts(Side::Bid, DistanceBucket::AwayFromTouch);
+    assert_eq!((away_from_touch.adds, away_from_touch.cancels), (1, 1));
+    assert_eq!(
+        distance_stats
+            .counts(Side::Ask, DistanceBucket::AtTouch)
+            .adds,
+        1
+    );
+    let total_adds: u64 = DistanceBucket::ALL
+        .iter()
+        .map(|bucket| distance_stats.counts(Side::Bid, *bucket).adds)
+        .sum();
+    assert_eq!(total_adds, 4);
+
+    // The bucket boundaries
+    assert_eq!(DistanceBucket::of_ticks(0), DistanceBucket::AtTouch);
+    assert_eq!(DistanceBucket::of_ticks(5), DistanceBucket::NearTouch);
+    assert_eq!(DistanceBucket::of_ticks(6), DistanceBucket::AwayFromTouch);
+
+    // Enabling again restarts the counts, and disabling stops them
+    order_book.set_distance_stats(Some(Decimal::new(1, 2)));
+    assert_eq!(
+        order_book
+            .distance_stats()
+            .unwrap()
+            .counts(Side::Bid, DistanceBucket::AtTouch),
+        FlowCounts::default()
+    );
+    order_book.set_distance_stats(None);
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    assert!(order_book.distance_stats().is_none());
+}
+
+#[test]
+/// Test that submitting a quote replaces what remains of the participant's previous quote.
+fn test_submit_quote() {
+    let mut order_book = OrderBook::new();
+    let market_maker = ParticipantId(1);
+    let other_market_maker = ParticipantId(2);
+
+    let first = order_book.submit_quote(
+        market_maker,
+        BidPrice(Decimal::new(99, 0)),
+        10,
+        AskPrice(Decimal::new(101, 0)),
+        10,
+    );
+    order_book.submit_quote(
+        other_market_maker,
+        BidPrice(Decimal::new(98, 0)),
+        3,
+        AskPrice(Decimal::new(102, 0)),
+        3,
+    );
+    assert_eq!(first.events.len(), 2);
+    assert_eq!(order_book.order_count(), 4);
+
+    // The first ask is hit in full before the quote is refreshed
+    order_book.match_order(Order::new(101.00, 10, Side::Bid));
+    let second = order_book.submit_quote(
+        market_maker,
+        BidPrice(Decimal::new(100, 0)),
+        5,
+        AskPrice(Decimal::ZERO),
+        0,
+    );
+
+    assert!(second.ask.is_none());
+    assert_eq!(second.events.len(), 2, "Only the resting bid is cancelled");
+    assert_eq!(second.events[0].quantity_delta, -10);
+    assert_eq!(second.events[0].side, Side::Bid);
+    assert_eq!(second.events[1].quantity_delta, 5);
+    assert!(order_book
+        .get_order(first.bid.unwrap().order_id())
+        .is_none());
+    assert_eq!(order_book.order_count(), 3);
+
+    // Pulling the quote leaves the other participant untouched
+    assert_eq!(order_book.cancel_quote(market_maker).len(), 1);
+    assert!(order_book.cancel_quote(market_maker).is_empty());
+    assert_eq!(order_book.order_count(), 2);
+}
+
+#[test]
+/// Test comparing prices typed by side
+fn test_side_typed_prices() {
+    let (low_bid, high_bid) = (
+        BidPrice(Decimal::new(99, 0)),
+        BidPrice(Decimal::new(100, 0)),
+    );
+    let (low_ask, high_ask) = (
+        AskPrice(Decimal::new(100, 0)),
+        AskPrice(Decimal::new(101, 0)),
+    );
+
+    // Better means higher on the bid side and lower on the ask side
+    assert!(high_bid.is_better_than(low_bid));
+    assert!(!low_bid.is_better_than(high_bid));
+    assert!(low_ask.is_better_than(high_ask));
+    assert!(!high_bid.is_better_than(high_bid));
+
+    assert!(high_bid.crosses(low_ask) && low_ask.crosses(high_bid));
+    assert!(!low_bid.crosses(low_ask));
+    assert_eq!(low_bid.spread_to(high_ask), Decimal::new(2, 0));
+    assert_eq!(high_ask.spread_to(low_bid), Decimal::new(2, 0));
+    assert_eq!(high_ask.to_string(), "101");
+
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(low_bid.order(10));
+    order_book.insert_order(high_ask.order(10));
+    let bbo = order_book.bbo();
+    assert_eq!(bbo.bid_price(), Some(low_bid));
+    assert_eq!(bbo.ask_price(), Some(high_ask));
+    assert_eq!(
+        Decimal::from(bbo.ask_price().unwrap()),
+        Decimal::new(101, 0)
+    );
+    assert_eq!(Bbo::default().bid_price(), None);
+}
+
+#[test]
+/// Test the overflow checks of typed prices and quantities
+fn test_typed_price_and_quantity() {
+    let price = Price::from(Decimal::new(10050, 2));
+    assert_eq!(
+        price - Price(Decimal::new(50, 2)),
+        Price(Decimal::new(100, 0))
+    );
+    assert_eq!(Price(Decimal::MAX).checked_add(price), None);
+    assert_eq!(Price(Decimal::MAX).notional(Quantity(2)), None);
+    assert_eq!(price.notional(Quantity(2)), Some(Decimal::new(201, 0)));
+    assert_eq!(Price::from(BidPrice(Decimal::ONE)), Price(Decimal::ONE));
+    assert_eq!(Decimal::from(price), Decimal::new(10050, 2));
+
+    assert_eq!(Quantity(u64::MAX).checked_add(Quantity(1)), None);
+    assert_eq!(Quantity(3).saturating_sub(Quantity(5)), Quantity::ZERO);
+    assert_eq!(u64::from(Quantity(7) + Quantity::from(3)), 10);
+    assert_eq!(Quantity(42).to_string(), "42");
+    let overflow = std::panic::catch_unwind(|| {
+        [Quantity(u64::MAX), Quantity(1)]
+            .into_iter()
+            .sum::<Quantity>()
+    });
+    assert!(overflow.is_err());
+
+    let order = Order::priced(price, Quantity(10), Side::Bid);
+    assert_eq!(order, Order::new(100.50, 10, Side::Bid));
+}
+
+#[test]
+/// Test that quote protection pulls every remaining quote once the limit is reached.
+fn test_quote_protection() {
+    let mut order_book = OrderBook::new();
+    let protected = ParticipantId(1);
+    let unprotected = ParticipantId(2);
+    order_book.set_quote_protection(
+        protected,
+        Some(QuoteProtection {
+            executed_quantity_limit: 15,
+            window: Duration::from_secs(60),
+        }),
+    );
+
+    let quote = order_book.submit_quote(
+        protected,
+        BidPrice(Decimal::new(99, 0)),
+        20,
+        AskPrice(Decimal::new(101, 0)),
+        20,
+    );
+    order_book.submit_quote(
+        unprotected,
+        BidPrice(Decimal::new(98, 0)),
+        20,
+        AskPrice(Decimal::new(101, 0)),
+        20,
+    );
+
+    // 10 executed against the protected ask stays under the limit
+    let outcome = order_book.match_order(Order::new(101.00, 10, Side::Bid));
+    assert!(outcome.protections_triggered.is_empty());
+
+    // Filling the other 10 breaches it: the bid is pulled and the order moves on
+    let outcome = order_book.match_order(Order::new(101.00, 12, Side::Bid));
+    assert_eq!(outcome.protections_triggered.len(), 1);
+    let triggered = &outcome.protections_triggered[0];
+    assert_eq!(triggered.participant_id, protected);
+    assert_eq!(triggered.executed_quantity, 20);
+    assert_eq!(triggered.pulled_orders, vec![quote.bid.unwrap().order_id()]);
+
+    let deltas: Vec<i64> = outcome
+        .events
+        .iter()
+        .map(|event| event.quantity_delta)
+        .collect();
+    assert_eq!(deltas, vec![-10, -20, -2]);
+    assert_eq!(order_book.trades().len(), 3);
+    assert_eq!(order_book.total_volume(Side::Ask), 18);
+    assert_eq!(order_book.total_volume(Side::Bid), 20);
+
+    // The history is cleared, so a fresh quote is not pulled by the next execution
+    order_book.submit_quote(
+        protected,
+        BidPrice(Decimal::new(99, 0)),
+        5,
+        AskPrice(Decimal::new(100, 0)),
+        5,
+    );
+    let outcome = order_book.match_order(Order::new(100.00, 1, Side::Bid));
+    assert!(outcome.protections_triggered.is_empty());
+}
+
+#[test]
+/// Test that trades outside the price bands halt the book until trading resumes.
+fn test_luld_circuit_breaker() {
+    let mut order_book = OrderBook::new();
+    order_book.set_luld_bands(Some(LuldBands {
+        band_fraction: Decimal::new(1, 1),
+    }));
+    order_book.insert_order(Order::new(100.00, 5, Side::Ask));
+    order_book.insert_order(Order::new(105.00, 5, Side::Ask));
+    order_book.insert_order(Order::new(120.00, 5, Side::Ask));
+
+    // Without a reference price nothing is enforced, and the first trade sets it
+    let outcome = order_book.match_order(Order::new(100.00, 5, Side::Bid));
+    assert!(outcome.state_change.is_none());
+    assert_eq!(order_book.reference_price(), Some(Decimal::new(100, 0)));
+
+    // 105 is inside 100 ± 10%, but 120 is not inside 105 ± 10%
+    let outcome = order_book.match_order(Order::new(125.00, 8, Side::Bid));
+    let state_change = outcome.state_change.expect("the book should halt");
+    assert_eq!(state_change.state, TradingState::Halted);
+    assert_eq!(state_change.reference_price, Decimal::new(105, 0));
+    assert_eq!(state_change.breach_price, Decimal::new(120, 0));
+    assert_eq!(state_change.upper_band, Decimal::new(1155, 1));
+    assert_eq!(order_book.trades().len(), 2);
+    assert!(outcome.handle.is_some(), "The remaining 3 should rest");
+
+    // While halted, crossing orders rest without matching
+    let outcome = order_book.match_order(Order::new(120.00, 1, Side::Bid));
+    assert!(outcome.state_change.is_none());
+    assert_eq!(order_book.trades().len(), 2);
+    assert_eq!(order_book.trading_state(), TradingState::Halted);
+
+    order_book.resume_trading();
+    order_book.set_reference_price(Decimal::new(120, 0));
+    order_book.match_order(Order::new(120.00, 5, Side::Bid));
+    assert_eq!(order_book.trades().len(), 3);
+    assert_eq!(order_book.trading_state(), TradingState::Continuous);
+}
+
+#[test]
+/// Test that a cloned book evolves independently of the original.
+fn test_order_book_fork() {
+    let mut order_book = OrderBook::new();
+    let ask = order_book
+        .insert_order(Order::new(101.00, 10, Side::Ask))
+        .handle;
+    order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+
+    let mut what_if = order_book.clone();
+    let outcome = what_if.match_order(Order::new(101.00, 4, Side::Bid));
+    assert!(outcome.handle.is_none());
+    assert_eq!(what_if.total_volume(Side::Ask), 6);
+    assert_eq!(what_if.trades().len(), 1);
+
+    // The live book is untouched, and its handles still address both copies
+    assert_eq!(order_book.total_volume(Side::Ask), 10);
+    assert!(order_book.trades().is_empty());
+    what_if
+        .cancel_by_handle(ask)
+        .expect("handle should be valid on the fork");
+    assert_eq!(what_if.ask_levels_count(), 0);
+    assert_eq!(order_book.ask_levels_count(), 1);
+    assert_eq!(order_book.get_order(ask.order_id()).unwrap().quantity, 10);
+}
+
+#[test]
+/// Test that book diffs list added, removed, and changed levels per side.
+fn test_order_book_diff() {
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(98.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    assert!(order_book.diff(&order_book.clone()).is_empty());
+
+    let mut other = order_book.clone();
+    other.match_order(Order::new(99.00, 10, Side::Ask)); // removes 99
+    other.insert_order(Order::new(97.50, 3, Side::Bid)); // adds 97.5
+    other.insert_order(Order::new(101.00, 2, Side::Ask)); // changes 101
+
+    let diff = order_book.diff(&other);
+    assert_eq!(
+        diff.bids,
+        vec![
+            LevelDiff::Added {
+                price: Decimal::new(975, 1),
+                quantity: 3
+            },
+            LevelDiff::Removed {
+                price: Decimal::new(99, 0),
+                quantity: 10
+            },
+        ]
+    );
+    assert_eq!(
+        diff.asks,
+        vec![LevelDiff::Changed {
+            price: Decimal::new(101, 0),
+            from: 10,
+            to: 12
+        }]
+    );
+
+    // The reverse diff undoes every change
+    let reverse = other.diff(&order_book);
+    assert_eq!(reverse.bids.len(), 2);
+    assert!(matches!(
+        reverse.asks[0],
+        LevelDiff::Changed {
+            from: 12,
+            to: 10,
+            ..
+        }
+    ));
+}
+
+#[test]
+/// Test that the order book agrees with the reference book on a generated command sequence.
+fn test_conformance_with_reference_book() {
+    use order_book::conformance::{check_conformance, Command};
+
+    // A small linear congruential generator keeps the sequence reproducible
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+
+    let mut commands = Vec::new();
+    for _ in 0..2_000 {
+        let side = if next(2) == 0 { Side::Bid } else { Side::Ask };
+        let price = Decimal::new(9_950 + next(100) as i64, 2);
+        let quantity = 1 + next(50);
+        let order = Order {
+            price,
+            quantity,
+            side,
+        };
+        let command = match next(10) {
+            0..=3 => Command::Insert(order),
+            4..=6 => Command::Match(order),
+            7 | 8 => Command::Cancel(OrderId(1 + next(commands.len() as u64 + 1))),
+            _ => Command::Reduce(OrderId(1 + next(commands.len() as u64 + 1)), next(20)),
+        };
+        commands.push(command);
+    }
+
+    if let Err(failure) = check_conformance(&commands) {
+        panic!("{failure}");
+    }
+}
+
+#[test]
+/// Test that order size and level occupancy histograms follow every book change.
+fn test_book_histograms() {
+    let mut order_book = OrderBook::new();
+    let first = order_book
+        .insert_order(Order::new(100.00, 12, Side::Ask))
+        .handle;
+    order_book.insert_order(Order::new(100.00, 3, Side::Ask));
+    order_book.insert_order(Order::new(100.00, 1, Side::Ask));
+    order_book.insert_order(Order::new(101.00, 40, Side::Ask));
+
+    let buckets = |histogram: &order_book::Histogram| histogram.buckets().collect::<Vec<_>>();
+    assert_eq!(
+        buckets(order_book.order_size_histogram(Side::Ask)),
+        vec![(1, 1), (2, 1), (8, 1), (32, 1)]
+    );
+    assert_eq!(
+        buckets(order_book.level_order_count_histogram(Side::Ask)),
+        vec![(1, 1), (2, 1)],
+        "One level with one order and one with three"
+    );
+    assert_eq!(order_book.order_size_histogram(Side::Bid).total(), 0);
+
+    // Executing 5 of the 12 moves it to the [4, 8) bucket
+    order_book.match_order(Order::new(100.00, 5, Side::Bid));
+    assert_eq!(
+        buckets(order_book.order_size_histogram(Side::Ask)),
+        vec![(1, 1), (2, 1), (4, 1), (32, 1)]
+    );
+
+    order_book
+        .cancel_by_handle(first)
+        .expect("order should be resting");
+    order_book
+        .reduce_order(OrderId(3), 1)
+        .expect("order should be resting");
+    assert_eq!(
+        buckets(order_book.order_size_histogram(Side::Ask)),
+        vec![(2, 1), (32, 1)]
+    );
+    assert_eq!(
+        buckets(order_book.level_order_count_histogram(Side::Ask)),
+        vec![(1, 2)]
+    );
+}
+
+#[test]
+/// Test that bulk cancellation removes exactly the orders matching the predicate.
+fn test_cancel_where() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    for (price, quantity, side) in [
+        (99.00, 10, Side::Bid),
+        (99.00, 1, Side::Bid),
+        (98.00, 2, Side::Bid),
+        (101.00, 30, Side::Ask),
+        (102.00, 3, Side::Ask),
+    ] {
+        let event = order_book
+            .insert_order(Order::new(price, quantity, side))
+            .event;
+        market_depth_cache.process_order_event(event);
+    }
+
+    let events = order_book.cancel_where(|order| order.quantity < 5);
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|event| event.quantity_delta < 0));
+    for event in events {
+        market_depth_cache.process_order_event(event);
+    }
+
+    assert_eq!(order_book.order_count(), 2);
+    assert_eq!(order_book.bid_levels_count(), 1);
+    assert_eq!(order_book.ask_levels_count(), 1);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(99, 0), Side::Bid),
+        10
+    );
+    assert_eq!(market_depth_cache.bid_levels_count(), 1);
+    assert!(order_book.cancel_where(|_| false).is_empty());
+}
+
+#[test]
+/// Test that band trimming keeps orders within the band of the best price on one side only.
+fn test_cancel_outside_band() {
+    let mut order_book = OrderBook::new();
+    assert!(order_book
+        .cancel_outside_band(Side::Bid, Decimal::ONE)
+        .is_empty());
+
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(99.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(98.00, 1, Side::Bid));
+    order_book.insert_order(Order::new(98.00, 2, Side::Bid));
+    order_book.insert_order(Order::new(50.00, 3, Side::Bid));
+    order_book.insert_order(Order::new(150.00, 3, Side::Ask));
+
+    let events = order_book.cancel_outside_band(Side::Bid, Decimal::ONE);
+    let deltas: Vec<i64> = events.iter().map(|event| event.quantity_delta).collect();
+    assert_eq!(
+        deltas,
+        vec![-1, -2, -3],
+        "Closest level first, FIFO within a level"
+    );
+
+    assert_eq!(
+        order_book.price_range(Side::Bid),
+        Some((Decimal::new(99, 0), Decimal::new(100, 0)))
+    );
+    assert_eq!(order_book.total_volume(Side::Bid), 20);
+    assert_eq!(order_book.total_volume(Side::Ask), 3);
+}
+
+#[test]
+/// Test that a bounded cache always holds the levels closest to the touch of an unbounded one.
+fn test_cache_max_levels_per_side() {
+    let mut order_book = OrderBook::new();
+    let unbounded_cache = MarketDepthCache::new();
+    let bounded_cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        max_levels_per_side: Some(3),
+        ..MarketDepthCacheConfig::default()
+    });
+    let mut handles = Vec::new();
+
+    let mut state: u64 = 7;
+    let mut next = |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+
+    for _ in 0..1_000 {
+        let event = if handles.is_empty() || next(3) > 0 {
+            let side = if next(2) == 0 { Side::Bid } else { Side::Ask };
+            let price = 90.0 + next(20) as f64 + 0.25;
+            let outcome = order_book.insert_order(Order::new(price, 1 + next(9), side));
+            handles.push(outcome.handle);
+            outcome.event
+        } else {
+            let handle = handles.swap_remove(next(handles.len() as u64) as usize);
+            order_book.cancel_by_handle(handle).unwrap()
+        };
+        unbounded_cache.process_order_event(event.clone());
+        bounded_cache.process_order_event(event);
+
+        let (all_bids, all_asks) = unbounded_cache.get_aggregated_market_depth();
+        let (kept_bids, kept_asks) = bounded_cache.get_aggregated_market_depth();
+        let closest_bids: Vec<_> = all_bids.iter().rev().take(3).collect();
+        let closest_asks: Vec<_> = all_asks.iter().take(3).collect();
+        assert_eq!(kept_bids.iter().rev().collect::<Vec<_>>(), closest_bids);
+        assert_eq!(kept_asks.iter().collect::<Vec<_>>(), closest_asks);
+
+        assert_eq!(
+            bounded_cache.evicted_levels_count(Side::Bid),
+            all_bids.len().saturating_sub(3)
+        );
+        for (price, quantity) in &all_bids {
+            assert_eq!(
+                bounded_cache.get_quantity_at_level(*price, Side::Bid),
+                *quantity
+            );
+        }
+    }
+}
+
+#[test]
+/// Test that the cache options compose: side-aware buckets, bounded depth, and exact levels.
+fn test_cache_combined_config() {
+    let config = MarketDepthCacheConfig {
+        bucketing_policy: BucketingPolicy::SideAware,
+        max_levels_per_side: Some(2),
+        exact_levels: true,
+        ..MarketDepthCacheConfig::default()
+    };
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::with_config(config);
+    assert_eq!(market_depth_cache.config(), config);
+
+    let mut handles = Vec::new();
+    for price in [100.25, 101.25, 102.25] {
+        let outcome = order_book.insert_order(Order::new(price, 5, Side::Ask));
+        market_depth_cache.process_order_event(outcome.event);
+        handles.push(outcome.handle);
+    }
+
+    // Asks round up, and only the two levels closest to the touch are kept
+    let (_, asks) = market_depth_cache.get_aggregated_market_depth();
+    assert_eq!(
+        asks.keys().copied().collect::<Vec<_>>(),
+        vec![Decimal::new(101, 0), Decimal::new(102, 0)]
+    );
+    assert_eq!(market_depth_cache.evicted_levels_count(Side::Ask), 1);
+
+    // The exact levels keep every price, evicted bucket or not
+    let exact = market_depth_cache.exact_snapshot().unwrap();
+    assert_eq!(
+        exact.asks.keys().copied().collect::<Vec<_>>(),
+        vec![
+            Decimal::new(10025, 2),
+            Decimal::new(10125, 2),
+            Decimal::new(10225, 2)
+        ]
+    );
+
+    // Removing the touch restores the evicted level
+    let event = order_book.cancel_by_handle(handles[0]).unwrap();
+    market_depth_cache.process_order_event(event);
+    let (_, asks) = market_depth_cache.get_aggregated_market_depth();
+    assert_eq!(
+        asks.keys().copied().collect::<Vec<_>>(),
+        vec![Decimal::new(102, 0), Decimal::new(103, 0)]
+    );
+    assert_eq!(market_depth_cache.evicted_levels_count(Side::Ask), 0);
+    assert_eq!(market_depth_cache.exact_snapshot().unwrap().asks.len(), 2);
+}
+
+#[test]
+/// Test that memory estimates grow with the book and cache contents.
+fn test_approx_memory_bytes() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    let empty_book_bytes = order_book.approx_memory_bytes();
+    assert_eq!(market_depth_cache.approx_memory_bytes(), 0);
+
+    for index in 0..500 {
+        let event = order_book
+            .insert_order(Order::new(100.0 + index as f64, 10, Side::Bid))
+            .event;
+        market_depth_cache.process_order_event(event);
+    }
+    let half_book_bytes = order_book.approx_memory_bytes();
+    let half_cache_bytes = market_depth_cache.approx_memory_bytes();
+    assert!(half_book_bytes > empty_book_bytes);
+    assert!(half_cache_bytes > 0);
+
+    for index in 500..1_000 {
+        let event = order_book
+            .insert_order(Order::new(100.0 + index as f64, 10, Side::Bid))
+            .event;
+        market_depth_cache.process_order_event(event);
+    }
+    assert!(order_book.approx_memory_bytes() > half_book_bytes);
+    assert_eq!(
+        market_depth_cache.approx_memory_bytes(),
+        2 * half_cache_bytes
+    );
+}
+
+#[test]
+/// Test that compaction releases vacant slots and capacity without changing the book
+fn test_compact() {
+    let mut order_book = OrderBook::new();
+    let market_maker = ParticipantId(7);
+    let mut handles = Vec::new();
+    for index in 0..2_000u64 {
+        let side = if index % 2 == 0 { Side::Bid } else { Side::Ask };
+        let price = if side == Side::Bid { 99.0 } else { 101.0 } - (index % 10) as f64 / 10.0;
+        handles.push(
+            order_book
+                .insert_order(Order::new(price, 1 + index % 7, side))
+                .handle,
+        );
+    }
+    // A quote resting in a high slot, so compaction moves it
+    let quote = order_book.submit_quote(
+        market_maker,
+        BidPrice(Decimal::new(95, 0)),
+        10,
+        AskPrice(Decimal::new(105, 0)),
+        10,
+    );
+    for (index, handle) in handles.iter().enumerate() {
+        if index % 3 != 0 {
+            order_book.cancel_by_handle(*handle).unwrap();
+        }
+    }
+    let snapshot = order_book.snapshot();
+    let hash = order_book.state_hash();
+
+    let report = order_book.compact();
+    assert_eq!(report.released_slots, 2_002 - order_book.order_count());
+    assert!(report.relocated_orders > 0);
+    assert!(report.bytes_after < report.bytes_before);
+    assert_eq!(order_book.snapshot(), snapshot);
+    assert_eq!(order_book.state_hash(), hash);
+    assert_eq!(order_book.compact().relocated_orders, 0);
+
+    // The handles of orders left in place still cancel them, while those of moved orders
+    // are stale, never alias an order inserted later, and their identifiers still work
+    let moved = handles
+        .iter()
+        .step_by(3)
+        .copied()
+        .filter(|handle| order_book.cancel_by_handle(*handle).is_err())
+        .collect::<Vec<_>>();
+    assert_eq!(moved.len() + 2, report.relocated_orders);
+    let inserted = order_book
+        .insert_order(Order::new(99.0, 1, Side::Bid))
+        .handle;
+    for handle in &moved {
+        assert_eq!(
+            order_book.cancel_by_handle(*handle),
+            Err(OrderBookError::StaleHandle(handle.order_id()))
+        );
+        assert!(order_book.get_order(handle.order_id()).is_some());
+    }
+    assert!(order_book.cancel_by_handle(inserted).is_ok());
+
+    // Quotes follow their moved orders, and the levels still match in time priority
+    assert!(quote
+        .bid
+        .is_some_and(|bid| order_book.cancel_by_handle(bid).is_err()));
+    assert_eq!(order_book.cancel_quote(market_maker).len(), 2);
+    let best_bid = order_book.snapshot().bids[0].clone();
+    let outcome = order_book.match_order(Order::new(90.0, best_bid.1.quantity, Side::Ask));
+    assert_eq!(
+        order_book.trades().last().unwrap().maker_order_id,
+        best_bid.0
+    );
+    assert_eq!(outcome.fills.filled_quantity, best_bid.1.quantity);
+}
+
+#[test]
+/// Test that top-of-book copies overwrite the caller's buffers without reallocating them.
+fn test_copy_top_levels_into() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    for (price, side) in [
+        (99.10, Side::Bid),
+        (98.10, Side::Bid),
+        (97.10, Side::Bid),
+        (101.10, Side::Ask),
+    ] {
+        let event = order_book.insert_order(Order::new(price, 10, side)).event;
+        market_depth_cache.process_order_event(event);
+    }
+
+    let mut bids = vec![(Decimal::ZERO, 0); 8];
+    let mut asks = Vec::with_capacity(8);
+    let (bid_capacity, ask_capacity) = (bids.capacity(), asks.capacity());
+
+    market_depth_cache.copy_top_levels_into(&mut bids, &mut asks, 2);
+    assert_eq!(
+        bids,
+        vec![(Decimal::new(99, 0), 10), (Decimal::new(98, 0), 10)]
+    );
+    assert_eq!(asks, vec![(Decimal::new(101, 0), 10)]);
+    assert_eq!(
+        (bids.capacity(), asks.capacity()),
+        (bid_capacity, ask_capacity)
+    );
+
+    market_depth_cache.clear();
+    market_depth_cache.copy_top_levels_into(&mut bids, &mut asks, 2);
+    assert!(bids.is_empty() && asks.is_empty());
+}
+
+/// Builds a book with many levels on both sides, returning it with an event-fed cache.
+fn build_deep_book(level_count: u32, market_depth_cache: &MarketDepthCache) -> OrderBook {
+    let mut order_book = OrderBook::new();
+    for index in 0..level_count {
+        let offset = Decimal::new(i64::from(index) * 25, 2);
+        for (price, side) in [
+            (Decimal::new(10_000, 0) - offset, Side::Bid),
+            (Decimal::new(10_001, 0) + offset, Side::Ask),
+        ] {
+            let order = Order {
+                price,
+                quantity: u64::from(index % 7 + 1),
+                side,
+            };
+            market_depth_cache.process_order_event(order_book.insert_order(order).event);
+        }
+    }
+    order_book
+}
+
+#[test]
+/// Test that rebuilding a cache from the book reproduces the event-driven depth.
+fn test_cache_rebuild_from_book() {
+    let market_depth_cache = MarketDepthCache::new();
+    let order_book = build_deep_book(2_000, &market_depth_cache);
+    let expected_depth = market_depth_cache.get_aggregated_market_depth();
+    let expected_levels = market_depth_cache.get_aggregated_levels();
+
+    market_depth_cache.clear();
+    market_depth_cache.rebuild_from(&order_book);
+    assert_eq!(
+        market_depth_cache.get_aggregated_market_depth(),
+        expected_depth
+    );
+    assert_eq!(market_depth_cache.get_aggregated_levels(), expected_levels);
+
+    // A bounded cache keeps the closest levels and evicts the rest
+    let bounded_cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        max_levels_per_side: Some(10),
+        ..MarketDepthCacheConfig::default()
+    });
+    bounded_cache.rebuild_from(&order_book);
+    let (bid_depth, ask_depth) = bounded_cache.get_aggregated_market_depth();
+    assert_eq!(bid_depth.len(), 10);
+    assert_eq!(
+        bid_depth.keys().next_back(),
+        expected_depth.0.keys().next_back()
+    );
+    assert_eq!(ask_depth.keys().next(), expected_depth.1.keys().next());
+    assert_eq!(
+        bounded_cache.evicted_levels_count(Side::Ask),
+        expected_depth.1.len() - 10
+    );
+}
+
+#[test]
+/// Test that coalesced events produce the same depth as the events they replace.
+fn test_coalescing_buffer() {
+    let mut order_book = OrderBook::new();
+    let direct_cache = MarketDepthCache::new();
+    let coalesced_cache = MarketDepthCache::new();
+    let coalescing_buffer = CoalescingBuffer::new();
+
+    let mut handles = Vec::new();
+    for index in 0..200u32 {
+        let price = Decimal::new(10_000 + i64::from(index % 40) * 10, 2);
+        let side = if index % 2 == 0 { Side::Bid } else { Side::Ask };
+        let outcome = order_book.insert_order(Order {
+            price,
+            quantity: u64::from(index % 5 + 1),
+            side,
+        });
+        handles.push(outcome.handle);
+        direct_cache.process_order_event(outcome.event.clone());
+        coalescing_buffer.push(outcome.event);
+    }
+    for handle in handles.into_iter().step_by(3) {
+        let event = order_book.cancel_by_handle(handle).unwrap();
+        direct_cache.process_order_event(event.clone());
+        coalescing_buffer.push(event);
+    }
+
+    // 200 inserts and 67 cancels fall into 8 buckets: 100 to 103 on each side
+    assert_eq!(coalescing_buffer.len(), 8);
+    assert_eq!(coalescing_buffer.coalesced_count(), 267 - 8);
+
+    for event in coalescing_buffer.drain() {
+        coalesced_cache.process_order_event(event);
+    }
+    assert!(coalescing_buffer.is_empty());
+    assert_eq!(
+        coalesced_cache.get_aggregated_market_depth(),
+        direct_cache.get_aggregated_market_depth()
+    );
+
+    // Buckets whose changes cancel out are dropped
+    let outcome = order_book.insert_order(Order::new(105.00, 10, Side::Bid));
+    coalescing_buffer.push(outcome.event);
+    coalescing_buffer.push(order_book.cancel_by_handle(outcome.handle).unwrap());
+    assert!(coalescing_buffer.drain().is_empty());
+}
+
+#[test]
+/// Test the three overflow policies of the bounded event channel.
+fn test_event_channel_overflow_policies() {
+    let events: Vec<OrderEvent> = (0..6)
+        .map(|index| OrderEvent::new(Decimal::new(100 + index % 2, 0), 10, Side::Bid))
+        .collect();
+
+    // Dropped events are replaced by a single gap marker where they would have been
+    let (event_sender, event_receiver) = event_channel(2, OverflowPolicy::DropWithGap);
+    for event in &events[..4] {
+        event_sender.send(event.clone());
+    }
+    event_receiver.try_recv().unwrap();
+    event_sender.send(events[4].clone());
+    let messages: Vec<ChannelMessage> = std::iter::from_fn(|| event_receiver.try_recv()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            ChannelMessage::Event(events[1].clone()),
+            ChannelMessage::Gap { dropped_events: 2 },
+            ChannelMessage::Event(events[4].clone()),
+        ]
+    );
+    assert_eq!(event_receiver.overflow_counts().dropped_events, 2);
+
+    // Overflowing events are merged per aggregated level and delivered once the queue drains
+    let (event_sender, event_receiver) = event_channel(2, OverflowPolicy::Coalesce);
+    for event in &events {
+        event_sender.send(event.clone());
+    }
+    let market_depth_cache = MarketDepthCache::new();
+    let mut message_count = 0;
+    while let Some(ChannelMessage::Event(event)) = event_receiver.try_recv() {
+        market_depth_cache.process_order_event(event);
+        message_count += 1;
+    }
+    assert_eq!(message_count, 4);
+    assert_eq!(
+        market_depth_cache.get_quantity_at_level(Decimal::new(100, 0), Side::Bid),
+        30
+    );
+    assert_eq!(
+        event_receiver.overflow_counts(),
+        OverflowCounts {
+            overflows: 4,
+            dropped_events: 0,
+            coalesced_events: 2,
+        }
+    );
+
+    // A blocked producer resumes as the consumer makes room, and nothing is lost
+    let (event_sender, event_receiver) = event_channel(1, OverflowPolicy::Block);
+    let producer = std::thread::spawn(move || {
+        for event in events {
+            event_sender.send(event);
+        }
+    });
+    let received = std::iter::from_fn(|| event_receiver.recv()).count();
+    producer.join().unwrap();
+    assert_eq!(received, 6);
+}
+
+#[test]
+/// Test that broadcast log consumers read at their own pace and detect being lapped.
+fn test_broadcast_log() {
+    let mut order_book = OrderBook::new();
+    let log = Arc::new(BroadcastLog::new(4));
+    let mut early = log.cursor();
+    let sequences =
+        |events: &[OrderEvent]| -> Vec<u64> { events.iter().map(|event| event.sequence).collect() };
+
+    for price in [100, 101, 102] {
+        let event = order_book
+            .insert_order(Order::new(price as f64, 10, Side::Ask))
+            .event;
+        log.publish(event);
+    }
+
+    // Consumers read in batches, from where they are
+    let mut events = Vec::new();
+    assert_eq!(log.backlog(&early), Some(3));
+    assert_eq!(log.read(&mut early, &mut events, 2), Ok(2));
+    assert_eq!(log.read(&mut early, &mut events, 2), Ok(1));
+    assert_eq!(log.read(&mut early, &mut events, 2), Ok(0));
+    assert_eq!(sequences(&events), vec![1, 2, 3]);
+    assert_eq!(early.position(), 3);
+    let mut late = log.cursor();
+    assert_eq!(log.backlog(&late), Some(0));
+    let mut replay = log.cursor_at_oldest();
+    assert_eq!(replay.position(), 0);
+
+    // A producer thread publishing past the capacity laps the consumer left behind
+    let producer = {
+        let log = Arc::clone(&log);
+        let events: Vec<OrderEvent> = (0..6)
+            .map(|index| {
+                order_book
+                    .insert_order(Order::new(90.0 - index as f64, 1, Side::Bid))
+                    .event
+            })
+            .collect();
+        std::thread::spawn(move || {
+            for event in events {
+                log.publish(event);
+            }
+        })
+    };
+    producer.join().unwrap();
+
+    events.clear();
+    assert_eq!(log.backlog(&replay), None);
+    assert_eq!(
+        log.read(&mut replay, &mut events, usize::MAX),
+        Err(Lapped { missed_events: 5 })
+    );
+    assert_eq!(log.read(&mut replay, &mut events, usize::MAX), Ok(4));
+    assert_eq!(sequences(&events), vec![6, 7, 8, 9]);
+
+    // Each cursor is lapped by what it alone missed
+    events.clear();
+    assert_eq!(
+        log.read(&mut late, &mut events, 3),
+        Err(Lapped { missed_events: 2 })
+    );
+    assert_eq!(log.read(&mut late, &mut events, 3), Ok(3));
+    assert_eq!(log.backlog(&late), Some(1));
+}
+
+#[test]
+/// Test that events are sequenced by the book and that the cache reports its lag.
+fn test_cache_sequence_lag() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::new();
+    assert_eq!(order_book.last_event_sequence(), 0);
+
+    let mut events = vec![
+        order_book
+            .insert_order(Order::new(100.00, 10, Side::Ask))
+            .event,
+    ];
+    events.extend(
+        order_book
+            .match_order(Order::new(100.00, 15, Side::Bid))
+            .events,
+    );
+    let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+    assert_eq!(order_book.last_event_sequence(), 3);
+
+    market_depth_cache.process_order_event(events[0].clone());
+    assert_eq!(market_depth_cache.lag(order_book.last_event_sequence()), 2);
+    for event in events.drain(1..) {
+        market_depth_cache.process_order_event(event);
+    }
+    assert_eq!(market_depth_cache.last_applied_sequence(), 3);
+    assert_eq!(market_depth_cache.lag(order_book.last_event_sequence()), 0);
+
+    // Unsequenced events leave the sequence alone, and a rebuild catches up with the book
+    market_depth_cache.process_order_event(OrderEvent::new(Decimal::ONE, 1, Side::Bid));
+    assert_eq!(market_depth_cache.last_applied_sequence(), 3);
+    order_book.insert_order(Order::new(99.00, 5, Side::Bid));
+    market_depth_cache.clear();
+    assert_eq!(market_depth_cache.last_applied_sequence(), 0);
+    market_depth_cache.rebuild_from(&order_book);
+    assert_eq!(market_depth_cache.lag(order_book.last_event_sequence()), 0);
+}
+
+#[test]
+/// Test that a latency pipeline delays each hop in order and measures stale reads
+fn test_latency_pipeline() {
+    let delays = PipelineDelays {
+        feed_to_book: Delay::Uniform {
+            min: Duration::from_micros(10),
+            max: Duration::from_micros(100),
+        },
+        book_to_cache: Delay::Fixed(Duration::from_micros(5)),
+    };
+    let run = |seed| {
+        let mut pipeline = LatencyPipeline::new(
+            OrderBook::deterministic(7),
+            MarketDepthCache::new(),
+            delays,
+            seed,
+        );
+        let mut reads = Vec::new();
+        for i in 0..20u64 {
+            pipeline.advance_to(i * 10_000);
+            pipeline.send(Command::Insert(Order::new(
+                100.00 - (i % 3) as f64,
+                i + 1,
+                Side::Bid,
+            )));
+            reads.push(pipeline.read());
+        }
+        pipeline.flush();
+        (pipeline, reads)
+    };
+
+    let (mut pipeline, reads) = run(3);
+    assert!(reads
+        .iter()
+        .all(|read| read.cache_staleness_nanos >= read.book_staleness_nanos));
+    assert!(reads.iter().any(|read| read.sequence_lag > 0));
+    assert_eq!(
+        pipeline.max_sequence_lag(),
+        reads.iter().map(|read| read.sequence_lag).max().unwrap()
+    );
+    assert_eq!(pipeline.cache_staleness().count, 20);
+
+    // Flushed, the cache has caught up with the book in order
+    assert_eq!(
+        (pipeline.pending_commands(), pipeline.pending_events()),
+        (0, 0)
+    );
+    let read = pipeline.read();
+    assert_eq!((read.sequence_lag, read.cache_staleness_nanos), (0, 0));
+    assert_eq!(pipeline.cache().last_applied_sequence(), 20);
+    for price in [98, 99, 100] {
+        let price = Decimal::new(price, 0);
+        assert_eq!(
+            pipeline.cache().get_quantity_at_level(price, Side::Bid),
+            pipeline.order_book().quantity_at(price, Side::Bid)
+        );
+    }
+
+    // Random delays are drawn from the seed
+    assert_eq!(run(3).1, reads);
+
+    // Without delays, commands are applied and processed at once
+    let mut pipeline = LatencyPipeline::new(
+        OrderBook::deterministic(7),
+        MarketDepthCache::new(),
+        PipelineDelays::default(),
+        3,
+    );
+    pipeline.send(Command::Insert(Order::new(100.00, 10, Side::Bid)));
+    pipeline.send(Command::Cancel(OrderId(99)));
+    assert_eq!(pipeline.read().sequence_lag, 0);
+    assert_eq!(pipeline.rejected_commands(), 1);
+}
+
+#[test]
+/// Test that a command script drives the book like the corresponding method calls.
+fn test_apply_commands() {
+    let script = vec![
+        Command::Insert(Order::new(100.00, 10, Side::Ask)),
+        Command::Insert(Order::new(100.00, 10, Side::Ask)),
+        Command::Insert(Order::new(99.00, 10, Side::Bid)),
+        // Lowering the first ask in place keeps it ahead of the second
+        Command::Amend {
+            order_id: OrderId(1),
+            price: Decimal::new(100, 0),
+            quantity: 6,
+        },
+        // Raising the bid's quantity loses its priority through a replace
+        Command::Amend {
+            order_id: OrderId(3),
+            price: Decimal::new(99, 0),
+            quantity: 12,
+        },
+        Command::SetState(TradingState::Halted),
+        Command::Match(Order::new(100.00, 4, Side::Bid)),
+        Command::SetState(TradingState::Continuous),
+        Command::Match(Order::new(100.00, 8, Side::Bid)),
+    ];
+
+    let mut order_book = OrderBook::new();
+    let mut events = Vec::new();
+    for command in script.clone() {
+        events.extend(order_book.apply(command).unwrap());
+    }
+
+    // The halted match rested, and the later one filled the amended ask first
+    assert_eq!(order_book.trades().len(), 2);
+    assert_eq!(order_book.trades()[0].quantity, 6);
+    assert_eq!(order_book.get_order(OrderId(2)).unwrap().quantity, 8);
+    assert_eq!(order_book.get_order(OrderId(4)).unwrap().quantity, 12);
+    assert_eq!(order_book.total_volume(Side::Bid), 16);
+
+    // Replaying the script reproduces the events exactly
+    let mut replayed_book = OrderBook::new();
+    let mut replayed_events = Vec::new();
+    for command in script {
+        replayed_events.extend(replayed_book.apply(command).unwrap());
+    }
+    let strip_time = |events: &[OrderEvent]| {
+        events
+            .iter()
+            .map(|event| {
+                (
+                    event.price,
+                    event.quantity_delta,
+                    event.side,
+                    event.sequence,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(strip_time(&events), strip_time(&replayed_events));
+    assert!(order_book.diff(&replayed_book).is_empty());
+
+    assert_eq!(
+        order_book.apply(Command::Cancel(OrderId(1))),
+        Err(OrderBookError::OrderNotFound(OrderId(1)))
+    );
+    let cleared = order_book.apply(Command::Clear).unwrap();
+    assert_eq!(cleared.len(), 3);
+    assert_eq!(order_book.order_count(), 0);
+}
+
+#[test]
+/// Test that a throttle enforces per-participant and global token buckets, rejecting or queueing
+fn test_throttle() {
+    let insert = || Command::Insert(Order::new(100.00, 10, Side::Bid));
+    let alice = ParticipantId(1);
+    let bob = ParticipantId(2);
+
+    // Per-participant limits reject the command beyond the burst, and refill over time
+    let mut throttle = Throttle::new(OrderBook::deterministic(1), ThrottlePolicy::Reject);
+    throttle.set_participant_limit(Some(RateLimit {
+        rate_per_second: 4,
+        burst: 2,
+    }));
+    assert!(
+        matches!(throttle.apply(alice, insert()), Ok(Admission::Applied(events)) if events.len() == 1)
+    );
+    assert!(throttle.apply(alice, insert()).is_ok());
+    assert_eq!(
+        throttle.apply(alice, insert()),
+        Err(OrderBookError::Throttled {
+            participant_id: alice
+        })
+    );
+    // Other participants have their own bucket
+    assert!(throttle.apply(bob, insert()).is_ok());
+    throttle.order_book_mut().set_time(249_999_999);
+    assert!(throttle.apply(alice, insert()).is_err());
+    throttle.order_book_mut().set_time(250_000_000);
+    assert!(throttle.apply(alice, insert()).is_ok());
+    assert_eq!(throttle.order_book().order_count(), 4);
+
+    // The refill never exceeds the burst
+    throttle.order_book_mut().set_time(10_000_000_000);
+    for _ in 0..2 {
+        assert!(throttle.apply(alice, insert()).is_ok());
+    }
+    assert!(throttle.apply(alice, insert()).is_err());
+
+    // A global limit is shared, and queued commands are released in order as tokens refill
+    let mut throttle = Throttle::new(
+        OrderBook::deterministic(1),
+        ThrottlePolicy::Queue { capacity: 2 },
+    );
+    throttle.set_global_limit(Some(RateLimit {
+        rate_per_second: 1,
+        burst: 1,
+    }));
+    assert!(throttle.apply(alice, insert()).is_ok());
+    assert_eq!(throttle.apply(bob, insert()), Ok(Admission::Queued));
+    assert_eq!(
+        throttle.apply(alice, Command::Cancel(OrderId(1))),
+        Ok(Admission::Queued)
+    );
+    assert!(throttle.apply(bob, insert()).is_err());
+    assert_eq!(throttle.queued_len(), 2);
+    assert!(throttle.release_queued().is_empty());
+
+    throttle.order_book_mut().set_time(1_000_000_000);
+    let released = throttle.release_queued();
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].0, bob);
+    assert_eq!(throttle.order_book().order_count(), 2);
+
+    throttle.order_book_mut().set_time(2_000_000_000);
+    let released = throttle.release_queued();
+    assert_eq!(released[0].0, alice);
+    assert!(released[0].1.is_ok());
+    assert_eq!(throttle.queued_len(), 0);
+    assert_eq!(throttle.into_inner().order_count(), 1);
+}
+
+#[test]
+/// Test writing events into a buffer reused across calls
+fn test_events_into_caller_buffer() {
+    let commands = vec![
+        Command::Insert(Order::new(100.00, 10, Side::Ask)),
+        Command::Insert(Order::new(100.50, 10, Side::Ask)),
+        Command::Match(Order::new(100.50, 15, Side::Bid)),
+        Command::MatchDay(Order::new(99.00, 5, Side::Bid)),
+        Command::Cancel(OrderId(99)),
+        Command::Clear,
+    ];
+
+    let mut expected_book = OrderBook::deterministic(1);
+    let mut order_book = OrderBook::deterministic(1);
+    let mut events = Vec::with_capacity(8);
+    for command in commands {
+        let expected = expected_book.apply(command.clone());
+        events.clear();
+        let result = order_book.apply_into(command, &mut events);
+        assert_eq!(result.is_ok(), expected.is_ok());
+        assert_eq!(events, expected.unwrap_or_default());
+    }
+    assert_eq!(order_book.snapshot(), expected_book.snapshot());
+
+    // Matched events are appended after whatever the buffer already holds
+    let mut events = vec![
+        order_book
+            .insert_order(Order::new(101.00, 3, Side::Ask))
+            .event,
+    ];
+    let outcome = order_book.match_order_into(Order::new(101.00, 5, Side::Bid), &mut events);
+    assert!(outcome.events.is_empty());
+    assert_eq!(outcome.fills.filled_quantity, 3);
+    let deltas: Vec<i64> = events.iter().map(|event| event.quantity_delta).collect();
+    assert_eq!(deltas, vec![3, -3, 2]);
+}
+
+#[test]
+/// Test that reused client order identifiers are rejected while resting or within the window.
+fn test_client_order_id_deduplication() {
+    let mut order_book = OrderBook::new();
+    let (first, second) = (ClientOrderId(1), ClientOrderId(2));
+
+    let outcome = order_book
+        .insert_order_with_client_id(Order::new(100.00, 10, Side::Ask), first)
+        .unwrap();
+    let order_id = outcome.handle.order_id();
+    assert_eq!(order_book.order_id_by_client_id(first), Some(order_id));
+    assert_eq!(order_book.client_order_id(order_id), Some(first));
+
+    // A fully filled order is still remembered within the window
+    let outcome = order_book
+        .match_order_with_client_id(Order::new(100.00, 10, Side::Bid), second)
+        .unwrap();
+    assert!(outcome.handle.is_none());
+    assert_eq!(order_book.order_id_by_client_id(first), None);
+    assert_eq!(
+        order_book.match_order_with_client_id(Order::new(100.00, 10, Side::Bid), second),
+        Err(OrderBookError::DuplicateClientOrderId {
+            client_order_id: second,
+            order_id: outcome.order_id,
+        })
+    );
+    assert_eq!(order_book.trades().len(), 1);
+
+    // Once the window has passed, identifiers of orders that left the book can be reused
+    order_book.set_client_order_id_window(Duration::from_nanos(1));
+    let resting = order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(3))
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(1));
+    assert!(order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), second)
+        .is_ok());
+
+    // but not those of orders still resting
+    assert!(order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(3))
+        .is_err());
+    order_book.cancel_by_handle(resting.handle).unwrap();
+    assert!(order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(3))
+        .is_ok());
+}
+
+#[test]
+/// Test that fills accumulate on resting orders and on the incoming order across levels.
+fn test_order_fill_tracking() {
+    let mut order_book = OrderBook::new();
+    let maker_id = order_book
+        .insert_order(Order::new(100.00, 10, Side::Ask))
+        .handle
+        .order_id();
+    order_book.insert_order(Order::new(101.00, 10, Side::Ask));
+    assert_eq!(
+        order_book.order_fills(maker_id),
+        Some(FillSummary::default())
+    );
+
+    let outcome = order_book.match_order(Order::new(100.00, 4, Side::Bid));
+    assert_eq!(outcome.fills.filled_quantity, 4);
+    order_book.reduce_order(maker_id, 2).unwrap();
+    order_book.match_order(Order::new(100.00, 1, Side::Bid));
+    let maker_fills = order_book.order_fills(maker_id).unwrap();
+    assert_eq!(maker_fills.filled_quantity, 5);
+    assert_eq!(maker_fills.filled_notional, Decimal::new(500, 0));
+
+    // The sweep fills 3 at 100 and 10 at 101, then rests 2 carrying those fills
+    let outcome = order_book.match_order(Order::new(101.00, 15, Side::Bid));
+    assert_eq!(outcome.fills.filled_quantity, 13);
+    assert_eq!(
+        outcome.fills.average_fill_price(),
+        Some(Decimal::new(1310, 0) / Decimal::new(13, 0))
+    );
+    let remainder_id = outcome.handle.unwrap().order_id();
+    assert_eq!(order_book.order_fills(remainder_id), Some(outcome.fills));
+    assert_eq!(order_book.get_order(remainder_id).unwrap().quantity, 2);
+    assert_eq!(order_book.order_fills(maker_id), None);
+}
+
+#[test]
+/// Test attaching caller payloads to resting orders
+fn test_order_metadata() {
+    #[derive(Debug, PartialEq)]
+    struct StrategyTag(&'static str);
+
+    let mut order_book = OrderBook::new();
+    let plain_order_id = order_book
+        .insert_order(Order::new(99.00, 10, Side::Bid))
+        .handle
+        .order_id();
+    let tagged_order_id = order_book
+        .insert_order_with_metadata(
+            Order::new(101.00, 10, Side::Ask),
+            Arc::new(StrategyTag("maker")),
+        )
+        .handle
+        .order_id();
+    assert!(order_book.order_metadata(plain_order_id).is_none());
+    assert_eq!(
+        order_book.order_metadata_as::<StrategyTag>(tagged_order_id),
+        Some(&StrategyTag("maker"))
+    );
+    assert!(order_book
+        .order_metadata_as::<String>(tagged_order_id)
+        .is_none());
+
+    // The payload survives partial fills and rests with a matched order's remainder
+    order_book.match_order(Order::new(101.00, 4, Side::Bid));
+    assert!(order_book.order_metadata(tagged_order_id).is_some());
+    let outcome = order_book.match_order_with_metadata(
+        Order::new(101.00, 8, Side::Bid),
+        Arc::new(StrategyTag("taker")),
+    );
+    let remainder_id = outcome.handle.unwrap().order_id();
+    assert_eq!(
+        order_book.order_metadata_as::<StrategyTag>(remainder_id),
+        Some(&StrategyTag("taker"))
+    );
+
+    // It is dropped with its order
+    assert!(order_book.order_metadata(tagged_order_id).is_none());
+    let metadata: OrderMetadata = Arc::new(StrategyTag("dropped"));
+    let order_id = order_book
+        .insert_order_with_metadata(Order::new(98.00, 1, Side::Bid), metadata.clone())
+        .handle
+        .order_id();
+    assert_eq!(Arc::strong_count(&metadata), 2);
+    order_book.cancel_order(order_id).unwrap();
+    assert_eq!(Arc::strong_count(&metadata), 1);
+}
+
+#[test]
+/// Test inserting orders described by borrowed specs
+fn test_insert_order_ref() {
+    let mut order_book = OrderBook::new();
+    let desk: OrderMetadata = Arc::new("desk-7");
+    let order = Order::new(100.25, 10, Side::Ask);
+
+    let outcome = order_book.insert_order_ref(&OrderSpec::from(&order));
+    assert_eq!(outcome.event.quantity_delta, 10);
+    assert_eq!(
+        order_book.get_order(outcome.handle.order_id()),
+        Some(&order)
+    );
+    assert!(order_book
+        .order_metadata(outcome.handle.order_id())
+        .is_none());
+
+    let spec = OrderSpec {
+        metadata: Some(&desk),
+        ..OrderSpec::from(&order)
+    };
+    let order_id = order_book.insert_order_ref(&spec).handle.order_id();
+    assert_eq!(
+        order_book.order_metadata_as::<&str>(order_id),
+        Some(&"desk-7")
+    );
+    assert_eq!(order_book.total_volume(Side::Ask), 20);
+}
+
+#[test]
+/// Test that ending a session expires day orders, archives trades, and handles sequences.
+fn test_end_of_day_session() {
+    let mut order_book = OrderBook::deterministic(0);
+    order_book.set_time(1_000);
+    let day_bid = order_book
+        .insert_day_order(Order::new(100.0, 10, Side::Bid))
+        .handle
+        .order_id();
+    let good_till_cancel = order_book
+        .insert_order(Order::new(99.0, 5, Side::Bid))
+        .handle
+        .order_id();
+    let day_ask = order_book
+        .match_day_order(Order::new(100.0, 12, Side::Ask))
+        .handle
+        .unwrap()
+        .order_id();
+    assert!(order_book.is_day_order(day_ask));
+    assert!(!order_book.is_day_order(good_till_cancel));
+
+    // A replaced day order is still a day order
+    let replaced = order_book
+        .replace_order(day_ask, Decimal::from(101), 3)
+        .unwrap()
+        .handle
+        .order_id();
+    assert!(order_book.is_day_order(replaced));
+    assert!(!order_book.is_day_order(day_bid));
+
+    order_book.set_time(2_000);
+    let summary = order_book.end_session();
+    assert_eq!(summary.session, 1);
+    assert_eq!(summary.trades.len(), 1);
+    assert_eq!(summary.last_event_sequence, 7);
+    assert_eq!(summary.expired.len(), 1);
+    assert_eq!(summary.expired[0].price, Decimal::from(101));
+    assert_eq!(summary.expired[0].sequence, 7);
+    assert!(order_book.trades().is_empty());
+    assert_eq!(order_book.order_count(), 1);
+    assert_eq!(
+        order_book.take_session_events(),
+        vec![
+            SessionEvent::Closed {
+                session: 1,
+                timestamp_nanos: 2_000,
+                expired_orders: 1,
+                trade_count: 1,
+            },
+            SessionEvent::Opened {
+                session: 2,
+                timestamp_nanos: 2_000,
+            },
+        ]
+    );
+
+    // Sequences continue by default and restart when reset
+    let event = order_book
+        .insert_order(Order::new(98.0, 1, Side::Bid))
+        .event;
+    assert_eq!(event.sequence, 8);
+    order_book.set_sequence_policy(SequencePolicy::Reset);
+    assert_eq!(
+        order_book
+            .apply(Command::InsertDay(Order::new(97.0, 1, Side::Bid)))
+            .unwrap()[0]
+            .sequence,
+        9
+    );
+    let expired = order_book.apply(Command::EndSession).unwrap();
+    assert_eq!(expired[0].sequence, 10);
+    assert_eq!(
+        order_book
+            .insert_order(Order::new(96.0, 1, Side::Bid))
+            .event
+            .sequence,
+        1
+    );
+    assert_eq!(order_book.session(), 3);
+}
+
+#[test]
+/// Test that a cache keeps following the book when a session ends.
+fn test_cache_follows_book_across_sessions() {
+    let shared = SharedOrderBook::new();
+    shared
+        .insert_and_wait_visible(Order::new(100.00, 10, Side::Bid), Duration::from_secs(1))
+        .unwrap();
+    let summary = shared.order_book().write().end_session();
+    assert_eq!(summary.last_event_sequence, 1);
+
+    // The new session's events continue the numbering the cache has applied
+    let outcome = shared
+        .insert_and_wait_visible(Order::new(99.00, 5, Side::Bid), Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(outcome.event.sequence, 2);
+    let market_depth_cache = shared.market_depth_cache();
+    assert_eq!(market_depth_cache.last_applied_sequence(), 2);
+    assert_eq!(
+        market_depth_cache.lag(shared.order_book().read().last_event_sequence()),
+        0
+    );
+    assert!(market_depth_cache.wait_for_sequence(2, Duration::ZERO));
+    assert_eq!(
+        shared.depth(2).0,
+        vec![(Decimal::new(100, 0), 10), (Decimal::new(99, 0), 5)]
+    );
+}
+
+#[test]
+/// Test that seeded books replay a command stream into byte-identical event logs.
+fn test_deterministic_replay() {
+    let commands = vec![
+        Command::SetTime(1_000),
+        Command::Insert(Order::new(100.00, 10, Side::Ask)),
+        Command::Insert(Order::new(100.50, 5, Side::Ask)),
+        Command::SetTime(2_500),
+        Command::Match(Order::new(100.50, 12, Side::Bid)),
+        Command::Amend {
+            order_id: OrderId(2),
+            price: Decimal::new(101, 0),
+            quantity: 3,
+        },
+        Command::SetTime(4_000),
+        Command::Clear,
+    ];
+    let run = || {
+        let mut order_book = OrderBook::deterministic(7);
+        order_book.set_market_by_order_events(true);
+        let mut log = String::new();
+        for command in commands.clone() {
+            for event in order_book.apply(command).unwrap() {
+                log.push_str(&format!("{event:?}\n"));
+            }
+        }
+        for event in order_book.take_market_by_order_events() {
+            log.push_str(&format!("{event:?}\n"));
+        }
+        for trade in order_book.trades() {
+            log.push_str(&format!("{trade:?}\n"));
+        }
+        log
+    };
+
+    let log = run();
+    assert_eq!(log.as_bytes(), run().as_bytes());
+    assert!(log.contains("timestamp_nanos: 2500"));
+
+    // Time windows follow the manual clock too
+    let mut order_book = OrderBook::deterministic(7);
+    assert!(order_book.has_manual_clock());
+    order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(1))
+        .unwrap();
+    order_book.cancel_order(OrderId(1)).unwrap();
+    assert!(order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(1))
+        .is_err());
+    order_book.set_time(Duration::from_secs(61).as_nanos() as u64);
+    assert!(order_book
+        .insert_order_with_client_id(Order::new(99.00, 5, Side::Bid), ClientOrderId(1))
+        .is_ok());
+    assert!(!OrderBook::new().has_manual_clock());
+}
+
+#[test]
+/// Test that order identifiers come from the configured generator.
+fn test_order_id_generators() {
+    use std::sync::atomic::AtomicU64;
+
+    let order_id = |order_book: &mut OrderBook| {
+        order_book
+            .insert_order(Order::new(100.00, 1, Side::Bid))
+            .handle
+            .order_id()
+    };
+
+    // A shared counter numbers the orders of several books without collisions
+    let counter = Arc::new(AtomicU64::new(10));
+    let mut first_book = OrderBook::new();
+    let mut second_book = OrderBook::new();
+    first_book.set_order_id_generator(IdGenerator::Shared(counter.clone()));
+    second_book.set_order_id_generator(IdGenerator::Shared(counter.clone()));
+    assert_eq!(order_id(&mut first_book), OrderId(10));
+    assert_eq!(order_id(&mut second_book), OrderId(11));
+    assert_eq!(order_id(&mut first_book), OrderId(12));
+
+    // The book's own counter continues above the identifiers assigned by other generators
+    first_book.set_order_id_generator(IdGenerator::Counter);
+    assert_eq!(order_id(&mut first_book), OrderId(13));
+    assert_eq!(first_book.snapshot().next_order_id, 14);
+
+    // Snowflake identifiers follow the clock, borrowing milliseconds when a sequence is
+    // exhausted and staying increasing when the clock goes back
+    let mut order_book = OrderBook::deterministic(0);
+    order_book.set_order_id_generator(IdGenerator::Snowflake {
+        node_id: 7,
+        epoch_millis: 1_000,
+    });
+    order_book.set_time(1_002_000_000);
+    let ids: Vec<OrderId> = (0..4097).map(|_| order_id(&mut order_book)).collect();
+    assert_eq!(ids[0], OrderId(2 << 22 | 7 << 12));
+    assert_eq!(ids[4095], OrderId(2 << 22 | 7 << 12 | 4095));
+    assert_eq!(ids[4096], OrderId(3 << 22 | 7 << 12));
+    order_book.set_time(1_001_000_000);
+    assert_eq!(order_id(&mut order_book), OrderId(3 << 22 | 7 << 12 | 1));
+    order_book.set_time(1_009_000_000);
+    assert_eq!(order_id(&mut order_book), OrderId(9 << 22 | 7 << 12));
+    assert!(matches!(
+        order_book.order_id_generator(),
+        IdGenerator::Snowflake { node_id: 7, .. }
+    ));
+
+    // Custom generators hand out the caller's identifiers
+    let next = Arc::new(AtomicU64::new(0));
+    let mut order_book = OrderBook::new();
+    order_book.set_order_id_generator(IdGenerator::Custom(Arc::new(move || {
+        (next.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1) * 1_000
+    })));
+    assert_eq!(order_id(&mut order_book), OrderId(1_000));
+    let outcome = order_book.match_order(Order::new(99.00, 5, Side::Ask));
+    assert_eq!(outcome.order_id, OrderId(2_000));
+    assert_eq!(
+        format!("{:?}", order_book.order_id_generator()),
+        "Custom(..)"
+    );
+}
+
+#[test]
+/// Test that trades identify themselves and the maker and taker orders they executed.
+fn test_trade_identifiers() {
+    let mut order_book = OrderBook::new();
+    let first_maker = order_book
+        .insert_order(Order::new(100.00, 5, Side::Ask))
+        .handle
+        .order_id();
+    let second_maker = order_book
+        .insert_order(Order::new(100.50, 5, Side::Ask))
+        .handle
+        .order_id();
+    order_book.set_market_by_order_events(true);
+    let taker = order_book
+        .match_order(Order::new(101.00, 8, Side::Bid))
+        .order_id;
+    let references: Vec<_> = order_book
+        .trades()
+        .iter()
+        .map(|trade| (trade.trade_id, trade.maker_order_id, trade.taker_order_id))
+        .collect();
+    assert_eq!(
+        references,
+        vec![
+            (TradeId(1), first_maker, taker),
+            (TradeId(2), second_maker, taker)
+        ]
+    );
+    assert!(matches!(
+        order_book.take_market_by_order_events()[1],
+        MarketByOrderEvent::Executed { order_id, trade_id: TradeId(2), taker_order_id, .. }
+            if order_id == second_maker && taker_order_id == taker
+    ));
+
+    // Trade identifiers have their own generator
+    order_book.set_trade_id_generator(IdGenerator::Shared(Arc::new(
+        std::sync::atomic::AtomicU64::new(100),
+    )));
+    order_book.match_order(Order::new(101.00, 1, Side::Bid));
+    assert_eq!(order_book.trades()[2].trade_id, TradeId(100));
+    assert!(matches!(
+        order_book.trade_id_generator(),
+        IdGenerator::Shared(_)
+    ));
+
+    // Checkpoints keep the trade counter, so recovered books continue the numbering
+    let storage = MemoryStorage::new();
+    let mut wal = WriteAheadLog::with_storage(storage.clone(), FsyncPolicy::Manual).unwrap();
+    wal.checkpoint(&order_book).unwrap();
+    let mut recovered = wal::recover_from(&mut storage.clone()).unwrap().order_book;
+    recovered.match_order(Order::new(100.50, 1, Side::Bid));
+    assert_eq!(recovered.trades()[0].trade_id, TradeId(101));
+
+    // Trade frames written before the identifiers were appended decode with zeros
+    let mut frame = Vec::new();
+    encode_v1(&Record::Trade(order_book.trades()[0].clone()), &mut frame);
+    assert_eq!(
+        decode_v1(&frame),
+        Ok((
+            Some(Record::Trade(order_book.trades()[0].clone())),
+            frame.len()
+        ))
+    );
+    frame.truncate(frame.len() - 24);
+    frame[2] -= 24;
+    let Ok((Some(Record::Trade(trade)), _)) = decode_v1(&frame) else {
+        panic!("expected a trade");
+    };
+    assert_eq!(trade.quantity, 5);
+    assert_eq!(
+        (trade.trade_id, trade.maker_order_id, trade.taker_order_id),
+        (TradeId(0), OrderId(0), OrderId(0))
+    );
+}
+
+#[test]
+/// Test that every record kind survives a round trip through the version 1 wire format.
+fn test_wire_format_v1() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    order_book.set_market_by_order_events(true);
+
+    let mut records = Vec::new();
+    for event in [
+        order_book
+            .insert_order(Order::new(100.25, 10, Side::Ask))
+            .event,
+        order_book
+            .insert_order(Order::new(99.50, 7, Side::Bid))
+            .event,
+    ]
+    .into_iter()
+    .chain(
+        order_book
+            .match_order(Order::new(100.25, 4, Side::Bid))
+            .events,
+    ) {
+        cache.process_order_event(event.clone());
+        records.push(Record::OrderEvent(event));
+    }
+    order_book
+        .replace_order(OrderId(2), Decimal::new(9900, 2), 5)
+        .unwrap();
+    order_book.cancel_order(OrderId(1)).unwrap();
+    records.extend(
+        order_book
+            .take_market_by_order_events()
+            .into_iter()
+            .map(Record::MarketByOrder),
+    );
+    records.extend(order_book.trades().iter().cloned().map(Record::Trade));
+    records.push(Record::DepthSnapshot(cache.snapshot()));
+    records.push(Record::DepthSnapshot(DepthSnapshot::default()));
+    assert_eq!(records.len(), 11);
+    assert!(matches!(records[8], Record::Trade(_)));
+
+    let mut journal = Vec::new();
+    for record in &records {
+        encode_v1(record, &mut journal);
+    }
+    let mut remaining = &journal[..];
+    let mut decoded = Vec::new();
+    while !remaining.is_empty() {
+        let (record, frame_length) = decode_v1(remaining).unwrap();
+        decoded.push(record.unwrap());
+        remaining = &remaining[frame_length..];
+    }
+    assert_eq!(decoded, records);
+
+    // Unknown record kinds are skipped, and trailing fields added later are ignored
+    let mut future = vec![VERSION_1, 200, 3, 0, 0, 0, 1, 2, 3];
+    let mut trade = Vec::new();
+    encode_v1(&records[8], &mut trade);
+    trade[2] += 2;
+    trade.extend_from_slice(&[0xAA, 0xBB]);
+    future.extend_from_slice(&trade);
+    assert_eq!(decode_v1(&future), Ok((None, 9)));
+    assert_eq!(
+        decode_v1(&future[9..]),
+        Ok((Some(records[8].clone()), trade.len()))
+    );
+
+    assert_eq!(decode_v1(&journal[..20]), Err(DecodeError::Truncated));
+    assert_eq!(
+        decode_v1(&[2, 1, 0, 0, 0, 0]),
+        Err(DecodeError::UnsupportedVersion(2))
+    );
+    assert!(matches!(
+        decode_v1(&[VERSION_1, 3, 1, 0, 0, 0, 0]),
+        Err(DecodeError::Malformed { kind: 3, .. })
+    ));
+}
+
+#[test]
+/// Test that the multicast feed writes fixed-size sequenced datagrams and the reader detects gaps
+fn test_multicast_feed() {
+    let mut order_book = OrderBook::new();
+    order_book.set_market_by_order_events(true);
+    order_book.insert_order(Order::new(100.00, 10, Side::Ask));
+    order_book.match_order(Order::new(100.00, 4, Side::Bid));
+    let mut records: Vec<Record> = order_book
+        .take_market_by_order_events()
+        .into_iter()
+        .map(Record::MarketByOrder)
+        .collect();
+    records.extend(order_book.trades().iter().cloned().map(Record::Trade));
+    records.push(Record::Command(Command::Cancel(OrderId(1))));
+
+    // Records are captured as fixed-size datagrams numbered from 1
+    let mut writer = FeedWriter::new(CaptureFile::new(Vec::new()).unwrap());
+    for (index, record) in records.iter().enumerate() {
+        assert_eq!(writer.publish(record).unwrap(), index as u64 + 1);
+    }
+    // Snapshots do not fit, and use up no sequence number
+    let snapshot = Record::DepthSnapshot(DepthSnapshot {
+        sequence: 1,
+        bids: (1..=10).map(|price| (Decimal::from(price), 10)).collect(),
+        asks: Default::default(),
+    });
+    assert!(matches!(
+        writer.publish(&snapshot),
+        Err(MulticastError::RecordTooLarge { .. })
+    ));
+    assert_eq!(writer.next_sequence(), records.len() as u64 + 1);
+    let capture = writer.into_sink().finish().unwrap();
+    let datagrams = read_capture(capture.as_slice()).unwrap();
+    assert!(datagrams
+        .iter()
+        .all(|datagram| datagram.len() == DATAGRAM_LENGTH));
+    // The message type is the record kind of the wire format
+    assert_eq!(
+        (datagrams[0][8], datagrams[2][8], datagrams[3][8]),
+        (2, 3, 5)
+    );
+    assert!(matches!(
+        read_capture(&capture[1..]),
+        Err(MulticastError::NotACapture)
+    ));
+
+    // Reading them in order delivers every record
+    let mut reader = FeedReader::new();
+    let delivered: Vec<Record> = datagrams
+        .iter()
+        .take(records.len())
+        .map(|datagram| reader.read(datagram).unwrap().unwrap().record)
+        .collect();
+    assert_eq!(delivered, records);
+    assert!(reader.gaps().is_empty());
+
+    // Losses open gaps that late datagrams close, and duplicates are dropped
+    let mut reader = FeedReader::new();
+    reader.read(&datagrams[3]).unwrap();
+    assert_eq!(reader.gaps().len(), 1);
+    assert_eq!(reader.gaps()[0], 1..4);
+    reader.read(&datagrams[1]).unwrap();
+    assert_eq!(reader.gaps(), &[1..2, 3..4]);
+    assert!(reader.read(&datagrams[1]).unwrap().is_none());
+    assert!(reader.read(&datagrams[3]).unwrap().is_none());
+    assert_eq!(reader.next_sequence(), 5);
+
+    // Malformed datagrams are rejected without being counted
+    assert!(matches!(
+        reader.read(&datagrams[0][..64]),
+        Err(MulticastError::InvalidDatagram(_))
+    ));
+    let mut corrupt = datagrams[0].clone();
+    corrupt[11] = 9;
+    assert!(reader.read(&corrupt).is_err());
+    let mut last = datagrams[0].clone();
+    last[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        reader.read(&last),
+        Err(MulticastError::InvalidDatagram("sequence number overflows"))
+    ));
+    assert_eq!(reader.gaps(), &[1..2, 3..4]);
+    assert_eq!(reader.next_sequence(), 5);
+
+    // Datagrams travel over UDP unchanged
+    let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+    let mut writer = FeedWriter::new(sender);
+    writer.publish(&records[0]).unwrap();
+    let mut buffer = [0; 512];
+    let length = receiver.recv(&mut buffer).unwrap();
+    assert_eq!(length, DATAGRAM_LENGTH);
+    let message = FeedReader::new().read(&buffer[..length]).unwrap().unwrap();
+    assert_eq!((message.sequence, message.record), (1, records[0].clone()));
+}
+
+#[test]
+/// Test that a book is recovered from its write-ahead log, ignoring a torn final frame.
+fn test_write_ahead_log() {
+    let path = std::env::temp_dir().join(format!("order-book-wal-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let commands = vec![
+        Command::SetTime(1_000),
+        Command::Insert(Order::new(100.00, 10, Side::Bid)),
+        Command::InsertWithClientId(Order::new(101.00, 5, Side::Ask), ClientOrderId(7)),
+        Command::InsertDay(Order::new(99.50, 3, Side::Bid)),
+        Command::Match(Order::new(100.00, 4, Side::Ask)),
+        Command::Amend {
+            order_id: OrderId(1),
+            price: Decimal::new(100, 0),
+            quantity: 5,
+        },
+        Command::MatchDay(Order::new(98.00, 2, Side::Bid)),
+        Command::SetState(TradingState::Halted),
+        Command::SetState(TradingState::Continuous),
+        Command::EndSession,
+        Command::SetTime(2_000),
+    ];
+
+    // Every command survives a round trip through the wire format
+    for command in &commands {
+        let mut frame = Vec::new();
+        encode_v1(&Record::Command(command.clone()), &mut frame);
+        assert_eq!(
+            decode_v1(&frame),
+            Ok((Some(Record::Command(command.clone())), frame.len()))
+        );
+    }
+
+    let mut order_book = OrderBook::deterministic(1);
+    let mut wal = WriteAheadLog::open(&path, FsyncPolicy::Batch(4)).unwrap();
+    for command in commands {
+        wal.apply(&mut order_book, command).unwrap();
+    }
+    assert_eq!(wal.command_count(), 11);
+    assert_eq!(wal.unsynced_count(), 3);
+
+    // Rejected commands are logged too, so replay rejects them again
+    assert!(matches!(
+        wal.apply(&mut order_book, Command::Cancel(OrderId(42))),
+        Err(WalError::Rejected(OrderBookError::OrderNotFound(OrderId(
+            42
+        ))))
+    ));
+    wal.sync().unwrap();
+    assert_eq!(wal.unsynced_count(), 0);
+    drop(wal);
+
+    // A crash in the middle of an append leaves a partial frame
+    let mut torn = Vec::new();
+    encode_v1(&Record::Command(Command::Clear), &mut torn);
+    torn.pop();
+    let mut bytes = std::fs::read(&path).unwrap();
+    let logged_length = bytes.len();
+    bytes.extend_from_slice(&torn);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let mut recovered = OrderBook::deterministic(1);
+    assert_eq!(wal::replay(&path, &mut recovered).unwrap(), 12);
+    assert_eq!(recovered.snapshot(), order_book.snapshot());
+    assert_eq!(
+        recovered.last_event_sequence(),
+        order_book.last_event_sequence()
+    );
+    assert_eq!(
+        recovered.order_id_by_client_id(ClientOrderId(7)),
+        Some(OrderId(2))
+    );
+    // The identifier counter continues where the logged book left off
+    assert_eq!(
+        recovered
+            .insert_order(Order::new(97.00, 1, Side::Bid))
+            .handle
+            .order_id(),
+        order_book
+            .insert_order(Order::new(97.00, 1, Side::Bid))
+            .handle
+            .order_id()
+    );
+
+    let recovery = wal::recover(&path).unwrap();
+    assert_eq!(recovery.command_count, 12);
+    assert_eq!(recovery.order_book.order_count(), 2);
+    assert_eq!(
+        recovery
+            .market_depth_cache
+            .get_quantity_at_level(Decimal::new(100, 0), Side::Bid),
+        5
+    );
+
+    // Reopening cuts off the partial frame before appending after it
+    let mut wal = WriteAheadLog::open(&path, FsyncPolicy::EveryCommand).unwrap();
+    assert_eq!(wal.command_count(), 12);
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().len(),
+        logged_length as u64
+    );
+    wal.append(&Command::Clear).unwrap();
+    drop(wal);
+    assert_eq!(wal::recover(&path).unwrap().order_book.order_count(), 0);
+
+    // A frame that cannot be decoded is reported with its offset
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[0] = 9;
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        wal::recover(&path),
+        Err(WalError::Corrupt {
+            offset: 0,
+            error: DecodeError::UnsupportedVersion(9)
+        })
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+/// Test that checkpoints replace the write-ahead log and that recovery replays the tail.
+fn test_write_ahead_log_checkpoints() {
+    let path =
+        std::env::temp_dir().join(format!("order-book-checkpoints-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut order_book = OrderBook::deterministic(1);
+    let mut wal = WriteAheadLog::open(&path, FsyncPolicy::Manual).unwrap();
+    wal.set_checkpoint_schedule(CheckpointSchedule {
+        events: Some(4),
+        interval: None,
+    });
+    let commands = [
+        Command::SetTime(5_000),
+        Command::InsertWithClientId(Order::new(100.00, 10, Side::Bid), ClientOrderId(7)),
+        Command::InsertDay(Order::new(101.00, 4, Side::Ask)),
+        Command::EndSession,
+        Command::SetState(TradingState::Halted),
+        Command::InsertDay(Order::new(101.50, 2, Side::Ask)),
+        // The checkpoint is written before this command, after four events
+        Command::Insert(Order::new(99.00, 1, Side::Bid)),
+        Command::SetTime(6_000),
+    ];
+    for command in commands {
+        wal.apply(&mut order_book, command).unwrap();
+    }
+    assert_eq!(wal.command_count(), 2);
+
+    let recovery = wal::recover(&path).unwrap();
+    assert!(recovery.restored_checkpoint);
+    assert_eq!(recovery.command_count, 2);
+    let recovered = recovery.order_book;
+    assert_eq!(recovered.snapshot(), order_book.snapshot());
+    assert_eq!(recovered.session(), 2);
+    assert!(recovered.is_day_order(OrderId(3)));
+    assert!(!recovered.is_day_order(OrderId(1)));
+    assert_eq!(
+        recovered.order_id_by_client_id(ClientOrderId(7)),
+        Some(OrderId(1))
+    );
+    assert_eq!(recovered.trading_state(), TradingState::Halted);
+    assert_eq!(
+        recovery
+            .market_depth_cache
+            .get_quantity_at_level(Decimal::new(99, 0), Side::Bid),
+        1
+    );
+
+    // The checkpoint's manual clock replaces the fresh book's system clock
+    assert!(recovered.has_manual_clock());
+
+    // Replaying into a configured book gives the same book, which ends the session alike
+    let mut replayed = OrderBook::deterministic(1);
+    assert_eq!(wal::replay(&path, &mut replayed).unwrap(), 2);
+    let mut live_book = order_book.clone();
+    assert_eq!(replayed.end_session(), live_book.end_session());
+
+    // The checkpoint record survives a round trip through the wire format
+    let mut frame = Vec::new();
+    let checkpoint = Record::Checkpoint(Checkpoint {
+        book: order_book.snapshot(),
+        trading_state: TradingState::Halted,
+        manual_time_nanos: Some(6_000),
+        session: 2,
+        day_orders: vec![OrderId(3)],
+        client_order_ids: vec![(OrderId(1), ClientOrderId(7))],
+        next_trade_id: 4,
+    });
+    encode_v1(&checkpoint, &mut frame);
+    assert_eq!(decode_v1(&frame), Ok((Some(checkpoint), frame.len())));
+
+    // Zero intervals checkpoint before every command, and manual checkpoints empty the log
+    wal.set_checkpoint_schedule(CheckpointSchedule {
+        events: None,
+        interval: Some(Duration::ZERO),
+    });
+    for _ in 0..3 {
+        wal.apply(
+            &mut order_book,
+            Command::Insert(Order::new(98.00, 1, Side::Bid)),
+        )
+        .unwrap();
+        assert_eq!(wal.command_count(), 1);
+    }
+    wal.checkpoint(&order_book).unwrap();
+    assert_eq!(wal.command_count(), 0);
+    drop(wal);
+    let recovery = wal::recover(&path).unwrap();
+    assert_eq!(recovery.command_count, 0);
+    assert_eq!(recovery.order_book.snapshot(), order_book.snapshot());
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// A storage keeping a write-ahead log as a checkpoint object and appended segments, as
+/// an object store would.
+#[derive(Default)]
+struct SegmentedStorage {
+    checkpoint: Vec<u8>,
+    segments: Vec<Vec<u8>>,
+    sync_count: usize,
+}
+
+impl Storage for SegmentedStorage {
+    fn read(&mut self) -> std::io::Result<Vec<u8>> {
+        Ok([self.checkpoint.clone(), self.segments.concat()].concat())
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.segments.push(bytes.to_vec());
+        Ok(())
+    }
+
+    fn sync(&mut self) -> std::io::Result<()> {
+        self.sync_count += 1;
+        Ok(())
+    }
+
+    fn truncate(&mut self, length: u64) -> std::io::Result<()> {
+        let mut bytes = self.read()?;
+        bytes.truncate(length as usize);
+        self.replace(&bytes)
+    }
+
+    fn replace(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.checkpoint = bytes.to_vec();
+        self.segments.clear();
+        Ok(())
+    }
+}
+
+#[test]
+/// Test that write-ahead logs can be kept in memory or in a storage of the embedder's.
+fn test_write_ahead_log_storage() {
+    let storage = MemoryStorage::new();
+    let mut order_book = OrderBook::new();
+    let mut wal = WriteAheadLog::with_storage(storage.clone(), FsyncPolicy::EveryCommand).unwrap();
+    for price in [100.00, 101.00] {
+        wal.apply(
+            &mut order_book,
+            Command::Insert(Order::new(price, 10, Side::Ask)),
+        )
+        .unwrap();
+    }
+    wal.checkpoint(&order_book).unwrap();
+    wal.apply(
+        &mut order_book,
+        Command::Match(Order::new(100.00, 4, Side::Bid)),
+    )
+    .unwrap();
+    drop(wal);
+
+    // A partial frame appended by a crash is ignored, then cut off on reopening
+    let mut torn = Vec::new();
+    encode_v1(&Record::Command(Command::Clear), &mut torn);
+    let logged_length = storage.to_bytes().len();
+    storage.clone().append(&torn[..5]).unwrap();
+    let recovery = wal::recover_from(&mut storage.clone()).unwrap();
+    assert!(recovery.restored_checkpoint);
+    assert_eq!(recovery.command_count, 1);
+    assert_eq!(recovery.order_book.snapshot(), order_book.snapshot());
+    let wal = WriteAheadLog::with_storage(storage.clone(), FsyncPolicy::Manual).unwrap();
+    assert_eq!(wal.command_count(), 1);
+    assert_eq!(wal.storage().to_bytes().len(), logged_length);
+
+    // Any storage implementing the trait can back the log
+    let mut order_book = OrderBook::deterministic(3);
+    let mut wal =
+        WriteAheadLog::with_storage(SegmentedStorage::default(), FsyncPolicy::Batch(2)).unwrap();
+    for command in [
+        Command::SetTime(10),
+        Command::Insert(Order::new(99.00, 5, Side::Bid)),
+        Command::Insert(Order::new(98.00, 5, Side::Bid)),
+    ] {
+        wal.apply(&mut order_book, command).unwrap();
+    }
+    assert_eq!(wal.storage().segments.len(), 3);
+    assert_eq!(wal.storage().sync_count, 1);
+    wal.checkpoint(&order_book).unwrap();
+    wal.apply(&mut order_book, Command::Cancel(OrderId(1)))
+        .unwrap();
+    assert_eq!(wal.storage().segments.len(), 1);
+
+    let mut storage = SegmentedStorage {
+        checkpoint: wal.storage().checkpoint.clone(),
+        segments: wal.storage().segments.clone(),
+        sync_count: 0,
+    };
+    let mut replayed = OrderBook::deterministic(3);
+    assert_eq!(wal::replay_from(&mut storage, &mut replayed).unwrap(), 1);
+    assert_eq!(replayed.snapshot(), order_book.snapshot());
+}
+
+#[test]
+/// Test that recorded sessions verify against themselves and report where replays diverge.
+fn test_recording_golden_files() {
+    use order_book::recording::{
+        verify_against_golden, verify_recording, Recorder, RecordingError,
+    };
+
+    let path = std::env::temp_dir().join(format!("order-book-golden-{}.rec", std::process::id()));
+    let mut order_book = OrderBook::new();
+    order_book.insert_order(Order::new(101.00, 5, Side::Ask));
+
+    // Recording starts from the book's current state
+    let mut recorder = Recorder::create(&path, &order_book).unwrap();
+    for command in [
+        Command::SetTime(1_000),
+        Command::Insert(Order::new(100.00, 10, Side::Bid)),
+        Command::Match(Order::new(100.00, 4, Side::Ask)),
+        Command::MatchDay(Order::new(101.00, 8, Side::Bid)),
+        Command::EndSession,
+    ] {
+        recorder.apply(&mut order_book, command).unwrap();
+    }
+    assert!(matches!(
+        recorder.apply(&mut order_book, Command::Cancel(OrderId(99))),
+        Err(RecordingError::Rejected(OrderBookError::OrderNotFound(
+            OrderId(99)
+        )))
+    ));
+    assert_eq!(recorder.command_count(), 6);
+    recorder.finish(&order_book).unwrap();
+    assert_eq!(verify_against_golden(&path).unwrap(), 6);
+
+    // A replay producing other events is reported at the command that produced them
+    let golden = std::fs::read(&path).unwrap();
+    let mut records = Vec::new();
+    let mut remaining = &golden[..];
+    while !remaining.is_empty() {
+        let (record, frame_length) = decode_v1(remaining).unwrap();
+        records.extend(record);
+        remaining = &remaining[frame_length..];
+    }
+    assert_eq!(records.len(), 15);
+    let mut tampered = Vec::new();
+    for record in &records {
+        let record = match record {
+            Record::Trade(trade) if trade.quantity == 5 => Record::Trade(Trade {
+                quantity: 2,
+                ..trade.clone()
+            }),
+            record => record.clone(),
+        };
+        encode_v1(&record, &mut tampered);
+    }
+    match verify_recording(&tampered, &mut OrderBook::new()) {
+        Err(RecordingError::Diverged { step, reason }) => {
+            assert_eq!(step, 3);
+            assert!(reason.contains("MatchDay"), "{reason}");
+        }
+        result => panic!("expected a divergence, got {result:?}"),
+    }
+
+    // A differing final state is reported after the last command
+    let mut tampered = Vec::new();
+    for record in &records[..records.len() - 1] {
+        encode_v1(record, &mut tampered);
+    }
+    let Some(Record::Checkpoint(mut last)) = records.last().cloned() else {
+        panic!("recordings end with a checkpoint");
+    };
+    last.session = 7;
+    encode_v1(&Record::Checkpoint(last), &mut tampered);
+    match verify_recording(&tampered, &mut OrderBook::new()) {
+        Err(RecordingError::Diverged { step, reason }) => {
+            assert_eq!(step, 6);
+            assert_eq!(reason, "final session was recorded as 7, replayed as 2");
+        }
+        result => panic!("expected a divergence, got {result:?}"),
+    }
+
+    // Unfinished or cut off recordings are incomplete
+    assert!(matches!(
+        verify_recording(&golden[..golden.len() - 1], &mut OrderBook::new()),
+        Err(RecordingError::Incomplete)
+    ));
+    assert!(matches!(
+        verify_recording(
+            &tampered[..tampered.len() - golden.len() / 2],
+            &mut OrderBook::new()
+        ),
+        Err(RecordingError::Incomplete)
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+/// Test that a book restored from a snapshot rests the same orders in the same priority.
+fn test_book_snapshot_restore() {
+    let mut order_book = OrderBook::new();
+    for (price, quantity, side) in [
+        (99.00, 10, Side::Bid),
+        (99.00, 4, Side::Bid),
+        (98.50, 7, Side::Bid),
+        (101.00, 3, Side::Ask),
+        (100.50, 6, Side::Ask),
+    ] {
+        order_book.insert_order(Order::new(price, quantity, side));
+    }
+    order_book.cancel_order(OrderId(1)).unwrap();
+
+    let snapshot = order_book.snapshot();
+    assert_eq!(snapshot.sequence, order_book.last_event_sequence());
+    assert_eq!(snapshot.next_order_id, 6);
+    let bid_ids: Vec<_> = snapshot
+        .bids
+        .iter()
+        .map(|(order_id, _)| *order_id)
+        .collect();
+    assert_eq!(bid_ids, vec![OrderId(2), OrderId(3)]);
+    assert_eq!(snapshot.asks[0].1.price, Decimal::new(1005, 1));
+
+    let mut restored = OrderBook::from_snapshot(&snapshot);
+    assert!(order_book.diff(&restored).is_empty());
+    assert_eq!(restored.last_event_sequence(), snapshot.sequence);
+    restored.insert_order(Order::new(99.00, 1, Side::Bid));
+    order_book.insert_order(Order::new(99.00, 1, Side::Bid));
+    assert_eq!(restored.snapshot(), order_book.snapshot());
+
+    // Priority within a level survives the round trip
+    let outcome = restored.match_order(Order::new(99.00, 4, Side::Ask));
+    assert_eq!(outcome.fills.filled_quantity, 4);
+    assert_eq!(restored.get_order(OrderId(2)), None);
+    assert_eq!(restored.get_order(OrderId(6)).unwrap().quantity, 1);
+    assert_eq!(BookSnapshot::default().next_order_id, 0);
+}
+
+#[test]
+/// Test that the state hash identifies the resting orders and their priority.
+fn test_state_hash() {
+    let mut order_book = OrderBook::new();
+    let empty_hash = order_book.state_hash();
+    order_book.insert_order(Order::new(100.00, 10, Side::Bid));
+    order_book.insert_order(Order::new(100.00, 20, Side::Bid));
+    order_book.insert_order(Order::new(101.50, 5, Side::Ask));
+    let hash = order_book.state_hash();
+    assert_ne!(hash, empty_hash);
+
+    // The digest is pinned, so it can be compared across processes and builds
+    assert_eq!(empty_hash, 0x8820_1fb9_60ff_6465);
+    assert_eq!(hash, 0xe3d0_4e51_43c4_ba87);
+    assert_eq!(OrderBook::new().state_hash(), empty_hash);
+    let copy = OrderBook::from_snapshot(&order_book.snapshot());
+    assert_eq!(copy.state_hash(), hash);
+
+    // Different paths to the same resting orders hash alike, whatever the price scale
+    let mut other = OrderBook::with_config(OrderBookConfig {
+        price_normalization: PriceNormalization::Scale(4),
+        ..OrderBookConfig::default()
+    });
+    other.insert_order(Order::new(100.00, 15, Side::Bid));
+    other.insert_order(Order::new(100.00, 20, Side::Bid));
+    other.insert_order(Order::new(101.50, 5, Side::Ask));
+    assert_ne!(other.state_hash(), hash);
+    other.reduce_order(OrderId(1), 5).unwrap();
+    assert_eq!(other.state_hash(), hash);
+
+    // Time priority, identifiers, and sides are all covered
+    let mut reordered = OrderBook::new();
+    reordered.insert_order(Order::new(100.00, 20, Side::Bid));
+    reordered.insert_order(Order::new(100.00, 10, Side::Bid));
+    reordered.insert_order(Order::new(101.50, 5, Side::Ask));
+    assert_ne!(reordered.state_hash(), hash);
+    let mut flipped = OrderBook::new();
+    flipped.insert_order(Order::new(100.00, 10, Side::Ask));
+    assert_ne!(flipped.state_hash(), {
+        let mut bid = OrderBook::new();
+        bid.insert_order(Order::new(100.00, 10, Side::Bid));
+        bid.state_hash()
+    });
+}
+
+#[test]
+/// Test that a follower fed the primary's market-by-order stream mirrors it, and
+/// catches up from a snapshot after a gap or a divergence
+fn test_follower_book() {
+    let mut primary = OrderBook::new();
+    primary.set_market_by_order_events(true);
+    let mut follower = FollowerBook::new();
+
+    // A deterministic mix of inserts, matches, cancels, reductions, and replacements
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+    for _ in 0..2_000 {
+        let price = Decimal::new(9_990 + next(20) as i64, 2);
+        let side = if next(2) == 0 { Side::Bid } else { Side::Ask };
+        let snapshot = primary.snapshot();
+        let resting: Vec<OrderId> = snapshot
+            .bids
+            .iter()
+            .chain(&snapshot.asks)
+            .map(|(order_id, _)| *order_id)
+            .collect();
+        let target = (!resting.is_empty()).then(|| resting[next(resting.len() as u64) as usize]);
+        match (next(5), target) {
+            (0, Some(order_id)) => {
+                primary.cancel_order(order_id).unwrap();
+            }
+            (1, Some(order_id)) => {
+                let remaining = primary.get_order(order_id).unwrap().quantity;
+                if remaining > 1 {
+                    primary.reduce_order(order_id, remaining / 2).unwrap();
+                }
+            }
+            (2, Some(order_id)) => {
+                primary
+                    .replace_order(order_id, price, 1 + next(50))
+                    .unwrap();
+            }
+            (3, _) => {
+                primary.match_order(Order {
+                    price,
+                    quantity: 1 + next(80),
+                    side,
+                });
+            }
+            _ => {
+                primary.insert_order(Order {
+                    price,
+                    quantity: 1 + next(50),
+                    side,
+                });
+            }
+        }
+        for event in primary.take_sequenced_market_by_order_events() {
+            assert_eq!(follower.apply(&event), Ok(true));
+        }
+        assert_eq!(follower.order_book().state_hash(), primary.state_hash());
+    }
+    assert_eq!(follower.sequence(), primary.market_by_order_sequence());
+    assert!(!primary.trades().is_empty());
+    assert_eq!(follower.order_book().trades(), primary.trades());
+    assert_eq!(follower.order_book().bbo(), primary.bbo());
+
+    // Replaying events the follower already reflects changes nothing
+    primary.insert_order(Order::new(99.00, 10, Side::Bid));
+    let events = primary.take_sequenced_market_by_order_events();
+    assert_eq!(follower.apply(&events[0]), Ok(true));
+    assert_eq!(follower.apply(&events[0]), Ok(false));
+
+    // A missing event is reported and leaves the replica where it stood
+    primary.insert_order(Order::new(99.00, 10, Side::Bid));
+    primary.insert_order(Order::new(99.00, 10, Side::Bid));
+    let events = primary.take_sequenced_market_by_order_events();
+    let sequence = follower.sequence();
+    assert_eq!(
+        follower.apply(&events[1]),
+        Err(OrderBookError::SequenceGap {
+            expected: sequence + 1,
+            received: sequence + 2,
+        })
+    );
+    assert_eq!(follower.sequence(), sequence);
+    assert!(follower.is_consistent());
+    // Late events still apply in order
+    assert_eq!(follower.apply(&events[0]), Ok(true));
+    assert_eq!(follower.apply(&events[1]), Ok(true));
+    assert_eq!(follower.order_book().state_hash(), primary.state_hash());
+
+    // An event that does not apply marks the replica as diverged until it catches up
+    let mut stale = FollowerBook::new();
+    let snapshot = primary.replication_snapshot();
+    primary.match_order(Order::new(99.00, 5, Side::Ask));
+    let events = primary.take_sequenced_market_by_order_events();
+    let diverging = SequencedMarketByOrderEvent {
+        sequence: 1,
+        event: events[0].event.clone(),
+    };
+    assert_eq!(
+        stale.apply(&diverging),
+        Err(OrderBookError::ReplicaDiverged { sequence: 1 })
+    );
+    assert!(!stale.is_consistent());
+    stale.catch_up(&snapshot);
+    assert!(stale.is_consistent());
+    for event in &events {
+        assert_eq!(stale.apply(event), Ok(true));
+    }
+    assert_eq!(stale.order_book().state_hash(), primary.state_hash());
+}
+
+#[test]
+/// Test that the book, its snapshot, the cache, and the cache's snapshot give the same view.
+fn test_book_views_agree() {
+    type Levels = Vec<(Decimal, u64)>;
+    fn describe(view: &impl BookView) -> (Bbo, Levels, Levels, u64) {
+        (
+            view.bbo(),
+            view.top_levels(Side::Bid, 2),
+            view.iter(Side::Ask).collect(),
+            view.quantity_at(Decimal::new(99, 0), Side::Bid),
+        )
+    }
+
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    for (price, quantity, side) in [
+        (100.00, 10, Side::Bid),
+        (99.00, 20, Side::Bid),
+        (99.00, 5, Side::Bid),
+        (98.00, 40, Side::Bid),
+        (102.00, 15, Side::Ask),
+        (101.00, 7, Side::Ask),
+        (101.00, 3, Side::Ask),
+    ] {
+        cache.process_order_event(
+            order_book
+                .insert_order(Order::new(price, quantity, side))
+                .event,
+        );
+    }
+
+    let expected = (
+        Bbo {
+            best_bid: Some(Decimal::new(100, 0)),
+            best_ask: Some(Decimal::new(101, 0)),
+        },
+        vec![(Decimal::new(100, 0), 10), (Decimal::new(99, 0), 25)],
+        vec![(Decimal::new(101, 0), 10), (Decimal::new(102, 0), 15)],
+        25,
+    );
+    assert_eq!(describe(&order_book), expected);
+    assert_eq!(describe(&order_book.snapshot()), expected);
+    assert_eq!(describe(&cache), expected);
+    assert_eq!(describe(&cache.snapshot()), expected);
+
+    // Empty sides and levels
+    let empty = OrderBook::new();
+    assert_eq!(BookView::bbo(&empty.snapshot()), Bbo::default());
+    assert_eq!(order_book.quantity_at(Decimal::new(97, 0), Side::Bid), 0);
+    assert_eq!(
+        order_book
+            .snapshot()
+            .quantity_at(Decimal::new(100, 0), Side::Ask),
+        0
+    );
+}
+
+#[test]
+/// Test that an external level 2 snapshot initializes an empty book level by level.
+fn test_load_l2_snapshot() {
+    let mut order_book = OrderBook::new();
+    let market_depth_cache = MarketDepthCache::with_config(MarketDepthCacheConfig {
+        exact_levels: true,
+        ..MarketDepthCacheConfig::default()
+    });
+    let bids = [
+        (Decimal::new(9975, 2), 10),
+        (Decimal::new(9950, 2), 0),
+        (Decimal::new(9925, 2), 25),
+    ];
+    let asks = [(Decimal::new(10025, 2), 7), (Decimal::new(10050, 2), 3)];
+
+    let events = order_book.load_l2_snapshot(&bids, &asks).unwrap();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| event.sequence)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    for event in events {
+        market_depth_cache.process_order_event(event);
+    }
+    assert_eq!(order_book.order_count(), 4);
+    let snapshot = market_depth_cache.exact_snapshot().unwrap();
+    assert_eq!(
+        snapshot.bids.into_iter().collect::<Vec<_>>(),
+        vec![(Decimal::new(9925, 2), 25), (Decimal::new(9975, 2), 10)]
+    );
+    assert_eq!(snapshot.asks.len(), 2);
+
+    // Deltas then apply to the synthetic orders like to any other
+    let (order_id, _) = order_book.snapshot().asks[0].clone();
+    market_depth_cache.process_order_event(order_book.reduce_order(order_id, 2).unwrap());
+    let (_, ask_levels) = market_depth_cache.get_exact_levels().unwrap();
+    assert_eq!(
+        ask_levels[&Decimal::new(10025, 2)],
+        LevelInfo {
+            quantity: 5,
+            order_count: 1,
+        }
+    );
+
+    assert_eq!(
+        order_book.load_l2_snapshot(&bids, &asks),
+        Err(OrderBookError::BookNotEmpty { order_count: 4 })
+    );
+}
+
+#[test]
+/// Test loading orders from CSV and JSON Lines files, and the lines errors point at.
+fn test_load_orders() {
+    use order_book::io::{load_orders, OrderFileFormat};
+
+    let csv = "Price,Qty,Side,id,ts\n\
+               100.25,10,bid,7,1000\n\
+               \n\
+               \"100.50\",3,BUY,,1000\n\
+               101.00,5,sell,9,2000\n";
+    let commands = load_orders(csv.as_bytes(), OrderFileFormat::Csv).unwrap();
+    assert_eq!(
+        commands,
+        vec![
+            Command::SetTime(1000),
+            Command::InsertWithClientId(
+                Order::with_price_str("100.25", 10, Side::Bid).unwrap(),
+                ClientOrderId(7)
+            ),
+            Command::Insert(Order::with_price_str("100.50", 3, Side::Bid).unwrap()),
+            Command::SetTime(2000),
+            Command::InsertWithClientId(
+                Order::with_price_str("101.00", 5, Side::Ask).unwrap(),
+                ClientOrderId(9)
+            ),
+        ]
+    );
+
+    let mut order_book = OrderBook::new();
+    let mut events = Vec::new();
+    for command in commands {
+        events.extend(order_book.apply(command).unwrap());
+    }
+    assert_eq!(events.last().unwrap().timestamp_nanos, 2000);
+    assert_eq!(
+        order_book.order_id_by_client_id(ClientOrderId(9)),
+        Some(OrderId(3))
+    );
+
+    let jsonl = r#"{"price": "99.5", "qty": 4, "side": "ask", "note": "a \"quoted\" é"}
+{"side":"bid","quantity":2,"price":98.125,"id":null}
+"#;
+    let commands = load_orders(jsonl.as_bytes(), OrderFileFormat::JsonLines).unwrap();
+    assert_eq!(
+        commands,
+        vec![
+            Command::Insert(Order::with_price_str("99.5", 4, Side::Ask).unwrap()),
+            Command::Insert(Order::with_price_str("98.125", 2, Side::Bid).unwrap()),
+        ]
+    );
+
+    let error = load_orders(
+        "price,qty,side\n100,1,bid\n100,x,bid\n".as_bytes(),
+        OrderFileFormat::Csv,
+    )
+    .unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(error.to_string(), "line 3: invalid quantity \"x\"");
+    let error = load_orders("price,qty\n100,1,bid\n".as_bytes(), OrderFileFormat::Csv).unwrap_err();
+    assert_eq!(
+        (error.line, error.reason.as_str()),
+        (2, "expected 2 fields, found 3")
+    );
+    let error = load_orders(
+        "{\"price\": 1, \"qty\": 1, \"side\": \"up\"}\n{\"price\": 1,".as_bytes(),
+        OrderFileFormat::JsonLines,
+    )
+    .unwrap_err();
+    assert_eq!(
+        (error.line, error.reason.as_str()),
+        (1, "invalid side \"up\"")
+    );
+    let error = load_orders(
+        "\n{\"price\": 1, \"qty\": 1".as_bytes(),
+        OrderFileFormat::JsonLines,
+    )
+    .unwrap_err();
+    assert_eq!(
+        (error.line, error.reason.as_str()),
+        (2, "expected ',' or '}'")
+    );
+}
+
+#[test]
+/// Test that venue payloads are normalized into events and that their checks resynchronize.
+fn test_feed_normalizers() {
+    use order_book::feeds::{CoinbaseLevel2, FeedError, KrakenBook};
+
+    let coinbase_message = |sequence_num: u64, kind: &str, updates: &str| {
+        format!(
+            r#"{{"channel":"l2_data","timestamp":"2023-02-09T20:32:50.5Z","sequence_num":{sequence_num},
+                "events":[{{"type":"{kind}","product_id":"BTC-USD","updates":[{updates}]}}]}}"#
+        )
+    };
+    let mut coinbase = CoinbaseLevel2::new("BTC-USD", 2);
+    let mut events = Vec::new();
+    assert_eq!(
+        coinbase.normalize(&coinbase_message(0, "update", ""), &mut events),
+        Err(FeedError::AwaitingSnapshot)
+    );
+    let snapshot = r#"{"side":"bid","price_level":"100.5","new_quantity":"2.5"},
+        {"side":"offer","price_level":"101","new_quantity":"1"}"#;
+    coinbase
+        .normalize(&coinbase_message(1, "snapshot", snapshot), &mut events)
+        .unwrap();
+    coinbase
+        .normalize(
+            &coinbase_message(
+                2,
+                "update",
+                r#"{"side":"bid","price_level":"100.5","new_quantity":"3"}"#,
+            ),
+            &mut events,
+        )
+        .unwrap();
+    let deltas: Vec<(i64, Side, u64)> = events
+        .iter()
+        .map(|event| (event.quantity_delta, event.side, event.sequence))
+        .collect();
+    assert_eq!(
+        deltas,
+        vec![(250, Side::Bid, 1), (100, Side::Ask, 2), (50, Side::Bid, 3)]
+    );
+    assert_eq!(events[0].timestamp_nanos, 1_675_974_770_500_000_000);
+
+    // Other channels advance the sequence, and a skipped message stops the updates
+    coinbase
+        .normalize(
+            r#"{"channel":"heartbeats","sequence_num":3,"events":[]}"#,
+            &mut events,
+        )
+        .unwrap();
+    assert_eq!(
+        coinbase.normalize(&coinbase_message(5, "update", ""), &mut events),
+        Err(FeedError::SequenceGap {
+            expected: 4,
+            received: 5
+        })
+    );
+    assert_eq!(
+        coinbase.normalize(&coinbase_message(6, "update", ""), &mut events),
+        Err(FeedError::AwaitingSnapshot)
+    );
+    assert!(!coinbase.is_synchronized());
+    let resynchronized_from = events.len();
+    coinbase
+        .normalize(
+            &coinbase_message(
+                7,
+                "snapshot",
+                r#"{"side":"offer","price_level":"101","new_quantity":"1.5"}"#,
+            ),
+            &mut events,
+        )
+        .unwrap();
+    let deltas: Vec<(i64, Side)> = events[resynchronized_from..]
+        .iter()
+        .map(|event| (event.quantity_delta, event.side))
+        .collect();
+    assert_eq!(deltas, vec![(-300, Side::Bid), (50, Side::Ask)]);
+    assert!(coinbase.is_synchronized());
+    assert_eq!(
+        coinbase.normalize(
+            &coinbase_message(
+                8,
+                "update",
+                r#"{"side":"bid","price_level":"99","new_quantity":"0.001"}"#,
+            ),
+            &mut events
+        ),
+        Err(FeedError::InvalidQuantity(Decimal::new(1, 3)))
+    );
+
+    // The only level makes the checksum string "123456789", whose CRC32 is 0xCBF43926
+    let mut kraken = KrakenBook::new("BTC/USD", 1, 4);
+    let kraken_message = |kind: &str, asks: &str, checksum: u32| {
+        format!(
+            r#"{{"channel":"book","type":"{kind}","data":[{{"symbol":"BTC/USD","bids":[],
+                "asks":[{asks}],"checksum":{checksum},"timestamp":"2023-10-06T17:35:55.440295Z"}}]}}"#
+        )
+    };
+    let mut kraken_events = Vec::new();
+    kraken
+        .normalize(
+            &kraken_message("snapshot", r#"{"price":1234.5,"qty":0.6789}"#, 0xCBF4_3926),
+            &mut kraken_events,
+        )
+        .unwrap();
+    assert_eq!(kraken_events.len(), 1);
+
+    // A level beyond the subscribed depth is removed again
+    kraken
+        .normalize(
+            &kraken_message("update", r#"{"price":1234.6,"qty":1.0}"#, 0xCBF4_3926),
+            &mut kraken_events,
+        )
+        .unwrap();
+    let deltas: Vec<i64> = kraken_events
+        .iter()
+        .map(|event| event.quantity_delta)
+        .collect();
+    assert_eq!(deltas, vec![6789, 10000, -10000]);
+    assert_eq!(kraken.snapshot().asks.len(), 1);
+
+    let error = kraken
+        .normalize(
+            &kraken_message("update", r#"{"price":1234.5,"qty":0.5}"#, 0xCBF4_3926),
+            &mut kraken_events,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        FeedError::ChecksumMismatch {
+            expected: 0xCBF4_3926,
+            ..
+        }
+    ));
+    assert_eq!(kraken_events.last().unwrap().quantity_delta, -1789);
+    assert_eq!(
+        kraken.normalize(&kraken_message("update", "", 0), &mut kraken_events),
+        Err(FeedError::AwaitingSnapshot)
+    );
+    kraken
+        .normalize(
+            &kraken_message("snapshot", r#"{"price":1234.5,"qty":0.6789}"#, 0xCBF4_3926),
+            &mut kraken_events,
+        )
+        .unwrap();
+    assert!(kraken.is_synchronized());
+
+    // The events of both venues consolidate into one cache
+    let cache = MarketDepthCache::new();
+    for event in events.into_iter().chain(kraken_events) {
+        cache.process_order_event(event);
+    }
+    assert_eq!(
+        cache.get_quantity_at_level(Decimal::new(101, 0), Side::Ask),
+        150
+    );
+    assert_eq!(
+        cache.get_quantity_at_level(Decimal::new(1234, 0), Side::Ask),
+        6789
+    );
+}
+
+#[test]
+/// Test that feed payloads which overflow the parser fail instead of panicking.
+fn test_feed_normalizers_reject_hostile_payloads() {
+    use order_book::feeds::{CoinbaseLevel2, FeedError};
+
+    let mut coinbase = CoinbaseLevel2::new("BTC-USD", 2);
+    let mut events = Vec::new();
+
+    // A timestamp beyond the range of nanoseconds is treated as missing
+    let snapshot = r#"{"channel":"l2_data","timestamp":"999999999999999-01-01T00:00:00Z",
+        "sequence_num":1,"events":[{"type":"snapshot","product_id":"BTC-USD",
+        "updates":[{"side":"bid","price_level":"100","new_quantity":"1"}]}]}"#;
+    coinbase.normalize(snapshot, &mut events).unwrap();
+    assert_eq!(events[0].timestamp_nanos, 0);
+
+    // The sequence number after the largest one does not exist
+    let heartbeat = format!(
+        r#"{{"channel":"heartbeats","sequence_num":{},"events":[]}}"#,
+        u64::MAX
+    );
+    assert_eq!(
+        coinbase.normalize(&heartbeat, &mut events),
+        Err(FeedError::Malformed("sequence_num overflows".to_string()))
+    );
+
+    // Deep nesting is rejected before it exhausts the stack
+    let nested = "[".repeat(1_000_000);
+    assert_eq!(
+        coinbase.normalize(&nested, &mut events),
+        Err(FeedError::Malformed(
+            "nested deeper than 64 levels".to_string()
+        ))
+    );
+    assert_eq!(events.len(), 1);
+}
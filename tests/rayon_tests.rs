@@ -0,0 +1,36 @@
+#![cfg(feature = "rayon")]
+
+use order_book::{MarketDepthCache, Order, OrderBook, Side};
+use rust_decimal::Decimal;
+
+/// Builds a book with many levels on both sides, returning it with an event-fed cache.
+fn build_deep_book(level_count: u32, market_depth_cache: &MarketDepthCache) -> OrderBook {
+    let mut order_book = OrderBook::new();
+    for index in 0..level_count {
+        let offset = Decimal::new(i64::from(index) * 25, 2);
+        for (price, side) in [
+            (Decimal::new(10_000, 0) - offset, Side::Bid),
+            (Decimal::new(10_001, 0) + offset, Side::Ask),
+        ] {
+            let order = Order {
+                price,
+                quantity: u64::from(index % 7 + 1),
+                side,
+            };
+            market_depth_cache.process_order_event(order_book.insert_order(order).event);
+        }
+    }
+    order_book
+}
+
+#[test]
+/// Test that the parallel rebuild agrees with the serial one across range boundaries.
+fn test_cache_par_rebuild_from_book() {
+    let market_depth_cache = MarketDepthCache::new();
+    let order_book = build_deep_book(20_000, &market_depth_cache);
+    let expected_levels = market_depth_cache.get_aggregated_levels();
+
+    market_depth_cache.clear();
+    market_depth_cache.par_rebuild_from(&order_book);
+    assert_eq!(market_depth_cache.get_aggregated_levels(), expected_levels);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "redis")]
+
+use order_book::redis::{DepthPayload, RedisError, RedisPublisher, RedisTarget};
+use order_book::{MarketDepthCache, Order, OrderBook, Side};
+use std::time::Duration;
+
+/// A Redis connection answering with canned replies and recording what it is sent.
+struct FakeRedis {
+    sent: std::rc::Rc<std::cell::RefCell<Vec<u8>>>,
+    replies: std::io::Cursor<Vec<u8>>,
+}
+
+impl std::io::Read for FakeRedis {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        std::io::Read::read(&mut self.replies, buffer)
+    }
+}
+
+impl std::io::Write for FakeRedis {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        self.sent.borrow_mut().extend_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encodes a command as a RESP array of bulk strings.
+fn resp(arguments: &[&str]) -> Vec<u8> {
+    let mut command = format!("*{}\r\n", arguments.len());
+    for argument in arguments {
+        command.push_str(&format!("${}\r\n{argument}\r\n", argument.len()));
+    }
+    command.into_bytes()
+}
+
+#[test]
+/// Test that depth snapshots and deltas are published to Redis channels and streams.
+fn test_redis_publisher() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.25, 10, Side::Bid))
+            .event,
+    );
+    let ask = order_book.insert_order(Order::new(101.00, 5, Side::Ask));
+    cache.process_order_event(ask.event);
+
+    let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let connection = FakeRedis {
+        sent: sent.clone(),
+        replies: std::io::Cursor::new(b":1\r\n:2\r\n-ERR closed\r\n".to_vec()),
+    };
+    let mut publisher = RedisPublisher::new(connection, RedisTarget::Channel("depth".into()), 2);
+    publisher.set_payload(DepthPayload::Deltas);
+
+    // The first publication is a snapshot, and deltas without changes are not sent
+    assert!(publisher.publish(&cache).unwrap());
+    let snapshot = r#"{"type":"snapshot","publication":1,"sequence":2,"bids":[["100",10]],"asks":[["101",5]]}"#;
+    assert_eq!(*sent.borrow(), resp(&["PUBLISH", "depth", snapshot]));
+    assert!(!publisher.publish(&cache).unwrap());
+    assert_eq!(publisher.publication(), 1);
+
+    sent.borrow_mut().clear();
+    cache.process_order_event(order_book.cancel_order(ask.handle.order_id()).unwrap());
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(99.00, 3, Side::Bid))
+            .event,
+    );
+    assert!(publisher.publish(&cache).unwrap());
+    let delta =
+        r#"{"type":"delta","publication":2,"sequence":4,"bids":[["99",3]],"asks":[["101",0]]}"#;
+    assert_eq!(*sent.borrow(), resp(&["PUBLISH", "depth", delta]));
+
+    // Error replies fail the publication, which is retried by the next call
+    cache.process_order_event(
+        order_book
+            .insert_order(Order::new(100.50, 1, Side::Bid))
+            .event,
+    );
+    assert!(matches!(
+        publisher.publish(&cache),
+        Err(RedisError::Server(message)) if message == "ERR closed"
+    ));
+    assert_eq!(publisher.publication(), 2);
+
+    // Streams are appended to and trimmed, at the configured cadence
+    let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let connection = FakeRedis {
+        sent: sent.clone(),
+        replies: std::io::Cursor::new(b"$15\r\n1526919030474-0\r\n".to_vec()),
+    };
+    let target = RedisTarget::Stream {
+        key: "book".into(),
+        max_length: Some(100),
+    };
+    let mut publisher = RedisPublisher::new(connection, target, 1);
+    publisher.set_cadence(Duration::from_secs(3600));
+    assert!(publisher.publish(&cache).unwrap());
+    let snapshot =
+        r#"{"type":"snapshot","publication":1,"sequence":5,"bids":[["100",11]],"asks":[]}"#;
+    assert_eq!(
+        *sent.borrow(),
+        resp(&["XADD", "book", "MAXLEN", "~", "100", "*", "depth", snapshot])
+    );
+    assert!(!publisher.publish(&cache).unwrap());
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "rest")]
+
+use order_book::rest::{router, BboResponse, DepthResponse, RestState, TradesResponse};
+use order_book::{MarketDepthCache, Order, OrderBook, Side, TradeId};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+#[test]
+/// Test that the REST endpoints serve depth, the top of book, and trades since an identifier.
+fn test_rest_endpoints() {
+    let state = RestState {
+        order_book: Arc::new(RwLock::new(OrderBook::new())),
+        market_depth_cache: Arc::new(MarketDepthCache::new()),
+    };
+    {
+        let mut order_book = state.order_book.write();
+        for order in [
+            Order::new(100.00, 10, Side::Bid),
+            Order::new(99.00, 5, Side::Bid),
+            Order::new(98.00, 5, Side::Bid),
+            Order::new(101.00, 7, Side::Ask),
+        ] {
+            let event = order_book.insert_order(order).event;
+            state.market_depth_cache.process_order_event(event);
+        }
+        let outcome = order_book.match_order(Order::new(100.00, 4, Side::Ask));
+        for event in outcome.events {
+            state.market_depth_cache.process_order_event(event);
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let get = |uri: &str| {
+        let request = axum::http::Request::get(uri)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        runtime.block_on(async {
+            let response = router(state.clone()).oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        })
+    };
+
+    let (status, body) = get("/depth?levels=2");
+    assert_eq!(status, 200);
+    let depth: DepthResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        depth.bids,
+        vec![(Decimal::new(100, 0), 6), (Decimal::new(99, 0), 5)]
+    );
+    assert_eq!(depth.asks, vec![(Decimal::new(101, 0), 7)]);
+    assert_eq!(
+        depth.sequence,
+        state.market_depth_cache.last_applied_sequence()
+    );
+    let (_, body) = get("/depth");
+    let depth: DepthResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(depth.bids.len(), 3);
+
+    let (_, body) = get("/bbo");
+    assert_eq!(
+        body,
+        r#"{"best_bid":{"price":"100","quantity":6},"best_ask":{"price":"101","quantity":7},"spread":"1","sequence":5}"#
+    );
+    let bbo: BboResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(bbo.spread, Some(Decimal::new(1, 0)));
+
+    let (_, body) = get("/trades");
+    let trades: TradesResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(trades.trades.len(), 1);
+    assert_eq!(trades.trades[0].trade_id, TradeId(1));
+    assert_eq!(trades.trades[0].quantity, 4);
+    assert_eq!(trades.next_since, Some(1));
+
+    state
+        .order_book
+        .write()
+        .match_order(Order::new(101.00, 2, Side::Bid));
+    let (_, body) = get(&format!("/trades?since={}", trades.next_since.unwrap()));
+    let trades: TradesResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(trades.trades.len(), 1);
+    assert_eq!(trades.trades[0].trade_id, TradeId(2));
+    assert_eq!(trades.trades[0].aggressor_side, Side::Bid);
+    let (_, body) = get("/trades?since=5");
+    assert_eq!(body, r#"{"trades":[],"next_since":5}"#);
+
+    // A new session empties the trade log but keeps numbering trades, so polling
+    // picks up where it left off
+    let since = trades.next_since.unwrap();
+    {
+        let mut order_book = state.order_book.write();
+        order_book.end_session();
+        order_book.insert_order(Order::new(102.00, 3, Side::Ask));
+        order_book.match_order(Order::new(102.00, 1, Side::Bid));
+    }
+    let (_, body) = get(&format!("/trades?since={since}"));
+    let trades: TradesResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(trades.trades.len(), 1);
+    assert_eq!(trades.trades[0].trade_id, TradeId(3));
+    assert_eq!(trades.next_since, Some(3));
+    let (_, body) = get("/trades?since=3");
+    assert_eq!(body, r#"{"trades":[],"next_since":3}"#);
+
+    let (status, _) = get("/depth?levels=many");
+    assert_eq!(status, 400);
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "rkyv")]
+
+use order_book::archive::{
+    access_book, access_depth, archive_book, archive_depth, deserialize_book, deserialize_depth,
+};
+use order_book::{MarketDepthCache, Order, OrderBook, Side};
+use rust_decimal::Decimal;
+
+#[test]
+/// Test that archived snapshots can be queried in place and deserialized back.
+fn test_snapshot_archives() {
+    let mut order_book = OrderBook::new();
+    let cache = MarketDepthCache::new();
+    for index in 0..200u64 {
+        let side = if index % 2 == 0 { Side::Bid } else { Side::Ask };
+        let price = if side == Side::Bid {
+            50.0 + index as f64 / 4.0
+        } else {
+            150.0 + index as f64
+        };
+        cache.process_order_event(
+            order_book
+                .insert_order(Order::new(price, index + 1, side))
+                .event,
+        );
+    }
+
+    let depth = cache.snapshot();
+    let bytes = archive_depth(&depth);
+    let archived = access_depth(&bytes).unwrap();
+    assert_eq!(archived.sequence, 200);
+    assert_eq!(
+        archived.quantity_at(Decimal::new(51, 0), Side::Bid),
+        Some(5 + 7)
+    );
+    assert_eq!(
+        archived.quantity_at(Decimal::new(151, 0), Side::Ask),
+        Some(2)
+    );
+    assert_eq!(archived.quantity_at(Decimal::new(152, 0), Side::Ask), None);
+    assert_eq!(deserialize_depth(archived), depth);
+
+    let book = order_book.snapshot();
+    let bytes = archive_book(&book);
+    let archived = access_book(&bytes).unwrap();
+    assert_eq!(archived.bids.len(), 100);
+    assert_eq!(archived.asks[0].0 .0, 2);
+    assert_eq!(deserialize_book(archived), book);
+
+    assert!(access_depth(&bytes[..bytes.len() / 2]).is_err());
+}
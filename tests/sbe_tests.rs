@@ -0,0 +1,98 @@
+#![cfg(feature = "sbe")]
+
+use order_book::sbe::{decode, encode, encoded_length, SbeError, SbeMessage, HEADER_LENGTH};
+use order_book::{ClientOrderId, Command, Order, OrderBook, OrderId, Side, TradeId, TradingState};
+use rust_decimal::Decimal;
+
+#[test]
+/// Test that every SBE template round-trips and that longer blocks of later versions decode.
+fn test_sbe_codec() {
+    let mut order_book = OrderBook::new();
+    let event = order_book
+        .insert_order(Order::new(100.125, 10, Side::Ask))
+        .event;
+    order_book.match_order(Order::new(100.125, 4, Side::Bid));
+    let messages = vec![
+        SbeMessage::OrderEvent(event),
+        SbeMessage::Trade(order_book.trades()[0].clone()),
+        SbeMessage::Command(Command::Insert(Order::new(99.5, 3, Side::Bid))),
+        SbeMessage::Command(Command::Match(Order::new(101.0, 7, Side::Ask))),
+        SbeMessage::Command(Command::Cancel(OrderId(12))),
+        SbeMessage::Command(Command::Amend {
+            order_id: OrderId(4),
+            price: Decimal::new(-25, 3),
+            quantity: 9,
+        }),
+        SbeMessage::Command(Command::InsertWithClientId(
+            Order::new(98.0, 1, Side::Bid),
+            ClientOrderId(77),
+        )),
+        SbeMessage::Command(Command::Clear),
+        SbeMessage::Command(Command::SetState(TradingState::Halted)),
+        SbeMessage::Command(Command::SetTime(123_456_789)),
+        SbeMessage::Command(Command::InsertDay(Order::new(97.25, 2, Side::Ask))),
+        SbeMessage::Command(Command::MatchDay(Order::new(96.0, 8, Side::Bid))),
+        SbeMessage::Command(Command::EndSession),
+    ];
+
+    let mut stream = vec![0u8; messages.iter().map(encoded_length).sum()];
+    let mut offset = 0;
+    for message in &messages {
+        offset += encode(message, &mut stream[offset..]).unwrap();
+    }
+    assert_eq!(offset, stream.len());
+
+    let mut decoded = Vec::new();
+    let mut remaining = &stream[..];
+    while !remaining.is_empty() {
+        let (message, length) = decode(remaining).unwrap();
+        decoded.push(message);
+        remaining = &remaining[length..];
+    }
+    assert_eq!(decoded, messages);
+
+    // A later schema version may append fields to a block
+    let mut extended = vec![0u8; encoded_length(&messages[4])];
+    encode(&messages[4], &mut extended).unwrap();
+    extended.extend_from_slice(&[0xEE; 4]);
+    extended[0] += 4;
+    extended[6] = 3;
+    assert_eq!(decode(&extended), Ok((messages[4].clone(), extended.len())));
+
+    // Version 0 trade blocks end before the identifiers
+    let mut version_0 = vec![0u8; encoded_length(&messages[1])];
+    encode(&messages[1], &mut version_0).unwrap();
+    version_0.truncate(version_0.len() - 24);
+    version_0[0] -= 24;
+    version_0[6] = 0;
+    let Ok((SbeMessage::Trade(trade), _)) = decode(&version_0) else {
+        panic!("expected a trade");
+    };
+    assert_eq!(trade.quantity, 4);
+    assert_eq!(trade.trade_id, TradeId(0));
+
+    let mut short = [0u8; HEADER_LENGTH + 4];
+    assert_eq!(
+        encode(&messages[4], &mut short),
+        Err(SbeError::BufferTooShort {
+            required: HEADER_LENGTH + 8
+        })
+    );
+    assert_eq!(
+        decode(&[0, 0, 99, 0, 1, 0, 0, 0]),
+        Err(SbeError::UnknownTemplate(99))
+    );
+    assert_eq!(
+        decode(&[0, 0, 15, 0, 2, 0, 0, 0]),
+        Err(SbeError::SchemaMismatch { schema_id: 2 })
+    );
+    let huge = SbeMessage::Command(Command::Insert(Order {
+        price: Decimal::MAX,
+        quantity: 1,
+        side: Side::Bid,
+    }));
+    assert_eq!(
+        encode(&huge, &mut [0u8; 64]),
+        Err(SbeError::PriceOutOfRange(Decimal::MAX))
+    );
+}
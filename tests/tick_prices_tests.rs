@@ -0,0 +1,67 @@
+#![cfg(feature = "tick-prices")]
+
+use order_book::{
+    ExcessPrecision, Order, OrderBook, OrderBookConfig, PriceNormalization, PricePrecision, Side,
+};
+use rust_decimal::Decimal;
+
+#[test]
+/// Test that tick-keyed books snap prices to the tick grid and report them as decimals.
+fn test_tick_price_keys() {
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        tick_size: Some(Decimal::new(5, 2)),
+        ..OrderBookConfig::default()
+    });
+    assert_eq!(order_book.tick_size(), Decimal::new(5, 2));
+
+    // 100.03 rounds to the nearest tick, which is the same level as 100.05
+    let outcome = order_book.insert_order(Order::new(100.03, 10, Side::Bid));
+    assert_eq!(outcome.handle.price(), Decimal::new(10005, 2));
+    order_book.insert_order(Order::new(100.05, 5, Side::Bid));
+    assert_eq!(order_book.bid_levels_count(), 1);
+    assert_eq!(
+        order_book
+            .level(Decimal::new(10005, 2), Side::Bid)
+            .map_or(0, |level| level.order_count()),
+        2
+    );
+    assert_eq!(
+        order_book
+            .level(Decimal::new(10003, 2), Side::Bid)
+            .map_or(0, |level| level.order_count()),
+        0
+    );
+
+    order_book.insert_order(Order::new(100.20, 10, Side::Ask));
+    assert_eq!(
+        order_book.compute_spread(),
+        (
+            Some(Decimal::new(10005, 2)),
+            Some(Decimal::new(1002, 1)),
+            Some(Decimal::new(15, 2))
+        )
+    );
+
+    order_book.match_order(Order::new(100.00, 12, Side::Ask));
+    assert_eq!(order_book.trades()[0].price, Decimal::new(10005, 2));
+    assert_eq!(order_book.total_volume(Side::Bid), 3);
+}
+
+#[test]
+/// Test that a tick size composes with bounded precision and a preallocated slab.
+fn test_tick_size_with_combined_config() {
+    let mut order_book = OrderBook::with_config(OrderBookConfig {
+        price_normalization: PriceNormalization::Preserve,
+        price_precision: Some(PricePrecision {
+            max_scale: 2,
+            excess_precision: ExcessPrecision::Round,
+        }),
+        order_capacity: 64,
+        tick_size: Some(Decimal::new(5, 2)),
+    });
+    assert_eq!(order_book.tick_size(), Decimal::new(5, 2));
+
+    let outcome = order_book.insert_order(Order::new(100.1, 10, Side::Bid));
+    assert_eq!(outcome.event.price, Decimal::new(10010, 2));
+    assert_eq!(outcome.event.price.to_string(), "100.10");
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "tui")]
+
+use order_book::tui::BookViewer;
+use order_book::{Order, OrderBook, Side};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+#[test]
+/// Test that the viewer renders the aggregated ladder and the event tape.
+fn test_viewer_renders_ladder_and_tape() {
+    let mut order_book = OrderBook::new();
+    let mut viewer = BookViewer::new(5, 2);
+
+    for (price, quantity, side) in [
+        (99.50, 10, Side::Bid),
+        (99.25, 5, Side::Bid),
+        (101.75, 20, Side::Ask),
+    ] {
+        let event = order_book
+            .insert_order(Order::new(price, quantity, side))
+            .event;
+        viewer.process_order_event(event);
+    }
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+    terminal.draw(|frame| viewer.render(frame)).unwrap();
+
+    let rendered: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+
+    // Bids at 99.50 and 99.25 share the aggregated level 99
+    assert!(
+        rendered.contains("15"),
+        "Aggregated bid quantity should be shown"
+    );
+    assert!(rendered.contains("101"), "Ask level should be shown");
+    // The tape only retains the two most recent events
+    assert!(rendered.contains("ASK 101.75 +20"));
+    assert!(rendered.contains("BID 99.25 +5"));
+    assert!(!rendered.contains("BID 99.5 +10"));
+}